@@ -6,21 +6,45 @@
 //! build script becomes required. Additionally, by requesting that
 //! Cargo re-run the build script whenever `memory.x` is changed,
 //! a rebuild of the application with new memory settings is ensured after updating `memory.x`.
+//!
+//! It also turns the animations in `assets/` into the frames of `src/assets.rs`, see
+//! `assets/README.md` for the two formats, and sets `FIRMWARE_COMMIT`, the git commit it
+//! was built from, and `FIRMWARE_VERSION`, the package version with that commit, for
+//! `src/about.rs`.
 
 use std::{
     env,
-    fs::File,
+    fmt::Write as _,
+    fs::{self, File},
     io::Write,
-    path::PathBuf
+    path::{Path, PathBuf},
+    process::Command
 };
 
+type Frame = [[u8; 5]; 5];
+
+const DEFAULT_FRAME_MS : u32 = 100;
+
+struct Animation {
+    name     : String,
+    frames   : Vec<Frame>,
+    frame_ms : u32,
+}
+
 fn main() {
     // Put `memory.x` in our output directory and ensure it's
     // on the linker search path.
     let out = &PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    // NOTE: the radio update needs the upper half of the flash for the new image, see
+    // src/ota.rs, so the program has to fit below it
+    let mut memory = String::from(include_str!("memory.x"));
+    if env::var_os("CARGO_FEATURE_OTA").is_some() {
+        assert!(memory.contains("LENGTH = 384K"), "memory.x: FLASH no longer 384K, see build.rs");
+        memory = memory.replace("LENGTH = 384K", "LENGTH = 236K");
+    }
     File::create(out.join("memory.x"))
         .unwrap()
-        .write_all(include_bytes!("memory.x"))
+        .write_all(memory.as_bytes())
         .unwrap();
     println!("cargo:rustc-link-search={}", out.display());
 
@@ -29,4 +53,159 @@ fn main() {
     // here, we ensure the build script is only re-run when
     // `memory.x` is changed.
     println!("cargo:rerun-if-changed=memory.x");
+
+    // NOTE: the directory itself, so added and removed assets are picked up too
+    println!("cargo:rerun-if-changed=assets");
+    let mut paths : Vec<PathBuf> = fs::read_dir("assets")
+        .map(|entries| entries.map(|entry| entry.unwrap().path()).collect())
+        .unwrap_or_default();
+    paths.sort();
+    let mut animations = Vec::new();
+    for path in paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let animation = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("txt") => from_text(&path),
+            Some("png") => from_png(&path),
+            _ => continue,
+        };
+        match animation {
+            Ok(animation) => animations.push(animation),
+            Err(error) => panic!("{}: {}", path.display(), error),
+        }
+    }
+    fs::write(out.join("assets.rs"), generate(&animations)).unwrap();
+
+    // NOTE: outside a git checkout, or without git, there is no commit and the version is
+    // the package version alone
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+    let commit = Command::new("git")
+        .args(["describe", "--always", "--dirty", "--exclude", "*"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .unwrap_or_default();
+    let mut version = env::var("CARGO_PKG_VERSION").unwrap();
+    if !commit.is_empty() {
+        version = format!("{}+{}", version, commit);
+    }
+    println!("cargo:rustc-env=FIRMWARE_COMMIT={}", commit);
+    println!("cargo:rustc-env=FIRMWARE_VERSION={}", version);
+}
+
+/// The file name up to the first dot, and the frame duration when the name has a second
+/// part, `heart.150.png` is "heart" at 150 ms a frame.
+fn name_and_ms(path : &Path) -> Result<(String, Option<u32>), String> {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).ok_or("file name isn't UTF-8")?;
+    let (name, ms) = match stem.split_once('.') {
+        Some((name, ms)) => (name, Some(ms.parse().map_err(|_| format!("'{}' isn't a frame duration in ms", ms))?)),
+        None => (stem, None),
+    };
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("'{}' isn't a name, use letters, digits, - and _", name));
+    }
+    Ok((name.to_string(), ms))
+}
+
+/// Frames of five lines of five LEDs, `.` off, `#` fully lit and `1`-`9` a level, with
+/// blank lines between them. `ms <n>` sets the frame duration, `//` starts a comment.
+fn from_text(path : &Path) -> Result<Animation, String> {
+    let (name, ms) = name_and_ms(path)?;
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let mut frame_ms = ms;
+    let mut frames = Vec::new();
+    let mut rows = Vec::new();
+    for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+        if let Some(ms) = line.strip_prefix("ms ") {
+            frame_ms = Some(ms.trim().parse().map_err(|_| format!("line {}: bad frame duration", number))?);
+            continue;
+        }
+        if line.starts_with("//") {
+            continue;
+        }
+        if line.is_empty() {
+            if !rows.is_empty() {
+                return Err(format!("line {}: a frame has 5 rows, this one {}", number, rows.len()));
+            }
+            continue;
+        }
+        let mut row = [0; 5];
+        if line.chars().count() != 5 {
+            return Err(format!("line {}: a row has 5 LEDs", number));
+        }
+        for (led, c) in row.iter_mut().zip(line.chars()) {
+            *led = match c {
+                '.' => 0,
+                '#' => 9,
+                '1'..='9' => c as u8 - b'0',
+                _ => return Err(format!("line {}: '{}' isn't an LED, use . # or 1-9", number, c)),
+            };
+        }
+        rows.push(row);
+        if rows.len() == 5 {
+            frames.push([rows[0], rows[1], rows[2], rows[3], rows[4]]);
+            rows.clear();
+        }
+    }
+    if !rows.is_empty() {
+        return Err(format!("the last frame has {} rows, not 5", rows.len()));
+    }
+    if frames.is_empty() {
+        return Err("no frames".to_string());
+    }
+    Ok(Animation { name, frames, frame_ms : frame_ms.unwrap_or(DEFAULT_FRAME_MS) })
+}
+
+/// A strip of 5x5 frames side by side, five pixels high, brightness becomes the LED level.
+fn from_png(path : &Path) -> Result<Animation, String> {
+    let (name, ms) = name_and_ms(path)?;
+    let mut decoder = png::Decoder::new(File::open(path).map_err(|error| error.to_string())?);
+    // NOTE: palettes and bit depths below 8 come out as 8 bit RGB or greyscale
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|error| error.to_string())?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(|error| error.to_string())?;
+    let (width, height) = (info.width as usize, info.height as usize);
+    if height != 5 || width == 0 || width % 5 != 0 {
+        return Err(format!("{}x{} isn't a strip of 5x5 frames", width, height));
+    }
+    let channels = info.color_type.samples();
+    let level = |x : usize, y : usize| {
+        let pixel = &pixels[y * info.line_size + x * channels..][..channels];
+        let luma = match pixel.len() {
+            1 | 2 => pixel[0] as u32,
+            _ => (pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000,
+        };
+        // NOTE: a transparent pixel is an LED that is off
+        let alpha = if channels == 2 || channels == 4 { pixel[channels - 1] as u32 } else { 255 };
+        ((luma * alpha / 255 * 9 + 127) / 255) as u8
+    };
+    let frames = (0..width / 5)
+        .map(|frame| {
+            let mut leds = [[0; 5]; 5];
+            for (y, row) in leds.iter_mut().enumerate() {
+                for (x, led) in row.iter_mut().enumerate() {
+                    *led = level(frame * 5 + x, y);
+                }
+            }
+            leds
+        })
+        .collect();
+    Ok(Animation { name, frames, frame_ms : ms.unwrap_or(DEFAULT_FRAME_MS) })
+}
+
+fn generate(animations : &[Animation]) -> String {
+    let mut code = String::from("// NOTE: generated by build.rs from the files in assets/\n\n");
+    code += "pub static ANIMATIONS : &[Animation] = &[\n";
+    for animation in animations {
+        let _ = writeln!(code, "    Animation {{ name : {:?}, frame_ms : {}, frames : &[", animation.name, animation.frame_ms);
+        for frame in &animation.frames {
+            let _ = writeln!(code, "        {:?},", frame);
+        }
+        code += "    ] },\n";
+    }
+    code += "];\n";
+    code
 }
@@ -0,0 +1,120 @@
+//! An attendance roll: the names of the boards that checked in over the radio and when, the
+//! first check-in of every name only, so a student pressing again isn't counted twice.
+//!
+//! The roll holds `N` names, a check-in after that is turned away. `frame` shows how many
+//! checked in as lit LEDs, row by row, a full matrix for 25 and more.
+
+use heapless::{String, Vec};
+use crate::Frame;
+
+pub const NAME_LEN : usize = 16;
+
+pub type Name = String<NAME_LEN>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub name    : Name,
+    pub address : u16,
+    pub at_s    : u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CheckIn {
+    New,
+    Again,
+    Full,
+}
+
+pub struct Roll<const N : usize> {
+    entries : Vec<Entry, N>,
+}
+
+impl<const N : usize> Roll<N> {
+    pub const fn new() -> Self {
+        Roll { entries : Vec::new() }
+    }
+
+    /// Enters `name` from the board at `address` at `at_s`, unless it is on the roll already.
+    /// A name longer than `NAME_LEN` is cut at a char boundary.
+    pub fn check_in(&mut self, name : &str, address : u16, at_s : u32) -> CheckIn {
+        let mut cut = Name::new();
+        for c in name.chars() {
+            if cut.push(c).is_err() {
+                break;
+            }
+        }
+        if self.entries.iter().any(|entry| entry.name == cut) {
+            return CheckIn::Again;
+        }
+        match self.entries.push(Entry { name : cut, address, at_s }) {
+            Ok(()) => CheckIn::New,
+            Err(_) => CheckIn::Full,
+        }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl<const N : usize> Default for Roll<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `count` LEDs lit row by row.
+pub fn frame(count : usize) -> Frame {
+    let mut frame = [[0; 5]; 5];
+    for i in 0..count.min(25) {
+        frame[i / 5][i % 5] = 9;
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_name_is_on_the_roll_once() {
+        let mut roll = Roll::<2>::new();
+        assert_eq!(roll.check_in("ada", 0x0102, 5), CheckIn::New);
+        assert_eq!(roll.check_in("ada", 0x0102, 9), CheckIn::Again);
+        assert_eq!(roll.check_in("bob", 0x0304, 12), CheckIn::New);
+        assert_eq!(roll.check_in("cy", 0x0506, 13), CheckIn::Full);
+        assert_eq!(roll.len(), 2);
+        assert_eq!(roll.entries()[0].at_s, 5);
+        roll.clear();
+        assert!(roll.is_empty());
+    }
+
+    #[test]
+    fn long_names_are_cut() {
+        let mut roll = Roll::<2>::new();
+        roll.check_in("abcdefghijklmnopqrst", 1, 0);
+        assert_eq!(roll.entries()[0].name.as_str(), "abcdefghijklmnop");
+        assert_eq!(roll.check_in("abcdefghijklmnopxyz", 2, 1), CheckIn::Again);
+    }
+
+    #[test]
+    fn the_count_fills_rows() {
+        assert_eq!(frame(0), [[0; 5]; 5]);
+        let seven = frame(7);
+        assert_eq!(seven[0], [9; 5]);
+        assert_eq!(seven[1], [9, 9, 0, 0, 0]);
+        assert_eq!(frame(40), [[9; 5]; 5]);
+    }
+}
@@ -0,0 +1,118 @@
+//! The name of the badge app, and the editor changing it on the board one character at a
+//! time, like the initials of the high-score table.
+
+use crate::{font, Frame};
+
+pub const MAX_LEN : usize = 24;
+
+// NOTE: what A steps through, the space first, trailing ones are dropped from the name
+const CHARSET : &[u8] = b" ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!?.-&'";
+// NOTE: a space is shown as a dim underline, so the cursor on one can be seen
+const SPACE_LEVEL : u8 = 3;
+
+pub type Name = heapless::String<MAX_LEN>;
+
+/// The name in `bytes` with what the editor can't show left out, None when nothing is left.
+pub fn decode(bytes : &[u8]) -> Option<Name> {
+    let mut name = Name::new();
+    for byte in bytes.iter().map(u8::to_ascii_uppercase).filter(|byte| CHARSET.contains(byte)) {
+        let _ = name.push(byte as char);
+    }
+    let trimmed = name.trim_end().len();
+    name.truncate(trimmed);
+    (!name.is_empty()).then_some(name)
+}
+
+#[derive(Clone, Copy)]
+pub struct NameEntry {
+    chars    : [u8; MAX_LEN],
+    len      : usize,
+    position : usize,
+}
+
+impl NameEntry {
+    /// Starts on the first character of `name`.
+    pub fn new(name : &str) -> Self {
+        let mut entry = NameEntry { chars : [b' '; MAX_LEN], len : 1, position : 0 };
+        if let Some(name) = decode(name.as_bytes()) {
+            entry.chars[..name.len()].copy_from_slice(name.as_bytes());
+            entry.len = name.len();
+        }
+        entry
+    }
+
+    /// Button A, steps the character at the cursor through the ones the font has.
+    pub fn next_char(&mut self) {
+        let c = &mut self.chars[self.position];
+        let index = CHARSET.iter().position(|known| known == c).unwrap_or(0);
+        *c = CHARSET[(index + 1) % CHARSET.len()];
+    }
+
+    /// Button B, moves on to the next character, a new space past the end of the name,
+    /// back to the first one once the name is `MAX_LEN` long.
+    pub fn advance(&mut self) {
+        self.position = (self.position + 1) % MAX_LEN;
+        self.len = self.len.max(self.position + 1);
+    }
+
+    /// The name as edited, without trailing spaces, None when that leaves nothing.
+    pub fn name(&self) -> Option<Name> {
+        decode(&self.chars[..self.len])
+    }
+
+    pub fn frame(&self) -> Frame {
+        match self.chars[self.position] {
+            b' ' => {
+                let mut frame = [[0; 5]; 5];
+                frame[4] = [SPACE_LEVEL, SPACE_LEVEL, SPACE_LEVEL, SPACE_LEVEL, 0];
+                frame
+            }
+            c => font::frame_of(c as char).map(|row| row.map(|led| led * 9)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_keeps_what_the_editor_can_show() {
+        assert_eq!(decode(b"Ada L.  ").as_deref(), Some("ADA L."));
+        assert_eq!(decode(b"\x00\xff  "), None);
+    }
+
+    #[test]
+    fn editing_changes_and_appends_characters() {
+        let mut entry = NameEntry::new("AB");
+        entry.next_char();
+        entry.advance();
+        entry.advance();
+        for _ in 0..3 {
+            entry.next_char();
+        }
+        assert_eq!(entry.name().as_deref(), Some("BBC"));
+    }
+
+    #[test]
+    fn moving_past_the_end_adds_nothing_but_a_space() {
+        let mut entry = NameEntry::new("BOB");
+        for _ in 0..5 {
+            entry.advance();
+        }
+        assert_eq!(entry.name().as_deref(), Some("BOB"));
+        for _ in 0..MAX_LEN - 5 {
+            entry.advance();
+        }
+        entry.next_char();
+        assert_eq!(entry.name().as_deref(), Some("COB"));
+    }
+
+    #[test]
+    fn a_name_of_spaces_is_none() {
+        let mut entry = NameEntry::new("");
+        entry.advance();
+        assert!(entry.name().is_none());
+        assert_eq!(entry.frame()[4][0], SPACE_LEVEL);
+    }
+}
@@ -0,0 +1,220 @@
+//! BLE advertisements of the standard beacons, iBeacon and Eddystone, so a phone with any
+//! beacon app finds the board.
+//!
+//! An advertisement is a non-connectable undirected advertising PDU: the header, the length,
+//! the board's address and up to 31 bytes of AD structures, each a length, a type and the
+//! data. The address is a random static one, the two top bits of its last byte set, as in the
+//! FICR's device address. iBeacon carries a 16 byte UUID, a major and a minor number.
+//! Eddystone-UID carries a 10 byte namespace and a 6 byte instance, Eddystone-URL a URL
+//! squeezed into 17 bytes, its scheme and common endings as single codes.
+//!
+//! `environment` lists the Environmental Sensing Service and carries the temperature, the
+//! supply voltage and the light level as service data, under the UUIDs and in the encodings
+//! of their GATT characteristics, so a phone reads them without connecting. The light level is
+//! the 0-255 estimate the firmware senses with the LED matrix, it isn't calibrated and goes
+//! out as illuminance at a rough `LUX_PER_LEVEL`.
+//!
+//! NOTE: the service itself, its characteristics read and notified over a connection, is
+//! missing. It needs the BLE stack the firmware lacks, see keyboard.rs.
+
+use heapless::Vec;
+
+pub const DATA_LEN : usize = 31;
+// NOTE: the header, the length and the address in front of the data
+pub const PDU_LEN : usize = 2 + 6 + DATA_LEN;
+pub const URL_LEN : usize = 17;
+
+// NOTE: ADV_NONCONN_IND with a random address
+const HEADER : u8 = 0x42;
+// NOTE: LE general discoverable, no BR/EDR
+const FLAGS : [u8; 3] = [0x02, 0x01, 0x06];
+const APPLE : [u8; 2] = [0x4c, 0x00];
+const EDDYSTONE : [u8; 2] = [0xaa, 0xfe];
+const ENVIRONMENTAL_SENSING : [u8; 2] = [0x1a, 0x18];
+const TEMPERATURE : [u8; 2] = [0x6e, 0x2a];
+const VOLTAGE : [u8; 2] = [0x18, 0x2b];
+const ILLUMINANCE : [u8; 2] = [0xfb, 0x2a];
+// NOTE: a guess, the matrix reads about full level in bright indoor light
+pub const LUX_PER_LEVEL : u32 = 4;
+// NOTE: the signal strength a receiver sees at 1 m for iBeacon and at 0 m for Eddystone, in
+// dBm, about right for the micro:bit at 0 dBm
+const POWER_AT_1M : i8 = -59;
+const POWER_AT_0M : i8 = -18;
+
+const SCHEMES : [&str; 4] = ["http://www.", "https://www.", "http://", "https://"];
+const ENDINGS : [&str; 14] = [
+    ".com/", ".org/", ".edu/", ".net/", ".info/", ".biz/", ".gov/",
+    ".com", ".org", ".edu", ".net", ".info", ".biz", ".gov",
+];
+
+/// The address in `address` made a random static one.
+pub fn static_address(address : [u8; 6]) -> [u8; 6] {
+    let mut address = address;
+    address[5] |= 0xc0;
+    address
+}
+
+/// The advertising PDU from `address` carrying `data`.
+pub fn pdu(address : [u8; 6], data : &[u8]) -> Vec<u8, PDU_LEN> {
+    let mut pdu = Vec::new();
+    let data = &data[..data.len().min(DATA_LEN)];
+    let _ = pdu.extend_from_slice(&[HEADER, (address.len() + data.len()) as u8]);
+    let _ = pdu.extend_from_slice(&address);
+    let _ = pdu.extend_from_slice(data);
+    pdu
+}
+
+pub fn ibeacon(uuid : &[u8; 16], major : u16, minor : u16) -> Vec<u8, DATA_LEN> {
+    let mut data = Vec::new();
+    let _ = data.extend_from_slice(&FLAGS);
+    let _ = data.extend_from_slice(&[0x1a, 0xff]);
+    let _ = data.extend_from_slice(&APPLE);
+    let _ = data.extend_from_slice(&[0x02, 0x15]);
+    let _ = data.extend_from_slice(uuid);
+    let _ = data.extend_from_slice(&major.to_be_bytes());
+    let _ = data.extend_from_slice(&minor.to_be_bytes());
+    let _ = data.push(POWER_AT_1M as u8);
+    data
+}
+
+fn eddystone(frame : &[u8]) -> Vec<u8, DATA_LEN> {
+    let mut data = Vec::new();
+    let _ = data.extend_from_slice(&FLAGS);
+    let _ = data.extend_from_slice(&[0x03, 0x03]);
+    let _ = data.extend_from_slice(&EDDYSTONE);
+    let _ = data.extend_from_slice(&[(frame.len() + 3) as u8, 0x16]);
+    let _ = data.extend_from_slice(&EDDYSTONE);
+    let _ = data.extend_from_slice(frame);
+    data
+}
+
+pub fn eddystone_uid(namespace : &[u8; 10], instance : &[u8; 6]) -> Vec<u8, DATA_LEN> {
+    let mut frame = [0; 20];
+    frame[1] = POWER_AT_0M as u8;
+    frame[2..12].copy_from_slice(namespace);
+    frame[12..18].copy_from_slice(instance);
+    eddystone(&frame)
+}
+
+/// The temperature characteristic's value for `quarters` of a degree, in hundredths.
+pub fn temperature_value(quarters : i32) -> [u8; 2] {
+    (quarters.saturating_mul(25).clamp(i16::MIN as i32, i16::MAX as i32) as i16).to_le_bytes()
+}
+
+/// The voltage characteristic's value for `mv`, in 64ths of a volt.
+pub fn voltage_value(mv : u16) -> [u8; 2] {
+    ((mv as u32 * 64 / 1000) as u16).to_le_bytes()
+}
+
+/// The illuminance characteristic's value for the light `level`, in hundredths of a lux.
+pub fn illuminance_value(level : u8) -> [u8; 3] {
+    let [low, middle, high, _] = (level as u32 * LUX_PER_LEVEL * 100).to_le_bytes();
+    [low, middle, high]
+}
+
+/// The readings there are, the temperature in quarters of a degree, the supply in mV and the
+/// light level.
+pub fn environment(quarters : Option<i32>, mv : Option<u16>, light : Option<u8>) -> Vec<u8, DATA_LEN> {
+    let mut data = Vec::new();
+    let _ = data.extend_from_slice(&FLAGS);
+    let _ = data.extend_from_slice(&[0x03, 0x03]);
+    let _ = data.extend_from_slice(&ENVIRONMENTAL_SENSING);
+    let (temperature, voltage, illuminance) = (quarters.map(temperature_value), mv.map(voltage_value), light.map(illuminance_value));
+    let readings = [
+        temperature.as_ref().map(|value| (TEMPERATURE, &value[..])),
+        voltage.as_ref().map(|value| (VOLTAGE, &value[..])),
+        illuminance.as_ref().map(|value| (ILLUMINANCE, &value[..])),
+    ];
+    for (uuid, value) in readings.into_iter().flatten() {
+        let _ = data.extend_from_slice(&[3 + value.len() as u8, 0x16]);
+        let _ = data.extend_from_slice(&uuid);
+        let _ = data.extend_from_slice(value);
+    }
+    data
+}
+
+/// `url` in the Eddystone-URL encoding, None when it has no scheme, takes a character it
+/// can't carry or doesn't fit.
+pub fn encode_url(url : &str) -> Option<Vec<u8, { URL_LEN + 1 }>> {
+    let (scheme, prefix) = SCHEMES.iter().enumerate().find(|(_, scheme)| url.starts_with(*scheme))?;
+    let mut encoded = Vec::new();
+    encoded.push(scheme as u8).ok()?;
+    let mut rest = &url[prefix.len()..];
+    while let Some(c) = rest.chars().next() {
+        match ENDINGS.iter().position(|ending| rest.starts_with(ending)) {
+            Some(code) => {
+                encoded.push(code as u8).ok()?;
+                rest = &rest[ENDINGS[code].len()..];
+            }
+            None if c.is_ascii_graphic() => {
+                encoded.push(c as u8).ok()?;
+                rest = &rest[1..];
+            }
+            None => return None,
+        }
+    }
+    Some(encoded)
+}
+
+pub fn eddystone_url(url : &str) -> Option<Vec<u8, DATA_LEN>> {
+    let encoded = encode_url(url)?;
+    let mut frame = Vec::<u8, { URL_LEN + 3 }>::new();
+    frame.extend_from_slice(&[0x10, POWER_AT_0M as u8]).ok()?;
+    frame.extend_from_slice(&encoded).ok()?;
+    Some(eddystone(&frame))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID : [u8; 16] = [0x11; 16];
+
+    #[test]
+    fn ibeacon_is_apples_layout() {
+        let data = ibeacon(&UUID, 0x0102, 0x0304);
+        assert_eq!(data.len(), 30);
+        assert_eq!(&data[3..9], &[0x1a, 0xff, 0x4c, 0x00, 0x02, 0x15]);
+        assert_eq!(&data[25..], &[0x01, 0x02, 0x03, 0x04, 0xc5]);
+        let pdu = pdu(static_address([1, 2, 3, 4, 5, 6]), &data);
+        assert_eq!(&pdu[..8], &[HEADER, 36, 1, 2, 3, 4, 5, 0xc6]);
+        assert_eq!(pdu.len(), 38);
+    }
+
+    #[test]
+    fn eddystone_uid_fills_the_advertisement() {
+        let data = eddystone_uid(&[0xaa; 10], &[0xbb; 6]);
+        assert_eq!(data.len(), DATA_LEN);
+        assert_eq!(&data[3..11], &[0x03, 0x03, 0xaa, 0xfe, 0x17, 0x16, 0xaa, 0xfe]);
+        assert_eq!(&data[11..13], &[0x00, 0xee]);
+        assert_eq!(&data[23..29], &[0xbb; 6]);
+    }
+
+    #[test]
+    fn the_environment_is_service_data() {
+        assert_eq!(temperature_value(-10), (-250i16).to_le_bytes());
+        assert_eq!(voltage_value(3000), 192u16.to_le_bytes());
+        assert_eq!(illuminance_value(255), (255 * LUX_PER_LEVEL * 100).to_le_bytes()[..3]);
+        let data = environment(Some(100), Some(3000), Some(1));
+        assert_eq!(&data[3..], &[
+            0x03, 0x03, 0x1a, 0x18,
+            0x05, 0x16, 0x6e, 0x2a, 0xc4, 0x09,
+            0x05, 0x16, 0x18, 0x2b, 0xc0, 0x00,
+            0x06, 0x16, 0xfb, 0x2a, 0x90, 0x01, 0x00,
+        ]);
+        assert!(environment(Some(i32::MAX), Some(u16::MAX), Some(u8::MAX)).len() <= DATA_LEN);
+        assert_eq!(environment(None, None, None).len(), 7);
+    }
+
+    #[test]
+    fn urls_are_squeezed() {
+        assert_eq!(encode_url("https://microbit.org").unwrap(), [3, b'm', b'i', b'c', b'r', b'o', b'b', b'i', b't', 8]);
+        assert_eq!(encode_url("http://www.a.com/b").unwrap(), [0, b'a', 0, b'b']);
+        assert_eq!(encode_url("microbit.org"), None);
+        assert_eq!(encode_url("https://a b.org"), None);
+        assert_eq!(encode_url("https://abcdefghijklmnopqr"), None);
+        let data = eddystone_url("https://microbit.org").unwrap();
+        assert_eq!(&data[7..13], &[0x0f, 0x16, 0xaa, 0xfe, 0x10, 0xee]);
+        assert_eq!(data.len(), 23);
+    }
+}
@@ -0,0 +1,43 @@
+//! The parts of a board the logic uses, for it to be written once for the micro:bit and the
+//! simulator on the host, and a board to come.
+//!
+//! The firmware implements them on the HAL: the button pins in long_press.rs, the display in
+//! display.rs, the PWM of the speaker in speaker.rs and the LSM303AGR in motion.rs. The
+//! simulator implements them on the terminal.
+
+use crate::FrameSink;
+
+/// Buttons A and B.
+pub trait Buttons {
+    /// True while A is down.
+    fn a(&self) -> bool;
+    /// True while B is down.
+    fn b(&self) -> bool;
+}
+
+/// The 5x5 matrix, showing frames of greyscale levels 0-9.
+pub trait Matrix : FrameSink {
+    /// 0-9, scales every level shown from the next frame on.
+    fn set_brightness(&mut self, brightness : u8);
+
+    /// Turns every LED off until the next frame.
+    fn clear(&mut self);
+}
+
+/// A speaker playing one tone at a time.
+pub trait Beeper {
+    /// Plays `hz` until the next call, 0 is silence.
+    fn tone(&mut self, hz : u32);
+
+    fn off(&mut self) {
+        self.tone(0)
+    }
+}
+
+/// An accelerometer.
+pub trait Motion {
+    /// Acceleration in mg seen from the display: x grows with its right edge tilted down, y
+    /// with its bottom edge tilted down and z with the display turned to the ground, about
+    /// -1 g lying face up. None when the sensor doesn't answer.
+    fn acceleration(&mut self) -> Option<(i32, i32, i32)>;
+}
@@ -0,0 +1,328 @@
+//! Breakout: a two LED paddle on the bottom row keeps a ball bouncing into the bricks of the
+//! top rows.
+//!
+//! The ball moves on a fixed-point grid, in thousandths of an LED, so it can fly at angles
+//! other than 45 degrees: hitting the paddle's left or right half sends it off to that side.
+//! Clearing the bricks brings a new wall and a faster ball, the game ends when the ball drops
+//! past the paddle. The bricks cleared are the score.
+
+use core::fmt::Write;
+use crate::board::Beeper;
+use crate::scroll::{self, Text};
+use crate::{Clock, Frame, Random};
+
+const ONE : i32 = 1000;
+// NOTE: the centre of the edge LEDs, the ball bounces there
+const MAX : i32 = 4 * ONE;
+const PADDLE_ROW : i32 = 4 * ONE;
+const BRICK_ROWS : usize = 2;
+// NOTE: in thousandths of an LED a second, the speed grows with every wall
+const SPEED : i32 = 3000;
+const FASTER : i32 = 500;
+const MAX_SPEED : i32 = 7000;
+const BRICK_HZ : u32 = 880;
+const PADDLE_HZ : u32 = 440;
+const BEEP_MS : u64 = 40;
+const LOST_HZ : u32 = 150;
+const OVER_MS : u64 = 1200;
+const BRICK : u8 = 5;
+const POLL_MS : u32 = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+    Left,
+    Right,
+}
+
+/// What an `advance` did beyond moving the ball.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// The last brick of a wall went, a new wall is up and the ball served faster.
+    Cleared,
+    /// The ball dropped past the paddle, the game is over.
+    Lost,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    Playing { moved : u64 },
+    Over { at : u64 },
+}
+
+#[derive(Clone, Copy)]
+pub struct Game {
+    // NOTE: the left LED of the paddle
+    paddle : usize,
+    x      : i32,
+    y      : i32,
+    dx     : i32,
+    dy     : i32,
+    bricks : [[bool; 5]; BRICK_ROWS],
+    walls  : u32,
+    score  : u32,
+    beep   : Option<(u64, u32)>,
+    phase  : Phase,
+}
+
+fn led(at : i32) -> usize {
+    ((at + ONE / 2) / ONE).clamp(0, 4) as usize
+}
+
+impl Game {
+    pub fn new(clock : &impl Clock, random : &mut impl Random) -> Self {
+        let mut game = Game {
+            paddle : 1,
+            x      : 0,
+            y      : 0,
+            dx     : 0,
+            dy     : 0,
+            bricks : [[false; 5]; BRICK_ROWS],
+            walls  : 0,
+            score  : 0,
+            beep   : None,
+            phase  : Phase::Over { at : 0 },
+        };
+        game.serve(clock.now_ms(), random);
+        game
+    }
+
+    /// The bricks cleared.
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    /// The walls cleared.
+    pub fn walls(&self) -> u32 {
+        self.walls
+    }
+
+    /// A new wall and a ball starting off the paddle, up and to a random side.
+    fn serve(&mut self, now : u64, random : &mut impl Random) {
+        let speed = (SPEED + FASTER * self.walls as i32).min(MAX_SPEED);
+        self.x = self.paddle as i32 * ONE + ONE / 2;
+        self.y = PADDLE_ROW - ONE;
+        self.dx = if random.below(2) == 0 { -speed / 2 } else { speed / 2 };
+        self.dy = -speed;
+        self.bricks = [[true; 5]; BRICK_ROWS];
+        self.phase = Phase::Playing { moved : now };
+    }
+
+    /// Moves the paddle, false once the game is over.
+    pub fn control(&mut self, control : Control) -> bool {
+        let Phase::Playing { .. } = self.phase else {
+            return false;
+        };
+        match control {
+            Control::Left  => self.paddle = self.paddle.saturating_sub(1),
+            Control::Right => self.paddle = (self.paddle + 1).min(3),
+        }
+        true
+    }
+
+    /// Moves the ball on to now, off the walls, the bricks and the paddle.
+    pub fn advance(&mut self, clock : &impl Clock, random : &mut impl Random, beeper : &mut impl Beeper) -> Option<Event> {
+        let Phase::Playing { moved } = self.phase else {
+            return None;
+        };
+        let now = clock.now_ms();
+        let dt = now.saturating_sub(moved) as i32;
+        self.phase = Phase::Playing { moved : now };
+        self.x += self.dx * dt / 1000;
+        self.y += self.dy * dt / 1000;
+
+        if self.x < 0 || self.x > MAX {
+            self.x = self.x.clamp(0, MAX);
+            self.dx = -self.dx;
+        }
+        if self.y < 0 {
+            self.y = 0;
+            self.dy = -self.dy;
+        }
+
+        let (column, row) = (led(self.x), led(self.y));
+        if self.dy < 0 && row < BRICK_ROWS && self.bricks[row][column] {
+            self.bricks[row][column] = false;
+            self.dy = -self.dy;
+            self.score += 1;
+            self.beep = Some((now, BRICK_HZ));
+            if self.bricks.iter().flatten().all(|brick| !brick) {
+                self.walls += 1;
+                self.serve(now, random);
+                return Some(Event::Cleared);
+            }
+        }
+
+        if self.dy > 0 && self.y >= PADDLE_ROW - ONE {
+            // NOTE: the half of the paddle hit decides which way the ball goes off
+            let offset = self.x - self.paddle as i32 * ONE;
+            if (-ONE / 2..=ONE * 3 / 2).contains(&offset) {
+                self.y = PADDLE_ROW - ONE;
+                self.dy = -self.dy;
+                let speed = self.dy.abs();
+                self.dx = if offset < ONE / 2 { -speed / 2 } else { speed / 2 };
+                if !(-ONE / 4..=ONE * 5 / 4).contains(&offset) {
+                    self.dx *= 2;
+                }
+                self.beep = Some((now, PADDLE_HZ));
+            } else if self.y > PADDLE_ROW {
+                beeper.tone(LOST_HZ);
+                self.phase = Phase::Over { at : now };
+                return Some(Event::Lost);
+            }
+        }
+        None
+    }
+
+    /// The frame for now with the time to show it for, None once the score went by at the
+    /// end of the game. At the end rain falls on the bricks left, `rain` is its frame that
+    /// many ms into it, see `particles::Effect::at`.
+    pub fn frame(&self, clock : &impl Clock, beeper : &mut impl Beeper, rain : impl FnOnce(u64) -> Frame) -> Option<(Frame, u32)> {
+        let now = clock.now_ms();
+        let mut leds = [[0; 5]; 5];
+        for (row, bricks) in self.bricks.iter().enumerate() {
+            for (column, brick) in bricks.iter().enumerate() {
+                if *brick {
+                    leds[row][column] = BRICK;
+                }
+            }
+        }
+        leds[4][self.paddle] = 9;
+        leds[4][self.paddle + 1] = 9;
+
+        match self.phase {
+            Phase::Playing { .. } => {
+                match self.beep {
+                    Some((at, hz)) if now.saturating_sub(at) < BEEP_MS => beeper.tone(hz),
+                    _ => beeper.off(),
+                }
+                leds[led(self.y)][led(self.x)] = 9;
+            }
+            Phase::Over { at } => {
+                let elapsed = now.saturating_sub(at);
+                if elapsed >= OVER_MS {
+                    let mut text = Text::new();
+                    let _ = write!(text, "score {}", self.score);
+                    return scroll::at(&text, elapsed - OVER_MS);
+                }
+                if elapsed >= OVER_MS / 2 {
+                    beeper.off();
+                }
+                for (row, rain) in leds.iter_mut().zip(rain(elapsed)) {
+                    for (led, rain) in row.iter_mut().zip(rain) {
+                        *led = (*led).max(rain);
+                    }
+                }
+            }
+        }
+        Some((leds, POLL_MS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use super::*;
+
+    struct Ticks(Cell<u64>);
+
+    impl Clock for Ticks {
+        fn now_ms(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    impl Ticks {
+        fn wait(&self, ms : u64) {
+            self.0.set(self.0.get() + ms);
+        }
+    }
+
+    // NOTE: every serve goes off to the left
+    struct Left;
+
+    impl Random for Left {
+        fn below(&mut self, _n : u32) -> u32 {
+            0
+        }
+    }
+
+    struct Tone(u32);
+
+    impl Beeper for Tone {
+        fn tone(&mut self, hz : u32) {
+            self.0 = hz;
+        }
+    }
+
+    fn dry(_elapsed : u64) -> Frame {
+        [[0; 5]; 5]
+    }
+
+    /// A game with the ball at `x`, `y` flying `dx`, `dy`.
+    fn flying(clock : &Ticks, x : i32, y : i32, dx : i32, dy : i32) -> Game {
+        Game { x, y, dx, dy, ..Game::new(clock, &mut Left) }
+    }
+
+    #[test]
+    fn the_serve_goes_up_off_the_paddle() {
+        let clock = Ticks(Cell::new(0));
+        let game = Game::new(&clock, &mut Left);
+        assert_eq!((game.x, game.y), (ONE + ONE / 2, PADDLE_ROW - ONE));
+        assert_eq!((game.dx, game.dy), (-SPEED / 2, -SPEED));
+        assert!(game.bricks.iter().flatten().all(|brick| *brick));
+    }
+
+    #[test]
+    fn the_paddle_half_hit_sends_the_ball_its_way() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut game = flying(&clock, ONE, PADDLE_ROW - ONE - 100, 0, SPEED);
+        clock.wait(100);
+        assert_eq!(game.advance(&clock, &mut Left, &mut beeper), None);
+        assert_eq!((game.y, game.dx, game.dy), (PADDLE_ROW - ONE, -SPEED / 2, -SPEED));
+        game.frame(&clock, &mut beeper, dry);
+        assert_eq!(beeper.0, PADDLE_HZ);
+
+        // NOTE: the far edge of the right half sends it off twice as steep
+        let mut game = flying(&clock, 3 * ONE + 400, PADDLE_ROW - ONE - 100, 0, SPEED);
+        game.control(Control::Right);
+        clock.wait(100);
+        game.advance(&clock, &mut Left, &mut beeper);
+        assert_eq!(game.dx, SPEED);
+    }
+
+    #[test]
+    fn a_brick_hit_scores_and_the_last_brings_a_faster_wall() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut game = flying(&clock, 2 * ONE, ONE + 600, 0, -SPEED);
+        clock.wait(100);
+        assert_eq!(game.advance(&clock, &mut Left, &mut beeper), None);
+        assert!(!game.bricks[1][2]);
+        assert_eq!((game.score(), game.dy), (1, SPEED));
+
+        game.bricks = [[false; 5]; BRICK_ROWS];
+        game.bricks[1][2] = true;
+        (game.y, game.dy) = (ONE + 600, -SPEED);
+        clock.wait(100);
+        assert_eq!(game.advance(&clock, &mut Left, &mut beeper), Some(Event::Cleared));
+        assert_eq!((game.score(), game.walls()), (2, 1));
+        assert!(game.bricks.iter().flatten().all(|brick| *brick));
+        assert_eq!(game.dy, -(SPEED + FASTER));
+    }
+
+    #[test]
+    fn a_ball_past_the_paddle_ends_the_game() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut game = flying(&clock, MAX, PADDLE_ROW - 100, 0, SPEED);
+        clock.wait(100);
+        assert_eq!(game.advance(&clock, &mut Left, &mut beeper), Some(Event::Lost));
+        assert_eq!(beeper.0, LOST_HZ);
+        assert!(!game.control(Control::Left));
+
+        let wet = |_elapsed| [[1; 5]; 5];
+        let (frame, _) = game.frame(&clock, &mut beeper, wet).unwrap();
+        assert_eq!(frame[0][0], BRICK);
+        assert_eq!(frame[2][2], 1);
+        clock.wait(OVER_MS + scroll::frames("score 0").count() as u64 * scroll::STEP_MS as u64);
+        assert_eq!(game.frame(&clock, &mut beeper, wet), None);
+    }
+}
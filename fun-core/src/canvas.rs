@@ -0,0 +1,117 @@
+//! A greyscale canvas larger than the display, shown through a 5x5 window.
+//!
+//! The window pans to follow a point, it moves once the point comes within `MARGIN` of its
+//! edge and never past the canvas edges, so a canvas no larger than the display doesn't pan.
+
+use crate::Frame;
+
+pub const MAX_WIDTH : usize = 16;
+pub const MAX_HEIGHT : usize = 16;
+const MARGIN : usize = 1;
+
+#[derive(Clone, Copy)]
+pub struct Canvas {
+    width  : usize,
+    height : usize,
+    leds   : [[u8; MAX_WIDTH]; MAX_HEIGHT],
+    // NOTE: the top left corner of the window
+    left   : usize,
+    top    : usize,
+}
+
+impl Canvas {
+    /// A dark canvas, cut to `MAX_WIDTH` by `MAX_HEIGHT`.
+    pub fn new(width : usize, height : usize) -> Self {
+        Canvas {
+            width  : width.min(MAX_WIDTH),
+            height : height.min(MAX_HEIGHT),
+            leds   : [[0; MAX_WIDTH]; MAX_HEIGHT],
+            left   : 0,
+            top    : 0,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The level at column `x` of row `y`, 0 off the canvas.
+    pub fn get(&self, x : usize, y : usize) -> u8 {
+        if x < self.width && y < self.height { self.leds[y][x] } else { 0 }
+    }
+
+    /// Sets the level at column `x` of row `y`, ignored off the canvas.
+    pub fn set(&mut self, x : usize, y : usize, level : u8) {
+        if x < self.width && y < self.height {
+            self.leds[y][x] = level;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.leds = [[0; MAX_WIDTH]; MAX_HEIGHT];
+    }
+
+    /// Pans the window just far enough to keep column `x` of row `y` in view.
+    pub fn follow(&mut self, x : usize, y : usize) {
+        fn pan(start : usize, at : usize, len : usize) -> usize {
+            let start = if at < start + MARGIN {
+                at.saturating_sub(MARGIN)
+            } else if at + MARGIN + 1 > start + 5 {
+                at + MARGIN + 1 - 5
+            } else {
+                start
+            };
+            start.min(len.saturating_sub(5))
+        }
+        self.left = pan(self.left, x, self.width);
+        self.top = pan(self.top, y, self.height);
+    }
+
+    /// The part of the canvas in the window.
+    pub fn frame(&self) -> Frame {
+        let mut leds = [[0; 5]; 5];
+        for (row, leds) in leds.iter_mut().enumerate() {
+            for (column, led) in leds.iter_mut().enumerate() {
+                *led = self.get(self.left + column, self.top + row);
+            }
+        }
+        leds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn off_the_canvas_is_dark_and_ignored() {
+        let mut canvas = Canvas::new(8, 8);
+        canvas.set(8, 0, 9);
+        assert_eq!(canvas.get(8, 0), 0);
+        assert_eq!(Canvas::new(99, 1).width(), MAX_WIDTH);
+    }
+
+    #[test]
+    fn window_follows_within_the_edges() {
+        let mut canvas = Canvas::new(10, 10);
+        canvas.set(9, 9, 7);
+        canvas.follow(9, 9);
+        assert_eq!(canvas.frame()[4][4], 7);
+        canvas.follow(3, 9);
+        assert_eq!(canvas.frame()[4][4], 0);
+        canvas.follow(0, 0);
+        assert_eq!(canvas.frame(), [[0; 5]; 5]);
+    }
+
+    #[test]
+    fn a_small_canvas_does_not_pan() {
+        let mut canvas = Canvas::new(5, 5);
+        canvas.set(0, 0, 5);
+        canvas.follow(4, 4);
+        assert_eq!(canvas.frame()[0][0], 5);
+    }
+}
@@ -0,0 +1,179 @@
+//! The measurements of an SHT3x temperature and humidity sensor, and the limits of a greenhouse
+//! watched over them.
+//!
+//! `MEASURE` written to the sensor at `ADDRESS` starts a measurement, `MEASURE_MS` later it
+//! reads as six bytes: the temperature and the humidity, each two bytes and a CRC, which
+//! `decode` turns into tenths of a degree and of a percent. A `Watch` raises an alarm when a
+//! reading goes past one of the `Limits`, once, and lets it go when the reading is back
+//! inside by a margin, so a reading sitting on the limit doesn't raise it over and over.
+
+use heapless::Vec;
+
+pub const ADDRESS : u8 = 0x44;
+// NOTE: single shot, high repeatability, without clock stretching
+pub const MEASURE : [u8; 2] = [0x24, 0x00];
+pub const MEASURE_MS : u32 = 16;
+
+const TEMPERATURE_MARGIN : i16 = 10;
+const HUMIDITY_MARGIN : i16 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reading {
+    pub tenths_c  : i16,
+    pub tenths_rh : i16,
+}
+
+/// The CRC-8 of the SHT3x, polynomial 0x31 starting from 0xff.
+pub fn crc8(bytes : &[u8]) -> u8 {
+    let mut crc = 0xffu8;
+    for byte in bytes {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x31 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// The reading in the six bytes read after a measurement, None when a CRC is off.
+pub fn decode(raw : &[u8; 6]) -> Option<Reading> {
+    if crc8(&raw[..2]) != raw[2] || crc8(&raw[3..5]) != raw[5] {
+        return None;
+    }
+    // NOTE: both span the 16 bits, -45 to 130 °C and 0 to 100 %, rounded to the tenth
+    let scaled = |hi : u8, lo : u8, span : i32| (span * u16::from_be_bytes([hi, lo]) as i32 + 32767) / 65535;
+    Some(Reading {
+        tenths_c  : (scaled(raw[0], raw[1], 1750) - 450) as i16,
+        tenths_rh : scaled(raw[3], raw[4], 1000) as i16,
+    })
+}
+
+/// In whole degrees and percent, a humidity range of 0 to 100 never raises an alarm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    pub temperature : (i8, i8),
+    pub humidity    : (u8, u8),
+}
+
+impl Limits {
+    pub const DEFAULT : Limits = Limits { temperature : (5, 35), humidity : (0, 100) };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alarm {
+    Cold,
+    Hot,
+    Dry,
+    Damp,
+}
+
+const ALARMS : [Alarm; 4] = [Alarm::Cold, Alarm::Hot, Alarm::Dry, Alarm::Damp];
+
+impl Alarm {
+    pub fn name(self) -> &'static str {
+        match self {
+            Alarm::Cold => "cold",
+            Alarm::Hot  => "hot",
+            Alarm::Dry  => "dry",
+            Alarm::Damp => "damp",
+        }
+    }
+
+    /// How far past the limit `reading` is, in tenths, negative while inside.
+    fn excess(self, reading : &Reading, limits : &Limits) -> i16 {
+        let (cold, hot) = limits.temperature;
+        let (dry, damp) = limits.humidity;
+        match self {
+            Alarm::Cold => cold as i16 * 10 - reading.tenths_c,
+            Alarm::Hot  => reading.tenths_c - hot as i16 * 10,
+            Alarm::Dry  => dry as i16 * 10 - reading.tenths_rh,
+            Alarm::Damp => reading.tenths_rh - damp as i16 * 10,
+        }
+    }
+
+    fn margin(self) -> i16 {
+        match self {
+            Alarm::Cold | Alarm::Hot => TEMPERATURE_MARGIN,
+            Alarm::Dry | Alarm::Damp => HUMIDITY_MARGIN,
+        }
+    }
+}
+
+pub struct Watch {
+    raised : [bool; 4],
+}
+
+impl Watch {
+    pub const fn new() -> Self {
+        Watch { raised : [false; 4] }
+    }
+
+    /// The alarms `reading` raises that weren't raised yet.
+    pub fn check(&mut self, reading : &Reading, limits : &Limits) -> Vec<Alarm, 4> {
+        let mut raised = Vec::new();
+        for (alarm, up) in ALARMS.iter().zip(self.raised.iter_mut()) {
+            let excess = alarm.excess(reading, limits);
+            if !*up && excess > 0 {
+                *up = true;
+                let _ = raised.push(*alarm);
+            } else if *up && excess <= -alarm.margin() {
+                *up = false;
+            }
+        }
+        raised
+    }
+
+    /// The alarms up now.
+    pub fn raised(&self) -> impl Iterator<Item = Alarm> + '_ {
+        ALARMS.iter().zip(self.raised).filter(|(_, up)| *up).map(|(alarm, _)| *alarm)
+    }
+}
+
+impl Default for Watch {
+    fn default() -> Self {
+        Watch::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_crc_of_the_datasheet() {
+        assert_eq!(crc8(&[0xbe, 0xef]), 0x92);
+    }
+
+    #[test]
+    fn decode_scales_and_checks() {
+        let mut raw = [0x66, 0x66, 0, 0x80, 0x00, 0];
+        raw[2] = crc8(&raw[..2]);
+        raw[5] = crc8(&raw[3..5]);
+        assert_eq!(decode(&raw), Some(Reading { tenths_c : 250, tenths_rh : 500 }));
+        raw[4] ^= 1;
+        assert_eq!(decode(&raw), None);
+    }
+
+    #[test]
+    fn an_alarm_is_raised_once_until_the_reading_is_well_back() {
+        let limits = Limits::DEFAULT;
+        let mut watch = Watch::new();
+        let at = |tenths_c| Reading { tenths_c, tenths_rh : 500 };
+        assert!(watch.check(&at(350), &limits).is_empty());
+        assert_eq!(watch.check(&at(351), &limits).as_slice(), &[Alarm::Hot]);
+        assert!(watch.check(&at(360), &limits).is_empty());
+        assert!(watch.check(&at(345), &limits).is_empty());
+        assert!(watch.check(&at(351), &limits).is_empty());
+        assert_eq!(watch.raised().collect::<std::vec::Vec<_>>(), [Alarm::Hot]);
+        watch.check(&at(340), &limits);
+        assert_eq!(watch.check(&at(351), &limits).as_slice(), &[Alarm::Hot]);
+    }
+
+    #[test]
+    fn the_default_humidity_range_never_alarms() {
+        let mut watch = Watch::new();
+        for tenths_rh in [0, 1000] {
+            assert!(watch.check(&Reading { tenths_c : 200, tenths_rh }, &Limits::DEFAULT).is_empty());
+        }
+    }
+}
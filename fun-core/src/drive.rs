@@ -0,0 +1,112 @@
+//! Driving two motors through a dual H-bridge, like a DRV8833: the speeds of the left and the
+//! right motor mixed from a throttle and a steering, which come from the tilt of a board or
+//! are stepped with buttons, and the signals for the bridge.
+//!
+//! A motor takes a PWM input and a direction input of the bridge, IN1 and IN2 of the DRV8833.
+//! Direction low, the high part of the PWM drives it forward, so the duty is the speed.
+//! Direction high, the low part drives it backwards and the duty is inverted.
+
+use crate::Frame;
+
+// NOTE: in percent of full speed, either way
+pub const FULL : i8 = 100;
+pub const STEP : i8 = 20;
+// NOTE: the tilt in mg left alone around level, and the tilt that is full speed
+const DEAD_MG : i32 = 150;
+const FULL_MG : i32 = 600;
+const CENTRE_LEVEL : u8 = 2;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Drive {
+    // NOTE: forward is positive
+    pub throttle : i8,
+    // NOTE: to the right is positive
+    pub steering : i8,
+}
+
+fn percent(mg : i32) -> i8 {
+    let beyond = (mg.abs() - DEAD_MG).clamp(0, FULL_MG - DEAD_MG);
+    (mg.signum() * beyond * FULL as i32 / (FULL_MG - DEAD_MG)) as i8
+}
+
+impl Drive {
+    pub const STOP : Drive = Drive { throttle : 0, steering : 0 };
+
+    /// From the tilt in mg, as `motion::Tracker::tilt` has it: the top edge down drives
+    /// forward and the right edge down steers right.
+    pub fn from_tilt(x : i32, y : i32) -> Drive {
+        Drive { throttle : percent(-y), steering : percent(x) }
+    }
+
+    /// `throttle` and `steering` more, each kept within full speed.
+    pub fn step(self, throttle : i8, steering : i8) -> Drive {
+        let within = |value : i8, by : i8| value.saturating_add(by).clamp(-FULL, FULL);
+        Drive { throttle : within(self.throttle, throttle), steering : within(self.steering, steering) }
+    }
+
+    /// The speeds of the left and the right motor, steering right speeds up the left one.
+    pub fn speeds(&self) -> (i8, i8) {
+        let mix = |speed : i16| speed.clamp(-FULL as i16, FULL as i16) as i8;
+        let (throttle, steering) = (self.throttle as i16, self.steering as i16);
+        (mix(throttle + steering), mix(throttle - steering))
+    }
+}
+
+/// The duty in percent of the PWM input and the level of the direction input for `speed`.
+pub fn bridge(speed : i8) -> (u8, bool) {
+    let duty = speed.unsigned_abs().min(FULL as u8);
+    if speed >= 0 { (duty, false) } else { (FULL as u8 - duty, true) }
+}
+
+/// The speeds as two bars each side, up from the middle row forward and down backwards,
+/// the middle row dim.
+pub fn gauge(left : i8, right : i8) -> Frame {
+    let mut frame = [[0; 5]; 5];
+    for (columns, speed) in [([0, 1], left), ([3, 4], right)] {
+        let rows = (speed.unsigned_abs() as usize * 2).div_ceil(FULL as usize).min(2);
+        let lit = if speed >= 0 { 2 - rows..2 } else { 3..3 + rows };
+        for column in columns {
+            frame[2][column] = CENTRE_LEVEL;
+            for row in lit.clone() {
+                frame[row][column] = 9;
+            }
+        }
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tilt_beyond_the_dead_zone_drives() {
+        assert_eq!(Drive::from_tilt(100, -100), Drive::STOP);
+        assert_eq!(Drive::from_tilt(0, -FULL_MG), Drive { throttle : FULL, steering : 0 });
+        assert_eq!(Drive::from_tilt(-1000, 375), Drive { throttle : -50, steering : -FULL });
+    }
+
+    #[test]
+    fn steering_mixes_into_the_speeds() {
+        assert_eq!(Drive { throttle : 60, steering : 20 }.speeds(), (80, 40));
+        assert_eq!(Drive { throttle : 100, steering : -50 }.speeds(), (50, 100));
+        assert_eq!(Drive { throttle : 0, steering : 40 }.speeds(), (40, -40));
+        assert_eq!(Drive::STOP.step(STEP, 0).step(FULL, -STEP), Drive { throttle : FULL, steering : -STEP });
+    }
+
+    #[test]
+    fn backwards_inverts_the_duty() {
+        assert_eq!(bridge(0), (0, false));
+        assert_eq!(bridge(70), (70, false));
+        assert_eq!(bridge(-70), (30, true));
+        assert_eq!(bridge(-FULL), (0, true));
+    }
+
+    #[test]
+    fn the_gauge_shows_either_way() {
+        let frame = gauge(FULL, -40);
+        assert_eq!(frame.map(|row| row[0]), [9, 9, CENTRE_LEVEL, 0, 0]);
+        assert_eq!(frame.map(|row| row[4]), [0, 0, CENTRE_LEVEL, 9, 0]);
+        assert_eq!(gauge(0, 0)[2], [CENTRE_LEVEL, CENTRE_LEVEL, 0, CENTRE_LEVEL, CENTRE_LEVEL]);
+    }
+}
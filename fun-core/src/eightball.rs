@@ -0,0 +1,178 @@
+//! Magic 8-ball: shake the board for an answer.
+//!
+//! A shake sets the display shimmering for a moment, then one of `ANSWERS` picked at random
+//! scrolls by. Until the next shake a question mark waits.
+
+use crate::scroll;
+use crate::{Clock, Frame, Random};
+
+const ANSWERS : [&str; 12] = [
+    "yes",
+    "no",
+    "maybe",
+    "ask again",
+    "certainly",
+    "doubtful",
+    "no way",
+    "without a doubt",
+    "not now",
+    "looks good",
+    "unlikely",
+    "who knows",
+];
+
+const SHIMMER_MS : u64 = 1200;
+const SHIMMER_STEP_MS : u32 = 60;
+const POLL_MS : u32 = 50;
+
+const QUESTION : Frame = [
+    [0, 1, 1, 1, 0],
+    [0, 0, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Waiting,
+    Shimmering { at : u64 },
+    Answering { answer : usize, at : u64 },
+}
+
+#[derive(Clone, Copy)]
+pub struct Ball {
+    state : State,
+}
+
+impl Ball {
+    pub const fn new() -> Self {
+        Ball { state : State::Waiting }
+    }
+
+    /// Starts the shimmer, a shake during an answer asks again.
+    pub fn shake(&mut self, clock : &impl Clock) {
+        self.state = State::Shimmering { at : clock.now_ms() };
+    }
+
+    /// Drops the answer showing, back to waiting for a shake.
+    pub fn settle(&mut self) {
+        if let State::Answering { .. } = self.state {
+            self.state = State::Waiting;
+        }
+    }
+
+    /// Picks the answer once the shimmer is over.
+    pub fn advance(&mut self, clock : &impl Clock, random : &mut impl Random) {
+        let now = clock.now_ms();
+        if let State::Shimmering { at } = self.state {
+            if now.saturating_sub(at) >= SHIMMER_MS {
+                self.state = State::Answering { answer : random.below(ANSWERS.len() as u32) as usize, at : now };
+            }
+        }
+    }
+
+    /// The frame for now with the time to show it for, None once the answer went by. The
+    /// shimmer is drawn from `random`.
+    pub fn frame(&self, clock : &impl Clock, random : &mut impl Random) -> Option<(Frame, u32)> {
+        let now = clock.now_ms();
+        match self.state {
+            State::Waiting => {
+                let level = if (now / 600).is_multiple_of(2) { 9 } else { 4 };
+                Some((QUESTION.map(|row| row.map(|led| led * level)), POLL_MS))
+            }
+            State::Shimmering { at } => {
+                // NOTE: random LEDs at random levels, fading in as the answer comes nearer
+                let top = 1 + (now.saturating_sub(at) * 8 / SHIMMER_MS) as u32;
+                let mut leds = [[0; 5]; 5];
+                for led in leds.iter_mut().flatten() {
+                    if random.below(3) == 0 {
+                        *led = 1 + random.below(top) as u8;
+                    }
+                }
+                Some((leds, SHIMMER_STEP_MS))
+            }
+            State::Answering { answer, at } => scroll::at(ANSWERS[answer], now.saturating_sub(at)),
+        }
+    }
+}
+
+impl Default for Ball {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use super::*;
+
+    struct Ticks(Cell<u64>);
+
+    impl Clock for Ticks {
+        fn now_ms(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    impl Ticks {
+        fn wait(&self, ms : u64) {
+            self.0.set(self.0.get() + ms);
+        }
+    }
+
+    // NOTE: the same number every time, as high as it goes
+    struct Highest;
+
+    impl Random for Highest {
+        fn below(&mut self, n : u32) -> u32 {
+            n - 1
+        }
+    }
+
+    // NOTE: lights every LED of the shimmer
+    struct Lowest;
+
+    impl Random for Lowest {
+        fn below(&mut self, _n : u32) -> u32 {
+            0
+        }
+    }
+
+    #[test]
+    fn a_shake_shimmers_then_answers() {
+        let clock = Ticks(Cell::new(0));
+        let mut ball = Ball::new();
+        assert_eq!(ball.frame(&clock, &mut Lowest), Some((QUESTION.map(|row| row.map(|led| led * 9)), POLL_MS)));
+        ball.shake(&clock);
+        clock.wait(SHIMMER_MS - 1);
+        ball.advance(&clock, &mut Highest);
+        assert_eq!(ball.frame(&clock, &mut Lowest), Some(([[1; 5]; 5], SHIMMER_STEP_MS)));
+
+        clock.wait(1);
+        ball.advance(&clock, &mut Highest);
+        assert_eq!(ball.state, State::Answering { answer : ANSWERS.len() - 1, at : SHIMMER_MS });
+        assert!(ball.frame(&clock, &mut Lowest).is_some());
+        clock.wait(scroll::frames(ANSWERS[ANSWERS.len() - 1]).count() as u64 * scroll::STEP_MS as u64);
+        assert_eq!(ball.frame(&clock, &mut Lowest), None);
+        ball.settle();
+        assert_eq!(ball.state, State::Waiting);
+    }
+
+    #[test]
+    fn settling_leaves_a_shimmer_be() {
+        let clock = Ticks(Cell::new(0));
+        let mut ball = Ball::new();
+        ball.shake(&clock);
+        ball.settle();
+        assert_eq!(ball.state, State::Shimmering { at : 0 });
+    }
+
+    #[test]
+    fn the_question_mark_blinks() {
+        let clock = Ticks(Cell::new(600));
+        let (frame, _) = Ball::new().frame(&clock, &mut Lowest).unwrap();
+        assert_eq!(frame[0][1], 4);
+    }
+}
@@ -0,0 +1,261 @@
+//! Flappy: a flap sends the pixel up, gravity pulls it down, and pipes with a gap scroll in
+//! from the right.
+//!
+//! The pixel stays in the second column, flying into a pipe or the ground ends the flight and
+//! every pipe passed scores. It falls smoothly between rows, so it wants a frame every
+//! `POLL_MS`, the frames drawn in flight make the frame rate it reached.
+
+use core::fmt::Write;
+use crate::scroll::{self, Text};
+use crate::{Clock, Frame, Random};
+
+const BIRD : usize = 1;
+const GAP : usize = 2;
+// NOTE: a pipe every few columns
+const PIPE_EVERY : u8 = 3;
+const SCROLL_MS : u64 = 350;
+// NOTE: the height is kept in thousandths of a row, growing downwards
+const GRAVITY : i32 = 14_000;
+const FLAP : i32 = -5_500;
+const GROUND : i32 = 4_500;
+const CRASH_MS : u64 = 800;
+const PIPE : u8 = 4;
+const POLL_MS : u32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    Ready,
+    Flying { since : u64, moved : u64, scrolled : u64 },
+    Crashed { at : u64, flown_ms : u64 },
+}
+
+#[derive(Clone, Copy)]
+pub struct Game {
+    height   : i32,
+    velocity : i32,
+    // NOTE: the top row of the gap of the pipe in each column, if there is one
+    pipes    : [Option<u8>; 5],
+    next     : u8,
+    score    : u32,
+    frames   : u32,
+    phase    : Phase,
+}
+
+fn row(height : i32) -> usize {
+    ((height + 500) / 1000).clamp(0, 4) as usize
+}
+
+impl Game {
+    /// The pixel waiting in the middle for the first flap.
+    pub const fn new() -> Self {
+        Game { height : 2000, velocity : 0, pipes : [None; 5], next : PIPE_EVERY, score : 0, frames : 0, phase : Phase::Ready }
+    }
+
+    /// The pipes passed.
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    /// The frames a second drawn in flight, once it crashed.
+    pub fn frame_rate(&self) -> Option<u64> {
+        let Phase::Crashed { flown_ms, .. } = self.phase else {
+            return None;
+        };
+        Some(self.frames as u64 * 1000 / flown_ms.max(1))
+    }
+
+    /// Sends the pixel up, the first flap starts the flight. False once it crashed.
+    pub fn flap(&mut self, clock : &impl Clock) -> bool {
+        let now = clock.now_ms();
+        match self.phase {
+            Phase::Ready => self.phase = Phase::Flying { since : now, moved : now, scrolled : now },
+            Phase::Flying { .. } => (),
+            Phase::Crashed { .. } => return false,
+        }
+        self.velocity = FLAP;
+        true
+    }
+
+    fn crash(&mut self, now : u64, since : u64) -> bool {
+        self.phase = Phase::Crashed { at : now, flown_ms : now.saturating_sub(since) };
+        true
+    }
+
+    /// Moves the pixel and the pipes on to now, true when that just crashed it.
+    pub fn advance(&mut self, clock : &impl Clock, random : &mut impl Random) -> bool {
+        let Phase::Flying { since, moved, mut scrolled } = self.phase else {
+            return false;
+        };
+        let now = clock.now_ms();
+        self.frames += 1;
+
+        let dt = now.saturating_sub(moved) as i32;
+        self.velocity += GRAVITY * dt / 1000;
+        self.height += self.velocity * dt / 1000;
+        if self.height < 0 {
+            self.height = 0;
+            self.velocity = 0;
+        }
+        if self.height > GROUND {
+            return self.crash(now, since);
+        }
+
+        if now.saturating_sub(scrolled) >= SCROLL_MS {
+            if self.pipes[BIRD].is_some() {
+                self.score += 1;
+            }
+            self.pipes.rotate_left(1);
+            self.next -= 1;
+            self.pipes[4] = if self.next == 0 {
+                self.next = PIPE_EVERY;
+                Some(random.below((5 - GAP as u32) + 1) as u8)
+            } else {
+                None
+            };
+            scrolled = now;
+        }
+        if let Some(gap) = self.pipes[BIRD] {
+            let gap = gap as usize;
+            if !(gap..gap + GAP).contains(&row(self.height)) {
+                return self.crash(now, since);
+            }
+        }
+        self.phase = Phase::Flying { since, moved : now, scrolled };
+        false
+    }
+
+    fn field(&self) -> Frame {
+        let mut leds = [[0; 5]; 5];
+        for (column, pipe) in self.pipes.iter().enumerate() {
+            let Some(gap) = pipe else { continue };
+            for (row, leds) in leds.iter_mut().enumerate() {
+                if !(*gap as usize..*gap as usize + GAP).contains(&row) {
+                    leds[column] = PIPE;
+                }
+            }
+        }
+        leds
+    }
+
+    /// The frame for now with the time to show it for, None once the score went by after the
+    /// crash.
+    pub fn frame(&self, clock : &impl Clock) -> Option<(Frame, u32)> {
+        let now = clock.now_ms();
+        let mut leds = self.field();
+        match self.phase {
+            // NOTE: the pixel bobs while it waits for the first flap
+            Phase::Ready => leds[if (now / 400).is_multiple_of(2) { 2 } else { 1 }][BIRD] = 9,
+            Phase::Flying { .. } => leds[row(self.height)][BIRD] = 9,
+            Phase::Crashed { at, .. } => {
+                let elapsed = now.saturating_sub(at);
+                if elapsed >= CRASH_MS {
+                    let mut text = Text::new();
+                    let _ = write!(text, "score {}", self.score);
+                    return scroll::at(&text, elapsed - CRASH_MS);
+                }
+                leds[row(self.height)][BIRD] = if (elapsed / 100).is_multiple_of(2) { 9 } else { 0 };
+            }
+        }
+        Some((leds, POLL_MS))
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use super::*;
+
+    struct Ticks(Cell<u64>);
+
+    impl Clock for Ticks {
+        fn now_ms(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    impl Ticks {
+        fn wait(&self, ms : u64) {
+            self.0.set(self.0.get() + ms);
+        }
+    }
+
+    // NOTE: every gap at the top
+    struct Top;
+
+    impl Random for Top {
+        fn below(&mut self, _n : u32) -> u32 {
+            0
+        }
+    }
+
+    #[test]
+    fn without_a_flap_it_falls_to_the_ground() {
+        let clock = Ticks(Cell::new(0));
+        let mut game = Game::new();
+        clock.wait(5_000);
+        assert!(!game.advance(&clock, &mut Top));
+        assert!(game.flap(&clock));
+        let mut polls = 0;
+        while !game.advance(&clock, &mut Top) {
+            clock.wait(POLL_MS as u64);
+            polls += 1;
+        }
+        // NOTE: up a row first, then down past the bottom one, in a little over a second
+        assert!((100..120).contains(&polls), "{}", polls);
+        assert_eq!(game.frame_rate(), Some(1000 / POLL_MS as u64));
+        assert!(!game.flap(&clock));
+        assert_eq!(game.score(), 0);
+    }
+
+    #[test]
+    fn a_pipe_passed_scores() {
+        let clock = Ticks(Cell::new(0));
+        let mut game = Game::new();
+        game.flap(&clock);
+        game.pipes[BIRD] = Some(1);
+        clock.wait(POLL_MS as u64);
+        assert!(!game.advance(&clock, &mut Top));
+        clock.wait(SCROLL_MS);
+        game.velocity = 0;
+        assert!(!game.advance(&clock, &mut Top));
+        assert_eq!(game.score(), 1);
+        assert_eq!(game.pipes[BIRD], None);
+    }
+
+    #[test]
+    fn flying_into_a_pipe_crashes() {
+        let clock = Ticks(Cell::new(0));
+        let mut game = Game::new();
+        game.flap(&clock);
+        game.pipes[BIRD] = Some(3);
+        clock.wait(POLL_MS as u64);
+        assert!(game.advance(&clock, &mut Top));
+        let (frame, _) = game.frame(&clock).unwrap();
+        assert_eq!(frame[2][BIRD], 9);
+        assert_eq!(frame[0][BIRD], PIPE);
+        clock.wait(CRASH_MS + scroll::frames("score 0").count() as u64 * scroll::STEP_MS as u64);
+        assert_eq!(game.frame(&clock), None);
+    }
+
+    #[test]
+    fn pipes_come_in_every_few_columns() {
+        let clock = Ticks(Cell::new(0));
+        let mut game = Game::new();
+        game.flap(&clock);
+        for _ in 0..PIPE_EVERY {
+            clock.wait(SCROLL_MS);
+            game.velocity = FLAP;
+            game.height = 2000;
+            game.advance(&clock, &mut Top);
+        }
+        assert_eq!(game.pipes, [None, None, None, None, Some(0)]);
+        assert_eq!(game.field()[2][4], PIPE);
+        assert_eq!(game.field()[1][4], 0);
+    }
+}
@@ -0,0 +1,167 @@
+//! Following a line with two analog reflectance sensors, a PID controller in fixed point
+//! steering two motors, see `drive`.
+//!
+//! The sensors sit either side of the line and read higher over it, the dark line reflecting
+//! less, like a TCRT5000 with its pull-up. The position of the line is their normalised
+//! difference, -100 under the left sensor to 100 under the right one, None when neither
+//! reads `DARK`. The gains are in hundredths, the integral is kept within `INTEGRAL_LIMIT`
+//! so it can't wind up while the robot is off the line.
+
+use crate::drive::{Drive, FULL};
+use crate::Frame;
+
+// NOTE: the raw 12 bit reading at least one sensor has over the line
+pub const DARK : u16 = 1200;
+const INTEGRAL_LIMIT : i32 = 2000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tuning {
+    // NOTE: hundredths of steering per percent of position
+    pub kp    : i16,
+    pub ki    : i16,
+    pub kd    : i16,
+    // NOTE: the throttle in percent
+    pub speed : i8,
+}
+
+impl Tuning {
+    pub const DEFAULT : Tuning = Tuning { kp : 80, ki : 0, kd : 40, speed : 40 };
+
+    pub fn encode(&self) -> [u8; 7] {
+        let mut bytes = [0; 7];
+        bytes[..2].copy_from_slice(&self.kp.to_le_bytes());
+        bytes[2..4].copy_from_slice(&self.ki.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.kd.to_le_bytes());
+        bytes[6] = self.speed as u8;
+        bytes
+    }
+
+    pub fn decode(bytes : &[u8]) -> Option<Tuning> {
+        let bytes : &[u8; 7] = bytes.try_into().ok()?;
+        Some(Tuning {
+            kp    : i16::from_le_bytes([bytes[0], bytes[1]]),
+            ki    : i16::from_le_bytes([bytes[2], bytes[3]]),
+            kd    : i16::from_le_bytes([bytes[4], bytes[5]]),
+            speed : bytes[6] as i8,
+        })
+    }
+}
+
+/// Where the line is between the sensors, None when it is under neither.
+pub fn position(left : u16, right : u16) -> Option<i8> {
+    if left.max(right) < DARK {
+        return None;
+    }
+    let (left, right) = (left as i32, right as i32);
+    Some(((right - left) * 100 / (right + left)) as i8)
+}
+
+pub struct Pid {
+    integral : i32,
+    last     : Option<i8>,
+}
+
+impl Pid {
+    pub const fn new() -> Self {
+        Pid { integral : 0, last : None }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Pid::new();
+    }
+
+    /// The steering towards the line at `position`, a sample later than the last one.
+    pub fn update(&mut self, tuning : &Tuning, position : i8) -> i8 {
+        let error = position as i32;
+        self.integral = (self.integral + error).clamp(-INTEGRAL_LIMIT, INTEGRAL_LIMIT);
+        let change = self.last.map_or(0, |last| error - last as i32);
+        self.last = Some(position);
+        let hundredths = tuning.kp as i32 * error + tuning.ki as i32 * self.integral + tuning.kd as i32 * change;
+        (hundredths / 100).clamp(-(FULL as i32), FULL as i32) as i8
+    }
+
+    /// The drive for the sensor readings, stopped when the line is lost.
+    pub fn drive(&mut self, tuning : &Tuning, left : u16, right : u16) -> Drive {
+        match position(left, right) {
+            Some(position) => Drive { throttle : tuning.speed, steering : self.update(tuning, position) },
+            None => {
+                self.reset();
+                Drive::STOP
+            }
+        }
+    }
+}
+
+impl Default for Pid {
+    fn default() -> Self {
+        Pid::new()
+    }
+}
+
+/// The line as a column under the board where it is, a dim frame round the edge when lost.
+pub fn marker(position : Option<i8>) -> Frame {
+    let mut frame = [[0; 5]; 5];
+    match position {
+        Some(position) => {
+            let column = ((position as i32 + 100) * 5 / 201) as usize;
+            for row in frame.iter_mut() {
+                row[column] = 9;
+            }
+        }
+        None => {
+            for (y, row) in frame.iter_mut().enumerate() {
+                for (x, led) in row.iter_mut().enumerate() {
+                    if x % 4 == 0 || y % 4 == 0 {
+                        *led = 2;
+                    }
+                }
+            }
+        }
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_position_is_the_normalised_difference() {
+        assert_eq!(position(2000, 2000), Some(0));
+        assert_eq!(position(0, 3000), Some(100));
+        assert_eq!(position(3000, 1000), Some(-50));
+        assert_eq!(position(DARK - 1, 100), None);
+    }
+
+    #[test]
+    fn the_pid_steers_towards_the_line() {
+        let tuning = Tuning { kp : 100, ki : 10, kd : 50, speed : 30 };
+        let mut pid = Pid::new();
+        // NOTE: 20 + 2, no change on the first sample
+        assert_eq!(pid.update(&tuning, 20), 22);
+        // NOTE: 30 + 5 + 5
+        assert_eq!(pid.update(&tuning, 30), 40);
+        assert_eq!(pid.update(&tuning, -100), -FULL);
+        assert_eq!(pid.drive(&tuning, 0, 0), Drive::STOP);
+        assert_eq!(pid.drive(&tuning, 2000, 2000), Drive { throttle : 30, steering : 0 });
+    }
+
+    #[test]
+    fn the_integral_doesnt_wind_up() {
+        let tuning = Tuning { kp : 0, ki : 100, kd : 0, speed : 0 };
+        let mut pid = Pid::new();
+        for _ in 0..1000 {
+            pid.update(&tuning, 100);
+        }
+        assert_eq!(pid.update(&tuning, -100), FULL);
+        assert_eq!(Tuning::decode(&tuning.encode()), Some(tuning));
+    }
+
+    #[test]
+    fn the_marker_follows_the_line() {
+        assert_eq!(marker(Some(-100))[0], [9, 0, 0, 0, 0]);
+        assert_eq!(marker(Some(0))[4], [0, 0, 9, 0, 0]);
+        assert_eq!(marker(Some(100))[2], [0, 0, 0, 0, 9]);
+        assert_eq!(marker(None)[0], [2; 5]);
+    }
+}
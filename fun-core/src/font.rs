@@ -0,0 +1,246 @@
+//! 5x5 font for scrolling text on the LED matrix, and for one or two characters standing
+//! still.
+//!
+//! A glyph is five rows, the leftmost led of a row is bit 4. Lowercase letters are shown
+//! as uppercase, characters without a glyph as `?`. Two digits side by side take the two
+//! columns wide `NARROW_DIGITS` when their usual glyphs don't fit.
+
+pub type Glyph = [u8; 5];
+
+const fn glyph(rows : [&str; 5]) -> Glyph {
+    let mut glyph = [0; 5];
+    let mut row = 0;
+    while row < 5 {
+        let bits = rows[row].as_bytes();
+        let mut column = 0;
+        while column < 5 {
+            if bits[column] == b'#' {
+                glyph[row] |= 1 << (4 - column);
+            }
+            column += 1;
+        }
+        row += 1;
+    }
+    glyph
+}
+
+const UNKNOWN : Glyph = glyph([".##..", "#..#.", "..#..", ".....", "..#.."]);
+
+// NOTE: ordered like ASCII, starting at the space
+const GLYPHS : [Glyph; 59] = [
+    glyph([".....", ".....", ".....", ".....", "....."]), // ' '
+    glyph([".#...", ".#...", ".#...", ".....", ".#..."]), // !
+    glyph(["#.#..", "#.#..", ".....", ".....", "....."]), // "
+    glyph([".#.#.", "#####", ".#.#.", "#####", ".#.#."]), // #
+    glyph([".###.", "##...", ".##..", "..##.", "###.."]), // $
+    glyph(["##..#", "##.#.", "..#..", ".#.##", "#..##"]), // %
+    glyph([".#...", "#.#..", ".#...", "#.#..", ".#.#."]), // &
+    glyph([".#...", ".#...", ".....", ".....", "....."]), // '
+    glyph(["..#..", ".#...", ".#...", ".#...", "..#.."]), // (
+    glyph([".#...", "..#..", "..#..", "..#..", ".#..."]), // )
+    glyph([".....", "#.#..", ".#...", "#.#..", "....."]), // *
+    glyph([".....", ".#...", "###..", ".#...", "....."]), // +
+    glyph([".....", ".....", ".....", ".#...", "#...."]), // ,
+    glyph([".....", ".....", "###..", ".....", "....."]), // -
+    glyph([".....", ".....", ".....", ".....", "#...."]), // .
+    glyph(["....#", "...#.", "..#..", ".#...", "#...."]), // /
+    glyph([".##..", "#.##.", "##.#.", "#..#.", ".##.."]), // 0
+    glyph([".#...", "##...", ".#...", ".#...", "###.."]), // 1
+    glyph(["###..", "...#.", ".##..", "#....", "####."]), // 2
+    glyph(["####.", "...#.", "..#..", "#..#.", ".##.."]), // 3
+    glyph(["..##.", ".#.#.", "#..#.", "#####", "...#."]), // 4
+    glyph(["####.", "#....", "###..", "...#.", "###.."]), // 5
+    glyph(["...#.", "..#..", ".###.", "#...#", ".###."]), // 6
+    glyph(["#####", "...#.", "..#..", ".#...", "#...."]), // 7
+    glyph([".###.", "#...#", ".###.", "#...#", ".###."]), // 8
+    glyph([".###.", "#...#", ".###.", "..#..", ".#..."]), // 9
+    glyph([".....", "#....", ".....", "#....", "....."]), // :
+    glyph([".....", ".#...", ".....", ".#...", "#...."]), // ;
+    glyph(["..#..", ".#...", "#....", ".#...", "..#.."]), // <
+    glyph([".....", "###..", ".....", "###..", "....."]), // =
+    glyph(["#....", ".#...", "..#..", ".#...", "#...."]), // >
+    UNKNOWN,                                              // ?
+    glyph([".###.", "#..##", "#.#.#", "#..#.", ".##.."]), // @
+    glyph([".##..", "#..#.", "####.", "#..#.", "#..#."]), // A
+    glyph(["###..", "#..#.", "###..", "#..#.", "###.."]), // B
+    glyph([".###.", "#....", "#....", "#....", ".###."]), // C
+    glyph(["###..", "#..#.", "#..#.", "#..#.", "###.."]), // D
+    glyph(["####.", "#....", "###..", "#....", "####."]), // E
+    glyph(["####.", "#....", "###..", "#....", "#...."]), // F
+    glyph([".###.", "#....", "#..##", "#...#", ".###."]), // G
+    glyph(["#..#.", "#..#.", "####.", "#..#.", "#..#."]), // H
+    glyph(["###..", ".#...", ".#...", ".#...", "###.."]), // I
+    glyph(["#####", "...#.", "...#.", "#..#.", ".##.."]), // J
+    glyph(["#..#.", "#.#..", "##...", "#.#..", "#..#."]), // K
+    glyph(["#....", "#....", "#....", "#....", "####."]), // L
+    glyph(["#...#", "##.##", "#.#.#", "#...#", "#...#"]), // M
+    glyph(["#...#", "##..#", "#.#.#", "#..##", "#...#"]), // N
+    glyph([".##..", "#..#.", "#..#.", "#..#.", ".##.."]), // O
+    glyph(["###..", "#..#.", "###..", "#....", "#...."]), // P
+    glyph([".##..", "#..#.", "#..#.", ".##..", "..##."]), // Q
+    glyph(["###..", "#..#.", "###..", "#..#.", "#...#"]), // R
+    glyph([".###.", "#....", ".##..", "...#.", "###.."]), // S
+    glyph(["#####", "..#..", "..#..", "..#..", "..#.."]), // T
+    glyph(["#..#.", "#..#.", "#..#.", "#..#.", ".##.."]), // U
+    glyph(["#...#", "#...#", "#...#", ".#.#.", "..#.."]), // V
+    glyph(["#...#", "#...#", "#.#.#", "##.##", "#...#"]), // W
+    glyph(["#..#.", "#..#.", ".##..", "#..#.", "#..#."]), // X
+    glyph(["#...#", ".#.#.", "..#..", "..#..", "..#.."]), // Y
+    glyph(["####.", "..#..", ".#...", "#....", "####."]), // Z
+];
+
+// NOTE: seven segments squeezed into two columns, the 0 is solid so the 8 has a hole
+const NARROW_DIGITS : [Glyph; 10] = [
+    glyph(["##...", "##...", "##...", "##...", "##..."]), // 0
+    glyph([".#...", "##...", ".#...", ".#...", ".#..."]), // 1
+    glyph(["##...", ".#...", "##...", "#....", "##..."]), // 2
+    glyph(["##...", ".#...", "##...", ".#...", "##..."]), // 3
+    glyph(["#....", "#....", "##...", ".#...", ".#..."]), // 4
+    glyph(["##...", "#....", "##...", ".#...", "##..."]), // 5
+    glyph(["#....", "#....", "##...", "##...", "##..."]), // 6
+    glyph(["##...", ".#...", ".#...", ".#...", ".#..."]), // 7
+    glyph(["##...", "##...", ".....", "##...", "##..."]), // 8
+    glyph(["##...", "##...", "##...", ".#...", ".#..."]), // 9
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+pub fn glyph_of(c : char) -> Glyph {
+    let c = c.to_ascii_uppercase();
+    match c {
+        ' '..='Z' => GLYPHS[c as usize - ' ' as usize],
+        '_' => glyph([".....", ".....", ".....", ".....", "####."]),
+        _ => UNKNOWN,
+    }
+}
+
+/// A single character, standing still.
+pub fn frame_of(c : char) -> [[u8; 5]; 5] {
+    let mut frame = [[0; 5]; 5];
+    for (leds, bits) in frame.iter_mut().zip(glyph_of(c)) {
+        for (column, led) in leds.iter_mut().enumerate() {
+            *led = (bits >> (4 - column)) & 1;
+        }
+    }
+    frame
+}
+
+/// Columns the glyph uses, counted from the left, so narrow glyphs scroll by faster.
+pub fn width(glyph : &Glyph) -> usize {
+    let used = glyph.iter().fold(0, |used, row| used | row);
+    if used == 0 {
+        // NOTE: the space
+        return 3;
+    }
+    5 - used.trailing_zeros() as usize
+}
+
+/// One or two characters standing still, lined up by `align`. The glyphs are trimmed to the
+/// columns they light with a blank one between them. None for more characters, or two that
+/// don't fit even so, those have to scroll.
+pub fn render(text : &str, align : Align) -> Option<[[u8; 5]; 5]> {
+    let mut chars = text.chars();
+    let glyphs = match (chars.next(), chars.next(), chars.next()) {
+        (Some(c), None, _) => [Some(trimmed(glyph_of(c))), None],
+        (Some(first), Some(second), None) => {
+            let glyphs = [trimmed(glyph_of(first)), trimmed(glyph_of(second))];
+            match [first.to_digit(10), second.to_digit(10)] {
+                [Some(first), Some(second)] if glyphs[0].1 + 1 + glyphs[1].1 > 5 => {
+                    [Some((NARROW_DIGITS[first as usize], 2)), Some((NARROW_DIGITS[second as usize], 2))]
+                }
+                _ => glyphs.map(Some),
+            }
+        }
+        _ => return None,
+    };
+    let total = glyphs.iter().flatten().map(|(_, width)| width + 1).sum::<usize>() - 1;
+    if total > 5 {
+        return None;
+    }
+    let mut column = match align {
+        Align::Left   => 0,
+        Align::Center => (5 - total) / 2,
+        Align::Right  => 5 - total,
+    };
+    let mut frame = [[0; 5]; 5];
+    for (glyph, width) in glyphs.into_iter().flatten() {
+        for (leds, bits) in frame.iter_mut().zip(glyph) {
+            for (offset, led) in leds[column..column + width].iter_mut().enumerate() {
+                *led = (bits >> (4 - offset)) & 1;
+            }
+        }
+        column += width + 1;
+    }
+    Some(frame)
+}
+
+/// The glyph moved over to the left edge, and its width from there.
+fn trimmed(glyph : Glyph) -> (Glyph, usize) {
+    let used = glyph.iter().fold(0, |used, row| used | row);
+    if used == 0 {
+        return (glyph, width(&glyph));
+    }
+    let blank = used.leading_zeros() as usize - 3;
+    let glyph = glyph.map(|row| row << blank & 0b11111);
+    (glyph, width(&glyph))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowercase_shows_as_uppercase() {
+        assert_eq!(glyph_of('k'), glyph_of('K'));
+        assert_eq!(glyph_of('~'), UNKNOWN);
+    }
+
+    #[test]
+    fn width_counts_from_the_left() {
+        assert_eq!(width(&glyph_of('I')), 3);
+        assert_eq!(width(&glyph_of('M')), 5);
+        assert_eq!(width(&glyph_of(' ')), 3);
+    }
+
+    #[test]
+    fn frame_of_lights_the_glyph() {
+        let frame = frame_of('_');
+        assert_eq!(frame[4], [1, 1, 1, 1, 0]);
+        assert!(frame[..4].iter().flatten().all(|&led| led == 0));
+    }
+
+    #[test]
+    fn single_character_lines_up() {
+        let at = |align| render("1", align).unwrap().map(|row| row.iter().position(|&led| led == 1));
+        assert_eq!(at(Align::Left)[4], Some(0));
+        assert_eq!(at(Align::Center)[4], Some(1));
+        assert_eq!(at(Align::Right)[4], Some(2));
+        assert_eq!(render("M", Align::Right), Some(frame_of('M')));
+    }
+
+    #[test]
+    fn narrow_pair_keeps_its_glyphs() {
+        let frame = render("I:", Align::Left).unwrap();
+        assert_eq!(frame[1], [0, 1, 0, 0, 1]);
+        assert_eq!(frame[2], [0, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn two_digits_go_narrow() {
+        let frame = render("47", Align::Center).unwrap();
+        assert_eq!(frame[0], [1, 0, 0, 1, 1]);
+        assert_eq!(frame[4], [0, 1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn too_wide_does_not_render() {
+        assert_eq!(render("MW", Align::Center), None);
+        assert_eq!(render("123", Align::Center), None);
+        assert_eq!(render("", Align::Center), None);
+    }
+}
@@ -0,0 +1,178 @@
+//! Frames on a byte stream shared with text: the data and its CRC-16, COBS-encoded so the
+//! frame holds no zero byte, between two zero bytes.
+//!
+//! The zero in front ends whatever text came before, so a reader that starts mid-stream or
+//! sees a line of text loses no frame. Bytes outside a frame are handed back as text, a frame
+//! whose CRC doesn't match is dropped.
+
+use heapless::Vec;
+
+/// The most data a frame carries.
+pub const DATA_LEN : usize = 64;
+// NOTE: the CRC and the COBS code byte, one is enough for less than 254 bytes
+const STUFFED_LEN : usize = DATA_LEN + 2 + 1;
+/// The longest frame on the wire, with both zero bytes.
+pub const FRAME_LEN : usize = STUFFED_LEN + 2;
+
+pub type Data = Vec<u8, DATA_LEN>;
+
+/// CRC-16/CCITT-FALSE, the one the host's crcmod and most tools call "ccitt".
+pub fn crc16(bytes : &[u8]) -> u16 {
+    bytes.iter().fold(0xffff, |crc, byte| {
+        (0..8).fold(crc ^ (*byte as u16) << 8, |crc, _| {
+            if crc & 0x8000 != 0 { crc << 1 ^ 0x1021 } else { crc << 1 }
+        })
+    })
+}
+
+/// `data` as a frame, None when it is longer than `DATA_LEN`.
+pub fn encode(data : &[u8]) -> Option<Vec<u8, FRAME_LEN>> {
+    if data.len() > DATA_LEN {
+        return None;
+    }
+    let crc = crc16(data).to_be_bytes();
+    let mut frame = Vec::new();
+    let _ = frame.push(0);
+    // NOTE: every zero is replaced by the distance to the next one, the code byte in front
+    // holds the distance to the first
+    let mut code = frame.len();
+    let _ = frame.push(1);
+    for byte in data.iter().chain(&crc) {
+        if *byte == 0 {
+            code = frame.len();
+            let _ = frame.push(1);
+        } else {
+            let _ = frame.push(*byte);
+            frame[code] += 1;
+        }
+    }
+    let _ = frame.push(0);
+    Some(frame)
+}
+
+fn unstuff(stuffed : &[u8]) -> Option<Data> {
+    let mut bytes = Vec::<u8, STUFFED_LEN>::new();
+    let mut rest = stuffed;
+    while let Some((&code, after)) = rest.split_first() {
+        let run = code as usize - 1;
+        if after.len() < run {
+            return None;
+        }
+        bytes.extend_from_slice(&after[..run]).ok()?;
+        rest = &after[run..];
+        if !rest.is_empty() {
+            bytes.push(0).ok()?;
+        }
+    }
+    let (data, crc) = bytes.split_at(bytes.len().checked_sub(2)?);
+    (crc16(data).to_be_bytes() == crc).then(|| Vec::from_slice(data).unwrap())
+}
+
+pub enum Received {
+    /// A byte outside any frame.
+    Text(u8),
+    /// A complete frame with a good CRC.
+    Frame(Data),
+    /// A byte taken into a frame, or a bad frame dropped.
+    Nothing,
+}
+
+/// Tells the frames in a byte stream from the text around them.
+pub struct Decoder {
+    stuffed  : Vec<u8, STUFFED_LEN>,
+    in_frame : bool,
+    overflow : bool,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder::new()
+    }
+}
+
+impl Decoder {
+    pub const fn new() -> Self {
+        Decoder { stuffed : Vec::new(), in_frame : false, overflow : false }
+    }
+
+    pub fn push(&mut self, byte : u8) -> Received {
+        match (byte, self.in_frame) {
+            (0, false) => {
+                self.in_frame = true;
+                Received::Nothing
+            }
+            // NOTE: two zeros in a row, the second opens the next frame
+            (0, true) if self.stuffed.is_empty() => Received::Nothing,
+            (0, true) => {
+                let data = (!self.overflow).then(|| unstuff(&self.stuffed)).flatten();
+                self.stuffed.clear();
+                self.in_frame = false;
+                self.overflow = false;
+                data.map_or(Received::Nothing, Received::Frame)
+            }
+            (_, true) => {
+                self.overflow |= self.stuffed.push(byte).is_err();
+                Received::Nothing
+            }
+            (_, false) => Received::Text(byte),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(bytes : &[u8]) -> (std::vec::Vec<u8>, std::vec::Vec<std::vec::Vec<u8>>) {
+        let mut decoder = Decoder::new();
+        let (mut text, mut frames) = (vec![], vec![]);
+        for byte in bytes {
+            match decoder.push(*byte) {
+                Received::Text(byte) => text.push(byte),
+                Received::Frame(data) => frames.push(data.to_vec()),
+                Received::Nothing => (),
+            }
+        }
+        (text, frames)
+    }
+
+    #[test]
+    fn crc_matches_the_check_value() {
+        assert_eq!(crc16(b"123456789"), 0x29b1);
+    }
+
+    #[test]
+    fn frames_hold_no_zero_between_the_delimiters() {
+        let frame = encode(&[0, 1, 0, 0, 2]).unwrap();
+        assert_eq!(frame.first(), Some(&0));
+        assert_eq!(frame.last(), Some(&0));
+        assert!(!frame[1..frame.len() - 1].contains(&0));
+    }
+
+    #[test]
+    fn frames_round_trip_between_text() {
+        let data : std::vec::Vec<u8> = (0..DATA_LEN as u8).collect();
+        let mut stream = b"ok\r\n".to_vec();
+        stream.extend_from_slice(&encode(&data).unwrap());
+        stream.extend_from_slice(b"hi");
+        stream.extend_from_slice(&encode(&[]).unwrap());
+        stream.extend_from_slice(&encode(&[0, 0]).unwrap());
+        let (text, frames) = decode(&stream);
+        assert_eq!(text, b"ok\r\nhi");
+        assert_eq!(frames, [data, vec![], vec![0, 0]]);
+    }
+
+    #[test]
+    fn a_corrupted_frame_is_dropped() {
+        let mut frame = encode(b"packet").unwrap();
+        frame[3] ^= 0x10;
+        let mut stream = frame.to_vec();
+        stream.extend_from_slice(&encode(b"next").unwrap());
+        assert_eq!(decode(&stream).1, [b"next".to_vec()]);
+    }
+
+    #[test]
+    fn too_much_data_is_not_framed() {
+        assert!(encode(&[1; DATA_LEN + 1]).is_none());
+    }
+}
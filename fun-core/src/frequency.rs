@@ -0,0 +1,64 @@
+//! A frequency counter's reading: the edges counted over a gate time and, when the CPU kept
+//! up with the edges, one period timed from them.
+//!
+//! Counting is good to the gate time, a hertz or two, so it is what shows from `TIMED_BELOW_HZ`
+//! up. Below, the timed period is the finer one. The duty cycle only comes from timing.
+
+use core::fmt;
+
+pub const TIMED_BELOW_HZ : u32 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reading {
+    pub hz           : u32,
+    pub duty_percent : Option<u32>,
+}
+
+impl Reading {
+    /// From `edges` counted over `gate_ms`, both directions, and the frequency and duty cycle
+    /// of a timed period.
+    pub fn new(edges : u32, gate_ms : u32, timed : Option<(u32, u32)>) -> Reading {
+        let counted_hz = (edges as u64 * 1000 / (2 * gate_ms.max(1) as u64)) as u32;
+        let hz = match timed {
+            Some((timed_hz, _)) if counted_hz < TIMED_BELOW_HZ => timed_hz,
+            _ => counted_hz,
+        };
+        Reading { hz, duty_percent : timed.map(|(_, duty)| duty) }
+    }
+}
+
+impl fmt::Display for Reading {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self.hz {
+            hz if hz < 1000 => write!(f, "{} Hz", hz)?,
+            hz if hz < 1_000_000 => write!(f, "{}.{:02} kHz", hz / 1000, hz % 1000 / 10)?,
+            hz => write!(f, "{}.{:02} MHz", hz / 1_000_000, hz % 1_000_000 / 10_000)?,
+        }
+        match self.duty_percent {
+            Some(duty) => write!(f, " {}%", duty),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_signals_are_timed_fast_ones_counted() {
+        assert_eq!(Reading::new(50, 500, Some((49, 25))), Reading { hz : 49, duty_percent : Some(25) });
+        assert_eq!(Reading::new(200_000, 500, Some((199_990, 50))).hz, 200_000);
+        assert_eq!(Reading::new(400_000, 500, None), Reading { hz : 400_000, duty_percent : None });
+        assert_eq!(Reading::new(0, 500, None).hz, 0);
+    }
+
+    #[test]
+    fn it_reads_in_the_right_unit() {
+        let reading = |hz, duty_percent| Reading { hz, duty_percent }.to_string();
+        assert_eq!(reading(950, Some(25)), "950 Hz 25%");
+        assert_eq!(reading(12_345, Some(50)), "12.34 kHz 50%");
+        assert_eq!(reading(312_500, None), "312.50 kHz");
+        assert_eq!(reading(1_250_000, None), "1.25 MHz");
+    }
+}
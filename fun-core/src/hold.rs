@@ -0,0 +1,91 @@
+//! Telling a long press from a short one, for a button read by polling.
+//!
+//! A button still down `LONG_MS` after it went down makes a long press. It is reported once,
+//! the button has to come up before it can make the next one.
+
+use crate::Clock;
+
+pub const LONG_MS : u64 = 800;
+
+#[derive(Clone, Copy)]
+enum State {
+    Up,
+    Down { at : u64 },
+    // NOTE: the long press was reported, waiting for the button to come up
+    Reported,
+}
+
+#[derive(Clone, Copy)]
+pub struct Hold {
+    state : State,
+}
+
+impl Hold {
+    pub const fn new() -> Self {
+        Hold { state : State::Up }
+    }
+
+    /// Takes whether the button is down now, true when that just made a long press.
+    pub fn poll(&mut self, down : bool, clock : &impl Clock) -> bool {
+        let now = clock.now_ms();
+        let (state, long) = match (self.state, down) {
+            (_, false) => (State::Up, false),
+            (State::Up, true) => (State::Down { at : now }, false),
+            (State::Down { at }, true) if now.saturating_sub(at) >= LONG_MS => (State::Reported, true),
+            (state, true) => (state, false),
+        };
+        self.state = state;
+        long
+    }
+}
+
+impl Default for Hold {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use super::*;
+
+    struct Ticks(Cell<u64>);
+
+    impl Clock for Ticks {
+        fn now_ms(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    fn hold_for(hold : &mut Hold, clock : &Ticks, ms : u64) -> usize {
+        let mut longs = 0;
+        for _ in 0..ms / 20 {
+            longs += hold.poll(true, clock) as usize;
+            clock.0.set(clock.0.get() + 20);
+        }
+        longs
+    }
+
+    #[test]
+    fn short_press_is_not_long() {
+        let (mut hold, clock) = (Hold::new(), Ticks(Cell::new(0)));
+        assert_eq!(hold_for(&mut hold, &clock, LONG_MS - 100), 0);
+        assert!(!hold.poll(false, &clock));
+    }
+
+    #[test]
+    fn long_press_is_reported_once() {
+        let (mut hold, clock) = (Hold::new(), Ticks(Cell::new(1000)));
+        assert_eq!(hold_for(&mut hold, &clock, 3 * LONG_MS), 1);
+    }
+
+    #[test]
+    fn release_starts_over() {
+        let (mut hold, clock) = (Hold::new(), Ticks(Cell::new(0)));
+        assert_eq!(hold_for(&mut hold, &clock, LONG_MS + 100), 1);
+        hold.poll(false, &clock);
+        assert_eq!(hold_for(&mut hold, &clock, LONG_MS - 100), 0);
+        assert_eq!(hold_for(&mut hold, &clock, 200), 1);
+    }
+}
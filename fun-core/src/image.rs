@@ -0,0 +1,134 @@
+//! Receiving a firmware image in chunks, for the radio update of the firmware's ota.rs.
+//!
+//! The board asks for the chunk at `Download::offset` until it comes, so chunks that are
+//! lost or come twice do no harm. Each one carries the CRC-16 of its data, the image as a
+//! whole has to match the CRC-32 the update was started with. The sender pads the image
+//! with 0xff to whole chunks, the padding is part of that CRC.
+
+use crate::frame::crc16;
+
+pub const CHUNK_LEN : usize = 12;
+
+pub type Chunk = [u8; CHUNK_LEN];
+
+/// CRC-32/ISO-HDLC, the one of zlib and `crc32` on the host, carried on from `crc`,
+/// which starts out as 0.
+pub fn crc32(crc : u32, bytes : &[u8]) -> u32 {
+    !bytes.iter().fold(!crc, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            if crc & 1 != 0 { crc >> 1 ^ 0xedb8_8320 } else { crc >> 1 }
+        })
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Taken {
+    /// The chunk wanted, to be written at the offset it came for.
+    Next,
+    /// Some other chunk, or one whose CRC doesn't match, ignored.
+    Ignored,
+    /// The chunk doesn't fit in the space the image goes to.
+    TooLong,
+}
+
+pub struct Download {
+    expected : u32,
+    capacity : u32,
+    offset   : u32,
+    crc      : u32,
+}
+
+impl Download {
+    /// An image whose CRC-32 is `expected`, for `capacity` bytes.
+    pub fn new(expected : u32, capacity : u32) -> Self {
+        Download { expected, capacity, offset : 0, crc : 0 }
+    }
+
+    /// The offset of the chunk wanted next, also the length received so far.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    pub fn take(&mut self, offset : u32, crc : u16, data : &Chunk) -> Taken {
+        if offset != self.offset || crc16(data) != crc {
+            return Taken::Ignored;
+        }
+        if offset + CHUNK_LEN as u32 > self.capacity {
+            return Taken::TooLong;
+        }
+        self.crc = crc32(self.crc, data);
+        self.offset += CHUNK_LEN as u32;
+        Taken::Next
+    }
+
+    /// True when everything received is the image expected.
+    pub fn complete(&self) -> bool {
+        self.offset > 0 && self.crc == self.expected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks(image : &[u8]) -> impl Iterator<Item = (u32, u16, Chunk)> + '_ {
+        image.chunks(CHUNK_LEN).enumerate().map(|(i, data)| {
+            let mut chunk = [0xff; CHUNK_LEN];
+            chunk[..data.len()].copy_from_slice(data);
+            ((i * CHUNK_LEN) as u32, crc16(&chunk), chunk)
+        })
+    }
+
+    fn padded(image : &[u8]) -> Vec<u8> {
+        let mut padded = image.to_vec();
+        padded.resize(image.len().div_ceil(CHUNK_LEN) * CHUNK_LEN, 0xff);
+        padded
+    }
+
+    #[test]
+    fn crc_matches_the_check_value() {
+        assert_eq!(crc32(0, b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(crc32(0, b"1234"), b"56789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn an_image_arrives_in_order_whatever_comes() {
+        let image : Vec<u8> = (0..100).collect();
+        let mut download = Download::new(crc32(0, &padded(&image)), 1024);
+        let all : Vec<_> = chunks(&image).collect();
+        for (i, (offset, crc, data)) in all.iter().enumerate() {
+            // NOTE: the one after it, a repeat of the one before, then a corrupted one
+            if let Some((offset, crc, data)) = all.get(i + 1) {
+                assert_eq!(download.take(*offset, *crc, data), Taken::Ignored);
+            }
+            if i > 0 {
+                let (offset, crc, data) = all[i - 1];
+                assert_eq!(download.take(offset, crc, &data), Taken::Ignored);
+            }
+            assert_eq!(download.take(*offset, crc ^ 1, data), Taken::Ignored);
+            assert!(!download.complete());
+            assert_eq!(download.take(*offset, *crc, data), Taken::Next);
+        }
+        assert_eq!(download.offset() as usize, padded(&image).len());
+        assert!(download.complete());
+    }
+
+    #[test]
+    fn a_different_image_is_not_complete() {
+        let mut download = Download::new(crc32(0, &padded(b"expected")), 1024);
+        for (offset, crc, data) in chunks(b"received") {
+            assert_eq!(download.take(offset, crc, &data), Taken::Next);
+        }
+        assert!(!download.complete());
+    }
+
+    #[test]
+    fn an_image_too_long_is_refused() {
+        let mut download = Download::new(0, CHUNK_LEN as u32);
+        let mut all = chunks(&[1; 2 * CHUNK_LEN]);
+        let (offset, crc, data) = all.next().unwrap();
+        assert_eq!(download.take(offset, crc, &data), Taken::Next);
+        let (offset, crc, data) = all.next().unwrap();
+        assert_eq!(download.take(offset, crc, &data), Taken::TooLong);
+    }
+}
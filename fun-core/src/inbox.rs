@@ -0,0 +1,174 @@
+//! An inbox of the messages a board received, so a message can be read again instead of
+//! being gone after one scroll.
+//!
+//! The inbox keeps `N` messages, newest first. Once it is full a new message pushes out the
+//! oldest one read, or the oldest one when none was read. A cursor points at the message
+//! being read, `next` moves it on to an older one and round to the newest again, `delete`
+//! drops the message it points at. `indicate` lights the top right corner of a frame for
+//! the unread messages, an LED each for up to `CORNER.len()`.
+
+use heapless::{String, Vec};
+use crate::Frame;
+
+// NOTE: filled in this order, the corner LED first
+const CORNER : [(usize, usize); 3] = [(0, 4), (0, 3), (1, 4)];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message<const LEN : usize> {
+    pub origin : u16,
+    pub text   : String<LEN>,
+    pub unread : bool,
+}
+
+pub struct Inbox<const N : usize, const LEN : usize> {
+    // NOTE: the newest first
+    messages : Vec<Message<LEN>, N>,
+    cursor   : usize,
+}
+
+impl<const N : usize, const LEN : usize> Inbox<N, LEN> {
+    pub const fn new() -> Self {
+        Inbox { messages : Vec::new(), cursor : 0 }
+    }
+
+    /// Keeps `text` from the board at `origin`, cut at a char boundary to `LEN`. The cursor
+    /// stays on the message it pointed at, unless that one was pushed out.
+    pub fn receive(&mut self, origin : u16, text : &str) {
+        let mut cut = String::new();
+        for c in text.chars() {
+            if cut.push(c).is_err() {
+                break;
+            }
+        }
+        if self.messages.is_full() {
+            let oldest = self.messages.iter().rposition(|message| !message.unread).unwrap_or(N - 1);
+            self.messages.remove(oldest);
+            if self.cursor > oldest {
+                self.cursor -= 1;
+            }
+        }
+        let _ = self.messages.insert(0, Message { origin, text : cut, unread : true });
+        if self.messages.len() > 1 {
+            self.cursor += 1;
+        }
+        self.cursor = self.cursor.min(self.messages.len().saturating_sub(1));
+    }
+
+    /// The message the cursor points at, and where it is counted from 1, marked read.
+    pub fn read(&mut self) -> Option<(&Message<LEN>, usize)> {
+        let cursor = self.cursor;
+        let message = self.messages.get_mut(cursor)?;
+        message.unread = false;
+        Some((message, cursor + 1))
+    }
+
+    /// Moves the cursor on to the next older message, round to the newest after the oldest.
+    pub fn next(&mut self) {
+        self.cursor = if self.cursor + 1 < self.messages.len() { self.cursor + 1 } else { 0 };
+    }
+
+    /// Moves the cursor to the newest message.
+    pub fn newest(&mut self) {
+        self.cursor = 0;
+    }
+
+    /// Drops the message the cursor points at, it moves on to the next older one.
+    pub fn delete(&mut self) {
+        if self.cursor < self.messages.len() {
+            self.messages.remove(self.cursor);
+        }
+        if self.cursor >= self.messages.len() {
+            self.cursor = 0;
+        }
+    }
+
+    pub fn unread(&self) -> usize {
+        self.messages.iter().filter(|message| message.unread).count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+impl<const N : usize, const LEN : usize> Default for Inbox<N, LEN> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `frame` with the corner lit for `unread` messages.
+pub fn indicate(frame : Frame, unread : usize) -> Frame {
+    let mut frame = frame;
+    for (row, column) in CORNER.into_iter().take(unread) {
+        frame[row][column] = 9;
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(inbox : &Inbox<3, 8>) -> Vec<&str, 3> {
+        inbox.messages.iter().map(|message| message.text.as_str()).collect()
+    }
+
+    #[test]
+    fn messages_are_kept_newest_first() {
+        let mut inbox = Inbox::<3, 8>::new();
+        inbox.receive(1, "one");
+        inbox.receive(2, "two is too long");
+        assert_eq!(texts(&inbox), ["two is t", "one"]);
+        assert_eq!(inbox.unread(), 2);
+        // NOTE: the cursor stays on the first message, the oldest now
+        let (message, place) = inbox.read().unwrap();
+        assert_eq!((message.origin, place), (1, 2));
+        assert_eq!(inbox.unread(), 1);
+        inbox.next();
+        assert_eq!(inbox.read().unwrap().1, 1);
+        assert_eq!(inbox.unread(), 0);
+    }
+
+    #[test]
+    fn a_full_inbox_pushes_out_the_oldest_read() {
+        let mut inbox = Inbox::<3, 8>::new();
+        inbox.receive(1, "a");
+        inbox.receive(2, "b");
+        inbox.newest();
+        inbox.read();
+        inbox.receive(3, "c");
+        inbox.receive(4, "d");
+        assert_eq!(texts(&inbox), ["d", "c", "a"]);
+        inbox.receive(5, "e");
+        assert_eq!(texts(&inbox), ["e", "d", "c"]);
+    }
+
+    #[test]
+    fn deleting_moves_on() {
+        let mut inbox = Inbox::<3, 8>::new();
+        inbox.receive(1, "a");
+        inbox.receive(2, "b");
+        inbox.newest();
+        inbox.delete();
+        assert_eq!(texts(&inbox), ["a"]);
+        assert_eq!(inbox.read().unwrap().0.text.as_str(), "a");
+        inbox.delete();
+        assert!(inbox.is_empty());
+        assert!(inbox.read().is_none());
+        inbox.delete();
+    }
+
+    #[test]
+    fn the_corner_counts_unread() {
+        assert_eq!(indicate([[0; 5]; 5], 0), [[0; 5]; 5]);
+        let frame = indicate([[1; 5]; 5], 2);
+        assert_eq!(frame[0], [1, 1, 1, 9, 9]);
+        assert_eq!(frame[1][4], 1);
+        assert_eq!(indicate([[0; 5]; 5], 10)[1][4], 9);
+    }
+}
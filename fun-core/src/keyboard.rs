@@ -0,0 +1,143 @@
+//! Typing text as a USB HID keyboard, the keyboard wedge: readings typed into whatever has
+//! the focus on the host, a spreadsheet say, with no program on the host to receive them.
+//!
+//! The keyboard is a boot keyboard, `REPORT_DESCRIPTOR` is the one of the HID spec's
+//! appendix B, a report is a modifier byte, a reserved one and six key codes. Every character
+//! is a report with its key down and one with all keys up, so the same character twice is
+//! two presses. A tab moves to the next cell and a newline to the next row, so readings
+//! typed as `Tab` separated lines fill a table. The key codes are those of a US layout, a
+//! host with another one types some of the punctuation as other characters.
+//!
+//! The firmware's wedge app types the temperature and the supply, the board has no compass
+//! driver or distance sensor to type yet. Its reports go to the peer over the radio like the
+//! slides below, the peer's host link hands them to a program on the host pressing the keys.
+//!
+//! NOTE: there is no USB keyboard in the firmware. The micro:bit's USB port belongs to the
+//! interface chip, the USB device of the nRF52833 isn't wired, see the firmware's
+//! transport.rs. On a board where it is, a build needs a HID class on the usb-device crate,
+//! which the HAL's `usbd` already pulls in, sending a report every poll of its interrupt
+//! endpoint.
+//!
+//! A presentation remote sends `slide`, page down for the next slide and page up for the one
+//! before, the keys every slide show program takes. The firmware's presenter.rs sends the
+//! report bytes to its peer over the radio, which hands them to its host like every packet.
+//! Over BLE it would be the same reports through the HID service over GATT, but there is no
+//! BLE stack: the firmware's radio.rs runs the proprietary mode between micro:bits. The
+//! profile needs a link layer that takes connections, L2CAP, an ATT server with the HID
+//! service and SMP for pairing, the bonding keys kept in the firmware's key-value store, more
+//! than fits beside the radio of radio.rs without a stack like the SoftDevice.
+
+// NOTE: from the HID spec, with the LED output report of caps lock and the rest
+pub const REPORT_DESCRIPTOR : [u8; 63] = [
+    0x05, 0x01, 0x09, 0x06, 0xa1, 0x01, 0x05, 0x07, 0x19, 0xe0, 0x29, 0xe7, 0x15, 0x00, 0x25, 0x01,
+    0x75, 0x01, 0x95, 0x08, 0x81, 0x02, 0x95, 0x01, 0x75, 0x08, 0x81, 0x01, 0x95, 0x05, 0x75, 0x01,
+    0x05, 0x08, 0x19, 0x01, 0x29, 0x05, 0x91, 0x02, 0x95, 0x01, 0x75, 0x03, 0x91, 0x01, 0x95, 0x06,
+    0x75, 0x08, 0x15, 0x00, 0x25, 0x65, 0x05, 0x07, 0x19, 0x00, 0x29, 0x65, 0x81, 0x00, 0xc0,
+];
+
+pub const REPORT_LEN : usize = 8;
+pub const LEFT_SHIFT : u8 = 0x02;
+const ENTER : u8 = 0x28;
+const TAB : u8 = 0x2b;
+const SPACE : u8 = 0x2c;
+const PAGE_UP : u8 = 0x4b;
+const PAGE_DOWN : u8 = 0x4e;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Report {
+    pub modifiers : u8,
+    pub keys      : [u8; 6],
+}
+
+impl Report {
+    pub const RELEASED : Report = Report { modifiers : 0, keys : [0; 6] };
+
+    fn key(modifiers : u8, code : u8) -> Report {
+        Report { modifiers, keys : [code, 0, 0, 0, 0, 0] }
+    }
+
+    pub fn to_bytes(&self) -> [u8; REPORT_LEN] {
+        let mut bytes = [0; REPORT_LEN];
+        bytes[0] = self.modifiers;
+        bytes[2..].copy_from_slice(&self.keys);
+        bytes
+    }
+
+    /// The report in `bytes`, the reserved byte is ignored.
+    pub fn from_bytes(bytes : [u8; REPORT_LEN]) -> Report {
+        let mut keys = [0; 6];
+        keys.copy_from_slice(&bytes[2..]);
+        Report { modifiers : bytes[0], keys }
+    }
+}
+
+/// The report pressing the key of `c`, None for a character a US keyboard has no key for.
+pub fn press(c : char) -> Option<Report> {
+    let shifted = |code| Some(Report::key(LEFT_SHIFT, code));
+    let plain = |code| Some(Report::key(0, code));
+    match c {
+        'a'..='z' => plain(0x04 + (c as u8 - b'a')),
+        'A'..='Z' => shifted(0x04 + (c as u8 - b'A')),
+        '1'..='9' => plain(0x1e + (c as u8 - b'1')),
+        '0'  => plain(0x27),
+        '\n' => plain(ENTER),
+        '\t' => plain(TAB),
+        ' '  => plain(SPACE),
+        '-'  => plain(0x2d),
+        '_'  => shifted(0x2d),
+        '='  => plain(0x2e),
+        '+'  => shifted(0x2e),
+        ';'  => plain(0x33),
+        ':'  => shifted(0x33),
+        ','  => plain(0x36),
+        '.'  => plain(0x37),
+        '/'  => plain(0x38),
+        '%'  => shifted(0x22),
+        _ => None,
+    }
+}
+
+/// The report pressing the key of a presentation remote, to the next slide or back one.
+pub fn slide(next : bool) -> Report {
+    Report::key(0, if next { PAGE_DOWN } else { PAGE_UP })
+}
+
+/// The reports typing `text`, a press and a release for every character that has a key.
+pub fn typed(text : &str) -> impl Iterator<Item = Report> + '_ {
+    text.chars().filter_map(press).flat_map(|report| [report, Report::RELEASED])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn characters_map_to_their_keys() {
+        assert_eq!(press('a'), Some(Report::key(0, 0x04)));
+        assert_eq!(press('Z'), Some(Report::key(LEFT_SHIFT, 0x1d)));
+        assert_eq!(press('1'), Some(Report::key(0, 0x1e)));
+        assert_eq!(press('0'), Some(Report::key(0, 0x27)));
+        assert_eq!(press('\t'), Some(Report::key(0, TAB)));
+        assert_eq!(press('é'), None);
+    }
+
+    #[test]
+    fn every_press_is_released() {
+        let reports : Vec<Report> = typed("2é2\n").collect();
+        assert_eq!(reports, [
+            Report::key(0, 0x1f), Report::RELEASED,
+            Report::key(0, 0x1f), Report::RELEASED,
+            Report::key(0, ENTER), Report::RELEASED,
+        ]);
+        assert_eq!(Report::key(LEFT_SHIFT, 0x22).to_bytes(), [LEFT_SHIFT, 0, 0x22, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn slides_are_the_page_keys() {
+        assert_eq!(slide(true).to_bytes(), [0, 0, 0x4e, 0, 0, 0, 0, 0]);
+        assert_eq!(slide(false).to_bytes(), [0, 0, 0x4b, 0, 0, 0, 0, 0]);
+        assert_eq!(Report::RELEASED.to_bytes(), [0; REPORT_LEN]);
+        assert_eq!(Report::from_bytes(slide(false).to_bytes()), slide(false));
+        assert_eq!(Report::from_bytes([LEFT_SHIFT, 0xff, 4, 0, 0, 0, 0, 0]), Report::key(LEFT_SHIFT, 4));
+    }
+}
@@ -0,0 +1,213 @@
+//! Knocks on the board told from the accelerometer, and the rhythm they are knocked in
+//! matched against a pattern, which unlocks a locked board.
+//!
+//! A knock is a jolt of `TAP_MG` off the 1 g the board feels at rest, and the next one
+//! counts `TAP_GAP_MS` later at the earliest, so one knock ringing on isn't two. A rhythm
+//! ends `END_MS` after its last knock. A pattern is written in beats, `x` a knock and `.` a
+//! beat without one, so `xx.x` is two knocks a beat apart and one two beats later. Only the
+//! rhythm matters, not the tempo: each gap of the knocks may be off by `TOLERANCE_PERCENT`
+//! of a beat, a beat being the time of the whole rhythm over its beats.
+//!
+//! NOTE: the samples come every `motion::POLL_MS`, a light tap over before the next one is
+//! missed, a firm knock rings on for longer.
+
+use core::fmt;
+use heapless::Vec;
+
+pub const MAX_KNOCKS : usize = 8;
+pub const MAX_BEATS : usize = 16;
+pub const TAP_MG : u32 = 400;
+pub const TAP_GAP_MS : u64 = 100;
+pub const END_MS : u64 = 1500;
+pub const TOLERANCE_PERCENT : u64 = 40;
+
+const REST_MG : u32 = 1000;
+
+/// The beats between the knocks of a pattern.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pattern {
+    gaps : [u8; MAX_KNOCKS - 1],
+    len  : usize,
+}
+
+impl Pattern {
+    /// `text` of `x` and `.`, starting and ending with a knock, at least two of them, None
+    /// otherwise.
+    pub fn parse(text : &str) -> Option<Pattern> {
+        let text = text.as_bytes();
+        if text.len() > MAX_BEATS || text.first() != Some(&b'x') || text.last() != Some(&b'x') {
+            return None;
+        }
+        let mut pattern = Pattern { gaps : [0; MAX_KNOCKS - 1], len : 0 };
+        let mut gap = 0;
+        for beat in &text[1..] {
+            gap += 1;
+            match beat {
+                b'x' => {
+                    *pattern.gaps.get_mut(pattern.len)? = gap;
+                    pattern.len += 1;
+                    gap = 0;
+                }
+                b'.' => (),
+                _ => return None,
+            }
+        }
+        (pattern.len > 0).then_some(pattern)
+    }
+
+    pub fn knocks(&self) -> usize {
+        self.len + 1
+    }
+
+    fn gaps(&self) -> &[u8] {
+        &self.gaps[..self.len]
+    }
+
+    /// True when knocks at `times_ms` follow the pattern.
+    pub fn matches(&self, times_ms : &[u64]) -> bool {
+        if times_ms.len() != self.knocks() {
+            return false;
+        }
+        let beats : u64 = self.gaps().iter().map(|gap| *gap as u64).sum();
+        let total_ms = times_ms[times_ms.len() - 1] - times_ms[0];
+        // NOTE: in ms times beats, so a beat needs no dividing out
+        times_ms.windows(2).zip(self.gaps()).all(|(knocks, gap)| {
+            let off = ((knocks[1] - knocks[0]) * beats).abs_diff(*gap as u64 * total_ms);
+            off * 100 <= TOLERANCE_PERCENT * total_ms
+        })
+    }
+}
+
+/// Two knocks a beat apart and one two beats later, `xx.x`.
+impl Default for Pattern {
+    fn default() -> Self {
+        let mut gaps = [0; MAX_KNOCKS - 1];
+        gaps[..2].copy_from_slice(&[1, 2]);
+        Pattern { gaps, len : 2 }
+    }
+}
+
+/// The pattern as `parse` takes it.
+impl fmt::Display for Pattern {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("x")?;
+        for gap in self.gaps() {
+            for _ in 1..*gap {
+                f.write_str(".")?;
+            }
+            f.write_str("x")?;
+        }
+        Ok(())
+    }
+}
+
+/// What a sample brought.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Heard {
+    Nothing,
+    /// A knock, this many so far.
+    Knock(usize),
+    /// The rhythm is over, true when it followed the pattern.
+    Rhythm(bool),
+}
+
+pub struct Listener {
+    knocks : Vec<u64, MAX_KNOCKS>,
+    // NOTE: past the jolt of the knock before, so a knock is its first sample off rest
+    still  : bool,
+}
+
+impl Listener {
+    pub const fn new() -> Self {
+        Listener { knocks : Vec::new(), still : true }
+    }
+
+    /// Takes the magnitude of a sample at `now_ms`.
+    pub fn sample(&mut self, now_ms : u64, magnitude_mg : u32, pattern : &Pattern) -> Heard {
+        let jolt = magnitude_mg.abs_diff(REST_MG) >= TAP_MG;
+        let knocked = jolt && self.still;
+        self.still = !jolt;
+        let last = self.knocks.last().copied();
+        if knocked && last.is_none_or(|last| now_ms.saturating_sub(last) >= TAP_GAP_MS) {
+            if self.knocks.is_full() {
+                // NOTE: more knocks than any pattern has, the rhythm is wrong whatever follows
+                self.knocks.remove(0);
+            }
+            let _ = self.knocks.push(now_ms);
+            return Heard::Knock(self.knocks.len());
+        }
+        match last {
+            Some(last) if now_ms.saturating_sub(last) >= END_MS => {
+                let matched = pattern.matches(&self.knocks);
+                self.knocks.clear();
+                Heard::Rhythm(matched)
+            }
+            _ => Heard::Nothing,
+        }
+    }
+}
+
+impl Default for Listener {
+    fn default() -> Self {
+        Listener::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn knock(listener : &mut Listener, at : u64, pattern : &Pattern) -> Heard {
+        let heard = listener.sample(at, 1800, pattern);
+        listener.sample(at + 20, 1000, pattern);
+        heard
+    }
+
+    #[test]
+    fn patterns_are_knocks_and_rests() {
+        let pattern = Pattern::parse("xx.x").unwrap();
+        assert_eq!(pattern, Pattern::default());
+        assert_eq!(pattern.knocks(), 3);
+        assert_eq!(pattern.gaps(), &[1, 2]);
+        assert_eq!(Pattern::parse("x"), None);
+        assert_eq!(Pattern::parse(".xx"), None);
+        assert_eq!(Pattern::parse("xx."), None);
+        assert_eq!(Pattern::parse("xxxxxxxxx"), None);
+        assert_eq!(Pattern::parse("xox"), None);
+        assert_eq!(Pattern::parse("x..xx.x").unwrap().to_string(), "x..xx.x");
+    }
+
+    #[test]
+    fn the_rhythm_matters_not_the_tempo() {
+        let pattern = Pattern::parse("xx.x").unwrap();
+        assert!(pattern.matches(&[0, 300, 900]));
+        assert!(pattern.matches(&[1000, 1180, 1600]));
+        assert!(!pattern.matches(&[0, 450, 900]));
+        assert!(!pattern.matches(&[0, 300]));
+    }
+
+    #[test]
+    fn a_listener_counts_knocks_and_judges_the_rhythm() {
+        let pattern = Pattern::parse("xx.x").unwrap();
+        let mut listener = Listener::new();
+        assert_eq!(knock(&mut listener, 0, &pattern), Heard::Knock(1));
+        assert_eq!(knock(&mut listener, 300, &pattern), Heard::Knock(2));
+        assert_eq!(knock(&mut listener, 900, &pattern), Heard::Knock(3));
+        assert_eq!(listener.sample(900 + END_MS - 1, 1000, &pattern), Heard::Nothing);
+        assert_eq!(listener.sample(900 + END_MS, 1000, &pattern), Heard::Rhythm(true));
+
+        knock(&mut listener, 5000, &pattern);
+        knock(&mut listener, 5300, &pattern);
+        assert_eq!(listener.sample(5300 + END_MS, 1000, &pattern), Heard::Rhythm(false));
+    }
+
+    #[test]
+    fn a_knock_ringing_on_counts_once() {
+        let pattern = Pattern::parse("xx").unwrap();
+        let mut listener = Listener::new();
+        assert_eq!(listener.sample(0, 1800, &pattern), Heard::Knock(1));
+        assert_eq!(listener.sample(20, 300, &pattern), Heard::Nothing);
+        assert_eq!(listener.sample(40, 1000, &pattern), Heard::Nothing);
+        assert_eq!(listener.sample(60, 1800, &pattern), Heard::Nothing);
+    }
+}
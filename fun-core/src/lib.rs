@@ -0,0 +1,78 @@
+//! The logic of the firmware that needs no peripherals, from the font to the radio packets,
+//! so it runs and is tested on the host. Each module's own doc says what it covers.
+//!
+//! Nothing in here knows about the PAC, the HAL or RTIC. Time comes in through `Clock`,
+//! randomness through `Random` and frames go out through `FrameSink`, the firmware
+//! implements them on its monotonic, its RNG and its display, the tests on counters and a
+//! closure. The buttons, the matrix, the speaker and the accelerometer come in through the
+//! traits of `board`. So the logic runs under `cargo test` on the host, in this directory,
+//! while main.rs stays the RTIC shell around it.
+
+#![cfg_attr(not(test), no_std)]
+
+pub mod attendance;
+pub mod badge;
+pub mod beacon;
+pub mod board;
+pub mod breakout;
+pub mod canvas;
+pub mod climate;
+pub mod drive;
+pub mod eightball;
+pub mod flappy;
+pub mod follow;
+pub mod font;
+pub mod frame;
+pub mod frequency;
+pub mod hold;
+pub mod image;
+pub mod inbox;
+pub mod keyboard;
+pub mod knock;
+pub mod maze;
+pub mod midi;
+pub mod motion;
+pub mod mouse;
+pub mod particles;
+pub mod protocol;
+pub mod proximity;
+pub mod quiet;
+pub mod quiz;
+pub mod scope;
+pub mod scores;
+pub mod scroll;
+pub mod shooter;
+pub mod simon;
+pub mod soil;
+pub mod swarm;
+pub mod thermo;
+pub mod utils;
+pub mod v1;
+pub mod wave;
+pub mod wear;
+
+/// Greyscale LED levels 0-9 of the 5x5 matrix, row by row.
+pub type Frame = [[u8; 5]; 5];
+
+/// Milliseconds since some fixed point, all that the logic needs to know about time.
+pub trait Clock {
+    fn now_ms(&self) -> u64;
+}
+
+/// Random numbers, all that the effects need of an RNG.
+pub trait Random {
+    /// A number in `0..n`.
+    fn below(&mut self, n : u32) -> u32;
+}
+
+/// Somewhere frames are shown.
+pub trait FrameSink {
+    /// Shows `frame` for `duration_ms`, returning once it is over.
+    fn show(&mut self, frame : Frame, duration_ms : u32);
+}
+
+impl<F : FnMut(Frame, u32)> FrameSink for F {
+    fn show(&mut self, frame : Frame, duration_ms : u32) {
+        self(frame, duration_ms)
+    }
+}
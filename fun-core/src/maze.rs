@@ -0,0 +1,277 @@
+//! Maze: tilt the board to roll the player through mazes larger than the display.
+//!
+//! The maze is drawn on a `Canvas`, the window pans along with the player. The player moves
+//! one cell at a time in the direction the board is tilted most, faster when tilted further,
+//! and stops at walls. Reaching the goal plays a short animation and starts the next level of
+//! `LEVELS`, after the last one the game is done.
+
+use crate::board::Beeper;
+use crate::canvas::Canvas;
+use crate::scroll;
+use crate::{Clock, Frame};
+
+// NOTE: '#' is a wall, 'S' where the player starts and 'G' the goal, every row of a level
+// is as long as the first and the outer rows and columns are walls
+static LEVELS : &[&[&[u8]]] = &[
+    &[
+        b"#########",
+        b"#S  #   #",
+        b"### # # #",
+        b"#   # # #",
+        b"# ### # #",
+        b"#     #G#",
+        b"#########",
+    ],
+    &[
+        b"###########",
+        b"#S    #   #",
+        b"##### # # #",
+        b"#     # # #",
+        b"# ##### # #",
+        b"#   #   # #",
+        b"### # ### #",
+        b"#   #   # #",
+        b"# ##### # #",
+        b"#       #G#",
+        b"###########",
+    ],
+    &[
+        b"###############",
+        b"#S  #         #",
+        b"### # ####### #",
+        b"#   # #   #   #",
+        b"# ### # # # ###",
+        b"# #   # #   # #",
+        b"# # # # ##### #",
+        b"# # # #     # #",
+        b"# # # ##### # #",
+        b"# # # #     # #",
+        b"# ### # ##### #",
+        b"#     #      G#",
+        b"###############",
+    ],
+];
+
+// NOTE: below this the board counts as flat, past FAST_MG it rolls at FAST_MS a cell
+const TILT_MG : i32 = 200;
+const FAST_MG : i32 = 500;
+const MOVE_MS : u64 = 250;
+const FAST_MS : u64 = 120;
+const GOAL_MS : u64 = 1200;
+const GOAL_HZ : u32 = 880;
+const WALL : u8 = 2;
+const POLL_MS : u32 = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    Rolling { moved : u64 },
+    Reached { at : u64 },
+    Done { at : u64 },
+}
+
+#[derive(Clone, Copy)]
+pub struct Game {
+    level : usize,
+    x     : usize,
+    y     : usize,
+    tilt  : (i32, i32),
+    phase : Phase,
+}
+
+fn find(level : &[&[u8]], cell : u8) -> (usize, usize) {
+    level.iter().enumerate()
+        .find_map(|(y, row)| row.iter().position(|c| *c == cell).map(|x| (x, y)))
+        .unwrap_or((1, 1))
+}
+
+fn start(level : usize, now : u64) -> Game {
+    let (x, y) = find(LEVELS[level], b'S');
+    Game { level, x, y, tilt : (0, 0), phase : Phase::Rolling { moved : now } }
+}
+
+impl Game {
+    /// The player at the start of the first level.
+    pub fn new(clock : &impl Clock) -> Self {
+        start(0, clock.now_ms())
+    }
+
+    /// The level played, counted from 0.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Takes the tilt of the board in mg, x growing to the right and y to the bottom.
+    pub fn tilt(&mut self, x : i32, y : i32) {
+        self.tilt = (x, y);
+    }
+
+    fn roll(&mut self, now : u64, moved : u64, beeper : &mut impl Beeper) -> bool {
+        let (tx, ty) = self.tilt;
+        let steepest = tx.abs().max(ty.abs());
+        let period = if steepest > FAST_MG { FAST_MS } else { MOVE_MS };
+        if steepest < TILT_MG || now.saturating_sub(moved) < period {
+            return false;
+        }
+        // NOTE: one axis at a time, so the player can't slip through a corner between two walls
+        let (x, y) = if tx.abs() >= ty.abs() {
+            (if tx > 0 { self.x + 1 } else { self.x - 1 }, self.y)
+        } else {
+            (self.x, if ty > 0 { self.y + 1 } else { self.y - 1 })
+        };
+        match LEVELS[self.level][y][x] {
+            b'#' => (),
+            b'G' => {
+                beeper.tone(GOAL_HZ);
+                (self.x, self.y) = (x, y);
+                self.phase = Phase::Reached { at : now };
+                return true;
+            }
+            _ => (self.x, self.y) = (x, y),
+        }
+        self.phase = Phase::Rolling { moved : now };
+        false
+    }
+
+    /// Moves the game on to now, true when the player just reached the goal of the level.
+    pub fn advance(&mut self, clock : &impl Clock, beeper : &mut impl Beeper) -> bool {
+        let now = clock.now_ms();
+        match self.phase {
+            Phase::Rolling { moved } => return self.roll(now, moved, beeper),
+            Phase::Reached { at } if now.saturating_sub(at) >= GOAL_MS => {
+                beeper.off();
+                *self = if self.level + 1 < LEVELS.len() {
+                    Game { tilt : self.tilt, ..start(self.level + 1, now) }
+                } else {
+                    Game { phase : Phase::Done { at : now }, ..*self }
+                };
+            }
+            _ => (),
+        }
+        false
+    }
+
+    /// The frame for now with the time to show it for, None once the message after the last
+    /// level went by.
+    pub fn frame(&self, clock : &impl Clock) -> Option<(Frame, u32)> {
+        let now = clock.now_ms();
+        let level = LEVELS[self.level];
+        let mut canvas = Canvas::new(level[0].len(), level.len());
+        for (y, row) in level.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                canvas.set(x, y, if *cell == b'#' { WALL } else { 0 });
+            }
+        }
+        let (gx, gy) = find(level, b'G');
+        match self.phase {
+            Phase::Rolling { .. } => {
+                canvas.set(gx, gy, if (now / 250).is_multiple_of(2) { 9 } else { 4 });
+                canvas.set(self.x, self.y, 9);
+            }
+            // NOTE: rings growing out of the goal
+            Phase::Reached { at } => {
+                let radius = (now.saturating_sub(at) / 150 % 4) as usize;
+                for y in gy.saturating_sub(radius)..=gy + radius {
+                    for x in gx.saturating_sub(radius)..=gx + radius {
+                        if x.abs_diff(gx).max(y.abs_diff(gy)) == radius {
+                            canvas.set(x, y, 9);
+                        }
+                    }
+                }
+            }
+            Phase::Done { at } => return scroll::at("maze done", now.saturating_sub(at)),
+        }
+        canvas.follow(self.x, self.y);
+        Some((canvas.frame(), POLL_MS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use super::*;
+
+    struct Ticks(Cell<u64>);
+
+    impl Clock for Ticks {
+        fn now_ms(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    impl Ticks {
+        fn wait(&self, ms : u64) {
+            self.0.set(self.0.get() + ms);
+        }
+    }
+
+    struct Tone(u32);
+
+    impl Beeper for Tone {
+        fn tone(&mut self, hz : u32) {
+            self.0 = hz;
+        }
+    }
+
+    #[test]
+    fn levels_are_walled_in() {
+        for level in LEVELS {
+            assert!(level.iter().all(|row| row.len() == level[0].len()));
+            assert!(level[0].iter().chain(level[level.len() - 1]).all(|cell| *cell == b'#'));
+            assert!(level.iter().all(|row| row[0] == b'#' && row[row.len() - 1] == b'#'));
+            for cell in *b"SG" {
+                assert_eq!(level.iter().flat_map(|row| row.iter()).filter(|c| **c == cell).count(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn the_player_rolls_until_a_wall() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut game = Game::new(&clock);
+        game.tilt(-TILT_MG, 0);
+        clock.wait(MOVE_MS);
+        game.advance(&clock, &mut beeper);
+        assert_eq!((game.x, game.y), (1, 1));
+
+        // NOTE: a slight tilt is flat, a steep one rolls faster
+        game.tilt(TILT_MG - 1, 0);
+        clock.wait(MOVE_MS);
+        game.advance(&clock, &mut beeper);
+        assert_eq!((game.x, game.y), (1, 1));
+        game.tilt(FAST_MG + 1, 100);
+        for _ in 0..3 {
+            clock.wait(FAST_MS);
+            game.advance(&clock, &mut beeper);
+        }
+        assert_eq!((game.x, game.y), (3, 1));
+    }
+
+    #[test]
+    fn the_goal_leads_to_the_next_level() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut game = Game::new(&clock);
+        (game.x, game.y) = (7, 4);
+        game.tilt(0, TILT_MG);
+        clock.wait(MOVE_MS);
+        assert!(game.advance(&clock, &mut beeper));
+        assert_eq!(beeper.0, GOAL_HZ);
+        clock.wait(GOAL_MS);
+        assert!(!game.advance(&clock, &mut beeper));
+        assert_eq!(beeper.0, 0);
+        assert_eq!(game.level(), 1);
+        assert_eq!((game.x, game.y), find(LEVELS[1], b'S'));
+        assert_eq!(game.tilt, (0, TILT_MG));
+    }
+
+    #[test]
+    fn after_the_last_level_the_game_is_done() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut game = Game { phase : Phase::Reached { at : 0 }, ..start(LEVELS.len() - 1, 0) };
+        clock.wait(GOAL_MS);
+        game.advance(&clock, &mut beeper);
+        assert_eq!(game.phase, Phase::Done { at : GOAL_MS });
+        assert!(game.frame(&clock).is_some());
+        clock.wait(scroll::frames("maze done").count() as u64 * scroll::STEP_MS as u64);
+        assert_eq!(game.frame(&clock), None);
+    }
+}
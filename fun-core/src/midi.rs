@@ -0,0 +1,126 @@
+//! A tiny motion MIDI controller: the tilt of the board picks a note of a pentatonic scale,
+//! the buttons start and stop it, as the USB MIDI event packets a host would get and as the
+//! frequency the speaker plays.
+//!
+//! Tilting right goes up the five notes of the scale, tilting the top edge down goes up an
+//! octave, level is the octave of middle C. A USB MIDI event packet is the cable number and
+//! the code index in a byte, then the three bytes of the MIDI message.
+//!
+//! NOTE: the firmware plays the notes on the speaker only. The micro:bit's USB port belongs
+//! to the interface chip, the USB device of the nRF52833 isn't wired, see keyboard.rs. On a
+//! board where it is, a build needs the MIDI streaming class on the usb-device crate, sending
+//! `note_on` and `note_off` on its bulk endpoint, and the speaker only when no host is there.
+
+use crate::Frame;
+
+// NOTE: the major pentatonic, in semitones above the first note
+const SCALE : [u8; 5] = [0, 2, 4, 7, 9];
+pub const OCTAVES : usize = 3;
+// NOTE: middle C, the first note of the middle octave
+const MIDDLE_C : u8 = 60;
+// NOTE: the tilt in mg that reaches the first or last note, or octave
+const FULL_MG : i32 = 600;
+// NOTE: the octave from middle C, C4 to B4, in mHz
+const OCTAVE_MHZ : [u32; 12] = [
+    261_626, 277_183, 293_665, 311_127, 329_628, 349_228, 369_994, 391_995, 415_305, 440_000, 466_164, 493_883,
+];
+pub const VELOCITY : u8 = 100;
+const CHANNEL : u8 = 0;
+const DIM_LEVEL : u8 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pitch {
+    pub degree : usize,
+    pub octave : usize,
+}
+
+impl Pitch {
+    pub const MIDDLE : Pitch = Pitch { degree : 0, octave : OCTAVES / 2 };
+
+    /// The pitch for the tilt in mg, as `motion::Tracker::tilt` has it.
+    pub fn from_tilt(x : i32, y : i32) -> Pitch {
+        let step = |mg : i32, steps : usize| {
+            let mg = mg.clamp(-FULL_MG, FULL_MG - 1) + FULL_MG;
+            (mg * steps as i32 / (2 * FULL_MG)) as usize
+        };
+        Pitch { degree : step(x, SCALE.len()), octave : step(-y, OCTAVES) }
+    }
+
+    /// The MIDI note number.
+    pub fn note(&self) -> u8 {
+        let octave = self.octave as i32 - (OCTAVES / 2) as i32;
+        (MIDDLE_C as i32 + 12 * octave) as u8 + SCALE[self.degree]
+    }
+}
+
+/// The frequency of the MIDI `note` in Hz, to the nearest one.
+pub fn hz(note : u8) -> u32 {
+    let mhz = OCTAVE_MHZ[(note % 12) as usize];
+    let octave = note as i32 / 12 - MIDDLE_C as i32 / 12;
+    let mhz = if octave >= 0 { mhz << octave } else { mhz >> -octave };
+    (mhz + 500) / 1000
+}
+
+fn event(code : u8, status : u8, note : u8, velocity : u8) -> [u8; 4] {
+    [code, status | CHANNEL, note, velocity]
+}
+
+/// The USB MIDI event packet starting `note`, on cable 0.
+pub fn note_on(note : u8) -> [u8; 4] {
+    event(0x09, 0x90, note, VELOCITY)
+}
+
+/// The USB MIDI event packet stopping `note`, on cable 0.
+pub fn note_off(note : u8) -> [u8; 4] {
+    event(0x08, 0x80, note, 0)
+}
+
+/// The pitch picked as a dot, its column the note of the scale and its row the octave,
+/// higher up. The note sounding lights its column dim.
+pub fn frame(picked : Pitch, sounding : Option<Pitch>) -> Frame {
+    let mut frame = [[0; 5]; 5];
+    if let Some(sounding) = sounding {
+        for row in frame.iter_mut() {
+            row[sounding.degree] = DIM_LEVEL;
+        }
+    }
+    frame[OCTAVES - picked.octave][picked.degree] = 9;
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tilt_picks_the_note() {
+        assert_eq!(Pitch::from_tilt(0, 0), Pitch { degree : 2, octave : 1 });
+        assert_eq!(Pitch::from_tilt(-1000, 1000), Pitch { degree : 0, octave : 0 });
+        assert_eq!(Pitch::from_tilt(FULL_MG, -FULL_MG), Pitch { degree : 4, octave : 2 });
+        assert_eq!(Pitch::MIDDLE.note(), MIDDLE_C);
+        assert_eq!(Pitch { degree : 4, octave : 2 }.note(), 81);
+        assert_eq!(Pitch { degree : 1, octave : 0 }.note(), 50);
+    }
+
+    #[test]
+    fn notes_have_their_frequencies() {
+        assert_eq!(hz(69), 440);
+        assert_eq!(hz(81), 880);
+        assert_eq!(hz(57), 220);
+        assert_eq!(hz(MIDDLE_C), 262);
+    }
+
+    #[test]
+    fn events_are_usb_midi_packets() {
+        assert_eq!(note_on(60), [0x09, 0x90, 60, VELOCITY]);
+        assert_eq!(note_off(60), [0x08, 0x80, 60, 0]);
+    }
+
+    #[test]
+    fn the_frame_shows_the_pitch() {
+        let high = Pitch { degree : 3, octave : 2 };
+        assert_eq!(frame(high, Some(high)).map(|row| row[3]), [DIM_LEVEL, 9, DIM_LEVEL, DIM_LEVEL, DIM_LEVEL]);
+        assert_eq!(frame(Pitch::MIDDLE, None)[2], [9, 0, 0, 0, 0]);
+        assert_eq!(frame(Pitch::MIDDLE, Some(high))[2], [9, 0, 0, DIM_LEVEL, 0]);
+    }
+}
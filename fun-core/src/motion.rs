@@ -0,0 +1,159 @@
+//! Shakes and the tilt from an accelerometer, a `board::Motion`.
+//!
+//! At rest the accelerometer reads 1 g in some direction, a shake is a sample well above
+//! that, at any angle. After a shake the next one is only reported once `HOLDOFF_MS` passed,
+//! so one shake of the board with several peaks counts once. The sample also gives the tilt,
+//! for the games steered by tilting the board, and its magnitude for the telemetry.
+//!
+//! The board is upside down, for the display to flip, once gravity pulls towards the top
+//! edge by `UPRIGHT_MG`, and upright again once it pulls as much towards the bottom edge,
+//! so a board lying flat or swaying round the middle keeps the way it was.
+//!
+//! It lies face down once gravity pulls it towards the display by `FACE_DOWN_MG` for
+//! `FACE_DOWN_MS`, and not any more as soon as it is picked up.
+
+use crate::board::Motion;
+
+// NOTE: polled at about this rate, 50 Hz catches the peaks of a shake
+pub const POLL_MS : u64 = 20;
+const SHAKE_MG : i32 = 1800;
+const HOLDOFF_MS : u64 = 500;
+// NOTE: about 35 degrees off flat
+const UPRIGHT_MG : i32 = 600;
+const FACE_DOWN_MG : i32 = 800;
+pub const FACE_DOWN_MS : u64 = 2000;
+
+pub struct Tracker<M> {
+    sensor      : M,
+    last_shake  : Option<u64>,
+    tilt        : Option<(i32, i32)>,
+    magnitude   : Option<u32>,
+    upside_down : bool,
+    // NOTE: since when the display faces the ground
+    face_down   : Option<u64>,
+}
+
+impl<M : Motion> Tracker<M> {
+    pub fn new(sensor : M) -> Self {
+        Tracker { sensor, last_shake : None, tilt : None, magnitude : None, upside_down : false, face_down : None }
+    }
+
+    /// The sensor polled, for the simulator to move its stand-in.
+    pub fn sensor(&mut self) -> &mut M {
+        &mut self.sensor
+    }
+
+    /// Acceleration in mg along x, y and z, read now.
+    pub fn acceleration(&mut self) -> Option<(i32, i32, i32)> {
+        self.sensor.acceleration()
+    }
+
+    /// Gravity along the display in mg as of the last poll, x grows with the right edge
+    /// tilted down and y with the bottom edge tilted down.
+    pub fn tilt(&self) -> Option<(i32, i32)> {
+        self.tilt
+    }
+
+    /// The length of the acceleration in mg as of the last poll, 1 g at rest.
+    pub fn magnitude(&self) -> Option<u32> {
+        self.magnitude
+    }
+
+    /// True when the board hangs upside down as of the last poll.
+    pub fn upside_down(&self) -> bool {
+        self.upside_down
+    }
+
+    /// True when the board has been lying face down for `FACE_DOWN_MS` at `now_ms`, as of
+    /// the last poll.
+    pub fn face_down(&self, now_ms : u64) -> bool {
+        self.face_down.is_some_and(|since| now_ms.saturating_sub(since) >= FACE_DOWN_MS)
+    }
+
+    /// Takes a sample, true when it is a new shake.
+    pub fn poll(&mut self, now_ms : u64) -> bool {
+        let Some((x, y, z)) = self.sensor.acceleration() else { return false };
+        self.tilt = Some((x, y));
+        if y < -UPRIGHT_MG {
+            self.upside_down = true;
+        } else if y > UPRIGHT_MG {
+            self.upside_down = false;
+        }
+        self.face_down = if z > FACE_DOWN_MG { self.face_down.or(Some(now_ms)) } else { None };
+        let squared = x * x + y * y + z * z;
+        self.magnitude = Some((squared as u32).isqrt());
+        if squared < SHAKE_MG * SHAKE_MG {
+            return false;
+        }
+        if self.last_shake.is_some_and(|last| now_ms.saturating_sub(last) < HOLDOFF_MS) {
+            return false;
+        }
+        self.last_shake = Some(now_ms);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Sample(Option<(i32, i32, i32)>);
+
+    impl Motion for Sample {
+        fn acceleration(&mut self) -> Option<(i32, i32, i32)> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn a_board_at_rest_is_no_shake() {
+        let mut tracker = Tracker::new(Sample(Some((300, -400, -866))));
+        assert!(!tracker.poll(0));
+        assert_eq!(tracker.tilt(), Some((300, -400)));
+        assert_eq!(tracker.magnitude(), Some(999));
+    }
+
+    #[test]
+    fn a_shake_counts_once_within_the_holdoff() {
+        let mut tracker = Tracker::new(Sample(Some((2000, 0, -1000))));
+        assert!(tracker.poll(1000));
+        assert!(!tracker.poll(1000 + POLL_MS));
+        assert!(!tracker.poll(1000 + HOLDOFF_MS - 1));
+        assert!(tracker.poll(1000 + HOLDOFF_MS));
+    }
+
+    #[test]
+    fn upside_down_takes_a_clear_tilt_either_way() {
+        let mut tracker = Tracker::new(Sample(Some((0, -900, -400))));
+        tracker.poll(0);
+        assert!(tracker.upside_down());
+        tracker.sensor().0 = Some((0, 0, -1000));
+        tracker.poll(POLL_MS);
+        assert!(tracker.upside_down());
+        tracker.sensor().0 = Some((0, 900, -400));
+        tracker.poll(2 * POLL_MS);
+        assert!(!tracker.upside_down());
+    }
+
+    #[test]
+    fn face_down_takes_a_while_and_ends_on_pickup() {
+        let mut tracker = Tracker::new(Sample(Some((0, 0, 1000))));
+        tracker.poll(100);
+        assert!(!tracker.face_down(100 + FACE_DOWN_MS - 1));
+        tracker.poll(100 + FACE_DOWN_MS);
+        assert!(tracker.face_down(100 + FACE_DOWN_MS));
+        tracker.sensor().0 = Some((0, 0, -1000));
+        tracker.poll(200 + FACE_DOWN_MS);
+        assert!(!tracker.face_down(200 + FACE_DOWN_MS));
+    }
+
+    #[test]
+    fn a_sensor_not_answering_keeps_the_last_sample() {
+        let mut tracker = Tracker::new(Sample(Some((0, 0, -1000))));
+        tracker.poll(0);
+        tracker.sensor.0 = None;
+        assert!(!tracker.poll(POLL_MS));
+        assert_eq!(tracker.tilt(), Some((0, 0)));
+        assert_eq!(tracker.acceleration(), None);
+    }
+}
@@ -0,0 +1,106 @@
+//! Tilt as a USB HID mouse: the tilt of the board moves the cursor, A and B click the left
+//! and the right button, for someone who can tilt a board more easily than push a mouse.
+//!
+//! The mouse is a boot mouse, `REPORT_DESCRIPTOR` is the one of the HID spec's appendix B, a
+//! report is the buttons, then x and y moved as signed bytes. `Pointer` makes one a poll.
+//! Tilt within `DEAD_MG` of level leaves the cursor alone, beyond it the speed grows with
+//! the tilt, times the sensitivity of 1 to 9. A gesture, a shake say, turns the pointer off
+//! and on again, off it sends nothing, so a board put down askew doesn't run the cursor into
+//! a corner.
+//!
+//! The firmware's mouse app feeds `Pointer` the tilt of every accelerometer poll and sends
+//! the reports to its peer over the radio, the peer's host link hands them to a program on
+//! the host moving the cursor, like the keyboard reports of keyboard.rs.
+//!
+//! NOTE: there is no USB mouse in the firmware, for the reason in keyboard.rs. A build on a
+//! board with the USB device wired would send the reports on a HID class's interrupt endpoint.
+
+// NOTE: from the HID spec, three buttons and a relative x and y
+pub const REPORT_DESCRIPTOR : [u8; 50] = [
+    0x05, 0x01, 0x09, 0x02, 0xa1, 0x01, 0x09, 0x01, 0xa1, 0x00, 0x05, 0x09, 0x19, 0x01, 0x29, 0x03,
+    0x15, 0x00, 0x25, 0x01, 0x95, 0x03, 0x75, 0x01, 0x81, 0x02, 0x95, 0x01, 0x75, 0x05, 0x81, 0x01,
+    0x05, 0x01, 0x09, 0x30, 0x09, 0x31, 0x15, 0x81, 0x25, 0x7f, 0x75, 0x08, 0x95, 0x02, 0x81, 0x06,
+    0xc0, 0xc0,
+];
+
+pub const REPORT_LEN : usize = 3;
+pub const LEFT : u8 = 0x01;
+pub const RIGHT : u8 = 0x02;
+const DEAD_MG : i32 = 100;
+// NOTE: mg of tilt beyond the dead zone per count moved a poll, at sensitivity 1
+const MG_PER_COUNT : i32 = 64;
+pub const MAX_SENSITIVITY : u8 = 9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Report {
+    pub buttons : u8,
+    pub x       : i8,
+    pub y       : i8,
+}
+
+impl Report {
+    pub fn to_bytes(&self) -> [u8; REPORT_LEN] {
+        [self.buttons, self.x as u8, self.y as u8]
+    }
+}
+
+pub struct Pointer {
+    sensitivity : u8,
+    on          : bool,
+}
+
+impl Pointer {
+    pub fn new(sensitivity : u8) -> Self {
+        Pointer { sensitivity : sensitivity.clamp(1, MAX_SENSITIVITY), on : true }
+    }
+
+    pub fn set_sensitivity(&mut self, sensitivity : u8) {
+        self.sensitivity = sensitivity.clamp(1, MAX_SENSITIVITY);
+    }
+
+    /// True while the pointer sends reports.
+    pub fn on(&self) -> bool {
+        self.on
+    }
+
+    /// Turns the pointer off or on again, true when it is on now.
+    pub fn toggle(&mut self) -> bool {
+        self.on = !self.on;
+        self.on
+    }
+
+    fn counts(&self, mg : i32) -> i8 {
+        let beyond = (mg.abs() - DEAD_MG).max(0);
+        (mg.signum() * beyond * self.sensitivity as i32 / MG_PER_COUNT).clamp(-127, 127) as i8
+    }
+
+    /// The report for a poll with the board tilted `x` and `y` mg and `buttons` down, None
+    /// while the pointer is off. The bottom edge tilted down moves the cursor down.
+    pub fn report(&self, x : i32, y : i32, buttons : u8) -> Option<Report> {
+        self.on.then(|| Report { buttons, x : self.counts(x), y : self.counts(y) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tilt_beyond_the_dead_zone_moves() {
+        let pointer = Pointer::new(1);
+        assert_eq!(pointer.report(DEAD_MG, -DEAD_MG, 0), Some(Report { buttons : 0, x : 0, y : 0 }));
+        assert_eq!(pointer.report(DEAD_MG + 128, -DEAD_MG - 64, LEFT), Some(Report { buttons : LEFT, x : 2, y : -1 }));
+        let pointer = Pointer::new(20);
+        assert_eq!(pointer.report(1000, 0, 0).map(|report| report.x), Some(126));
+        assert_eq!(pointer.report(-2000, 0, 0).map(|report| report.x), Some(-127));
+    }
+
+    #[test]
+    fn off_sends_nothing() {
+        let mut pointer = Pointer::new(5);
+        assert!(!pointer.toggle());
+        assert_eq!(pointer.report(1000, 1000, RIGHT), None);
+        assert!(pointer.toggle());
+        assert_eq!(Report { buttons : RIGHT, x : -1, y : 3 }.to_bytes(), [RIGHT, 0xff, 3]);
+    }
+}
@@ -0,0 +1,317 @@
+//! A tiny particle system on the 5x5 grid, with the rain, sparkle and firework presets.
+//!
+//! Positions and velocities are fixed point, `ONE` to an LED, so a particle can move slower
+//! than an LED a step and at any angle. Every `step` moves the particles by their velocity,
+//! pulls them down by their gravity and ages them, a particle dies once its life is over or
+//! it leaves the grid. `render` draws the ones left, fading each out over its last steps.
+//!
+//! A `Preset` spawns the particles of one effect, once a step, with randomness coming in
+//! through `Random`. An `Effect` runs a preset over time, for the idle animations a step a
+//! frame and for a game polling faster than that the step some time into it.
+
+use heapless::Vec;
+use crate::{Frame, Random};
+
+/// An LED in fixed point, LED `n` covers `n * ONE` up to `(n + 1) * ONE`.
+pub const ONE : i32 = 256;
+pub const MAX_PARTICLES : usize = 16;
+// NOTE: the last steps of a particle's life, over which it fades out
+const FADE : u8 = 3;
+pub const STEP_MS : u32 = 80;
+// NOTE: every particle has died out by then, there is no point stepping through more
+const CATCH_UP : u64 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Particle {
+    pub x       : i32,
+    pub y       : i32,
+    pub dx      : i32,
+    pub dy      : i32,
+    // NOTE: added to dy every step, positive pulls down
+    pub gravity : i32,
+    pub level   : u8,
+    // NOTE: the steps it has left
+    pub life    : u8,
+}
+
+impl Particle {
+    /// A particle at rest in the middle of the LED at `x`, `y`.
+    pub const fn at(x : usize, y : usize, level : u8, life : u8) -> Self {
+        Particle {
+            x       : x as i32 * ONE + ONE / 2,
+            y       : y as i32 * ONE + ONE / 2,
+            dx      : 0,
+            dy      : 0,
+            gravity : 0,
+            level,
+            life,
+        }
+    }
+
+    fn led(&self) -> Option<(usize, usize)> {
+        let on_grid = |v : i32| (0..5 * ONE).contains(&v).then_some((v / ONE) as usize);
+        Some((on_grid(self.x)?, on_grid(self.y)?))
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Particles {
+    particles : Vec<Particle, MAX_PARTICLES>,
+}
+
+impl Particles {
+    pub const fn new() -> Self {
+        Particles { particles : Vec::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Adds `particle`, false when there are `MAX_PARTICLES` already and it was dropped.
+    pub fn spawn(&mut self, particle : Particle) -> bool {
+        self.particles.push(particle).is_ok()
+    }
+
+    pub fn step(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.x += particle.dx;
+            particle.y += particle.dy;
+            particle.dy += particle.gravity;
+            particle.life = particle.life.saturating_sub(1);
+        }
+        self.particles.retain(|particle| particle.life > 0 && particle.led().is_some());
+    }
+
+    /// The particles on the grid, the brightest one where they overlap.
+    pub fn render(&self) -> Frame {
+        let mut leds = [[0; 5]; 5];
+        for particle in &self.particles {
+            if let Some((x, y)) = particle.led() {
+                let level = if particle.life < FADE {
+                    (particle.level as u32 * particle.life as u32 / FADE as u32) as u8
+                } else {
+                    particle.level
+                };
+                leds[y][x] = leds[y][x].max(level);
+            }
+        }
+        leds
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    /// Drops falling down random columns at slightly different speeds.
+    Rain,
+    /// Still LEDs at random places and levels, each lit for a few steps.
+    Sparkle,
+    /// A burst at a random place once the last one has burnt out, its sparks falling away.
+    Firework,
+}
+
+// NOTE: the eight directions of a firework's sparks
+const BURST : [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+impl Preset {
+    /// Spawns this step's particles of the effect.
+    pub fn spawn(self, particles : &mut Particles, random : &mut impl Random) {
+        match self {
+            Preset::Rain => {
+                if random.below(3) == 0 {
+                    let mut drop = Particle::at(random.below(5) as usize, 0, 9, 20);
+                    drop.dy = ONE / 2 + random.below(ONE as u32 / 4) as i32;
+                    particles.spawn(drop);
+                }
+            }
+            Preset::Sparkle => {
+                for _ in 0..2 {
+                    let led = random.below(25) as usize;
+                    let life = 2 + random.below(3) as u8;
+                    particles.spawn(Particle::at(led % 5, led / 5, 1 + random.below(9) as u8, life));
+                }
+            }
+            Preset::Firework => {
+                if !particles.is_empty() {
+                    return;
+                }
+                let (x, y) = (1 + random.below(3) as usize, 1 + random.below(2) as usize);
+                let life = 6 + random.below(3) as u8;
+                particles.spawn(Particle::at(x, y, 9, life));
+                for (dx, dy) in BURST {
+                    particles.spawn(Particle {
+                        dx      : dx * ONE / 2,
+                        dy      : dy * ONE / 2,
+                        gravity : ONE / 16,
+                        ..Particle::at(x, y, 9, life)
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// One preset over time, it starts over with no particles whenever another takes over.
+#[derive(Clone, Default)]
+pub struct Effect {
+    preset    : Option<Preset>,
+    particles : Particles,
+    steps     : u64,
+}
+
+impl Effect {
+    pub const fn new() -> Self {
+        Effect { preset : None, particles : Particles::new(), steps : 0 }
+    }
+
+    /// The next frame of `preset`, a step on from the last one.
+    pub fn next(&mut self, preset : Preset, random : &mut impl Random) -> Frame {
+        self.advance(preset, |steps| steps + 1, random)
+    }
+
+    /// The frame of `preset` `elapsed_ms` into it, an earlier time than the last starts it over.
+    pub fn at(&mut self, preset : Preset, elapsed_ms : u64, random : &mut impl Random) -> Frame {
+        self.advance(preset, |_| elapsed_ms / STEP_MS as u64 + 1, random)
+    }
+
+    fn advance(&mut self, preset : Preset, target : impl FnOnce(u64) -> u64, random : &mut impl Random) -> Frame {
+        let fresh = Effect { preset : Some(preset), ..Effect::new() };
+        if self.preset != Some(preset) {
+            *self = fresh.clone();
+        }
+        let target = target(self.steps);
+        if target < self.steps {
+            *self = fresh;
+        }
+        self.steps = self.steps.max(target.saturating_sub(CATCH_UP));
+        while self.steps < target {
+            self.particles.step();
+            preset.spawn(&mut self.particles, random);
+            self.steps += 1;
+        }
+        self.particles.render()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NOTE: counts up, so every call picks something different but predictable
+    struct Counter(u32);
+
+    impl Random for Counter {
+        fn below(&mut self, n : u32) -> u32 {
+            self.0 += 1;
+            self.0 % n
+        }
+    }
+
+    #[test]
+    fn particle_moves_and_dies() {
+        let mut particles = Particles::new();
+        particles.spawn(Particle { dx : ONE, ..Particle::at(0, 2, 9, 3) });
+        particles.step();
+        assert_eq!(particles.render()[2][1], 6);
+        particles.step();
+        assert_eq!(particles.render()[2][2], 3);
+        particles.step();
+        assert!(particles.is_empty());
+    }
+
+    #[test]
+    fn particle_leaving_the_grid_dies() {
+        let mut particles = Particles::new();
+        particles.spawn(Particle { dy : -ONE, ..Particle::at(2, 0, 9, 20) });
+        particles.step();
+        assert!(particles.is_empty());
+    }
+
+    #[test]
+    fn slow_particle_takes_steps_per_led() {
+        let mut particles = Particles::new();
+        particles.spawn(Particle { dy : ONE / 4, ..Particle::at(1, 0, 9, 20) });
+        particles.step();
+        assert_eq!(particles.render()[0][1], 9);
+        particles.step();
+        particles.step();
+        assert_eq!(particles.render()[1][1], 9);
+    }
+
+    #[test]
+    fn overlapping_particles_show_the_brightest() {
+        let mut particles = Particles::new();
+        particles.spawn(Particle::at(3, 3, 4, 10));
+        particles.spawn(Particle::at(3, 3, 7, 10));
+        assert_eq!(particles.render()[3][3], 7);
+    }
+
+    #[test]
+    fn spawning_past_the_limit_drops() {
+        let mut particles = Particles::new();
+        for _ in 0..MAX_PARTICLES {
+            assert!(particles.spawn(Particle::at(0, 0, 9, 10)));
+        }
+        assert!(!particles.spawn(Particle::at(0, 0, 9, 10)));
+    }
+
+    #[test]
+    fn firework_bursts_once_the_last_burnt_out() {
+        let (mut particles, mut random) = (Particles::new(), Counter(0));
+        Preset::Firework.spawn(&mut particles, &mut random);
+        assert_eq!(particles.len(), 1 + BURST.len());
+        Preset::Firework.spawn(&mut particles, &mut random);
+        assert_eq!(particles.len(), 1 + BURST.len());
+        let mut steps = 0;
+        while !particles.is_empty() {
+            particles.step();
+            steps += 1;
+        }
+        assert!(steps <= 8);
+        Preset::Firework.spawn(&mut particles, &mut random);
+        assert_eq!(particles.len(), 1 + BURST.len());
+    }
+
+    #[test]
+    fn rain_falls() {
+        let (mut particles, mut random) = (Particles::new(), Counter(0));
+        for _ in 0..30 {
+            particles.step();
+            Preset::Rain.spawn(&mut particles, &mut random);
+            assert!(particles.render().iter().flatten().all(|led| *led == 0 || *led == 9));
+        }
+        assert!(!particles.is_empty());
+    }
+
+    #[test]
+    fn effect_steps_to_the_time_asked_for() {
+        let (mut effect, mut random) = (Effect::new(), Counter(0));
+        effect.at(Preset::Rain, 4 * STEP_MS as u64, &mut random);
+        assert_eq!(effect.steps, 5);
+        effect.next(Preset::Rain, &mut random);
+        assert_eq!(effect.steps, 6);
+        // NOTE: back in time, or another preset, starts over
+        effect.at(Preset::Rain, 0, &mut random);
+        assert_eq!(effect.steps, 1);
+        effect.next(Preset::Rain, &mut random);
+        effect.next(Preset::Sparkle, &mut random);
+        assert_eq!(effect.steps, 1);
+    }
+
+    #[test]
+    fn effect_catches_up_no_more_than_it_shows() {
+        let (mut effect, mut random) = (Effect::new(), Counter(0));
+        effect.at(Preset::Sparkle, 1_000 * STEP_MS as u64, &mut random);
+        assert_eq!(effect.steps, 1_001);
+        // NOTE: a sparkle takes 6 random numbers a step
+        assert_eq!(random.0, 6 * CATCH_UP as u32);
+    }
+}
@@ -0,0 +1,279 @@
+//! The packets the boards send each other over the radio, the one place their layout is
+//! defined, for the firmware and for anything on the host that listens in.
+//!
+//! A packet is a `Packet` encoded with postcard: the variant index, then the fields in order,
+//! integers as varints unless marked fixint. Sealing adds the sender address and the MIC
+//! around it, so an encoded packet has to fit in `MAX_LEN`, `encode` gives None for one that
+//! doesn't. A packet for one board only is a `Packet::Unicast` header with the packet for it
+//! following, see `address` and `split`.
+//!
+//! NOTE: boards running different firmware still have to understand each other, so new
+//! variants and fields go at the end, like the settings of the firmware's storage.rs
+
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+use crate::keyboard;
+use crate::mouse;
+
+/// The longest packet that fits in a sealed radio packet, seal.rs asserts it.
+pub const MAX_LEN : usize = 21;
+
+/// The `Unicast` header in front of an addressed packet, `to` is a fixint.
+pub const UNICAST_HEADER_LEN : usize = 3;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Packet<'a> {
+    /// A log record, the level as the firmware's `Level`.
+    Log { level : u8, text : &'a str },
+    /// The firmware version, broadcast at boot.
+    Hello { major : u8, minor : u8, patch : u8, commit : &'a str },
+    Pair(Pair),
+    /// The packet following it is for the board at `to` only.
+    Unicast {
+        #[serde(with = "postcard::fixint::le")]
+        to : u16,
+    },
+    Rps(Rps),
+    Tug(Tug),
+    /// The signal strength meter's beacon.
+    Beacon,
+    /// The master's time in microseconds.
+    Time { us : u64 },
+    /// A text message flooded through the boards, `ttl` hops to go.
+    Relay {
+        #[serde(with = "postcard::fixint::le")]
+        origin   : u16,
+        #[serde(with = "postcard::fixint::le")]
+        sequence : u16,
+        ttl      : u8,
+        text     : &'a str,
+    },
+    Remote(Remote),
+    Telemetry(Telemetry),
+    /// Asks the board at `to` for a command nonce.
+    Challenge {
+        #[serde(with = "postcard::fixint::le")]
+        to : u16,
+    },
+    /// The nonce a board handed out, for the next command to it.
+    Nonce([u8; 8]),
+    /// A console command for the board at `to`, `tag` authenticates it with the last nonce.
+    Command {
+        #[serde(with = "postcard::fixint::le")]
+        to   : u16,
+        tag  : [u8; 4],
+        text : &'a str,
+    },
+    Ota(Ota),
+    /// The drive of a radio controlled car, in percent, see the firmware's car.rs.
+    Drive { throttle : i8, steering : i8 },
+    /// A student board's name, checking in with the teacher's, see the firmware's attendance.rs.
+    CheckIn { name : &'a str },
+    Quiz(Quiz),
+    /// The proximity alert's beacon, see the firmware's proximity.rs.
+    Presence,
+    /// The animation every board draws from `start_us` on the synced clock, see the
+    /// firmware's swarm.rs.
+    Swarm { animation : u8, start_us : u64 },
+    /// The bytes of a keyboard report with the keys pressed, see `keyboard::Report`, for the
+    /// host of the board it is sent to, which releases them again, see the firmware's
+    /// presenter.rs.
+    Hid([u8; keyboard::REPORT_LEN]),
+    /// The bytes of a mouse report, see `mouse::Report`, for the host of the board it is sent
+    /// to, see the firmware's mouse.rs.
+    Mouse([u8; mouse::REPORT_LEN]),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Pair {
+    Request { device_id : u64 },
+    Answer { device_id : u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Rps {
+    /// The digest of the choice, the nonce and the committing board's address.
+    Commit([u8; 16]),
+    Reveal { choice : u8, nonce : [u8; 8] },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Tug {
+    Ready,
+    Go,
+    Count(u16),
+    Won,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+    A,
+    B,
+    AB,
+    Logo,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Remote {
+    Press(Key),
+    LongPress(Key),
+}
+
+/// A firmware image sent over the radio, see image.rs.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Ota {
+    /// The chunk an updating board wants next, again until it comes.
+    Want { offset : u32 },
+    Chunk {
+        #[serde(with = "postcard::fixint::le")]
+        offset : u32,
+        #[serde(with = "postcard::fixint::le")]
+        crc    : u16,
+        data   : [u8; 12],
+    },
+    /// The image ends before the offset wanted.
+    End,
+}
+
+/// The quiz buzzer, times in µs on the synced clock, see the firmware's quiz.rs.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Quiz {
+    /// The host's go, again until the round settles.
+    Go { round : u8, us : u64 },
+    /// A press, again until its place comes.
+    Buzz { round : u8, us : u64 },
+    Placed {
+        round : u8,
+        #[serde(with = "postcard::fixint::le")]
+        to    : u16,
+        place : u8,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Telemetry {
+    pub uptime_s    : u32,
+    // NOTE: in the quarter degrees of the TEMP peripheral
+    pub temperature : Option<i32>,
+    pub accel_mg    : Option<u32>,
+    pub supply_mv   : u16,
+    pub presses     : u32,
+}
+
+impl<'a> Packet<'a> {
+    /// The packet as bytes, None when it doesn't fit in `MAX_LEN`.
+    pub fn encode<const N : usize>(&self) -> Option<Vec<u8, N>> {
+        let mut bytes = [0; MAX_LEN];
+        let used = postcard::to_slice(self, &mut bytes).ok()?;
+        Vec::from_slice(used).ok()
+    }
+
+    /// The packet at the start of `bytes` and the bytes after it.
+    pub fn split(bytes : &'a [u8]) -> Option<(Packet<'a>, &'a [u8])> {
+        postcard::take_from_bytes(bytes).ok()
+    }
+
+    /// The packet in `bytes`, None for anything that isn't one.
+    pub fn decode(bytes : &'a [u8]) -> Option<Packet<'a>> {
+        Packet::split(bytes).map(|(packet, _)| packet)
+    }
+}
+
+/// The encoded `packet` addressed to the board at `to`.
+pub fn address<const N : usize>(to : u16, packet : &[u8]) -> Option<Vec<u8, N>> {
+    if UNICAST_HEADER_LEN + packet.len() > MAX_LEN {
+        return None;
+    }
+    let mut bytes : Vec<u8, N> = Packet::Unicast { to }.encode()?;
+    bytes.extend_from_slice(packet).ok()?;
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(packet : Packet) -> usize {
+        let bytes : Vec<u8, MAX_LEN> = packet.encode().unwrap();
+        assert_eq!(Packet::decode(&bytes), Some(packet));
+        bytes.len()
+    }
+
+    #[test]
+    fn every_packet_round_trips() {
+        round_trip(Packet::Log { level : 2, text : "héllo" });
+        round_trip(Packet::Hello { major : 0, minor : 1, patch : 0, commit : "5d025b8" });
+        round_trip(Packet::Pair(Pair::Answer { device_id : u64::MAX }));
+        round_trip(Packet::Rps(Rps::Reveal { choice : 2, nonce : [7; 8] }));
+        round_trip(Packet::Tug(Tug::Count(300)));
+        round_trip(Packet::Beacon);
+        round_trip(Packet::Time { us : u64::MAX });
+        round_trip(Packet::Relay { origin : 0xbeef, sequence : 1, ttl : 3, text : "hi" });
+        round_trip(Packet::Remote(Remote::LongPress(Key::Logo)));
+        round_trip(Packet::Challenge { to : 0x1234 });
+        round_trip(Packet::Nonce([9; 8]));
+        round_trip(Packet::Command { to : 0x1234, tag : [1, 2, 3, 4], text : "set group 12" });
+        round_trip(Packet::Ota(Ota::Want { offset : 240 * 1024 }));
+        round_trip(Packet::Ota(Ota::End));
+        round_trip(Packet::Drive { throttle : -100, steering : 40 });
+        round_trip(Packet::CheckIn { name : "ada" });
+        round_trip(Packet::Quiz(Quiz::Placed { round : 200, to : 0xbeef, place : 1 }));
+        round_trip(Packet::Presence);
+        round_trip(Packet::Swarm { animation : 3, start_us : u64::MAX });
+        round_trip(Packet::Hid(keyboard::slide(true).to_bytes()));
+        round_trip(Packet::Mouse(mouse::Report { buttons : mouse::LEFT, x : -127, y : 127 }.to_bytes()));
+        round_trip(Packet::Telemetry(Telemetry {
+            uptime_s    : 3600,
+            temperature : Some(-20),
+            accel_mg    : None,
+            supply_mv   : 3000,
+            presses     : 12,
+        }));
+    }
+
+    #[test]
+    fn the_largest_fixed_packets_fit() {
+        assert_eq!(round_trip(Packet::Rps(Rps::Commit([0xff; 16]))), MAX_LEN - UNICAST_HEADER_LEN);
+        assert!(round_trip(Packet::Pair(Pair::Request { device_id : u64::MAX })) <= MAX_LEN);
+        assert_eq!(round_trip(Packet::Command { to : 0, tag : [0; 4], text : "set auth off" }), MAX_LEN - 1);
+        assert_eq!(round_trip(Packet::Ota(Ota::Chunk { offset : u32::MAX, crc : 0, data : [0xff; 12] })), MAX_LEN - 1);
+        // NOTE: the longest name a board has, see the firmware's identity.rs
+        assert!(round_trip(Packet::CheckIn { name : "abcdefghijklmnop" }) <= MAX_LEN);
+        assert!(round_trip(Packet::Quiz(Quiz::Buzz { round : u8::MAX, us : u64::MAX })) <= MAX_LEN);
+        assert!(round_trip(Packet::Hid([0xff; keyboard::REPORT_LEN])) <= MAX_LEN - UNICAST_HEADER_LEN);
+        assert!(round_trip(Packet::Mouse([0xff; mouse::REPORT_LEN])) <= MAX_LEN - UNICAST_HEADER_LEN);
+        // NOTE: a year up, hot, shaken and pressed a million times
+        assert!(round_trip(Packet::Telemetry(Telemetry {
+            uptime_s    : 365 * 24 * 3600,
+            temperature : Some(i16::MAX as i32),
+            accel_mg    : Some(u16::MAX as u32),
+            supply_mv   : u16::MAX,
+            presses     : 1_000_000,
+        })) <= MAX_LEN);
+    }
+
+    #[test]
+    fn a_packet_too_long_is_not_encoded() {
+        let text = "x".repeat(MAX_LEN);
+        assert_eq!(Packet::Log { level : 0, text : &text }.encode::<32>(), None);
+    }
+
+    #[test]
+    fn an_addressed_packet_splits_into_header_and_packet() {
+        let commit : Vec<u8, MAX_LEN> = Packet::Rps(Rps::Commit([1; 16])).encode().unwrap();
+        let bytes : Vec<u8, 32> = address(0x1234, &commit).unwrap();
+        assert_eq!(bytes.len(), MAX_LEN);
+        let (header, rest) = Packet::split(&bytes).unwrap();
+        assert_eq!(header, Packet::Unicast { to : 0x1234 });
+        assert_eq!(Packet::decode(rest), Some(Packet::Rps(Rps::Commit([1; 16]))));
+        assert_eq!(address::<32>(0x1234, &[0; MAX_LEN - 2]), None);
+    }
+
+    #[test]
+    fn garbage_is_not_a_packet() {
+        assert_eq!(Packet::decode(&[]), None);
+        assert_eq!(Packet::decode(&[0xff]), None);
+        // NOTE: a log packet whose text runs past the end
+        assert_eq!(Packet::decode(&[0, 1, 5, b'a']), None);
+    }
+}
@@ -0,0 +1,161 @@
+//! How close the other boards are, from the signal strength of their presence beacons, and
+//! which one stayed too close for too long.
+//!
+//! Every board heard is tracked by its address. Its strength is smoothed, a `SMOOTHING`th
+//! of every new sample going in, so one reflection doesn't make it close. A board is close
+//! from `CLOSE_DBM` on and stays close until it falls `HYSTERESIS_DB` below, so one at the
+//! edge doesn't flicker. Close for `TOO_LONG_MS` and `alert` names it, again every
+//! `REPEAT_MS` while it stays. A board not heard for `LOST_MS` is forgotten.
+
+use heapless::Vec;
+
+// NOTE: about half a metre between two micro:bits at 0 dBm, walls and hands change it a lot
+pub const CLOSE_DBM : i32 = -60;
+const HYSTERESIS_DB : i32 = 5;
+pub const TOO_LONG_MS : u64 = 5_000;
+const REPEAT_MS : u64 = 30_000;
+const LOST_MS : u64 = 3_000;
+const SMOOTHING : i32 = 4;
+
+#[derive(Clone, Copy, Debug)]
+struct Board {
+    address     : u16,
+    // NOTE: 16 times the dBm, so the smoothing keeps its fraction
+    strength    : i32,
+    heard_ms    : u64,
+    close_since : Option<u64>,
+    alerted_ms  : Option<u64>,
+}
+
+impl Board {
+    fn dbm(&self) -> i32 {
+        self.strength / 16
+    }
+}
+
+pub struct Tracker<const N : usize> {
+    boards : Vec<Board, N>,
+}
+
+impl<const N : usize> Tracker<N> {
+    pub const fn new() -> Self {
+        Tracker { boards : Vec::new() }
+    }
+
+    /// Takes a beacon from the board at `address` heard at `rssi` dBm at `now_ms`. A new board
+    /// takes the place of the one heard longest ago when all are taken.
+    pub fn heard(&mut self, address : u16, rssi : i8, now_ms : u64) {
+        let sample = rssi as i32 * 16;
+        let board = match self.boards.iter().position(|board| board.address == address) {
+            Some(i) => &mut self.boards[i],
+            None => {
+                let fresh = Board { address, strength : sample, heard_ms : now_ms, close_since : None, alerted_ms : None };
+                if self.boards.push(fresh).is_err() {
+                    let oldest = self.boards.iter().enumerate().min_by_key(|(_, board)| board.heard_ms).map_or(0, |(i, _)| i);
+                    self.boards[oldest] = fresh;
+                }
+                self.boards.iter_mut().find(|board| board.address == address).unwrap()
+            }
+        };
+        board.strength += (sample - board.strength) / SMOOTHING;
+        board.heard_ms = now_ms;
+        let close = board.dbm() >= if board.close_since.is_some() { CLOSE_DBM - HYSTERESIS_DB } else { CLOSE_DBM };
+        board.close_since = match (close, board.close_since) {
+            (true, since) => Some(since.unwrap_or(now_ms)),
+            (false, _) => None,
+        };
+        if !close {
+            board.alerted_ms = None;
+        }
+    }
+
+    /// The board too close for too long at `now_ms` and its smoothed strength, if it wasn't
+    /// alerted about within `REPEAT_MS`.
+    pub fn alert(&mut self, now_ms : u64) -> Option<(u16, i32)> {
+        self.boards.retain(|board| now_ms.saturating_sub(board.heard_ms) < LOST_MS);
+        let board = self.boards.iter_mut().find(|board| {
+            board.close_since.is_some_and(|since| now_ms.saturating_sub(since) >= TOO_LONG_MS)
+                && board.alerted_ms.is_none_or(|at| now_ms.saturating_sub(at) >= REPEAT_MS)
+        })?;
+        board.alerted_ms = Some(now_ms);
+        Some((board.address, board.dbm()))
+    }
+
+    /// How many boards are close at the moment.
+    pub fn close(&self) -> usize {
+        self.boards.iter().filter(|board| board.close_since.is_some()).count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.boards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boards.is_empty()
+    }
+}
+
+impl<const N : usize> Default for Tracker<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn close_for_too_long_alerts_once_then_repeats() {
+        let mut tracker = Tracker::<4>::new();
+        let mut now = 0;
+        while now <= TOO_LONG_MS {
+            tracker.heard(7, -50, now);
+            if now < TOO_LONG_MS {
+                assert_eq!(tracker.alert(now), None);
+            }
+            now += 500;
+        }
+        assert_eq!(tracker.alert(now), Some((7, -50)));
+        tracker.heard(7, -50, now + 500);
+        assert_eq!(tracker.alert(now + 500), None);
+        tracker.heard(7, -50, now + REPEAT_MS);
+        assert_eq!(tracker.alert(now + REPEAT_MS), Some((7, -50)));
+    }
+
+    #[test]
+    fn one_strong_sample_is_smoothed_away() {
+        let mut tracker = Tracker::<4>::new();
+        tracker.heard(1, -90, 0);
+        tracker.heard(1, -40, 100);
+        assert_eq!(tracker.close(), 0);
+        assert_eq!(tracker.len(), 1);
+    }
+
+    #[test]
+    fn the_edge_does_not_flicker() {
+        let mut tracker = Tracker::<4>::new();
+        tracker.heard(1, CLOSE_DBM as i8, 0);
+        assert_eq!(tracker.close(), 1);
+        // NOTE: within the hysteresis it stays close, smoothed a little at a time
+        tracker.heard(1, (CLOSE_DBM - 4) as i8, 100);
+        assert_eq!(tracker.close(), 1);
+        for at in 2..20 {
+            tracker.heard(1, (CLOSE_DBM - 20) as i8, at * 100);
+        }
+        assert_eq!(tracker.close(), 0);
+    }
+
+    #[test]
+    fn silent_boards_are_forgotten() {
+        let mut tracker = Tracker::<2>::new();
+        tracker.heard(1, -80, 0);
+        tracker.heard(2, -80, 1000);
+        tracker.heard(3, -80, 2000);
+        assert_eq!(tracker.len(), 2);
+        assert_eq!(tracker.alert(4000), None);
+        assert_eq!(tracker.len(), 1);
+        assert_eq!(tracker.alert(10_000), None);
+        assert!(tracker.is_empty());
+    }
+}
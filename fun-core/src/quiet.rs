@@ -0,0 +1,79 @@
+//! Quiet hours: the part of the day a board holds back its notifications, from a start to an
+//! end time, in minutes since midnight.
+//!
+//! An end before the start wraps past midnight, 22:00-07:00 is the night. The same start and
+//! end is no time at all, not the whole day.
+
+use core::fmt;
+
+pub const DAY_MIN : u16 = 24 * 60;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hours {
+    pub start_min : u16,
+    pub end_min   : u16,
+}
+
+/// `hh:mm` as minutes since midnight.
+pub fn parse_time(text : &str) -> Option<u16> {
+    let (hours, minutes) = text.split_once(':')?;
+    if minutes.len() != 2 {
+        return None;
+    }
+    let (hours, minutes) = (hours.parse::<u16>().ok()?, minutes.parse::<u16>().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+impl Hours {
+    /// `hh:mm-hh:mm`.
+    pub fn parse(text : &str) -> Option<Hours> {
+        let (start, end) = text.split_once('-')?;
+        Some(Hours { start_min : parse_time(start)?, end_min : parse_time(end)? })
+    }
+
+    /// True when `minute` of the day is within the hours, from the start up to the end.
+    pub fn contains(&self, minute : u16) -> bool {
+        if self.start_min <= self.end_min {
+            (self.start_min..self.end_min).contains(&minute)
+        } else {
+            minute >= self.start_min || minute < self.end_min
+        }
+    }
+}
+
+impl fmt::Display for Hours {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}-{:02}:{:02}", self.start_min / 60, self.start_min % 60, self.end_min / 60, self.end_min % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_parse() {
+        assert_eq!(parse_time("07:30"), Some(450));
+        assert_eq!(parse_time("7:05"), Some(425));
+        assert_eq!(parse_time("24:00"), None);
+        assert_eq!(parse_time("12:60"), None);
+        assert_eq!(parse_time("12:5"), None);
+        assert_eq!(Hours::parse("22:00-07:00"), Some(Hours { start_min : 1320, end_min : 420 }));
+        assert_eq!(Hours::parse("22:00"), None);
+        assert_eq!(Hours { start_min : 1320, end_min : 5 }.to_string(), "22:00-00:05");
+    }
+
+    #[test]
+    fn the_night_wraps_past_midnight() {
+        let night = Hours::parse("22:00-07:00").unwrap();
+        assert!(night.contains(23 * 60));
+        assert!(night.contains(0));
+        assert!(night.contains(6 * 60 + 59));
+        assert!(!night.contains(7 * 60));
+        assert!(!night.contains(12 * 60));
+        let lunch = Hours::parse("12:00-13:00").unwrap();
+        assert!(lunch.contains(12 * 60 + 30));
+        assert!(!lunch.contains(13 * 60));
+        assert!(!Hours::parse("09:00-09:00").unwrap().contains(9 * 60));
+    }
+}
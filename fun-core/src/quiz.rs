@@ -0,0 +1,115 @@
+//! The standings of a quiz buzzer round: which boards buzzed, in the order they pressed, not
+//! the order their buzzes came in.
+//!
+//! A buzz carries the synced time of the press, see the firmware's timesync.rs, so a buzz
+//! held up by a retry or by another board on air still takes the place its press earned. A
+//! press stamped before the go is a false start and isn't placed. Presses within `TIE_US` of
+//! the one before share its place, finer than that the synced clocks can't tell apart, and
+//! the place after a tie is skipped, 1, 1, 3.
+
+use heapless::Vec;
+
+pub const TIE_US : u64 = 2_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Buzz {
+    pub address : u16,
+    pub us      : u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Taken {
+    Placed,
+    Again,
+    FalseStart,
+    Full,
+}
+
+pub struct Round<const N : usize> {
+    go_us  : u64,
+    // NOTE: in the order of the presses
+    buzzes : Vec<Buzz, N>,
+}
+
+impl<const N : usize> Round<N> {
+    /// A round that went at `go_us` on the synced clock.
+    pub const fn new(go_us : u64) -> Self {
+        Round { go_us, buzzes : Vec::new() }
+    }
+
+    pub fn go_us(&self) -> u64 {
+        self.go_us
+    }
+
+    /// Takes the buzz of the board at `address` pressed at `us`, a board is placed once.
+    pub fn buzz(&mut self, address : u16, us : u64) -> Taken {
+        if self.buzzes.iter().any(|buzz| buzz.address == address) {
+            return Taken::Again;
+        }
+        if us < self.go_us {
+            return Taken::FalseStart;
+        }
+        let at = self.buzzes.iter().position(|buzz| buzz.us > us).unwrap_or(self.buzzes.len());
+        match self.buzzes.insert(at, Buzz { address, us }) {
+            Ok(()) => Taken::Placed,
+            Err(_) => Taken::Full,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buzzes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buzzes.is_empty()
+    }
+
+    /// Every buzz with its place, counted from 1, and how long after the go it was pressed.
+    pub fn places(&self) -> impl Iterator<Item = (Buzz, u8, u64)> + '_ {
+        let mut last : Option<(u64, u8)> = None;
+        self.buzzes.iter().enumerate().map(move |(i, buzz)| {
+            let place = match last {
+                Some((us, place)) if buzz.us - us <= TIE_US => place,
+                _ => (i + 1).min(u8::MAX as usize) as u8,
+            };
+            last = Some((buzz.us, place));
+            (*buzz, place, buzz.us - self.go_us)
+        })
+    }
+
+    pub fn place_of(&self, address : u16) -> Option<u8> {
+        self.places().find(|(buzz, _, _)| buzz.address == address).map(|(_, place, _)| place)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presses_are_placed_in_the_order_they_happened() {
+        let mut round = Round::<4>::new(1_000_000);
+        assert_eq!(round.buzz(0xb, 1_300_000), Taken::Placed);
+        // NOTE: pressed first, heard second
+        assert_eq!(round.buzz(0xa, 1_200_000), Taken::Placed);
+        assert_eq!(round.buzz(0xa, 1_100_000), Taken::Again);
+        assert_eq!(round.buzz(0xc, 999_999), Taken::FalseStart);
+        assert_eq!(round.place_of(0xa), Some(1));
+        assert_eq!(round.place_of(0xb), Some(2));
+        assert_eq!(round.place_of(0xc), None);
+        let reactions : Vec<u64, 4> = round.places().map(|(_, _, us)| us).collect();
+        assert_eq!(reactions, [200_000, 300_000]);
+    }
+
+    #[test]
+    fn ties_share_a_place() {
+        let mut round = Round::<4>::new(0);
+        round.buzz(1, 10_000);
+        round.buzz(2, 10_000 + TIE_US);
+        round.buzz(3, 50_000);
+        assert_eq!(round.buzz(4, 60_000), Taken::Placed);
+        assert_eq!(round.buzz(5, 70_000), Taken::Full);
+        let places : Vec<u8, 4> = round.places().map(|(_, place, _)| place).collect();
+        assert_eq!(places, [1, 1, 3, 4]);
+    }
+}
@@ -0,0 +1,122 @@
+//! A mini oscilloscope: the samples of an analog input in a ring buffer, drawn as a trace
+//! scrolling across the matrix.
+//!
+//! The samples come in bursts of `PER_COLUMN`, the interval of the timebase apart, and a
+//! burst is a column of the trace, lit from its lowest sample to its highest, the bottom row
+//! 0 and the top row `FULL_SCALE`. New columns come in on the right. The timebase steps
+//! through `TIMEBASES_US`, from 4 kHz down to 50 Hz.
+
+use heapless::Deque;
+use crate::Frame;
+
+pub const PER_COLUMN : usize = 4;
+// NOTE: 12 bits
+pub const FULL_SCALE : u16 = 4096;
+// NOTE: between two samples
+pub const TIMEBASES_US : [u32; 5] = [250, 1000, 2500, 5000, 20000];
+const RING : usize = 5 * PER_COLUMN;
+
+pub struct Trace {
+    samples  : Deque<u16, RING>,
+    timebase : usize,
+}
+
+fn row(sample : u16) -> usize {
+    4 - (sample as usize * 5 / FULL_SCALE as usize).min(4)
+}
+
+impl Trace {
+    pub const fn new() -> Self {
+        Trace { samples : Deque::new(), timebase : 0 }
+    }
+
+    pub fn push(&mut self, sample : u16) {
+        if self.samples.is_full() {
+            self.samples.pop_front();
+        }
+        let _ = self.samples.push_back(sample);
+    }
+
+    pub fn interval_us(&self) -> u32 {
+        TIMEBASES_US[self.timebase]
+    }
+
+    /// A step of the timebase, `faster` to the shorter intervals, false at the end.
+    pub fn step(&mut self, faster : bool) -> bool {
+        let next = if faster { self.timebase.checked_sub(1) } else { Some(self.timebase + 1) };
+        match next.filter(|next| *next < TIMEBASES_US.len()) {
+            Some(next) => {
+                self.timebase = next;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The trace, the columns not sampled yet dark.
+    pub fn frame(&self) -> Frame {
+        let mut frame = [[0; 5]; 5];
+        let len = self.samples.len();
+        for col in 0..5 {
+            let Some(start) = len.checked_sub((5 - col) * PER_COLUMN) else { continue };
+            let mut burst = self.samples.iter().skip(start).take(PER_COLUMN);
+            let Some(first) = burst.next() else { continue };
+            let (low, high) = burst.fold((*first, *first), |(low, high), sample| (low.min(*sample), high.max(*sample)));
+            for line in frame.iter_mut().take(row(low) + 1).skip(row(high)) {
+                line[col] = 9;
+            }
+        }
+        frame
+    }
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Trace::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn burst(trace : &mut Trace, samples : [u16; PER_COLUMN]) {
+        for sample in samples {
+            trace.push(sample);
+        }
+    }
+
+    #[test]
+    fn columns_come_in_on_the_right() {
+        let mut trace = Trace::new();
+        burst(&mut trace, [0; PER_COLUMN]);
+        let frame = trace.frame();
+        assert_eq!(frame[4], [0, 0, 0, 0, 9]);
+        assert_eq!(frame[0], [0; 5]);
+        burst(&mut trace, [4095; PER_COLUMN]);
+        let frame = trace.frame();
+        assert_eq!(frame[4], [0, 0, 0, 9, 0]);
+        assert_eq!(frame[0], [0, 0, 0, 0, 9]);
+    }
+
+    #[test]
+    fn a_column_spans_its_burst() {
+        let mut trace = Trace::new();
+        for _ in 0..6 {
+            burst(&mut trace, [2048; PER_COLUMN]);
+        }
+        burst(&mut trace, [100, 3000, 1000, 900]);
+        let column = trace.frame().map(|line| line[4]);
+        assert_eq!(column, [0, 9, 9, 9, 9]);
+        assert_eq!(trace.frame().map(|line| line[0]), [0, 0, 9, 0, 0]);
+    }
+
+    #[test]
+    fn the_timebase_stops_at_both_ends() {
+        let mut trace = Trace::new();
+        assert!(!trace.step(true));
+        assert_eq!(trace.interval_us(), 250);
+        while trace.step(false) {}
+        assert_eq!(trace.interval_us(), 20000);
+    }
+}
@@ -0,0 +1,158 @@
+//! A high-score table of the top `ENTRIES` scores, its stored form and the initials entry.
+//!
+//! Where a table is stored is up to the caller, `encode` and `decode` turn it into bytes and
+//! back: three initials and a little-endian u32 per entry, empty places zeroed.
+
+use core::fmt::Write;
+use crate::font;
+use crate::scroll::Text;
+use crate::Frame;
+
+pub const ENTRIES : usize = 3;
+const ENTRY_LEN : usize = 7;
+pub const TABLE_LEN : usize = ENTRIES * ENTRY_LEN;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry {
+    pub initials : [u8; 3],
+    pub score    : u32,
+}
+
+pub type Table = [Option<Entry>; ENTRIES];
+
+/// The table in `bytes`, which may be cut short or empty.
+pub fn decode(bytes : &[u8]) -> Table {
+    let mut table = [None; ENTRIES];
+    for (entry, bytes) in table.iter_mut().zip(bytes.as_chunks::<ENTRY_LEN>().0) {
+        // NOTE: empty places are stored with zeroed initials
+        if bytes[0] != 0 {
+            *entry = Some(Entry {
+                initials : [bytes[0], bytes[1], bytes[2]],
+                score    : u32::from_le_bytes([bytes[3], bytes[4], bytes[5], bytes[6]]),
+            });
+        }
+    }
+    table
+}
+
+pub fn encode(table : &Table) -> [u8; TABLE_LEN] {
+    let mut bytes = [0; TABLE_LEN];
+    for (entry, bytes) in table.iter().zip(bytes.as_chunks_mut::<ENTRY_LEN>().0) {
+        if let Some(entry) = entry {
+            bytes[..3].copy_from_slice(&entry.initials);
+            bytes[3..].copy_from_slice(&entry.score.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// The place, counted from 0, `score` would take in the table.
+pub fn place(table : &Table, score : u32) -> Option<usize> {
+    table.iter().position(|entry| entry.is_none_or(|entry| score > entry.score))
+}
+
+/// Enters the score, returns its place or None when it doesn't make the table.
+pub fn insert(table : &mut Table, initials : [u8; 3], score : u32) -> Option<usize> {
+    let place = place(table, score)?;
+    table[place..].rotate_right(1);
+    table[place] = Some(Entry { initials, score });
+    Some(place)
+}
+
+/// `1 ABC 120 2 XYZ 80 3 --- 0`
+pub fn text(table : &Table) -> Text {
+    let mut text = Text::new();
+    for (place, entry) in table.iter().enumerate() {
+        let (initials, score) = match entry {
+            Some(entry) => (core::str::from_utf8(&entry.initials).unwrap_or("???"), entry.score),
+            None => ("---", 0),
+        };
+        let _ = write!(text, "{}{} {} {}", if place == 0 { "" } else { " " }, place + 1, initials, score);
+    }
+    text
+}
+
+/// Three initials picked one letter at a time with the buttons.
+#[derive(Clone, Copy)]
+pub struct InitialsEntry {
+    initials : [u8; 3],
+    position : usize,
+}
+
+impl Default for InitialsEntry {
+    fn default() -> Self {
+        InitialsEntry { initials : *b"AAA", position : 0 }
+    }
+}
+
+impl InitialsEntry {
+    /// Button A, steps the current letter through the alphabet.
+    pub fn next_letter(&mut self) {
+        let letter = &mut self.initials[self.position];
+        *letter = if *letter == b'Z' { b'A' } else { *letter + 1 };
+    }
+
+    /// Button B, keeps the current letter, returns the initials after the third one.
+    pub fn confirm(&mut self) -> Option<[u8; 3]> {
+        self.position += 1;
+        if self.position == self.initials.len() {
+            return Some(self.initials);
+        }
+        None
+    }
+
+    pub fn frame(&self) -> Frame {
+        font::frame_of(self.initials[self.position.min(2)] as char)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(scores : &[u32]) -> Table {
+        let mut table = [None; ENTRIES];
+        for score in scores {
+            insert(&mut table, *b"ABC", *score);
+        }
+        table
+    }
+
+    #[test]
+    fn insert_keeps_the_table_sorted() {
+        let table = table(&[10, 30, 20, 5]);
+        let scores : Vec<_> = table.iter().map(|entry| entry.map(|entry| entry.score)).collect();
+        assert_eq!(scores, [Some(30), Some(20), Some(10)]);
+    }
+
+    #[test]
+    fn a_tie_does_not_push_out_the_older_score() {
+        let table = table(&[30, 20, 10]);
+        assert_eq!(place(&table, 10), None);
+        assert_eq!(place(&table, 20), Some(2));
+    }
+
+    #[test]
+    fn stored_form_round_trips() {
+        let table = table(&[70_000, 3]);
+        assert_eq!(decode(&encode(&table)), table);
+        assert_eq!(decode(&[]), [None; ENTRIES]);
+    }
+
+    #[test]
+    fn text_fills_the_empty_places() {
+        assert_eq!(text(&table(&[120])).as_str(), "1 ABC 120 2 --- 0 3 --- 0");
+    }
+
+    #[test]
+    fn initials_wrap_and_confirm_after_three() {
+        let mut entry = InitialsEntry::default();
+        for _ in 0..26 {
+            entry.next_letter();
+        }
+        assert_eq!(entry.confirm(), None);
+        entry.next_letter();
+        assert_eq!(entry.confirm(), None);
+        assert_eq!(entry.confirm(), Some(*b"ABA"));
+    }
+}
@@ -0,0 +1,254 @@
+//! Scrolling text for the LED matrix: `frames` turns a string into the frames that move it
+//! in from the right and out to the left one column at a time, the caller shows each frame
+//! for as long as a column step should take.
+//!
+//! `frames_towards` moves it any other way: to the right it comes in from the left, so the
+//! end of the text leads, up and down the characters stand on top of each other and move a
+//! row at a time. A `Style` bundles the direction with the step time and how often `play`
+//! goes through the message.
+
+use core::str::Chars;
+use crate::font::{self, Glyph};
+use crate::FrameSink;
+
+pub use crate::Frame;
+
+pub const TEXT_LEN : usize = 48;
+// NOTE: what the scroll_text task takes
+pub type Text = heapless::String<TEXT_LEN>;
+
+// NOTE: how long the display tasks show each frame, about six characters a second
+pub const STEP_MS : u32 = 90;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+const DIRECTIONS : [Direction; 4] = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
+
+impl Direction {
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Left  => "left",
+            Direction::Right => "right",
+            Direction::Up    => "up",
+            Direction::Down  => "down",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Direction> {
+        DIRECTIONS.into_iter().find(|direction| direction.name() == name)
+    }
+
+    /// The direction stored as `direction as u8`.
+    pub fn from_u8(value : u8) -> Option<Direction> {
+        DIRECTIONS.get(value as usize).copied()
+    }
+
+    fn vertical(self) -> bool {
+        matches!(self, Direction::Up | Direction::Down)
+    }
+
+    // NOTE: the text comes in from its end, and the window fills from the other side
+    fn backwards(self) -> bool {
+        matches!(self, Direction::Right | Direction::Down)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Style {
+    pub step_ms   : u32,
+    pub direction : Direction,
+    // NOTE: how often the message goes by, 0 counts as once
+    pub repeat    : u8,
+}
+
+impl Style {
+    pub const DEFAULT : Style = Style { step_ms : STEP_MS, direction : Direction::Left, repeat : 1 };
+}
+
+pub struct Frames<'a> {
+    chars     : Chars<'a>,
+    direction : Direction,
+    glyph     : Glyph,
+    // NOTE: of the glyph in the direction of travel, its width or, going up or down, 5 rows
+    length    : usize,
+    line      : usize,
+    // NOTE: blank lines still to scroll in after the last glyph
+    trailing  : usize,
+    // NOTE: the visible lines, one bit per row for a column or the glyph bits of a row
+    window    : [u8; 5],
+}
+
+pub fn frames(text : &str) -> Frames<'_> {
+    frames_towards(text, Direction::Left)
+}
+
+pub fn frames_towards(text : &str, direction : Direction) -> Frames<'_> {
+    Frames {
+        chars    : text.chars(),
+        direction,
+        glyph    : [0; 5],
+        length   : 0,
+        line     : 0,
+        trailing : 5,
+        window   : [0; 5],
+    }
+}
+
+/// Scrolls `text` across `sink` the way `style` says.
+pub fn play(text : &str, style : &Style, sink : &mut impl FrameSink) {
+    for _ in 0..style.repeat.max(1) {
+        for frame in frames_towards(text, style.direction) {
+            sink.show(frame, style.step_ms);
+        }
+    }
+}
+
+/// The frame of `text` going by once at full brightness, `elapsed_ms` into it, with the step
+/// time to show it for. None once it went by. For the games polling faster than a step.
+pub fn at(text : &str, elapsed_ms : u64) -> Option<(Frame, u32)> {
+    let column = elapsed_ms / STEP_MS as u64;
+    frames(text).nth(column as usize).map(|frame| (frame.map(|row| row.map(|led| led * 9)), STEP_MS))
+}
+
+impl Frames<'_> {
+    fn next_line(&mut self) -> Option<u8> {
+        // NOTE: one blank line between glyphs
+        if self.line > self.length {
+            let c = if self.direction.backwards() { self.chars.next_back() } else { self.chars.next() };
+            match c {
+                Some(c) => {
+                    self.glyph = font::glyph_of(c);
+                    self.length = if self.direction.vertical() { 5 } else { font::width(&self.glyph) };
+                    self.line = 0;
+                }
+                None if self.trailing > 0 => {
+                    self.trailing -= 1;
+                    return Some(0);
+                }
+                None => return None,
+            }
+        }
+
+        let line = if self.line < self.length {
+            let line = if self.direction.backwards() { self.length - 1 - self.line } else { self.line };
+            if self.direction.vertical() {
+                self.glyph[line]
+            } else {
+                self.glyph.iter().enumerate().fold(0, |bits, (row, glyph_row)| {
+                    bits | ((glyph_row >> (4 - line)) & 1) << row
+                })
+            }
+        } else {
+            0
+        };
+        self.line += 1;
+        Some(line)
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        let line = self.next_line()?;
+        if self.direction.backwards() {
+            self.window.rotate_right(1);
+            self.window[0] = line;
+        } else {
+            self.window.rotate_left(1);
+            self.window[4] = line;
+        }
+
+        let mut frame = [[0; 5]; 5];
+        for (row, leds) in frame.iter_mut().enumerate() {
+            for (column, led) in leds.iter_mut().enumerate() {
+                *led = if self.direction.vertical() {
+                    (self.window[row] >> (4 - column)) & 1
+                } else {
+                    (self.window[column] >> row) & 1
+                };
+            }
+        }
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_scrolls_in_and_out_again() {
+        let frames : Vec<Frame> = frames("HI").collect();
+        assert_eq!(frames.first(), Some(&[[0; 5]; 5]));
+        assert_eq!(frames.last(), Some(&[[0; 5]; 5]));
+        // NOTE: a column at a time, so for a step the H fills the display up to its gap
+        assert!(frames.contains(&font::frame_of('H')));
+    }
+
+    #[test]
+    fn at_is_the_frame_of_the_step() {
+        let count = frames("HI").count() as u64;
+        let (frame, step_ms) = at("HI", 4 * STEP_MS as u64 + 1).unwrap();
+        assert_eq!(frame, frames("HI").nth(4).unwrap().map(|row| row.map(|led| led * 9)));
+        assert_eq!(step_ms, STEP_MS);
+        assert!(at("HI", (count - 1) * STEP_MS as u64).is_some());
+        assert!(at("HI", count * STEP_MS as u64).is_none());
+    }
+
+    #[test]
+    fn play_shows_every_frame_for_a_step() {
+        let mut shown = Vec::new();
+        play("A", &Style::DEFAULT, &mut |frame, duration_ms| shown.push((frame, duration_ms)));
+        assert_eq!(shown.len(), frames("A").count());
+        assert!(shown.iter().all(|&(_, duration_ms)| duration_ms == STEP_MS));
+    }
+
+    #[test]
+    fn play_repeats_at_the_style_step() {
+        let mut shown = Vec::new();
+        let style = Style { step_ms : 40, direction : Direction::Up, repeat : 3 };
+        play("A", &style, &mut |frame, duration_ms| shown.push((frame, duration_ms)));
+        assert_eq!(shown.len(), 3 * frames_towards("A", Direction::Up).count());
+        assert!(shown.iter().all(|&(_, duration_ms)| duration_ms == 40));
+    }
+
+    #[test]
+    fn every_direction_passes_the_glyph() {
+        for direction in DIRECTIONS {
+            let frames : Vec<Frame> = frames_towards("T", direction).collect();
+            assert_eq!(frames.last(), Some(&[[0; 5]; 5]));
+            assert!(frames.contains(&font::frame_of('T')), "{:?}", direction);
+        }
+    }
+
+    #[test]
+    fn right_brings_the_end_in_first() {
+        // NOTE: going right the first glyph in is the I, its column is lit on the left
+        let second : Frame = frames_towards("HI", Direction::Right).nth(1).unwrap();
+        assert_eq!(second.map(|row| row[0]), [1, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn up_moves_a_row_at_a_time() {
+        let frames : Vec<Frame> = frames_towards("-", Direction::Up).collect();
+        // NOTE: the bar of the - is its middle row, it comes up from the bottom
+        let at = frames.iter().position(|frame| frame[4] == [1, 1, 1, 0, 0]).unwrap();
+        assert_eq!(frames[at + 1][3], [1, 1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn direction_names_round_trip() {
+        for direction in DIRECTIONS {
+            assert_eq!(Direction::from_name(direction.name()), Some(direction));
+            assert_eq!(Direction::from_u8(direction as u8), Some(direction));
+        }
+        assert_eq!(Direction::from_u8(4), None);
+    }
+}
@@ -0,0 +1,297 @@
+//! Shooter: a ship on the bottom row clears the invaders coming down at it.
+//!
+//! The ship moves a column at a time, or keeps moving while the board is tilted, and fires
+//! up its column. Every time the invaders step down a new one appears in the top row, and
+//! they step faster the more are shot down. The game ends when one reaches the bottom row,
+//! the invaders shot down are the score.
+
+use core::fmt::Write;
+use crate::board::Beeper;
+use crate::scroll::{self, Text};
+use crate::{Clock, Frame, Random};
+
+// NOTE: the field is kept as bit masks of the 25 LEDs, bit 5 * row + column, so moving
+// everything a row up or down is a shift
+const BOTTOM : u32 = 0b11111 << 20;
+
+const START_MS : u64 = 1500;
+const FASTER_MS : u64 = 40;
+const MIN_MS : u64 = 400;
+const SHOT_MS : u64 = 80;
+const STEER_MS : u64 = 150;
+const TILT_MG : i32 = 250;
+const BOOM_MS : u64 = 120;
+const BOOM_HZ : u32 = 400;
+const OVER_MS : u64 = 1500;
+const OVER_HZ : u32 = 220;
+const INVADER : u8 = 4;
+const POLL_MS : u32 = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+    Left,
+    Right,
+    Fire,
+    // NOTE: ends the game as if an invader got through
+    Quit,
+    // NOTE: the sideways tilt in mg, past `TILT_MG` the ship keeps moving that way
+    Tilt(i32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    Playing { descended : u64, shots_moved : u64, steered : u64 },
+    Over { at : u64 },
+}
+
+#[derive(Clone, Copy)]
+pub struct Game {
+    ship     : usize,
+    invaders : u32,
+    shots    : u32,
+    score    : u32,
+    tilt     : i32,
+    // NOTE: when the last invader was shot down, for the sound
+    boom     : Option<u64>,
+    phase    : Phase,
+}
+
+const fn bit(row : usize, column : usize) -> u32 {
+    1 << (5 * row + column)
+}
+
+fn descend_ms(score : u32) -> u64 {
+    START_MS.saturating_sub(FASTER_MS * score as u64).max(MIN_MS)
+}
+
+impl Game {
+    pub fn new(clock : &impl Clock, random : &mut impl Random) -> Self {
+        let now = clock.now_ms();
+        Game {
+            ship     : 2,
+            invaders : bit(0, random.below(5) as usize),
+            shots    : 0,
+            score    : 0,
+            tilt     : 0,
+            boom     : None,
+            phase    : Phase::Playing { descended : now, shots_moved : now, steered : now },
+        }
+    }
+
+    /// The invaders shot down.
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    fn over(&mut self, now : u64, beeper : &mut impl Beeper) {
+        beeper.tone(OVER_HZ);
+        self.phase = Phase::Over { at : now };
+    }
+
+    /// Takes `control`, false once the game is over.
+    pub fn control(&mut self, control : Control, clock : &impl Clock, beeper : &mut impl Beeper) -> bool {
+        let Phase::Playing { .. } = self.phase else {
+            return false;
+        };
+        match control {
+            Control::Left    => self.ship = self.ship.saturating_sub(1),
+            Control::Right   => self.ship = (self.ship + 1).min(4),
+            Control::Fire    => self.shots |= bit(3, self.ship),
+            Control::Quit    => self.over(clock.now_ms(), beeper),
+            Control::Tilt(x) => self.tilt = x,
+        }
+        true
+    }
+
+    /// Moves the ship, the shots and the invaders on to now, true when an invader just reached
+    /// the bottom row and ended the game.
+    pub fn advance(&mut self, clock : &impl Clock, random : &mut impl Random, beeper : &mut impl Beeper) -> bool {
+        let Phase::Playing { mut descended, mut shots_moved, mut steered } = self.phase else {
+            return false;
+        };
+        let now = clock.now_ms();
+        if self.tilt.abs() > TILT_MG && now.saturating_sub(steered) >= STEER_MS {
+            self.ship = if self.tilt > 0 { (self.ship + 1).min(4) } else { self.ship.saturating_sub(1) };
+            steered = now;
+        }
+        if now.saturating_sub(shots_moved) >= SHOT_MS {
+            self.shots >>= 5;
+            shots_moved = now;
+        }
+        if now.saturating_sub(descended) >= descend_ms(self.score) {
+            self.invaders <<= 5;
+            if self.invaders & BOTTOM != 0 {
+                self.over(now, beeper);
+                return true;
+            }
+            self.invaders |= bit(0, random.below(5) as usize);
+            descended = now;
+        }
+
+        let hits = self.shots & self.invaders;
+        if hits != 0 {
+            self.invaders &= !hits;
+            self.shots &= !hits;
+            self.score += hits.count_ones();
+            self.boom = Some(now);
+        }
+        self.phase = Phase::Playing { descended, shots_moved, steered };
+        false
+    }
+
+    fn field(&self) -> Frame {
+        let mut leds = [[0; 5]; 5];
+        for (row, leds) in leds.iter_mut().enumerate() {
+            for (column, led) in leds.iter_mut().enumerate() {
+                if self.shots & bit(row, column) != 0 {
+                    *led = 9;
+                } else if self.invaders & bit(row, column) != 0 {
+                    *led = INVADER;
+                }
+            }
+        }
+        leds[4][self.ship] = 9;
+        leds
+    }
+
+    /// The frame for now with the time to show it for, None once the score went by at the
+    /// end of the game.
+    pub fn frame(&self, clock : &impl Clock, beeper : &mut impl Beeper) -> Option<(Frame, u32)> {
+        let now = clock.now_ms();
+        let frame = match self.phase {
+            Phase::Playing { .. } => {
+                // NOTE: a short falling tone for every invader shot down
+                match self.boom.map(|boom| now.saturating_sub(boom)) {
+                    Some(since) if since < BOOM_MS => beeper.tone(BOOM_HZ - 2 * since as u32),
+                    _ => beeper.off(),
+                }
+                self.field()
+            }
+            Phase::Over { at } => {
+                let elapsed = now.saturating_sub(at);
+                if elapsed >= OVER_MS {
+                    beeper.off();
+                    let mut text = Text::new();
+                    let _ = write!(text, "score {}", self.score);
+                    return scroll::at(&text, elapsed - OVER_MS);
+                }
+                beeper.tone(OVER_HZ.saturating_sub(elapsed as u32 / 10));
+                let mut leds = self.field();
+                // NOTE: the ship blows up
+                let level = if (elapsed / 150).is_multiple_of(2) { 9 } else { 0 };
+                let wreck = self.ship.saturating_sub(1)..=(self.ship + 1).min(4);
+                for (column, led) in leds[4].iter_mut().enumerate() {
+                    if wreck.contains(&column) {
+                        *led = level;
+                    }
+                }
+                leds[3][self.ship] = level;
+                leds
+            }
+        };
+        Some((frame, POLL_MS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use super::*;
+
+    struct Ticks(Cell<u64>);
+
+    impl Clock for Ticks {
+        fn now_ms(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    impl Ticks {
+        fn wait(&self, ms : u64) {
+            self.0.set(self.0.get() + ms);
+        }
+    }
+
+    // NOTE: always the middle column
+    struct Middle;
+
+    impl Random for Middle {
+        fn below(&mut self, n : u32) -> u32 {
+            n / 2
+        }
+    }
+
+    struct Tone(u32);
+
+    impl Beeper for Tone {
+        fn tone(&mut self, hz : u32) {
+            self.0 = hz;
+        }
+    }
+
+    #[test]
+    fn an_invader_reaching_the_bottom_ends_the_game() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut game = Game::new(&clock, &mut Middle);
+        game.control(Control::Left, &clock, &mut beeper);
+        for _ in 0..3 {
+            clock.wait(START_MS);
+            assert!(!game.advance(&clock, &mut Middle, &mut beeper));
+        }
+        assert_eq!(game.field()[3][2], INVADER);
+        clock.wait(START_MS);
+        assert!(game.advance(&clock, &mut Middle, &mut beeper));
+        assert_eq!(beeper.0, OVER_HZ);
+        assert!(!game.control(Control::Fire, &clock, &mut beeper));
+
+        clock.wait(OVER_MS);
+        assert!(game.frame(&clock, &mut beeper).is_some());
+        clock.wait(scroll::frames("score 0").count() as u64 * scroll::STEP_MS as u64);
+        assert_eq!(game.frame(&clock, &mut beeper), None);
+    }
+
+    #[test]
+    fn a_shot_clears_the_invader_it_meets() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut game = Game::new(&clock, &mut Middle);
+        clock.wait(START_MS);
+        game.advance(&clock, &mut Middle, &mut beeper);
+        assert!(game.control(Control::Fire, &clock, &mut beeper));
+        // NOTE: fired from row 3, the shot meets the invader in row 1 two steps on
+        for _ in 0..2 {
+            clock.wait(SHOT_MS);
+            game.advance(&clock, &mut Middle, &mut beeper);
+        }
+        assert_eq!(game.score(), 1);
+        assert_eq!(game.invaders, bit(0, 2));
+        assert_eq!(game.shots, 0);
+        game.frame(&clock, &mut beeper);
+        assert_eq!(beeper.0, BOOM_HZ);
+        clock.wait(BOOM_MS);
+        game.frame(&clock, &mut beeper);
+        assert_eq!(beeper.0, 0);
+    }
+
+    #[test]
+    fn tilting_keeps_the_ship_moving() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut game = Game::new(&clock, &mut Middle);
+        game.control(Control::Tilt(TILT_MG + 1), &clock, &mut beeper);
+        for _ in 0..4 {
+            clock.wait(STEER_MS);
+            game.advance(&clock, &mut Middle, &mut beeper);
+        }
+        assert_eq!(game.ship, 4);
+        game.control(Control::Tilt(-TILT_MG), &clock, &mut beeper);
+        clock.wait(STEER_MS);
+        game.advance(&clock, &mut Middle, &mut beeper);
+        assert_eq!(game.ship, 4);
+    }
+
+    #[test]
+    fn invaders_step_faster_the_more_are_down() {
+        assert_eq!(descend_ms(0), START_MS);
+        assert_eq!(descend_ms(5), START_MS - 5 * FASTER_MS);
+        assert_eq!(descend_ms(100), MIN_MS);
+    }
+}
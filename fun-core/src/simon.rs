@@ -0,0 +1,292 @@
+//! Simon: the board plays a growing sequence of quadrants, each with its own tone, and the
+//! player repeats it.
+//!
+//! The quadrants are the corners of the display, 0 to 3 from the top left to the bottom
+//! right, the firmware's simon.rs answers each with its own input. Every round adds one to
+//! the sequence and plays it a little faster, a wrong answer or a long wait ends the game.
+//! The rounds completed are the score.
+
+use core::fmt::Write;
+use crate::board::Beeper;
+use crate::scroll::{self, Text};
+use crate::{Clock, Frame, Random};
+
+pub const MAX_LEN : usize = 32;
+// NOTE: the tones of the original game, one per quadrant
+const TONES : [u32; 4] = [415, 310, 252, 209];
+const FAIL_HZ : u32 = 110;
+
+// NOTE: how long a quadrant of the sequence is lit in the first round, each round takes
+// STEP_MS off down to MIN_ON_MS
+const ON_MS : u64 = 450;
+const STEP_MS : u64 = 20;
+const MIN_ON_MS : u64 = 180;
+const GAP_MS : u64 = 100;
+const ECHO_MS : u64 = 250;
+const ANSWER_MS : u64 = 3000;
+const PAUSE_MS : u64 = 800;
+const OVER_MS : u64 = 1500;
+const FAIL_TONE_MS : u64 = 600;
+const POLL_MS : u32 = 10;
+
+const CROSS : Frame = [
+    [9, 0, 0, 0, 9],
+    [0, 9, 0, 9, 0],
+    [0, 0, 9, 0, 0],
+    [0, 9, 0, 9, 0],
+    [9, 0, 0, 0, 9],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    // NOTE: the sequence plays from `at` on
+    Showing { at : u64 },
+    // NOTE: `at` is the time of the last answer, or the end of the sequence
+    Answering { index : usize, at : u64, echo : Option<usize> },
+    Passed { at : u64, echo : Option<usize> },
+    Over { at : u64 },
+}
+
+#[derive(Clone, Copy)]
+pub struct Game {
+    sequence  : [u8; MAX_LEN],
+    len       : usize,
+    completed : usize,
+    phase     : Phase,
+}
+
+fn on_ms(len : usize) -> u64 {
+    ON_MS.saturating_sub(STEP_MS * len as u64).max(MIN_ON_MS)
+}
+
+fn quadrant(quadrant : usize) -> Frame {
+    let mut leds = [[0; 5]; 5];
+    let (rows, columns) = match quadrant {
+        0 => (0..2, 0..2),
+        1 => (0..2, 3..5),
+        2 => (3..5, 0..2),
+        _ => (3..5, 3..5),
+    };
+    for row in rows {
+        for column in columns.clone() {
+            leds[row][column] = 9;
+        }
+    }
+    leds
+}
+
+fn echo(echo : Option<usize>, since_ms : u64, beeper : &mut impl Beeper) -> Frame {
+    match echo {
+        Some(lit) if since_ms < ECHO_MS => quadrant(lit),
+        _ => {
+            beeper.off();
+            [[0; 5]; 5]
+        }
+    }
+}
+
+impl Game {
+    /// A game with its whole sequence drawn. It starts as if a round of none was passed, the
+    /// pause leads into the first one.
+    pub fn new(clock : &impl Clock, random : &mut impl Random) -> Self {
+        let mut sequence = [0; MAX_LEN];
+        for quadrant in sequence.iter_mut() {
+            *quadrant = random.below(4) as u8;
+        }
+        Game { sequence, len : 0, completed : 0, phase : Phase::Passed { at : clock.now_ms(), echo : None } }
+    }
+
+    /// The rounds completed.
+    pub fn score(&self) -> u32 {
+        self.completed as u32
+    }
+
+    /// Takes `quadrant` as the next answer, false when the game isn't waiting for one.
+    pub fn answer(&mut self, quadrant : usize, clock : &impl Clock, beeper : &mut impl Beeper) -> bool {
+        let Phase::Answering { index, .. } = self.phase else {
+            return false;
+        };
+        let now = clock.now_ms();
+        self.phase = if self.sequence[index] as usize != quadrant {
+            beeper.tone(FAIL_HZ);
+            Phase::Over { at : now }
+        } else if index + 1 == self.len {
+            beeper.tone(TONES[quadrant]);
+            self.completed = self.len;
+            Phase::Passed { at : now, echo : Some(quadrant) }
+        } else {
+            beeper.tone(TONES[quadrant]);
+            Phase::Answering { index : index + 1, at : now, echo : Some(quadrant) }
+        };
+        true
+    }
+
+    /// Moves the game on to now, from the sequence to the answers, past a long wait to the end
+    /// and from the pause after a round to the next.
+    pub fn advance(&mut self, clock : &impl Clock, beeper : &mut impl Beeper) {
+        let now = clock.now_ms();
+        self.phase = match self.phase {
+            Phase::Showing { at } if now.saturating_sub(at) >= self.len as u64 * (on_ms(self.len) + GAP_MS) =>
+                Phase::Answering { index : 0, at : now, echo : None },
+            Phase::Answering { at, .. } if now.saturating_sub(at) > ANSWER_MS => {
+                beeper.tone(FAIL_HZ);
+                Phase::Over { at : now }
+            }
+            Phase::Passed { at, .. } if now.saturating_sub(at) >= PAUSE_MS => {
+                if self.len == MAX_LEN {
+                    Phase::Over { at : now }
+                } else {
+                    self.len += 1;
+                    Phase::Showing { at : now }
+                }
+            }
+            phase => phase,
+        };
+    }
+
+    /// The frame for now with the time to show it for, None once the score went by at the
+    /// end of the game.
+    pub fn frame(&self, clock : &impl Clock, beeper : &mut impl Beeper) -> Option<(Frame, u32)> {
+        let now = clock.now_ms();
+        let frame = match self.phase {
+            Phase::Showing { at } => {
+                let slot = on_ms(self.len) + GAP_MS;
+                let elapsed = now.saturating_sub(at);
+                let lit = self.sequence[(elapsed / slot) as usize % self.len] as usize;
+                if elapsed % slot < on_ms(self.len) {
+                    beeper.tone(TONES[lit]);
+                    quadrant(lit)
+                } else {
+                    beeper.off();
+                    [[0; 5]; 5]
+                }
+            }
+            Phase::Answering { at, echo : lit, .. } => echo(lit, now.saturating_sub(at), beeper),
+            Phase::Passed { at, echo : lit } => echo(lit, now.saturating_sub(at), beeper),
+            Phase::Over { at } => {
+                let elapsed = now.saturating_sub(at);
+                if elapsed >= FAIL_TONE_MS {
+                    beeper.off();
+                }
+                if elapsed >= OVER_MS {
+                    let mut text = Text::new();
+                    let _ = write!(text, "score {}", self.completed);
+                    return scroll::at(&text, elapsed - OVER_MS);
+                }
+                if (elapsed / 150).is_multiple_of(2) { CROSS } else { [[0; 5]; 5] }
+            }
+        };
+        Some((frame, POLL_MS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use super::*;
+
+    struct Ticks(Cell<u64>);
+
+    impl Clock for Ticks {
+        fn now_ms(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    impl Ticks {
+        fn wait(&self, ms : u64) {
+            self.0.set(self.0.get() + ms);
+        }
+    }
+
+    // NOTE: counts up, so the sequence goes round the quadrants
+    struct Counter(u32);
+
+    impl Random for Counter {
+        fn below(&mut self, n : u32) -> u32 {
+            self.0 += 1;
+            self.0 % n
+        }
+    }
+
+    struct Tone(u32);
+
+    impl Beeper for Tone {
+        fn tone(&mut self, hz : u32) {
+            self.0 = hz;
+        }
+    }
+
+    /// Waits out the pause and the sequence, until the answers are taken.
+    fn to_answers(game : &mut Game, clock : &Ticks, beeper : &mut Tone) {
+        clock.wait(PAUSE_MS);
+        game.advance(clock, beeper);
+        clock.wait(game.len as u64 * (on_ms(game.len) + GAP_MS));
+        game.advance(clock, beeper);
+    }
+
+    #[test]
+    fn a_round_plays_the_sequence_then_takes_it_back() {
+        let (clock, mut beeper) = (Ticks(Cell::new(1000)), Tone(0));
+        let mut game = Game::new(&clock, &mut Counter(0));
+        assert_eq!(game.sequence[..4], [1, 2, 3, 0]);
+        clock.wait(PAUSE_MS);
+        game.advance(&clock, &mut beeper);
+        assert_eq!(game.frame(&clock, &mut beeper), Some((quadrant(1), POLL_MS)));
+        assert_eq!(beeper.0, TONES[1]);
+        assert!(!game.answer(1, &clock, &mut beeper));
+
+        clock.wait(on_ms(1) + GAP_MS);
+        game.advance(&clock, &mut beeper);
+        assert!(game.answer(1, &clock, &mut beeper));
+        assert_eq!(game.score(), 1);
+        to_answers(&mut game, &clock, &mut beeper);
+        assert_eq!(game.len, 2);
+        assert!(game.answer(1, &clock, &mut beeper));
+        assert_eq!(game.score(), 1);
+        assert!(game.answer(2, &clock, &mut beeper));
+        assert_eq!(game.score(), 2);
+        assert!(!game.answer(3, &clock, &mut beeper));
+    }
+
+    #[test]
+    fn a_wrong_answer_ends_the_game() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut game = Game::new(&clock, &mut Counter(0));
+        to_answers(&mut game, &clock, &mut beeper);
+        game.answer(1, &clock, &mut beeper);
+        to_answers(&mut game, &clock, &mut beeper);
+        assert!(game.answer(3, &clock, &mut beeper));
+        assert_eq!(beeper.0, FAIL_HZ);
+        assert_eq!(game.frame(&clock, &mut beeper), Some((CROSS, POLL_MS)));
+
+        // NOTE: then the score goes by, and the game is over
+        clock.wait(OVER_MS);
+        assert!(game.frame(&clock, &mut beeper).is_some());
+        assert_eq!(beeper.0, 0);
+        clock.wait(scroll::frames("score 1").count() as u64 * scroll::STEP_MS as u64);
+        assert_eq!(game.frame(&clock, &mut beeper), None);
+        assert_eq!(game.score(), 1);
+    }
+
+    #[test]
+    fn a_long_wait_ends_the_game() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut game = Game::new(&clock, &mut Counter(0));
+        to_answers(&mut game, &clock, &mut beeper);
+        clock.wait(ANSWER_MS);
+        game.advance(&clock, &mut beeper);
+        assert!(matches!(game.phase, Phase::Answering { .. }));
+        clock.wait(1);
+        game.advance(&clock, &mut beeper);
+        assert_eq!(game.phase, Phase::Over { at : clock.now_ms() });
+        assert_eq!(beeper.0, FAIL_HZ);
+    }
+
+    #[test]
+    fn every_round_plays_faster() {
+        assert_eq!(on_ms(1), ON_MS - STEP_MS);
+        assert!(on_ms(5) < on_ms(4));
+        assert_eq!(on_ms(MAX_LEN), MIN_ON_MS);
+    }
+}
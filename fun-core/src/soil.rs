@@ -0,0 +1,145 @@
+//! Soil moisture from a resistive or capacitive probe read by an ADC: the median of a burst
+//! of samples, scaled between two calibration points, and whether the plant is dry.
+//!
+//! The calibration is the raw reading of the probe in dry soil and in wet soil, whichever
+//! is higher, so probes reading up and probes reading down with the moisture both work.
+//! The plant is dry below `DRY_PERCENT` and stays dry until it is back above
+//! `WATERED_PERCENT`.
+
+use crate::Frame;
+
+pub const DRY_PERCENT : u8 = 25;
+const WATERED_PERCENT : u8 = 35;
+
+// NOTE: the LEDs of the droplet, the rows fill from the bottom with the moisture
+const DROPLET : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+    [1, 1, 1, 1, 1],
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+];
+const OUTLINE_LEVEL : u8 = 2;
+
+/// The median of `samples`, which it sorts, 0 for none.
+pub fn median(samples : &mut [u16]) -> u16 {
+    samples.sort_unstable();
+    samples.get(samples.len() / 2).copied().unwrap_or(0)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Calibration {
+    pub dry : u16,
+    pub wet : u16,
+}
+
+impl Calibration {
+    // NOTE: a resistive probe between 3 V and the pin, read with the 3.6 V full scale of 12 bits
+    pub const DEFAULT : Calibration = Calibration { dry : 0, wet : 3400 };
+
+    /// The moisture of `raw` in percent, clamped to 0-100, 0 when both points are the same.
+    pub fn percent(&self, raw : u16) -> u8 {
+        let span = self.wet as i32 - self.dry as i32;
+        if span == 0 {
+            return 0;
+        }
+        ((raw as i32 - self.dry as i32) * 100 / span).clamp(0, 100) as u8
+    }
+
+    pub fn encode(&self) -> [u8; 4] {
+        let mut bytes = [0; 4];
+        bytes[..2].copy_from_slice(&self.dry.to_le_bytes());
+        bytes[2..].copy_from_slice(&self.wet.to_le_bytes());
+        bytes
+    }
+
+    pub fn decode(bytes : &[u8]) -> Option<Calibration> {
+        let bytes : &[u8; 4] = bytes.try_into().ok()?;
+        Some(Calibration {
+            dry : u16::from_le_bytes([bytes[0], bytes[1]]),
+            wet : u16::from_le_bytes([bytes[2], bytes[3]]),
+        })
+    }
+}
+
+pub struct Watch {
+    dry : bool,
+}
+
+impl Watch {
+    pub const fn new() -> Self {
+        Watch { dry : false }
+    }
+
+    /// True when `percent` just made the plant dry.
+    pub fn check(&mut self, percent : u8) -> bool {
+        let was = self.dry;
+        self.dry = if was { percent < WATERED_PERCENT } else { percent < DRY_PERCENT };
+        self.dry && !was
+    }
+
+    pub fn dry(&self) -> bool {
+        self.dry
+    }
+}
+
+impl Default for Watch {
+    fn default() -> Self {
+        Watch::new()
+    }
+}
+
+/// The droplet, dim, lit up from the bottom a row per 20 % of `percent`.
+pub fn droplet(percent : u8) -> Frame {
+    let rows = (percent as usize * 5).div_ceil(100);
+    let mut frame = DROPLET.map(|row| row.map(|led| led * OUTLINE_LEVEL));
+    for row in frame.iter_mut().rev().take(rows) {
+        for led in row.iter_mut().filter(|led| **led > 0) {
+            *led = 9;
+        }
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_median_leaves_a_spike_out() {
+        assert_eq!(median(&mut [500, 510, 4000, 505, 0]), 505);
+        assert_eq!(median(&mut []), 0);
+    }
+
+    #[test]
+    fn percent_works_either_way_round() {
+        let rising = Calibration { dry : 1000, wet : 3000 };
+        assert_eq!(rising.percent(2000), 50);
+        assert_eq!(rising.percent(500), 0);
+        let falling = Calibration { dry : 3000, wet : 1000 };
+        assert_eq!(falling.percent(1500), 75);
+        assert_eq!(falling.percent(100), 100);
+        assert_eq!(Calibration { dry : 7, wet : 7 }.percent(7), 0);
+        assert_eq!(Calibration::decode(&rising.encode()), Some(rising));
+    }
+
+    #[test]
+    fn dry_is_told_once_until_watered() {
+        let mut watch = Watch::new();
+        assert!(!watch.check(40));
+        assert!(watch.check(20));
+        assert!(!watch.check(30));
+        assert!(watch.dry());
+        assert!(!watch.check(35));
+        assert!(watch.check(10));
+    }
+
+    #[test]
+    fn the_droplet_fills_from_the_bottom() {
+        let half = droplet(50);
+        assert_eq!(half[4], [0, 9, 9, 9, 0]);
+        assert_eq!(half[2], [9, 9, 9, 9, 9]);
+        assert_eq!(half[1], [0, OUTLINE_LEVEL, OUTLINE_LEVEL, OUTLINE_LEVEL, 0]);
+        assert_eq!(droplet(0)[4][2], OUTLINE_LEVEL);
+    }
+}
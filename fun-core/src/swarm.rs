@@ -0,0 +1,133 @@
+//! Animations every board of a class draws the same, in lockstep, for a light show.
+//!
+//! A frame is a function of the animation and the time since it started only, no state and
+//! no randomness, so boards that agree on the start and on the clock show the same frame at
+//! the same moment. Every animation loops over its `period_ms`.
+
+use crate::Frame;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Animation {
+    Pulse,
+    Strobe,
+    Spin,
+    Ripple,
+    Wave,
+}
+
+pub const ANIMATIONS : [Animation; 5] = [Animation::Pulse, Animation::Strobe, Animation::Spin, Animation::Ripple, Animation::Wave];
+
+// NOTE: the arms of the spinning line, a quarter turn each
+const ARMS : [[(usize, usize); 5]; 4] = [
+    [(0, 2), (1, 2), (2, 2), (3, 2), (4, 2)],
+    [(0, 4), (1, 3), (2, 2), (3, 1), (4, 0)],
+    [(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)],
+    [(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)],
+];
+
+impl Animation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Animation::Pulse  => "pulse",
+            Animation::Strobe => "strobe",
+            Animation::Spin   => "spin",
+            Animation::Ripple => "ripple",
+            Animation::Wave   => "wave",
+        }
+    }
+
+    /// The animation sent as `animation as u8`, None for one a newer firmware added.
+    pub fn from_u8(value : u8) -> Option<Animation> {
+        ANIMATIONS.get(value as usize).copied()
+    }
+
+    /// The one after this, round to the first again.
+    pub fn next(self) -> Animation {
+        ANIMATIONS[(self as usize + 1) % ANIMATIONS.len()]
+    }
+
+    pub fn period_ms(self) -> u64 {
+        match self {
+            Animation::Pulse  => 2000,
+            Animation::Strobe => 500,
+            Animation::Spin   => 800,
+            Animation::Ripple => 1200,
+            Animation::Wave   => 1000,
+        }
+    }
+}
+
+/// Up from 0 to 9 over the first half of `period`, down again over the second.
+fn triangle(t : u64, period : u64) -> u8 {
+    let t = t % period;
+    let up = if t < period / 2 { t } else { period - t };
+    (up * 18 / period).min(9) as u8
+}
+
+/// The frame of `animation` `elapsed_ms` after it started.
+pub fn frame(animation : Animation, elapsed_ms : u64) -> Frame {
+    let period = animation.period_ms();
+    let t = elapsed_ms % period;
+    let mut frame = [[0; 5]; 5];
+    match animation {
+        Animation::Pulse => frame = [[triangle(t, period); 5]; 5],
+        Animation::Strobe => if t < period / 5 { frame = [[9; 5]; 5] },
+        Animation::Spin => {
+            for (row, column) in ARMS[(t * 4 / period) as usize] {
+                frame[row][column] = 9;
+            }
+        }
+        Animation::Ripple => {
+            // NOTE: the ring out from the centre, the one inside it fading
+            let ring = (t * 3 / period) as usize;
+            for (row, leds) in frame.iter_mut().enumerate() {
+                for (column, led) in leds.iter_mut().enumerate() {
+                    let distance = row.abs_diff(2).max(column.abs_diff(2));
+                    *led = if distance == ring { 9 } else if distance + 1 == ring { 2 } else { 0 };
+                }
+            }
+        }
+        Animation::Wave => {
+            for leds in frame.iter_mut() {
+                for (column, led) in leds.iter_mut().enumerate() {
+                    *led = triangle(t + period - column as u64 * period / 5, period);
+                }
+            }
+        }
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animations_loop_over_their_period() {
+        for animation in ANIMATIONS {
+            for t in [0, 130, 499, 777] {
+                assert_eq!(frame(animation, t), frame(animation, t + 3 * animation.period_ms()));
+            }
+        }
+    }
+
+    #[test]
+    fn frames_move_on() {
+        assert_eq!(frame(Animation::Pulse, 0), [[0; 5]; 5]);
+        assert_eq!(frame(Animation::Pulse, 1000), [[9; 5]; 5]);
+        assert_eq!(frame(Animation::Strobe, 50), [[9; 5]; 5]);
+        assert_eq!(frame(Animation::Strobe, 300), [[0; 5]; 5]);
+        assert_eq!(frame(Animation::Spin, 0)[0][2], 9);
+        assert_eq!(frame(Animation::Spin, 400)[2][0], 9);
+        assert_eq!(frame(Animation::Ripple, 0)[2][2], 9);
+        assert_eq!(frame(Animation::Ripple, 900)[0], [9; 5]);
+        assert_ne!(frame(Animation::Wave, 0)[0][0], frame(Animation::Wave, 0)[0][2]);
+    }
+
+    #[test]
+    fn the_ids_go_round() {
+        assert_eq!(Animation::Wave.next(), Animation::Pulse);
+        assert_eq!(Animation::from_u8(Animation::Ripple as u8), Some(Animation::Ripple));
+        assert_eq!(Animation::from_u8(200), None);
+    }
+}
@@ -0,0 +1,207 @@
+//! The thermometer log: a sample of the temperature every minute, the last `HISTORY` of them,
+//! the lowest and the highest of each of the last `DAYS` days, and which way it is going.
+//!
+//! Temperatures are in the quarter degrees of the nRF's TEMP sensor. A day is
+//! `MINUTES_PER_DAY` samples, counted from when the log started, there is no wall clock to
+//! start it at midnight. The days go to flash as `encode` writes them, so they outlive a
+//! reset; the minutes don't, and a reset starts a new day.
+
+use core::fmt;
+use heapless::Deque;
+
+pub const HISTORY : usize = 60;
+pub const DAYS : usize = 7;
+pub const MINUTES_PER_DAY : u32 = 24 * 60;
+// NOTE: the trend is the change over this many minutes, a quarter degree is the sensor's noise
+const TREND_MINUTES : usize = 10;
+const TREND_QUARTERS : i16 = 2;
+
+pub const ENCODED_LEN : usize = 1 + 4 * DAYS;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Day {
+    pub min : i16,
+    pub max : i16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trend {
+    Rising,
+    Steady,
+    Falling,
+}
+
+pub struct Log {
+    // NOTE: oldest first, like the days
+    minutes : Deque<i16, HISTORY>,
+    days    : Deque<Day, DAYS>,
+    // NOTE: the samples of the last day so far, 0 starts a new one
+    in_day  : u32,
+}
+
+impl Log {
+    pub const fn new() -> Self {
+        Log { minutes : Deque::new(), days : Deque::new(), in_day : 0 }
+    }
+
+    /// Takes the sample of a minute, true when it changed the days, so they need storing.
+    pub fn record(&mut self, quarters : i16) -> bool {
+        if self.minutes.is_full() {
+            self.minutes.pop_front();
+        }
+        let _ = self.minutes.push_back(quarters);
+
+        let changed = match self.days.back_mut() {
+            Some(day) if self.in_day > 0 => {
+                let before = *day;
+                day.min = day.min.min(quarters);
+                day.max = day.max.max(quarters);
+                *day != before
+            }
+            _ => {
+                if self.days.is_full() {
+                    self.days.pop_front();
+                }
+                let _ = self.days.push_back(Day { min : quarters, max : quarters });
+                true
+            }
+        };
+        self.in_day = (self.in_day + 1) % MINUTES_PER_DAY;
+        changed
+    }
+
+    pub fn latest(&self) -> Option<i16> {
+        self.minutes.back().copied()
+    }
+
+    /// None until `TREND_MINUTES` have been sampled.
+    pub fn trend(&self) -> Option<Trend> {
+        let then = *self.minutes.iter().rev().nth(TREND_MINUTES)?;
+        let change = self.latest()? - then;
+        Some(if change >= TREND_QUARTERS {
+            Trend::Rising
+        } else if change <= -TREND_QUARTERS {
+            Trend::Falling
+        } else {
+            Trend::Steady
+        })
+    }
+
+    /// The samples of the last minutes, oldest first.
+    pub fn minutes(&self) -> impl Iterator<Item = i16> + '_ {
+        self.minutes.iter().copied()
+    }
+
+    /// The days, oldest first, the last one is today.
+    pub fn days(&self) -> impl Iterator<Item = Day> + '_ {
+        self.days.iter().copied()
+    }
+
+    pub fn today(&self) -> Option<Day> {
+        self.days.back().copied()
+    }
+
+    /// The days as they are stored, the count and then the lowest and highest of each.
+    pub fn encode(&self) -> [u8; ENCODED_LEN] {
+        let mut bytes = [0; ENCODED_LEN];
+        bytes[0] = self.days.len() as u8;
+        for (i, day) in self.days.iter().enumerate() {
+            bytes[1 + 4 * i..3 + 4 * i].copy_from_slice(&day.min.to_le_bytes());
+            bytes[3 + 4 * i..5 + 4 * i].copy_from_slice(&day.max.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// A log with the days `encode` wrote, the next sample starts a new one.
+    pub fn decode(bytes : &[u8]) -> Log {
+        let mut log = Log::new();
+        let i16_at = |i : usize| Some(i16::from_le_bytes([*bytes.get(i)?, *bytes.get(i + 1)?]));
+        let count = bytes.first().map_or(0, |count| (*count as usize).min(DAYS));
+        for i in 0..count {
+            let (Some(min), Some(max)) = (i16_at(1 + 4 * i), i16_at(3 + 4 * i)) else { break };
+            let _ = log.days.push_back(Day { min, max });
+        }
+        log
+    }
+}
+
+impl Default for Log {
+    fn default() -> Self {
+        Log::new()
+    }
+}
+
+/// Quarter degrees written as degrees, `21.25` or `-0.50`.
+pub struct Celsius(pub i16);
+
+impl fmt::Display for Celsius {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let quarters = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, quarters / 4, quarters % 4 * 25)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_day_keeps_its_lowest_and_highest() {
+        let mut log = Log::new();
+        assert!(log.record(80));
+        assert!(log.record(70));
+        assert!(!log.record(75));
+        assert!(log.record(90));
+        assert_eq!(log.today(), Some(Day { min : 70, max : 90 }));
+        assert_eq!(log.latest(), Some(90));
+    }
+
+    #[test]
+    fn days_roll_over_and_the_oldest_go() {
+        let mut log = Log::new();
+        for day in 0..DAYS as i16 + 2 {
+            for _ in 0..MINUTES_PER_DAY {
+                log.record(day);
+            }
+        }
+        assert_eq!(log.days().count(), DAYS);
+        assert_eq!(log.days().next(), Some(Day { min : 2, max : 2 }));
+        assert_eq!(log.minutes().count(), HISTORY);
+    }
+
+    #[test]
+    fn the_trend_needs_a_few_minutes() {
+        let mut log = Log::new();
+        for minute in 0..TREND_MINUTES as i16 {
+            log.record(80 + minute);
+        }
+        assert_eq!(log.trend(), None);
+        log.record(90);
+        assert_eq!(log.trend(), Some(Trend::Rising));
+        for _ in 0..TREND_MINUTES {
+            log.record(90);
+        }
+        assert_eq!(log.trend(), Some(Trend::Steady));
+        log.record(85);
+        assert_eq!(log.trend(), Some(Trend::Falling));
+    }
+
+    #[test]
+    fn stored_days_come_back_and_a_new_one_starts() {
+        let mut log = Log::new();
+        log.record(-3);
+        log.record(100);
+        let mut restored = Log::decode(&log.encode());
+        assert_eq!(restored.today(), Some(Day { min : -3, max : 100 }));
+        restored.record(50);
+        assert_eq!(restored.days().count(), 2);
+        assert_eq!(Log::decode(&[]).days().count(), 0);
+    }
+
+    #[test]
+    fn celsius_has_two_decimals() {
+        assert_eq!(Celsius(85).to_string(), "21.25");
+        assert_eq!(Celsius(-2).to_string(), "-0.50");
+    }
+}
@@ -0,0 +1,378 @@
+//! Classroom utilities: a coin flip, a random number from 1 to N and a group picker.
+//!
+//! One tool is shown at a time. The coin spins a moment and lands on H or T, the number
+//! shimmers before it settles, the picker's arrow spins round and slows down until it points
+//! at someone, a tick sounding with every turn. N can be raised, wrapping back to 2 past
+//! `MAX_N`.
+
+use core::fmt::Write;
+use crate::board::Beeper;
+use crate::font::{self, Align};
+use crate::scroll::{self, Text};
+use crate::{Clock, Frame, Random};
+
+const COIN : Frame = [
+    [0, 1, 1, 1, 0],
+    [1, 0, 0, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 0, 0, 1],
+    [0, 1, 1, 1, 0],
+];
+
+const COIN_TURNED : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+];
+
+const COIN_EDGE : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+const ARROW : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+    [1, 0, 1, 0, 1],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+const ARROW_DIAGONAL : Frame = [
+    [0, 0, 1, 1, 1],
+    [0, 0, 0, 1, 1],
+    [0, 0, 1, 0, 1],
+    [0, 1, 0, 0, 0],
+    [1, 0, 0, 0, 0],
+];
+
+pub const MAX_N : u32 = 99;
+const FLIP_MS : u64 = 1200;
+const FLIP_STEP_MS : u64 = 100;
+const ROLL_MS : u64 = 800;
+const ROLL_STEP_MS : u64 = 80;
+// NOTE: the picker goes round twice before the direction it lands on, every step a bit slower
+const PICK_TURNS : u32 = 16;
+const PICK_STEP_MS : u64 = 40;
+const PICK_SLOWER_MS : u64 = 8;
+const TICK_HZ : u32 = 1200;
+const TICK_MS : u64 = 15;
+const SET_MS : u64 = 1500;
+const POLL_MS : u32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tool {
+    Coin,
+    Number,
+    Picker,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    Waiting,
+    // NOTE: the outcome is drawn at the start, the animation only leads up to it
+    Running { outcome : u32, at : u64 },
+    Done { outcome : u32, at : u64 },
+    Setting { at : u64 },
+}
+
+#[derive(Clone, Copy)]
+pub struct Tools {
+    tool  : Tool,
+    n     : u32,
+    phase : Phase,
+}
+
+/// Turns the frame a quarter clockwise.
+fn rotate(frame : Frame) -> Frame {
+    let mut rotated = [[0; 5]; 5];
+    for (row, leds) in rotated.iter_mut().enumerate() {
+        for (column, led) in leds.iter_mut().enumerate() {
+            *led = frame[4 - column][row];
+        }
+    }
+    rotated
+}
+
+/// The arrow pointing `direction` eighths of a turn clockwise from up.
+fn arrow(direction : u32) -> Frame {
+    let base = if direction.is_multiple_of(2) { ARROW } else { ARROW_DIAGONAL };
+    (0..direction / 2).fold(base, |frame, _| rotate(frame))
+}
+
+/// The picker's step `elapsed` ms into its spin, None once it stopped.
+fn pick_step(outcome : u32, elapsed : u64) -> Option<(u32, u64)> {
+    let mut start = 0;
+    for step in 0..=PICK_TURNS + outcome {
+        let length = PICK_STEP_MS + PICK_SLOWER_MS * step as u64;
+        if elapsed < start + length {
+            return Some((step, elapsed - start));
+        }
+        start += length;
+    }
+    None
+}
+
+fn centered(c : char) -> Frame {
+    font::render(c.encode_utf8(&mut [0; 4]), Align::Center).unwrap_or_default()
+}
+
+/// The number standing still when it fits, otherwise scrolling round.
+fn number(value : u32, elapsed : u64) -> Frame {
+    let mut text = Text::new();
+    let _ = write!(text, "{}", value);
+    if let Some(frame) = font::render(&text, Align::Center) {
+        return frame;
+    }
+    let count = scroll::frames(&text).count() as u64;
+    let column = elapsed / scroll::STEP_MS as u64 % count;
+    scroll::frames(&text).nth(column as usize).unwrap_or_default()
+}
+
+impl Tools {
+    /// The coin, with N at 6 for a die.
+    pub const fn new() -> Self {
+        Tools { tool : Tool::Coin, n : 6, phase : Phase::Waiting }
+    }
+
+    pub fn tool(&self) -> Tool {
+        self.tool
+    }
+
+    pub fn n(&self) -> u32 {
+        self.n
+    }
+
+    fn running(&self) -> bool {
+        matches!(self.phase, Phase::Running { .. })
+    }
+
+    /// Moves on to the next tool, cutting short whatever the last one did.
+    pub fn next_tool(&mut self) {
+        self.tool = match self.tool {
+            Tool::Coin   => Tool::Number,
+            Tool::Number => Tool::Picker,
+            Tool::Picker => Tool::Coin,
+        };
+        self.phase = Phase::Waiting;
+    }
+
+    /// Back to waiting, the tool and N stay.
+    pub fn stop(&mut self) {
+        self.phase = Phase::Waiting;
+    }
+
+    /// Uses the tool shown, drawing its outcome. False while it is still running.
+    pub fn start(&mut self, clock : &impl Clock, random : &mut impl Random) -> bool {
+        if self.running() {
+            return false;
+        }
+        let outcome = match self.tool {
+            Tool::Coin   => random.below(2),
+            Tool::Number => 1 + random.below(self.n),
+            Tool::Picker => random.below(8),
+        };
+        self.phase = Phase::Running { outcome, at : clock.now_ms() };
+        true
+    }
+
+    /// Raises N by `by` and shows it, false unless the number is shown and not running.
+    pub fn raise(&mut self, by : u32, clock : &impl Clock) -> bool {
+        if self.tool != Tool::Number || self.running() {
+            return false;
+        }
+        self.n = if self.n + by > MAX_N { 2 } else { self.n + by };
+        self.phase = Phase::Setting { at : clock.now_ms() };
+        true
+    }
+
+    /// Moves the tool on to now, the outcome when it just stopped. The coin lands 0 for heads
+    /// and 1 for tails, the picker points `outcome` eighths of a turn from up.
+    pub fn advance(&mut self, clock : &impl Clock) -> Option<u32> {
+        let now = clock.now_ms();
+        match self.phase {
+            Phase::Running { outcome, at } => {
+                let elapsed = now.saturating_sub(at);
+                let over = match self.tool {
+                    Tool::Coin   => elapsed >= FLIP_MS,
+                    Tool::Number => elapsed >= ROLL_MS,
+                    Tool::Picker => pick_step(outcome, elapsed).is_none(),
+                };
+                if over {
+                    self.phase = Phase::Done { outcome, at : now };
+                    return Some(outcome);
+                }
+            }
+            Phase::Setting { at } if now.saturating_sub(at) >= SET_MS => self.phase = Phase::Waiting,
+            _ => (),
+        }
+        None
+    }
+
+    /// The frame for now with the time to show it for, ticking `beeper` along with the spins.
+    pub fn frame(&self, clock : &impl Clock, beeper : &mut impl Beeper) -> (Frame, u32) {
+        let now = clock.now_ms();
+        let mut tick = false;
+        let frame = match self.phase {
+            Phase::Waiting => {
+                let icon = match self.tool {
+                    Tool::Coin   => COIN,
+                    Tool::Number => font::frame_of('#'),
+                    Tool::Picker => ARROW,
+                };
+                let level = if (now / 600).is_multiple_of(2) { 9 } else { 4 };
+                return (icon.map(|row| row.map(|led| led * level)), POLL_MS);
+            }
+            Phase::Setting { at } => number(self.n, now.saturating_sub(at)),
+            Phase::Running { outcome, at } => {
+                let elapsed = now.saturating_sub(at);
+                match self.tool {
+                    Tool::Coin => {
+                        let spin = elapsed / FLIP_STEP_MS;
+                        tick = elapsed % FLIP_STEP_MS < TICK_MS && spin % 4 == 2;
+                        [COIN, COIN_TURNED, COIN_EDGE, COIN_TURNED][spin as usize % 4]
+                    }
+                    Tool::Number => {
+                        tick = elapsed % ROLL_STEP_MS < TICK_MS;
+                        // NOTE: a digit hashed from the step, so it holds still for the whole step
+                        let seed = (elapsed / ROLL_STEP_MS) as u32 ^ outcome;
+                        centered((b'0' + (seed.wrapping_mul(2654435761) >> 28) as u8 % 10) as char)
+                    }
+                    Tool::Picker => {
+                        let (turn, into) = pick_step(outcome, elapsed).unwrap_or((PICK_TURNS + outcome, 0));
+                        tick = into < TICK_MS;
+                        arrow(turn % 8)
+                    }
+                }
+            }
+            Phase::Done { outcome, at } => match self.tool {
+                Tool::Coin   => centered(if outcome == 0 { 'H' } else { 'T' }),
+                Tool::Number => number(outcome, now.saturating_sub(at)),
+                Tool::Picker => arrow(outcome % 8),
+            },
+        };
+        if tick { beeper.tone(TICK_HZ) } else { beeper.off() }
+        (frame.map(|row| row.map(|led| led * 9)), POLL_MS)
+    }
+}
+
+impl Default for Tools {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use super::*;
+
+    struct Ticks(Cell<u64>);
+
+    impl Clock for Ticks {
+        fn now_ms(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    impl Ticks {
+        fn wait(&self, ms : u64) {
+            self.0.set(self.0.get() + ms);
+        }
+    }
+
+    // NOTE: the same number every time, as high as it goes
+    struct Highest;
+
+    impl Random for Highest {
+        fn below(&mut self, n : u32) -> u32 {
+            n - 1
+        }
+    }
+
+    struct Tone(u32);
+
+    impl Beeper for Tone {
+        fn tone(&mut self, hz : u32) {
+            self.0 = hz;
+        }
+    }
+
+    #[test]
+    fn the_coin_spins_and_lands() {
+        let (clock, mut beeper) = (Ticks(Cell::new(0)), Tone(0));
+        let mut tools = Tools::new();
+        assert!(tools.start(&clock, &mut Highest));
+        assert!(!tools.start(&clock, &mut Highest));
+        clock.wait(2 * FLIP_STEP_MS);
+        assert_eq!(tools.advance(&clock), None);
+        assert_eq!(tools.frame(&clock, &mut beeper).0, COIN_EDGE.map(|row| row.map(|led| led * 9)));
+        assert_eq!(beeper.0, TICK_HZ);
+
+        clock.wait(FLIP_MS);
+        assert_eq!(tools.advance(&clock), Some(1));
+        assert_eq!(tools.frame(&clock, &mut beeper).0, centered('T').map(|row| row.map(|led| led * 9)));
+        assert_eq!(beeper.0, 0);
+        assert!(tools.start(&clock, &mut Highest));
+    }
+
+    #[test]
+    fn the_number_is_up_to_n() {
+        let clock = Ticks(Cell::new(0));
+        let mut tools = Tools::new();
+        assert!(!tools.raise(1, &clock));
+        tools.next_tool();
+        assert_eq!(tools.tool(), Tool::Number);
+        assert!(tools.raise(1, &clock));
+        assert_eq!(tools.n(), 7);
+        clock.wait(SET_MS);
+        assert_eq!(tools.advance(&clock), None);
+        assert_eq!(tools.phase, Phase::Waiting);
+
+        tools.start(&clock, &mut Highest);
+        assert!(!tools.raise(1, &clock));
+        clock.wait(ROLL_MS);
+        assert_eq!(tools.advance(&clock), Some(7));
+    }
+
+    #[test]
+    fn n_wraps_back_to_two() {
+        let clock = Ticks(Cell::new(0));
+        let mut tools = Tools { tool : Tool::Number, ..Tools::new() };
+        tools.n = MAX_N - 5;
+        tools.raise(10, &clock);
+        assert_eq!(tools.n(), 2);
+        tools.n = MAX_N;
+        tools.raise(1, &clock);
+        assert_eq!(tools.n(), 2);
+    }
+
+    #[test]
+    fn the_picker_slows_down_to_its_direction() {
+        let clock = Ticks(Cell::new(0));
+        let mut tools = Tools { tool : Tool::Picker, ..Tools::new() };
+        tools.start(&clock, &mut Highest);
+        let mut spun = 0;
+        while tools.advance(&clock).is_none() {
+            clock.wait(POLL_MS as u64);
+            spun += POLL_MS as u64;
+        }
+        let steps = (PICK_TURNS + 7) as u64 + 1;
+        let total = steps * PICK_STEP_MS + PICK_SLOWER_MS * steps * (steps - 1) / 2;
+        assert!((total..total + POLL_MS as u64).contains(&spun));
+        assert_eq!(tools.frame(&clock, &mut Tone(0)).0, arrow(7).map(|row| row.map(|led| led * 9)));
+    }
+
+    #[test]
+    fn arrows_point_all_round() {
+        assert_eq!(arrow(0), ARROW);
+        let upside_down : Frame = core::array::from_fn(|row| core::array::from_fn(|column| ARROW[4 - row][4 - column]));
+        assert_eq!(arrow(4), upside_down);
+        assert_eq!(arrow(9), arrow(1));
+    }
+}
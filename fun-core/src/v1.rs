@@ -0,0 +1,76 @@
+//! The LED wiring of the micro:bit v1. Only this: the firmware does not run on the v1's
+//! nRF51822, see the note below.
+//!
+//! The v1 matrix is 3 row lines by 9 column lines, each row line drives nine LEDs spread
+//! over the 5x5 grid, two of its 27 places have no LED. A driver lights one row line at a
+//! time with `columns` of it pulled low, and has no PWM for greyscale, a level that isn't
+//! 0 is fully lit. Button A is on P0.17 and B on P0.26, active low like on the v2.
+//!
+//! NOTE: v1 support is not done, there is no `v1` feature and no v1 build. Besides the nRF51
+//! HAL, which isn't a dependency, a v1 build needs the RTIC thumbv6 backend for its
+//! Cortex-M0, a display driver multiplexing `columns` from a timer in place of the PWM one,
+//! the flash layout of its 256K with 1K pages, and `bench` without the DWT cycle counter the
+//! M0 lacks. The speaker, the microphone, the touch logo and the radio update have no v1
+//! counterpart and would stay out of it.
+
+use crate::Frame;
+
+pub const ROWS : usize = 3;
+pub const COLUMNS : usize = 9;
+
+/// The (x, y) of the LED on each column line of each row line, from the top left.
+const LAYOUT : [[Option<(usize, usize)>; ROWS]; COLUMNS] = [
+    [Some((0, 0)), Some((4, 2)), Some((2, 4))],
+    [Some((2, 0)), Some((0, 2)), Some((4, 4))],
+    [Some((4, 0)), Some((2, 2)), Some((0, 4))],
+    [Some((4, 3)), Some((1, 0)), Some((0, 1))],
+    [Some((3, 3)), Some((3, 0)), Some((1, 1))],
+    [Some((2, 3)), Some((3, 4)), Some((2, 1))],
+    [Some((1, 3)), Some((1, 4)), Some((3, 1))],
+    [Some((0, 3)), None, Some((4, 1))],
+    [Some((1, 2)), None, Some((3, 2))],
+];
+
+/// The column lines to pull low while row line `row` is lit to show `frame`, bit n for
+/// COL(n + 1).
+pub fn columns(frame : &Frame, row : usize) -> u16 {
+    LAYOUT.iter().enumerate().fold(0, |lit, (column, rows)| match rows[row] {
+        Some((x, y)) if frame[y][x] > 0 => lit | 1 << column,
+        _ => lit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_led_is_on_one_line() {
+        for y in 0..5 {
+            for x in 0..5 {
+                let mut frame = [[0; 5]; 5];
+                frame[y][x] = 1;
+                let lit : Vec<u16> = (0..ROWS).map(|row| columns(&frame, row)).collect();
+                assert_eq!(lit.iter().map(|columns| columns.count_ones()).sum::<u32>(), 1, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn the_corners_are_where_the_schematic_has_them() {
+        let mut frame = [[0; 5]; 5];
+        frame[0][0] = 9;
+        frame[4][4] = 9;
+        assert_eq!(columns(&frame, 0), 1 << 0);
+        assert_eq!(columns(&frame, 1), 0);
+        assert_eq!(columns(&frame, 2), 1 << 1);
+    }
+
+    #[test]
+    fn a_full_frame_lights_every_line_with_an_led() {
+        let frame = [[9; 5]; 5];
+        assert_eq!(columns(&frame, 0), 0x1ff);
+        assert_eq!(columns(&frame, 1), 0x07f);
+        assert_eq!(columns(&frame, 2), 0x1ff);
+    }
+}
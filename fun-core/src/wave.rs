@@ -0,0 +1,84 @@
+//! The square wave of a signal generator: a frequency and a duty cycle, stepped through
+//! presets with the buttons or set outright, and their timing on a 32768 Hz clock.
+//!
+//! The clock makes the timing coarse: a period is a whole number of ticks, so the higher
+//! frequencies come out a little off and the duty cycle in steps of a tick, 12.5 % at
+//! `MAX_HZ`.
+
+pub const CLOCK_HZ : u32 = 32768;
+pub const MAX_HZ : u32 = 4096;
+pub const FREQUENCIES : [u32; 8] = [1, 10, 50, 100, 500, 1000, 2000, 4096];
+pub const DUTY_STEP : u8 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wave {
+    pub hz           : u32,
+    pub duty_percent : u8,
+}
+
+impl Wave {
+    pub const DEFAULT : Wave = Wave { hz : 1000, duty_percent : 50 };
+
+    /// None when `hz` is out of 1 to `MAX_HZ` or the duty cycle above 100.
+    pub fn new(hz : u32, duty_percent : u8) -> Option<Wave> {
+        ((1..=MAX_HZ).contains(&hz) && duty_percent <= 100).then_some(Wave { hz, duty_percent })
+    }
+
+    /// The ticks of a period and of its high part, 0 stays low and all of them high.
+    pub fn ticks(&self) -> (u32, u32) {
+        let period = CLOCK_HZ / self.hz;
+        let high = match self.duty_percent {
+            0 => 0,
+            100 => period,
+            // NOTE: a tick at least either way, so the wave doesn't turn into a level
+            duty => (period * duty as u32 / 100).clamp(1, period - 1),
+        };
+        (period, high)
+    }
+
+    /// The next preset frequency, round to the first after the last.
+    pub fn next_frequency(self) -> Wave {
+        let hz = FREQUENCIES.iter().copied().find(|hz| *hz > self.hz).unwrap_or(FREQUENCIES[0]);
+        Wave { hz, ..self }
+    }
+
+    /// `DUTY_STEP` more, round to the first step after 100.
+    pub fn next_duty(self) -> Wave {
+        let duty_percent = match self.duty_percent / DUTY_STEP * DUTY_STEP + DUTY_STEP {
+            duty if duty >= 100 => DUTY_STEP,
+            duty => duty,
+        };
+        Wave { duty_percent, ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_what_the_clock_can_make() {
+        assert_eq!(Wave::new(0, 50), None);
+        assert_eq!(Wave::new(MAX_HZ + 1, 50), None);
+        assert_eq!(Wave::new(440, 101), None);
+        assert_eq!(Wave::new(440, 100), Some(Wave { hz : 440, duty_percent : 100 }));
+    }
+
+    #[test]
+    fn the_ticks_keep_a_wave() {
+        assert_eq!(Wave::DEFAULT.ticks(), (32, 16));
+        assert_eq!(Wave { hz : MAX_HZ, duty_percent : 1 }.ticks(), (8, 1));
+        assert_eq!(Wave { hz : MAX_HZ, duty_percent : 99 }.ticks(), (8, 7));
+        assert_eq!(Wave { hz : 1, duty_percent : 0 }.ticks(), (CLOCK_HZ, 0));
+        assert_eq!(Wave { hz : 1, duty_percent : 100 }.ticks(), (CLOCK_HZ, CLOCK_HZ));
+    }
+
+    #[test]
+    fn the_presets_go_round() {
+        let wave = Wave { hz : 440, duty_percent : 85 };
+        assert_eq!(wave.next_frequency().hz, 500);
+        assert_eq!(Wave { hz : MAX_HZ, ..wave }.next_frequency().hz, 1);
+        assert_eq!(wave.next_duty().duty_percent, 90);
+        assert_eq!(wave.next_duty().next_duty().duty_percent, DUTY_STEP);
+    }
+}
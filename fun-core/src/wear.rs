@@ -0,0 +1,114 @@
+//! Evening out the wear of the LEDs on a board showing the same thing all day, as a badge.
+//!
+//! Two things, both done to the frames on their way to the display. A frame that stays the
+//! same for `STILL_MS` moves a column to the right, wrapping round, and another one every
+//! `STILL_MS` after that, so no LED stays lit the whole time. And the time each LED is lit
+//! is added up over windows of `WINDOW_MS`: one lit more than `MAX_DUTY_PERCENT` of a window
+//! at full level is shown at `CAPPED_LEVEL` at most through the next one.
+
+use crate::Frame;
+
+pub const WINDOW_MS : u64 = 60_000;
+pub const STILL_MS : u64 = 120_000;
+const MAX_DUTY_PERCENT : u64 = 50;
+// NOTE: still lit on the display without greyscale, see the firmware's display.rs
+pub const CAPPED_LEVEL : u8 = 5;
+
+pub struct Wear {
+    last      : Frame,
+    still_ms  : u64,
+    // NOTE: level times ms, over the window running
+    lit       : [[u64; 5]; 5],
+    window_ms : u64,
+    capped    : [[bool; 5]; 5],
+}
+
+impl Wear {
+    pub const fn new() -> Self {
+        Wear { last : [[0; 5]; 5], still_ms : 0, lit : [[0; 5]; 5], window_ms : 0, capped : [[false; 5]; 5] }
+    }
+
+    /// The frame to show in place of `frame`, which is shown for `duration_ms`.
+    pub fn apply(&mut self, frame : Frame, duration_ms : u32) -> Frame {
+        if frame != self.last {
+            self.last = frame;
+            self.still_ms = 0;
+        }
+        let shift = (self.still_ms / STILL_MS) as usize % 5;
+        self.still_ms += duration_ms as u64;
+
+        let mut shown = [[0; 5]; 5];
+        for (y, row) in shown.iter_mut().enumerate() {
+            for (x, led) in row.iter_mut().enumerate() {
+                let level = frame[y][(x + 5 - shift) % 5];
+                *led = if self.capped[y][x] { level.min(CAPPED_LEVEL) } else { level };
+            }
+        }
+        self.record(&shown, duration_ms as u64);
+        shown
+    }
+
+    fn record(&mut self, shown : &Frame, duration_ms : u64) {
+        for (lit, level) in self.lit.iter_mut().flatten().zip(shown.iter().flatten()) {
+            *lit += *level as u64 * duration_ms;
+        }
+        self.window_ms += duration_ms;
+        if self.window_ms < WINDOW_MS {
+            return;
+        }
+        for (capped, lit) in self.capped.iter_mut().flatten().zip(self.lit.iter_mut().flatten()) {
+            *capped = *lit * 100 > MAX_DUTY_PERCENT * 9 * self.window_ms;
+            *lit = 0;
+        }
+        self.window_ms = 0;
+    }
+}
+
+impl Default for Wear {
+    fn default() -> Self {
+        Wear::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOT : Frame = [[0, 0, 0, 0, 0], [0, 0, 0, 0, 0], [0, 0, 9, 0, 0], [0, 0, 0, 0, 0], [0, 0, 0, 0, 0]];
+
+    #[test]
+    fn a_frame_left_alone_moves_round() {
+        let mut wear = Wear::new();
+        let mut shown = wear.apply(DOT, STILL_MS as u32);
+        assert_eq!(shown, DOT);
+        for column in [3, 4, 0, 1, 2] {
+            shown = wear.apply(DOT, STILL_MS as u32);
+            assert_eq!(shown[2][column], 9, "column {}", column);
+        }
+    }
+
+    #[test]
+    fn a_new_frame_starts_where_it_is() {
+        let mut wear = Wear::new();
+        wear.apply(DOT, 2 * STILL_MS as u32);
+        let mut other = DOT;
+        other[0][0] = 9;
+        assert_eq!(wear.apply(other, 100)[0][0], 9);
+    }
+
+    #[test]
+    fn an_led_lit_too_long_is_capped_through_the_next_window() {
+        let mut wear = Wear::new();
+        let mut half = [[0; 5]; 5];
+        half[0][0] = 9;
+        half[0][1] = 4;
+        // NOTE: changing frames, so nothing moves
+        for _ in 0..WINDOW_MS / 1000 {
+            wear.apply(half, 1000);
+            wear.apply([[0; 5]; 5], 0);
+        }
+        let shown = wear.apply(half, 1000);
+        assert_eq!(shown[0][0], CAPPED_LEVEL);
+        assert_eq!(shown[0][1], 4);
+    }
+}
@@ -0,0 +1,126 @@
+#!/usr/bin/env python3
+"""Regression tests of input dispatch and display arbitration, run on a real board.
+
+Talks to a firmware built with the `hil` feature over RTT channel 1, through the TCP server
+of OpenOCD (`make hil` starts both). One command a line goes down, one reply a line comes
+back, see src/hil.rs for the commands.
+
+    usage: selftest.py [host:port]
+"""
+
+import socket
+import sys
+import time
+
+
+class Board:
+    def __init__(self, address):
+        host, port = address.rsplit(":", 1)
+        self.sock = socket.create_connection((host, int(port)), timeout=5)
+        self.pending = b""
+
+    def command(self, line):
+        self.sock.sendall(line.encode() + b"\n")
+        while b"\n" not in self.pending:
+            chunk = self.sock.recv(256)
+            if not chunk:
+                raise ConnectionError("board went away")
+            self.pending += chunk
+        reply, self.pending = self.pending.split(b"\n", 1)
+        return reply.decode().strip()
+
+    def frame(self):
+        reply = self.command("frame")
+        assert reply.startswith("frame "), reply
+        return [[int(led) for led in row] for row in reply.split()[1:]]
+
+    def app(self):
+        return self.command("app")
+
+    def wait_for(self, what, check, timeout=3.0):
+        deadline = time.monotonic() + timeout
+        while time.monotonic() < deadline:
+            value = what()
+            if check(value):
+                return value
+            time.sleep(0.02)
+        raise AssertionError(f"timed out, last {value}")
+
+
+def lit(frame):
+    return sum(led > 0 for row in frame for led in row)
+
+
+def test_ping(board):
+    assert board.command("ping") == "pong"
+
+
+def test_unknown_command(board):
+    assert board.command("jump").startswith("error")
+
+
+def test_launcher_opens_and_closes(board):
+    assert board.app().startswith("app "), "start with an app running"
+    assert board.command("press ab") == "ok"
+    board.wait_for(board.app, lambda app: app.startswith("launcher "))
+    # NOTE: B steps on through the apps while the launcher is open, A+B launches
+    first = board.app()
+    board.command("press b")
+    board.wait_for(board.app, lambda app: app != first)
+    board.command("press ab")
+    board.wait_for(board.app, lambda app: app.startswith("app "))
+
+
+def test_launcher_shows_the_icon(board):
+    board.command("press ab")
+    board.wait_for(board.app, lambda app: app.startswith("launcher "))
+    # NOTE: the heartbeat blips a corner now and then, an icon lights more than that
+    board.wait_for(board.frame, lambda frame: lit(frame) > 1)
+    board.command("press ab")
+    board.wait_for(board.app, lambda app: app.startswith("app "))
+
+
+def test_inputs_reach_the_app(board):
+    for command in ["long a", "long b", "shake", "tilt 0 0", "press logo"]:
+        assert board.command(command) == "ok", command
+
+
+def test_accelerometer_sees_gravity(board):
+    reply = board.command("accel")
+    assert reply.startswith("accel "), reply
+    if reply == "accel none":
+        print("  no accelerometer, skipped")
+        return
+    x, y, z = (int(value) for value in reply.split()[1:])
+    magnitude = (x * x + y * y + z * z) ** 0.5
+    assert 700 < magnitude < 1300, f"{magnitude:.0f} mg at rest"
+
+
+TESTS = [
+    test_ping,
+    test_unknown_command,
+    test_launcher_opens_and_closes,
+    test_launcher_shows_the_icon,
+    test_inputs_reach_the_app,
+    test_accelerometer_sees_gravity,
+]
+
+
+def main():
+    board = Board(sys.argv[1] if len(sys.argv) > 1 else "localhost:9091")
+    failed = 0
+    for test in TESTS:
+        try:
+            test(board)
+            print(f"ok   {test.__name__}")
+        except AssertionError as error:
+            failed += 1
+            print(f"FAIL {test.__name__}: {error}")
+    print(f"{len(TESTS) - failed} passed, {failed} failed")
+    # the board logs the result and halts, or exits through semihosting for its runner
+    assert board.command("exit fail" if failed else "exit pass") == "ok"
+    sys.exit(1 if failed else 0)
+
+
+if __name__ == "__main__":
+    main()
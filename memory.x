@@ -1,6 +1,8 @@
 MEMORY
 {
   /* NOTE K = KiBi = 1024 bytes */
-  FLASH : ORIGIN = 0x00000000, LENGTH = 256K
+  /* NOTE the nRF52833 has 512K of flash, the pages at the top are used for
+     persistent data (see src/flash.rs) and must stay outside of this region */
+  FLASH : ORIGIN = 0x00000000, LENGTH = 384K
   RAM : ORIGIN = 0x20000000, LENGTH = 16K
 }
@@ -0,0 +1,32 @@
+//! The apps the simulator runs, and the inputs they take like on the board, see
+//! `src/apps.rs` of the firmware.
+
+use crate::display::Frame;
+use crate::events::Button;
+use crate::mono::Instant;
+use crate::{breakout, eightball, flappy, maze, shooter, simon, utils};
+
+pub struct App {
+    pub name     : &'static str,
+    pub draw     : fn(step : usize) -> Option<(Frame, u32)>,
+    pub on_input : fn(Input, Instant) -> bool,
+}
+
+// NOTE: has to match the firmware's, the shared app modules match on it
+#[derive(Clone, Copy)]
+pub enum Input {
+    Button(Button),
+    Shake,
+    LongPress(Button),
+    Tilt { x : i32, y : i32 },
+}
+
+pub static APPS : &[App] = &[
+    App { name : "simon", draw : simon::draw, on_input : simon::on_input },
+    App { name : "maze", draw : maze::draw, on_input : maze::on_input },
+    App { name : "shooter", draw : shooter::draw, on_input : shooter::on_input },
+    App { name : "flappy", draw : flappy::draw, on_input : flappy::on_input },
+    App { name : "breakout", draw : breakout::draw, on_input : breakout::on_input },
+    App { name : "8-ball", draw : eightball::draw, on_input : eightball::on_input },
+    App { name : "utils", draw : utils::draw, on_input : utils::on_input },
+];
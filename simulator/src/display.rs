@@ -0,0 +1,3 @@
+//! The frame type of the firmware's display driver, the terminal stands in for the LEDs.
+
+pub use fun_core::Frame;
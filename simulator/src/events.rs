@@ -0,0 +1,9 @@
+//! The buttons, as the firmware's event journal names them.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Button {
+    A,
+    B,
+    AB,
+    Logo,
+}
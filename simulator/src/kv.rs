@@ -0,0 +1,23 @@
+//! The key-value store kept in memory, gone when the simulator quits.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+pub type Key = u16;
+
+// NOTE: the keys of the firmware's kv.rs the simulated apps use
+pub const HIGH_SCORES : Key = 0x0100;
+
+static VALUES : Mutex<Option<HashMap<Key, Vec<u8>>>> = Mutex::new(None);
+
+pub fn get(key : Key, buf : &mut [u8]) -> Option<usize> {
+    let values = VALUES.lock().unwrap();
+    let value = values.as_ref()?.get(&key)?;
+    let len = value.len().min(buf.len());
+    buf[..len].copy_from_slice(&value[..len]);
+    Some(len)
+}
+
+pub fn set(key : Key, value : &[u8]) {
+    VALUES.lock().unwrap().get_or_insert_with(HashMap::new).insert(key, value.to_vec());
+}
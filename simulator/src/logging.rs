@@ -0,0 +1,38 @@
+//! `log!` like the firmware's, the last record is shown under the matrix.
+
+use std::sync::Mutex;
+
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+static LAST : Mutex<String> = Mutex::new(String::new());
+
+pub fn record(level : Level, message : String) {
+    let level = match level {
+        Level::Trace => "TRACE",
+        Level::Debug => "DEBUG",
+        Level::Info  => "INFO",
+        Level::Warn  => "WARN",
+        Level::Error => "ERROR",
+    };
+    *LAST.lock().unwrap() = format!("{} {}", level, message);
+}
+
+pub fn last() -> String {
+    LAST.lock().unwrap().clone()
+}
+
+#[macro_export]
+macro_rules! log {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::log!($crate::logging::Level::Info, $fmt $(, $arg)*)
+    };
+    ($level:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::logging::record($level, format!($fmt $(, $arg)*))
+    };
+}
@@ -0,0 +1,226 @@
+//! Runs the games and animations of the firmware on the host, with the 5x5 matrix drawn in
+//! the terminal.
+//!
+//! The app modules are compiled straight from `../src`, next to small stand-ins for the
+//! hardware modules they use: the monotonic runs on the host clock, the speaker shows the
+//! tone it would play and the key-value store lives in memory. Their critical sections
+//! are critical-section's, with its std implementation here and cortex-m's on the board.
+//! Keys take the place of the buttons, the logo and the accelerometer, see `KEYS`, whose
+//! samples go through the same shake and tilt logic as on the board, `fun_core::motion`.
+//!
+//!     cd simulator && cargo run
+//!
+//! NOTE: the root crate is built for the micro:bit, so cargo has to be run from here, the
+//! rust-toolchain.toml up there also picks the nightly for the host.
+
+// NOTE: the shared modules have more in them than the simulated apps use
+#![allow(dead_code)]
+
+use std::io::{Read, Write};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+mod apps;
+mod display;
+mod events;
+mod kv;
+mod logging;
+mod mono;
+mod rng;
+mod speaker;
+
+#[path = "../../src/breakout.rs"]
+mod breakout;
+#[path = "../../src/effects.rs"]
+mod effects;
+#[path = "../../src/eightball.rs"]
+mod eightball;
+#[path = "../../src/flappy.rs"]
+mod flappy;
+#[path = "../../src/highscores.rs"]
+mod highscores;
+#[path = "../../src/maze.rs"]
+mod maze;
+#[path = "../../src/shooter.rs"]
+mod shooter;
+#[path = "../../src/simon.rs"]
+mod simon;
+#[path = "../../src/utils.rs"]
+mod utils;
+
+use apps::{App, Input, APPS};
+use fun_core::board::Motion;
+use fun_core::motion::{Tracker, POLL_MS};
+use fun_core::scroll;
+use display::Frame;
+use events::Button;
+use mono::{Instant, Mono};
+
+const KEYS : &str = "a/b buttons, space A+B, A/B long press, l logo, s shake, arrows tilt, tab next app, q quit";
+
+// NOTE: how hard an arrow tilts the board, and for how long after the key came in
+const TILT_MG : i32 = 500;
+const TILT_MS : u64 = 250;
+// NOTE: well above what fun_core::motion takes for a shake
+const SHAKE_MG : i32 = 2500;
+const G_MG : i32 = 1000;
+
+enum Key {
+    Input(Input),
+    Tilt { x : i32, y : i32 },
+    Shake,
+    NextApp,
+    Quit,
+}
+
+/// Reads the terminal byte by byte, arrows come in as `ESC [ A`..`ESC [ D`.
+fn keys() -> Receiver<Key> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut bytes = std::io::stdin().lock().bytes().map_while(Result::ok);
+        while let Some(byte) = bytes.next() {
+            let key = match byte {
+                b'a'  => Key::Input(Input::Button(Button::A)),
+                b'b'  => Key::Input(Input::Button(Button::B)),
+                b' '  => Key::Input(Input::Button(Button::AB)),
+                b'A'  => Key::Input(Input::LongPress(Button::A)),
+                b'B'  => Key::Input(Input::LongPress(Button::B)),
+                b'l'  => Key::Input(Input::Button(Button::Logo)),
+                b's'  => Key::Shake,
+                b'\t' => Key::NextApp,
+                b'q'  => Key::Quit,
+                0x1b => match (bytes.next(), bytes.next()) {
+                    (Some(b'['), Some(b'A')) => Key::Tilt { x : 0, y : -TILT_MG },
+                    (Some(b'['), Some(b'B')) => Key::Tilt { x : 0, y : TILT_MG },
+                    (Some(b'['), Some(b'C')) => Key::Tilt { x : TILT_MG, y : 0 },
+                    (Some(b'['), Some(b'D')) => Key::Tilt { x : -TILT_MG, y : 0 },
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            if sender.send(key).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+fn stty(args : &[&str]) {
+    let _ = Command::new("stty").args(args).stdin(std::process::Stdio::inherit()).status();
+}
+
+/// The accelerometer of a board lying flat, tilted by the arrows for a while and shaken
+/// for one sample by `s`.
+struct Keys {
+    tilt   : (i32, i32, Instant),
+    shaken : bool,
+}
+
+impl Motion for Keys {
+    fn acceleration(&mut self) -> Option<(i32, i32, i32)> {
+        if std::mem::take(&mut self.shaken) {
+            return Some((SHAKE_MG, 0, -G_MG));
+        }
+        let (x, y, at) = self.tilt;
+        let (x, y) = if ms_since(Mono::now(), at) < TILT_MS { (x, y) } else { (0, 0) };
+        Some((x, y, -G_MG))
+    }
+}
+
+/// The matrix in shades of red, the LEDs two columns wide so they come out about square.
+fn render(app : &App, frame : &Frame) {
+    let mut out = String::from("\x1b[H");
+    out += &format!("{} ({})\x1b[K\r\n\r\n", app.name, KEYS);
+    for row in frame {
+        out += "  ";
+        for &led in row {
+            // NOTE: the 256 colour cube's reds, 16 + 36 * r for r in 0..6
+            let red = 16 + 36 * (led.min(9) as u32 * 5 / 9);
+            out += &format!("\x1b[38;5;{}m{}\x1b[0m", if led == 0 { 236 } else { red }, "██");
+        }
+        out += "\r\n";
+    }
+    out += &format!("\r\n{}\x1b[K\r\n", speaker::status());
+    out += &format!("{}\x1b[K\r\n", logging::last());
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(out.as_bytes());
+    let _ = stdout.flush();
+}
+
+/// What idle does for a finished game on the board, minus the initials entry.
+fn finished() {
+    if let Some((game, score)) = highscores::take_finished() {
+        if highscores::submit(game, *b"SIM", score).is_some() {
+            crate::log!("high scores {}", highscores::text(game).as_str());
+        }
+    }
+}
+
+fn main() {
+    stty(&["raw", "-echo"]);
+    print!("\x1b[2J\x1b[?25l");
+    let keys = keys();
+
+    let mut current = 0;
+    let mut step = 0;
+    let mut frame = [[0; 5]; 5];
+    let mut frame_due = Mono::now();
+    let mut motion = Tracker::new(Keys { tilt : (0, 0, Mono::now()), shaken : false });
+    let mut polled = Mono::now();
+    'run : loop {
+        let now = Mono::now();
+        for key in keys.try_iter() {
+            match key {
+                Key::Input(input) => {
+                    let _ = (APPS[current].on_input)(input, now);
+                }
+                Key::Tilt { x, y } => motion.sensor().tilt = (x, y, now),
+                Key::Shake => motion.sensor().shaken = true,
+                Key::NextApp => {
+                    current = (current + 1) % APPS.len();
+                    step = 0;
+                    frame_due = now;
+                    speaker::off();
+                }
+                Key::Quit => break 'run,
+            }
+        }
+
+        let app = &APPS[current];
+        if ms_since(now, polled) >= POLL_MS {
+            polled = now;
+            if motion.poll(now.duration_since_epoch().to_millis()) {
+                let _ = (app.on_input)(Input::Shake, now);
+            }
+            if let Some((x, y)) = motion.tilt() {
+                let _ = (app.on_input)(Input::Tilt { x, y }, now);
+            }
+        }
+        if now >= frame_due {
+            match (app.draw)(step) {
+                Some((next, ms)) => {
+                    frame = next;
+                    step += 1;
+                    frame_due = now + mono::Duration::millis(ms as u64);
+                }
+                None => {
+                    finished();
+                    step = 0;
+                }
+            }
+        }
+        render(app, &frame);
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    speaker::off();
+    print!("\x1b[?25h\r\n");
+    stty(&["sane"]);
+}
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
@@ -0,0 +1,28 @@
+//! The firmware's monotonic, counting the same 32.768 kHz ticks off the host clock.
+
+use std::sync::OnceLock;
+use std::time;
+
+pub type Instant = fugit::TimerInstantU64<32_768>;
+pub type Duration = fugit::TimerDurationU64<32_768>;
+
+static START : OnceLock<time::Instant> = OnceLock::new();
+
+pub struct Mono;
+
+impl Mono {
+    pub fn now() -> Instant {
+        let elapsed = START.get_or_init(time::Instant::now).elapsed();
+        Instant::from_ticks((elapsed.as_micros() * 32_768 / 1_000_000) as u64)
+    }
+
+    pub fn now_us() -> u64 {
+        Self::now().duration_since_epoch().to_micros()
+    }
+}
+
+impl fun_core::Clock for Mono {
+    fn now_ms(&self) -> u64 {
+        Self::now().duration_since_epoch().to_millis()
+    }
+}
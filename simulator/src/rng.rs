@@ -0,0 +1,39 @@
+//! The firmware's `Rng`, a xorshift seeded from the host clock in place of the RNG peripheral.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static STATE : AtomicU32 = AtomicU32::new(0);
+
+pub struct Rng;
+
+impl Rng {
+    fn next_u32(&mut self) -> u32 {
+        let mut x = STATE.load(Ordering::Relaxed);
+        if x == 0 {
+            x = SystemTime::now().duration_since(UNIX_EPOCH).map_or(1, |since| since.subsec_nanos() | 1);
+        }
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        STATE.store(x, Ordering::Relaxed);
+        x
+    }
+
+    /// Uniform in `0..n`, like the firmware's.
+    pub fn below(&mut self, n : u32) -> u32 {
+        let zone = u32::MAX - u32::MAX % n;
+        loop {
+            let x = self.next_u32();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+}
+
+impl fun_core::Random for Rng {
+    fn below(&mut self, n : u32) -> u32 {
+        Rng::below(self, n)
+    }
+}
@@ -0,0 +1,32 @@
+//! The speaker, the tone playing is shown under the matrix instead.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use fun_core::board::Beeper;
+
+// NOTE: the PWM can't go lower, see the firmware's speaker.rs
+const MIN_HZ : u32 = 61;
+
+static PLAYING_HZ : AtomicU32 = AtomicU32::new(0);
+
+pub fn tone(hz : u32) {
+    PLAYING_HZ.store(if hz < MIN_HZ { 0 } else { hz }, Ordering::Relaxed);
+}
+
+pub fn off() {
+    tone(0);
+}
+
+pub struct Speaker;
+
+impl Beeper for Speaker {
+    fn tone(&mut self, hz : u32) {
+        tone(hz)
+    }
+}
+
+pub fn status() -> String {
+    match PLAYING_HZ.load(Ordering::Relaxed) {
+        0 => String::from("silent"),
+        hz => format!("playing {} Hz", hz),
+    }
+}
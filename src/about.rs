@@ -0,0 +1,158 @@
+//! About this board: the firmware version, the uptime, why it last reset and its device id,
+//! for the `info` console command and the about app.
+//!
+//! `init` reads the reset reason from RESETREAS and clears it, the register otherwise
+//! collects the reasons of every reset since power-on. The version also goes out in the
+//! hello packet every board broadcasts at boot, and scrolls by for A+B pressed within
+//! `BOOT_WINDOW_MS` of it, so a board in the field tells what it runs.
+
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use microbit::pac::POWER;
+use crate::display::Frame;
+use crate::mono::Mono;
+use crate::protocol::{self, Packet};
+use crate::radio::Payload;
+use crate::scroll::{self, Text};
+
+// NOTE: set by build.rs, the package version and the git commit, which is empty when the
+// build didn't come from a git checkout
+pub const VERSION : &str = env!("FIRMWARE_VERSION");
+pub const COMMIT : &str = env!("FIRMWARE_COMMIT");
+pub const BOOT_WINDOW_MS : u64 = 5000;
+
+// NOTE: the kind, the version and the length of the commit
+const COMMIT_LEN : usize = protocol::MAX_LEN - 5;
+
+#[derive(Clone, Copy)]
+pub struct Version<'a> {
+    pub major  : u8,
+    pub minor  : u8,
+    pub patch  : u8,
+    pub commit : &'a str,
+}
+
+pub fn version() -> Version<'static> {
+    let part = |part : &str| part.parse().unwrap_or(0);
+    Version {
+        major  : part(env!("CARGO_PKG_VERSION_MAJOR")),
+        minor  : part(env!("CARGO_PKG_VERSION_MINOR")),
+        patch  : part(env!("CARGO_PKG_VERSION_PATCH")),
+        commit : COMMIT,
+    }
+}
+
+impl core::fmt::Display for Version<'_> {
+    fn fmt(&self, f : &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.commit.is_empty() {
+            write!(f, "+{}", self.commit)?;
+        }
+        Ok(())
+    }
+}
+
+/// The hello packet: the version, with the commit as text.
+pub fn hello_packet() -> Option<Payload> {
+    let version = version();
+    // NOTE: the commit is ascii, so any cut is a char boundary
+    let commit = &version.commit[..version.commit.len().min(COMMIT_LEN)];
+    Packet::Hello { major : version.major, minor : version.minor, patch : version.patch, commit }.encode()
+}
+
+/// The version in a hello packet.
+pub fn decode_hello<'a>(packet : &Packet<'a>) -> Option<Version<'a>> {
+    match *packet {
+        Packet::Hello { major, minor, patch, commit } => Some(Version { major, minor, patch, commit }),
+        _ => None,
+    }
+}
+
+/// True for as long after boot as A+B shows the version.
+pub fn in_boot_window() -> bool {
+    Mono::now().duration_since_epoch().to_millis() < BOOT_WINDOW_MS
+}
+
+pub fn version_text() -> Text {
+    let mut text = Text::new();
+    let _ = write!(text, "v{}", version());
+    text
+}
+
+pub const ICON : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+// NOTE: the RESETREAS bits in the order they are reported, the first one set wins
+const REASONS : [(u32, &str); 9] = [
+    (1 << 1, "watchdog"),
+    (1 << 3, "lockup"),
+    (1 << 2, "soft reset"),
+    (1 << 0, "reset pin"),
+    (1 << 16, "wake from off"),
+    (1 << 17, "wake by lpcomp"),
+    (1 << 18, "debug interface"),
+    (1 << 19, "wake by nfc"),
+    (1 << 20, "wake by vbus"),
+];
+
+static RESET : Mutex<Cell<u32>> = Mutex::new(Cell::new(0));
+static DEVICE_ID : Mutex<Cell<u64>> = Mutex::new(Cell::new(0));
+
+pub fn init(device_id : u64) {
+    // NOTE: the POWER peripheral is not part of the board struct, only RESETREAS is used
+    let power = unsafe { &*POWER::ptr() };
+    let reset = power.resetreas.read().bits();
+    // NOTE: the bits clear by writing 1 to them
+    power.resetreas.write(|w| unsafe { w.bits(reset) });
+    cortex_m::interrupt::free(|cs| {
+        RESET.borrow(cs).set(reset);
+        DEVICE_ID.borrow(cs).set(device_id);
+    });
+}
+
+/// Why the board last reset, a power-on reset sets no bit at all.
+pub fn reset_reason() -> &'static str {
+    let reset = cortex_m::interrupt::free(|cs| RESET.borrow(cs).get());
+    REASONS.into_iter()
+        .find(|(bit, _)| reset & bit != 0)
+        .map_or("power-on", |(_, reason)| reason)
+}
+
+pub fn device_id() -> u64 {
+    cortex_m::interrupt::free(|cs| DEVICE_ID.borrow(cs).get())
+}
+
+pub fn uptime_secs() -> u64 {
+    Mono::now().duration_since_epoch().to_secs()
+}
+
+type AboutText = heapless::String<96>;
+
+fn text() -> AboutText {
+    let mut text = AboutText::new();
+    let secs = uptime_secs();
+    let _ = write!(
+        text, "v{} up {}h{:02}m reset {} id {:016x}",
+        VERSION, secs / 3600, secs / 60 % 60, reset_reason(), device_id());
+    text
+}
+
+static TEXT : Mutex<RefCell<AboutText>> = Mutex::new(RefCell::new(AboutText::new()));
+
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    // NOTE: made at the start of a lap, so the uptime doesn't change under the scroll
+    let text = cortex_m::interrupt::free(|cs| {
+        let mut shown = TEXT.borrow(cs).borrow_mut();
+        if step == 0 {
+            *shown = text();
+        }
+        shown.clone()
+    });
+    scroll::frames(&text).nth(step).map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
@@ -0,0 +1,159 @@
+//! The app modes, the launcher lists every entry of `APPS` in order.
+//!
+//! While an app runs idle keeps calling its `draw` with the step it is at, the app returns
+//! the greyscale frame for that step and how many ms to show it. Returning None ends a
+//! cycle, idle then does its own bookkeeping and starts the app over at step 0.
+//! An app that wants the buttons, long presses, the logo, shakes or the tilt gets them first through `on_input`.
+//! Adding an app is writing its `draw` and appending it to `APPS`.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use crate::about;
+use crate::attendance;
+use crate::assets;
+use crate::badge;
+use crate::breakout;
+use crate::car;
+use crate::display::Frame;
+use crate::effects::{self, Preset};
+use crate::eightball;
+use crate::events::Button;
+use crate::flappy;
+use crate::follower;
+use crate::frequency;
+use crate::generator;
+use crate::greenhouse;
+use crate::highscores;
+use crate::inbox;
+use crate::instrument;
+use crate::maze;
+use crate::meter;
+use crate::menu;
+use crate::morse;
+use crate::mouse;
+use crate::motor;
+use crate::pairing;
+use crate::quiz;
+use crate::mono::Instant;
+use crate::playlist;
+use crate::presenter;
+use crate::reaction;
+use crate::remote;
+use crate::rng::Rng;
+use crate::rps;
+use crate::shooter;
+use crate::sketch;
+use crate::stats;
+use crate::swarm;
+use crate::thermo;
+use crate::tug;
+use crate::utils;
+use crate::wedge;
+use crate::simon;
+use crate::scope;
+use crate::soil;
+
+pub struct App {
+    pub name     : &'static str,
+    pub icon     : Frame,
+    pub draw     : fn(step : usize) -> Option<(Frame, u32)>,
+    /// Called with every input and its time, A and B from the button interrupt, the app
+    /// returns true when it took the input and a button's usual action shouldn't run.
+    pub on_input : Option<fn(Input, Instant) -> bool>,
+}
+
+#[derive(Clone, Copy)]
+pub enum Input {
+    Button(Button),
+    Shake,
+    LongPress(Button),
+    // NOTE: every accelerometer poll, see `fun_core::motion::Tracker::tilt`
+    Tilt { x : i32, y : i32 },
+}
+
+pub static APPS : &[App] = &[
+    App { name : "idle", icon : playlist::ICON, draw : playlist::draw, on_input : Some(playlist::on_input) },
+    App { name : "sparkle", icon : SPARKLE_ICON, draw : sparkle, on_input : None },
+    App { name : "reaction", icon : reaction::ICON, draw : reaction::draw, on_input : Some(reaction::on_input) },
+    App { name : "simon", icon : simon::ICON, draw : simon::draw, on_input : Some(simon::on_input) },
+    App { name : "rps", icon : rps::ICON, draw : rps::draw, on_input : Some(rps::on_input) },
+    App { name : "maze", icon : maze::ICON, draw : maze::draw, on_input : Some(maze::on_input) },
+    App { name : "shooter", icon : shooter::ICON, draw : shooter::draw, on_input : Some(shooter::on_input) },
+    App { name : "flappy", icon : flappy::ICON, draw : flappy::draw, on_input : Some(flappy::on_input) },
+    App { name : "sketch", icon : sketch::ICON, draw : sketch::draw, on_input : Some(sketch::on_input) },
+    App { name : "breakout", icon : breakout::ICON, draw : breakout::draw, on_input : Some(breakout::on_input) },
+    App { name : "8-ball", icon : eightball::ICON, draw : eightball::draw, on_input : Some(eightball::on_input) },
+    App { name : "pair", icon : pairing::ICON, draw : pairing::draw, on_input : Some(pairing::on_input) },
+    App { name : "meter", icon : meter::ICON, draw : meter::draw, on_input : None },
+    App { name : "remote", icon : remote::ICON, draw : remote::draw, on_input : Some(remote::on_input) },
+    App { name : "tug", icon : tug::ICON, draw : tug::draw, on_input : Some(tug::on_input) },
+    App { name : "morse", icon : morse::ICON, draw : morse::draw, on_input : Some(morse::on_input) },
+    App { name : "utils", icon : utils::ICON, draw : utils::draw, on_input : Some(utils::on_input) },
+    App { name : "gallery", icon : sketch::GALLERY_ICON, draw : sketch::gallery, on_input : Some(sketch::gallery_on_input) },
+    App { name : "anims", icon : assets::ICON, draw : assets::draw, on_input : Some(assets::on_input) },
+    App { name : "stats", icon : stats::ICON, draw : stats::draw, on_input : None },
+    App { name : "settings", icon : menu::ICON, draw : menu::draw, on_input : Some(menu::on_input) },
+    App { name : "about", icon : about::ICON, draw : about::draw, on_input : None },
+    // NOTE: added at the end, the stored default mode and boot setting are indices
+    App { name : "badge", icon : badge::ICON, draw : badge::draw, on_input : Some(badge::on_input) },
+    App { name : "thermo", icon : thermo::ICON, draw : thermo::draw, on_input : Some(thermo::on_input) },
+    App { name : "greenhouse", icon : greenhouse::ICON, draw : greenhouse::draw, on_input : None },
+    App { name : "soil", icon : soil::ICON, draw : soil::draw, on_input : Some(soil::on_input) },
+    App { name : "scope", icon : scope::ICON, draw : scope::draw, on_input : Some(scope::on_input) },
+    App { name : "freq", icon : frequency::ICON, draw : frequency::draw, on_input : None },
+    App { name : "pwm", icon : generator::ICON, draw : generator::draw, on_input : Some(generator::on_input) },
+    App { name : "motor", icon : motor::ICON, draw : motor::draw, on_input : Some(motor::on_input) },
+    App { name : "follow", icon : follower::ICON, draw : follower::draw, on_input : Some(follower::on_input) },
+    App { name : "car", icon : car::ICON, draw : car::draw, on_input : Some(car::on_input) },
+    App { name : "instrument", icon : instrument::ICON, draw : instrument::draw, on_input : Some(instrument::on_input) },
+    App { name : "attendance", icon : attendance::ICON, draw : attendance::draw, on_input : Some(attendance::on_input) },
+    App { name : "quiz", icon : quiz::ICON, draw : quiz::draw, on_input : Some(quiz::on_input) },
+    App { name : "swarm", icon : swarm::ICON, draw : swarm::draw, on_input : Some(swarm::on_input) },
+    App { name : "inbox", icon : inbox::ICON, draw : inbox::draw, on_input : Some(inbox::on_input) },
+    App { name : "scores", icon : highscores::ICON, draw : highscores::draw, on_input : Some(highscores::on_input) },
+    App { name : "presenter", icon : presenter::ICON, draw : presenter::draw, on_input : Some(presenter::on_input) },
+    App { name : "wedge", icon : wedge::ICON, draw : wedge::draw, on_input : Some(wedge::on_input) },
+    App { name : "mouse", icon : mouse::ICON, draw : mouse::draw, on_input : Some(mouse::on_input) },
+];
+
+/// The app at `index`, the first one for an index that isn't registered (any more).
+pub fn get(index : usize) -> &'static App {
+    APPS.get(index).unwrap_or(&APPS[0])
+}
+
+const CIRCLE : [(usize, usize); 8] = [(1,1), (1,2), (1,3), (2,3), (3,3), (3,2), (3,1), (2,1)];
+
+// NOTE: the circle runs either way round, picked at random every lap
+static REVERSED : AtomicBool = AtomicBool::new(false);
+
+/// One lap of a lit LED round the middle, with a fading tail behind it.
+pub fn circle(step : usize) -> Option<(Frame, u32)> {
+    if step >= CIRCLE.len() {
+        return None;
+    }
+    if step == 0 {
+        REVERSED.store(Rng.below(2) == 1, Ordering::Relaxed);
+    }
+    let at = |i : usize| if REVERSED.load(Ordering::Relaxed) { CIRCLE[CIRCLE.len() - 1 - i] } else { CIRCLE[i] };
+
+    let mut leds = [[0; 5]; 5];
+    let previous = at((step + CIRCLE.len() - 1) % CIRCLE.len());
+    let (x, y) = at(step);
+    leds[previous.0][previous.1] = 3;
+    leds[x][y] = 9;
+    Some((leds, 250))
+}
+
+const SPARKLE_ICON : Frame = [
+    [1, 0, 0, 0, 0],
+    [0, 0, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+    [0, 1, 0, 0, 0],
+    [0, 0, 0, 0, 1],
+];
+
+const SPARKLES : usize = 20;
+
+/// LEDs at random places and levels, each lit for a few steps.
+pub fn sparkle(step : usize) -> Option<(Frame, u32)> {
+    (step < SPARKLES).then(|| (effects::next(Preset::Sparkle), 150))
+}
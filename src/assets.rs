@@ -0,0 +1,51 @@
+//! The animations in `assets/`, turned into frames by build.rs, and the app playing them.
+//!
+//! Adding an animation is dropping a file into `assets/`, see `assets/README.md`. The app
+//! loops the current one, A steps to the next and B back to the previous.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::log;
+use crate::mono::Instant;
+
+pub struct Animation {
+    pub name     : &'static str,
+    pub frame_ms : u32,
+    pub frames   : &'static [Frame],
+}
+
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+pub const ICON : Frame = [
+    [1, 0, 1, 0, 1],
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+    [1, 0, 1, 0, 1],
+];
+
+static CURRENT : AtomicUsize = AtomicUsize::new(0);
+
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let Some(animation) = ANIMATIONS.get(CURRENT.load(Ordering::Relaxed)) else {
+        // NOTE: built without assets, there is nothing to loop
+        return (step == 0).then_some((ICON, 1000));
+    };
+    if step == 0 {
+        log!("animation {}", animation.name);
+    }
+    animation.frames.get(step).map(|frame| (*frame, animation.frame_ms))
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let step = match input {
+        Input::Button(Button::A) => 1,
+        Input::Button(Button::B) => ANIMATIONS.len().saturating_sub(1),
+        _ => return false,
+    };
+    let next = (CURRENT.load(Ordering::Relaxed) + step) % ANIMATIONS.len().max(1);
+    CURRENT.store(next, Ordering::Relaxed);
+    true
+}
@@ -0,0 +1,172 @@
+//! Attendance: students check in with the teacher's board over the radio, and the teacher
+//! prints the roll, see `fun_core::attendance`.
+//!
+//! `set attendance student|teacher` picks the part a board plays. In the attendance app A on
+//! a student board broadcasts its name, `SENDS` times round the radio_log loop in case one
+//! gets lost, and shows a tick for `TICK_MS`. A board with no name in the UICR, see
+//! identity.rs, checks in as its radio address in hex. The teacher keeps its receiver on
+//! whatever app runs, enters every name the first time it is heard, with the seconds since
+//! its boot, and shows how many checked in. `attendance` on the teacher's console prints the
+//! roll as CSV, `attendance clear` starts a new one.
+//!
+//! NOTE: check-ins come from every board in the group, paired or not, and the roll is in RAM,
+//! a reset loses it.
+
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+use core::sync::atomic::{AtomicU8, Ordering};
+use cortex_m::interrupt::Mutex;
+use fun_core::attendance::{self, CheckIn, Name, Roll};
+use crate::about;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::identity::Identity;
+use crate::log;
+use crate::logging::Level;
+use crate::mono::{Instant, Mono};
+use crate::protocol::Packet;
+use crate::radio::Payload;
+
+const ROLL_LEN : usize = 40;
+const SENDS : u8 = 3;
+const TICK_MS : u64 = 1000;
+const POLL_MS : u32 = 100;
+const OFF_LEVEL : u8 = 3;
+
+pub const ICON : Frame = [
+    [0, 1, 1, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 1, 1, 0],
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+];
+
+const TICK : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 9],
+    [0, 0, 0, 9, 0],
+    [9, 0, 9, 0, 0],
+    [0, 9, 0, 0, 0],
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Role {
+    Off,
+    Student,
+    Teacher,
+}
+
+const ROLES : [Role; 3] = [Role::Off, Role::Student, Role::Teacher];
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Off     => "off",
+            Role::Student => "student",
+            Role::Teacher => "teacher",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Role> {
+        ROLES.into_iter().find(|role| role.name() == name)
+    }
+
+    /// The role stored as `role as u8`.
+    pub fn from_u8(value : u8) -> Option<Role> {
+        ROLES.get(value as usize).copied()
+    }
+}
+
+static ROLE : AtomicU8 = AtomicU8::new(Role::Off as u8);
+// NOTE: the check-ins radio_log still has to send
+static SENDING : AtomicU8 = AtomicU8::new(0);
+static PRESSED : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+static ROLL : Mutex<RefCell<Roll<ROLL_LEN>>> = Mutex::new(RefCell::new(Roll::new()));
+
+fn role() -> Role {
+    Role::from_u8(ROLE.load(Ordering::Relaxed)).unwrap_or(Role::Off)
+}
+
+pub fn configure(role : Role) {
+    ROLE.store(role as u8, Ordering::Relaxed);
+    SENDING.store(0, Ordering::Relaxed);
+}
+
+/// True on the teacher's board, the radio has to listen for check-ins then.
+pub fn active() -> bool {
+    role() == Role::Teacher
+}
+
+/// The check-in with this board's name, while there are some to send.
+pub fn next_packet(identity : &Identity) -> Option<Payload> {
+    if role() != Role::Student || SENDING.load(Ordering::Relaxed) == 0 {
+        return None;
+    }
+    SENDING.fetch_sub(1, Ordering::Relaxed);
+    let mut name = Name::new();
+    match &identity.name {
+        Some(named) => { let _ = name.push_str(named); }
+        None => { let _ = write!(name, "{:04x}", identity.radio_address()); }
+    }
+    Packet::CheckIn { name : &name }.encode()
+}
+
+/// Takes a check-in from the board at `sender`, false when the packet isn't one.
+pub fn on_packet(sender : u16, packet : &Packet) -> bool {
+    let Packet::CheckIn { name } = *packet else { return false };
+    if role() != Role::Teacher {
+        return true;
+    }
+    let at_s = about::uptime_secs() as u32;
+    let checked = cortex_m::interrupt::free(|cs| ROLL.borrow(cs).borrow_mut().check_in(name, sender, at_s));
+    match checked {
+        CheckIn::New => log!("{} checked in from {:04x}", name, sender),
+        CheckIn::Full => log!(Level::Warn, "attendance roll full, {} not entered", name),
+        CheckIn::Again => (),
+    }
+    true
+}
+
+/// Calls `line` with every line of the roll as CSV, a header first, then clears it when
+/// `clear`.
+pub fn print(clear : bool, mut line : impl FnMut(&str)) {
+    line("name,address,seconds");
+    let mut text = heapless::String::<{ attendance::NAME_LEN + 24 }>::new();
+    // NOTE: an entry at a time, the serial isn't written with interrupts off
+    let entry = |i : usize| cortex_m::interrupt::free(|cs| ROLL.borrow(cs).borrow().entries().get(i).cloned());
+    for entry in (0..).map_while(entry) {
+        text.clear();
+        let _ = write!(text, "{},{:04x},{}", entry.name.as_str(), entry.address, entry.at_s);
+        line(&text);
+    }
+    if clear {
+        cortex_m::interrupt::free(|cs| ROLL.borrow(cs).borrow_mut().clear());
+    }
+}
+
+pub fn on_input(input : Input, now : Instant) -> bool {
+    match (role(), input) {
+        (Role::Student, Input::Button(Button::A)) => {
+            SENDING.store(SENDS, Ordering::Relaxed);
+            cortex_m::interrupt::free(|cs| PRESSED.borrow(cs).set(Some(now)));
+            true
+        }
+        _ => false,
+    }
+}
+
+/// A tick for a student that just checked in, the count on the teacher's board.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    let frame = match role() {
+        Role::Off => ICON.map(|row| row.map(|led| led * OFF_LEVEL)),
+        Role::Student => {
+            let pressed = cortex_m::interrupt::free(|cs| PRESSED.borrow(cs).get());
+            let ticked = pressed.is_some_and(|at| now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis()) < TICK_MS);
+            if ticked { TICK } else { ICON.map(|row| row.map(|led| led * 9)) }
+        }
+        Role::Teacher => attendance::frame(cortex_m::interrupt::free(|cs| ROLL.borrow(cs).borrow().len())),
+    };
+    (step == 0).then_some((frame, POLL_MS))
+}
@@ -0,0 +1,142 @@
+//! Authenticated commands, so only someone holding the class key can change a board, over its
+//! serial port or from anywhere within radio range.
+//!
+//! `nonce` on the console, or a `Challenge` packet addressed to the board, hands out a fresh
+//! random nonce, the radio one is broadcast back as a `Nonce` packet. The command then comes
+//! as `auth <tag> <command>` on the console, or as a `Command` packet, the tag being the first
+//! `TAG_LEN` bytes of the HMAC of the nonce and the command text, see `Seal::mac`. A nonce is
+//! good for one try, so a command recorded and played back is refused, and so is a guess
+//! after a wrong tag. The console and the radio each have their own nonce, a challenge heard
+//! over the radio leaves the one just printed on the console good.
+//!
+//! The HMAC key comes from the class key set with `key`, see seal.rs. Without one no tag
+//! checks out, so the commands needing one are refused, apart from setting the first key.
+//!
+//! Radio commands always need the tag, and are heard while the receiver is on, in listener
+//! mode, relaying or following a time master. On the console the commands that change the
+//! board (`guarded`) only need it with `set auth on`, typing them stays as it was otherwise,
+//! except for `dfu` and `ota`, which always need it.
+
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::Mutex;
+use heapless::Vec;
+use rand_core::RngCore;
+use crate::console::{Command, Line, LINE_LEN};
+use crate::logging;
+use crate::protocol::Packet;
+use crate::radio::Payload;
+use crate::rng::Rng;
+use crate::seal::Digest;
+
+pub const NONCE_LEN : usize = 8;
+pub const TAG_LEN : usize = 4;
+
+pub type Nonce = [u8; NONCE_LEN];
+pub type Tag = [u8; TAG_LEN];
+
+/// Where a nonce was asked for and its command comes from.
+#[derive(Clone, Copy)]
+pub enum Channel {
+    Console,
+    Radio,
+}
+
+impl Channel {
+    fn nonce(self) -> &'static Mutex<Cell<Option<Nonce>>> {
+        match self {
+            Channel::Console => &CONSOLE_NONCE,
+            Channel::Radio => &RADIO_NONCE,
+        }
+    }
+}
+
+static REQUIRED : AtomicBool = AtomicBool::new(false);
+// NOTE: the nonce handed out last on each channel, taken by the next command over it
+static CONSOLE_NONCE : Mutex<Cell<Option<Nonce>>> = Mutex::new(Cell::new(None));
+static RADIO_NONCE : Mutex<Cell<Option<Nonce>>> = Mutex::new(Cell::new(None));
+// NOTE: a nonce asked for over the radio, until radio_log broadcasts it
+static SENDING : Mutex<Cell<Option<Nonce>>> = Mutex::new(Cell::new(None));
+
+pub fn configure(required : bool) {
+    REQUIRED.store(required, Ordering::Relaxed);
+}
+
+/// True when `command` typed on the console needs a tag. A firmware update or a new class key
+/// always does.
+pub fn guarded(command : &Command) -> bool {
+    let changing = matches!(
+        command,
+        Command::Set(_) | Command::Knock(_) | Command::Reboot | Command::Route(Some(_)) | Command::Level(Some(_))
+            | Command::Ack | Command::Listen | Command::Send(_) | Command::Pwm(_) | Command::Follow(Some(_))
+            | Command::Beacon(Some(_)) | Command::Attendance(true) | Command::Clock(Some(_))
+    );
+    matches!(command, Command::Dfu | Command::Ota(_) | Command::Key(Some(_))) || REQUIRED.load(Ordering::Relaxed) && changing
+}
+
+/// A fresh nonce for `channel`, the one handed out there before is no good any more.
+pub fn challenge(channel : Channel) -> Nonce {
+    let mut nonce = [0; NONCE_LEN];
+    Rng.fill_bytes(&mut nonce);
+    cortex_m::interrupt::free(|cs| channel.nonce().borrow(cs).set(Some(nonce)));
+    nonce
+}
+
+/// True when `tag` authenticates `text` with the nonce handed out last on `channel`, which is
+/// used up either way. `mac` is `Seal::mac`.
+pub fn check(channel : Channel, tag : &Tag, text : &str, mac : impl FnOnce(&[u8]) -> Option<Digest>) -> bool {
+    let Some(nonce) = cortex_m::interrupt::free(|cs| channel.nonce().borrow(cs).take()) else { return false };
+    let mut data = Vec::<u8, { NONCE_LEN + LINE_LEN }>::new();
+    let _ = data.extend_from_slice(&nonce);
+    if data.extend_from_slice(text.as_bytes()).is_err() {
+        return false;
+    }
+    // NOTE: every byte compared, how long it takes tells nothing about the tag
+    mac(&data).is_some_and(|digest| digest[..TAG_LEN].iter().zip(tag).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0)
+}
+
+/// The tag of the `auth` command, from hex.
+pub fn parse_tag(hex : &str) -> Option<Tag> {
+    if hex.len() != 2 * TAG_LEN || !hex.is_ascii() {
+        return None;
+    }
+    let mut tag = [0; TAG_LEN];
+    for (byte, digits) in tag.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(core::str::from_utf8(digits).ok()?, 16).ok()?;
+    }
+    Some(tag)
+}
+
+/// The nonce asked for over the radio.
+pub fn next_packet() -> Option<Payload> {
+    let nonce = cortex_m::interrupt::free(|cs| SENDING.borrow(cs).take())?;
+    Packet::Nonce(nonce).encode()
+}
+
+/// Takes a packet, false when it isn't a challenge or command for the board at `own`.
+/// `run` gets the text of a command that passed the check.
+pub fn on_packet(
+    own : u16,
+    packet : &Packet,
+    mac : impl FnOnce(&[u8]) -> Option<Digest>,
+    run : impl FnOnce(Line),
+) -> bool {
+    match *packet {
+        Packet::Challenge { to } if to == own => {
+            let nonce = challenge(Channel::Radio);
+            cortex_m::interrupt::free(|cs| SENDING.borrow(cs).set(Some(nonce)));
+        }
+        Packet::Command { to, tag, text } if to == own => {
+            if !check(Channel::Radio, &tag, text, mac) {
+                logging::warn("radio command refused, bad tag or no nonce");
+                return true;
+            }
+            let mut line = Line::new();
+            if line.push_str(text).is_ok() {
+                run(line);
+            }
+        }
+        _ => return false,
+    }
+    true
+}
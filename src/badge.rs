@@ -0,0 +1,85 @@
+//! Name badge: the name scrolls by, over and over.
+//!
+//! A long press of B opens the editor on the first character of the name, A steps it through
+//! the characters the font has and B moves on to the next one, past the end of the name a
+//! new one. Another long press of B stores the name in the key-value store, trailing spaces
+//! dropped, see `fun_core::badge`. A name of spaces only leaves the stored one as it was.
+
+use core::cell::{Cell, RefCell};
+use cortex_m::interrupt::Mutex;
+use fun_core::badge::{self, Name, NameEntry};
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::kv;
+use crate::log;
+use crate::mono::Instant;
+use crate::scroll::{self, Text};
+
+pub const ICON : Frame = [
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 1, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+];
+
+const DEFAULT_NAME : &str = "HELLO";
+const POLL_MS : u32 = 50;
+
+// NOTE: None until the stored name is read, on the first draw
+static NAME : Mutex<RefCell<Option<Name>>> = Mutex::new(RefCell::new(None));
+// NOTE: the name being edited, input_poll and the button interrupt edit it, idle draws
+static EDITING : Mutex<Cell<Option<NameEntry>>> = Mutex::new(Cell::new(None));
+
+fn name() -> Name {
+    cortex_m::interrupt::free(|cs| {
+        let mut name = NAME.borrow(cs).borrow_mut();
+        name.get_or_insert_with(|| {
+            let mut bytes = [0; kv::MAX_VALUE_LEN];
+            kv::get(kv::BADGE_NAME, &mut bytes)
+                .and_then(|len| badge::decode(&bytes[..len]))
+                .unwrap_or_else(|| badge::decode(DEFAULT_NAME.as_bytes()).unwrap_or_default())
+        })
+        .clone()
+    })
+}
+
+fn save(entry : &NameEntry) {
+    let Some(name) = entry.name() else { return };
+    kv::set(kv::BADGE_NAME, name.as_bytes());
+    log!("badge name {}", name.as_str());
+    cortex_m::interrupt::free(|cs| *NAME.borrow(cs).borrow_mut() = Some(name));
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let editing = cortex_m::interrupt::free(|cs| EDITING.borrow(cs).get());
+    let editing = match (input, editing) {
+        (Input::LongPress(Button::B), None) => Some(NameEntry::new(&name())),
+        (Input::LongPress(Button::B), Some(entry)) => {
+            save(&entry);
+            None
+        }
+        (Input::Button(Button::A), Some(mut entry)) => {
+            entry.next_char();
+            Some(entry)
+        }
+        (Input::Button(Button::B), Some(mut entry)) => {
+            entry.advance();
+            Some(entry)
+        }
+        _ => return false,
+    };
+    cortex_m::interrupt::free(|cs| EDITING.borrow(cs).set(editing));
+    true
+}
+
+/// One lap of the name, or a frame of the editor.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    if let Some(entry) = cortex_m::interrupt::free(|cs| EDITING.borrow(cs).get()) {
+        return (step == 0).then_some((entry.frame(), POLL_MS));
+    }
+    let mut text = Text::new();
+    let _ = text.push_str(&name());
+    scroll::frames(&text).nth(step).map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
@@ -0,0 +1,82 @@
+//! Supply voltage monitoring on the SAADC's internal VDD channel.
+//!
+//! Running from USB the regulator holds VDD at about 3.3 V, on the battery pack VDD follows the
+//! cells down. Below `LOW_MV` the supply counts as low: idle toasts `ICON` over the running
+//! app every `REMINDER_MS` and the display brightness is capped at `LOW_BRIGHTNESS`.
+//!
+//! The monitor owns the SAADC, so the soil probe is read through `measure`, see soil.rs.
+
+use embedded_hal::adc::{Channel, OneShot};
+use microbit::hal::saadc::{InternalVdd, Saadc, SaadcConfig, Resolution, Oversample, Reference, Gain};
+use microbit::pac::SAADC;
+
+pub const LOW_MV : u16 = 2600;
+// NOTE: the supply only stops being low a bit above LOW_MV, so a sagging pack doesn't flap
+const RECOVERED_MV : u16 = LOW_MV + 100;
+pub const LOW_BRIGHTNESS : u8 = 3;
+pub const REMINDER_MS : u64 = 60_000;
+pub const ICON_SECS : u32 = 2;
+// NOTE: between two measurements of supply_monitor
+pub const PERIOD_SECS : u64 = 10;
+
+pub const ICON : [[u8; 5]; 5] = [
+    [0, 0, 0, 0, 0],
+    [1, 1, 1, 1, 0],
+    [1, 1, 0, 1, 1],
+    [1, 1, 1, 1, 0],
+    [0, 0, 0, 0, 0],
+];
+
+#[derive(Clone, Copy)]
+pub struct Supply {
+    pub millivolts : u16,
+    pub low        : bool,
+}
+
+impl Supply {
+    // NOTE: until the first measurement
+    pub const UNKNOWN : Supply = Supply { millivolts : 0, low : false };
+
+    /// The brightness to show with, the stored setting unless the supply is low.
+    pub fn brightness(&self, setting : u8) -> u8 {
+        if self.low {
+            setting.min(LOW_BRIGHTNESS)
+        } else {
+            setting
+        }
+    }
+}
+
+pub struct Monitor {
+    saadc  : Saadc,
+    supply : Supply,
+}
+
+impl Monitor {
+    pub fn new(saadc : SAADC) -> Self {
+        // NOTE: 0.6 V internal reference with 1/6 gain, full scale is 3.6 V
+        let config = SaadcConfig {
+            resolution : Resolution::_12BIT,
+            oversample : Oversample::OVER8X,
+            reference  : Reference::INTERNAL,
+            gain       : Gain::GAIN1_6,
+            ..SaadcConfig::default()
+        };
+        Monitor { saadc : Saadc::new(saadc, config), supply : Supply::UNKNOWN }
+    }
+
+    /// Measures VDD, blocks for the few tens of µs the conversion takes.
+    pub fn sample(&mut self) -> Supply {
+        if let Ok(raw) = self.saadc.read(&mut InternalVdd) {
+            let millivolts = (raw.max(0) as u32 * 3600 / 4096) as u16;
+            let low = if self.supply.low { millivolts < RECOVERED_MV } else { millivolts < LOW_MV };
+            self.supply = Supply { millivolts, low };
+        }
+        self.supply
+    }
+
+    /// Measures the analog input `pin` in 12 bits of 3.6 V, None when the conversion failed.
+    pub fn measure<PIN : Channel<Saadc, ID = u8>>(&mut self, pin : &mut PIN) -> Option<u16> {
+        self.saadc.read(pin).ok().map(|raw| raw.max(0) as u16)
+    }
+}
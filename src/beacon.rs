@@ -0,0 +1,186 @@
+//! BLE beacon: the board advertises itself as an iBeacon or an Eddystone beacon, see
+//! `fun_core::beacon`, so any beacon app on a phone finds it.
+//!
+//! `set beacon ibeacon|uid|url` picks the frame, radio_log sends it every `PERIOD_MS` through
+//! `Radio::advertise`. The iBeacon UUID is the beacon id, its major number the radio group
+//! and its minor the board's radio address. The Eddystone-UID namespace is the first 10
+//! bytes of the id, its instance the board's device address. `beacon id <32 hex digits>` and
+//! `beacon url <url>` on the console change the id and the URL, the key-value store keeps
+//! them. `env` advertises the latest temperature and supply telemetry.rs has and the light
+//! level supply_monitor senses with the LED matrix while it is on, see light.rs, as
+//! Environmental Sensing service data.
+//!
+//! NOTE: the request was the Environmental Sensing Service over GATT, its characteristics
+//! read and notified over a connection. That is a known gap: there is no BLE stack to take
+//! connections, see `fun_core::keyboard`, so phones only get the readings advertised.
+
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicU8, Ordering};
+use cortex_m::interrupt::Mutex;
+use fun_core::beacon::{self, PDU_LEN};
+use heapless::{String, Vec};
+use crate::kv;
+use crate::mono::Instant;
+use crate::telemetry;
+
+const PERIOD_MS : u64 = 500;
+pub const ID_LEN : usize = 16;
+// NOTE: "microbitrtic-fun" in ascii
+const DEFAULT_ID : [u8; ID_LEN] = *b"microbitrtic-fun";
+const DEFAULT_URL : &str = "https://microbit.org";
+
+pub type Url = String<{ kv::MAX_VALUE_LEN }>;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Off,
+    IBeacon,
+    Uid,
+    Url,
+    Environment,
+}
+
+const KINDS : [Kind; 5] = [Kind::Off, Kind::IBeacon, Kind::Uid, Kind::Url, Kind::Environment];
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Off         => "off",
+            Kind::IBeacon     => "ibeacon",
+            Kind::Uid         => "uid",
+            Kind::Url         => "url",
+            Kind::Environment => "env",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Kind> {
+        KINDS.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// The kind stored as `kind as u8`.
+    pub fn from_u8(value : u8) -> Option<Kind> {
+        KINDS.get(value as usize).copied()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Change<'a> {
+    Id([u8; ID_LEN]),
+    Url(&'a str),
+}
+
+impl<'a> Change<'a> {
+    /// The change of `beacon <name> <value>`, None for an id that isn't 32 hex digits or a
+    /// URL Eddystone can't carry.
+    pub fn parse(name : &str, value : &'a str) -> Option<Change<'a>> {
+        match name {
+            "id" if value.len() == 2 * ID_LEN && value.is_ascii() => {
+                let mut id = [0; ID_LEN];
+                for (byte, digits) in id.iter_mut().zip(value.as_bytes().chunks(2)) {
+                    *byte = u8::from_str_radix(core::str::from_utf8(digits).ok()?, 16).ok()?;
+                }
+                Some(Change::Id(id))
+            }
+            "url" if value.len() <= kv::MAX_VALUE_LEN && beacon::encode_url(value).is_some() => Some(Change::Url(value)),
+            _ => None,
+        }
+    }
+}
+
+struct Stored {
+    id  : [u8; ID_LEN],
+    url : Url,
+}
+
+static KIND : AtomicU8 = AtomicU8::new(Kind::Off as u8);
+// NOTE: None until the stored id and URL are read, on the first use
+static STORED : Mutex<RefCell<Option<Stored>>> = Mutex::new(RefCell::new(None));
+static SENT : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+// NOTE: the light level sensed last, see light.rs
+static LIGHT : Mutex<Cell<Option<u8>>> = Mutex::new(Cell::new(None));
+
+pub fn configure(kind : Kind) {
+    KIND.store(kind as u8, Ordering::Relaxed);
+}
+
+fn kind() -> Kind {
+    Kind::from_u8(KIND.load(Ordering::Relaxed)).unwrap_or(Kind::Off)
+}
+
+/// True while the light level is advertised, sensing it blanks the display for a moment.
+pub fn wants_light() -> bool {
+    kind() == Kind::Environment
+}
+
+pub fn record_light(level : Option<u8>) {
+    cortex_m::interrupt::free(|cs| LIGHT.borrow(cs).set(level));
+}
+
+fn stored<T>(read : impl FnOnce(&Stored) -> T) -> T {
+    cortex_m::interrupt::free(|cs| {
+        let mut stored = STORED.borrow(cs).borrow_mut();
+        let stored = stored.get_or_insert_with(|| {
+            let mut bytes = [0; kv::MAX_VALUE_LEN];
+            let id = kv::get(kv::BEACON_ID, &mut bytes)
+                .and_then(|len| bytes[..len].try_into().ok())
+                .unwrap_or(DEFAULT_ID);
+            let url = kv::get(kv::BEACON_URL, &mut bytes)
+                .and_then(|len| core::str::from_utf8(&bytes[..len]).ok())
+                .and_then(|url| Url::try_from(url).ok())
+                .unwrap_or_else(|| Url::try_from(DEFAULT_URL).unwrap_or_default());
+            Stored { id, url }
+        });
+        read(stored)
+    })
+}
+
+/// The kind, the id and the URL, for the console.
+pub fn status() -> (Kind, [u8; ID_LEN], Url) {
+    stored(|stored| (kind(), stored.id, stored.url.clone()))
+}
+
+pub fn change(change : Change) {
+    match change {
+        Change::Id(id) => kv::set(kv::BEACON_ID, &id),
+        Change::Url(url) => kv::set(kv::BEACON_URL, url.as_bytes()),
+    }
+    // NOTE: read again from the store on the next use
+    cortex_m::interrupt::free(|cs| STORED.borrow(cs).replace(None));
+}
+
+/// The advertisement once `PERIOD_MS` passed since the last, from the board with the
+/// device `address` in radio `group`.
+pub fn next_pdu(now : Instant, group : u8, address : [u8; 6]) -> Option<Vec<u8, PDU_LEN>> {
+    let kind = kind();
+    if kind == Kind::Off {
+        return None;
+    }
+    let due = cortex_m::interrupt::free(|cs| {
+        let sent = SENT.borrow(cs);
+        let due = sent.get().is_none_or(|at| now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis()) >= PERIOD_MS);
+        if due {
+            sent.set(Some(now));
+        }
+        due
+    });
+    if !due {
+        return None;
+    }
+    let data = stored(|stored| match kind {
+        Kind::IBeacon => Some(beacon::ibeacon(&stored.id, group as u16, u16::from_le_bytes([address[0], address[1]]))),
+        Kind::Uid => {
+            let mut namespace = [0; 10];
+            namespace.copy_from_slice(&stored.id[..10]);
+            Some(beacon::eddystone_uid(&namespace, &address))
+        }
+        Kind::Url => beacon::eddystone_url(&stored.url),
+        Kind::Environment => {
+            let latest = telemetry::latest();
+            let light = cortex_m::interrupt::free(|cs| LIGHT.borrow(cs).get());
+            // NOTE: a supply of 0 mV wasn't measured yet
+            Some(beacon::environment(latest.temperature, (latest.supply_mv > 0).then_some(latest.supply_mv), light))
+        }
+        Kind::Off => None,
+    })?;
+    Some(beacon::pdu(beacon::static_address(address), &data))
+}
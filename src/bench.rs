@@ -0,0 +1,124 @@
+//! Frame-render benchmark, for comparing display drivers (`bench` on the console).
+//!
+//! The `render_bench` task shows `FRAMES` frames of each synthetic workload back to back and
+//! logs what it measured: the frame rate, the CPU cycles spent making a frame and showing it,
+//! and the worst-case jitter, the spread between the shortest and the longest frame period.
+//! Timing is on the DWT cycle counter, every frame is shown for `FRAME_MS`, so the cycles a
+//! driver spends beyond that are its overhead: loading the PWM sequences with the default
+//! driver, multiplexing in software with `software_display`.
+
+use cortex_m::peripheral::{DCB, DWT};
+use crate::display::Frame;
+use crate::font;
+
+pub const FRAMES : u32 = 200;
+pub const FRAME_MS : u32 = 10;
+const CYCLES_PER_US : u32 = 64;
+
+#[derive(Clone, Copy)]
+pub enum Workload {
+    // NOTE: every LED fully lit, the most a frame can ask of the driver
+    Solid,
+    // NOTE: a moving ramp through all ten levels
+    Greyscale,
+    // NOTE: a character a column at a time, like scrolling text
+    Scroll,
+}
+
+pub const WORKLOADS : [Workload; 3] = [Workload::Solid, Workload::Greyscale, Workload::Scroll];
+
+impl Workload {
+    pub fn name(self) -> &'static str {
+        match self {
+            Workload::Solid     => "solid",
+            Workload::Greyscale => "greyscale",
+            Workload::Scroll    => "scroll",
+        }
+    }
+
+    pub fn frame(self, step : u32) -> Frame {
+        let mut frame = [[0; 5]; 5];
+        match self {
+            Workload::Solid => frame = [[9; 5]; 5],
+            Workload::Greyscale => {
+                for (y, row) in frame.iter_mut().enumerate() {
+                    for (x, led) in row.iter_mut().enumerate() {
+                        *led = ((x + y) as u32 + step) as u8 % 10;
+                    }
+                }
+            }
+            Workload::Scroll => {
+                let glyph = font::frame_of((b'A' + (step / 6 % 26) as u8) as char);
+                let shift = (step % 6) as usize;
+                for (row, glyph_row) in frame.iter_mut().zip(glyph) {
+                    for (x, led) in row.iter_mut().enumerate() {
+                        *led = glyph_row.get(x + shift).map_or(0, |lit| lit * 9);
+                    }
+                }
+            }
+        }
+        frame
+    }
+}
+
+/// Starts the cycle counter, once from init.
+pub fn enable(dcb : &mut DCB, dwt : &mut DWT) {
+    dcb.enable_trace();
+    dwt.enable_cycle_counter();
+}
+
+pub fn cycles() -> u32 {
+    DWT::cycle_count()
+}
+
+pub struct Stats {
+    frames        : u32,
+    render_cycles : u64,
+    show_cycles   : u64,
+    // NOTE: in cycles, between the starts of consecutive frames
+    total_period  : u64,
+    min_period    : u32,
+    max_period    : u32,
+}
+
+impl Stats {
+    pub const fn new() -> Self {
+        Stats { frames : 0, render_cycles : 0, show_cycles : 0, total_period : 0, min_period : u32::MAX, max_period : 0 }
+    }
+
+    /// A frame that took `render` cycles to make and `show` to show, `period` cycles after
+    /// the one before it, or None for the first one.
+    pub fn record(&mut self, render : u32, show : u32, period : Option<u32>) {
+        self.frames += 1;
+        self.render_cycles += render as u64;
+        self.show_cycles += show as u64;
+        if let Some(period) = period {
+            self.total_period += period as u64;
+            self.min_period = self.min_period.min(period);
+            self.max_period = self.max_period.max(period);
+        }
+    }
+
+    /// Frames a second in tenths.
+    pub fn fps_tenths(&self) -> u32 {
+        let periods = self.frames.saturating_sub(1) as u64;
+        if self.total_period == 0 {
+            return 0;
+        }
+        (periods * 10 * 1_000_000 * CYCLES_PER_US as u64 / self.total_period) as u32
+    }
+
+    pub fn render_cycles(&self) -> u32 {
+        (self.render_cycles / self.frames.max(1) as u64) as u32
+    }
+
+    /// The cycles a show took beyond the frame's own duration, on average.
+    pub fn driver_cycles(&self) -> u32 {
+        let shown = (self.show_cycles / self.frames.max(1) as u64) as u32;
+        shown.saturating_sub(FRAME_MS * 1000 * CYCLES_PER_US)
+    }
+
+    pub fn jitter_us(&self) -> u32 {
+        self.max_period.saturating_sub(self.min_period) / CYCLES_PER_US
+    }
+}
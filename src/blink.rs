@@ -0,0 +1,57 @@
+//! Blinks the microphone LED without the CPU: TIMER1's compare event is wired over PPI to a
+//! GPIOTE task that toggles the pin, once started nothing runs in software.
+//!
+//! NOTE: that is the red LED next to the microphone, lit by the pin that powers the
+//! microphone. The matrix LEDs don't work for this, the display multiplexes them.
+
+use microbit::hal::gpio::{Pin, Output, OpenDrain};
+use microbit::hal::gpiote::Gpiote;
+use microbit::pac::{GPIOTE, TIMER1};
+use crate::channels::{self, Exhausted, GpioteChannel, PpiChannel};
+use crate::ppi;
+
+// NOTE: in 1 MHz timer ticks, the LED toggles twice per blink
+const HALF_PERIOD : u32 = 500_000;
+
+pub struct Blink {
+    timer  : TIMER1,
+    pin    : GpioteChannel,
+    wiring : PpiChannel,
+    on     : bool,
+}
+
+impl Blink {
+    pub fn new(timer : TIMER1, gpiote : &Gpiote, led : Pin<Output<OpenDrain>>) -> Result<Self, Exhausted> {
+        let (pin, wiring) = (channels::gpiote("blink")?, channels::ppi("blink")?);
+        timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+        timer.mode.write(|w| w.mode().timer());
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.prescaler.write(|w| unsafe { w.prescaler().bits(4) });
+        timer.cc[0].write(|w| unsafe { w.bits(HALF_PERIOD) });
+        timer.shorts.write(|w| w.compare0_clear().enabled());
+
+        let channel = pin.of(gpiote);
+        channel.output_pin(led).init_low();
+        ppi::connect(wiring, &timer.events_compare[0], channel.task_out());
+
+        Ok(Blink { timer, pin, wiring, on : false })
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    pub fn set(&mut self, on : bool) {
+        self.on = on;
+        if on {
+            self.timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+            ppi::enable(self.wiring);
+            self.timer.tasks_start.write(|w| unsafe { w.bits(1) });
+        } else {
+            self.timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+            ppi::disable(self.wiring);
+            // NOTE: the gpiote resource belongs to the button interrupt, turning the LED off is all that is done here
+            unsafe { (*GPIOTE::ptr()).tasks_clr[self.pin.index()].write(|w| w.bits(1)) };
+        }
+    }
+}
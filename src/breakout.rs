@@ -0,0 +1,76 @@
+//! Breakout: a two LED paddle on the bottom row keeps a ball bouncing into the bricks of the
+//! top rows, see `fun_core::breakout` for the game.
+//!
+//! A and B move the paddle. When the ball drops past it rain falls on the bricks left, then
+//! the bricks cleared go to the high-score table.
+
+use core::cell::Cell;
+use critical_section::Mutex;
+use fun_core::breakout::{Control, Event, Game};
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::effects::{self, Preset};
+use crate::events::Button;
+use crate::highscores::{self, GameId};
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::rng::Rng;
+use crate::speaker::Speaker;
+
+pub const GAME : GameId = 3;
+
+pub const ICON : Frame = [
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 0, 0],
+];
+
+// NOTE: the button interrupt and idle both move the game on
+static GAME_STATE : Mutex<Cell<Option<Game>>> = Mutex::new(Cell::new(None));
+
+fn update(next : impl FnOnce(Option<Game>) -> Option<Game>) -> Option<Game> {
+    critical_section::with(|cs| {
+        let game = next(GAME_STATE.borrow(cs).get());
+        GAME_STATE.borrow(cs).set(game);
+        game
+    })
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let control = match input {
+        Input::Button(Button::A) => Control::Left,
+        Input::Button(Button::B) => Control::Right,
+        _ => return false,
+    };
+    let mut taken = false;
+    update(|game| {
+        let mut game = game?;
+        taken = game.control(control);
+        Some(game)
+    });
+    taken
+}
+
+/// One game, from the first serve to the scrolled score.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let game = update(|game| Some(match game {
+        Some(mut game) if step > 0 => {
+            match game.advance(&Mono, &mut Rng, &mut Speaker) {
+                Some(Event::Cleared) => log!("breakout wall {} cleared", game.walls()),
+                Some(Event::Lost) => log!("breakout over with {} bricks", game.score()),
+                None => (),
+            }
+            game
+        }
+        // NOTE: a new game, or the launcher cut the last one short
+        _ => Game::new(&Mono, &mut Rng),
+    }))?;
+
+    let frame = game.frame(&Mono, &mut Speaker, |elapsed| effects::at(Preset::Rain, elapsed));
+    if frame.is_none() {
+        highscores::finish(GAME, game.score());
+    }
+    frame
+}
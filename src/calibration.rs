@@ -0,0 +1,72 @@
+//! Keeps the RTC on time across temperature swings.
+//!
+//! The micro:bit has no 32 kHz crystal, the LFCLK clocking the RTC (and with it `Mono` and
+//! everything timed by it) comes from the internal RC oscillator, which drifts with
+//! temperature. The CLOCK peripheral can calibrate the RC oscillator against the crystal
+//! clocked HFCLK, Nordic recommends doing that whenever the temperature moved by 0.5 °C.
+//! `drift_ppm` measures how far the RTC is off against TIMER4, which runs from the HFCLK as
+//! well, so the log shows the correction every calibration applied.
+//!
+//! NOTE: the RTC prescaler only divides by whole numbers, changing it would change the tick
+//! rate `Mono` counts in instead of trimming it, so the oscillator is trimmed instead.
+
+use microbit::hal::temp::Temp;
+use microbit::pac::{CLOCK, TEMP, TIMER4};
+
+// NOTE: in quarter degrees, the resolution of the TEMP sensor
+const THRESHOLD : i32 = 2;
+
+pub struct Calibration {
+    temp  : Temp,
+    timer : TIMER4,
+    // NOTE: the temperature of the last calibration in quarter degrees
+    calibrated_at : Option<i32>,
+}
+
+impl Calibration {
+    pub fn new(temp : TEMP, timer : TIMER4) -> Self {
+        // NOTE: free running at 1 MHz
+        timer.mode.write(|w| w.mode().timer());
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.prescaler.write(|w| unsafe { w.prescaler().bits(4) });
+        timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        timer.tasks_start.write(|w| unsafe { w.bits(1) });
+        Calibration { temp : Temp::new(temp), timer, calibrated_at : None }
+    }
+
+    /// Die temperature in quarter degrees Celsius, blocks for the 36 µs the measurement takes.
+    pub fn temperature(&mut self) -> i32 {
+        self.temp.measure().to_bits()
+    }
+
+    /// HFCLK time in µs, wraps around.
+    pub fn now_us(&self) -> u32 {
+        self.timer.tasks_capture[0].write(|w| unsafe { w.bits(1) });
+        self.timer.cc[0].read().bits()
+    }
+
+    /// How much faster than the HFCLK the RTC ran, in parts per million, given the
+    /// `now_us` from when it started counting `rtc_us`.
+    pub fn drift_ppm(&self, since_us : u32, rtc_us : u32) -> i32 {
+        let elapsed = self.now_us().wrapping_sub(since_us).max(1) as i64;
+        ((rtc_us as i64 - elapsed) * 1_000_000 / elapsed) as i32
+    }
+
+    /// True when the temperature moved far enough since the last calibration, or there
+    /// hasn't been one yet.
+    pub fn due(&self, temperature : i32) -> bool {
+        self.calibrated_at.is_none_or(|at| (temperature - at).abs() >= THRESHOLD)
+    }
+
+    /// Calibrates the RC oscillator, blocks for the few ms it takes.
+    pub fn calibrate(&mut self, temperature : i32) {
+        // NOTE: the board struct's CLOCK went into Clocks in init, only the calibration
+        // registers are touched here
+        let clock = unsafe { &*CLOCK::ptr() };
+        clock.events_done.reset();
+        clock.tasks_cal.write(|w| unsafe { w.bits(1) });
+        while clock.events_done.read().bits() == 0 {}
+        clock.events_done.reset();
+        self.calibrated_at = Some(temperature);
+    }
+}
@@ -0,0 +1,167 @@
+//! Radio controlled car: the tilt of one board drives the motors of its peer, see motor.rs and
+//! pairing.rs.
+//!
+//! `set car controller|vehicle` picks the part a board plays. In the car app the controller
+//! turns its tilt into a drive, like the motor app with `tilt`, and sends it to the peer every
+//! time round the radio_log loop. The vehicle keeps its receiver on while the app is on screen
+//! and drives the motors with what comes in. Nothing heard for `FAILSAFE_MS` and it stops
+//! them, so a car out of range or a controller gone quiet doesn't run off. Both show the drive
+//! as the motor app does, the vehicle dim while it hears nothing, and with the car off the
+//! icon is dim.
+//!
+//! NOTE: the vehicle needs the motors on, `set motor tilt` or `remote`, which hands them their
+//! pins.
+
+use core::cell::Cell;
+use core::sync::atomic::{AtomicU8, Ordering};
+use cortex_m::interrupt::Mutex;
+use fun_core::drive::{self, Drive};
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::log;
+use crate::logging::Level;
+use crate::mono::{Instant, Mono};
+use crate::motor;
+use crate::protocol::Packet;
+use crate::radio::Payload;
+
+const FAILSAFE_MS : u64 = 500;
+const ACTIVE_MS : u64 = 500;
+const POLL_MS : u32 = 50;
+const LOST_LEVEL : u8 = 3;
+
+pub const ICON : Frame = [
+    [0, 1, 1, 1, 0],
+    [1, 1, 0, 1, 1],
+    [1, 1, 1, 1, 1],
+    [1, 1, 0, 1, 1],
+    [0, 1, 1, 1, 0],
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Role {
+    Off,
+    Controller,
+    Vehicle,
+}
+
+const ROLES : [Role; 3] = [Role::Off, Role::Controller, Role::Vehicle];
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Off        => "off",
+            Role::Controller => "controller",
+            Role::Vehicle    => "vehicle",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Role> {
+        ROLES.into_iter().find(|role| role.name() == name)
+    }
+
+    /// The role stored as `role as u8`.
+    pub fn from_u8(value : u8) -> Option<Role> {
+        ROLES.get(value as usize).copied()
+    }
+}
+
+static ROLE : AtomicU8 = AtomicU8::new(Role::Off as u8);
+// NOTE: the drive sent on the controller, the one heard last on the vehicle
+static DRIVE : Mutex<Cell<Drive>> = Mutex::new(Cell::new(Drive::STOP));
+static HEARD : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+static DRAWN : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+fn role() -> Role {
+    Role::from_u8(ROLE.load(Ordering::Relaxed)).unwrap_or(Role::Off)
+}
+
+pub fn configure(role : Role) {
+    ROLE.store(role as u8, Ordering::Relaxed);
+    cortex_m::interrupt::free(|cs| {
+        DRIVE.borrow(cs).set(Drive::STOP);
+        HEARD.borrow(cs).set(None);
+    });
+}
+
+fn active(now : Instant) -> bool {
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).get()).is_some_and(|drawn| ms_since(now, drawn) < ACTIVE_MS)
+}
+
+/// True while the vehicle's app is on screen, the radio has to listen for the drive then.
+pub fn listening(now : Instant) -> bool {
+    role() == Role::Vehicle && active(now)
+}
+
+fn linked(now : Instant) -> bool {
+    cortex_m::interrupt::free(|cs| HEARD.borrow(cs).get()).is_some_and(|heard| ms_since(now, heard) < FAILSAFE_MS)
+}
+
+/// The drive, while the controller's app is on screen.
+pub fn next_packet(now : Instant) -> Option<Payload> {
+    if role() != Role::Controller || !active(now) {
+        return None;
+    }
+    let drive = cortex_m::interrupt::free(|cs| DRIVE.borrow(cs).get());
+    Packet::Drive { throttle : drive.throttle, steering : drive.steering }.encode()
+}
+
+/// Takes a packet from the peer, false when it isn't a drive.
+pub fn on_packet(packet : &Packet, now : Instant) -> bool {
+    let Packet::Drive { throttle, steering } = *packet else { return false };
+    if !listening(now) {
+        return true;
+    }
+    // NOTE: kept within full speed, whatever came in
+    let drive = Drive { throttle, steering }.step(0, 0);
+    cortex_m::interrupt::free(|cs| {
+        DRIVE.borrow(cs).set(drive);
+        HEARD.borrow(cs).set(Some(now));
+    });
+    motor::set(drive);
+    true
+}
+
+/// Stops the vehicle's motors once nothing was heard for `FAILSAFE_MS`, call it every input
+/// poll.
+pub fn watch(now : Instant) {
+    if role() != Role::Vehicle || linked(now) || motor::driving() == Drive::STOP {
+        return;
+    }
+    motor::set(Drive::STOP);
+    cortex_m::interrupt::free(|cs| DRIVE.borrow(cs).set(Drive::STOP));
+    log!(Level::Warn, "car link lost, motors stopped");
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    match (role(), input) {
+        (Role::Controller, Input::Tilt { x, y }) => {
+            cortex_m::interrupt::free(|cs| DRIVE.borrow(cs).set(Drive::from_tilt(x, y)));
+            true
+        }
+        _ => false,
+    }
+}
+
+/// The drive sent or heard, as the speeds of both motors.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).set(Some(now)));
+    let role = role();
+    let linked = linked(now);
+    if role == Role::Vehicle && linked {
+        motor::hold(now);
+    }
+    let (left, right) = cortex_m::interrupt::free(|cs| DRIVE.borrow(cs).get()).speeds();
+    let frame = drive::gauge(left, right);
+    let frame = match role {
+        Role::Off => ICON.map(|row| row.map(|led| led * LOST_LEVEL)),
+        Role::Vehicle if !linked => frame.map(|row| row.map(|led| led.min(LOST_LEVEL))),
+        _ => frame,
+    };
+    (step == 0).then_some((frame, POLL_MS))
+}
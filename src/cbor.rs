@@ -0,0 +1,37 @@
+//! The pieces of CBOR (RFC 8949) the firmware writes: unsigned integers, text strings and
+//! arrays, into a `heapless::Vec`.
+//!
+//! NOTE: always the shortest head for a value, the way RFC 8949 calls deterministic.
+
+use heapless::Vec;
+
+const UNSIGNED : u8 = 0;
+const TEXT : u8 = 3;
+const ARRAY : u8 = 4;
+
+fn head<const N : usize>(bytes : &mut Vec<u8, N>, major : u8, value : u64) {
+    let be = value.to_be_bytes();
+    let (info, extra) : (u8, &[u8]) = match value {
+        0..=23                 => (value as u8, &[]),
+        24..=0xff              => (24, &be[7..]),
+        0x100..=0xffff         => (25, &be[6..]),
+        0x1_0000..=0xffff_ffff => (26, &be[4..]),
+        _                      => (27, &be[..]),
+    };
+    let _ = bytes.push(major << 5 | info);
+    let _ = bytes.extend_from_slice(extra);
+}
+
+pub fn unsigned<const N : usize>(bytes : &mut Vec<u8, N>, value : u64) {
+    head(bytes, UNSIGNED, value);
+}
+
+pub fn text<const N : usize>(bytes : &mut Vec<u8, N>, text : &str) {
+    head(bytes, TEXT, text.len() as u64);
+    let _ = bytes.extend_from_slice(text.as_bytes());
+}
+
+/// The head of an array of `len` items, the items follow it.
+pub fn array<const N : usize>(bytes : &mut Vec<u8, N>, len : usize) {
+    head(bytes, ARRAY, len as u64);
+}
@@ -0,0 +1,98 @@
+//! Who holds which GPIOTE and PPI channel.
+//!
+//! A feature claims its channels at init, under its name, and keeps the handle it gets
+//! instead of a hard-coded channel number, so two features can't end up on the same channel.
+//! When they are all taken the claim fails with an `Exhausted` that names the feature and
+//! the ones holding the channels, init unwraps it, so the crash report tells what to drop.
+//!
+//! NOTE: channels are never given back, the features hold them for as long as the board runs.
+
+use core::cell::RefCell;
+use core::fmt;
+use cortex_m::interrupt::Mutex;
+use heapless::Vec;
+use microbit::hal::gpiote::{self, Gpiote};
+
+pub const GPIOTE_CHANNELS : usize = 8;
+// NOTE: the channels above are preprogrammed, they can't be wired
+pub const PPI_CHANNELS : usize = 20;
+
+struct Registry<const N : usize> {
+    owners : [Option<&'static str>; N],
+}
+
+impl<const N : usize> Registry<N> {
+    const fn new() -> Self {
+        Registry { owners : [None; N] }
+    }
+
+    fn claim(&mut self, kind : &'static str, owner : &'static str) -> Result<usize, Exhausted> {
+        let Some(index) = self.owners.iter().position(Option::is_none) else {
+            let owners = self.owners.iter().flatten().copied().collect();
+            return Err(Exhausted { kind, wanted : owner, owners });
+        };
+        self.owners[index] = Some(owner);
+        Ok(index)
+    }
+}
+
+static GPIOTE : Mutex<RefCell<Registry<GPIOTE_CHANNELS>>> = Mutex::new(RefCell::new(Registry::new()));
+static PPI : Mutex<RefCell<Registry<PPI_CHANNELS>>> = Mutex::new(RefCell::new(Registry::new()));
+
+/// The channels of one kind ran out.
+pub struct Exhausted {
+    kind   : &'static str,
+    wanted : &'static str,
+    owners : Vec<&'static str, PPI_CHANNELS>,
+}
+
+// NOTE: Debug, it is what unwrap prints
+impl fmt::Debug for Exhausted {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no {} channel left for {}, held by", self.kind, self.wanted)?;
+        for owner in &self.owners {
+            write!(f, " {}", owner)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct GpioteChannel(usize);
+
+impl GpioteChannel {
+    pub fn index(self) -> usize {
+        self.0
+    }
+
+    /// The HAL's handle of the channel.
+    pub fn of(self, gpiote : &Gpiote) -> gpiote::GpioteChannel<'_> {
+        match self.0 {
+            0 => gpiote.channel0(),
+            1 => gpiote.channel1(),
+            2 => gpiote.channel2(),
+            3 => gpiote.channel3(),
+            4 => gpiote.channel4(),
+            5 => gpiote.channel5(),
+            6 => gpiote.channel6(),
+            _ => gpiote.channel7(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct PpiChannel(usize);
+
+impl PpiChannel {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+pub fn gpiote(owner : &'static str) -> Result<GpioteChannel, Exhausted> {
+    cortex_m::interrupt::free(|cs| GPIOTE.borrow(cs).borrow_mut().claim("GPIOTE", owner)).map(GpioteChannel)
+}
+
+pub fn ppi(owner : &'static str) -> Result<PpiChannel, Exhausted> {
+    cortex_m::interrupt::free(|cs| PPI.borrow(cs).borrow_mut().claim("PPI", owner)).map(PpiChannel)
+}
@@ -0,0 +1,24 @@
+//! The time of day, set from the console with `clock <hh:mm>`, for the quiet hours of dnd.rs.
+//!
+//! NOTE: the micro:bit keeps no time over a reset, the RTC behind `Mono` counts from the
+//! reset. The time set is kept in RAM as the minute of the day at an instant, so a reset
+//! forgets it and it goes as fast as the 32 kHz crystal, off by a few seconds a day.
+
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+use fun_core::quiet::DAY_MIN;
+use crate::mono::Instant;
+
+// NOTE: the minute of the day it was at that instant
+static SET : Mutex<Cell<Option<(Instant, u16)>>> = Mutex::new(Cell::new(None));
+
+pub fn set(minute : u16, now : Instant) {
+    cortex_m::interrupt::free(|cs| SET.borrow(cs).set(Some((now, minute % DAY_MIN))));
+}
+
+/// The minute of the day at `now`, None until the clock is set.
+pub fn minute(now : Instant) -> Option<u16> {
+    let (at, minute) = cortex_m::interrupt::free(|cs| SET.borrow(cs).get())?;
+    let elapsed_min = now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_secs()) / 60;
+    Some(((minute as u64 + elapsed_min) % DAY_MIN as u64) as u16)
+}
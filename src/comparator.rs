@@ -0,0 +1,57 @@
+//! Threshold events for an analog signal on ring 2, from the low power comparator.
+//!
+//! A light sensor or potentiometer on ring 2 is compared against a fraction of VDD, set in
+//! sixteenths with `set threshold`. The comparator raises an interrupt when the signal crosses
+//! it either way, so nothing polls the SAADC, and hysteresis keeps a noisy signal from
+//! raising a burst of them.
+//!
+//! NOTE: the comparator is also set up to wake the chip from System OFF on a crossing.
+
+use microbit::hal::gpio::{p0::P0_04, Input, Floating};
+use microbit::hal::lpcomp::{LpComp, VRef, Transition, CompResult};
+use microbit::pac::LPCOMP;
+
+pub const DEFAULT_SIXTEENTHS : u8 = 8;
+
+// NOTE: indexed by sixteenths - 1, the even ones are the eighths
+const REFERENCES : [VRef; 15] = [
+    VRef::_1_16Vdd, VRef::_1_8Vdd, VRef::_3_16Vdd, VRef::_2_8Vdd, VRef::_5_16Vdd,
+    VRef::_3_8Vdd, VRef::_7_16Vdd, VRef::_4_8Vdd, VRef::_9_16Vdd, VRef::_5_8Vdd,
+    VRef::_11_16Vdd, VRef::_6_8Vdd, VRef::_13_16Vdd, VRef::_7_8Vdd, VRef::_15_16Vdd,
+];
+
+pub struct Comparator {
+    lpcomp : LpComp,
+    _pin   : P0_04<Input<Floating>>,
+}
+
+impl Comparator {
+    pub fn new(lpcomp : LPCOMP, pin : P0_04<Input<Floating>>, sixteenths : u8) -> Self {
+        let lpcomp = LpComp::new(lpcomp, &pin);
+        lpcomp.hysteresis(true)
+            .analog_detect(Transition::Cross)
+            .enable_interrupt(Transition::Cross);
+        let mut comparator = Comparator { lpcomp, _pin : pin };
+        comparator.set_threshold(sixteenths);
+        comparator
+    }
+
+    /// 1-15 sixteenths of VDD, anything else is clamped.
+    pub fn set_threshold(&mut self, sixteenths : u8) {
+        // NOTE: the reference can only be changed while the comparator is off
+        self.lpcomp.disable();
+        self.lpcomp.vref(REFERENCES[sixteenths.clamp(1, 15) as usize - 1]);
+        self.lpcomp.reset_events();
+        self.lpcomp.enable();
+    }
+
+    /// Whether the signal crossed the threshold, and which side it ended up at,
+    /// call it from the COMP_LPCOMP interrupt.
+    pub fn on_interrupt(&mut self) -> Option<bool> {
+        if !self.lpcomp.is_cross() {
+            return None;
+        }
+        self.lpcomp.reset_events();
+        Some(self.lpcomp.read() == CompResult::Above)
+    }
+}
@@ -0,0 +1,478 @@
+//! Line based command console on the UART that the interface chip exposes over USB
+//! (`make serial`). Bytes are collected into lines by the UARTE interrupt, complete
+//! lines are parsed here and executed by the `console_command` task.
+
+use core::fmt::Write;
+use embedded_hal::serial;
+use fun_core::knock::Pattern;
+use fun_core::quiet::{self, Hours};
+use fun_core::wave::Wave;
+use heapless::String;
+use microbit::hal::uarte::UarteTx;
+use microbit::pac::UARTE0;
+use crate::auth::{self, Tag};
+use crate::events::Format;
+use crate::attendance;
+use crate::beacon;
+use crate::car;
+use crate::facedown;
+use crate::follower::Tune;
+use crate::logging::{Level, Sink};
+use crate::motor;
+use crate::quiz;
+use crate::seal;
+use crate::highscores::GameId;
+use crate::launcher::Boot;
+use crate::scroll::{Direction, Style};
+use crate::timesync::Role;
+use crate::transport::Link;
+
+pub const LINE_LEN : usize = 64;
+
+pub type Line = String<LINE_LEN>;
+pub type SerialTx = UarteTx<UARTE0>;
+
+pub enum Command<'a> {
+    Help,
+    Dump,
+    Crash,
+    Ack,
+    Listen,
+    Events(Format),
+    Temps,
+    // NOTE: None shows the current routes
+    Route(Option<u8>),
+    // NOTE: None prints the level
+    Level(Option<Level>),
+    Settings,
+    Kv,
+    Reboot,
+    Dfu,
+    // NOTE: the CRC-32 of the image
+    Ota(u32),
+    Scores(GameId),
+    Score(GameId, u32),
+    Id,
+    Status,
+    Ps,
+    Info,
+    Blink,
+    Pulse,
+    Scope,
+    // NOTE: None stops the wave
+    Pwm(Option<Wave>),
+    // NOTE: None prints the tuning
+    Follow(Option<Tune>),
+    // NOTE: None prints the beacon
+    Beacon(Option<beacon::Change<'a>>),
+    // NOTE: true clears the roll once printed
+    Attendance(bool),
+    // NOTE: the minute of the day, None prints the time
+    Clock(Option<u16>),
+    Bench,
+    Sync,
+    Set(Setting),
+    Scroll(ScrollOptions, &'a str),
+    Send(&'a str),
+    Nonce,
+    // NOTE: None tells whether one is set
+    Key(Option<&'a str>),
+    Knock(Pattern),
+    // NOTE: the tag and the command it authenticates
+    Auth(Tag, &'a str),
+    Unknown(&'a str),
+}
+
+pub enum Setting {
+    Brightness(u8),
+    Sound(bool),
+    RadioGroup(u8),
+    DefaultMode(u8),
+    Threshold(u8),
+    PlaylistSecs(u8),
+    Shuffle(bool),
+    ScrollMs(u8),
+    ScrollDirection(Direction),
+    ScrollRepeat(u8),
+    Stealth(bool),
+    TimeSync(Role),
+    Relay(bool),
+    Remote(bool),
+    TelemetrySecs(u8),
+    Link(Link),
+    Auth(bool),
+    Boot(Boot),
+    Wear(bool),
+    Flip(bool),
+    FaceDown(facedown::Mode),
+    Lock(bool),
+    GreenhouseTemperature(i8, i8),
+    GreenhouseHumidity(u8, u8),
+    Motor(motor::Control),
+    Car(car::Role),
+    Beacon(beacon::Kind),
+    Attendance(attendance::Role),
+    Quiz(quiz::Role),
+    Proximity(bool),
+    // NOTE: None is off
+    Dnd(Option<Hours>),
+}
+
+// NOTE: slower than a column every 10 ms can still be read, the stored step is a byte
+const MIN_STEP_MS : u8 = 10;
+const MAX_REPEAT : u8 = 9;
+
+/// What a `scroll` command sets for its message, the rest comes from the stored style.
+#[derive(Clone, Copy, Default)]
+pub struct ScrollOptions {
+    pub step_ms   : Option<u8>,
+    pub direction : Option<Direction>,
+    pub repeat    : Option<u8>,
+}
+
+impl ScrollOptions {
+    pub fn apply(&self, style : Style) -> Style {
+        Style {
+            step_ms   : self.step_ms.map_or(style.step_ms, |ms| ms as u32),
+            direction : self.direction.unwrap_or(style.direction),
+            repeat    : self.repeat.unwrap_or(style.repeat),
+        }
+    }
+
+    /// Takes an option word like `ms=60`, false when it is none.
+    fn take(&mut self, word : &str) -> Result<bool, ()> {
+        let Some((name, value)) = word.split_once('=') else {
+            return Ok(false);
+        };
+        match name {
+            "ms"     => self.step_ms = Some(step_ms(value).ok_or(())?),
+            "dir"    => self.direction = Some(Direction::from_name(value).ok_or(())?),
+            "repeat" => self.repeat = Some(repeat(value).ok_or(())?),
+            _        => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+fn step_ms(value : &str) -> Option<u8> {
+    value.parse().ok().filter(|ms| *ms >= MIN_STEP_MS)
+}
+
+fn repeat(value : &str) -> Option<u8> {
+    value.parse().ok().filter(|n| (1..=MAX_REPEAT).contains(n))
+}
+
+/// `low..high`, the low end below the high one.
+fn range<T : core::str::FromStr + PartialOrd>(value : &str) -> Option<(T, T)> {
+    let (low, high) = value.split_once("..")?;
+    let (low, high) = (low.parse().ok()?, high.parse().ok()?);
+    (low < high).then_some((low, high))
+}
+
+fn parse_setting(name : &str, value : &str) -> Option<Setting> {
+    let number = value.parse::<u8>().ok();
+    let on_off = match value {
+        "on"  => Some(true),
+        "off" => Some(false),
+        _     => None,
+    };
+    match name {
+        "brightness" => number.filter(|n| *n <= 9).map(Setting::Brightness),
+        "sound" => on_off.map(Setting::Sound),
+        "group" => number.map(Setting::RadioGroup),
+        "mode"  => number.map(Setting::DefaultMode),
+        "threshold" => number.filter(|n| (1..=15).contains(n)).map(Setting::Threshold),
+        "playlist" => number.map(Setting::PlaylistSecs),
+        "shuffle" => on_off.map(Setting::Shuffle),
+        "scroll_ms" => step_ms(value).map(Setting::ScrollMs),
+        "scroll_dir" => Direction::from_name(value).map(Setting::ScrollDirection),
+        "scroll_repeat" => repeat(value).map(Setting::ScrollRepeat),
+        "stealth" => on_off.map(Setting::Stealth),
+        "sync" => Role::from_name(value).map(Setting::TimeSync),
+        "relay" => on_off.map(Setting::Relay),
+        "remote" => on_off.map(Setting::Remote),
+        "telemetry" => number.map(Setting::TelemetrySecs),
+        "link" => Link::from_name(value).filter(Link::available).map(Setting::Link),
+        "auth" => on_off.map(Setting::Auth),
+        "boot" => Boot::from_name(value).map(Setting::Boot),
+        "wear" => on_off.map(Setting::Wear),
+        "flip" => on_off.map(Setting::Flip),
+        "facedown" => facedown::Mode::from_name(value).map(Setting::FaceDown),
+        "lock" => on_off.map(Setting::Lock),
+        "greenhouse_temp" => range(value).map(|(low, high)| Setting::GreenhouseTemperature(low, high)),
+        "greenhouse_humidity" => range(value)
+            .filter(|(_, high)| *high <= 100)
+            .map(|(low, high)| Setting::GreenhouseHumidity(low, high)),
+        "motor" => motor::Control::from_name(value).map(Setting::Motor),
+        "car" => car::Role::from_name(value).map(Setting::Car),
+        "beacon" => beacon::Kind::from_name(value).map(Setting::Beacon),
+        "attendance" => attendance::Role::from_name(value).map(Setting::Attendance),
+        "quiz" => quiz::Role::from_name(value).map(Setting::Quiz),
+        "proximity" => on_off.map(Setting::Proximity),
+        "dnd" => match on_off {
+            Some(false) => Some(Setting::Dnd(None)),
+            _ => Hours::parse(value).map(|hours| Setting::Dnd(Some(hours))),
+        },
+        _       => None,
+    }
+}
+
+pub fn parse(line : &str) -> Command {
+    let mut words = line.split_whitespace();
+    match words.next() {
+        Some("help") => Command::Help,
+        Some("dump") => Command::Dump,
+        Some("crash") => Command::Crash,
+        Some("ack")  => Command::Ack,
+        Some("listen") => Command::Listen,
+        Some("events") => match words.next() {
+            None | Some("csv") => Command::Events(Format::Csv),
+            Some("cbor")       => Command::Events(Format::Cbor),
+            Some(other)        => Command::Unknown(other),
+        },
+        Some("route") => {
+            let mut routes = None;
+            for word in words {
+                let sink = match word {
+                    "none" => 0,
+                    name => match Sink::from_name(name) {
+                        Some(sink) => sink as u8,
+                        None => return Command::Unknown(word),
+                    },
+                };
+                *routes.get_or_insert(0) |= sink;
+            }
+            Command::Route(routes)
+        }
+        Some("level") => match words.next().map(Level::from_name) {
+            None => Command::Level(None),
+            Some(Some(level)) => Command::Level(Some(level)),
+            Some(None) => Command::Unknown(line),
+        },
+        Some("temps")    => Command::Temps,
+        Some("settings") => Command::Settings,
+        Some("kv")       => Command::Kv,
+        Some("reboot")   => Command::Reboot,
+        Some("dfu")      => Command::Dfu,
+        Some("ota") => match words.next().map(|hex| u32::from_str_radix(hex, 16)) {
+            Some(Ok(crc)) => Command::Ota(crc),
+            _ => Command::Unknown(line),
+        },
+        Some("id")       => Command::Id,
+        Some("blink")    => Command::Blink,
+        Some("status")   => Command::Status,
+        Some("ps")       => Command::Ps,
+        Some("info")     => Command::Info,
+        Some("pulse")    => Command::Pulse,
+        Some("scope")    => Command::Scope,
+        Some("pwm") => match (words.next(), words.next().map(str::parse).unwrap_or(Ok(50))) {
+            (Some("off"), _) => Command::Pwm(None),
+            (Some(hz), Ok(duty)) => match hz.parse().ok().and_then(|hz| Wave::new(hz, duty)) {
+                Some(wave) => Command::Pwm(Some(wave)),
+                None => Command::Unknown(line),
+            },
+            _ => Command::Unknown(line),
+        },
+        Some("beacon") => match (words.next(), words.next()) {
+            (None, _) => Command::Beacon(None),
+            (Some(name), Some(value)) => match beacon::Change::parse(name, value) {
+                Some(change) => Command::Beacon(Some(change)),
+                None => Command::Unknown(line),
+            },
+            _ => Command::Unknown(line),
+        },
+        Some("attendance") => match words.next() {
+            None => Command::Attendance(false),
+            Some("clear") => Command::Attendance(true),
+            _ => Command::Unknown(line),
+        },
+        Some("clock") => match words.next().map(quiet::parse_time) {
+            None => Command::Clock(None),
+            Some(Some(minute)) => Command::Clock(Some(minute)),
+            Some(None) => Command::Unknown(line),
+        },
+        Some("follow") => match (words.next(), words.next()) {
+            (None, _) => Command::Follow(None),
+            (Some(name), Some(value)) => match Tune::parse(name, value) {
+                Some(tune) => Command::Follow(Some(tune)),
+                None => Command::Unknown(line),
+            },
+            _ => Command::Unknown(line),
+        },
+        Some("bench")    => Command::Bench,
+        Some("sync")     => Command::Sync,
+        Some("scores") => match words.next().map(str::parse) {
+            Some(Ok(game)) => Command::Scores(game),
+            _ => Command::Unknown(line),
+        },
+        Some("score") => match (words.next().map(str::parse), words.next().map(str::parse)) {
+            (Some(Ok(game)), Some(Ok(score))) => Command::Score(game, score),
+            _ => Command::Unknown(line),
+        },
+        Some("knock") => match words.next().and_then(Pattern::parse) {
+            Some(pattern) => Command::Knock(pattern),
+            None => Command::Unknown(line),
+        },
+        Some("set") => match (words.next(), words.next()) {
+            (Some(name), Some(value)) => match parse_setting(name, value) {
+                Some(setting) => Command::Set(setting),
+                None => Command::Unknown(line),
+            },
+            _ => Command::Unknown(line),
+        },
+        Some("scroll") => {
+            // NOTE: the option words up front, the message is the rest of the line as typed
+            let mut options = ScrollOptions::default();
+            let mut rest = line.trim_start()["scroll".len()..].trim_start();
+            loop {
+                let (word, after) = rest.split_once(' ').unwrap_or((rest, ""));
+                match options.take(word) {
+                    Ok(true) => rest = after.trim_start(),
+                    Ok(false) => break,
+                    Err(()) => return Command::Unknown(word),
+                }
+            }
+            if rest.is_empty() {
+                return Command::Unknown(line);
+            }
+            Command::Scroll(options, rest)
+        }
+        Some("send") => match line.trim_start()["send".len()..].trim() {
+            "" => Command::Unknown(line),
+            text => Command::Send(text),
+        },
+        Some("nonce") => Command::Nonce,
+        Some("key") => match line.trim_start()["key".len()..].trim() {
+            "" => Command::Key(None),
+            secret if secret.len() <= seal::MAX_SECRET_LEN => Command::Key(Some(secret)),
+            _ => Command::Unknown(line),
+        },
+        Some("auth") => match words.next().and_then(auth::parse_tag) {
+            // NOTE: the command as typed, it is what the tag was made over
+            Some(tag) => match line.trim_start()["auth".len()..].trim_start().split_once(' ') {
+                Some((_, command)) if !command.trim().is_empty() => Command::Auth(tag, command.trim()),
+                _ => Command::Unknown(line),
+            },
+            None => Command::Unknown(line),
+        },
+        Some(other)  => Command::Unknown(other),
+        None         => Command::Unknown(""),
+    }
+}
+
+/// `line` cut before the secret of a `key` command, typed or behind `auth`, for the log.
+pub fn redacted(line : &str) -> &str {
+    let mut words = line.split_whitespace();
+    let keyed = match words.next() {
+        Some("key") => true,
+        Some("auth") => words.nth(1) == Some("key"),
+        _ => false,
+    };
+    // NOTE: a tag is hex digits, the first "key" is the command
+    match line.find("key") {
+        Some(at) if keyed => &line[..at + "key".len()],
+        _ => line,
+    }
+}
+
+pub const HELP : &[&str] = &[
+    "help  - list commands",
+    "dump  - print the most recent log records",
+    "crash - print the stored crash report",
+    "ack   - acknowledge the stored crash report",
+    "listen - toggle forwarding radio log and telemetry packets to this port",
+    "events [csv|cbor] - export the event journal, cbor as one hex line per record",
+    "temps - print the temperature of the last hour and the lowest and highest of each day as CSV",
+    "route [rtt uart radio buffer|none] - show or set where log records go",
+    "level [trace|debug|info|warn|error] - show or set the lowest level of the log records kept",
+    "settings - print the stored settings",
+    "kv    - print the key-value store usage",
+    "reboot - save the usage counters and reset",
+    "dfu   - reset into the bootloader for a firmware update, always needs a tag",
+    "ota <crc32> - take a firmware image over the radio, experimental, always needs a tag",
+    "scores <game> - print and scroll the high scores of a game",
+    "id    - print and scroll the device identity",
+    "status - print uptime, liveness, supply voltage and display brightness",
+    "ps    - per task: spawns, spawns dropped while busy, runs, last run and longest run",
+    "info  - print the firmware version, uptime, last reset reason and device id",
+    "pulse - measure frequency and pulse widths of the signal on ring 1",
+    "scope - toggle printing the raw samples of the scope app, ring 0, a line per burst",
+    "pwm <hz> [<duty>]|off - a square wave of 1-4096 Hz on P16, duty in percent, 50 if left out",
+    "follow [kp|ki|kd|speed <n>] - print or change the line follower tuning, gains in hundredths",
+    "beacon [id <32 hex digits>|url <url>] - print or change the BLE beacon id and URL",
+    "attendance [clear] - print the students checked in as CSV, clear starts a new roll",
+    "clock [hh:mm] - print or set the time of day for the quiet hours, a reset forgets it",
+    "bench - render test frames and log the frame rate, cycles and jitter",
+    "sync  - print the time sync role, master and offset",
+    "blink - toggle blinking the microphone LED, timer to pin over PPI without the CPU",
+    "score <game> <points> - submit a score, initials are entered with A and B",
+    "set brightness 0-9|sound on/off|group 0-255|mode n|threshold 1-15 - change and store a setting",
+    "set playlist <secs>|shuffle on/off - idle animation time, 0 stays put, and random order",
+    "set scroll_ms <ms>|scroll_dir left/right/up/down|scroll_repeat 1-9 - how messages scroll",
+    "set stealth on/off - keep the display dark, everything else runs, A+B held toggles it too",
+    "set sync off|master|follow - broadcast this board's time, or follow a master's",
+    "set relay on/off - listen for messages sent over the radio, scroll and pass them on",
+    "set remote on/off - take the button presses of the peer's remote app as our own",
+    "set telemetry <secs> - broadcast a sensor snapshot this often, 0 is off, listeners print JSON",
+    "set link off|uart|rtt - frame the packets heard for a host program, broadcast the ones it sends",
+    "set auth on/off - the commands changing the board need a tag, radio commands always do",
+    "set boot last|launcher|<app> - boot into the app launched last, the launcher or this app",
+    "set wear on/off - move frames left alone for long and dim the LEDs lit the most",
+    "set flip on/off - turn the frames round while the board is upside down",
+    "set facedown off|mute|sleep - lying face down 2 s mutes and blanks, sleep also polls less",
+    "set lock on/off - refuse settings and ota until the knock pattern is knocked on the board",
+    "set greenhouse_temp <low>..<high>|greenhouse_humidity <low>..<high> - alarm limits, C and %",
+    "set motor off|tilt|remote - P12-P15 drive two motors over an H-bridge, from the next reset",
+    "set car off|controller|vehicle - in the car app the tilt of the controller drives the peer",
+    "set beacon off|ibeacon|uid|url|env - advertise as a BLE beacon, or the temperature, supply and light",
+    "set attendance off|student|teacher - A checks a student in, the teacher collects the names",
+    "set quiz player|host - in the quiz app A on the host says go, the players race to press A",
+    "set proximity on/off - beacon to the boards around, alert when one stays too close for long",
+    "set dnd off|<hh:mm>-<hh:mm> - quiet hours, no alarm beeps and messages held back until over",
+    "knock <pattern> - the pattern unlocking the board, x a knock and . a rest, like xx.x",
+    "key [<secret>] - tell whether the class key is set, or set it, up to 32 bytes, needs a tag once set",
+    "nonce - print a fresh nonce for the next authenticated command",
+    "auth <tag> <command> - run a command, tag is the HMAC of nonce and command, 8 hex digits",
+    "scroll [ms=<ms>] [dir=<dir>] [repeat=<n>] <text> - scroll a message, options as the settings",
+    "send <text> - flood a short message over the radio, to the boards relaying",
+];
+
+pub struct LineBuffer {
+    line : Line,
+}
+
+impl LineBuffer {
+    pub const fn new() -> Self {
+        LineBuffer { line : String::new() }
+    }
+
+    /// Adds a received byte, returns the line once it is terminated.
+    pub fn push(&mut self, byte : u8) -> Option<Line> {
+        match byte {
+            b'\r' | b'\n' => {
+                if self.line.is_empty() {
+                    None
+                } else {
+                    Some(core::mem::take(&mut self.line))
+                }
+            }
+            // backspace and delete
+            0x08 | 0x7f => {
+                self.line.pop();
+                None
+            }
+            // NOTE: overlong lines are truncated, the command will most likely be rejected
+            byte if byte.is_ascii() && !byte.is_ascii_control() => {
+                let _ = self.line.push(byte as char);
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+pub fn write_line(tx : &mut SerialTx, s : &str) {
+    let _ = tx.write_str(s);
+    let _ = tx.write_str("\r\n");
+    let _ = nb::block!(serial::Write::flush(tx));
+}
@@ -0,0 +1,173 @@
+//! Crash report that survives a reset.
+//!
+//! The panic handler and the fault handlers write what they know into a reserved
+//! flash page, `init` picks it up on the next boot, logs it and the display shows
+//! `ICON` until the report is acknowledged (button A or the `ack` console command).
+
+use core::fmt::Write;
+use core::panic::PanicInfo;
+use core::sync::atomic::{compiler_fence, Ordering};
+use heapless::String;
+use microbit::pac::{Interrupt, SCB};
+use crate::flash::{self, CRASH_LOG_PAGE};
+use crate::mono::Mono;
+use crate::log;
+use crate::logging::Level;
+
+// "CRSH"
+const MAGIC : u32 = 0x4853_5243;
+const HEADER_WORDS : usize = 6;
+pub const MESSAGE_LEN : usize = 96;
+
+pub const ICON : [[u8; 5]; 5] = [
+    [1, 0, 0, 0, 1],
+    [0, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Panic            = 1,
+    HardFault        = 2,
+    MemoryManagement = 3,
+    BusFault         = 4,
+    UsageFault       = 5,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Panic            => "panic",
+            Kind::HardFault        => "HardFault",
+            Kind::MemoryManagement => "MemoryManagement",
+            Kind::BusFault         => "BusFault",
+            Kind::UsageFault       => "UsageFault",
+        }
+    }
+}
+
+pub struct CrashReport {
+    pub kind      : Kind,
+    pub pc        : u32,
+    // NOTE: the active exception number, tells which task was running
+    pub context   : u32,
+    pub uptime_ms : u32,
+    pub message   : String<MESSAGE_LEN>,
+}
+
+/// Writes the crash report, meant to be called with interrupts disabled.
+pub fn store(kind : Kind, pc : u32, context : u32, message : &str) {
+    let message = &message.as_bytes()[..message.len().min(MESSAGE_LEN)];
+
+    let mut words = [0xffff_ffff_u32; HEADER_WORDS + MESSAGE_LEN / 4];
+    words[0] = MAGIC;
+    words[1] = kind as u32;
+    words[2] = pc;
+    words[3] = context;
+    words[4] = Mono::now().duration_since_epoch().to_millis() as u32;
+    words[5] = message.len() as u32;
+    for (i, chunk) in message.chunks(4).enumerate() {
+        let mut bytes = [0; 4];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        words[HEADER_WORDS + i] = u32::from_le_bytes(bytes);
+    }
+
+    flash::erase_page(CRASH_LOG_PAGE);
+    flash::write_words(CRASH_LOG_PAGE, &words);
+}
+
+pub fn load() -> Option<CrashReport> {
+    let word = |i : usize| flash::read_word(CRASH_LOG_PAGE + 4 * i as u32);
+    if word(0) != MAGIC {
+        return None;
+    }
+
+    let kind = match word(1) {
+        1 => Kind::Panic,
+        3 => Kind::MemoryManagement,
+        4 => Kind::BusFault,
+        5 => Kind::UsageFault,
+        _ => Kind::HardFault,
+    };
+    let len = (word(5) as usize).min(MESSAGE_LEN);
+    let mut message = String::new();
+    for i in 0..len {
+        let byte = word(HEADER_WORDS + i / 4).to_le_bytes()[i % 4];
+        // NOTE: the message was cut at a byte boundary, so only keep plain ascii
+        let c = if byte.is_ascii() { byte as char } else { '?' };
+        let _ = message.push(c);
+    }
+
+    Some(CrashReport {
+        kind,
+        pc        : word(2),
+        context   : word(3),
+        uptime_ms : word(4),
+        message,
+    })
+}
+
+pub fn log_report(report : &CrashReport) {
+    log!(
+        Level::Error,
+        "crash report: {} in {} at pc 0x{:x} after {} ms",
+        report.kind.name(),
+        context_name(report.context),
+        report.pc,
+        report.uptime_ms);
+    log!(Level::Error, "crash message: {}", report.message.as_str());
+}
+
+/// Acknowledges the report, so it is not shown again on the next boot.
+pub fn clear() {
+    flash::erase_page(CRASH_LOG_PAGE);
+}
+
+pub fn context_name(context : u32) -> &'static str {
+    const IRQ : u32 = 16;
+    match context {
+        0 => "thread (init or idle)",
+        3 => "HardFault",
+        4 => "MemoryManagement",
+        5 => "BusFault",
+        6 => "UsageFault",
+        c if c == IRQ + Interrupt::GPIOTE as u32       => "button_pressed",
+        c if c == IRQ + Interrupt::UARTE0_UART0 as u32 => "serial_received",
+        c if c == IRQ + Interrupt::SWI0_EGU0 as u32    => "priority 1 task",
+        c if c == IRQ + Interrupt::SWI1_EGU1 as u32    => "priority 2 task",
+        c if c == IRQ + Interrupt::RTC0 as u32         => "monotonic",
+        c if c == IRQ + Interrupt::RADIO as u32        => "radio_interrupt",
+        c if c == IRQ + Interrupt::RNG as u32          => "rng_ready",
+        _ => "unknown",
+    }
+}
+
+pub fn active_exception() -> u32 {
+    unsafe { (*SCB::PTR).icsr.read() & 0x1ff }
+}
+
+// NOTE: replaces panic-halt and panic-rtt-target, it prints the same way they did
+// but stores the report first
+#[panic_handler]
+fn panic(info : &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+
+    let mut message = String::<MESSAGE_LEN>::new();
+    let _ = write!(message, "{}", info);
+    // NOTE: there is no exception frame for a panic, the return address is the best we have
+    let lr : u32;
+    unsafe { core::arch::asm!("mov {}, lr", out(reg) lr) };
+    store(Kind::Panic, lr, active_exception(), message.as_str());
+
+    #[cfg(feature = "use_rtt")]
+    rtt_target::rprintln!("{}", info);
+
+    #[cfg(feature = "use_defmt")]
+    defmt::error!("{}", defmt::Display2Format(info));
+
+    loop {
+        compiler_fence(Ordering::SeqCst);
+    }
+}
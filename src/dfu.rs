@@ -0,0 +1,60 @@
+//! Resetting into a bootloader for a firmware update, the `dfu` command.
+//!
+//! The request goes into GPREGRET, which a soft reset keeps, as the `MAGIC` the nRF52 UF2
+//! bootloaders take for staying in update mode. Before the reset an arrow drops into the
+//! board a few times, so whoever sent the command sees which board took it.
+//!
+//! NOTE: the micro:bit comes without a bootloader on the nRF, the interface chip flashes it
+//! straight from the MICROBIT drive at any time. So for now the board comes back up in this
+//! firmware, `init` finds the magic nobody took, clears it and logs that.
+
+use microbit::pac::POWER;
+use crate::display::Frame;
+use crate::log;
+
+// NOTE: DFU_MAGIC_UF2_RESET of the Adafruit nRF52 bootloader
+pub const MAGIC : u8 = 0x57;
+pub const FRAME_MS : u32 = 80;
+pub const ROUNDS : usize = 3;
+
+const ARROW : [[u8; 5]; 4] = [
+    [0, 0, 9, 0, 0],
+    [9, 0, 9, 0, 9],
+    [0, 9, 9, 9, 0],
+    [0, 0, 9, 0, 0],
+];
+// NOTE: the board the arrow drops into
+const BOARD_LEVEL : u8 = 3;
+
+fn power() -> &'static microbit::pac::power::RegisterBlock {
+    // NOTE: the POWER peripheral is not part of the board struct, see about.rs
+    unsafe { &*POWER::ptr() }
+}
+
+/// Clears a request that no bootloader took.
+pub fn init() {
+    if power().gpregret.read().gpregret().bits() == MAGIC {
+        power().gpregret.write(|w| unsafe { w.gpregret().bits(0) });
+        log!("dfu: no bootloader took the update request, running the firmware");
+    }
+}
+
+/// The arrow at `step`, coming in at the top, its tip lands on the board.
+pub fn frame(step : usize) -> Frame {
+    let mut frame = [[0; 5]; 5];
+    // NOTE: -3 shows the tip only, 1 has the tip in the bottom row
+    let shift = (step % 5) as isize - 3;
+    for (arrow_row, leds) in ARROW.iter().enumerate() {
+        if let Ok(row) = usize::try_from(arrow_row as isize + shift) {
+            frame[row] = *leds;
+        }
+    }
+    frame[4] = frame[4].map(|led| led.max(BOARD_LEVEL));
+    frame
+}
+
+/// Leaves the request for the bootloader and resets.
+pub fn reset() -> ! {
+    power().gpregret.write(|w| unsafe { w.gpregret().bits(MAGIC) });
+    cortex_m::peripheral::SCB::sys_reset()
+}
@@ -0,0 +1,360 @@
+//! LED matrix driver, refreshed by the PWM peripherals without the CPU.
+//!
+//! The ten matrix lines are PWM outputs: PWM0 drives COL1-4, PWM1 COL5 and ROW1-3, PWM2
+//! ROW4-5. Every instance loops over a sequence of five PWM periods, one per row, taking a
+//! compare value per channel each period: the row of the period is driven high for all of it
+//! and each column is pulled low for as long as its LED should be lit, which gives greyscale.
+//! The three instances are started in lockstep over PPI and then run on their own, drawing
+//! a frame only means writing the sequence buffers.
+//!
+//! With the `software_display` feature the CPU multiplexes the matrix instead, through the
+//! blocking driver of the microbit crate, without greyscale or brightness.
+//!
+//! Either way `show` keeps the caller for the frame's duration and leaves the matrix dark,
+//! so the tasks drawing frames don't care which driver runs.
+//!
+//! In stealth mode, see `set_stealth`, the frames are still shown for their duration but
+//! every LED stays off, everything else on the board runs as before. `set_blanked` does the
+//! same for the face-down gesture, see facedown.rs, without touching the setting.
+//!
+//! With the auto flip on, see `set_auto_flip`, frames are turned round while input_poll
+//! finds the board upside down, so a badge hung either way can be read. The buttons stay
+//! where they are.
+//!
+//! With wear levelling on, see `set_wear_levelling`, every frame goes through
+//! `fun_core::wear` first, which moves frames left alone for long and dims the LEDs lit
+//! the most, for a board showing the same thing all day.
+
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::Mutex;
+use fun_core::board::Matrix;
+use fun_core::wear::Wear;
+use fun_core::FrameSink;
+use microbit::gpio::DisplayPins;
+use microbit::hal::Timer;
+use microbit::pac::{PWM0, PWM1, PWM2, TIMER0};
+
+pub use fun_core::Frame;
+
+// NOTE: `show` holds the display for the whole frame, so what it shows is kept out here for
+// whoever wants to know without waiting on the lock
+static SHOWN : Mutex<Cell<Frame>> = Mutex::new(Cell::new([[0; 5]; 5]));
+
+static STEALTH : AtomicBool = AtomicBool::new(false);
+
+pub fn set_stealth(on : bool) {
+    STEALTH.store(on, Ordering::Relaxed);
+}
+
+pub fn stealth() -> bool {
+    STEALTH.load(Ordering::Relaxed)
+}
+
+static BLANKED : AtomicBool = AtomicBool::new(false);
+
+pub fn set_blanked(on : bool) {
+    BLANKED.store(on, Ordering::Relaxed);
+}
+
+fn dark() -> bool {
+    stealth() || BLANKED.load(Ordering::Relaxed)
+}
+
+static AUTO_FLIP : AtomicBool = AtomicBool::new(false);
+static FLIPPED : AtomicBool = AtomicBool::new(false);
+
+pub fn set_auto_flip(on : bool) {
+    AUTO_FLIP.store(on, Ordering::Relaxed);
+    if !on {
+        FLIPPED.store(false, Ordering::Relaxed);
+    }
+}
+
+/// What the accelerometer says, the frames are turned round while it is true and the auto
+/// flip is on.
+pub fn set_upside_down(upside_down : bool) {
+    FLIPPED.store(upside_down && AUTO_FLIP.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+// NOTE: None while wear levelling is off
+static WEAR : Mutex<RefCell<Option<Wear>>> = Mutex::new(RefCell::new(None));
+
+pub fn set_wear_levelling(on : bool) {
+    cortex_m::interrupt::free(|cs| *WEAR.borrow(cs).borrow_mut() = on.then(Wear::new));
+}
+
+/// The levels to show for `levels`, shown for `duration_ms`.
+fn prepared(levels : Frame, duration_ms : u32) -> Frame {
+    let levels = if FLIPPED.load(Ordering::Relaxed) {
+        let mut turned = levels;
+        turned.reverse();
+        turned.map(|mut row| {
+            row.reverse();
+            row
+        })
+    } else {
+        levels
+    };
+    cortex_m::interrupt::free(|cs| match WEAR.borrow(cs).borrow_mut().as_mut() {
+        Some(wear) => wear.apply(levels, duration_ms),
+        None => levels,
+    })
+}
+
+fn showing(levels : Frame) {
+    cortex_m::interrupt::free(|cs| SHOWN.borrow(cs).set(levels));
+}
+
+/// The LED levels on the matrix right now, all 0 between frames.
+#[cfg(feature = "hil")]
+pub fn shown() -> Frame {
+    cortex_m::interrupt::free(|cs| SHOWN.borrow(cs).get())
+}
+
+#[cfg(not(feature = "software_display"))]
+mod pwm {
+    use embedded_hal::blocking::delay::DelayMs;
+    use super::*;
+    use crate::channels;
+    use crate::ppi;
+
+    const ROWS : usize = 5;
+    // NOTE: four compare values per period, one period per row
+    const SEQUENCE_LEN : usize = 4 * ROWS;
+    // NOTE: in 1 µs ticks, each row is lit for 1 ms so the matrix refreshes at 200 Hz
+    const ROW_PERIOD : u16 = 1000;
+    // NOTE: columns let go a little before the row changes, so the next row doesn't ghost
+    const DEAD_TIME : u16 = 50;
+    // NOTE: the top bit of a compare value inverts the channel, low for the duty then high
+    const ACTIVE_LOW : u16 = 0x8000;
+
+    #[derive(Clone, Copy)]
+    enum Output {
+        Column(usize),
+        Row(usize),
+        Unused,
+    }
+
+    const OUTPUTS : [[Output; 4]; 3] = [
+        [Output::Column(0), Output::Column(1), Output::Column(2), Output::Column(3)],
+        [Output::Column(4), Output::Row(0), Output::Row(1), Output::Row(2)],
+        [Output::Row(3), Output::Row(4), Output::Unused, Output::Unused],
+    ];
+
+    type Sequences = [[u16; SEQUENCE_LEN]; 3];
+
+    // NOTE: read by the PWM's DMA, so it has to stay put, only the one Display touches it
+    static mut SEQUENCES : Sequences = [[0; SEQUENCE_LEN]; 3];
+
+    /// Column duty for an LED level of 0-9 at a display brightness of 0-9,
+    /// roughly quadratic so the steps look even.
+    fn duty(level : u8, brightness : u8) -> u16 {
+        // NOTE: rounded up, a lit LED stays lit at any brightness above 0
+        let level = (level.min(9) as u32 * brightness.min(9) as u32 + 8) / 9;
+        (level * level * (ROW_PERIOD - DEAD_TIME) as u32 / 81) as u16
+    }
+
+    pub struct Display {
+        sequences  : &'static mut Sequences,
+        brightness : u8,
+        pwm        : (PWM0, PWM1, PWM2),
+        // NOTE: starts the three PWMs in the same clock cycle, see `start`
+        lockstep   : channels::PpiChannel,
+    }
+
+    impl Display {
+        pub fn new(pins : DisplayPins, pwm0 : PWM0, pwm1 : PWM1, pwm2 : PWM2) -> Self {
+            let (columns, rows) = pins.degrade();
+            let mut display = Display {
+                sequences  : unsafe { &mut *core::ptr::addr_of_mut!(SEQUENCES) },
+                brightness : 9,
+                pwm        : (pwm0, pwm1, pwm2),
+                // NOTE: the display comes first in init, a channel is always left for it
+                lockstep   : channels::ppi("display").unwrap(),
+            };
+            display.clear();
+
+            for ((pwm, sequence), outputs) in display.instances().iter().zip(display.sequences.iter()).zip(OUTPUTS) {
+                pwm.enable.write(|w| w.enable().enabled());
+                pwm.mode.write(|w| w.updown().up());
+                pwm.prescaler.write(|w| w.prescaler().div_16());
+                pwm.countertop.write(|w| unsafe { w.countertop().bits(ROW_PERIOD) });
+                pwm.decoder.write(|w| w.load().individual().mode().refresh_count());
+                for seq in [&pwm.seq0, &pwm.seq1] {
+                    seq.ptr.write(|w| unsafe { w.bits(sequence.as_ptr() as u32) });
+                    seq.cnt.write(|w| unsafe { w.bits(SEQUENCE_LEN as u32) });
+                    seq.refresh.write(|w| unsafe { w.bits(0) });
+                    seq.enddelay.write(|w| unsafe { w.bits(0) });
+                }
+                // NOTE: seq0 then seq1, then seq0 again, forever
+                pwm.loop_.write(|w| unsafe { w.cnt().bits(1) });
+                pwm.shorts.write(|w| w.loopsdone_seqstart0().enabled());
+                for (psel, output) in pwm.psel.out.iter().zip(outputs) {
+                    let pin = match output {
+                        Output::Column(column) => Some(&columns[column]),
+                        Output::Row(row) => Some(&rows[row]),
+                        Output::Unused => None,
+                    };
+                    match pin {
+                        Some(pin) => psel.write(|w| unsafe { w.bits(pin.psel_bits()) }),
+                        None => psel.reset(),
+                    }
+                }
+            }
+
+            let [pwm0, pwm1, pwm2] = display.instances();
+            ppi::connect(display.lockstep, &pwm0.events_seqstarted[0], &pwm1.tasks_seqstart[0]);
+            ppi::fork(display.lockstep, &pwm2.tasks_seqstart[0]);
+            display.start();
+            display
+        }
+
+        fn instances(&self) -> [&microbit::pac::pwm0::RegisterBlock; 3] {
+            [&self.pwm.0, &self.pwm.1, &self.pwm.2]
+        }
+
+        fn start(&self) {
+            // NOTE: PWM0 starting starts the other two in the same clock cycle, after that
+            // they run off the same clock with sequences of the same length
+            let [pwm0, _, pwm2] = self.instances();
+            for pwm in self.instances() {
+                pwm.events_seqstarted[0].reset();
+            }
+            ppi::enable(self.lockstep);
+            pwm0.tasks_seqstart[0].write(|w| unsafe { w.bits(1) });
+            while pwm2.events_seqstarted[0].read().bits() == 0 {}
+            ppi::disable(self.lockstep);
+        }
+
+        /// Runs `f` with the PWMs stopped, the matrix lines follow their GPIO registers
+        /// meanwhile, then starts them again, see light.rs.
+        pub fn paused<R>(&mut self, f : impl FnOnce() -> R) -> R {
+            for pwm in self.instances() {
+                pwm.events_stopped.reset();
+                pwm.tasks_stop.write(|w| unsafe { w.bits(1) });
+                while pwm.events_stopped.read().bits() == 0 {}
+                pwm.enable.write(|w| w.enable().disabled());
+            }
+            let result = f();
+            for pwm in self.instances() {
+                pwm.enable.write(|w| w.enable().enabled());
+            }
+            self.start();
+            result
+        }
+
+        /// 0-9, applies from the next frame on.
+        pub fn set_brightness(&mut self, brightness : u8) {
+            self.brightness = brightness;
+        }
+
+        fn load(&mut self, levels : &Frame) {
+            let levels = if dark() { &[[0; 5]; 5] } else { levels };
+            showing(*levels);
+            // NOTE: written while the PWMs' DMA reads them, so the refresh under way may show
+            // rows of both frames, for 5 ms at most, the next one is the new frame whole
+            for (sequence, outputs) in self.sequences.iter_mut().zip(OUTPUTS) {
+                for (row, values) in sequence.chunks_exact_mut(4).enumerate() {
+                    for (value, output) in values.iter_mut().zip(outputs) {
+                        *value = match output {
+                            Output::Column(column) => ACTIVE_LOW | duty(levels[row][column], self.brightness),
+                            Output::Row(lit) if lit == row => ROW_PERIOD,
+                            Output::Row(_) | Output::Unused => 0,
+                        };
+                    }
+                }
+            }
+        }
+
+        pub fn clear(&mut self) {
+            self.load(&[[0; 5]; 5]);
+        }
+
+        /// Shows `frame` with every LED that isn't 0 fully lit.
+        pub fn show(&mut self, timer : &mut Timer<TIMER0>, frame : Frame, duration_ms : u32) {
+            let levels = frame.map(|row| row.map(|led| if led > 0 { 9 } else { 0 }));
+            self.show_greyscale(timer, levels, duration_ms);
+        }
+
+        /// Shows a frame of LED levels 0-9.
+        pub fn show_greyscale(&mut self, timer : &mut Timer<TIMER0>, levels : Frame, duration_ms : u32) {
+            self.load(&prepared(levels, duration_ms));
+            timer.delay_ms(duration_ms);
+            self.clear();
+        }
+    }
+}
+
+#[cfg(feature = "software_display")]
+mod software {
+    use microbit::display::blocking;
+    use super::*;
+
+    // NOTE: without greyscale the dimmer levels stay dark
+    const LIT_LEVEL : u8 = 5;
+
+    pub struct Display {
+        display : blocking::Display,
+    }
+
+    impl Display {
+        // NOTE: takes the PWMs as well, so init looks the same with either driver
+        pub fn new(pins : DisplayPins, _pwm0 : PWM0, _pwm1 : PWM1, _pwm2 : PWM2) -> Self {
+            Display { display : blocking::Display::new(pins) }
+        }
+
+        pub fn set_brightness(&mut self, _brightness : u8) {}
+
+        /// Runs `f`, the matrix is only driven while a frame is shown.
+        pub fn paused<R>(&mut self, f : impl FnOnce() -> R) -> R {
+            f()
+        }
+
+        pub fn clear(&mut self) {
+            showing([[0; 5]; 5]);
+            self.display.clear();
+        }
+
+        pub fn show(&mut self, timer : &mut Timer<TIMER0>, frame : Frame, duration_ms : u32) {
+            let levels = frame.map(|row| row.map(|led| if led > 0 { 9 } else { 0 }));
+            self.show_greyscale(timer, levels, duration_ms);
+        }
+
+        pub fn show_greyscale(&mut self, timer : &mut Timer<TIMER0>, levels : Frame, duration_ms : u32) {
+            let levels = prepared(levels, duration_ms);
+            let frame = if dark() { [[0; 5]; 5] } else { levels.map(|row| row.map(|level| (level >= LIT_LEVEL) as u8)) };
+            showing(frame.map(|row| row.map(|led| led * 9)));
+            self.display.show(timer, frame, duration_ms);
+            showing([[0; 5]; 5]);
+        }
+    }
+}
+
+#[cfg(not(feature = "software_display"))]
+pub use pwm::Display;
+#[cfg(feature = "software_display")]
+pub use software::Display;
+
+/// The display with the timer it waits out frames on, the `board::Matrix` of the logic.
+/// Frames are shown in greyscale.
+pub struct Screen<'a> {
+    pub display : &'a mut Display,
+    pub timer   : &'a mut Timer<TIMER0>,
+}
+
+impl FrameSink for Screen<'_> {
+    fn show(&mut self, frame : Frame, duration_ms : u32) {
+        self.display.show_greyscale(self.timer, frame, duration_ms)
+    }
+}
+
+impl Matrix for Screen<'_> {
+    fn set_brightness(&mut self, brightness : u8) {
+        self.display.set_brightness(brightness)
+    }
+
+    fn clear(&mut self) {
+        self.display.clear()
+    }
+}
@@ -0,0 +1,144 @@
+//! Do not disturb: the quiet hours of `set dnd <hh:mm>-<hh:mm>`, when the board keeps its
+//! notifications to itself, see `fun_core::quiet`.
+//!
+//! In the quiet hours the greenhouse alarm and the proximity alert don't beep, and the
+//! messages relay.rs passes on aren't shown as toasts. They still go into the inbox, and the
+//! last `HELD` of them wait for the hours to end to scroll by one after the other. The beeps
+//! wait too, the last sound held back plays `BEEPS` times once the hours are over. `ICON`
+//! shows as a toast when the hours start and the bottom left LED of every app's frame stays
+//! dimly lit while they last.
+//!
+//! NOTE: the hours go by the time of day set with `clock`, see clock.rs, they don't start
+//! before it is set.
+
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use cortex_m::interrupt::Mutex;
+use fun_core::board::Beeper;
+use fun_core::quiet::Hours;
+use heapless::Deque;
+use crate::clock;
+use crate::display::Frame;
+use crate::log;
+use crate::mono::Instant;
+use crate::scroll::Text;
+use crate::speaker::Speaker;
+use crate::toast;
+
+const HELD : usize = 4;
+const ICON_SECS : u32 = 2;
+const DIM_LEVEL : u8 = 3;
+const BEEPS : u64 = 3;
+const BEEP_MS : u64 = 150;
+
+pub const ICON : Frame = [
+    [0, 1, 1, 0, 0],
+    [1, 1, 0, 0, 0],
+    [1, 1, 0, 0, 0],
+    [1, 1, 0, 0, 0],
+    [0, 1, 1, 0, 0],
+];
+
+static HOURS : Mutex<Cell<Option<Hours>>> = Mutex::new(Cell::new(None));
+// NOTE: whether the hours had started when `watch` looked last
+static QUIET : AtomicBool = AtomicBool::new(false);
+static MESSAGES : Mutex<RefCell<Deque<Text, HELD>>> = Mutex::new(RefCell::new(Deque::new()));
+// NOTE: the tone of the last sound held back, 0 for none
+static SOUND_HZ : AtomicU32 = AtomicU32::new(0);
+// NOTE: when the held sound started playing and its tone, until its beeps are over
+static PLAYING : Mutex<Cell<Option<(Instant, u32)>>> = Mutex::new(Cell::new(None));
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+pub fn configure(hours : Option<Hours>) {
+    cortex_m::interrupt::free(|cs| HOURS.borrow(cs).set(hours));
+}
+
+/// True in the quiet hours.
+pub fn active(now : Instant) -> bool {
+    let hours = cortex_m::interrupt::free(|cs| HOURS.borrow(cs).get());
+    hours.zip(clock::minute(now)).is_some_and(|(hours, minute)| hours.contains(minute))
+}
+
+/// Shows a relayed message as a toast, or holds it back in the quiet hours, pushing out the
+/// oldest held.
+pub fn notify(text : Text, now : Instant) {
+    if !active(now) {
+        toast::text(text);
+        return;
+    }
+    cortex_m::interrupt::free(|cs| {
+        let mut messages = MESSAGES.borrow(cs).borrow_mut();
+        if messages.is_full() {
+            messages.pop_front();
+        }
+        let _ = messages.push_back(text);
+    });
+}
+
+/// True when a notification sound of `hz` may play now, in the quiet hours it is held back
+/// instead, taking the place of the one held before.
+pub fn sound(hz : u32, now : Instant) -> bool {
+    if !active(now) {
+        return true;
+    }
+    SOUND_HZ.store(hz, Ordering::Relaxed);
+    false
+}
+
+/// Notices the hours starting and ending, and once over plays the sound held back and shows
+/// the messages held back, one toast at a time. Call it every input poll.
+pub fn watch(now : Instant) {
+    let quiet = active(now);
+    match (QUIET.swap(quiet, Ordering::Relaxed), quiet) {
+        (false, true) => {
+            log!("quiet hours started");
+            toast::icon(ICON, ICON_SECS);
+        }
+        (true, false) => log!("quiet hours over, {} messages held", cortex_m::interrupt::free(|cs| MESSAGES.borrow(cs).borrow().len())),
+        _ => (),
+    }
+    if quiet {
+        return;
+    }
+    play(now);
+    if toast::showing() {
+        return;
+    }
+    if let Some(text) = cortex_m::interrupt::free(|cs| MESSAGES.borrow(cs).borrow_mut().pop_front()) {
+        toast::text(text);
+    }
+}
+
+/// The beeps of the sound held back, from the first call after the hours are over.
+fn play(now : Instant) {
+    let playing = cortex_m::interrupt::free(|cs| {
+        let playing = PLAYING.borrow(cs);
+        if playing.get().is_none() {
+            let hz = SOUND_HZ.swap(0, Ordering::Relaxed);
+            playing.set((hz > 0).then_some((now, hz)));
+        }
+        playing.get()
+    });
+    let Some((at, hz)) = playing else { return };
+    let elapsed = ms_since(now, at);
+    if elapsed >= BEEPS * 2 * BEEP_MS {
+        Speaker.off();
+        cortex_m::interrupt::free(|cs| PLAYING.borrow(cs).set(None));
+    } else if (elapsed / BEEP_MS).is_multiple_of(2) {
+        Speaker.tone(hz);
+    } else {
+        Speaker.off();
+    }
+}
+
+/// `frame` with the bottom left LED dimly lit in the quiet hours.
+pub fn indicate(frame : Frame) -> Frame {
+    let mut frame = frame;
+    if QUIET.load(Ordering::Relaxed) {
+        frame[4][0] = DIM_LEVEL;
+    }
+    frame
+}
@@ -0,0 +1,36 @@
+//! The particle effects of `fun_core::particles`, for the idle animations and the ends of
+//! games.
+//!
+//! There is one `Effect`, it starts over with no particles whenever another preset takes
+//! over. An animation moves it on a step a frame with `next`, a game polling faster than
+//! that asks for the frame some time into the effect with `at`.
+
+use core::cell::RefCell;
+use critical_section::Mutex;
+use fun_core::particles::{self, Effect};
+use crate::display::Frame;
+use crate::rng::Rng;
+
+pub use fun_core::particles::Preset;
+
+pub const STEP_MS : u32 = particles::STEP_MS;
+
+static EFFECT : Mutex<RefCell<Effect>> = Mutex::new(RefCell::new(Effect::new()));
+
+/// The next frame of `preset`, a step on from the last one.
+pub fn next(preset : Preset) -> Frame {
+    run(|effect| effect.next(preset, &mut Rng))
+}
+
+/// The frame of `preset` `elapsed_ms` into it, an earlier time than the last starts it over.
+pub fn at(preset : Preset, elapsed_ms : u64) -> Frame {
+    run(|effect| effect.at(preset, elapsed_ms, &mut Rng))
+}
+
+fn run(step : impl FnOnce(&mut Effect) -> Frame) -> Frame {
+    // NOTE: Rng can't be used with interrupts disabled, so the steps run on a copy
+    let mut effect = critical_section::with(|cs| EFFECT.borrow(cs).borrow().clone());
+    let frame = step(&mut effect);
+    critical_section::with(|cs| *EFFECT.borrow(cs).borrow_mut() = effect);
+    frame
+}
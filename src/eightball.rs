@@ -0,0 +1,61 @@
+//! Magic 8-ball: shake the board for an answer, see `fun_core::eightball`.
+//!
+//! The shake comes in from input_poll. An answer cut short by the launcher is dropped, the
+//! next launch waits for a shake again.
+
+use core::cell::Cell;
+use critical_section::Mutex;
+use fun_core::eightball::Ball;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::mono::{Instant, Mono};
+use crate::rng::Rng;
+
+pub const ICON : Frame = [
+    [0, 1, 1, 1, 0],
+    [1, 0, 0, 0, 1],
+    [0, 1, 1, 1, 0],
+    [1, 0, 0, 0, 1],
+    [0, 1, 1, 1, 0],
+];
+
+// NOTE: the shake comes in from input_poll, idle draws
+static BALL : Mutex<Cell<Ball>> = Mutex::new(Cell::new(Ball::new()));
+
+fn update(next : impl FnOnce(Ball) -> Ball) -> Ball {
+    critical_section::with(|cs| {
+        let ball = next(BALL.borrow(cs).get());
+        BALL.borrow(cs).set(ball);
+        ball
+    })
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let Input::Shake = input else { return false };
+    update(|mut ball| {
+        ball.shake(&Mono);
+        ball
+    });
+    true
+}
+
+/// Waiting for a shake, or one answer.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let ball = update(|mut ball| {
+        // NOTE: the launcher cut the last answer short
+        if step == 0 {
+            ball.settle();
+        }
+        ball.advance(&Mono, &mut Rng);
+        ball
+    });
+
+    let frame = ball.frame(&Mono, &mut Rng);
+    if frame.is_none() {
+        update(|mut ball| {
+            ball.settle();
+            ball
+        });
+    }
+    frame
+}
@@ -0,0 +1,151 @@
+//! Journal of typed events, next to the text log.
+//!
+//! Events are small and fixed size, so the journal keeps more history than the log buffer.
+//! The `events` console command exports it as CSV, or as a CBOR sequence (one array
+//! `[timestamp_us, event, detail]` per record) in hex, for loading into a notebook.
+
+use core::cell::RefCell;
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use heapless::{HistoryBuffer, String, Vec};
+use crate::cbor;
+use crate::timesync;
+
+pub const RECORDS : usize = 32;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Button {
+    A,
+    B,
+    AB,
+    Logo,
+}
+
+impl Button {
+    fn name(self) -> &'static str {
+        match self {
+            Button::A    => "A",
+            Button::B    => "B",
+            Button::AB   => "AB",
+            Button::Logo => "logo",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Event {
+    ButtonPress(Button),
+    LongPress(Button),
+    ModeChange(&'static str),
+    RadioRx { len : u8 },
+    PinEdge { pin : &'static str, high : bool },
+    Threshold { above : bool, sixteenths : u8 },
+    Gesture(&'static str),
+    Error(&'static str),
+}
+
+enum Detail {
+    Text(&'static str),
+    Number(u32),
+}
+
+impl Event {
+    fn name(&self) -> &'static str {
+        match self {
+            Event::ButtonPress(_)                  => "button",
+            Event::LongPress(_)                    => "long_press",
+            Event::ModeChange(_)                   => "mode",
+            Event::RadioRx { .. }                  => "radio_rx",
+            Event::PinEdge { high : true, .. }     => "pin_high",
+            Event::PinEdge { high : false, .. }    => "pin_low",
+            Event::Threshold { above : true, .. }  => "threshold_up",
+            Event::Threshold { above : false, .. } => "threshold_down",
+            Event::Gesture(_)                      => "gesture",
+            Event::Error(_)                        => "error",
+        }
+    }
+
+    fn detail(&self) -> Detail {
+        match *self {
+            Event::ButtonPress(button)          => Detail::Text(button.name()),
+            Event::LongPress(button)            => Detail::Text(button.name()),
+            Event::ModeChange(mode)             => Detail::Text(mode),
+            Event::RadioRx { len }              => Detail::Number(len as u32),
+            Event::PinEdge { pin, .. }          => Detail::Text(pin),
+            Event::Threshold { sixteenths, .. } => Detail::Number(sixteenths as u32),
+            Event::Gesture(gesture)             => Detail::Text(gesture),
+            Event::Error(what)                  => Detail::Text(what),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Record {
+    timestamp_us : u64,
+    event        : Event,
+}
+
+// NOTE: events are recorded from every priority, so the journal sits behind a critical section
+static JOURNAL : Mutex<RefCell<HistoryBuffer<Record, RECORDS>>> =
+    Mutex::new(RefCell::new(HistoryBuffer::new()));
+
+pub fn record(event : Event) {
+    let record = Record { timestamp_us : timesync::now_us(), event };
+    cortex_m::interrupt::free(|cs| {
+        JOURNAL.borrow(cs).borrow_mut().write(record);
+    });
+}
+
+pub enum Format {
+    Csv,
+    Cbor,
+}
+
+pub const LINE_LEN : usize = 96;
+
+/// Hands the journal, oldest first, to `sink` one line per record.
+pub fn export(format : Format, mut sink : impl FnMut(&str)) {
+    if let Format::Csv = format {
+        sink("timestamp_us,event,detail");
+    }
+
+    // NOTE: copied out one at a time, like the log buffer dump
+    for i in 0..RECORDS {
+        let record = cortex_m::interrupt::free(|cs| {
+            JOURNAL.borrow(cs).borrow().oldest_ordered().nth(i).copied()
+        });
+        let Some(record) = record else { break };
+
+        let mut line = String::<LINE_LEN>::new();
+        match format {
+            Format::Csv => {
+                let _ = write!(line, "{},{},", record.timestamp_us, record.event.name());
+                match record.event.detail() {
+                    Detail::Text(text) => { let _ = line.push_str(text); }
+                    Detail::Number(n)  => { let _ = write!(line, "{}", n); }
+                }
+            }
+            Format::Cbor => {
+                for byte in cbor(&record) {
+                    let _ = write!(line, "{:02x}", byte);
+                }
+            }
+        }
+        sink(line.as_str());
+    }
+}
+
+// NOTE: two hex digits per byte
+const CBOR_LEN : usize = LINE_LEN / 2;
+
+fn cbor(record : &Record) -> Vec<u8, CBOR_LEN> {
+    let mut bytes = Vec::new();
+    cbor::array(&mut bytes, 3);
+    cbor::unsigned(&mut bytes, record.timestamp_us);
+    cbor::text(&mut bytes, record.event.name());
+    match record.event.detail() {
+        Detail::Text(text) => cbor::text(&mut bytes, text),
+        Detail::Number(n)  => cbor::unsigned(&mut bytes, n as u64),
+    }
+    bytes
+}
@@ -0,0 +1,31 @@
+//! Ends a self-test run with its result, so a runner attached to the board can gate on it.
+//!
+//! The result is logged as a marker line, `RESULTS_PASS` or `RESULTS_FAIL`, for a runner
+//! watching the log. With the `semihosting_exit` feature the board then exits through
+//! semihosting with status 0 or 1, which `probe-rs run`, or OpenOCD with `arm semihosting
+//! enable`, pass on as their own exit status. Without it the board halts on a breakpoint.
+//!
+//! NOTE: a semihosting call faults when no debugger is attached, so only builds made for a
+//! runner should have the feature.
+
+use crate::log;
+use crate::logging::Level;
+
+pub const RESULTS_PASS : &str = "TEST RESULT: PASS";
+pub const RESULTS_FAIL : &str = "TEST RESULT: FAIL";
+
+pub fn exit(passed : bool) -> ! {
+    if passed {
+        log!(Level::Info, "{}", RESULTS_PASS);
+    } else {
+        log!(Level::Error, "{}", RESULTS_FAIL);
+    }
+    #[cfg(feature = "semihosting_exit")]
+    {
+        use cortex_m_semihosting::debug;
+        debug::exit(if passed { debug::EXIT_SUCCESS } else { debug::EXIT_FAILURE });
+    }
+    loop {
+        cortex_m::asm::bkpt();
+    }
+}
@@ -0,0 +1,76 @@
+//! The face-down gesture: a board left lying on its display for
+//! `fun_core::motion::FACE_DOWN_MS` goes quiet and dark until it is picked up again, see
+//! `set facedown off|mute|sleep`.
+//!
+//! input_poll passes on what the accelerometer says. Face down the speaker is hushed and the
+//! display blanked, over the sound and stealth settings, which are back as they were once
+//! the board is picked up. The apps keep running, a game goes on in the dark. With `sleep`
+//! input_poll also polls only every `SLEEP_POLL_MS` while the board lies there, so the CPU
+//! sleeps in between, which is as low as the board's power goes, nothing else is switched
+//! off. A pickup is seen within that time.
+
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use crate::display;
+use crate::log;
+use crate::motion;
+use crate::speaker;
+
+pub const SLEEP_POLL_MS : u64 = 500;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    Off,
+    Mute,
+    Sleep,
+}
+
+const MODES : [Mode; 3] = [Mode::Off, Mode::Mute, Mode::Sleep];
+
+impl Mode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Off   => "off",
+            Mode::Mute  => "mute",
+            Mode::Sleep => "sleep",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Mode> {
+        MODES.into_iter().find(|mode| mode.name() == name)
+    }
+
+    /// The mode stored as `mode as u8`.
+    pub fn from_u8(value : u8) -> Option<Mode> {
+        MODES.get(value as usize).copied()
+    }
+}
+
+static MODE : AtomicU8 = AtomicU8::new(Mode::Off as u8);
+static DOWN : AtomicBool = AtomicBool::new(false);
+
+fn mode() -> Mode {
+    Mode::from_u8(MODE.load(Ordering::Relaxed)).unwrap_or(Mode::Off)
+}
+
+pub fn configure(mode : Mode) {
+    MODE.store(mode as u8, Ordering::Relaxed);
+    if mode == Mode::Off {
+        update(false);
+    }
+}
+
+/// Whether the board lies face down, as of input_poll's last sample.
+pub fn update(face_down : bool) {
+    let down = face_down && mode() != Mode::Off;
+    if DOWN.swap(down, Ordering::Relaxed) == down {
+        return;
+    }
+    speaker::set_hushed(down);
+    display::set_blanked(down);
+    log!("{}", if down { "face down, quiet" } else { "picked up" });
+}
+
+/// How long input_poll waits before the next poll.
+pub fn poll_ms() -> u64 {
+    if DOWN.load(Ordering::Relaxed) && mode() == Mode::Sleep { SLEEP_POLL_MS } else { motion::POLL_MS }
+}
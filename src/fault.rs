@@ -0,0 +1,158 @@
+//! Fault handlers that dump the stacked registers and the fault status registers
+//! instead of locking up silently.
+//!
+//! MemoryManagement, BusFault and UsageFault get their own handlers once `enable` has run,
+//! anything else (or a fault before `enable`) escalates to HardFault. Every handler stores
+//! a crash report and, with the `fault_blink` feature, blinks the fault code on the display.
+
+use core::fmt::Write;
+use cortex_m::peripheral::scb::Exception;
+use cortex_m_rt::{exception, ExceptionFrame};
+use heapless::String;
+use microbit::pac::SCB;
+use crate::crashlog::{self, Kind};
+
+pub fn enable(scb : &mut SCB) {
+    scb.enable(Exception::MemoryManagement);
+    scb.enable(Exception::BusFault);
+    scb.enable(Exception::UsageFault);
+}
+
+// NOTE: cortex-m-rt only hands the exception frame to HardFault,
+// so the configurable faults share a trampoline doing the same thing
+core::arch::global_asm!(
+    ".section .text.FaultTrampoline, \"ax\"
+     .global MemoryManagement
+     .global BusFault
+     .global UsageFault
+     .type MemoryManagement,%function
+     .type BusFault,%function
+     .type UsageFault,%function
+     .thumb_func
+     MemoryManagement:
+     .thumb_func
+     BusFault:
+     .thumb_func
+     UsageFault:
+     mov r0, lr
+     movs r1, #4
+     tst r0, r1
+     bne 0f
+     mrs r0, MSP
+     b configurable_fault
+     0:
+     mrs r0, PSP
+     b configurable_fault"
+);
+
+#[no_mangle]
+unsafe extern "C" fn configurable_fault(frame : &ExceptionFrame) -> ! {
+    let kind = match crashlog::active_exception() {
+        4 => Kind::MemoryManagement,
+        5 => Kind::BusFault,
+        _ => Kind::UsageFault,
+    };
+    report(kind, frame)
+}
+
+#[exception]
+unsafe fn HardFault(frame : &ExceptionFrame) -> ! {
+    report(Kind::HardFault, frame)
+}
+
+fn report(kind : Kind, frame : &ExceptionFrame) -> ! {
+    let scb = unsafe { &*SCB::PTR };
+    let cfsr = scb.cfsr.read();
+    let hfsr = scb.hfsr.read();
+    let mmfar = scb.mmfar.read();
+    let bfar = scb.bfar.read();
+    // NOTE: the stacked xPSR holds the exception number of the code that faulted
+    let context = frame.xpsr() & 0x1ff;
+
+    let mut message = String::<{ crashlog::MESSAGE_LEN }>::new();
+    let _ = write!(message, "cfsr={:08x} hfsr={:08x} mmfar={:08x} bfar={:08x}", cfsr, hfsr, mmfar, bfar);
+    crashlog::store(kind, frame.pc(), context, message.as_str());
+
+    #[cfg(feature = "use_rtt")]
+    {
+        rtt_target::rprintln!("{} in {}", kind.name(), crashlog::context_name(context));
+        rtt_target::rprintln!(
+            "r0  = {:#010x} r1 = {:#010x} r2 = {:#010x}   r3 = {:#010x}",
+            frame.r0(), frame.r1(), frame.r2(), frame.r3());
+        rtt_target::rprintln!(
+            "r12 = {:#010x} lr = {:#010x} pc = {:#010x} xpsr = {:#010x}",
+            frame.r12(), frame.lr(), frame.pc(), frame.xpsr());
+        rtt_target::rprintln!("{}", message.as_str());
+    }
+
+    #[cfg(feature = "use_defmt")]
+    {
+        defmt::error!("{=str} in {=str}", kind.name(), crashlog::context_name(context));
+        defmt::error!(
+            "r0  = {=u32:#x} r1 = {=u32:#x} r2 = {=u32:#x} r3 = {=u32:#x}",
+            frame.r0(), frame.r1(), frame.r2(), frame.r3());
+        defmt::error!(
+            "r12 = {=u32:#x} lr = {=u32:#x} pc = {=u32:#x} xpsr = {=u32:#x}",
+            frame.r12(), frame.lr(), frame.pc(), frame.xpsr());
+        defmt::error!(
+            "cfsr = {=u32:#x} hfsr = {=u32:#x} mmfar = {=u32:#x} bfar = {=u32:#x}",
+            cfsr, hfsr, mmfar, bfar);
+    }
+
+    #[cfg(feature = "fault_blink")]
+    blink_forever(kind);
+
+    #[cfg(not(feature = "fault_blink"))]
+    loop {
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/// Blinks the whole matrix once per round for a HardFault, up to four times for a UsageFault.
+#[cfg(feature = "fault_blink")]
+fn blink_forever(kind : Kind) -> ! {
+    use microbit::pac::{P0, P1};
+    #[cfg(not(feature = "software_display"))]
+    use microbit::pac::{PWM0, PWM1, PWM2};
+
+    // NOTE: the display resource may be locked by the faulting task, so drive the pins directly.
+    // LEDs light with the row high and the column low.
+    const ROWS_P0 : u32 = 1 << 21 | 1 << 22 | 1 << 15 | 1 << 24 | 1 << 19;
+    const COLS_P0 : u32 = 1 << 28 | 1 << 11 | 1 << 31 | 1 << 30;
+    const COLS_P1 : u32 = 1 << 5;
+    // NOTE: the core runs at 64 MHz
+    const MS : u32 = 64_000;
+
+    // NOTE: the PWM display drives the matrix lines through PSEL.OUT, OUT only reaches them
+    // again once the PWMs are stopped and let go of the pins
+    #[cfg(not(feature = "software_display"))]
+    for pwm in [PWM0::ptr(), PWM1::ptr(), PWM2::ptr()] {
+        let pwm = unsafe { &*pwm };
+        pwm.shorts.reset();
+        pwm.tasks_stop.write(|w| unsafe { w.bits(1) });
+        pwm.enable.write(|w| w.enable().disabled());
+        for psel in pwm.psel.out.iter() {
+            psel.reset();
+        }
+    }
+
+    let p0 = unsafe { &*P0::ptr() };
+    let p1 = unsafe { &*P1::ptr() };
+    p0.dirset.write(|w| unsafe { w.bits(ROWS_P0 | COLS_P0) });
+    p1.dirset.write(|w| unsafe { w.bits(COLS_P1) });
+    p0.outset.write(|w| unsafe { w.bits(COLS_P0) });
+    p1.outset.write(|w| unsafe { w.bits(COLS_P1) });
+
+    let blinks = kind as u32 - 1;
+    loop {
+        for _ in 0..blinks {
+            p0.outclr.write(|w| unsafe { w.bits(COLS_P0) });
+            p1.outclr.write(|w| unsafe { w.bits(COLS_P1) });
+            p0.outset.write(|w| unsafe { w.bits(ROWS_P0) });
+            cortex_m::asm::delay(200 * MS);
+            p0.outclr.write(|w| unsafe { w.bits(ROWS_P0) });
+            cortex_m::asm::delay(200 * MS);
+        }
+        cortex_m::asm::delay(1000 * MS);
+    }
+}
@@ -0,0 +1,61 @@
+//! Flappy: A makes the pixel flap up, gravity pulls it down, and pipes with a gap scroll in
+//! from the right, see `fun_core::flappy` for the game.
+//!
+//! After the score scrolls by the pixel waits for A to fly again. Its frames come every 10 ms,
+//! which also keeps the frame scheduler busy: the frame rate reached in flight is logged at
+//! the crash.
+
+use core::cell::Cell;
+use critical_section::Mutex;
+use fun_core::flappy::Game;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::rng::Rng;
+
+pub const ICON : Frame = [
+    [0, 0, 0, 1, 0],
+    [0, 0, 0, 1, 0],
+    [0, 1, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 1, 0],
+];
+
+// NOTE: the button interrupt and idle both move the game on
+static GAME : Mutex<Cell<Option<Game>>> = Mutex::new(Cell::new(None));
+
+fn update(next : impl FnOnce(Option<Game>) -> Option<Game>) -> Option<Game> {
+    critical_section::with(|cs| {
+        let game = next(GAME.borrow(cs).get());
+        GAME.borrow(cs).set(game);
+        game
+    })
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let Input::Button(Button::A) = input else { return false };
+    let mut taken = false;
+    update(|game| {
+        let mut game = game?;
+        taken = game.flap(&Mono);
+        Some(game)
+    });
+    taken
+}
+
+/// One flight, from waiting for the first flap to the scrolled score.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let game = update(|game| Some(match game {
+        Some(mut game) if step > 0 => {
+            if game.advance(&Mono, &mut Rng) {
+                log!("flappy crashed after {} pipes, {} frames a second", game.score(), game.frame_rate().unwrap_or(0));
+            }
+            game
+        }
+        // NOTE: a new flight, or the launcher cut the last one short
+        _ => Game::new(),
+    }))?;
+    game.frame(&Mono)
+}
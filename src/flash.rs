@@ -0,0 +1,56 @@
+//! Minimal NVMC driver for the pages reserved at the top of flash.
+//!
+//! NOTE: these are free functions poking the NVMC registers directly, without owning the
+//! peripheral, so they can also be used from the panic and fault handlers.
+//! The CPU stalls while the NVMC erases or writes, which keeps this simple but means
+//! an erase (~85 ms) holds off every interrupt for that long.
+
+use microbit::pac::NVMC;
+
+pub const PAGE_SIZE : u32 = 4096;
+
+// NOTE: memory.x only hands the lower 384K of the 512K flash to the linker,
+// so these pages can never overlap the program
+pub const CRASH_LOG_PAGE : u32 = 0x0007_F000;
+pub const SETTINGS_PAGE  : u32 = 0x0007_E000;
+// NOTE: two pages, see kv.rs
+pub const KV_PAGES       : u32 = 0x0007_C000;
+// NOTE: the ota feature links the program into the lower half, the upper one up to here
+// takes the new image, see ota.rs
+#[cfg(feature = "ota")]
+pub const OTA_BANK         : u32 = 0x0004_0000;
+#[cfg(feature = "ota")]
+pub const OTA_TRAILER_PAGE : u32 = 0x0007_B000;
+
+fn nvmc() -> &'static microbit::pac::nvmc::RegisterBlock {
+    unsafe { &*NVMC::ptr() }
+}
+
+fn wait_ready() {
+    while nvmc().ready.read().ready().is_busy() {}
+}
+
+pub fn erase_page(page : u32) {
+    debug_assert!(page % PAGE_SIZE == 0);
+    wait_ready();
+    nvmc().config.write(|w| w.wen().een());
+    nvmc().erasepage().write(|w| unsafe { w.bits(page) });
+    wait_ready();
+    nvmc().config.write(|w| w.wen().ren());
+}
+
+/// Writing can only clear bits, so the target words have to be erased first.
+pub fn write_words(address : u32, words : &[u32]) {
+    debug_assert!(address % 4 == 0);
+    nvmc().config.write(|w| w.wen().wen());
+    for (i, word) in words.iter().enumerate() {
+        wait_ready();
+        unsafe { core::ptr::write_volatile((address as *mut u32).add(i), *word) };
+    }
+    wait_ready();
+    nvmc().config.write(|w| w.wen().ren());
+}
+
+pub fn read_word(address : u32) -> u32 {
+    unsafe { core::ptr::read_volatile(address as *const u32) }
+}
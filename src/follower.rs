@@ -0,0 +1,160 @@
+//! Line follower: the motors of motor.rs steered along a dark line by two reflectance sensors,
+//! see `fun_core::follow`.
+//!
+//! The left sensor goes on ring 0 and the right one on ring 1, the only edge pins with an
+//! analog input to spare: ring 0 is the soil probe's too, ring 1 stays the pulse meter's and
+//! the SAADC reads it alongside. supply_monitor owns the SAADC, so while the follow app is on
+//! screen it comes round every `CONTROL_MS`, reads both sensors and hands them to `step`.
+//! A starts following, B stops, so does losing the line. The app shows where the line is
+//! under the board. `follow` on the console prints the tuning, `follow kp|ki|kd|speed <n>`
+//! changes it and keeps it in the key-value store.
+//!
+//! NOTE: it needs the motors on, `set motor tilt` or `remote`, which hands them their pins.
+
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::Mutex;
+use embedded_hal::adc::Channel;
+use fun_core::drive::{Drive, FULL};
+use fun_core::follow::{self, Pid, Tuning};
+use microbit::hal::saadc::Saadc;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::kv;
+use crate::log;
+use crate::logging::Level;
+use crate::mono::{Instant, Mono};
+use crate::motor;
+
+pub const CONTROL_MS : u64 = 20;
+// NOTE: how often supply_monitor looks whether the app came on screen
+const IDLE_POLL_MS : u64 = 500;
+const ACTIVE_MS : u64 = 500;
+const POLL_MS : u32 = 50;
+
+pub const ICON : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 0, 0],
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+];
+
+/// Ring 1 as an analog input for the SAADC, which only needs its channel, the pin stays the
+/// pulse meter's.
+pub struct Ring1;
+
+impl Channel<Saadc> for Ring1 {
+    type ID = u8;
+
+    fn channel() -> u8 {
+        1
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Tune {
+    Kp(i16),
+    Ki(i16),
+    Kd(i16),
+    Speed(i8),
+}
+
+impl Tune {
+    pub fn parse(name : &str, value : &str) -> Option<Tune> {
+        match name {
+            "kp" => value.parse().ok().map(Tune::Kp),
+            "ki" => value.parse().ok().map(Tune::Ki),
+            "kd" => value.parse().ok().map(Tune::Kd),
+            "speed" => value.parse().ok().filter(|speed : &i8| (0..=FULL).contains(speed)).map(Tune::Speed),
+            _ => None,
+        }
+    }
+}
+
+// NOTE: None until the stored tuning is read, on the first use
+static TUNING : Mutex<Cell<Option<Tuning>>> = Mutex::new(Cell::new(None));
+static PID : Mutex<RefCell<Pid>> = Mutex::new(RefCell::new(Pid::new()));
+static FOLLOWING : AtomicBool = AtomicBool::new(false);
+// NOTE: where the line was at the last reading, None when lost
+static POSITION : Mutex<Cell<Option<i8>>> = Mutex::new(Cell::new(None));
+static DRAWN : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+pub fn tuning() -> Tuning {
+    if let Some(tuning) = cortex_m::interrupt::free(|cs| TUNING.borrow(cs).get()) {
+        return tuning;
+    }
+    let mut bytes = [0; kv::MAX_VALUE_LEN];
+    let tuning = kv::get(kv::FOLLOW_TUNING, &mut bytes)
+        .and_then(|len| Tuning::decode(&bytes[..len]))
+        .unwrap_or(Tuning::DEFAULT);
+    cortex_m::interrupt::free(|cs| TUNING.borrow(cs).set(Some(tuning)));
+    tuning
+}
+
+pub fn tune(tune : Tune) {
+    let mut tuning = tuning();
+    match tune {
+        Tune::Kp(kp) => tuning.kp = kp,
+        Tune::Ki(ki) => tuning.ki = ki,
+        Tune::Kd(kd) => tuning.kd = kd,
+        Tune::Speed(speed) => tuning.speed = speed,
+    }
+    kv::set(kv::FOLLOW_TUNING, &tuning.encode());
+    cortex_m::interrupt::free(|cs| TUNING.borrow(cs).set(Some(tuning)));
+}
+
+/// True while the app is on screen, supply_monitor reads the sensors for it then.
+pub fn active(now : Instant) -> bool {
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).get())
+        .is_some_and(|drawn| now.checked_duration_since(drawn).map_or(0, |elapsed| elapsed.to_millis()) < ACTIVE_MS)
+}
+
+/// How long supply_monitor waits before it comes round again.
+pub fn poll_ms(now : Instant) -> u64 {
+    if active(now) { CONTROL_MS } else { IDLE_POLL_MS }
+}
+
+/// Takes the raw readings of the left and the right sensor and, while following, drives.
+pub fn step(left : u16, right : u16) {
+    cortex_m::interrupt::free(|cs| POSITION.borrow(cs).set(follow::position(left, right)));
+    if !FOLLOWING.load(Ordering::Relaxed) {
+        return;
+    }
+    let tuning = tuning();
+    let drive = cortex_m::interrupt::free(|cs| PID.borrow(cs).borrow_mut().drive(&tuning, left, right));
+    motor::set(drive);
+    if drive == Drive::STOP {
+        FOLLOWING.store(false, Ordering::Relaxed);
+        log!(Level::Warn, "line lost, stopped following");
+    }
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    match input {
+        Input::Button(Button::A) if motor::wired() => {
+            cortex_m::interrupt::free(|cs| PID.borrow(cs).borrow_mut().reset());
+            FOLLOWING.store(true, Ordering::Relaxed);
+            log!("following the line");
+        }
+        Input::Button(Button::A) => log!(Level::Warn, "no motors to follow the line with, see set motor"),
+        Input::Button(Button::B) => {
+            FOLLOWING.store(false, Ordering::Relaxed);
+            motor::set(Drive::STOP);
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Where the line is under the board.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).set(Some(now)));
+    if FOLLOWING.load(Ordering::Relaxed) {
+        motor::hold(now);
+    }
+    let position = cortex_m::interrupt::free(|cs| POSITION.borrow(cs).get());
+    (step == 0).then_some((follow::marker(position), POLL_MS))
+}
@@ -0,0 +1,70 @@
+//! Frequency counter: the frequency and duty cycle of a digital signal on ring 1, scrolled
+//! across the display, see `fun_core::frequency`.
+//!
+//! The frequency_counter task measures on the pulse meter of the `pulse` command, only while
+//! the app is on screen: the edges over `GATE_MS` and then one period timed, which gives up
+//! after `TIMEOUT_US` or when the edges come faster than the CPU follows, several hundred kHz
+//! still count. Every lap scrolls the latest reading.
+
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use fun_core::frequency::Reading;
+use crate::display::Frame;
+use crate::mono::{Instant, Mono};
+use crate::pulse_meter::Pulse;
+use crate::scroll::{self, Text};
+
+pub const GATE_MS : u32 = 500;
+pub const TIMEOUT_US : u32 = 100_000;
+// NOTE: how often frequency_counter looks whether the app came on screen
+pub const IDLE_POLL_MS : u64 = 500;
+const ACTIVE_MS : u64 = 1000;
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 0],
+    [1, 1, 0, 1, 1],
+    [1, 1, 0, 1, 1],
+    [1, 1, 0, 1, 1],
+    [1, 0, 1, 1, 0],
+];
+
+static LAST : Mutex<Cell<Option<Reading>>> = Mutex::new(Cell::new(None));
+static DRAWN : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+// NOTE: the text of the lap scrolling, made at its start so it stays put
+static TEXT : Mutex<RefCell<Text>> = Mutex::new(RefCell::new(Text::new()));
+
+/// True while the app is on screen, frequency_counter measures then.
+pub fn active(now : Instant) -> bool {
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).get())
+        .is_some_and(|drawn| now.checked_duration_since(drawn).map_or(0, |elapsed| elapsed.to_millis()) < ACTIVE_MS)
+}
+
+/// Keeps the reading of `edges` counted over `GATE_MS` and `pulse` timed after them.
+pub fn record(edges : u32, pulse : Option<Pulse>) {
+    let reading = Reading::new(edges, GATE_MS, pulse.map(|pulse| (pulse.frequency_hz(), pulse.duty_percent())));
+    cortex_m::interrupt::free(|cs| LAST.borrow(cs).set(Some(reading)));
+}
+
+fn text() -> Text {
+    let mut text = Text::new();
+    let _ = match cortex_m::interrupt::free(|cs| LAST.borrow(cs).get()) {
+        Some(reading) => write!(text, "{}", reading),
+        None => write!(text, "measuring"),
+    };
+    text
+}
+
+/// One lap of the latest reading.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    let text = cortex_m::interrupt::free(|cs| {
+        DRAWN.borrow(cs).set(Some(now));
+        let mut shown = TEXT.borrow(cs).borrow_mut();
+        if step == 0 {
+            *shown = text();
+        }
+        shown.clone()
+    });
+    scroll::frames(&text).nth(step).map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
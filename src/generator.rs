@@ -0,0 +1,144 @@
+//! Signal generator: a square wave on P16 of the edge connector, see `fun_core::wave`, a test
+//! signal for LEDs or a motor driver on a breadboard.
+//!
+//! Made without the CPU, like blink.rs: RTC2 counts at 32768 Hz, its first compare sets the
+//! pin over PPI and clears the counter, the second clears the pin. The display and the
+//! speaker hold the PWMs and every timer is taken, hence the RTC and its coarse timing.
+//! `pwm <hz> [<duty>]` on the console starts it and `pwm off` stops it. In the app A steps the
+//! frequency through presets and B the duty cycle, both start it, a long press of A stops it.
+//! The wave keeps going after the app is left.
+//!
+//! NOTE: free functions like speaker.rs, `init` takes RTC2 and the pin so nothing else drives
+//! them.
+
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use fun_core::frequency::Reading;
+use fun_core::wave::Wave;
+use microbit::hal::gpio::{Pin, Output, PushPull};
+use microbit::hal::gpiote::Gpiote;
+use microbit::pac::{GPIOTE, RTC2};
+use crate::apps::Input;
+use crate::channels::{self, Exhausted, GpioteChannel, PpiChannel};
+use crate::display::Frame;
+use crate::events::Button;
+use crate::log;
+use crate::mono::Instant;
+use crate::ppi;
+use crate::scroll::{self, Text};
+
+pub const PIN : &str = "P16";
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 0],
+    [1, 1, 0, 1, 1],
+    [1, 0, 0, 1, 0],
+    [1, 0, 0, 1, 0],
+    [1, 0, 1, 1, 0],
+];
+
+#[derive(Clone, Copy)]
+struct Wiring {
+    pin  : GpioteChannel,
+    rise : PpiChannel,
+    fall : PpiChannel,
+}
+
+// NOTE: None until init wired it up
+static WIRING : Mutex<Cell<Option<Wiring>>> = Mutex::new(Cell::new(None));
+// NOTE: None while stopped
+static WAVE : Mutex<Cell<Option<Wave>>> = Mutex::new(Cell::new(None));
+// NOTE: the text of the lap scrolling, made at its start so it stays put
+static TEXT : Mutex<RefCell<Text>> = Mutex::new(RefCell::new(Text::new()));
+
+fn rtc() -> &'static microbit::pac::rtc0::RegisterBlock {
+    unsafe { &*RTC2::ptr() }
+}
+
+pub fn init(_rtc : RTC2, gpiote : &Gpiote, pin : Pin<Output<PushPull>>) -> Result<(), Exhausted> {
+    let wiring = Wiring {
+        pin  : channels::gpiote("generator")?,
+        rise : channels::ppi("generator")?,
+        fall : channels::ppi("generator")?,
+    };
+    let rtc = rtc();
+    rtc.tasks_stop.write(|w| unsafe { w.bits(1) });
+    rtc.prescaler.write(|w| unsafe { w.prescaler().bits(0) });
+    rtc.evtenset.write(|w| w.compare0().set().compare1().set());
+
+    let channel = wiring.pin.of(gpiote);
+    channel.output_pin(pin).init_low();
+    ppi::connect(wiring.rise, &rtc.events_compare[0], channel.task_set());
+    ppi::fork(wiring.rise, &rtc.tasks_clear);
+    ppi::connect(wiring.fall, &rtc.events_compare[1], channel.task_clr());
+
+    cortex_m::interrupt::free(|cs| WIRING.borrow(cs).set(Some(wiring)));
+    Ok(())
+}
+
+pub fn wave() -> Option<Wave> {
+    cortex_m::interrupt::free(|cs| WAVE.borrow(cs).get())
+}
+
+/// Starts `wave` on the pin, None stops it and leaves the pin low.
+pub fn set(wave : Option<Wave>) {
+    let Some(wiring) = cortex_m::interrupt::free(|cs| WIRING.borrow(cs).get()) else { return };
+    let rtc = rtc();
+    rtc.tasks_stop.write(|w| unsafe { w.bits(1) });
+    ppi::disable(wiring.rise);
+    ppi::disable(wiring.fall);
+    // NOTE: the gpiote resource belongs to the button interrupt, the pin's tasks are all that is touched
+    let gpiote = unsafe { &*GPIOTE::ptr() };
+    match wave.map(|wave| wave.ticks()) {
+        Some((period, high)) if high == period => gpiote.tasks_set[wiring.pin.index()].write(|w| unsafe { w.bits(1) }),
+        Some((period, high)) if high > 0 => {
+            // NOTE: the clear lands a tick after the compare
+            rtc.cc[0].write(|w| unsafe { w.bits(period - 1) });
+            rtc.cc[1].write(|w| unsafe { w.bits(high) });
+            rtc.tasks_clear.write(|w| unsafe { w.bits(1) });
+            gpiote.tasks_set[wiring.pin.index()].write(|w| unsafe { w.bits(1) });
+            ppi::enable(wiring.rise);
+            ppi::enable(wiring.fall);
+            rtc.tasks_start.write(|w| unsafe { w.bits(1) });
+        }
+        _ => gpiote.tasks_clr[wiring.pin.index()].write(|w| unsafe { w.bits(1) }),
+    }
+    cortex_m::interrupt::free(|cs| WAVE.borrow(cs).set(wave));
+    match wave {
+        Some(wave) => log!("generator {} Hz {}% on {}", wave.hz, wave.duty_percent, PIN),
+        None => log!("generator off"),
+    }
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let wave = wave();
+    match input {
+        Input::Button(Button::A) => set(Some(wave.map_or(Wave::DEFAULT, Wave::next_frequency))),
+        Input::Button(Button::B) => set(Some(wave.map_or(Wave::DEFAULT, Wave::next_duty))),
+        Input::LongPress(Button::A) => set(None),
+        _ => return false,
+    }
+    true
+}
+
+fn text() -> Text {
+    let mut text = Text::new();
+    let _ = match wave() {
+        Some(wave) => write!(text, "{}", Reading { hz : wave.hz, duty_percent : Some(wave.duty_percent as u32) }),
+        None => write!(text, "off"),
+    };
+    text
+}
+
+/// One lap of the wave going out.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let text = cortex_m::interrupt::free(|cs| {
+        let mut shown = TEXT.borrow(cs).borrow_mut();
+        if step == 0 {
+            *shown = text();
+        }
+        shown.clone()
+    });
+    scroll::frames(&text).nth(step).map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
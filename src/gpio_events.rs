@@ -0,0 +1,83 @@
+//! Edge events from the free edge connector pins, without a GPIOTE channel per pin.
+//!
+//! Every pin senses the level opposite to the one it is at, a change on any of them raises the
+//! one GPIOTE PORT event. The ports run in latched detect mode, so their LATCH registers tell
+//! which pins changed, and those pins get their sense flipped to catch the edge back.
+//!
+//! NOTE: a pin that changes twice before the interrupt gets to it is reported once, with the
+//! level it ended up at.
+
+use embedded_hal::digital::v2::InputPin;
+use heapless::Vec;
+use microbit::hal::gpio::{Pin, Input, PullUp, Port};
+use microbit::hal::gpiote::Gpiote;
+use microbit::pac::{P0, P1};
+
+// NOTE: at most, init hands in none while the motors have the pins, see motor.rs
+pub const PINS : usize = 4;
+
+/// An edge connector pin by its label, with its pull-up the pin is high until shorted to GND.
+pub type EdgePin = (&'static str, Pin<Input<PullUp>>);
+
+#[derive(Clone, Copy)]
+pub struct Edge {
+    pub pin  : &'static str,
+    pub high : bool,
+}
+
+pub struct GpioEvents {
+    pins : Vec<EdgePin, PINS>,
+}
+
+fn port(pin : &Pin<Input<PullUp>>) -> &'static microbit::pac::p0::RegisterBlock {
+    // NOTE: the pins are handed in, only their sense and latch bits are touched through these
+    match pin.port() {
+        Port::Port0 => unsafe { &*P0::ptr() },
+        Port::Port1 => unsafe { &*P1::ptr() },
+    }
+}
+
+/// Senses the level opposite to the current one, returns the current one.
+fn sense(gpiote : &Gpiote, pin : &Pin<Input<PullUp>>) -> bool {
+    let high = pin.is_high().unwrap();
+    let port = gpiote.port();
+    if high {
+        port.input_pin(pin).low();
+    } else {
+        port.input_pin(pin).high();
+    }
+    high
+}
+
+impl GpioEvents {
+    pub fn new(gpiote : &Gpiote, pins : Vec<EdgePin, PINS>) -> Self {
+        for regs in [unsafe { &*P0::ptr() }, unsafe { &*P1::ptr() }] {
+            regs.detectmode.write(|w| w.detectmode().ldetect());
+        }
+        for (_, pin) in &pins {
+            sense(gpiote, pin);
+            port(pin).latch.write(|w| unsafe { w.bits(1 << pin.pin()) });
+        }
+        gpiote.port().reset_events();
+        gpiote.port().enable_interrupt();
+        GpioEvents { pins }
+    }
+
+    /// Hands every pin that changed to `handle`, call it from the GPIOTE interrupt.
+    pub fn on_interrupt(&mut self, gpiote : &Gpiote, mut handle : impl FnMut(Edge)) {
+        if !gpiote.port().is_event_triggered() {
+            return;
+        }
+        gpiote.port().reset_events();
+        for (label, pin) in &self.pins {
+            let regs = port(pin);
+            let bit = 1 << pin.pin();
+            if regs.latch.read().bits() & bit != 0 {
+                // NOTE: flipped before the latch is cleared, so a change in between latches again
+                let high = sense(gpiote, pin);
+                regs.latch.write(|w| unsafe { w.bits(bit) });
+                handle(Edge { pin : label, high });
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Greenhouse monitor: an SHT3x on the external I2C bus of the edge connector, read every
+//! `PERIOD_SECS`, with alarms when the temperature or the humidity goes past the limits of
+//! `set greenhouse_temp` and `set greenhouse_humidity`, see `fun_core::climate`.
+//!
+//! init looks for the sensor once, the greenhouse_monitor task only runs when it answered.
+//! An alarm shows `ALARM` as a toast, beeps, held back in the quiet hours of dnd.rs, and floods
+//! a message over the radio like `send`, so the boards relaying show it too. The app scrolls
+//! the last reading and the alarms up.
+//!
+//! NOTE: a BME280 on the same bus isn't read, its readings need the compensation of its
+//! calibration data, which isn't done here.
+
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::Mutex;
+use fun_core::climate::{self, Alarm, Limits, Reading};
+use microbit::board::I2CExternalPins;
+use microbit::hal::twim::{self, Twim};
+use microbit::pac::TWIM1;
+use crate::display::Frame;
+use crate::log;
+use crate::logging::Level;
+use crate::relay;
+use crate::scroll::{self, Text};
+use crate::toast;
+
+pub const PERIOD_SECS : u64 = 10;
+pub const ALARM_SECS : u32 = 3;
+pub const ALARM_HZ : u32 = 2000;
+
+pub const ICON : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+    [0, 1, 1, 1, 0],
+    [0, 1, 1, 1, 0],
+];
+
+pub const ALARM : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+    [0, 1, 1, 1, 0],
+    [1, 1, 1, 1, 1],
+    [0, 0, 1, 0, 0],
+];
+
+const SOFT_RESET : [u8; 2] = [0x30, 0xa2];
+
+pub struct Sensor {
+    twim : Twim<TWIM1>,
+}
+
+impl Sensor {
+    /// None when no sensor answers.
+    pub fn new(twim : TWIM1, pins : I2CExternalPins) -> Option<Self> {
+        let mut twim = Twim::new(twim, pins.into(), twim::Frequency::K100);
+        // NOTE: copied to the stack, the TWIM sends from RAM only
+        let reset = SOFT_RESET;
+        twim.write(climate::ADDRESS, &reset).ok()?;
+        Some(Sensor { twim })
+    }
+
+    /// Starts a measurement, ready `climate::MEASURE_MS` later.
+    pub fn start(&mut self) -> bool {
+        let measure = climate::MEASURE;
+        self.twim.write(climate::ADDRESS, &measure).is_ok()
+    }
+
+    pub fn read(&mut self) -> Option<Reading> {
+        let mut raw = [0; 6];
+        self.twim.read(climate::ADDRESS, &mut raw).ok()?;
+        climate::decode(&raw)
+    }
+}
+
+static PRESENT : AtomicBool = AtomicBool::new(false);
+static LIMITS : Mutex<Cell<Limits>> = Mutex::new(Cell::new(Limits::DEFAULT));
+static LAST : Mutex<Cell<Option<Reading>>> = Mutex::new(Cell::new(None));
+// NOTE: the alarms up after the last reading
+static RAISED : Mutex<Cell<[Option<Alarm>; 4]>> = Mutex::new(Cell::new([None; 4]));
+// NOTE: the text of the lap scrolling, made at its start so it stays put
+static TEXT : Mutex<RefCell<Text>> = Mutex::new(RefCell::new(Text::new()));
+
+pub fn set_present(present : bool) {
+    PRESENT.store(present, Ordering::Relaxed);
+}
+
+pub fn present() -> bool {
+    PRESENT.load(Ordering::Relaxed)
+}
+
+pub fn configure(limits : Limits) {
+    cortex_m::interrupt::free(|cs| LIMITS.borrow(cs).set(limits));
+}
+
+pub fn limits() -> Limits {
+    cortex_m::interrupt::free(|cs| LIMITS.borrow(cs).get())
+}
+
+fn write_reading(text : &mut impl Write, reading : &Reading) -> core::fmt::Result {
+    let tenths = |value : i16| (if value < 0 { "-" } else { "" }, value.unsigned_abs() / 10, value.unsigned_abs() % 10);
+    let (sign, degrees, tenth) = tenths(reading.tenths_c);
+    let (_, percent, _) = tenths(reading.tenths_rh);
+    write!(text, "{}{}.{}C {}%", sign, degrees, tenth, percent)
+}
+
+/// Keeps a reading, None when the sensor didn't answer, and the alarms up after it.
+pub fn record(reading : Option<Reading>, raised : impl Iterator<Item = Alarm>) {
+    let mut up = [None; 4];
+    for (slot, alarm) in up.iter_mut().zip(raised) {
+        *slot = Some(alarm);
+    }
+    cortex_m::interrupt::free(|cs| {
+        LAST.borrow(cs).set(reading);
+        RAISED.borrow(cs).set(up);
+    });
+}
+
+/// Tells everyone about `alarm`, raised by `reading`; the beeps are up to the caller.
+pub fn raise(alarm : Alarm, reading : &Reading, origin : u16) {
+    let mut text = Text::new();
+    let _ = write!(text, "greenhouse {} ", alarm.name());
+    let _ = write_reading(&mut text, reading);
+    log!(Level::Warn, "{}", text.as_str());
+    toast::icon(ALARM, ALARM_SECS);
+    relay::send(origin, &text);
+}
+
+fn text() -> Text {
+    let mut text = Text::new();
+    if !present() {
+        let _ = text.push_str("no sensor");
+        return text;
+    }
+    let (last, raised) = cortex_m::interrupt::free(|cs| (LAST.borrow(cs).get(), RAISED.borrow(cs).get()));
+    let _ = match last {
+        Some(reading) => write_reading(&mut text, &reading),
+        None => write!(text, "no reading"),
+    };
+    for alarm in raised.into_iter().flatten() {
+        let _ = write!(text, " {}!", alarm.name());
+    }
+    text
+}
+
+/// One lap of the last reading and the alarms up.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let text = cortex_m::interrupt::free(|cs| {
+        let mut shown = TEXT.borrow(cs).borrow_mut();
+        if step == 0 {
+            *shown = text();
+        }
+        shown.clone()
+    });
+    scroll::frames(&text).nth(step).map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
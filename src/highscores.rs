@@ -0,0 +1,121 @@
+//! Top three scores of every game, kept in the key-value store.
+//!
+//! A game hands a finished score to `finish`, or straight to `qualifies`, when it makes the table the player enters
+//! three initials with the buttons (A picks the letter, B confirms it) and `submit` stores it.
+//! `text` renders a table for scrolling across the display, the scores app scrolls the table
+//! of one game after the other, A skips on to the next game.
+
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use critical_section::Mutex;
+use fun_core::scores;
+use crate::apps::Input;
+use crate::breakout;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::kv::{self, Key};
+use crate::mono::Instant;
+use crate::scroll::{self, Text};
+use crate::shooter;
+use crate::simon;
+
+pub use fun_core::scores::{InitialsEntry, Table};
+
+pub type GameId = u8;
+
+// NOTE: the games keeping a table, in the order the scores app shows them
+const GAMES : [(GameId, &str); 3] = [(simon::GAME, "simon"), (shooter::GAME, "shooter"), (breakout::GAME, "breakout")];
+
+pub const ICON : Frame = [
+    [1, 1, 1, 1, 1],
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+];
+
+fn key(game : GameId) -> Key {
+    kv::HIGH_SCORES + game as Key
+}
+
+pub fn load(game : GameId) -> Table {
+    let mut bytes = [0; scores::TABLE_LEN];
+    let len = kv::get(key(game), &mut bytes).unwrap_or(0);
+    scores::decode(&bytes[..len])
+}
+
+fn store(game : GameId, table : &Table) {
+    kv::set(key(game), &scores::encode(table));
+}
+
+/// The place, counted from 0, `score` would take in the table.
+pub fn qualifies(game : GameId, score : u32) -> Option<usize> {
+    scores::place(&load(game), score)
+}
+
+/// Enters the score, returns its place or None when it didn't make the table after all.
+pub fn submit(game : GameId, initials : [u8; 3], score : u32) -> Option<usize> {
+    let mut table = load(game);
+    let place = scores::insert(&mut table, initials, score)?;
+    store(game, &table);
+    Some(place)
+}
+
+/// `1 ABC 120 2 XYZ 80 3 --- 0`
+pub fn text(game : GameId) -> Text {
+    scores::text(&load(game))
+}
+
+// NOTE: the games run in idle and the app hooks, which can't reach the initials entry
+static FINISHED : Mutex<Cell<Option<(GameId, u32)>>> = Mutex::new(Cell::new(None));
+
+/// Hands the final score of a round to idle, which starts the initials entry when it
+/// makes the table.
+pub fn finish(game : GameId, score : u32) {
+    critical_section::with(|cs| FINISHED.borrow(cs).set(Some((game, score))));
+}
+
+pub fn take_finished() -> Option<(GameId, u32)> {
+    critical_section::with(|cs| FINISHED.borrow(cs).take())
+}
+
+/// A score that made the table, waiting for its initials.
+#[derive(Clone, Copy)]
+pub struct Pending {
+    pub game  : GameId,
+    pub score : u32,
+    pub entry : InitialsEntry,
+}
+
+static SHOWING : AtomicUsize = AtomicUsize::new(0);
+// NOTE: set by A, the lap scrolling ends early for the next game's to start
+static SKIPPED : AtomicBool = AtomicBool::new(false);
+static TEXT : Mutex<RefCell<Text>> = Mutex::new(RefCell::new(Text::new()));
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    if !matches!(input, Input::Button(Button::A)) {
+        return false;
+    }
+    SHOWING.store((SHOWING.load(Ordering::Relaxed) + 1) % GAMES.len(), Ordering::Relaxed);
+    SKIPPED.store(true, Ordering::Relaxed);
+    true
+}
+
+/// The name and the table of the game showing, scrolling round.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    if SKIPPED.swap(false, Ordering::Relaxed) && step > 0 {
+        return None;
+    }
+    // NOTE: made at the start of a lap, a score entered meanwhile shows on the next
+    let text = critical_section::with(|cs| {
+        let mut shown = TEXT.borrow(cs).borrow_mut();
+        if step == 0 {
+            let (game, name) = GAMES[SHOWING.load(Ordering::Relaxed) % GAMES.len()];
+            shown.clear();
+            let _ = write!(shown, "{} {}", name, text(game));
+        }
+        shown.clone()
+    });
+    scroll::frames(&text).nth(step).map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
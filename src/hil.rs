@@ -0,0 +1,134 @@
+//! Self-test commands over RTT, for a host script running regression tests on a real board
+//! (`make hil`, the script is hil/selftest.py).
+//!
+//! With the `hil` feature RTT gets a second pair of channels named "hil" next to the log's
+//! "Terminal". The script writes one command a line to down channel 1 and reads one reply a
+//! line from up channel 1: injected presses run through the same dispatch as the button
+//! interrupt, the other inputs reach the running app like the polled ones, and the frame on
+//! the display, the running app and the accelerometer can be read back.
+//!
+//!     press a|b|ab|logo  long a|b  shake  tilt <x> <y>  frame  app  accel  ping  exit pass|fail
+//!
+//! `exit` ends the run with the script's result, see exit.rs.
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+use rtt_target::{rtt_init, ChannelMode, DownChannel, UpChannel};
+use crate::console::{Line, LineBuffer};
+use crate::events::Button;
+
+pub enum Command {
+    Press(Button),
+    LongPress(Button),
+    Shake,
+    Tilt { x : i32, y : i32 },
+    Frame,
+    App,
+    Accel,
+    Ping,
+    Exit { passed : bool },
+    Unknown,
+}
+
+fn parse_button(word : Option<&str>) -> Option<Button> {
+    match word? {
+        "a"    => Some(Button::A),
+        "b"    => Some(Button::B),
+        "ab"   => Some(Button::AB),
+        "logo" => Some(Button::Logo),
+        _      => None,
+    }
+}
+
+pub fn parse(line : &str) -> Command {
+    let mut words = line.split_whitespace();
+    match words.next() {
+        Some("press") => parse_button(words.next()).map_or(Command::Unknown, Command::Press),
+        Some("long") => match parse_button(words.next()) {
+            Some(button @ (Button::A | Button::B)) => Command::LongPress(button),
+            _ => Command::Unknown,
+        },
+        Some("shake") => Command::Shake,
+        Some("tilt") => match (words.next().map(str::parse), words.next().map(str::parse)) {
+            (Some(Ok(x)), Some(Ok(y))) => Command::Tilt { x, y },
+            _ => Command::Unknown,
+        },
+        Some("frame") => Command::Frame,
+        Some("app")   => Command::App,
+        Some("accel") => Command::Accel,
+        Some("ping")  => Command::Ping,
+        Some("exit") => match words.next() {
+            Some("pass") => Command::Exit { passed : true },
+            Some("fail") => Command::Exit { passed : false },
+            _ => Command::Unknown,
+        },
+        _             => Command::Unknown,
+    }
+}
+
+struct Channels {
+    up   : UpChannel,
+    down : DownChannel,
+    line : LineBuffer,
+}
+
+static CHANNELS : Mutex<RefCell<Option<Channels>>> = Mutex::new(RefCell::new(None));
+
+/// Sets up RTT in place of `rtt_init_print!`, the log keeps channel 0.
+pub fn init() {
+    let channels = rtt_init! {
+        up: {
+            0: {
+                size: 1024
+                mode: NoBlockSkip
+                name: "Terminal"
+            }
+            1: {
+                size: 256
+                mode: NoBlockSkip
+                name: "hil"
+            }
+        }
+        down: {
+            0: {
+                size: 16
+                name: "Terminal"
+            }
+            1: {
+                size: 64
+                name: "hil"
+            }
+        }
+    };
+    rtt_target::set_print_channel(channels.up.0);
+    let (mut up, down) = (channels.up.1, channels.down.1);
+    // NOTE: a self-test build always has the script attached, and a dropped reply fails a test
+    up.set_mode(ChannelMode::BlockIfFull);
+    cortex_m::interrupt::free(|cs| {
+        CHANNELS.borrow(cs).replace(Some(Channels { up, down, line : LineBuffer::new() }));
+    });
+}
+
+/// The next complete command line, bytes after it stay in the channel for the next call.
+pub fn next_command() -> Option<Line> {
+    cortex_m::interrupt::free(|cs| {
+        let mut channels = CHANNELS.borrow(cs).borrow_mut();
+        let channels = channels.as_mut()?;
+        let mut byte = [0; 1];
+        while channels.down.read(&mut byte) == 1 {
+            if let Some(line) = channels.line.push(byte[0]) {
+                return Some(line);
+            }
+        }
+        None
+    })
+}
+
+pub fn reply(s : &str) {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(channels) = CHANNELS.borrow(cs).borrow_mut().as_mut() {
+            channels.up.write(s.as_bytes());
+            channels.up.write(b"\n");
+        }
+    });
+}
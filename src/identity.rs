@@ -0,0 +1,52 @@
+//! Who this board is: the factory programmed FICR device id and address, plus an optional
+//! name in the UICR customer registers.
+//!
+//! The name is up to 16 ascii bytes in CUSTOMER[0..4], unused bytes erased (0xff), and is
+//! written with the debugger, e.g. `probe-rs write b32 --chip nRF52833_xxAA 0x10001080 0x6c6c6562`
+//! for "bell". The low bytes of the device address identify the board in radio packets.
+
+use heapless::String;
+use microbit::pac::{FICR, UICR};
+
+pub const NAME_LEN : usize = 16;
+
+#[derive(Clone)]
+pub struct Identity {
+    pub device_id : u64,
+    pub address   : [u8; 6],
+    pub name      : Option<String<NAME_LEN>>,
+}
+
+impl Identity {
+    pub fn read(ficr : &FICR) -> Identity {
+        let device_id = (ficr.deviceid[1].read().bits() as u64) << 32 | ficr.deviceid[0].read().bits() as u64;
+        let address_low = ficr.deviceaddr[0].read().bits().to_le_bytes();
+        let address_high = ficr.deviceaddr[1].read().bits().to_le_bytes();
+        let address = [
+            address_low[0], address_low[1], address_low[2], address_low[3],
+            address_high[0], address_high[1],
+        ];
+
+        // NOTE: the UICR is not part of the board struct, it is only read here
+        let uicr = unsafe { &*UICR::ptr() };
+        let mut name = String::new();
+        for register in &uicr.customer[..NAME_LEN / 4] {
+            for byte in register.read().bits().to_le_bytes() {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    let _ = name.push(byte as char);
+                }
+            }
+        }
+
+        Identity {
+            device_id,
+            address,
+            name : if name.is_empty() { None } else { Some(name) },
+        }
+    }
+
+    /// Sender address in radio packets, the low bytes of the device address.
+    pub fn radio_address(&self) -> u16 {
+        u16::from_le_bytes([self.address[0], self.address[1]])
+    }
+}
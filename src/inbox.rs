@@ -0,0 +1,84 @@
+//! Inbox: the relayed messages kept after their toast scrolled by, see `fun_core::inbox`
+//! and relay.rs.
+//!
+//! Every new message relay.rs shows goes into the inbox too, the last `MESSAGES` of them are
+//! kept. While any is unread the top right corner of every app's frame lights up, an LED a
+//! message. The inbox app scrolls the message the cursor is on, its place in front, over and
+//! over, reading it. A moves on to the next older message, round to the newest after the
+//! oldest, and B deletes the one showing.
+//!
+//! NOTE: the inbox is in RAM, a reset empties it.
+
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use fun_core::inbox::{self, Inbox};
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::mono::{Instant, Mono};
+use crate::relay::{Message, TEXT_LEN};
+use crate::scroll::{self, Text};
+
+const MESSAGES : usize = 8;
+const POLL_MS : u32 = 100;
+const DIM_LEVEL : u8 = 3;
+
+pub const ICON : Frame = [
+    [1, 1, 1, 1, 1],
+    [1, 1, 0, 1, 1],
+    [1, 0, 1, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+];
+
+static INBOX : Mutex<RefCell<Inbox<MESSAGES, TEXT_LEN>>> = Mutex::new(RefCell::new(Inbox::new()));
+// NOTE: when the message showing started scrolling, from the start again on A or B
+static SHOWN : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+pub fn receive(message : &Message) {
+    cortex_m::interrupt::free(|cs| INBOX.borrow(cs).borrow_mut().receive(message.origin, &message.text));
+}
+
+/// `frame` with the corner lit for the unread messages.
+pub fn indicate(frame : Frame) -> Frame {
+    inbox::indicate(frame, cortex_m::interrupt::free(|cs| INBOX.borrow(cs).borrow().unread()))
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    cortex_m::interrupt::free(|cs| {
+        let mut inbox = INBOX.borrow(cs).borrow_mut();
+        match input {
+            Input::Button(Button::A) => inbox.next(),
+            Input::Button(Button::B) => inbox.delete(),
+            _ => return false,
+        }
+        SHOWN.borrow(cs).set(None);
+        true
+    })
+}
+
+/// The message the cursor is on, scrolling round, the dim icon while there is none.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    let mut text = Text::new();
+    let read = cortex_m::interrupt::free(|cs| {
+        let mut inbox = INBOX.borrow(cs).borrow_mut();
+        let (message, place) = inbox.read()?;
+        let _ = write!(text, "{} {:04x} {}", place, message.origin, message.text.as_str());
+        let shown = SHOWN.borrow(cs);
+        shown.set(Some(shown.get().unwrap_or(now)));
+        shown.get()
+    });
+    let Some(shown) = read else {
+        return (step == 0).then_some((ICON.map(|row| row.map(|led| led * DIM_LEVEL)), POLL_MS));
+    };
+    let elapsed = now.checked_duration_since(shown).map_or(0, |elapsed| elapsed.to_millis());
+    // NOTE: a cycle is a lap long, the lap goes by the time since the message came up
+    let frames = scroll::frames(&text).count().max(1);
+    if step >= frames {
+        return None;
+    }
+    let at = (elapsed / scroll::STEP_MS as u64) as usize % frames;
+    scroll::frames(&text).nth(at).map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
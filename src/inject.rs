@@ -0,0 +1,106 @@
+//! Synthetic button presses for robustness testing, with the `inject_buttons` feature.
+//!
+//! The `button_storm` task runs every storm in `STORMS` once after boot: it queues presses
+//! here and pends the GPIOTE interrupt, which takes them off the queue and dispatches them
+//! like real ones, at the same priority and preempting the same tasks. Every injected press
+//! is tallied as delivered, to the running app or to a spawned action, or as dropped when
+//! its task was still busy. After each storm the tally is logged, a press counted twice or
+//! not at all means the pipeline duplicated or lost it.
+//!
+//! NOTE: there is no debouncer in the tree, the button interrupt fires on every falling edge,
+//! so a bounce storm shows what a bouncing contact would do without one.
+
+use core::cell::{Cell, RefCell};
+use cortex_m::interrupt::Mutex;
+use heapless::Deque;
+use microbit::pac::{Interrupt, NVIC};
+use crate::events::Button;
+use crate::log;
+use crate::logging::Level;
+use crate::mono::{ExtU64, Mono};
+
+const QUEUE_LEN : usize = 16;
+// NOTE: long enough for the actions a storm spawned to finish
+const SETTLE_MS : u64 = 3000;
+
+static QUEUE : Mutex<RefCell<Deque<Button, QUEUE_LEN>>> = Mutex::new(RefCell::new(Deque::new()));
+
+#[derive(Clone, Copy, Default)]
+struct Tally {
+    injected  : u32,
+    delivered : u32,
+    dropped   : u32,
+}
+
+static TALLY : Mutex<Cell<Tally>> = Mutex::new(Cell::new(Tally { injected : 0, delivered : 0, dropped : 0 }));
+
+struct Storm {
+    name     : &'static str,
+    // NOTE: the presses of one interrupt
+    presses  : &'static [Button],
+    repeats  : u32,
+    apart_ms : u64,
+}
+
+const STORMS : [Storm; 4] = [
+    Storm { name : "burst",        presses : &[Button::A],            repeats : 10, apart_ms : 50 },
+    Storm { name : "bounce",       presses : &[Button::B],            repeats : 8,  apart_ms : 1 },
+    Storm { name : "simultaneous", presses : &[Button::A, Button::B], repeats : 5,  apart_ms : 200 },
+    Storm { name : "queue full",   presses : &[Button::Logo; QUEUE_LEN + 4], repeats : 1, apart_ms : 0 },
+];
+
+/// The next injected press, for the button interrupt.
+pub fn take() -> Option<Button> {
+    cortex_m::interrupt::free(|cs| QUEUE.borrow(cs).borrow_mut().pop_front())
+}
+
+/// How the button interrupt dispatched an injected press.
+pub fn tally(delivered : bool) {
+    cortex_m::interrupt::free(|cs| {
+        let cell = TALLY.borrow(cs);
+        let mut tally = cell.get();
+        if delivered {
+            tally.delivered += 1;
+        } else {
+            tally.dropped += 1;
+        }
+        cell.set(tally);
+    });
+}
+
+fn inject(presses : &[Button]) {
+    cortex_m::interrupt::free(|cs| {
+        let mut queue = QUEUE.borrow(cs).borrow_mut();
+        let cell = TALLY.borrow(cs);
+        let mut tally = cell.get();
+        for &button in presses {
+            tally.injected += 1;
+            // NOTE: a press that doesn't fit is dropped here, the same as one a busy task drops
+            if queue.push_back(button).is_err() {
+                tally.dropped += 1;
+            }
+        }
+        cell.set(tally);
+    });
+    NVIC::pend(Interrupt::GPIOTE);
+}
+
+/// Runs the storms, for the `button_storm` task.
+pub async fn run() {
+    for storm in STORMS {
+        cortex_m::interrupt::free(|cs| TALLY.borrow(cs).set(Tally::default()));
+        for _ in 0..storm.repeats {
+            inject(storm.presses);
+            Mono::delay_until(Mono::now() + storm.apart_ms.millis()).await;
+        }
+        Mono::delay_until(Mono::now() + SETTLE_MS.millis()).await;
+
+        let tally = cortex_m::interrupt::free(|cs| TALLY.borrow(cs).get());
+        let level = if tally.dropped > 0 { Level::Warn } else { Level::Info };
+        log!(level, "storm {}: {} presses, {} delivered, {} dropped",
+            storm.name, tally.injected, tally.delivered, tally.dropped);
+        if tally.delivered + tally.dropped != tally.injected {
+            log!(Level::Error, "storm {}: presses lost or duplicated in the pipeline", storm.name);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Instrument: the board as a tiny motion MIDI controller, see `fun_core::midi`, its notes
+//! played on the speaker.
+//!
+//! Tilting picks the note, A plucks it for `PLUCK_MS`, B holds it until B again and the logo
+//! stops whatever sounds. A note sounding keeps its pitch while the board tilts on. The app
+//! shows the note picked as a dot, the column of a note sounding dim.
+//!
+//! NOTE: the USB MIDI events have nowhere to go on the micro:bit, see `fun_core::midi`, so
+//! the speaker always plays, unless it is muted.
+
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+use fun_core::board::Beeper;
+use fun_core::midi::{self, Pitch};
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::mono::{Instant, Mono, ExtU64};
+use crate::speaker::Speaker;
+
+const PLUCK_MS : u64 = 300;
+const POLL_MS : u32 = 20;
+
+pub const ICON : Frame = [
+    [0, 0, 1, 1, 0],
+    [0, 0, 1, 0, 1],
+    [0, 0, 1, 0, 0],
+    [1, 1, 1, 0, 0],
+    [1, 1, 1, 0, 0],
+];
+
+#[derive(Clone, Copy)]
+struct Sounding {
+    pitch : Pitch,
+    // NOTE: None while held
+    until : Option<Instant>,
+}
+
+static PITCH : Mutex<Cell<Pitch>> = Mutex::new(Cell::new(Pitch::MIDDLE));
+static SOUNDING : Mutex<Cell<Option<Sounding>>> = Mutex::new(Cell::new(None));
+
+fn sound(sounding : Option<Sounding>) {
+    cortex_m::interrupt::free(|cs| SOUNDING.borrow(cs).set(sounding));
+    match sounding {
+        Some(sounding) => Speaker.tone(midi::hz(sounding.pitch.note())),
+        None => Speaker.off(),
+    }
+}
+
+pub fn on_input(input : Input, now : Instant) -> bool {
+    let (pitch, sounding) = cortex_m::interrupt::free(|cs| (PITCH.borrow(cs).get(), SOUNDING.borrow(cs).get()));
+    match input {
+        Input::Tilt { x, y } => cortex_m::interrupt::free(|cs| PITCH.borrow(cs).set(Pitch::from_tilt(x, y))),
+        Input::Button(Button::A) => sound(Some(Sounding { pitch, until : Some(now + PLUCK_MS.millis()) })),
+        Input::Button(Button::B) => match sounding {
+            Some(Sounding { until : None, .. }) => sound(None),
+            _ => sound(Some(Sounding { pitch, until : None })),
+        },
+        Input::Button(Button::Logo) => sound(None),
+        _ => return false,
+    }
+    true
+}
+
+/// The note picked, and the one sounding.
+pub fn draw(_step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    let (pitch, sounding) = cortex_m::interrupt::free(|cs| (PITCH.borrow(cs).get(), SOUNDING.borrow(cs).get()));
+    let sounding = sounding.filter(|sounding| sounding.until.is_none_or(|until| now < until));
+    // NOTE: a pluck ends here
+    sound(sounding);
+    Some((midi::frame(pitch, sounding.map(|sounding| sounding.pitch)), POLL_MS))
+}
@@ -0,0 +1,237 @@
+//! Append-only key-value store on two flash pages, for values that change often.
+//!
+//! Updating a value appends a new record instead of erasing, the newest record of a key wins.
+//! When the active page is full, the newest record of every key is copied to the other page,
+//! which then becomes the active one, so the erases alternate between the two pages.
+//! A page starts with `[magic, generation]`, the page with the highest generation is active.
+//! Records are `[key << 16 | len, value words.., crc]`, a record torn by a reset fails
+//! its crc and is skipped.
+//!
+//! NOTE: reads and writes go straight to flash, like `flash.rs`, so there is no resource
+//! to lock. Writing runs in a critical section, the CPU stalls during flash writes anyway.
+
+use crate::flash::{self, KV_PAGES, PAGE_SIZE};
+use crate::storage::crc32;
+
+pub type Key = u16;
+
+// NOTE: keys are never reused, a stored value could otherwise come back as something else
+pub const BOOTS          : Key = 0x0001;
+pub const BUTTON_PRESSED : Key = 0x0002;
+pub const BUTTON_A       : Key = 0x0003;
+pub const BUTTON_B       : Key = 0x0004;
+pub const IDLE           : Key = 0x0005;
+pub const REACTION_BEST  : Key = 0x0006;
+pub const DRAWINGS_SAVED : Key = 0x0007;
+pub const MORSE_PROGRESS : Key = 0x0008;
+pub const CHORDS         : Key = 0x0009;
+pub const LOGO_TOUCHES   : Key = 0x000a;
+pub const PEER           : Key = 0x000b;
+pub const BADGE_NAME     : Key = 0x000c;
+pub const KNOCK_PATTERN  : Key = 0x000d;
+pub const THERMO_DAYS    : Key = 0x000e;
+pub const SOIL_CALIBRATION : Key = 0x000f;
+pub const FOLLOW_TUNING  : Key = 0x0010;
+pub const BEACON_ID      : Key = 0x0011;
+pub const BEACON_URL     : Key = 0x0012;
+pub const CLASS_KEY      : Key = 0x0013;
+pub const SEAL_COUNTER   : Key = 0x0014;
+pub const LAST_MODE      : Key = 0x0015;
+// NOTE: one key per game, up to 0x01ff
+pub const HIGH_SCORES    : Key = 0x0100;
+// NOTE: one key per saved drawing, see sketch.rs
+pub const DRAWINGS       : Key = 0x0200;
+
+pub const MAX_VALUE_LEN : usize = 32;
+
+// "KVST"
+const MAGIC : u32 = 0x5453_564b;
+const PAGES : [u32; 2] = [KV_PAGES, KV_PAGES + PAGE_SIZE];
+const PAGE_HEADER_WORDS : usize = 2;
+const PAGE_WORDS : usize = PAGE_SIZE as usize / 4;
+const ERASED : u32 = 0xffff_ffff;
+
+#[derive(Clone, Copy)]
+struct Record {
+    offset : usize,
+    key    : Key,
+    len    : usize,
+}
+
+impl Record {
+    fn words(len : usize) -> usize {
+        1 + len.div_ceil(4) + 1
+    }
+
+    fn end(&self) -> usize {
+        self.offset + Record::words(self.len)
+    }
+}
+
+fn word(page : u32, i : usize) -> u32 {
+    flash::read_word(page + 4 * i as u32)
+}
+
+/// The active page and its generation.
+fn active() -> Option<(u32, u32)> {
+    PAGES.into_iter()
+        .filter(|page| word(*page, 0) == MAGIC)
+        .map(|page| (page, word(page, 1)))
+        .max_by_key(|(_, generation)| *generation)
+}
+
+fn read_value(page : u32, record : &Record, buf : &mut [u8]) -> usize {
+    let len = record.len.min(buf.len());
+    for (i, byte) in buf[..len].iter_mut().enumerate() {
+        *byte = word(page, record.offset + 1 + i / 4).to_le_bytes()[i % 4];
+    }
+    len
+}
+
+fn record_crc(key : Key, value : &[u8]) -> u32 {
+    let mut bytes = [0; 4 + MAX_VALUE_LEN];
+    bytes[..4].copy_from_slice(&((key as u32) << 16 | value.len() as u32).to_le_bytes());
+    bytes[4..4 + value.len()].copy_from_slice(value);
+    crc32(&bytes[..4 + value.len()])
+}
+
+/// Hands every intact record from word `start` on to `f`, in the order they were written.
+/// Returns where the free space starts, or None when a torn header makes
+/// the rest of the page unusable until it is compacted.
+fn scan(page : u32, start : usize, mut f : impl FnMut(Record)) -> Option<usize> {
+    let mut offset = start;
+    while offset < PAGE_WORDS {
+        let header = word(page, offset);
+        if header == ERASED {
+            return Some(offset);
+        }
+        let record = Record { offset, key : (header >> 16) as Key, len : (header & 0xffff) as usize };
+        if record.len > MAX_VALUE_LEN || record.end() > PAGE_WORDS {
+            return None;
+        }
+
+        let mut value = [0; MAX_VALUE_LEN];
+        let len = read_value(page, &record, &mut value);
+        if word(page, record.end() - 1) == record_crc(record.key, &value[..len]) {
+            f(record);
+        }
+        offset = record.end();
+    }
+    Some(PAGE_WORDS)
+}
+
+fn latest(page : u32, key : Key) -> Option<Record> {
+    let mut latest = None;
+    scan(page, PAGE_HEADER_WORDS, |record| {
+        if record.key == key {
+            latest = Some(record);
+        }
+    });
+    latest
+}
+
+/// Copies `value` of `key` into `buf`, returns its length.
+pub fn get(key : Key, buf : &mut [u8]) -> Option<usize> {
+    let (page, _) = active()?;
+    let record = latest(page, key)?;
+    Some(read_value(page, &record, buf))
+}
+
+pub fn get_u32(key : Key) -> Option<u32> {
+    let mut bytes = [0; 4];
+    match get(key, &mut bytes)? {
+        4 => Some(u32::from_le_bytes(bytes)),
+        _ => None,
+    }
+}
+
+fn write_record(page : u32, offset : usize, key : Key, value : &[u8]) {
+    let mut words = [ERASED; 2 + MAX_VALUE_LEN / 4];
+    words[0] = (key as u32) << 16 | value.len() as u32;
+    for (i, chunk) in value.chunks(4).enumerate() {
+        let mut bytes = [0; 4];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        words[1 + i] = u32::from_le_bytes(bytes);
+    }
+    let len = Record::words(value.len());
+    words[len - 1] = record_crc(key, value);
+    flash::write_words(page + 4 * offset as u32, &words[..len]);
+}
+
+/// Moves the newest record of every key to the other page, returns it and where its free space starts.
+fn compact(from : u32, generation : u32) -> (u32, usize) {
+    let to = if from == PAGES[0] { PAGES[1] } else { PAGES[0] };
+    flash::erase_page(to);
+
+    let mut end = PAGE_HEADER_WORDS;
+    scan(from, PAGE_HEADER_WORDS, |record| {
+        let mut superseded = false;
+        scan(from, record.end(), |later| superseded |= later.key == record.key);
+        if superseded {
+            return;
+        }
+        let mut value = [0; MAX_VALUE_LEN];
+        let len = read_value(from, &record, &mut value);
+        write_record(to, end, record.key, &value[..len]);
+        end += Record::words(len);
+    });
+
+    // NOTE: the header goes last, so a reset while copying leaves the old page active
+    flash::write_words(to, &[MAGIC, generation.wrapping_add(1)]);
+    (to, end)
+}
+
+/// Stores `value`, cut to `MAX_VALUE_LEN`, as the new value of `key`.
+pub fn set(key : Key, value : &[u8]) {
+    let value = &value[..value.len().min(MAX_VALUE_LEN)];
+    let needed = Record::words(value.len());
+
+    cortex_m::interrupt::free(|_| {
+        let (page, generation) = active().unwrap_or_else(|| {
+            flash::erase_page(PAGES[0]);
+            flash::write_words(PAGES[0], &[MAGIC, 0]);
+            (PAGES[0], 0)
+        });
+
+        let (page, end) = match scan(page, PAGE_HEADER_WORDS, |_| ()) {
+            Some(end) if end + needed <= PAGE_WORDS => (page, end),
+            _ => compact(page, generation),
+        };
+        // NOTE: still no room only with more distinct keys than fit a page, the value is dropped
+        if end + needed <= PAGE_WORDS {
+            write_record(page, end, key, value);
+        }
+    });
+}
+
+/// Erases both pages, every key reads as missing afterwards.
+pub fn erase() {
+    cortex_m::interrupt::free(|_| {
+        for page in PAGES {
+            flash::erase_page(page);
+        }
+    });
+}
+
+pub fn set_u32(key : Key, value : u32) {
+    set(key, &value.to_le_bytes());
+}
+
+pub struct Stats {
+    pub page       : usize,
+    pub generation : u32,
+    pub records    : usize,
+    pub used_bytes : usize,
+}
+
+pub fn stats() -> Option<Stats> {
+    let (page, generation) = active()?;
+    let mut records = 0;
+    let end = scan(page, PAGE_HEADER_WORDS, |_| records += 1).unwrap_or(PAGE_WORDS);
+    Some(Stats {
+        page : if page == PAGES[0] { 0 } else { 1 },
+        generation,
+        records,
+        used_bytes : 4 * end,
+    })
+}
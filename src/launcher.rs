@@ -0,0 +1,137 @@
+//! Picks the app that runs.
+//!
+//! A+B opens the launcher on the running app's icon, A and B step back and forth through
+//! `apps::APPS`, A+B again launches the app shown. The launched app is stored as the
+//! default mode, so the board boots into it, unless the `boot` setting names the app to boot
+//! into whatever ran last, or has it boot with the launcher open. It goes to the key-value
+//! store, launching an app appends a record there instead of erasing the settings page.
+//!
+//! NOTE: an app may take A+B for itself while it needs it, the shooter does during a game.
+
+use crate::apps::{self, App, APPS};
+use crate::kv;
+
+/// What the board boots into.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Boot {
+    /// The default mode, the app launched last.
+    Last,
+    /// The launcher, open on the default mode.
+    Launcher,
+    /// The app at this index of `APPS`.
+    App(u8),
+}
+
+impl Boot {
+    /// How many there are as `to_u8` numbers them.
+    pub fn count() -> u8 {
+        2 + APPS.len() as u8
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Boot::Last       => "last",
+            Boot::Launcher   => "launcher",
+            Boot::App(index) => apps::get(index as usize).name,
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Boot> {
+        match name {
+            "last"     => Some(Boot::Last),
+            "launcher" => Some(Boot::Launcher),
+            _ => APPS.iter().position(|app| app.name == name).map(|index| Boot::App(index as u8)),
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Boot::Last       => 0,
+            Boot::Launcher   => 1,
+            Boot::App(index) => 2 + index,
+        }
+    }
+
+    /// The boot stored as `to_u8`.
+    pub fn from_u8(value : u8) -> Option<Boot> {
+        match value {
+            0 => Some(Boot::Last),
+            1 => Some(Boot::Launcher),
+            _ if value < Boot::count() => Some(Boot::App(value - 2)),
+            _ => None,
+        }
+    }
+}
+
+/// The app launched or set last, `stored` from the settings until there is one.
+pub fn default_mode(stored : u8) -> u8 {
+    let mut mode = [0];
+    match kv::get(kv::LAST_MODE, &mut mode) {
+        Some(1) => mode[0],
+        _ => stored,
+    }
+}
+
+/// Keeps `mode` as the default mode.
+pub fn remember(mode : u8) {
+    if default_mode(mode) != mode {
+        kv::set(kv::LAST_MODE, &[mode]);
+    }
+}
+
+pub struct Launcher {
+    current  : usize,
+    // NOTE: the app shown while the launcher is open
+    selected : Option<usize>,
+}
+
+impl Launcher {
+    pub fn new(default_mode : u8, boot : Boot) -> Self {
+        let current = match boot {
+            Boot::App(index) => index as usize,
+            Boot::Last | Boot::Launcher => default_mode as usize,
+        };
+        let current = if current < APPS.len() { current } else { 0 };
+        let selected = (boot == Boot::Launcher).then_some(current);
+        Launcher { current, selected }
+    }
+
+    pub fn current(&self) -> &'static App {
+        apps::get(self.current)
+    }
+
+    /// The app that gets the inputs, None while the launcher has them.
+    pub fn running(&self) -> Option<&'static App> {
+        match self.selected {
+            None => Some(self.current()),
+            Some(_) => None,
+        }
+    }
+
+    /// The app to show the icon of, or None while the launcher is closed.
+    pub fn selected(&self) -> Option<&'static App> {
+        self.selected.map(apps::get)
+    }
+
+    pub fn open(&mut self) {
+        self.selected = Some(self.current);
+    }
+
+    pub fn previous(&mut self) {
+        if let Some(selected) = &mut self.selected {
+            *selected = (*selected + APPS.len() - 1) % APPS.len();
+        }
+    }
+
+    pub fn next(&mut self) {
+        if let Some(selected) = &mut self.selected {
+            *selected = (*selected + 1) % APPS.len();
+        }
+    }
+
+    /// Closes the launcher running the app shown, returns its index.
+    pub fn launch(&mut self) -> Option<usize> {
+        self.current = self.selected.take()?;
+        Some(self.current)
+    }
+}
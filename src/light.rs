@@ -0,0 +1,78 @@
+//! Light level sensed with the LED matrix, the way the micro:bit's own runtime does it.
+//!
+//! An LED reverse biased holds a little charge, and the light falling on it leaks the charge
+//! away. `sense` pulls the rows low and the columns high, then lets COL1, COL3 and COL5 float
+//! for `DISCHARGE_MS` and reads them on the SAADC: the brighter the light, the lower they got.
+//! The estimate is 0 in the dark to 255 in bright light, not calibrated to anything.
+//!
+//! The display stops for the few ms it takes, between two frames, see `Display::paused`.
+
+use embedded_hal::adc::Channel;
+use microbit::hal::saadc::Saadc;
+use microbit::pac::{P0, P1};
+use crate::battery::Monitor;
+use crate::display::Display;
+
+const ROWS_P0 : u32 = 1 << 21 | 1 << 22 | 1 << 15 | 1 << 24 | 1 << 19;
+const COLS_P0 : u32 = 1 << 28 | 1 << 11 | 1 << 31 | 1 << 30;
+const COLS_P1 : u32 = 1 << 5;
+// NOTE: the columns on an analog input, COL1, COL3 and COL5
+const SENSED_P0 : u32 = 1 << 28 | 1 << 31 | 1 << 30;
+// NOTE: the core runs at 64 MHz
+const US : u32 = 64;
+const CHARGE_US : u32 = 10;
+const DISCHARGE_MS : u32 = 4;
+// NOTE: until the supply is measured
+const TYPICAL_MV : u16 = 3300;
+
+macro_rules! analog_input {
+    ($name : ident, $ain : literal) => {
+        struct $name;
+
+        impl Channel<Saadc> for $name {
+            type ID = u8;
+
+            fn channel() -> u8 {
+                $ain
+            }
+        }
+    };
+}
+
+analog_input!(Col1, 4);
+analog_input!(Col3, 7);
+analog_input!(Col5, 6);
+
+/// The light level, 0-255, None when a conversion failed. `supply_mv` is what the columns
+/// start from.
+pub fn sense(display : &mut Display, monitor : &mut Monitor, supply_mv : u16) -> Option<u8> {
+    let p0 = unsafe { &*P0::ptr() };
+    let p1 = unsafe { &*P1::ptr() };
+    let readings = display.paused(|| {
+        // NOTE: every LED reverse biased, the ones on the sensed columns charged
+        p0.outclr.write(|w| unsafe { w.bits(ROWS_P0) });
+        p0.outset.write(|w| unsafe { w.bits(COLS_P0) });
+        p1.outset.write(|w| unsafe { w.bits(COLS_P1) });
+        p0.dirset.write(|w| unsafe { w.bits(ROWS_P0 | COLS_P0) });
+        p1.dirset.write(|w| unsafe { w.bits(COLS_P1) });
+        cortex_m::asm::delay(CHARGE_US * US);
+
+        p0.dirclr.write(|w| unsafe { w.bits(SENSED_P0) });
+        cortex_m::asm::delay(DISCHARGE_MS * 1000 * US);
+        let readings = [monitor.measure(&mut Col1), monitor.measure(&mut Col3), monitor.measure(&mut Col5)];
+
+        // NOTE: back to driven and dark, the display takes the lines over from here
+        p0.dirset.write(|w| unsafe { w.bits(SENSED_P0) });
+        readings
+    });
+
+    let mut sum = 0;
+    for reading in readings {
+        sum += reading? as u32;
+    }
+    // NOTE: in the 12 bits of 3.6 V the monitor measures in
+    let mv = if supply_mv > 0 { supply_mv } else { TYPICAL_MV };
+    let charged = (mv as u32 * 4096 / 3600).max(1);
+    let left = (sum / readings.len() as u32).min(charged);
+    Some((255 - left * 255 / charged) as u8)
+}
@@ -0,0 +1,114 @@
+//! Lock mode: with `set lock on` the settings and firmware updates are refused, from the
+//! console, the radio and the settings menu alike, until the knock pattern is knocked on the
+//! board.
+//!
+//! `knock <pattern>` sets the pattern, `x` a knock and `.` a beat without one, see
+//! `fun_core::knock`, it is kept in the key-value store and is `xx.x` until then. While the
+//! board is locked input_poll hands every accelerometer sample over, each knock lights one
+//! more LED for a moment and the end of the rhythm shows whether it was right. The right
+//! rhythm clears the setting, so a reset doesn't lock the board again. The feedback comes as
+//! toasts, over the launcher it isn't seen, see toast.rs.
+//!
+//! NOTE: a demo for the classroom more than security, anyone watching learns the rhythm, and
+//! the board can still be flashed from the MICROBIT drive.
+
+use core::cell::Cell;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::Mutex;
+use fun_core::knock::{Heard, Listener, Pattern, MAX_BEATS};
+use heapless::String;
+use crate::console::Command;
+use crate::display::Frame;
+use crate::kv;
+use crate::log;
+use crate::toast;
+
+const RHYTHM_SECS : u32 = 2;
+
+const OPEN : Frame = [
+    [0, 1, 1, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 0, 0, 1, 0],
+    [1, 1, 1, 1, 1],
+    [1, 1, 1, 1, 1],
+];
+
+const WRONG : Frame = [
+    [1, 0, 0, 0, 1],
+    [0, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+static LOCKED : AtomicBool = AtomicBool::new(false);
+// NOTE: None until the stored pattern is read, on the first sample or command needing it
+static PATTERN : Mutex<Cell<Option<Pattern>>> = Mutex::new(Cell::new(None));
+
+pub fn configure(locked : bool) {
+    LOCKED.store(locked, Ordering::Relaxed);
+}
+
+pub fn locked() -> bool {
+    LOCKED.load(Ordering::Relaxed)
+}
+
+/// True when `command` has to wait until the board is unlocked.
+pub fn blocked(command : &Command) -> bool {
+    locked() && matches!(command, Command::Set(_) | Command::Ota(_) | Command::Knock(_))
+}
+
+pub fn pattern() -> Pattern {
+    if let Some(pattern) = cortex_m::interrupt::free(|cs| PATTERN.borrow(cs).get()) {
+        return pattern;
+    }
+    let mut bytes = [0; kv::MAX_VALUE_LEN];
+    let pattern = kv::get(kv::KNOCK_PATTERN, &mut bytes)
+        .and_then(|len| core::str::from_utf8(&bytes[..len]).ok())
+        .and_then(Pattern::parse)
+        .unwrap_or_default();
+    cortex_m::interrupt::free(|cs| PATTERN.borrow(cs).set(Some(pattern)));
+    pattern
+}
+
+pub fn set_pattern(pattern : Pattern) {
+    let mut text = String::<MAX_BEATS>::new();
+    let _ = write!(text, "{}", pattern);
+    kv::set(kv::KNOCK_PATTERN, text.as_bytes());
+    cortex_m::interrupt::free(|cs| PATTERN.borrow(cs).set(Some(pattern)));
+}
+
+// NOTE: the LEDs row by row, one more for every knock
+fn counted(knocks : usize) -> Frame {
+    let mut frame = [[0; 5]; 5];
+    for led in frame.iter_mut().flatten().take(knocks) {
+        *led = 1;
+    }
+    frame
+}
+
+/// Takes an accelerometer sample at `now_ms` while the board is locked, true once it
+/// completed the pattern and the board is unlocked.
+pub fn on_sample(listener : &mut Listener, now_ms : u64, magnitude_mg : u32) -> bool {
+    if !locked() {
+        return false;
+    }
+    match listener.sample(now_ms, magnitude_mg, &pattern()) {
+        Heard::Nothing => false,
+        Heard::Knock(knocks) => {
+            toast::icon(counted(knocks), 1);
+            false
+        }
+        Heard::Rhythm(false) => {
+            toast::icon(WRONG, RHYTHM_SECS);
+            false
+        }
+        Heard::Rhythm(true) => {
+            configure(false);
+            toast::icon(OPEN, RHYTHM_SECS);
+            log!("unlocked by knocking");
+            true
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Ring buffer holding the most recent log records, whichever backend is in use.
+//! Dumping it shows what happened before the debugger or serial terminal was attached.
+
+use core::cell::RefCell;
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use heapless::{HistoryBuffer, String};
+use crate::logging::Level;
+use crate::timesync;
+
+pub const RECORDS : usize = 16;
+pub const TEXT_LEN : usize = 40;
+
+#[derive(Clone)]
+struct Record {
+    timestamp_us : u64,
+    level        : Level,
+    text         : String<TEXT_LEN>,
+}
+
+// NOTE: logging happens from every priority, so the buffer sits behind a critical section
+static LOG_BUFFER : Mutex<RefCell<HistoryBuffer<Record, RECORDS>>> =
+    Mutex::new(RefCell::new(HistoryBuffer::new()));
+
+pub fn record(level : Level, s : &str) {
+    // NOTE: long messages only keep their start, which is usually enough to recognize them
+    let mut text = String::new();
+    for c in s.chars() {
+        if text.push(c).is_err() {
+            break;
+        }
+    }
+
+    let record = Record { timestamp_us : timesync::now_us(), level, text };
+    cortex_m::interrupt::free(|cs| {
+        LOG_BUFFER.borrow(cs).borrow_mut().write(record);
+    });
+}
+
+/// Hands every record, oldest first, to `sink` as a formatted line.
+pub fn dump(mut sink : impl FnMut(&str)) {
+    // NOTE: records are copied out one at a time,
+    // so interrupts are not held off while the sink does slow I/O
+    for i in 0..RECORDS {
+        let record = cortex_m::interrupt::free(|cs| {
+            LOG_BUFFER.borrow(cs).borrow().oldest_ordered().nth(i).cloned()
+        });
+        let Some(record) = record else { break };
+
+        let mut line = String::<{ TEXT_LEN + 24 }>::new();
+        let _ = write!(
+            line, "{}.{:06} {} {}",
+            record.timestamp_us / 1_000_000,
+            record.timestamp_us % 1_000_000,
+            record.level.prefix(),
+            record.text.as_str());
+        sink(line.as_str());
+    }
+}
@@ -1,4 +1,10 @@
+use core::cell::Cell;
+use core::fmt::Write as _;
+use core::sync::atomic::{AtomicU8, Ordering};
+use cortex_m::interrupt::Mutex;
 use heapless::String;
+use crate::mono::Mono;
+use crate::timesync;
 
 #[cfg(feature = "use_defmt")]
 use defmt_rtt as _;
@@ -9,13 +15,362 @@ use defmt as _;
 #[cfg(feature = "use_rtt")]
 use rtt_target as _;
 
+// NOTE: defmt renders `us` timestamps as seconds with microsecond precision,
+// the rtt path below prints the same format so logs from both backends line up.
+// Both take the synced time, so the logs of boards following one master line up too
+#[cfg(feature = "use_defmt")]
+defmt::timestamp!("{=u64:us}", timesync::now_us());
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub const ALL : [Level; 5] = [Level::Trace, Level::Debug, Level::Info, Level::Warn, Level::Error];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info  => "info",
+            Level::Warn  => "warn",
+            Level::Error => "error",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Level> {
+        Level::ALL.into_iter().find(|level| level.name() == name)
+    }
+
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info  => "INFO ",
+            Level::Warn  => "WARN ",
+            Level::Error => "ERROR",
+        }
+    }
+
+    pub fn from_u8(level : u8) -> Level {
+        match level {
+            0 => Level::Trace,
+            1 => Level::Debug,
+            2 => Level::Info,
+            3 => Level::Warn,
+            _ => Level::Error,
+        }
+    }
+}
+
+// NOTE: messages below this level are dropped at runtime.
+// defmt also filters at compile time, which is why the Makefile builds with DEFMT_LOG=trace
+// and leaves the actual filtering to this level.
+static LEVEL : AtomicU8 = AtomicU8::new(Level::Debug as u8);
+
+pub fn level() -> Level {
+    Level::from_u8(LEVEL.load(Ordering::Relaxed))
+}
+
+/// Set from the console with `level <level>`.
+pub fn set_level(level : Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+// NOTE: the token bucket of a single `log!` call site, it holds BURST messages
+// and refills one message every REFILL_US
+const BURST : u32 = 5;
+const REFILL_US : u64 = 100_000;
+
+#[derive(Clone, Copy)]
+struct Bucket {
+    tokens      : u32,
+    refilled_us : u64,
+    suppressed  : u32,
+}
+
+pub struct RateLimit {
+    bucket : Mutex<Cell<Bucket>>,
+}
+
+impl RateLimit {
+    pub const fn new() -> Self {
+        RateLimit {
+            bucket : Mutex::new(Cell::new(Bucket { tokens : BURST, refilled_us : 0, suppressed : 0 })),
+        }
+    }
+
+    /// Takes a token, returns how many messages were suppressed since the last one got through.
+    fn take(&self) -> Option<u32> {
+        let now = Mono::now_us();
+        cortex_m::interrupt::free(|cs| {
+            let cell = self.bucket.borrow(cs);
+            let mut bucket = cell.get();
+
+            let refills = (now - bucket.refilled_us) / REFILL_US;
+            if refills > 0 {
+                bucket.tokens = (bucket.tokens + refills.min(BURST as u64) as u32).min(BURST);
+                bucket.refilled_us += refills * REFILL_US;
+            }
+
+            let taken = if bucket.tokens > 0 {
+                bucket.tokens -= 1;
+                Some(core::mem::take(&mut bucket.suppressed))
+            } else {
+                bucket.suppressed += 1;
+                None
+            };
+            cell.set(bucket);
+            taken
+        })
+    }
+}
+
+// NOTE: FNV-1a over the formatted message, so duplicates are found without a buffer
+struct Hasher(u32);
+
+impl core::fmt::Write for Hasher {
+    fn write_str(&mut self, s : &str) -> core::fmt::Result {
+        for byte in s.bytes() {
+            self.0 = (self.0 ^ byte as u32).wrapping_mul(0x0100_0193);
+        }
+        Ok(())
+    }
+}
+
+fn hash(level : Level, args : core::fmt::Arguments) -> u32 {
+    let mut hasher = Hasher(0x811c_9dc5 ^ level as u32);
+    let _ = hasher.write_fmt(args);
+    hasher.0
+}
+
+#[derive(Clone, Copy)]
+struct Last {
+    hash    : u32,
+    level   : Level,
+    repeats : u32,
+}
+
+static LAST : Mutex<Cell<Last>> = Mutex::new(Cell::new(Last { hash : 0, level : Level::Trace, repeats : 0 }));
+
+/// Swallows a message identical to the previous one,
+/// the next different message is preceded by how often it was repeated.
+fn dedup(level : Level, hash : u32) -> bool {
+    let last = cortex_m::interrupt::free(|cs| {
+        let cell = LAST.borrow(cs);
+        let mut last = cell.get();
+        if last.hash == hash {
+            last.repeats += 1;
+            cell.set(last);
+            return None;
+        }
+        cell.set(Last { hash, level, repeats : 0 });
+        Some(last)
+    });
+
+    match last {
+        None => false,
+        Some(Last { repeats : 0, .. }) => true,
+        Some(Last { level, repeats, .. }) => {
+            let mut note = String::<48>::new();
+            let _ = write!(note, "last message repeated {} times", repeats);
+            emit(level, note.as_str());
+            true
+        }
+    }
+}
+
+/// The checks of a `log!` call site: level filter, rate limit and duplicate suppression.
+pub fn admit(level : Level, limit : &RateLimit, args : core::fmt::Arguments) -> bool {
+    if level < self::level() {
+        return false;
+    }
+    match limit.take() {
+        None => return false,
+        Some(0) => (),
+        Some(suppressed) => {
+            let mut note = String::<48>::new();
+            let _ = write!(note, "{} messages suppressed by rate limit", suppressed);
+            emit(Level::Warn, note.as_str());
+        }
+    }
+    dedup(level, hash(level, args))
+}
+
+// NOTE: the helpers below share one rate limit, a call site that may fire in bursts takes
+// `log!` for a limit of its own
+static HELPERS : RateLimit = RateLimit::new();
+
+pub fn log(level : Level, s : &str) {
+    if admit(level, &HELPERS, format_args!("{}", s)) {
+        emit(level, s);
+    }
+}
+
+/// Where log records go, any combination can be routed at runtime with the `route` command.
+#[derive(Clone, Copy)]
+pub enum Sink {
+    // NOTE: the debugger, through rtt-target or defmt depending on the backend
+    Rtt    = 1 << 0,
+    Uart   = 1 << 1,
+    Radio  = 1 << 2,
+    Buffer = 1 << 3,
+}
+
+impl Sink {
+    pub const ALL : [Sink; 4] = [Sink::Rtt, Sink::Uart, Sink::Radio, Sink::Buffer];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Sink::Rtt    => "rtt",
+            Sink::Uart   => "uart",
+            Sink::Radio  => "radio",
+            Sink::Buffer => "buffer",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Sink> {
+        Sink::ALL.into_iter().find(|sink| sink.name() == name)
+    }
+}
+
+#[cfg(not(feature = "radio_log"))]
+const DEFAULT_ROUTES : u8 = Sink::Rtt as u8 | Sink::Buffer as u8;
+#[cfg(feature = "radio_log")]
+const DEFAULT_ROUTES : u8 = Sink::Rtt as u8 | Sink::Buffer as u8 | Sink::Radio as u8;
+
+static ROUTES : AtomicU8 = AtomicU8::new(DEFAULT_ROUTES);
+
+/// Bit set of `Sink`s.
+pub fn routes() -> u8 {
+    ROUTES.load(Ordering::Relaxed)
+}
+
+pub fn set_routes(routes : u8) {
+    ROUTES.store(routes, Ordering::Relaxed);
+}
+
+pub fn routed(sink : Sink) -> bool {
+    routes() & sink as u8 != 0
+}
+
+/// Writes a message to the routed sinks without any of the checks `log` does.
+pub fn emit(level : Level, s : &str) {
+    capture(level, s);
+
+    if !routed(Sink::Rtt) {
+        return;
+    }
+
+    #[cfg(feature = "use_defmt")]
+    match level {
+        Level::Trace => defmt::trace!("{}", s),
+        Level::Debug => defmt::debug!("{}", s),
+        Level::Info  => defmt::info!("{}", s),
+        Level::Warn  => defmt::warn!("{}", s),
+        Level::Error => defmt::error!("{}", s),
+    }
+
+    #[cfg(feature = "use_rtt")]
+    {
+        let us = timesync::now_us();
+        rtt_target::rprintln!("{}.{:06} {} {}", us / 1_000_000, us % 1_000_000, level.prefix(), s);
+    }
+}
+
+// NOTE: hands every record that passed the filter to the routed sinks other than rtt,
+// whichever backend printed it
+pub fn capture(level : Level, s : &str) {
+    if routed(Sink::Buffer) {
+        crate::logbuf::record(level, s);
+    }
+    if routed(Sink::Radio) {
+        crate::radiolog::queue(level, s);
+    }
+    if routed(Sink::Uart) {
+        crate::seriallog::queue(level, s);
+    }
+}
+
+// NOTE: the defmt backend never formats on the target, so the captured copy is formatted here
+#[cfg(feature = "use_defmt")]
+pub fn capture_args(level : Level, args : core::fmt::Arguments) {
+    if routes() & !(Sink::Rtt as u8) == 0 {
+        return;
+    }
+    let mut text = String::<MESSAGE_LEN>::new();
+    let _ = text.write_fmt(args);
+    capture(level, text.as_str());
+}
+
+/// Format and log a message, at `Level::Info` unless a level is given first.
+///
+/// `log!("button {} pressed {} times", name, count)`
+/// `log!(Level::Debug, "idle count: {}", count)`
+///
+/// With `use_defmt` the arguments go straight to defmt, with `use_rtt` the message is
+/// formatted into a stack buffer, overlong messages are cut off rather than dropped.
+/// Every call site has its own rate limit, so a hot loop can't flood the RTT buffer,
+/// and a message repeating the previous one is only counted.
+/// The captured copy always formats with `core::fmt`, so stick to format strings
+/// that both `core::fmt` and defmt understand, like plain `{}`.
+#[macro_export]
+macro_rules! log {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::log!($crate::logging::Level::Info, $fmt $(, $arg)*)
+    };
+    ($level:expr, $fmt:literal $(, $arg:expr)* $(,)?) => {{
+        static LIMIT : $crate::logging::RateLimit = $crate::logging::RateLimit::new();
+        let level : $crate::logging::Level = $level;
+        if $crate::logging::admit(level, &LIMIT, format_args!($fmt $(, $arg)*)) {
+            #[cfg(feature = "use_defmt")]
+            if $crate::logging::routed($crate::logging::Sink::Rtt) {
+                match level {
+                    $crate::logging::Level::Trace => defmt::trace!($fmt $(, $arg)*),
+                    $crate::logging::Level::Debug => defmt::debug!($fmt $(, $arg)*),
+                    $crate::logging::Level::Info  => defmt::info!($fmt $(, $arg)*),
+                    $crate::logging::Level::Warn  => defmt::warn!($fmt $(, $arg)*),
+                    $crate::logging::Level::Error => defmt::error!($fmt $(, $arg)*),
+                }
+            }
+            #[cfg(feature = "use_defmt")]
+            $crate::logging::capture_args(level, format_args!($fmt $(, $arg)*));
+
+            #[cfg(feature = "use_rtt")]
+            {
+                use core::fmt::Write as _;
+                let mut message = heapless::String::<{ $crate::logging::MESSAGE_LEN }>::new();
+                let _ = write!(message, $fmt $(, $arg)*);
+                $crate::logging::emit(level, message.as_str());
+            }
+        }
+    }};
+}
+
+pub const MESSAGE_LEN : usize = 128;
+
+pub fn trace(s : &str) { log(Level::Trace, s) }
+pub fn debug(s : &str) { log(Level::Debug, s) }
+pub fn info(s : &str) { log(Level::Info, s) }
+pub fn warn(s : &str) { log(Level::Warn, s) }
+pub fn error(s : &str) { log(Level::Error, s) }
+
+// NOTE: prints regardless of the log level
 pub fn print(s : &str) {
     // Log using the appropriate method
     #[cfg(feature = "use_defmt")]
     defmt::println!("{}", s);
 
     #[cfg(feature = "use_rtt")]
-    rtt_target::rprintln!("{}", s);
+    {
+        let us = timesync::now_us();
+        rtt_target::rprintln!("{}.{:06} {}", us / 1_000_000, us % 1_000_000, s);
+    }
 }
 
 pub fn test_print(s : &str) {
@@ -0,0 +1,75 @@
+//! Long presses of A and B, for the apps that want them.
+//!
+//! The presses themselves come in through the GPIOTE interrupt, a long press is a button
+//! still down a while after it went down, so it is polled, `fun_core::hold` tells the two
+//! apart. `held` is there for the apps that time presses themselves.
+//!
+//! Holding A and B together makes a long press of `Button::AB` instead of one of each, the
+//! chord that toggles stealth mode.
+//!
+//! NOTE: the button pins belong to the button_pressed task, `Pins` only reads them through
+//! the IN register, which doesn't touch their configuration.
+
+use fun_core::board::Buttons;
+use fun_core::hold::Hold;
+use fun_core::Clock;
+use microbit::pac::P0;
+use crate::events::Button;
+// NOTE: P0.14 and P0.23, active low
+const PINS : [(Button, u32); 2] = [(Button::A, 14), (Button::B, 23)];
+
+/// The buttons of the board.
+pub struct Pins;
+
+impl Pins {
+    fn down(pin : u32) -> bool {
+        unsafe { (*P0::ptr()).in_.read().bits() & (1 << pin) == 0 }
+    }
+}
+
+impl Buttons for Pins {
+    fn a(&self) -> bool {
+        Pins::down(PINS[0].1)
+    }
+
+    fn b(&self) -> bool {
+        Pins::down(PINS[1].1)
+    }
+}
+
+/// True while `button` is down, false for the buttons without a pin of their own.
+pub fn held(button : Button) -> bool {
+    match button {
+        Button::A => Pins.a(),
+        Button::B => Pins.b(),
+        _ => false,
+    }
+}
+
+pub struct LongPress {
+    holds : [Hold; 2],
+    both  : Hold,
+}
+
+impl LongPress {
+    pub const fn new() -> Self {
+        LongPress { holds : [Hold::new(); 2], both : Hold::new() }
+    }
+
+    /// Reads the buttons, the one that just made a long press if any.
+    pub fn poll(&mut self, buttons : &impl Buttons, clock : &impl Clock) -> Option<Button> {
+        let down = [buttons.a(), buttons.b()];
+        let both = down[0] && down[1];
+        if self.both.poll(both, clock) {
+            return Some(Button::AB);
+        }
+        let mut long = None;
+        for (((button, _), down), hold) in PINS.into_iter().zip(down).zip(self.holds.iter_mut()) {
+            // NOTE: while both are down neither makes a long press of its own
+            if hold.poll(down && !both, clock) {
+                long = Some(button);
+            }
+        }
+        long
+    }
+}
@@ -2,125 +2,617 @@
 #![no_std]
 #![feature(type_alias_impl_trait)]
 
+mod about;
+mod apps;
+mod attendance;
+mod auth;
+mod assets;
+mod badge;
+mod beacon;
+mod battery;
+mod bench;
+mod blink;
+mod breakout;
+mod calibration;
+mod car;
+mod clock;
+mod cbor;
+mod channels;
+mod comparator;
+mod console;
+mod crashlog;
+mod dfu;
+mod display;
+mod dnd;
+mod effects;
+mod eightball;
+mod events;
+mod facedown;
+#[cfg(feature = "hil")]
+mod exit;
+mod fault;
+mod flappy;
+mod flash;
+mod follower;
+mod frequency;
+mod generator;
+mod gpio_events;
+mod greenhouse;
+mod highscores;
+#[cfg(feature = "hil")]
+mod hil;
+mod identity;
+mod inbox;
+#[cfg(feature = "inject_buttons")]
+mod inject;
+mod instrument;
+mod kv;
+mod launcher;
+mod light;
+mod lock;
+mod logbuf;
 mod logging;
+mod long_press;
+mod maze;
+mod menu;
+mod meter;
+mod mono;
+mod morse;
+mod motor;
+mod mouse;
+mod motion;
+#[cfg(feature = "ota")]
+mod ota;
+mod pairing;
+mod playlist;
+mod ppi;
+mod presenter;
+mod proximity;
+mod pulse_meter;
+mod quiz;
+mod radio;
+mod radiolog;
+mod relay;
+mod remote;
+mod reaction;
+mod rng;
+mod rps;
+mod scope;
+mod seal;
+mod seriallog;
+mod shooter;
+mod simon;
+mod sketch;
+mod splash;
+mod soil;
+mod speaker;
+mod stats;
+mod storage;
+mod swarm;
+mod tasks;
+mod telemetry;
+mod thermo;
+mod timesync;
+mod toast;
+mod touch;
+mod transport;
+mod tug;
+mod usage;
+mod utils;
+mod wedge;
+// NOTE: the modules moved to fun-core keep their paths in here
+use fun_core::{canvas, font, frame, protocol, scroll};
 use rtic::app;
 
 #[app(device = microbit::pac, peripherals = true, dispatchers = [SWI0_EGU0, SWI1_EGU1])]
 mod app {
-    //use crate::button_pressed_action;
-    use crate::log_count;
+    use crate::log;
     use crate::logging;
-    use crate::compose_string;
-    // NOTE: The defmt version of these macros will log the panic message using defmt
-    // and then call core::panic!, so the rtt message will be emitted before panic is invoked
-    #[cfg(feature = "use_defmt")]
-    use panic_halt as _;
+    use crate::logging::Level;
+    use crate::mono::{self, Mono, ExtU64};
+    use crate::console::{self, Command, LineBuffer, SerialTx};
+    use crate::logbuf;
+    use crate::events::{self, Event, Button};
+    // NOTE: the panic handler lives in crashlog, it stores a crash report before halting
+    use crate::crashlog;
+    use crate::dfu;
+    use crate::fault;
+    use crate::radio::{self, Radio};
+    use crate::protocol::Packet;
+    use crate::radiolog;
+    use crate::relay;
+    use crate::remote;
+    use crate::presenter;
+    use crate::wedge;
+    use crate::mouse;
+    use crate::seriallog;
+    use crate::storage::{self, Settings};
+    use crate::console::Setting;
+    use crate::kv;
+    use crate::usage::{self, Counters};
+    use crate::highscores::{self, InitialsEntry};
+    use crate::scope;
+    use crate::follower::{self, Ring1};
+    use crate::scroll;
+    use crate::identity::Identity;
+    use crate::rng;
+    use crate::rps;
+    use crate::tug;
+    use crate::seal::Seal;
+    use crate::blink::Blink;
+    use crate::battery::{self, Supply};
+    use crate::bench::{self, Stats};
+    use crate::pulse_meter::{self, PulseMeter};
+    use crate::calibration::Calibration;
+    use crate::channels;
+    use crate::gpio_events::GpioEvents;
+    use crate::comparator::Comparator;
+    use crate::about;
+    use crate::splash;
+    use crate::soil::Probe;
+    use crate::auth::{self, Channel};
+    use crate::apps;
+    use crate::speaker::{self, Speaker};
+    use crate::touch::Logo;
+    use crate::motion::{Accelerometer, Motion};
+    use crate::facedown;
+    use crate::frequency;
+    use crate::generator;
+    use crate::motor;
+    use crate::car;
+    use crate::beacon;
+    use crate::light;
+    use crate::attendance;
+    use crate::quiz;
+    use crate::proximity;
+    use crate::swarm;
+    use crate::greenhouse;
+    use crate::lock;
+    use crate::launcher::{self, Launcher};
+    use crate::playlist;
+    use crate::menu;
+    use crate::meter;
+    use crate::pairing;
+    use crate::tasks::{self, Task};
+    use crate::telemetry::{self, Reading};
+    use crate::thermo;
+    use crate::timesync;
+    use crate::toast;
+    use crate::clock;
+    use crate::dnd;
+    use crate::inbox;
+    use crate::transport::{self, Link};
+    use crate::frame::{Decoder, Received};
+    use crate::long_press::{LongPress, Pins};
+    use microbit::hal::pac::RNG;
 
-    #[cfg(feature = "use_rtt")]
-    use rtt_target::{rtt_init_print};
-
-    #[cfg(feature = "use_rtt")]
-    use panic_rtt_target as _;
+    #[cfg(feature = "hil")]
+    use crate::hil;
+    #[cfg(feature = "inject_buttons")]
+    use crate::inject;
+    #[cfg(feature = "ota")]
+    use crate::ota;
 
     use microbit::board::Board;
     use microbit::hal::gpiote::Gpiote;
-    use microbit::display::blocking::Display;
+    use crate::display::{self, Display, Screen};
+    use fun_core::FrameSink;
+    use fun_core::knock::Listener;
+    use fun_core::climate::{self, Alarm, Watch};
+    use fun_core::board::Beeper;
     use microbit::hal::Timer;
     use microbit::hal::pac::TIMER0;
+    use microbit::hal::clocks::Clocks;
+    use microbit::hal::gpio::{self, Pin, Input, Floating};
+    use microbit::hal::uarte::{self, Uarte, UarteRx, Parity, Baudrate};
+    use microbit::hal::pac::UARTE0;
+    use embedded_hal::digital::v2::InputPin;
+    use embedded_hal::serial::Read;
     use heapless::String;
+    use core::fmt::Write;
 
     #[shared]
     struct Shared {
         gpiote  : Gpiote,
         display : Display,
         timer   : Timer<TIMER0>,
-        key     : String<32>
+        identity : Identity,
+        // NOTE: bumped by the heartbeat task, so a counter that stops moving means a hang
+        liveness : u32,
+        serial   : SerialTx,
+        // NOTE: set while a crash report from a previous run waits to be acknowledged
+        crash_pending : bool,
+        radio    : Radio,
+        seal     : Seal,
+        blink    : Blink,
+        comparator : Comparator,
+        settings : Settings,
+        launcher : Launcher,
+        // NOTE: measured by supply_monitor
+        supply   : Supply,
+        // NOTE: restored from flash in init, so they count over the life of the board
+        counters : Counters,
+        // NOTE: a score that made the high-score table, the buttons enter its initials
+        initials : Option<highscores::Pending>,
+        // NOTE: the pulse command and the frequency counter app both measure on it
+        pulse_meter : PulseMeter,
     }
 
     #[local]
     struct Local {
         //idle           : u32,
-        button_pressed : u32,
-        button_a       : u32,
-        button_b       : u32,
+        button_a_pin   : Pin<Input<Floating>>,
+        button_b_pin   : Pin<Input<Floating>>,
+        button_channels : [channels::GpioteChannel; 2],
+        serial_rx      : UarteRx<UARTE0>,
+        rng            : RNG,
+        monitor        : battery::Monitor,
+        probe          : Probe,
+        calibration    : Calibration,
+        gpio_events    : GpioEvents,
+        logo           : Logo,
+        motion         : Option<Motion>,
+        climate        : Option<greenhouse::Sensor>,
     }
 
-    #[init]
+    #[init(local = [
+        serial_tx_buf : [u8; 32] = [0; 32],
+        serial_rx_buf : [u8; 1] = [0; 1],
+        radio_buf : [u8; radio::BUFFER_LEN] = [0; radio::BUFFER_LEN],
+    ])]
     fn init(cx: init::Context) -> (Shared, Local) {
-        #[cfg(feature = "use_rtt")]
-        rtt_init_print!();
+        #[cfg(all(feature = "use_rtt", not(feature = "hil")))]
+        transport::rtt::init();
+        #[cfg(feature = "hil")]
+        hil::init();
 
         logging::test_print("in init");
 
-        let board = Board::new(cx.device, cx.core);
+        let mut board = Board::new(cx.device, cx.core);
+        fault::enable(&mut board.SCB);
+        bench::enable(&mut board.DCB, &mut board.DWT);
+
+        // NOTE: the monotonic runs on RTC0 which is clocked by the LFCLK,
+        // the radio needs the crystal oscillator
+        Clocks::new(board.CLOCK).enable_ext_hfosc().start_lfclk();
+        Mono::start(board.RTC0);
 
-        let display = Display::new(board.display_pins);
-        let timer = Timer::new(board.TIMER0);
+        let mut display = Display::new(board.display_pins, board.PWM0, board.PWM1, board.PWM2);
+        let mut timer = Timer::new(board.TIMER0);
 
         let gpiote = Gpiote::new(board.GPIOTE);
-        let chan0 = gpiote.channel0();
-        chan0.input_pin(&board.buttons.button_a.degrade())
-            .hi_to_lo()
-            .enable_interrupt();
-        let chan1 = gpiote.channel1();
-        chan1.input_pin(&board.buttons.button_b.degrade())
-            .hi_to_lo()
-            .enable_interrupt();
+        let button_a_pin = board.buttons.button_a.degrade();
+        let button_b_pin = board.buttons.button_b.degrade();
+        // NOTE: every channel is claimed here, at init, an exhausted claim panics naming the
+        // features that hold them, see channels.rs
+        let button_channels = [channels::gpiote("button A").unwrap(), channels::gpiote("button B").unwrap()];
+        for (channel, pin) in button_channels.iter().zip([&button_a_pin, &button_b_pin]) {
+            channel.of(&gpiote).input_pin(pin)
+                .hi_to_lo()
+                .enable_interrupt();
+        }
+        let monitor = battery::Monitor::new(board.SAADC);
+        let calibration = Calibration::new(board.TEMP, board.TIMER4);
+        let blink = Blink::new(board.TIMER1, &gpiote, board.microphone_pins.mic_run.degrade()).unwrap();
+        // NOTE: ring 1 of the edge connector
+        let pulse_meter = PulseMeter::new(board.TIMER2, board.TIMER3, &gpiote, board.pins.p0_03.into_floating_input().degrade()).unwrap();
+        // NOTE: ring 0, the soil probe's
+        let probe = Probe::new(board.pins.p0_02);
+
+        // NOTE: holding A+B through a reset wipes everything persisted,
+        // the way out when stored settings make the board boot into something unexpected
+        if button_a_pin.is_low().unwrap() && button_b_pin.is_low().unwrap() {
+            storage::factory_reset();
+            log!(Level::Warn, "factory reset, settings and key-value store erased");
+            for frame in storage::RESET_ANIMATION {
+                display.show(&mut timer, frame, 150);
+            }
+            display.clear();
+        }
+
+        rng::start(&board.RNG);
+
+        let identity = Identity::read(&board.FICR);
+        about::init(identity.device_id);
+        dfu::init();
+        #[cfg(feature = "ota")]
+        ota::init();
+        log!("firmware {} reset by {}", about::VERSION, about::reset_reason());
+
+        let mut settings = storage::load();
+        settings.default_mode = launcher::default_mode(settings.default_mode);
+        display.set_brightness(settings.brightness);
+        display::set_stealth(settings.stealth);
+        display::set_wear_levelling(settings.wear);
+        display::set_auto_flip(settings.flip);
+        facedown::configure(settings.facedown);
+        lock::configure(settings.lock);
+        greenhouse::configure(settings.greenhouse);
+        let launcher = Launcher::new(settings.default_mode, settings.boot);
+        playlist::configure(&settings);
+        menu::configure(&settings);
+        pairing::init();
+        timesync::configure(settings.time_sync);
+        relay::configure(settings.relay);
+        remote::configure(settings.remote);
+        telemetry::configure(settings.telemetry_secs);
+        transport::configure(settings.link);
+        auth::configure(settings.auth);
+        speaker::init(board.PWM3, board.speaker_pin);
+        speaker::set_muted(!settings.sound);
+        let logo = Logo::new(board.pins.p1_04);
+        let motion = Accelerometer::new(board.TWIM0, board.i2c_internal, &mut timer).map(Motion::new);
+        if motion.is_none() {
+            log!(Level::Warn, "accelerometer not answering, no shake input");
+        }
+        let boots = kv::get_u32(kv::BOOTS).unwrap_or(0) + 1;
+        kv::set_u32(kv::BOOTS, boots);
+        log!("boot number {}", boots);
+        let counters = Counters::load();
+        let mut radio = Radio::new(board.RADIO, cx.local.radio_buf);
+        radio.set_group(settings.radio_group);
+
+        // NOTE: the board struct doesn't hand out the AES peripherals, the LPCOMP, TWIM1 or RTC2, nothing else uses them
+        let unhanded = unsafe { microbit::pac::Peripherals::steal() };
+        generator::init(unhanded.RTC2, &gpiote, board.pins.p1_02.into_push_pull_output(gpio::Level::Low).degrade()).unwrap();
+        // NOTE: the edge connector pins nothing else uses, P8 and P9 are left out as they
+        // default to the NFC antenna, ring 2 is the comparator's and P16 the signal generator's.
+        // The motors take them when they are on, see motor.rs
+        let edge_pins = [
+            ("P12", board.pins.p0_12.degrade()),
+            ("P13", board.pins.p0_17.degrade()),
+            ("P14", board.pins.p0_01.degrade()),
+            ("P15", board.pins.p0_13.degrade()),
+        ];
+        motor::configure(settings.motor);
+        car::configure(settings.car);
+        beacon::configure(settings.beacon);
+        attendance::configure(settings.attendance);
+        quiz::configure(settings.quiz);
+        proximity::configure(settings.proximity);
+        dnd::configure(settings.dnd);
+        let gpio_events = if settings.motor == motor::Control::Off {
+            GpioEvents::new(&gpiote, edge_pins.into_iter().map(|(label, pin)| (label, pin.into_pullup_input())).collect())
+        } else {
+            motor::init(unhanded.RTC1, &gpiote, edge_pins.map(|(_, pin)| pin)).unwrap();
+            GpioEvents::new(&gpiote, heapless::Vec::new())
+        };
+        let seal = Seal::new(unhanded.ECB, unhanded.CCM, unhanded.AAR, identity.radio_address());
+        if !seal.keyed() {
+            log!(Level::Warn, "no class key, radio packets are neither sealed nor opened until 'key' sets one");
+        }
+        let comparator = Comparator::new(unhanded.LPCOMP, board.pins.p0_04.into_floating_input(), settings.threshold);
+        let climate = greenhouse::Sensor::new(unhanded.TWIM1, board.i2c_external);
+        greenhouse::set_present(climate.is_some());
+        if climate.is_some() {
+            log!("greenhouse sensor found on the external I2C bus");
+        }
+
+        let serial = Uarte::new(
+            board.UARTE0,
+            uarte::Pins::from(board.uart),
+            Parity::EXCLUDED,
+            Baudrate::BAUD115200);
+        let (serial, mut serial_rx) = serial
+            .split(cx.local.serial_tx_buf, cx.local.serial_rx_buf)
+            .unwrap();
+        // NOTE: every received byte raises ENDRX, the first read starts the reception
+        unsafe { (*UARTE0::ptr()).intenset.write(|w| w.endrx().set()) };
+        serial_rx.read().ok();
+
+        let crash_pending = match crashlog::load() {
+            Some(report) => {
+                crashlog::log_report(&report);
+                true
+            }
+            None => false,
+        };
+
+        tasks::spawned(Task::Heartbeat, heartbeat::spawn()).ok();
+        tasks::spawned(Task::PersistCounters, persist_counters::spawn(counters)).ok();
+        tasks::spawned(Task::RadioLog, radio_log::spawn()).ok();
+        tasks::spawned(Task::SerialLog, serial_log::spawn()).ok();
+        tasks::spawned(Task::SupplyMonitor, supply_monitor::spawn()).ok();
+        tasks::spawned(Task::ClockCalibration, clock_calibration::spawn()).ok();
+        tasks::spawned(Task::InputPoll, input_poll::spawn()).ok();
+        tasks::spawned(Task::FrequencyCounter, frequency_counter::spawn()).ok();
+        if greenhouse::present() {
+            tasks::spawned(Task::GreenhouseMonitor, greenhouse_monitor::spawn()).ok();
+        }
+        #[cfg(feature = "inject_buttons")]
+        tasks::spawned(Task::ButtonStorm, button_storm::spawn()).ok();
 
         (
             Shared {
                 gpiote,
                 display,
                 timer,
-                key : String::from("hello"),
+                identity,
+                liveness : 0,
+                serial,
+                crash_pending,
+                radio,
+                seal,
+                blink,
+                comparator,
+                settings,
+                launcher,
+                supply : Supply::UNKNOWN,
+                counters,
+                initials : None,
+                pulse_meter,
             },
             // TODO: precompute the led states for button presses and add them as locals
             Local {
-                button_a       : 0,
-                button_b       : 0,
-                button_pressed : 0
+                button_a_pin,
+                button_b_pin,
+                button_channels,
+                serial_rx,
+                rng : board.RNG,
+                monitor,
+                probe,
+                calibration,
+                gpio_events,
+                logo,
+                motion,
+                climate,
             }
         )
     }
 
-    #[task(binds = GPIOTE, priority = 3, shared = [gpiote], local = [button_pressed])]
+    // NOTE: the edge pin events share the GPIOTE interrupt with the buttons
+    #[task(binds = GPIOTE, priority = 3, shared = [gpiote, counters, launcher], local = [button_a_pin, button_b_pin, button_channels, gpio_events])]
     fn button_pressed(mut ctx : button_pressed::Context) {
-        let button_pressed_count = ctx.local.button_pressed;
-        *button_pressed_count += 1;
-        log_count("button pressed count: ", *button_pressed_count);
+        // NOTE: taken first thing, the running app may time the press
+        let now = Mono::now();
+        let _run = tasks::Run::start(Task::ButtonPressed);
+        let gpio_events = ctx.local.gpio_events;
+        let button_channels = *ctx.local.button_channels;
+        let buttons = ctx.shared.gpiote.lock(|gpiote| {
+            gpio_events.on_interrupt(gpiote, |edge| {
+                log!(Level::Debug, "pin {} {}", edge.pin, if edge.high { "high" } else { "low" });
+                events::record(Event::PinEdge { pin : edge.pin, high : edge.high });
+            });
+            button_channels.iter().any(|channel| channel.of(gpiote).is_event_triggered())
+        });
+        #[cfg(feature = "inject_buttons")]
+        while let Some(button) = inject::take() {
+            let delivered = press(&mut ctx.shared.launcher, button, now);
+            inject::tally(delivered);
+        }
+        // NOTE: the peer's presses go the way of our own, its long presses to the running app
+        while let Some(input) = remote::take() {
+            match input {
+                apps::Input::Button(button) => { press(&mut ctx.shared.launcher, button, now); }
+                input => {
+                    if let apps::Input::LongPress(button) = input {
+                        events::record(Event::LongPress(button));
+                    }
+                    let on_input = ctx.shared.launcher.lock(|launcher| launcher.running().and_then(|app| app.on_input));
+                    if let Some(on_input) = on_input {
+                        on_input(input, now);
+                    }
+                }
+            }
+        }
+        if !buttons {
+            return;
+        }
 
-        ctx.shared.gpiote.lock(|gpiote| {
-            let chan0 = gpiote.channel0();
-            let chan1 = gpiote.channel1();
+        let button_pressed_count = ctx.shared.counters.lock(|counters| {
+            counters.button_pressed += 1;
+            counters.button_pressed
+        });
+        log!(Level::Debug, "button pressed count: {}", button_pressed_count);
 
-            if chan0.is_event_triggered() {
-                logging::print("Button A pressed");
+        // NOTE: the buttons are active low
+        let both_held =
+            ctx.local.button_a_pin.is_low().unwrap()
+            && ctx.local.button_b_pin.is_low().unwrap();
+
+        let pressed = ctx.shared.gpiote.lock(|gpiote| {
+            let [chan0, chan1] = button_channels.map(|channel| channel.of(gpiote));
+            let (a, b) = (chan0.is_event_triggered(), chan1.is_event_triggered());
+            // NOTE: pressing A and B together goes to the launcher instead of running the actions
+            if both_held && (a || b) {
                 chan0.reset_events();
-                match button_a_action::spawn() {
-                    Ok(()) => (),
-                    Err(()) => logging::print("failed to spawn task!"),
-                }
+                chan1.reset_events();
+                return [Some(Button::AB), None];
             }
-
-            if chan1.is_event_triggered() {
-                logging::print("Button B pressed");
+            if a {
+                chan0.reset_events();
+            }
+            if b {
                 chan1.reset_events();
-                match button_b_action::spawn() {
-                    Ok(()) => (),
-                    Err(()) => logging::print("failed to spawn task!"),
-                }
             }
-
-            //button_pressed_action(chan0, "A");
-            //button_pressed_action(chan1, "B");
+            [a.then_some(Button::A), b.then_some(Button::B)]
         });
+        if pressed[0] == Some(Button::AB) {
+            ctx.shared.counters.lock(|counters| counters.chords += 1);
+        }
+        for button in pressed.into_iter().flatten() {
+            press(&mut ctx.shared.launcher, button, now);
+        }
+    }
+
+    // NOTE: only spawned with the inject_buttons feature
+    #[task(priority = 1)]
+    async fn button_storm(_ctx : button_storm::Context) {
+        let _run = tasks::Run::start(Task::ButtonStorm);
+        #[cfg(feature = "inject_buttons")]
+        inject::run().await;
     }
 
-    #[task(priority = 1, shared = [display, timer], local = [button_a])]
-    async fn button_a_action(ctx : button_a_action::Context) {
-        let button_a_count = ctx.local.button_a;
-        *button_a_count += 1;
-        log_count("Task A count: ", *button_a_count);
+    /// What a press does: the running app gets it first, unless the launcher is open, then A
+    /// and B run their actions and A+B opens the launcher. The self-test's presses come in
+    /// here as well, see hil.rs, and so do the injected ones, see inject.rs.
+    /// False when the press was dropped, its task still busy with the last one.
+    fn press(launcher : &mut impl rtic::Mutex<T = Launcher>, button : Button, now : mono::Instant) -> bool {
+        match button {
+            Button::A    => log!(Level::Debug, "Button A pressed"),
+            Button::B    => log!(Level::Debug, "Button B pressed"),
+            Button::AB   => log!(Level::Debug, "Buttons A+B pressed"),
+            Button::Logo => log!(Level::Debug, "logo touched"),
+        }
+        events::record(Event::ButtonPress(button));
+        // NOTE: a press during the boot splash only cuts it short
+        if splash::skip() {
+            return true;
+        }
+        // NOTE: right after boot A+B shows the version instead, before any app sees it
+        if button == Button::AB && about::in_boot_window() {
+            if tasks::spawned(Task::ScrollText, scroll_text::spawn(about::version_text(), None)).is_err() {
+                logging::warn("display busy scrolling");
+            }
+            return true;
+        }
+        let on_input = launcher.lock(|launcher| launcher.running().and_then(|app| app.on_input));
+        if on_input.is_some_and(|on_input| on_input(apps::Input::Button(button), now)) {
+            return true;
+        }
+        let (spawned, task) = match button {
+            Button::A    => (tasks::spawned(Task::ButtonAAction, button_a_action::spawn()), "button_a_action spawn"),
+            Button::B    => (tasks::spawned(Task::ButtonBAction, button_b_action::spawn()), "button_b_action spawn"),
+            Button::AB   => {
+                if tasks::spawned(Task::AppLauncher, app_launcher::spawn()).is_err() {
+                    logging::warn("launcher busy");
+                    return false;
+                }
+                return true;
+            }
+            Button::Logo => return true,
+        };
+        if spawned.is_err() {
+            logging::error("failed to spawn task!");
+            events::record(Event::Error(task));
+            return false;
+        }
+        true
+    }
+
+    #[task(priority = 1, shared = [display, timer, crash_pending, counters, initials, launcher])]
+    async fn button_a_action(mut ctx : button_a_action::Context) {
+        let _run = tasks::Run::start(Task::ButtonAAction);
+        // NOTE: while a crash report is shown, button A acknowledges it instead
+        if ctx.shared.crash_pending.lock(|pending| core::mem::replace(pending, false)) {
+            crashlog::clear();
+            log!("crash report acknowledged");
+            return;
+        }
+        // NOTE: and while initials are entered, it picks the letter
+        if ctx.shared.initials.lock(|initials| initials.as_mut().map(|pending| pending.entry.next_letter())).is_some() {
+            return;
+        }
+        // NOTE: and while the launcher is open, it steps back through the apps
+        if ctx.shared.launcher.lock(|launcher| launcher.selected().map(|_| launcher.previous())).is_some() {
+            return;
+        }
+
+        let button_a_count = ctx.shared.counters.lock(|counters| {
+            counters.button_a += 1;
+            counters.button_a
+        });
+        log!("Task A count: {}", button_a_count);
 
         let mut display = ctx.shared.display;
         let mut timer = ctx.shared.timer;
@@ -128,9 +620,7 @@ mod app {
         let leds_empty = [[0; 5]; 5];
         let mut leds = leds_empty;
         for x in 0..5 {
-            for y in 0..5 {
-                leds[x][y] = 1;
-            }
+            leds[x] = [1; 5];
             (&mut display, &mut timer).lock(|d, t| {
                 d.show(t, leds, 400);
             });
@@ -138,11 +628,39 @@ mod app {
         }
     }
 
-    #[task(priority = 2, shared = [display, timer], local = [button_b])]
-    async fn button_b_action(ctx : button_b_action::Context) {
-        let button_b_count = ctx.local.button_b;
-        *button_b_count += 1;
-        log_count("Task B count: ", *button_b_count);
+    #[task(priority = 2, shared = [display, timer, counters, initials, launcher])]
+    async fn button_b_action(mut ctx : button_b_action::Context) {
+        let _run = tasks::Run::start(Task::ButtonBAction);
+        // NOTE: while initials are entered, button B confirms the letter
+        let entered = ctx.shared.initials.lock(|initials| {
+            let pending = initials.as_mut()?;
+            let done = pending.entry.confirm().map(|letters| (pending.game, pending.score, letters));
+            if done.is_some() {
+                *initials = None;
+            }
+            Some(done)
+        });
+        if let Some(entered) = entered {
+            if let Some((game, score, letters)) = entered {
+                if let Some(place) = highscores::submit(game, letters, score) {
+                    log!("high score {} for game {}, place {}", score, game, place + 1);
+                }
+                if tasks::spawned(Task::ScrollText, scroll_text::spawn(highscores::text(game), None)).is_err() {
+                    logging::warn("display busy scrolling");
+                }
+            }
+            return;
+        }
+        // NOTE: and while the launcher is open, it steps on through the apps
+        if ctx.shared.launcher.lock(|launcher| launcher.selected().map(|_| launcher.next())).is_some() {
+            return;
+        }
+
+        let button_b_count = ctx.shared.counters.lock(|counters| {
+            counters.button_b += 1;
+            counters.button_b
+        });
+        log!("Task B count: {}", button_b_count);
 
         let mut display = ctx.shared.display;
         let mut timer = ctx.shared.timer;
@@ -150,8 +668,8 @@ mod app {
         let leds_empty = [[0; 5]; 5];
         let mut leds = leds_empty;
         for x in 0..5 {
-            for y in 0..5 {
-                leds[y][x] = 1;
+            for row in leds.iter_mut() {
+                row[x] = 1;
             }
             (&mut display, &mut timer).lock(|d, t| {
                 d.show(t, leds, 400);
@@ -160,68 +678,1329 @@ mod app {
         }
     }
 
+    // NOTE: blips the top right corner once per second, so a frozen board is obvious at a glance
+    #[task(priority = 1, shared = [display, timer, liveness])]
+    async fn heartbeat(ctx : heartbeat::Context) {
+        let mut display = ctx.shared.display;
+        let mut timer = ctx.shared.timer;
+        let mut liveness = ctx.shared.liveness;
+
+        let mut leds = [[0; 5]; 5];
+        leds[0][4] = 1;
+
+        let mut next_beat = Mono::now();
+        loop {
+            let run = tasks::Run::start(Task::Heartbeat);
+            liveness.lock(|liveness| *liveness += 1);
+            logging::trace("heartbeat");
+            (&mut display, &mut timer).lock(|d, t| {
+                d.show(t, leds, 30);
+            });
+            next_beat += 1.secs();
+            drop(run);
+            Mono::delay_until(next_beat).await;
+        }
+    }
+
+    // NOTE: only writes the counters that changed, idle's counter moves every few seconds
+    // so this is about one small record a minute, spread over the key-value store pages
+    #[task(priority = 1, shared = [counters])]
+    async fn persist_counters(mut ctx : persist_counters::Context, mut saved : Counters) {
+        loop {
+            Mono::delay_until(Mono::now() + 60.secs()).await;
+            let _run = tasks::Run::start(Task::PersistCounters);
+            let counters = ctx.shared.counters.lock(|counters| *counters);
+            counters.save(&mut saved);
+        }
+    }
+
+    // NOTE: the soil probe is on the SAADC too, it is measured here as well, the scope app
+    // samples the same pin through here while it is on screen, the follow app rings 0 and 1
+    #[task(priority = 1, shared = [supply, settings, display, serial], local = [monitor, probe])]
+    async fn supply_monitor(mut ctx : supply_monitor::Context) {
+        let mut measured : Option<mono::Instant> = None;
+        loop {
+            let now = Mono::now();
+            let run = tasks::Run::start(Task::SupplyMonitor);
+            if scope::active(now) {
+                let (monitor, probe) = (&mut *ctx.local.monitor, &mut *ctx.local.probe);
+                if let Some(line) = scope::capture(|| probe.measure(monitor)) {
+                    ctx.shared.serial.lock(|serial| console::write_line(serial, &line));
+                }
+            }
+            if follower::active(now) {
+                let monitor = &mut *ctx.local.monitor;
+                if let (Some(left), Some(right)) = (ctx.local.probe.measure(monitor), monitor.measure(&mut Ring1)) {
+                    follower::step(left, right);
+                }
+            }
+            if measured.is_none_or(|at| now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_secs()) >= battery::PERIOD_SECS) {
+                measured = Some(now);
+                ctx.local.probe.sample(ctx.local.monitor);
+                let supply = ctx.local.monitor.sample();
+                telemetry::record(Reading::SupplyMv(supply.millivolts));
+                if beacon::wants_light() {
+                    let monitor = &mut *ctx.local.monitor;
+                    let light = ctx.shared.display.lock(|display| light::sense(display, monitor, supply.millivolts));
+                    beacon::record_light(light);
+                }
+                let was_low = ctx.shared.supply.lock(|shared| core::mem::replace(shared, supply).low);
+                if supply.low != was_low {
+                    if supply.low {
+                        log!(Level::Warn, "supply low at {} mV, capping brightness", supply.millivolts);
+                    } else {
+                        log!("supply recovered at {} mV", supply.millivolts);
+                    }
+                    let brightness = supply.brightness(ctx.shared.settings.lock(|settings| settings.brightness));
+                    ctx.shared.display.lock(|display| display.set_brightness(brightness));
+                }
+            }
+            drop(run);
+            Mono::delay_until(now + scope::poll_ms(now).min(follower::poll_ms(now)).millis()).await;
+        }
+    }
+
+    // NOTE: only spawned when init found the sensor
+    #[task(priority = 1, shared = [&identity], local = [climate, watch : Watch = Watch::new()])]
+    async fn greenhouse_monitor(ctx : greenhouse_monitor::Context) {
+        let Some(sensor) = ctx.local.climate.as_mut() else { return };
+        let mut answering = true;
+        loop {
+            let started = tasks::Run::start(Task::GreenhouseMonitor);
+            let measuring = sensor.start();
+            drop(started);
+            Mono::delay_until(Mono::now() + (climate::MEASURE_MS as u64).millis()).await;
+            let run = tasks::Run::start(Task::GreenhouseMonitor);
+            let reading = if measuring { sensor.read() } else { None };
+            let mut alarms = heapless::Vec::<Alarm, 4>::new();
+            if let Some(reading) = reading {
+                alarms = ctx.local.watch.check(&reading, &greenhouse::limits());
+                for alarm in &alarms {
+                    greenhouse::raise(*alarm, &reading, ctx.shared.identity.radio_address());
+                }
+            } else if answering {
+                logging::warn("greenhouse sensor not answering");
+            }
+            answering = reading.is_some();
+            greenhouse::record(reading, ctx.local.watch.raised());
+            drop(run);
+            if !alarms.is_empty() && dnd::sound(greenhouse::ALARM_HZ, Mono::now()) {
+                for _ in 0..3 {
+                    Speaker.tone(greenhouse::ALARM_HZ);
+                    Mono::delay_until(Mono::now() + 150.millis()).await;
+                    Speaker.off();
+                    Mono::delay_until(Mono::now() + 100.millis()).await;
+                }
+            }
+            Mono::delay_until(Mono::now() + greenhouse::PERIOD_SECS.secs()).await;
+        }
+    }
+
+    // NOTE: every round measures how far the RTC is off and calibrates when the temperature
+    // moved, so the drift each calibration corrected ends up in the log. A reading a minute
+    // goes to the thermometer log.
+    #[task(priority = 1, local = [calibration])]
+    async fn clock_calibration(ctx : clock_calibration::Context) {
+        const WINDOW_S : u64 = 4;
+        const FRACTIONS : [&str; 4] = ["00", "25", "50", "75"];
+        let calibration = ctx.local.calibration;
+        let mut logged : Option<mono::Instant> = None;
+        loop {
+            // NOTE: starting on an RTC tick, both ends of the window are taken right after a wake up
+            Mono::delay_until(Mono::now() + mono::Duration::from_ticks(1)).await;
+            let start = Mono::now();
+            let since_us = calibration.now_us();
+            Mono::delay_until(start + WINDOW_S.secs()).await;
+            let _run = tasks::Run::start(Task::ClockCalibration);
+            let drift = calibration.drift_ppm(since_us, WINDOW_S as u32 * 1_000_000);
+
+            let temperature = calibration.temperature();
+            telemetry::record(Reading::Temperature(temperature));
+            if logged.is_none_or(|at| start.checked_duration_since(at).is_some_and(|since| since.to_secs() >= 60)) {
+                thermo::record(temperature);
+                logged = Some(start);
+            }
+            if calibration.due(temperature) {
+                calibration.calibrate(temperature);
+                let sign = if temperature < 0 { "-" } else { "" };
+                let quarters = temperature.unsigned_abs();
+                log!("rtc off by {} ppm at {}{}.{} C, lfclk calibrated",
+                    drift, sign, quarters / 4, FRACTIONS[(quarters % 4) as usize]);
+            }
+        }
+    }
+
+    #[task(binds = COMP_LPCOMP, priority = 2, shared = [comparator, settings])]
+    fn threshold_crossed(mut ctx : threshold_crossed::Context) {
+        let _run = tasks::Run::start(Task::ThresholdCrossed);
+        let Some(above) = ctx.shared.comparator.lock(|comparator| comparator.on_interrupt()) else { return };
+        let sixteenths = ctx.shared.settings.lock(|settings| settings.threshold);
+        log!("ring 2 went {} {}/16 of the supply", if above { "above" } else { "below" }, sixteenths);
+        events::record(Event::Threshold { above, sixteenths });
+    }
+
+    // NOTE: the logo and the accelerometer have no interrupt wired up for this, so they are
+    // polled, and their inputs go to the running app
+    #[task(priority = 1, shared = [launcher, settings, counters], local = [logo, motion, long_press : LongPress = LongPress::new(), knock : Listener = Listener::new()])]
+    async fn input_poll(mut ctx : input_poll::Context) {
+        loop {
+            let now = Mono::now();
+            let run = tasks::Run::start(Task::InputPoll);
+            let mut inputs = heapless::Vec::<apps::Input, 4>::new();
+            if let Some(button) = ctx.local.long_press.poll(&Pins, &Mono) {
+                log!(Level::Debug, "long press");
+                events::record(Event::LongPress(button));
+                if button == Button::AB {
+                    // NOTE: the chord belongs to stealth mode, no app gets it
+                    let settings = ctx.shared.settings.lock(|settings| {
+                        settings.stealth = !settings.stealth;
+                        *settings
+                    });
+                    set_stealth(settings.stealth);
+                    storage::save(&settings);
+                } else {
+                    let _ = inputs.push(apps::Input::LongPress(button));
+                }
+            }
+            if ctx.local.logo.poll() {
+                log!(Level::Debug, "logo touched");
+                ctx.shared.counters.lock(|counters| counters.logo += 1);
+                events::record(Event::ButtonPress(Button::Logo));
+                let _ = inputs.push(apps::Input::Button(Button::Logo));
+            }
+            if ctx.local.motion.as_mut().is_some_and(|motion| motion.poll(now.duration_since_epoch().to_millis())) {
+                log!(Level::Debug, "shake");
+                events::record(Event::Gesture("shake"));
+                let _ = inputs.push(apps::Input::Shake);
+            }
+            if let Some(motion) = ctx.local.motion.as_ref() {
+                display::set_upside_down(motion.upside_down());
+                facedown::update(motion.face_down(now.duration_since_epoch().to_millis()));
+            }
+            if let Some((x, y)) = ctx.local.motion.as_ref().and_then(|motion| motion.tilt()) {
+                let _ = inputs.push(apps::Input::Tilt { x, y });
+            }
+            if let Some(mg) = ctx.local.motion.as_ref().and_then(|motion| motion.magnitude()) {
+                telemetry::record(Reading::AccelMg(mg));
+                if lock::on_sample(ctx.local.knock, now.duration_since_epoch().to_millis(), mg) {
+                    let settings = ctx.shared.settings.lock(|settings| {
+                        settings.lock = false;
+                        *settings
+                    });
+                    storage::save(&settings);
+                }
+            }
+            #[cfg(feature = "hil")]
+            if let Some(line) = hil::next_command() {
+                self_test(&line, &mut ctx.shared.launcher, ctx.local.motion, &mut inputs, now);
+            }
+            if !inputs.is_empty() {
+                let on_input = ctx.shared.launcher.lock(|launcher| launcher.running().and_then(|app| app.on_input));
+                if let Some(on_input) = on_input {
+                    for input in inputs {
+                        on_input(input, now);
+                    }
+                }
+            }
+            car::watch(now);
+            motor::watch(now);
+            proximity::watch(now);
+            dnd::watch(now);
+            drop(run);
+            Mono::delay_until(now + facedown::poll_ms().millis()).await;
+        }
+    }
+
+    fn set_stealth(on : bool) {
+        display::set_stealth(on);
+        log!("stealth mode {}", if on { "on" } else { "off" });
+    }
+
+    /// Runs a command of the self-test script, the inputs for the running app go on `inputs`.
+    #[cfg(feature = "hil")]
+    fn self_test(
+        line : &str,
+        launcher : &mut impl rtic::Mutex<T = Launcher>,
+        motion : &mut Option<Motion>,
+        inputs : &mut heapless::Vec<apps::Input, 4>,
+        now : mono::Instant,
+    ) {
+        let mut reply = String::<{ console::LINE_LEN }>::new();
+        let input = match hil::parse(line) {
+            hil::Command::Press(button) => {
+                press(launcher, button, now);
+                None
+            }
+            hil::Command::LongPress(button) => {
+                events::record(Event::LongPress(button));
+                Some(apps::Input::LongPress(button))
+            }
+            hil::Command::Shake => {
+                events::record(Event::Gesture("shake"));
+                Some(apps::Input::Shake)
+            }
+            hil::Command::Tilt { x, y } => Some(apps::Input::Tilt { x, y }),
+            hil::Command::Frame => {
+                let _ = reply.push_str("frame");
+                for row in crate::display::shown() {
+                    let _ = reply.push(' ');
+                    for led in row {
+                        let _ = reply.push((b'0' + led.min(9)) as char);
+                    }
+                }
+                None
+            }
+            hil::Command::App => {
+                let (selected, current) = launcher.lock(|launcher| (launcher.selected(), launcher.current()));
+                let _ = match selected {
+                    Some(app) => write!(reply, "launcher {}", app.name),
+                    None => write!(reply, "app {}", current.name),
+                };
+                None
+            }
+            hil::Command::Accel => {
+                let _ = match motion.as_mut().and_then(|motion| motion.acceleration()) {
+                    Some((x, y, z)) => write!(reply, "accel {} {} {}", x, y, z),
+                    None => write!(reply, "accel none"),
+                };
+                None
+            }
+            hil::Command::Ping => {
+                let _ = reply.push_str("pong");
+                None
+            }
+            hil::Command::Exit { passed } => {
+                // NOTE: the reply goes first, the script waits for it before letting go
+                hil::reply("ok");
+                crate::exit::exit(passed);
+            }
+            hil::Command::Unknown => {
+                let _ = write!(reply, "error unknown command: {}", line);
+                None
+            }
+        };
+        if let Some(input) = input {
+            let _ = inputs.push(input);
+        }
+        if reply.is_empty() {
+            let _ = reply.push_str("ok");
+        }
+        hil::reply(&reply);
+    }
+
+    // NOTE: a task of its own, the gate time is waited out and console_command can't await
+    // while it holds the serial port
+    #[task(priority = 1, shared = [serial, pulse_meter])]
+    async fn pulse_measure(mut ctx : pulse_measure::Context) {
+        let _run = tasks::Run::start(Task::PulseMeasure);
+        let edges = ctx.shared.pulse_meter.lock(|meter| meter.edges());
+        Mono::delay_until(Mono::now() + 100.millis()).await;
+        let (counted, pulse) = ctx.shared.pulse_meter.lock(|meter| (meter.edges(), meter.measure(100_000)));
+        // NOTE: two edges per cycle over a tenth of a second
+        let counted_hz = counted.wrapping_sub(edges) / 2 * 10;
+
+        let mut line = String::<{ console::LINE_LEN }>::new();
+        let _ = write!(line, "counted {} Hz", counted_hz);
+        match pulse {
+            Some(pulse) => {
+                let hundredths = |ticks : u32| ticks * 100 / pulse_meter::TICKS_PER_US;
+                let (high, low) = (hundredths(pulse.high_ticks), hundredths(pulse.low_ticks));
+                let _ = write!(
+                    line, ", {} Hz high {}.{:02} us low {}.{:02} us duty {}%",
+                    pulse.frequency_hz(), high / 100, high % 100, low / 100, low % 100, pulse.duty_percent());
+            }
+            None => { let _ = line.push_str(", no pulse to time"); }
+        }
+        ctx.shared.serial.lock(|serial| console::write_line(serial, &line));
+    }
+
+    // NOTE: idles until the frequency counter app is on screen
+    #[task(priority = 1, shared = [pulse_meter])]
+    async fn frequency_counter(mut ctx : frequency_counter::Context) {
+        loop {
+            if !frequency::active(Mono::now()) {
+                Mono::delay_until(Mono::now() + frequency::IDLE_POLL_MS.millis()).await;
+                continue;
+            }
+            let run = tasks::Run::start(Task::FrequencyCounter);
+            let edges = ctx.shared.pulse_meter.lock(|meter| meter.edges());
+            Mono::delay_until(Mono::now() + (frequency::GATE_MS as u64).millis()).await;
+            let (counted, pulse) = ctx.shared.pulse_meter.lock(|meter| (meter.edges(), meter.measure(frequency::TIMEOUT_US)));
+            frequency::record(counted.wrapping_sub(edges), pulse);
+            drop(run);
+        }
+    }
+
+    // NOTE: holds the display for every frame, like the apps do, the results go to the log
+    #[task(priority = 1, shared = [display, timer])]
+    async fn render_bench(ctx : render_bench::Context) {
+        let _run = tasks::Run::start(Task::RenderBench);
+        let mut display = ctx.shared.display;
+        let mut timer = ctx.shared.timer;
+
+        for workload in bench::WORKLOADS {
+            let mut stats = Stats::new();
+            let mut last_start = None;
+            for step in 0..bench::FRAMES {
+                let start = bench::cycles();
+                let frame = workload.frame(step);
+                let rendered = bench::cycles();
+                (&mut display, &mut timer).lock(|display, timer| Screen { display, timer }.show(frame, bench::FRAME_MS));
+                let shown = bench::cycles();
+                let period = last_start.map(|last : u32| start.wrapping_sub(last));
+                stats.record(rendered.wrapping_sub(start), shown.wrapping_sub(rendered), period);
+                last_start = Some(start);
+            }
+            let fps = stats.fps_tenths();
+            log!(
+                "bench {}: {} frames at {}.{} fps, render {} cycles, driver {} cycles, jitter {} us",
+                workload.name(), bench::FRAMES, fps / 10, fps % 10,
+                stats.render_cycles(), stats.driver_cycles(), stats.jitter_us());
+        }
+    }
+
+    // NOTE: a task of its own, the arrow is shown before the reset and console_command can't
+    // await the frames
+    #[task(priority = 1, shared = [display, timer])]
+    async fn dfu_reset(ctx : dfu_reset::Context) {
+        let _run = tasks::Run::start(Task::DfuReset);
+        let mut display = ctx.shared.display;
+        let mut timer = ctx.shared.timer;
+        for step in 0..dfu::ROUNDS * 5 {
+            (&mut display, &mut timer).lock(|display, timer| Screen { display, timer }.show(dfu::frame(step), dfu::FRAME_MS));
+        }
+        dfu::reset();
+    }
+
+    // NOTE: asks for the chunks one by one, again and again until each comes, see ota.rs
+    #[cfg(feature = "ota")]
+    #[task(priority = 1, shared = [radio, seal, counters])]
+    async fn ota_update(mut ctx : ota_update::Context, crc : u32) {
+        let _run = tasks::Run::start(Task::OtaUpdate);
+        let Some(mut update) = ota::Update::start(crc) else { return };
+        let mut misses = 0;
+        loop {
+            if let Some(sealed) = update.want().and_then(|packet| ctx.shared.seal.lock(|seal| seal.seal(&packet))) {
+                ctx.shared.radio.lock(|radio| radio.send(&sealed));
+            }
+            let deadline = Mono::now() + ota::WAIT_MS.millis();
+            let mut answer = None;
+            while answer.is_none() && Mono::now() < deadline {
+                Mono::delay_until(Mono::now() + 5.millis()).await;
+                answer = ota::answer();
+            }
+            let Some(answer) = answer else {
+                misses += 1;
+                if misses == ota::MAX_MISSES {
+                    log!(Level::Warn, "ota: no answer, update given up");
+                    return;
+                }
+                continue;
+            };
+            misses = 0;
+            match update.take(answer) {
+                ota::Step::More => (),
+                ota::Step::Staged => {
+                    log!("ota: image staged, swapping it in at the reset");
+                    let counters = ctx.shared.counters.lock(|counters| *counters);
+                    counters.save(&mut Counters::load());
+                    cortex_m::peripheral::SCB::sys_reset();
+                }
+                ota::Step::Failed(reason) => {
+                    log!(Level::Warn, "ota: {}, update given up", reason);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Scrolls `text` in `style`, or in the stored one for None.
+    #[task(priority = 1, shared = [display, timer, settings])]
+    async fn scroll_text(mut ctx : scroll_text::Context, text : scroll::Text, style : Option<scroll::Style>) {
+        let _run = tasks::Run::start(Task::ScrollText);
+        let style = style.unwrap_or_else(|| ctx.shared.settings.lock(|settings| settings.scroll));
+        let mut display = ctx.shared.display;
+        let mut timer = ctx.shared.timer;
+
+        scroll::play(&text, &style, &mut |frame, duration_ms| {
+            (&mut display, &mut timer).lock(|d, t| d.show(t, frame, duration_ms));
+        });
+    }
+
+    // NOTE: a task of its own, launching stores the default mode and the flash write takes
+    // too long for the button interrupt
+    #[task(priority = 1, shared = [launcher, settings])]
+    async fn app_launcher(mut ctx : app_launcher::Context) {
+        let _run = tasks::Run::start(Task::AppLauncher);
+        let launched = ctx.shared.launcher.lock(|launcher| {
+            let launched = launcher.launch();
+            if launched.is_none() {
+                launcher.open();
+                // NOTE: an app may have left a tone playing
+                speaker::off();
+            }
+            launched
+        });
+        let Some(index) = launched else {
+            log!(Level::Debug, "launcher open");
+            return;
+        };
+
+        let app = apps::get(index);
+        log!("launched {}", app.name);
+        events::record(Event::ModeChange(app.name));
+        ctx.shared.settings.lock(|settings| settings.default_mode = index as u8);
+        launcher::remember(index as u8);
+    }
+
+    #[task(binds = UARTE0_UART0, priority = 2, local = [serial_rx, line : LineBuffer = LineBuffer::new(), frames : Decoder = Decoder::new()])]
+    fn serial_received(ctx : serial_received::Context) {
+        let _run = tasks::Run::start(Task::SerialReceived);
+        // NOTE: a successful read also starts receiving the next byte, which returns WouldBlock
+        loop {
+            match ctx.local.serial_rx.read() {
+                Ok(byte) => {
+                    // NOTE: with the uart link on, the console gets the bytes outside the frames
+                    let byte = match transport::link() {
+                        Link::Uart => match ctx.local.frames.push(byte) {
+                            Received::Text(byte) => byte,
+                            Received::Frame(data) => {
+                                transport::received(&data);
+                                continue;
+                            }
+                            Received::Nothing => continue,
+                        },
+                        _ => byte,
+                    };
+                    if let Some(line) = ctx.local.line.push(byte) {
+                        if tasks::spawned(Task::ConsoleCommand, console_command::spawn(line, false)).is_err() {
+                            logging::warn("console busy, command dropped");
+                            events::record(Event::Error("console busy"));
+                        }
+                    }
+                }
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(_)) => {
+                    logging::error("serial receive error");
+                    events::record(Event::Error("serial receive"));
+                }
+            }
+        }
+    }
+
+    // NOTE: polls the queue instead of sending from `log!` directly,
+    // so logging never blocks on the radio and never needs the radio resource
+    #[task(priority = 1, shared = [radio, seal, &identity])]
+    async fn radio_log(mut ctx : radio_log::Context) {
+        // NOTE: whether a radio game turned the receiver on, it goes off again once the
+        // game is left
+        let mut playing = false;
+        // NOTE: one hello at boot, so a listener learns who came up and what it runs
+        if let Some(sealed) = about::hello_packet().and_then(|packet| ctx.shared.seal.lock(|seal| seal.seal(&packet))) {
+            ctx.shared.radio.lock(|radio| radio.send(&sealed));
+        }
+        loop {
+            let run = tasks::Run::start(Task::RadioLog);
+            while let Some(payload) = radiolog::next() {
+                let Some(sealed) = ctx.shared.seal.lock(|seal| seal.seal(&payload)) else {
+                    continue;
+                };
+                // NOTE: a listener keeps its receiver on, its own records stay local
+                ctx.shared.radio.lock(|radio| {
+                    if !radio.is_listening() {
+                        radio.send(&sealed);
+                    }
+                });
+            }
+
+            let now = Mono::now();
+            let active = rps::active(now) || tug::active(now) || pairing::active(now) || meter::active(now)
+                || timesync::active() || relay::active() || remote::active() || car::listening(now)
+                || attendance::active() || quiz::active(now) || proximity::active()
+                || swarm::active(now) || presenter::listening();
+            #[cfg(feature = "ota")]
+            let active = active || ota::active();
+            ctx.shared.radio.lock(|radio| {
+                if active && !radio.is_listening() {
+                    radio.listen(true);
+                    playing = true;
+                } else if !active && playing {
+                    radio.listen(false);
+                    playing = false;
+                }
+            });
+            let seal = &mut ctx.shared.seal;
+            let address = ctx.shared.identity.radio_address();
+            // NOTE: the game packets go to the peer only, once there is one
+            let packets = [
+                rps::next_packet(now, address, |data| seal.lock(|seal| seal.digest(data))).and_then(pairing::wrap),
+                tug::next_packet(now).and_then(pairing::wrap),
+                meter::next_packet(now).and_then(pairing::wrap),
+                car::next_packet(now).and_then(pairing::wrap),
+                pairing::next_packet(now, ctx.shared.identity.device_id),
+                timesync::next_packet(now),
+                telemetry::next_packet(now),
+                auth::next_packet(),
+                attendance::next_packet(&ctx.shared.identity),
+                proximity::next_packet(now),
+                swarm::next_packet(now),
+            ];
+            let remote = core::iter::from_fn(remote::next_packet).filter_map(pairing::wrap);
+            let presenter = core::iter::from_fn(presenter::next_packet).filter_map(pairing::wrap);
+            let wedge = core::iter::from_fn(wedge::next_packet).filter_map(pairing::wrap);
+            let mouse = core::iter::from_fn(mouse::next_packet).filter_map(pairing::wrap);
+            let quiz = core::iter::from_fn(|| quiz::next_packet(now));
+            // NOTE: the flooded messages go to every board, whoever is paired
+            for packet in packets.into_iter().flatten().chain(remote).chain(presenter).chain(wedge).chain(mouse).chain(quiz).chain(core::iter::from_fn(relay::next)).chain(core::iter::from_fn(transport::next)) {
+                if let Some(sealed) = seal.lock(|seal| seal.seal(&packet)) {
+                    ctx.shared.radio.lock(|radio| radio.send(&sealed));
+                }
+            }
+            let group = ctx.shared.radio.lock(|radio| radio.group());
+            if let Some(pdu) = beacon::next_pdu(now, group, ctx.shared.identity.address) {
+                ctx.shared.radio.lock(|radio| radio.advertise(&pdu));
+            }
+            drop(run);
+            Mono::delay_until(Mono::now() + 100.millis()).await;
+        }
+    }
+
+    // NOTE: same as radio_log, the serial resource can't be locked from every priority
+    #[task(priority = 1, shared = [serial])]
+    async fn serial_log(mut ctx : serial_log::Context) {
+        loop {
+            let run = tasks::Run::start(Task::SerialLog);
+            while let Some(line) = seriallog::next() {
+                ctx.shared.serial.lock(|serial| console::write_line(serial, &line));
+            }
+            drop(run);
+            Mono::delay_until(Mono::now() + 50.millis()).await;
+        }
+    }
+
+    // NOTE: above every task that may take random numbers, see rng.rs
+    #[task(binds = RNG, priority = 4, local = [rng])]
+    fn rng_ready(ctx : rng_ready::Context) {
+        let _run = tasks::Run::start(Task::RngReady);
+        rng::on_interrupt(ctx.local.rng);
+    }
+
+    #[task(binds = RADIO, priority = 2, shared = [radio])]
+    fn radio_interrupt(mut ctx : radio_interrupt::Context) {
+        // NOTE: taken first thing, the time sync needs when the packet came in
+        let at = Mono::now();
+        let _run = tasks::Run::start(Task::RadioInterrupt);
+        if let Some((payload, rssi)) = ctx.shared.radio.lock(|radio| radio.on_interrupt().map(|payload| (payload, radio.rssi()))) {
+            events::record(Event::RadioRx { len : payload.len() as u8 });
+            if tasks::spawned(Task::RadioReceived, radio_received::spawn(payload, rssi, at)).is_err() {
+                logging::warn("radio packet dropped");
+                events::record(Event::Error("radio packet dropped"));
+            }
+        }
+    }
+
+    #[task(priority = 1, shared = [serial, seal, &identity])]
+    async fn radio_received(mut ctx : radio_received::Context, payload : radio::Payload, rssi : i8, at : mono::Instant) {
+        let _run = tasks::Run::start(Task::RadioReceived);
+        // NOTE: packets that aren't sealed with our key are dropped, whoever sent them
+        let Some((sender, payload)) = ctx.shared.seal.lock(|seal| seal.open(&payload)) else {
+            logging::debug("radio packet not sealed with our key, or replayed");
+            return;
+        };
+        let Some((from_peer, payload)) = pairing::heard(sender, ctx.shared.identity.radio_address(), &payload) else {
+            logging::debug("radio packet for another board");
+            return;
+        };
+        let Some(packet) = Packet::decode(payload) else {
+            logging::debug("unknown radio packet");
+            return;
+        };
+        // NOTE: the host gets every packet, whatever this board makes of it
+        match transport::link() {
+            Link::Uart => ctx.shared.serial.lock(|serial| transport::forward(serial, sender, rssi, payload)),
+            #[cfg(all(feature = "use_rtt", not(feature = "hil")))]
+            Link::Rtt => transport::forward(&mut transport::rtt::Rtt, sender, rssi, payload),
+            _ => (),
+        }
+        let packet = &packet;
+        let seal = &mut ctx.shared.seal;
+        let now = Mono::now();
+        if pairing::on_packet(sender, packet, now) || timesync::on_packet(sender, packet, at) {
+            return;
+        }
+        let own = ctx.shared.identity.radio_address();
+        let commanded = auth::on_packet(own, packet, |data| seal.lock(|seal| seal.mac(data)), |line| {
+            log!("radio command from {:04x}: {}", sender, console::redacted(&line));
+            if tasks::spawned(Task::ConsoleCommand, console_command::spawn(line, true)).is_err() {
+                logging::warn("console busy, radio command dropped");
+            }
+        });
+        if commanded {
+            return;
+        }
+        #[cfg(feature = "ota")]
+        if ota::on_packet(packet) {
+            return;
+        }
+        // NOTE: only the peer's remote works this board, drives it or turns its host's slides and moves its cursor, its packets come addressed to us
+        if from_peer && (remote::on_packet(packet) || car::on_packet(packet, now) || presenter::on_packet(packet) || mouse::on_packet(packet)) {
+            return;
+        }
+        // NOTE: every student checks in, every player buzzes and the swarm follows its master, paired or not
+        if attendance::on_packet(sender, packet)
+            || quiz::on_packet(sender, own, packet, now)
+            || proximity::on_packet(sender, packet, rssi, now)
+            || swarm::on_packet(packet, now)
+        {
+            return;
+        }
+        // NOTE: a paired board only plays its peer
+        if (from_peer || pairing::peer().is_none())
+            && (rps::on_packet(sender, packet, now, |data| seal.lock(|seal| seal.digest(data)))
+                || tug::on_packet(sender, packet, now)
+                || meter::on_packet(packet, rssi, now))
+        {
+            return;
+        }
+        let relayed = relay::on_packet(packet, |message| {
+            let mut line = String::<{ relay::TEXT_LEN + 24 }>::new();
+            let _ = write!(line, "relay {:04x}: {}", message.origin, message.text);
+            ctx.shared.serial.lock(|serial| console::write_line(serial, &line));
+            let mut text = scroll::Text::new();
+            let _ = text.push_str(&message.text);
+            dnd::notify(text, now);
+            inbox::receive(&message);
+        });
+        if relayed {
+            return;
+        }
+        if let Some(snapshot) = telemetry::decode(packet) {
+            let line = telemetry::json(sender, &snapshot);
+            ctx.shared.serial.lock(|serial| console::write_line(serial, &line));
+            return;
+        }
+        if let Some(version) = about::decode_hello(packet) {
+            let mut line = String::<{ radio::PAYLOAD_LEN + 32 }>::new();
+            let _ = write!(line, "radio {:04x}: hello firmware {}", sender, version);
+            ctx.shared.serial.lock(|serial| console::write_line(serial, &line));
+            return;
+        }
+        let Some((level, text)) = radiolog::decode(packet) else {
+            logging::debug("radio packet not taken");
+            return;
+        };
+        let mut line = String::<{ radio::PAYLOAD_LEN + 16 }>::new();
+        let _ = write!(line, "radio {:04x}: {} {}", sender, level.prefix(), text);
+        ctx.shared.serial.lock(|serial| console::write_line(serial, &line));
+    }
+
+    // NOTE: `authenticated` for a command that came over the radio with a good tag
+    #[task(priority = 1, shared = [serial, display, crash_pending, radio, blink, comparator, settings, supply, liveness, counters, initials, seal, &identity])]
+    async fn console_command(mut ctx : console_command::Context, line : console::Line, authenticated : bool) {
+        let _run = tasks::Run::start(Task::ConsoleCommand);
+        log!(Level::Debug, "console: {}", console::redacted(&line));
+        let seal = &mut ctx.shared.seal;
+        let crash_pending = &mut ctx.shared.crash_pending;
+        let radio = &mut ctx.shared.radio;
+        let blink = &mut ctx.shared.blink;
+        let comparator = &mut ctx.shared.comparator;
+        let display = &mut ctx.shared.display;
+        let settings = &mut ctx.shared.settings;
+        let supply = &mut ctx.shared.supply;
+        let liveness = &mut ctx.shared.liveness;
+        let counters = &mut ctx.shared.counters;
+        let initials = &mut ctx.shared.initials;
+        ctx.shared.serial.lock(|serial| {
+            let mut authenticated = authenticated;
+            let command = match console::parse(&line) {
+                Command::Auth(tag, command) => {
+                    if !auth::check(Channel::Console, &tag, command, |data| seal.lock(|seal| seal.mac(data))) {
+                        console::write_line(serial, "refused, bad tag or no nonce, ask for a new one");
+                        return;
+                    }
+                    authenticated = true;
+                    console::parse(command)
+                }
+                command => command,
+            };
+            // NOTE: the first class key can't come with a tag, there is no key to make one with yet
+            let first_key = matches!(command, Command::Key(Some(_))) && !seal.lock(|seal| seal.keyed());
+            if !authenticated && !first_key && auth::guarded(&command) {
+                console::write_line(serial, "needs a tag, see 'nonce' and 'auth'");
+                return;
+            }
+            if lock::blocked(&command) {
+                console::write_line(serial, "locked, knock the pattern on the board first");
+                return;
+            }
+            match command {
+                Command::Help => {
+                    for help in console::HELP {
+                        console::write_line(serial, help);
+                    }
+                }
+                Command::Dump => logbuf::dump(|s| console::write_line(serial, s)),
+                Command::Crash => match crashlog::load() {
+                    Some(report) => {
+                        let mut line = String::<{ crashlog::MESSAGE_LEN + 64 }>::new();
+                        let _ = write!(
+                            line, "{} in {} at pc 0x{:x} after {} ms: {}",
+                            report.kind.name(),
+                            crashlog::context_name(report.context),
+                            report.pc,
+                            report.uptime_ms,
+                            report.message.as_str());
+                        console::write_line(serial, &line);
+                    }
+                    None => console::write_line(serial, "no crash report"),
+                },
+                Command::Ack => {
+                    crash_pending.lock(|pending| *pending = false);
+                    crashlog::clear();
+                    console::write_line(serial, "crash report acknowledged");
+                }
+                Command::Events(format) => events::export(format, |s| console::write_line(serial, s)),
+                Command::Temps => thermo::export(|s| console::write_line(serial, s)),
+                Command::Route(routes) => {
+                    if let Some(routes) = routes {
+                        logging::set_routes(routes);
+                    }
+                    let mut line = String::<40>::from("routes:");
+                    for sink in logging::Sink::ALL {
+                        if logging::routed(sink) {
+                            let _ = write!(line, " {}", sink.name());
+                        }
+                    }
+                    console::write_line(serial, &line);
+                }
+                Command::Level(level) => {
+                    if let Some(level) = level {
+                        logging::set_level(level);
+                    }
+                    let mut line = String::<16>::new();
+                    let _ = write!(line, "level {}", logging::level().name());
+                    console::write_line(serial, &line);
+                }
+                Command::Settings => {
+                    let settings = settings.lock(|settings| *settings);
+                    let mut line = String::<{ console::LINE_LEN }>::new();
+                    let _ = write!(
+                        line, "brightness {} sound {} group {} mode {} threshold {}/16",
+                        settings.brightness,
+                        if settings.sound { "on" } else { "off" },
+                        settings.radio_group,
+                        settings.default_mode,
+                        settings.threshold);
+                    console::write_line(serial, &line);
+                    line.clear();
+                    let _ = write!(
+                        line, "playlist {} s shuffle {} stealth {} sync {} relay {}",
+                        settings.playlist_secs,
+                        if settings.shuffle { "on" } else { "off" },
+                        if settings.stealth { "on" } else { "off" },
+                        settings.time_sync.name(),
+                        if settings.relay { "on" } else { "off" });
+                    console::write_line(serial, &line);
+                    line.clear();
+                    let _ = write!(
+                        line, "remote {} telemetry {} s link {} auth {}",
+                        if settings.remote { "on" } else { "off" },
+                        settings.telemetry_secs,
+                        settings.link.name(),
+                        if settings.auth { "on" } else { "off" });
+                    console::write_line(serial, &line);
+                    line.clear();
+                    let _ = write!(
+                        line, "boot {} wear {} flip {} facedown {}",
+                        settings.boot.name(),
+                        if settings.wear { "on" } else { "off" },
+                        if settings.flip { "on" } else { "off" },
+                        settings.facedown.name());
+                    console::write_line(serial, &line);
+                    line.clear();
+                    let _ = write!(
+                        line, "lock {} knock {} motor {} car {}",
+                        if settings.lock { "on" } else { "off" },
+                        lock::pattern(),
+                        settings.motor.name(),
+                        settings.car.name());
+                    console::write_line(serial, &line);
+                    line.clear();
+                    let _ = write!(
+                        line, "beacon {} attendance {} quiz {} proximity {}",
+                        settings.beacon.name(),
+                        settings.attendance.name(),
+                        settings.quiz.name(),
+                        if settings.proximity { "on" } else { "off" });
+                    console::write_line(serial, &line);
+                    line.clear();
+                    match settings.dnd {
+                        Some(hours) => { let _ = write!(line, "dnd {}", hours); }
+                        None => { let _ = line.push_str("dnd off"); }
+                    }
+                    console::write_line(serial, &line);
+                    line.clear();
+                    let limits = settings.greenhouse;
+                    let _ = write!(
+                        line, "greenhouse temp {}..{} C humidity {}..{} %",
+                        limits.temperature.0, limits.temperature.1,
+                        limits.humidity.0, limits.humidity.1);
+                    console::write_line(serial, &line);
+                    line.clear();
+                    let _ = write!(
+                        line, "scroll {} ms {} repeat {}",
+                        settings.scroll.step_ms,
+                        settings.scroll.direction.name(),
+                        settings.scroll.repeat);
+                    console::write_line(serial, &line);
+                    line.clear();
+                    let calibration = settings.calibration;
+                    let _ = write!(
+                        line, "accel offset {} {} {} temperature offset {}",
+                        calibration.accel_offset[0],
+                        calibration.accel_offset[1],
+                        calibration.accel_offset[2],
+                        calibration.temperature_offset);
+                    console::write_line(serial, &line);
+                }
+                Command::Set(setting) => {
+                    let updated = settings.lock(|settings| {
+                        match setting {
+                            Setting::Brightness(level) => settings.brightness = level,
+                            Setting::Sound(on)         => settings.sound = on,
+                            Setting::RadioGroup(group) => settings.radio_group = group,
+                            Setting::DefaultMode(mode) => settings.default_mode = mode,
+                            Setting::Threshold(sixteenths) => settings.threshold = sixteenths,
+                            Setting::PlaylistSecs(secs) => settings.playlist_secs = secs,
+                            Setting::Shuffle(on)        => settings.shuffle = on,
+                            Setting::ScrollMs(ms)       => settings.scroll.step_ms = ms as u32,
+                            Setting::ScrollDirection(direction) => settings.scroll.direction = direction,
+                            Setting::ScrollRepeat(repeat) => settings.scroll.repeat = repeat,
+                            Setting::Stealth(on)        => settings.stealth = on,
+                            Setting::TimeSync(role)     => settings.time_sync = role,
+                            Setting::Relay(on)          => settings.relay = on,
+                            Setting::Remote(on)         => settings.remote = on,
+                            Setting::TelemetrySecs(secs) => settings.telemetry_secs = secs,
+                            Setting::Link(link)         => settings.link = link,
+                            Setting::Auth(on)           => settings.auth = on,
+                            Setting::Boot(boot)         => settings.boot = boot,
+                            Setting::Wear(on)           => settings.wear = on,
+                            Setting::Flip(on)           => settings.flip = on,
+                            Setting::FaceDown(mode)     => settings.facedown = mode,
+                            Setting::Lock(on)           => settings.lock = on,
+                            Setting::GreenhouseTemperature(low, high) => settings.greenhouse.temperature = (low, high),
+                            Setting::GreenhouseHumidity(low, high) => settings.greenhouse.humidity = (low, high),
+                            Setting::Motor(control) => settings.motor = control,
+                            Setting::Car(role)      => settings.car = role,
+                            Setting::Beacon(kind)   => settings.beacon = kind,
+                            Setting::Attendance(role) => settings.attendance = role,
+                            Setting::Quiz(role)     => settings.quiz = role,
+                            Setting::Proximity(on)  => settings.proximity = on,
+                            Setting::Dnd(hours)     => settings.dnd = hours,
+                        }
+                        *settings
+                    });
+                    menu::configure(&updated);
+                    match setting {
+                        Setting::RadioGroup(group) => radio.lock(|radio| radio.set_group(group)),
+                        Setting::Sound(on) => speaker::set_muted(!on),
+                        Setting::Threshold(sixteenths) => comparator.lock(|comparator| comparator.set_threshold(sixteenths)),
+                        Setting::PlaylistSecs(_) | Setting::Shuffle(_) => playlist::configure(&updated),
+                        Setting::Stealth(on) => set_stealth(on),
+                        Setting::TimeSync(role) => timesync::configure(role),
+                        Setting::Relay(on) => relay::configure(on),
+                        Setting::Remote(on) => remote::configure(on),
+                        Setting::TelemetrySecs(secs) => telemetry::configure(secs),
+                        Setting::Link(link) => transport::configure(link),
+                        Setting::Auth(on) => auth::configure(on),
+                        Setting::Wear(on) => display::set_wear_levelling(on),
+                        Setting::Flip(on) => display::set_auto_flip(on),
+                        Setting::FaceDown(mode) => facedown::configure(mode),
+                        Setting::Lock(on) => lock::configure(on),
+                        Setting::GreenhouseTemperature(..) | Setting::GreenhouseHumidity(..) => greenhouse::configure(updated.greenhouse),
+                        Setting::Motor(control) => motor::configure(control),
+                        Setting::Car(role) => car::configure(role),
+                        Setting::Beacon(kind) => beacon::configure(kind),
+                        Setting::Attendance(role) => attendance::configure(role),
+                        Setting::Quiz(role) => quiz::configure(role),
+                        Setting::Proximity(on) => proximity::configure(on),
+                        Setting::Dnd(hours) => dnd::configure(hours),
+                        Setting::DefaultMode(mode) => launcher::remember(mode),
+                        Setting::Brightness(level) => {
+                            let brightness = supply.lock(|supply| supply.brightness(level));
+                            display.lock(|display| display.set_brightness(brightness));
+                        }
+                        _ => (),
+                    }
+                    storage::save(&updated);
+                    console::write_line(serial, "saved");
+                }
+                Command::Scroll(options, message) => {
+                    let style = options.apply(settings.lock(|settings| settings.scroll));
+                    let mut text = scroll::Text::new();
+                    // NOTE: a console line is longer than a text, the end is cut off
+                    for c in message.chars() {
+                        if text.push(c).is_err() {
+                            break;
+                        }
+                    }
+                    if tasks::spawned(Task::ScrollText, scroll_text::spawn(text, Some(style))).is_err() {
+                        console::write_line(serial, "display busy scrolling");
+                    }
+                }
+                Command::Send(text) => {
+                    relay::send(ctx.shared.identity.radio_address(), text);
+                    console::write_line(serial, "sending");
+                }
+                Command::Kv => match kv::stats() {
+                    Some(stats) => {
+                        let mut line = String::<{ console::LINE_LEN }>::new();
+                        let _ = write!(
+                            line, "page {} generation {}: {} records, {} of {} bytes",
+                            stats.page,
+                            stats.generation,
+                            stats.records,
+                            stats.used_bytes,
+                            crate::flash::PAGE_SIZE);
+                        console::write_line(serial, &line);
+                    }
+                    None => console::write_line(serial, "key-value store is empty"),
+                },
+                Command::Reboot => {
+                    console::write_line(serial, "saving counters and rebooting");
+                    let counters = counters.lock(|counters| *counters);
+                    counters.save(&mut Counters::load());
+                    cortex_m::peripheral::SCB::sys_reset();
+                }
+                Command::Dfu => {
+                    console::write_line(serial, "saving counters and resetting into the bootloader");
+                    let counters = counters.lock(|counters| *counters);
+                    counters.save(&mut Counters::load());
+                    if tasks::spawned(Task::DfuReset, dfu_reset::spawn()).is_err() {
+                        dfu::reset();
+                    }
+                }
+                #[cfg(feature = "ota")]
+                Command::Ota(crc) => {
+                    if tasks::spawned(Task::OtaUpdate, ota_update::spawn(crc)).is_err() {
+                        console::write_line(serial, "ota: an update is running already");
+                    } else {
+                        console::write_line(serial, "ota: asking for the image");
+                    }
+                }
+                #[cfg(not(feature = "ota"))]
+                Command::Ota(_) => console::write_line(serial, "ota: not in this build, see the ota feature"),
+                Command::Scores(game) => {
+                    let text = highscores::text(game);
+                    console::write_line(serial, &text);
+                    if tasks::spawned(Task::ScrollText, scroll_text::spawn(text, None)).is_err() {
+                        console::write_line(serial, "display busy scrolling");
+                    }
+                }
+                Command::Id => {
+                    let identity = ctx.shared.identity;
+                    let mut line = String::<{ console::LINE_LEN }>::new();
+                    let a = identity.address;
+                    let _ = write!(
+                        line, "id {:016x} address {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x} name {}",
+                        identity.device_id, a[5], a[4], a[3], a[2], a[1], a[0],
+                        identity.name.as_deref().unwrap_or("-"));
+                    console::write_line(serial, &line);
+
+                    // NOTE: the name if there is one, otherwise the radio address
+                    let mut text = scroll::Text::new();
+                    match &identity.name {
+                        Some(name) => { let _ = text.push_str(name); }
+                        None => { let _ = write!(text, "{:04x}", identity.radio_address()); }
+                    }
+                    if tasks::spawned(Task::ScrollText, scroll_text::spawn(text, None)).is_err() {
+                        console::write_line(serial, "display busy scrolling");
+                    }
+                }
+                // NOTE: stands in for a game until there are games
+                Command::Score(game, score) => match highscores::qualifies(game, score) {
+                    Some(_) => {
+                        let entry = InitialsEntry::default();
+                        initials.lock(|initials| *initials = Some(highscores::Pending { game, score, entry }));
+                        console::write_line(serial, "high score! enter initials, A picks a letter, B confirms");
+                    }
+                    None => console::write_line(serial, "not a high score"),
+                },
+                Command::Status => {
+                    let supply = supply.lock(|supply| *supply);
+                    let brightness = settings.lock(|settings| settings.brightness);
+                    let mut line = String::<{ console::LINE_LEN }>::new();
+                    let _ = write!(
+                        line, "up {} s liveness {} supply {} mV{} brightness {}",
+                        Mono::now().duration_since_epoch().to_secs(),
+                        liveness.lock(|liveness| *liveness),
+                        supply.millivolts,
+                        if supply.low { " (low)" } else { "" },
+                        supply.brightness(brightness));
+                    console::write_line(serial, &line);
+                }
+                Command::Ps => {
+                    console::write_line(serial, "task              spawned dropped   runs  last s   max us");
+                    for task in tasks::TASKS {
+                        let stats = tasks::stats(task);
+                        let mut last = String::<12>::new();
+                        match stats.last_ms {
+                            Some(ms) => { let _ = write!(last, "{}.{:03}", ms / 1000, ms % 1000); }
+                            None => { let _ = last.push('-'); }
+                        }
+                        let mut line = String::<{ console::LINE_LEN }>::new();
+                        let _ = write!(
+                            line, "{:<17} {:>7} {:>7} {:>6} {:>7} {:>8}",
+                            task.name(), stats.spawned, stats.dropped, stats.runs, last, stats.max_us);
+                        console::write_line(serial, &line);
+                    }
+                }
+                Command::Info => {
+                    let mut line = String::<{ console::LINE_LEN }>::new();
+                    let _ = write!(line, "firmware {}", about::VERSION);
+                    console::write_line(serial, &line);
+                    line.clear();
+                    let _ = write!(
+                        line, "up {} s reset {} id {:016x}",
+                        about::uptime_secs(), about::reset_reason(), about::device_id());
+                    console::write_line(serial, &line);
+                }
+                Command::Clock(set) => {
+                    let now = Mono::now();
+                    if let Some(minute) = set {
+                        clock::set(minute, now);
+                    }
+                    let mut line = String::<{ console::LINE_LEN }>::new();
+                    match clock::minute(now) {
+                        Some(minute) => { let _ = write!(line, "clock {:02}:{:02}", minute / 60, minute % 60); }
+                        None => { let _ = line.push_str("clock not set"); }
+                    }
+                    console::write_line(serial, &line);
+                }
+                Command::Sync => {
+                    let status = timesync::status(Mono::now());
+                    let mut line = String::<{ console::LINE_LEN }>::new();
+                    let _ = write!(line, "sync {} offset {} us", status.role.name(), status.offset_us);
+                    match status.master {
+                        Some((address, heard_ms)) => { let _ = write!(line, " master {:04x} heard {} ms ago", address, heard_ms); }
+                        None if status.role == timesync::Role::Follow => { let _ = line.push_str(" no master heard"); }
+                        None => (),
+                    }
+                    console::write_line(serial, &line);
+                }
+                Command::Bench => {
+                    if tasks::spawned(Task::RenderBench, render_bench::spawn()).is_err() {
+                        console::write_line(serial, "already benchmarking");
+                    } else {
+                        console::write_line(serial, "benchmarking, results in the log");
+                    }
+                }
+                Command::Pulse => {
+                    if tasks::spawned(Task::PulseMeasure, pulse_measure::spawn()).is_err() {
+                        console::write_line(serial, "already measuring");
+                    }
+                }
+                Command::Pwm(wave) => {
+                    generator::set(wave);
+                    let mut line = String::<{ console::LINE_LEN }>::new();
+                    let _ = match wave {
+                        Some(wave) => write!(line, "{} Hz duty {}% on {}", wave.hz, wave.duty_percent, generator::PIN),
+                        None => write!(line, "pwm off"),
+                    };
+                    console::write_line(serial, &line);
+                }
+                Command::Beacon(change) => {
+                    if let Some(change) = change {
+                        beacon::change(change);
+                    }
+                    let (kind, id, url) = beacon::status();
+                    let mut line = String::<{ console::LINE_LEN }>::new();
+                    let _ = write!(line, "{} id ", kind.name());
+                    for byte in id {
+                        let _ = write!(line, "{:02x}", byte);
+                    }
+                    console::write_line(serial, &line);
+                    line.clear();
+                    let _ = write!(line, "url {}", url.as_str());
+                    console::write_line(serial, &line);
+                }
+                Command::Attendance(clear) => attendance::print(clear, |line| console::write_line(serial, line)),
+                Command::Follow(tune) => {
+                    if let Some(tune) = tune {
+                        follower::tune(tune);
+                    }
+                    let tuning = follower::tuning();
+                    let mut line = String::<{ console::LINE_LEN }>::new();
+                    let _ = write!(line, "kp {} ki {} kd {} speed {}", tuning.kp, tuning.ki, tuning.kd, tuning.speed);
+                    console::write_line(serial, &line);
+                }
+                Command::Scope => {
+                    let streaming = scope::toggle_streaming();
+                    console::write_line(serial, if streaming { "streaming scope samples" } else { "scope streaming off" });
+                }
+                Command::Blink => {
+                    let on = blink.lock(|blink| {
+                        blink.set(!blink.is_on());
+                        blink.is_on()
+                    });
+                    console::write_line(serial, if on { "blinking" } else { "blink off" });
+                }
+                Command::Listen => {
+                    let listening = radio.lock(|radio| {
+                        radio.listen(!radio.is_listening());
+                        radio.is_listening()
+                    });
+                    events::record(Event::ModeChange(if listening { "listener" } else { "normal" }));
+                    console::write_line(serial, if listening {
+                        "forwarding radio log packets"
+                    } else {
+                        "radio listener off"
+                    });
+                }
+                // NOTE: no tag checks out without a class key, a nonce would only mislead
+                Command::Nonce if !seal.lock(|seal| seal.keyed()) => {
+                    console::write_line(serial, "no class key, see 'key'");
+                }
+                Command::Nonce => {
+                    let mut line = String::<{ 2 * auth::NONCE_LEN }>::new();
+                    for byte in auth::challenge(Channel::Console) {
+                        let _ = write!(line, "{:02x}", byte);
+                    }
+                    console::write_line(serial, &line);
+                }
+                Command::Key(None) => console::write_line(serial, match seal.lock(|seal| seal.keyed()) {
+                    true => "class key set",
+                    false => "no class key, radio packets are neither sealed nor opened, see 'key'",
+                }),
+                Command::Key(Some(secret)) => {
+                    seal.lock(|seal| seal.set_key(secret.as_bytes()));
+                    console::write_line(serial, "class key stored");
+                }
+                Command::Knock(pattern) => {
+                    lock::set_pattern(pattern);
+                    console::write_line(serial, "knock pattern stored");
+                }
+                Command::Auth(..) => console::write_line(serial, "one tag for one command"),
+                Command::Unknown(command) => {
+                    console::write_line(serial, "unknown command, try 'help':");
+                    console::write_line(serial, command);
+                }
+            }
+        });
+    }
+
     // NOTE: local variable declared here.
     // This does not require the local variable to implement the Send trait.
-    #[idle(shared = [display, timer, &key], local = [idle_count : u32 = 0])]
+    #[idle(shared = [display, timer, crash_pending, counters, initials, supply, launcher, radio, settings])]
     fn idle(mut ctx : idle::Context) -> ! {
-        let idle_count = ctx.local.idle_count;
 
-        logging::print("idling...");
-        logging::print(
-            compose_string::<32>(
-                &[ "The key is: "
-                // NOTE: accessing a shared resource without locking
-                // ... possible because its a reference
-                 , ctx.shared.key.as_str()]
-                 ).unwrap().as_str());
+        logging::info("idling...");
+
+        // NOTE: a crash report waiting goes first, the splash would only hold it up
+        if ctx.shared.crash_pending.lock(|pending| *pending) {
+            splash::skip();
+        }
+        let mut step = 0;
+        while let Some((leds, duration_ms)) = splash::frame(step) {
+            ctx.shared.display.lock(|display| {
+                ctx.shared.timer.lock(|timer| {
+                    Screen { display, timer }.show(leds, duration_ms)
+                })
+            });
+            step += 1;
+        }
 
         let leds_empty = [[0; 5]; 5];
-        let mut leds = leds_empty;
-        let led_states = [ (1,1), (1,2), (1,3), (2,3), (3,3), (3,2), (3,1), (2,1) ];
+        let mut low_warned : Option<mono::Instant> = None;
         loop {
-            for (x,y) in led_states {
-                leds[x][y] = 1;
+            if ctx.shared.crash_pending.lock(|pending| *pending) {
+                for leds in [crashlog::ICON, leds_empty] {
+                    ctx.shared.display.lock(|display| {
+                        ctx.shared.timer.lock(|timer| {
+                            display.show(timer, leds, 500)
+                        })
+                    });
+                }
+                continue;
+            }
+
+            // NOTE: a game that ended with a score for the table goes on to its initials
+            if let Some((game, score)) = highscores::take_finished() {
+                if highscores::qualifies(game, score).is_some() {
+                    let entry = InitialsEntry::default();
+                    ctx.shared.initials.lock(|initials| *initials = Some(highscores::Pending { game, score, entry }));
+                }
+            }
+
+            if let Some(frame) = ctx.shared.initials.lock(|initials| initials.map(|pending| pending.entry.frame())) {
                 ctx.shared.display.lock(|display| {
                     ctx.shared.timer.lock(|timer| {
-                        display.show(timer, leds, 250)
+                        display.show(timer, frame, 100)
                     })
                 });
-                leds = leds_empty;
+                continue;
             }
-            *idle_count += 1;
-            log_count("Idle count: ", *idle_count);
-        }
-    }
-}
 
-use microbit::hal::gpiote::GpioteChannel;
-use heapless::String;
-use core::fmt::Write;
+            if let Some(icon) = ctx.shared.launcher.lock(|launcher| launcher.selected().map(|app| app.icon)) {
+                ctx.shared.display.lock(|display| {
+                    ctx.shared.timer.lock(|timer| {
+                        display.show(timer, icon, 100)
+                    })
+                });
+                continue;
+            }
 
-fn button_pressed_action(chan : GpioteChannel, button_name : &str) {
-    if chan.is_event_triggered() {
-        let message =
-            compose_string::<32>(
-                &["Button ", button_name, " has been pressed"])
-            .unwrap();
-        logging::print(message.as_str());
-        chan.reset_events()
-    }
-}
+            // NOTE: one cycle of the running app, cut short when the launcher opens
+            let app = ctx.shared.launcher.lock(|launcher| launcher.current());
+            let mut step = 0;
+            while let Some((leds, duration_ms)) = (app.draw)(step) {
+                if ctx.shared.launcher.lock(|launcher| launcher.selected().is_some()) {
+                    break;
+                }
+                // NOTE: a toast covers the app's frame and the corners, the app runs on underneath
+                let leds = dnd::indicate(inbox::indicate(leds));
+                let (leds, duration_ms) = toast::frame(Mono::now()).unwrap_or((leds, duration_ms));
+                ctx.shared.display.lock(|display| {
+                    ctx.shared.timer.lock(|timer| {
+                        Screen { display, timer }.show(leds, duration_ms)
+                    })
+                });
+                step += 1;
+            }
 
-fn compose_string<const N : usize>(xs : &[&str]) -> Result<String<N>, ()> {
-    let mut s = String::<N>::new();
-    for x in xs {
-        s.push_str(x)?;
-    }
-    Ok(s)
-}
+            // NOTE: a change made in the settings menu, it's stored once the menu is left alone
+            if let Some(change) = menu::take_change() {
+                match change {
+                    menu::Change::Group(group) => {
+                        ctx.shared.settings.lock(|settings| settings.radio_group = group);
+                        ctx.shared.radio.lock(|radio| radio.set_group(group));
+                    }
+                    menu::Change::Brightness(level) => {
+                        ctx.shared.settings.lock(|settings| settings.brightness = level);
+                        let brightness = ctx.shared.supply.lock(|supply| supply.brightness(level));
+                        ctx.shared.display.lock(|display| display.set_brightness(brightness));
+                    }
+                    menu::Change::Boot(boot) => ctx.shared.settings.lock(|settings| settings.boot = boot),
+                }
+            }
+            if menu::take_save(Mono::now()) {
+                storage::save(&ctx.shared.settings.lock(|settings| *settings));
+                log!("menu settings saved");
+            }
 
-fn log_count(message : &str, count : u32) {
-    let mut s = String::<10>::new();
-    write!(&mut s, "{}", count).unwrap();
-    let message =
-        compose_string::<32>(&[message, s.as_str()])
-        .unwrap();
-    logging::print(message.as_str());
+            if ctx.shared.supply.lock(|supply| supply.low) {
+                let now = Mono::now();
+                if low_warned.is_none_or(|at| (now - at).to_millis() >= battery::REMINDER_MS) {
+                    toast::icon(battery::ICON, battery::ICON_SECS);
+                    low_warned = Some(now);
+                }
+            }
+            let idle_count = ctx.shared.counters.lock(|counters| {
+                counters.idle += 1;
+                usage::publish(counters);
+                counters.idle
+            });
+            log!(Level::Trace, "Idle count: {}", idle_count);
+        }
+    }
 }
@@ -0,0 +1,57 @@
+//! Maze: tilt the board to roll the player through mazes larger than the display, see
+//! `fun_core::maze` for the game.
+//!
+//! The tilt comes in from input_poll, each level done is logged and after the last one the
+//! cycle ends.
+
+use core::cell::Cell;
+use critical_section::Mutex;
+use fun_core::maze::Game;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::speaker::Speaker;
+
+pub const ICON : Frame = [
+    [1, 1, 1, 1, 1],
+    [0, 0, 0, 0, 1],
+    [1, 1, 1, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+];
+
+// NOTE: the tilt comes in from input_poll, idle moves the game on
+static GAME : Mutex<Cell<Option<Game>>> = Mutex::new(Cell::new(None));
+
+fn update(next : impl FnOnce(Option<Game>) -> Option<Game>) -> Option<Game> {
+    critical_section::with(|cs| {
+        let game = next(GAME.borrow(cs).get());
+        GAME.borrow(cs).set(game);
+        game
+    })
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let Input::Tilt { x, y } = input else { return false };
+    update(|game| game.map(|mut game| {
+        game.tilt(x, y);
+        game
+    }));
+    true
+}
+
+/// All levels, from the first to the scrolled message after the last.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let game = update(|game| Some(match game {
+        Some(mut game) if step > 0 => {
+            if game.advance(&Mono, &mut Speaker) {
+                log!("maze level {} done", game.level() + 1);
+            }
+            game
+        }
+        // NOTE: from the first level, or the launcher cut the last game short
+        _ => Game::new(&Mono),
+    }))?;
+    game.frame(&Mono)
+}
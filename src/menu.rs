@@ -0,0 +1,173 @@
+//! The settings menu, for changing the radio group, the display brightness and what the
+//! board boots into on the board, so a classroom can split its boards into groups and a
+//! badge can be set up without a serial port.
+//!
+//! The entry scrolls by with its value, over and over. A takes the value down and B up,
+//! wrapping round, a long press of A steps to the next entry. An app can't lock the
+//! settings, so a change ends the cycle and goes to idle through `take_change`, which
+//! applies it straight away;
+//! idle stores the settings once `take_save` says they were left alone for `SAVE_MS`, so
+//! stepping through values doesn't wear the flash. `configure` keeps the shown values in
+//! step with settings changed elsewhere. While the board is locked, see lock.rs, A and B
+//! change nothing.
+
+use core::cell::Cell;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use cortex_m::interrupt::Mutex;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::launcher::Boot;
+use crate::lock;
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::scroll::{self, Text};
+use crate::storage::Settings;
+
+pub const ICON : Frame = [
+    [0, 1, 0, 1, 0],
+    [1, 1, 1, 1, 1],
+    [0, 1, 0, 1, 0],
+    [1, 1, 1, 1, 1],
+    [0, 1, 0, 1, 0],
+];
+
+const SAVE_MS : u64 = 3000;
+
+#[derive(Clone, Copy)]
+enum Entry {
+    Group,
+    Brightness,
+    Boot,
+}
+
+const ENTRIES : [Entry; 3] = [Entry::Group, Entry::Brightness, Entry::Boot];
+
+impl Entry {
+    fn name(self) -> &'static str {
+        match self {
+            Entry::Group      => "group",
+            Entry::Brightness => "brightness",
+            Entry::Boot       => "boot",
+        }
+    }
+
+    // NOTE: group 0 is the MakeCode default, it stays settable over serial
+    fn range(self) -> (u8, u8) {
+        match self {
+            Entry::Group      => (1, 255),
+            Entry::Brightness => (0, 9),
+            // NOTE: as `Boot::to_u8` numbers them
+            Entry::Boot       => (0, Boot::count() - 1),
+        }
+    }
+
+    fn write_value(self, text : &mut Text, value : u8) {
+        let _ = match (self, Boot::from_u8(value)) {
+            (Entry::Boot, Some(boot)) => write!(text, "{} {}", self.name(), boot.name()),
+            _ => write!(text, "{} {}", self.name(), value),
+        };
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Change {
+    Group(u8),
+    Brightness(u8),
+    Boot(Boot),
+}
+
+static CURRENT : AtomicUsize = AtomicUsize::new(0);
+// NOTE: by entry, as in `ENTRIES`
+static VALUES : Mutex<Cell<[u8; 3]>> = Mutex::new(Cell::new([0; 3]));
+// NOTE: the change idle hasn't applied yet, and when the last one was made if it isn't stored
+static CHANGE : Mutex<Cell<Option<Change>>> = Mutex::new(Cell::new(None));
+static UNSAVED : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+// NOTE: when the text on the display started scrolling, a change starts it over
+static SCROLLED : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+pub fn configure(settings : &Settings) {
+    cortex_m::interrupt::free(|cs| VALUES.borrow(cs).set([settings.radio_group, settings.brightness, settings.boot.to_u8()]));
+}
+
+pub fn take_change() -> Option<Change> {
+    cortex_m::interrupt::free(|cs| CHANGE.borrow(cs).take())
+}
+
+/// True once, when the last change has been left alone long enough to store.
+pub fn take_save(now : Instant) -> bool {
+    cortex_m::interrupt::free(|cs| {
+        let unsaved = UNSAVED.borrow(cs);
+        let due = unsaved.get().is_some_and(|at| ms_since(now, at) >= SAVE_MS);
+        if due {
+            unsaved.set(None);
+        }
+        due
+    })
+}
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+fn step(up : bool, now : Instant) {
+    let current = CURRENT.load(Ordering::Relaxed);
+    let entry = ENTRIES[current];
+    let (low, high) = entry.range();
+    let value = cortex_m::interrupt::free(|cs| {
+        let mut values = VALUES.borrow(cs).get();
+        let value = values[current].clamp(low, high);
+        values[current] = match (up, value) {
+            (true, value) if value == high => low,
+            (true, value) => value + 1,
+            (false, value) if value == low => high,
+            (false, value) => value - 1,
+        };
+        VALUES.borrow(cs).set(values);
+        let change = match entry {
+            Entry::Group      => Change::Group(values[current]),
+            Entry::Brightness => Change::Brightness(values[current]),
+            Entry::Boot       => Change::Boot(Boot::from_u8(values[current]).unwrap_or(Boot::Last)),
+        };
+        CHANGE.borrow(cs).set(Some(change));
+        UNSAVED.borrow(cs).set(Some(now));
+        SCROLLED.borrow(cs).set(Some(now));
+        values[current]
+    });
+    log!("menu {} {}", entry.name(), value);
+}
+
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    // NOTE: ends the cycle, so idle applies the change without waiting for the scroll
+    if cortex_m::interrupt::free(|cs| CHANGE.borrow(cs).get().is_some()) {
+        return None;
+    }
+    let now = Mono::now();
+    let current = CURRENT.load(Ordering::Relaxed);
+    let (value, scrolled) = cortex_m::interrupt::free(|cs| {
+        let scrolled = SCROLLED.borrow(cs);
+        if step == 0 || scrolled.get().is_none() {
+            scrolled.set(Some(now));
+        }
+        (VALUES.borrow(cs).get()[current], scrolled.get().unwrap_or(now))
+    });
+    let mut text = Text::new();
+    ENTRIES[current].write_value(&mut text, value);
+    let column = ms_since(now, scrolled) / scroll::STEP_MS as u64;
+    scroll::frames(&text).nth(column as usize).map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
+
+pub fn on_input(input : Input, now : Instant) -> bool {
+    match input {
+        Input::Button(Button::A | Button::B) if lock::locked() => (),
+        Input::Button(Button::A) => step(false, now),
+        Input::Button(Button::B) => step(true, now),
+        Input::LongPress(Button::A) => {
+            CURRENT.store((CURRENT.load(Ordering::Relaxed) + 1) % ENTRIES.len(), Ordering::Relaxed);
+            cortex_m::interrupt::free(|cs| SCROLLED.borrow(cs).set(Some(now)));
+        }
+        _ => return false,
+    }
+    true
+}
@@ -0,0 +1,133 @@
+//! Signal strength meter: how strong the other board comes in, as bars and beeps, for
+//! hot and cold hide-and-seek or walking out the radio range.
+//!
+//! Boards running the meter broadcast a beacon every time round the radio_log loop, to the
+//! peer when paired, see pairing.rs. The strength of the beacons heard is smoothed and
+//! shown as up to five rising bars, the beeps come faster the stronger it is. Nothing heard
+//! for `LOST_MS` and the bars go out, the beeps stop.
+
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+use fun_core::board::Beeper;
+use crate::display::Frame;
+use crate::mono::{Instant, Mono};
+use crate::protocol::Packet;
+use crate::radio::Payload;
+use crate::speaker::Speaker;
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 1],
+    [0, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1],
+    [0, 1, 1, 1, 1],
+    [1, 1, 1, 1, 1],
+];
+
+// NOTE: dBm, from out of range to right next to each other
+const WEAKEST : i32 = -95;
+const STRONGEST : i32 = -45;
+// NOTE: sixteenths of the new sample going into the smoothed strength
+const SMOOTHING : i32 = 4;
+const LOST_MS : u64 = 1000;
+const BEEP_MS : u64 = 30;
+const BEEP_HZ : u32 = 1760;
+// NOTE: between the starts of two beeps, at the weakest and the strongest
+const SLOWEST_MS : u64 = 1000;
+const FASTEST_MS : u64 = 100;
+const ACTIVE_MS : u64 = 500;
+const POLL_MS : u32 = 20;
+
+#[derive(Clone, Copy)]
+struct Heard {
+    // NOTE: 16 times the dBm, so the smoothing keeps its fraction
+    strength : i32,
+    at       : Instant,
+}
+
+static HEARD : Mutex<Cell<Option<Heard>>> = Mutex::new(Cell::new(None));
+static DRAWN : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+static BEEPED : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+/// True while the meter is on screen, the radio has to listen for the beacons then.
+pub fn active(now : Instant) -> bool {
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).get()).is_some_and(|drawn| ms_since(now, drawn) < ACTIVE_MS)
+}
+
+/// The beacon, while the meter is on screen.
+pub fn next_packet(now : Instant) -> Option<Payload> {
+    if !active(now) {
+        return None;
+    }
+    Packet::Beacon.encode()
+}
+
+/// Takes a packet that came in at `rssi` dBm, false when it isn't a beacon.
+pub fn on_packet(packet : &Packet, rssi : i8, now : Instant) -> bool {
+    let Packet::Beacon = packet else { return false };
+    if !active(now) {
+        return true;
+    }
+    let sample = rssi as i32 * 16;
+    cortex_m::interrupt::free(|cs| {
+        let heard = HEARD.borrow(cs);
+        let strength = match heard.get() {
+            Some(last) if ms_since(now, last.at) < LOST_MS => last.strength + (sample - last.strength) * SMOOTHING / 16,
+            _ => sample,
+        };
+        heard.set(Some(Heard { strength, at : now }));
+    });
+    true
+}
+
+/// The strength heard lately, from 0 for the weakest to 5 for the strongest.
+fn level(now : Instant) -> Option<i32> {
+    let heard = cortex_m::interrupt::free(|cs| HEARD.borrow(cs).get())?;
+    if ms_since(now, heard.at) >= LOST_MS {
+        return None;
+    }
+    let dbm = (heard.strength / 16).clamp(WEAKEST, STRONGEST);
+    Some((dbm - WEAKEST) * 5 / (STRONGEST - WEAKEST))
+}
+
+fn bars(level : i32) -> Frame {
+    let mut frame = [[0; 5]; 5];
+    for (column, height) in (1..=5).enumerate() {
+        let lit = if column as i32 <= level { 9 } else { 1 };
+        for row in &mut frame[5 - height..] {
+            row[column] = lit;
+        }
+    }
+    frame
+}
+
+fn beep(level : Option<i32>, now : Instant) {
+    let Some(level) = level else {
+        Speaker.off();
+        return;
+    };
+    let period = SLOWEST_MS - (SLOWEST_MS - FASTEST_MS) * level as u64 / 5;
+    let beeped = cortex_m::interrupt::free(|cs| {
+        let beeped = BEEPED.borrow(cs);
+        if beeped.get().is_none_or(|at| ms_since(now, at) >= period) {
+            beeped.set(Some(now));
+        }
+        beeped.get()
+    });
+    match beeped {
+        Some(at) if ms_since(now, at) < BEEP_MS => Speaker.tone(BEEP_HZ),
+        _ => Speaker.off(),
+    }
+}
+
+pub fn draw(_step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).set(Some(now)));
+    let level = level(now);
+    beep(level, now);
+    // NOTE: the dim outline while nothing is heard
+    Some((bars(level.unwrap_or(-1)), POLL_MS))
+}
@@ -0,0 +1,161 @@
+//! Monotonic timer for the RTIC timer queue, running on RTC0.
+//!
+//! NOTE: `rtic-monotonics` has an RTC monotonic for the nRF52, but it is built against
+//! a newer `nrf52833-pac` than the one `microbit-v2` pulls in, so this is a small port
+//! of it to our PAC.
+//! The RTC is clocked by the 32.768 kHz LFCLK, which must be started before `Mono::start`.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use microbit::pac::{self, Interrupt, RTC0};
+use rtic_time::half_period_counter::{calculate_now, TimerValue};
+use rtic_time::{Monotonic, TimerQueue};
+pub use fugit::ExtU64;
+
+pub type Instant = fugit::TimerInstantU64<32_768>;
+pub type Duration = fugit::TimerDurationU64<32_768>;
+
+static OVERFLOWS : AtomicU32 = AtomicU32::new(0);
+static TIMER_QUEUE : TimerQueue<Mono> = TimerQueue::new();
+
+struct TimerValueU24(u32);
+
+impl TimerValue for TimerValueU24 {
+    const BITS : u32 = 24;
+}
+
+impl From<TimerValueU24> for u64 {
+    fn from(value : TimerValueU24) -> Self {
+        Self::from(value.0)
+    }
+}
+
+pub struct Mono;
+
+impl Mono {
+    pub fn start(rtc : RTC0) {
+        rtc.prescaler.write(|w| unsafe { w.bits(0) });
+
+        rtc.intenclr.write(|w| w
+            .compare0().clear()
+            .compare1().clear()
+            .ovrflw().clear());
+
+        // compare 0 is the dynamic wakeup, compare 1 marks the half period of the 24 bit counter
+        rtc.cc[0].write(|w| unsafe { w.bits(0) });
+        rtc.cc[1].write(|w| unsafe { w.bits(0x80_0000) });
+
+        // NOTE: timing critical, the overflow counter has to be in sync with the counter
+        cortex_m::interrupt::free(|_| {
+            rtc.tasks_clear.write(|w| unsafe { w.bits(1) });
+            rtc.tasks_start.write(|w| unsafe { w.bits(1) });
+
+            rtc.events_ovrflw.write(|w| w);
+            rtc.events_compare[0].write(|w| w);
+            rtc.events_compare[1].write(|w| w);
+
+            OVERFLOWS.store(0, Ordering::SeqCst);
+            TIMER_QUEUE.initialize(Mono);
+
+            rtc.intenset.write(|w| w
+                .compare0().set()
+                .compare1().set()
+                .ovrflw().set());
+            rtc.evtenset.write(|w| w
+                .compare0().set()
+                .compare1().set()
+                .ovrflw().set());
+        });
+
+        // NOTE: the monotonic interrupt must run at the highest priority of any async task,
+        // RTIC exports that priority for exactly this purpose
+        extern "C" {
+            static RTIC_ASYNC_MAX_LOGICAL_PRIO : u8;
+        }
+        unsafe {
+            let max_prio = RTIC_ASYNC_MAX_LOGICAL_PRIO.clamp(1, 1 << pac::NVIC_PRIO_BITS);
+            let hw_prio = ((1 << pac::NVIC_PRIO_BITS) - max_prio) << (8 - pac::NVIC_PRIO_BITS);
+            let mut nvic : pac::NVIC = core::mem::transmute(());
+            nvic.set_priority(Interrupt::RTC0, hw_prio);
+            pac::NVIC::unmask(Interrupt::RTC0);
+        }
+    }
+
+    #[inline]
+    pub fn now() -> Instant {
+        <Self as Monotonic>::now()
+    }
+
+    /// Microseconds since `Mono::start`, used to timestamp log messages
+    pub fn now_us() -> u64 {
+        Self::now().duration_since_epoch().to_micros()
+    }
+
+    #[inline]
+    pub async fn delay_until(instant : Instant) {
+        TIMER_QUEUE.delay_until(instant).await;
+    }
+}
+
+impl Monotonic for Mono {
+    const ZERO : Self::Instant = Self::Instant::from_ticks(0);
+    const TICK_PERIOD : Self::Duration = Self::Duration::from_ticks(1);
+
+    type Instant = Instant;
+    type Duration = Duration;
+
+    fn now() -> Self::Instant {
+        let rtc = unsafe { &*RTC0::PTR };
+        Self::Instant::from_ticks(calculate_now(
+            || OVERFLOWS.load(Ordering::Relaxed),
+            || TimerValueU24(rtc.counter.read().bits())))
+    }
+
+    fn on_interrupt() {
+        let rtc = unsafe { &*RTC0::PTR };
+        if rtc.events_ovrflw.read().bits() == 1 {
+            rtc.events_ovrflw.write(|w| unsafe { w.bits(0) });
+            let prev = OVERFLOWS.fetch_add(1, Ordering::Relaxed);
+            assert!(prev % 2 == 1, "Monotonic must have skipped an interrupt!");
+        }
+        if rtc.events_compare[1].read().bits() == 1 {
+            rtc.events_compare[1].write(|w| unsafe { w.bits(0) });
+            let prev = OVERFLOWS.fetch_add(1, Ordering::Relaxed);
+            assert!(prev.is_multiple_of(2), "Monotonic must have skipped an interrupt!");
+        }
+    }
+
+    fn set_compare(mut instant : Self::Instant) {
+        let rtc = unsafe { &*RTC0::PTR };
+        cortex_m::interrupt::free(|_| {
+            let now = Self::now();
+            if let Some(diff) = instant.checked_duration_since(now) {
+                // NOTE: errata, compare events less than two ticks ahead may not fire
+                if diff.ticks() < 3 {
+                    instant = Self::Instant::from_ticks(now.ticks().wrapping_add(3));
+                }
+                rtc.cc[0].write(|w| unsafe { w.bits(instant.ticks() as u32 & 0xff_ffff) });
+            }
+        });
+    }
+
+    fn clear_compare_flag() {
+        let rtc = unsafe { &*RTC0::PTR };
+        rtc.events_compare[0].write(|w| unsafe { w.bits(0) });
+    }
+
+    fn pend_interrupt() {
+        pac::NVIC::pend(Interrupt::RTC0);
+    }
+}
+
+impl fun_core::Clock for Mono {
+    fn now_ms(&self) -> u64 {
+        Self::now().duration_since_epoch().to_millis()
+    }
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+unsafe extern "C" fn RTC0() {
+    TIMER_QUEUE.on_monotonic_interrupt();
+}
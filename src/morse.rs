@@ -0,0 +1,242 @@
+//! Morse trainer: the board plays a random character in Morse, the player keys it back on A.
+//!
+//! The character is blinked on the whole display with a tone, then A keys it back, a press
+//! shorter than `DASH_MS` is a dot and a longer one a dash, the symbols keyed so far show
+//! along the middle row. A pause of `LETTER_GAP_MS` ends the character, B clears what was
+//! keyed and plays the character again. A round is `ROUND` characters, the characters keyed
+//! right are added to the progress kept in the key-value store and the accuracy so far
+//! scrolls by.
+
+use core::cell::Cell;
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use fun_core::board::Beeper;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::font;
+use crate::kv;
+use crate::log;
+use crate::long_press;
+use crate::mono::{Instant, Mono};
+use crate::rng::Rng;
+use crate::scroll::{self, Text};
+use crate::speaker::Speaker;
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+];
+
+const CODES : [(char, &str); 36] = [
+    ('a', ".-"), ('b', "-..."), ('c', "-.-."), ('d', "-.."), ('e', "."), ('f', "..-."),
+    ('g', "--."), ('h', "...."), ('i', ".."), ('j', ".---"), ('k', "-.-"), ('l', ".-.."),
+    ('m', "--"), ('n', "-."), ('o', "---"), ('p', ".--."), ('q', "--.-"), ('r', ".-."),
+    ('s', "..."), ('t', "-"), ('u', "..-"), ('v', "...-"), ('w', ".--"), ('x', "-..-"),
+    ('y', "-.--"), ('z', "--.."), ('0', "-----"), ('1', ".----"), ('2', "..---"),
+    ('3', "...--"), ('4', "....-"), ('5', "....."), ('6', "-...."), ('7', "--..."),
+    ('8', "---.."), ('9', "----."),
+];
+const MAX_SYMBOLS : usize = 5;
+
+// NOTE: the dot of the played characters, a dash is three and the gaps between symbols one
+const UNIT_MS : u64 = 150;
+const DASH_MS : u64 = 2 * UNIT_MS;
+const LETTER_GAP_MS : u64 = 1200;
+const RESULT_MS : u64 = 1200;
+const TONE_HZ : u32 = 600;
+const ROUND : u32 = 10;
+const POLL_MS : u32 = 10;
+
+#[derive(Clone, Copy)]
+enum Phase {
+    Playing { at : Instant },
+    Keying { pressed : Option<Instant>, last : Option<Instant> },
+    Result { right : bool, at : Instant },
+    Done { at : Instant },
+}
+
+#[derive(Clone, Copy)]
+struct Trainer {
+    code   : usize,
+    keyed  : [u8; MAX_SYMBOLS],
+    len    : usize,
+    played : u32,
+    right  : u32,
+    phase  : Phase,
+}
+
+// NOTE: the button interrupt and idle both move the trainer on
+static TRAINER : Mutex<Cell<Option<Trainer>>> = Mutex::new(Cell::new(None));
+
+fn update(next : impl FnOnce(Option<Trainer>) -> Option<Trainer>) -> Option<Trainer> {
+    cortex_m::interrupt::free(|cs| {
+        let trainer = next(TRAINER.borrow(cs).get());
+        TRAINER.borrow(cs).set(trainer);
+        trainer
+    })
+}
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+fn symbols(code : usize) -> &'static [u8] {
+    CODES[code].1.as_bytes()
+}
+
+/// How long the character takes to play, with the gap after its last symbol.
+fn play_ms(code : usize) -> u64 {
+    symbols(code).iter().map(|symbol| if *symbol == b'-' { 4 * UNIT_MS } else { 2 * UNIT_MS }).sum()
+}
+
+/// True when the character is sounding `elapsed` ms into playing it.
+fn sounding(code : usize, elapsed : u64) -> bool {
+    let mut start = 0;
+    for symbol in symbols(code) {
+        let on = if *symbol == b'-' { 3 * UNIT_MS } else { UNIT_MS };
+        if elapsed < start + on {
+            return elapsed >= start;
+        }
+        start += on + UNIT_MS;
+    }
+    false
+}
+
+fn next_character(mut trainer : Trainer, now : Instant) -> Trainer {
+    trainer.code = Rng.below(CODES.len() as u32) as usize;
+    trainer.len = 0;
+    trainer.phase = Phase::Playing { at : now };
+    trainer
+}
+
+pub fn on_input(input : Input, now : Instant) -> bool {
+    let mut taken = false;
+    update(|trainer| {
+        let mut trainer = trainer?;
+        match (input, trainer.phase) {
+            (Input::Button(Button::A), Phase::Keying { pressed : None, last }) => {
+                trainer.phase = Phase::Keying { pressed : Some(now), last };
+                taken = true;
+            }
+            (Input::Button(Button::B), Phase::Keying { .. }) => {
+                trainer.len = 0;
+                trainer.phase = Phase::Playing { at : now };
+                taken = true;
+            }
+            _ => (),
+        }
+        Some(trainer)
+    });
+    taken
+}
+
+fn advance(mut trainer : Trainer, now : Instant) -> Trainer {
+    trainer.phase = match trainer.phase {
+        Phase::Playing { at } if ms_since(now, at) >= play_ms(trainer.code) =>
+            Phase::Keying { pressed : None, last : None },
+        // NOTE: the release is polled, the GPIOTE channels only see presses
+        Phase::Keying { pressed : Some(at), .. } if !long_press::held(Button::A) => {
+            let symbol = if ms_since(now, at) < DASH_MS { b'.' } else { b'-' };
+            if trainer.len < MAX_SYMBOLS {
+                trainer.keyed[trainer.len] = symbol;
+                trainer.len += 1;
+            }
+            Phase::Keying { pressed : None, last : Some(now) }
+        }
+        Phase::Keying { pressed : None, last : Some(last) } if ms_since(now, last) >= LETTER_GAP_MS => {
+            let right = &trainer.keyed[..trainer.len] == symbols(trainer.code);
+            trainer.played += 1;
+            trainer.right += right as u32;
+            Phase::Result { right, at : now }
+        }
+        Phase::Result { at, .. } if ms_since(now, at) >= RESULT_MS => {
+            if trainer.played < ROUND {
+                return next_character(trainer, now);
+            }
+            save_progress(trainer.right);
+            Phase::Done { at : now }
+        }
+        phase => phase,
+    };
+    trainer
+}
+
+/// Adds a round to the characters played and keyed right so far, kept as two u32.
+fn save_progress(right : u32) {
+    let (played, keyed_right) = progress();
+    let mut bytes = [0; 8];
+    bytes[..4].copy_from_slice(&(played + ROUND).to_le_bytes());
+    bytes[4..].copy_from_slice(&(keyed_right + right).to_le_bytes());
+    kv::set(kv::MORSE_PROGRESS, &bytes);
+    log!("morse round {} of {} right, {} of {} so far", right, ROUND, keyed_right + right, played + ROUND);
+}
+
+fn progress() -> (u32, u32) {
+    let mut bytes = [0; 8];
+    match kv::get(kv::MORSE_PROGRESS, &mut bytes) {
+        Some(8) => (
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+        ),
+        _ => (0, 0),
+    }
+}
+
+const RIGHT : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 9],
+    [0, 0, 0, 9, 0],
+    [9, 0, 9, 0, 0],
+    [0, 9, 0, 0, 0],
+];
+
+/// One round of characters, then the accuracy.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    let trainer = update(|trainer| Some(match trainer {
+        Some(trainer) if step > 0 => advance(trainer, now),
+        // NOTE: a new round, or the launcher cut the last one short
+        _ => next_character(Trainer { code : 0, keyed : [0; MAX_SYMBOLS], len : 0, played : 0, right : 0, phase : Phase::Done { at : now } }, now),
+    }))?;
+
+    let frame = match trainer.phase {
+        Phase::Playing { at } => {
+            if sounding(trainer.code, ms_since(now, at)) {
+                Speaker.tone(TONE_HZ);
+                [[9; 5]; 5]
+            } else {
+                Speaker.off();
+                [[0; 5]; 5]
+            }
+        }
+        Phase::Keying { pressed, .. } => {
+            // NOTE: a sidetone while A is down
+            Speaker.tone(if pressed.is_some() { TONE_HZ } else { 0 });
+            let mut leds = [[0; 5]; 5];
+            for (column, symbol) in trainer.keyed[..trainer.len].iter().enumerate() {
+                leds[2][column] = 9;
+                if *symbol == b'-' {
+                    leds[1][column] = 9;
+                    leds[3][column] = 9;
+                }
+            }
+            leds
+        }
+        // NOTE: the tick, or the character that was played
+        Phase::Result { right : true, .. } => RIGHT,
+        Phase::Result { right : false, .. } => font::frame_of(CODES[trainer.code].0).map(|row| row.map(|led| led * 9)),
+        Phase::Done { at } => {
+            let (played, right) = progress();
+            let mut text = Text::new();
+            let _ = write!(text, "{}/{} {}%", trainer.right, ROUND, right * 100 / played.max(1));
+            let column = ms_since(now, at) / scroll::STEP_MS as u64;
+            return scroll::frames(&text).nth(column as usize)
+                .map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS));
+        }
+    };
+    Some((frame, POLL_MS))
+}
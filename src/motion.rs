@@ -0,0 +1,38 @@
+//! The accelerometer of the LSM303AGR on the internal I2C bus, a `board::Motion` for
+//! `fun_core::motion`, which tells shakes and the tilt from its samples.
+
+use embedded_hal::blocking::delay::DelayUs;
+use fun_core::board;
+use lsm303agr::{interface::I2cInterface, mode::MagOneShot, AccelMode, AccelOutputDataRate, AccelScale, Lsm303agr};
+use microbit::board::I2CInternalPins;
+use microbit::hal::twim::{self, Twim};
+use microbit::pac::TWIM0;
+
+pub use fun_core::motion::POLL_MS;
+
+pub type Motion = fun_core::motion::Tracker<Accelerometer>;
+
+pub struct Accelerometer {
+    sensor : Lsm303agr<I2cInterface<Twim<TWIM0>>, MagOneShot>,
+}
+
+impl Accelerometer {
+    /// None when the accelerometer doesn't answer.
+    pub fn new(twim : TWIM0, pins : I2CInternalPins, delay : &mut impl DelayUs<u32>) -> Option<Self> {
+        let i2c = Twim::new(twim, pins.into(), twim::Frequency::K100);
+        let mut sensor = Lsm303agr::new_with_i2c(i2c);
+        sensor.init().ok()?;
+        sensor.set_accel_mode_and_odr(delay, AccelMode::Normal, AccelOutputDataRate::Hz50).ok()?;
+        // NOTE: the default 2 g range would clip every shake at 2 g
+        sensor.set_accel_scale(AccelScale::G4).ok()?;
+        Some(Accelerometer { sensor })
+    }
+}
+
+impl board::Motion for Accelerometer {
+    fn acceleration(&mut self) -> Option<(i32, i32, i32)> {
+        // NOTE: the chip sits on the back of the board, its x axis points left seen from
+        // the display
+        self.sensor.acceleration().ok().map(|a| a.xyz_mg()).map(|(x, y, z)| (-x, y, z))
+    }
+}
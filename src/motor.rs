@@ -0,0 +1,213 @@
+//! Motors: two DC motors through a dual H-bridge on P12 to P15, see `fun_core::drive`, the
+//! start of a simple robot.
+//!
+//! `set motor tilt|remote` hands P12 to P15 to the motors instead of the edge events, from the
+//! next reset on: P12 is the PWM and P13 the direction of the left motor, P14 and P15 those
+//! of the right one. The PWMs are made without the CPU like generator.rs, on RTC1, a period
+//! is `PERIOD_TICKS` of its 32768 Hz. The motors only run while an app driving them is on
+//! screen and `hold`s them, input_poll stops them within `ACTIVE_MS` of it leaving. The
+//! follow app drives them too, see follower.rs. In the motor app, with `tilt` the tilt of the
+//! board drives. With `remote` the buttons step the drive, so with remote control on the
+//! remote app of the peer does: A and B steer left and right, the logo speeds up, a long
+//! press of A slows down and then goes backwards, of B stops.
+
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicU8, Ordering};
+use cortex_m::interrupt::Mutex;
+use embedded_hal::digital::v2::OutputPin;
+use fun_core::drive::{self, Drive, STEP};
+use microbit::hal::gpio::{Disconnected, Level, Output, Pin, PushPull};
+use microbit::hal::gpiote::Gpiote;
+use microbit::pac::{GPIOTE, RTC1};
+use crate::apps::Input;
+use crate::channels::{self, Exhausted, GpioteChannel, PpiChannel};
+use crate::display::Frame;
+use crate::events::Button;
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::ppi;
+
+// NOTE: 128 Hz, the duty in steps of a 256th
+const PERIOD_TICKS : u32 = 256;
+const ACTIVE_MS : u64 = 500;
+const POLL_MS : u32 = 50;
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 0],
+    [1, 1, 1, 1, 1],
+    [1, 0, 1, 0, 1],
+    [1, 1, 1, 1, 1],
+    [0, 1, 0, 1, 0],
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Control {
+    Off,
+    Tilt,
+    Remote,
+}
+
+const CONTROLS : [Control; 3] = [Control::Off, Control::Tilt, Control::Remote];
+
+impl Control {
+    pub fn name(self) -> &'static str {
+        match self {
+            Control::Off    => "off",
+            Control::Tilt   => "tilt",
+            Control::Remote => "remote",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Control> {
+        CONTROLS.into_iter().find(|control| control.name() == name)
+    }
+
+    /// The control stored as `control as u8`.
+    pub fn from_u8(value : u8) -> Option<Control> {
+        CONTROLS.get(value as usize).copied()
+    }
+}
+
+struct Motors {
+    directions : [Pin<Output<PushPull>>; 2],
+    pwms       : [GpioteChannel; 2],
+    rises      : [PpiChannel; 2],
+    falls      : [PpiChannel; 2],
+}
+
+static CONTROL : AtomicU8 = AtomicU8::new(Control::Off as u8);
+// NOTE: None unless init handed them the pins
+static MOTORS : Mutex<RefCell<Option<Motors>>> = Mutex::new(RefCell::new(None));
+static DRIVE : Mutex<Cell<Drive>> = Mutex::new(Cell::new(Drive::STOP));
+// NOTE: when an app driving the motors last held them
+static HELD : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+fn rtc() -> &'static microbit::pac::rtc0::RegisterBlock {
+    unsafe { &*RTC1::ptr() }
+}
+
+fn control() -> Control {
+    Control::from_u8(CONTROL.load(Ordering::Relaxed)).unwrap_or(Control::Off)
+}
+
+pub fn wired() -> bool {
+    cortex_m::interrupt::free(|cs| MOTORS.borrow(cs).borrow().is_some())
+}
+
+/// Takes the PWM and the direction pin of the left motor and then of the right one.
+pub fn init(_rtc : RTC1, gpiote : &Gpiote, pins : [Pin<Disconnected>; 4]) -> Result<(), Exhausted> {
+    let wrap = channels::ppi("motor")?;
+    let pwms = [channels::gpiote("motor")?, channels::gpiote("motor")?];
+    let rises = [channels::ppi("motor")?, channels::ppi("motor")?];
+    let falls = [channels::ppi("motor")?, channels::ppi("motor")?];
+    let rtc = rtc();
+    rtc.tasks_stop.write(|w| unsafe { w.bits(1) });
+    rtc.prescaler.write(|w| unsafe { w.prescaler().bits(0) });
+    rtc.evtenset.write(|w| w.compare0().set().compare1().set().compare2().set());
+    // NOTE: the clear lands a tick after the compare
+    rtc.cc[0].write(|w| unsafe { w.bits(PERIOD_TICKS - 1) });
+    ppi::connect(wrap, &rtc.events_compare[0], &rtc.tasks_clear);
+    ppi::enable(wrap);
+
+    let [left, left_direction, right, right_direction] = pins;
+    for (i, pin) in [left, right].into_iter().enumerate() {
+        let channel = pwms[i].of(gpiote);
+        channel.output_pin(pin.into_push_pull_output(Level::Low)).init_low();
+        ppi::connect(rises[i], &rtc.events_compare[0], channel.task_set());
+        ppi::connect(falls[i], &rtc.events_compare[1 + i], channel.task_clr());
+    }
+    let directions = [left_direction.into_push_pull_output(Level::Low), right_direction.into_push_pull_output(Level::Low)];
+    rtc.tasks_start.write(|w| unsafe { w.bits(1) });
+
+    cortex_m::interrupt::free(|cs| MOTORS.borrow(cs).replace(Some(Motors { directions, pwms, rises, falls })));
+    Ok(())
+}
+
+pub fn configure(control : Control) {
+    CONTROL.store(control as u8, Ordering::Relaxed);
+    if (control != Control::Off) != wired() {
+        log!("motor pins change after a reset");
+    }
+}
+
+/// Drives the motors at the speeds of `drive`, nothing happens when they aren't wired.
+pub fn set(drive : Drive) {
+    let (left, right) = drive.speeds();
+    // NOTE: the gpiote resource belongs to the button interrupt, the PWM pins' tasks are all that is touched
+    let gpiote = unsafe { &*GPIOTE::ptr() };
+    let rtc = rtc();
+    cortex_m::interrupt::free(|cs| {
+        let mut motors = MOTORS.borrow(cs).borrow_mut();
+        let Some(motors) = motors.as_mut() else { return };
+        DRIVE.borrow(cs).set(drive);
+        for (i, speed) in [left, right].into_iter().enumerate() {
+            let (duty, backwards) = drive::bridge(speed);
+            let _ = if backwards { motors.directions[i].set_high() } else { motors.directions[i].set_low() };
+            let pwm = motors.pwms[i].index();
+            match PERIOD_TICKS * duty as u32 / 100 {
+                0 => {
+                    ppi::disable(motors.rises[i]);
+                    ppi::disable(motors.falls[i]);
+                    gpiote.tasks_clr[pwm].write(|w| unsafe { w.bits(1) });
+                }
+                PERIOD_TICKS => {
+                    ppi::disable(motors.rises[i]);
+                    ppi::disable(motors.falls[i]);
+                    gpiote.tasks_set[pwm].write(|w| unsafe { w.bits(1) });
+                }
+                high => {
+                    rtc.cc[1 + i].write(|w| unsafe { w.bits(high) });
+                    ppi::enable(motors.rises[i]);
+                    ppi::enable(motors.falls[i]);
+                }
+            }
+        }
+    });
+}
+
+pub fn driving() -> Drive {
+    cortex_m::interrupt::free(|cs| DRIVE.borrow(cs).get())
+}
+
+/// Keeps the motors going for `ACTIVE_MS` more, call it from the draw of an app driving them.
+pub fn hold(now : Instant) {
+    cortex_m::interrupt::free(|cs| HELD.borrow(cs).set(Some(now)));
+}
+
+fn active(now : Instant) -> bool {
+    cortex_m::interrupt::free(|cs| HELD.borrow(cs).get())
+        .is_some_and(|drawn| now.checked_duration_since(drawn).map_or(0, |elapsed| elapsed.to_millis()) < ACTIVE_MS)
+}
+
+/// Stops the motors when no app holds them any more, call it every input poll.
+pub fn watch(now : Instant) {
+    if driving() != Drive::STOP && !active(now) {
+        set(Drive::STOP);
+        log!("motors stopped, no app holds them");
+    }
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    if !wired() {
+        return false;
+    }
+    let drive = driving();
+    let next = match (control(), input) {
+        (Control::Tilt, Input::Tilt { x, y }) => Drive::from_tilt(x, y),
+        (Control::Remote, Input::Button(Button::A)) => drive.step(0, -STEP),
+        (Control::Remote, Input::Button(Button::B)) => drive.step(0, STEP),
+        (Control::Remote, Input::Button(Button::Logo)) => drive.step(STEP, 0),
+        (Control::Remote, Input::LongPress(Button::A)) => drive.step(-STEP, 0),
+        (Control::Remote, Input::LongPress(Button::B)) => Drive::STOP,
+        _ => return false,
+    };
+    set(next);
+    true
+}
+
+/// The speeds of both motors.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    hold(Mono::now());
+    let (left, right) = driving().speeds();
+    (step == 0).then_some((drive::gauge(left, right), POLL_MS))
+}
@@ -0,0 +1,121 @@
+//! Tilt mouse: the tilt of one board moves the cursor on the computer its peer is plugged
+//! into, A clicks the left button and B the right one, see `fun_core::mouse`.
+//!
+//! Every accelerometer poll adds what `Pointer` makes of the tilt to the movement not sent
+//! yet, radio_log sends it to the peer as a `Mouse` packet along with the clicks, a press and
+//! a release each. The peer's host link hands the packets to a program on the host moving the
+//! cursor, like the keys of presenter.rs. A shake turns the pointer off and on again. The
+//! icon is dim without a peer and with the pointer off.
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+use fun_core::mouse::{self, Pointer, Report};
+use heapless::Deque;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::log;
+use crate::logging::Level;
+use crate::mono::Instant;
+use crate::pairing;
+use crate::protocol::Packet;
+use crate::radio::Payload;
+use crate::transport::{self, Link};
+
+pub const ICON : Frame = [
+    [0, 1, 1, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 1, 1, 0],
+    [0, 1, 1, 1, 0],
+    [0, 0, 1, 0, 0],
+];
+
+const SENSITIVITY : u8 = 5;
+const QUEUE_LEN : usize = 8;
+const POLL_MS : u32 = 50;
+
+struct State {
+    pointer : Option<Pointer>,
+    // NOTE: the counts moved since the last report went out
+    moved   : (i32, i32),
+    // NOTE: the buttons of the reports to send, a click is a press then a release
+    buttons : Deque<u8, QUEUE_LEN>,
+}
+
+impl State {
+    fn pointer(&mut self) -> &mut Pointer {
+        self.pointer.get_or_insert_with(|| Pointer::new(SENSITIVITY))
+    }
+
+    fn click(&mut self, button : u8) {
+        if self.buttons.len() + 2 > QUEUE_LEN {
+            return;
+        }
+        let _ = self.buttons.push_back(button);
+        let _ = self.buttons.push_back(0);
+    }
+}
+
+// NOTE: filled from the button interrupt and input_poll, emptied by radio_log
+static STATE : Mutex<RefCell<State>> = Mutex::new(RefCell::new(State { pointer : None, moved : (0, 0), buttons : Deque::new() }));
+
+fn with<R>(f : impl FnOnce(&mut State) -> R) -> R {
+    cortex_m::interrupt::free(|cs| f(&mut STATE.borrow(cs).borrow_mut()))
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    // NOTE: nobody to send it to, the input is still taken so A and B don't run their actions
+    let peered = pairing::peer().is_some();
+    match input {
+        Input::Tilt { x, y } if peered => with(|state| {
+            if let Some(report) = state.pointer().report(x, y, 0) {
+                state.moved.0 += report.x as i32;
+                state.moved.1 += report.y as i32;
+            }
+        }),
+        Input::Button(Button::A) if peered => with(|state| state.click(mouse::LEFT)),
+        Input::Button(Button::B) if peered => with(|state| state.click(mouse::RIGHT)),
+        Input::Tilt { .. } | Input::Button(Button::A | Button::B) => (),
+        Input::Shake => {
+            let on = with(|state| {
+                state.moved = (0, 0);
+                state.pointer().toggle()
+            });
+            log!("mouse pointer {}", if on { "on" } else { "off" });
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// The next report to send to the peer, None with nothing to send.
+pub fn next_packet() -> Option<Payload> {
+    let report = with(|state| {
+        let buttons = state.buttons.pop_front();
+        if buttons.is_none() && state.moved == (0, 0) {
+            return None;
+        }
+        // NOTE: whatever doesn't fit a report goes with the next one
+        let (x, y) = (state.moved.0.clamp(-127, 127), state.moved.1.clamp(-127, 127));
+        state.moved = (state.moved.0 - x, state.moved.1 - y);
+        Some(Report { buttons : buttons.unwrap_or(0), x : x as i8, y : y as i8 })
+    })?;
+    Packet::Mouse(report.to_bytes()).encode()
+}
+
+/// Takes a packet from the peer, false when it isn't a mouse report. The host link already
+/// has it.
+pub fn on_packet(packet : &Packet) -> bool {
+    let Packet::Mouse(_) = packet else { return false };
+    if transport::link() == Link::Off {
+        log!(Level::Warn, "mouse report from the peer, but no host link to pass it on, set link uart");
+    }
+    true
+}
+
+/// The icon, dim without a peer or with the pointer off.
+pub fn draw(_step : usize) -> Option<(Frame, u32)> {
+    let on = with(|state| state.pointer().on());
+    let level = if pairing::peer().is_some() && on { 9 } else { 2 };
+    Some((ICON.map(|row| row.map(|led| led * level)), POLL_MS))
+}
@@ -0,0 +1,216 @@
+//! Experimental: a new firmware image over the radio, swapped in at the next boot.
+//!
+//! `ota <crc32>`, which always needs a tag (see auth.rs), starts an update. The board then
+//! broadcasts `Ota::Want` for the chunk it needs until that chunk comes, so a lost packet
+//! costs a retry and not the update, see image.rs in fun-core. The answers come from a host
+//! program through a link board (transport.rs), sealed with the class key like every
+//! packet. Chunks go to the upper half of the flash, `OTA_BANK`, as they come. Once `End`
+//! says there are no more and the CRC-32 matches, the trailer page records the image and
+//! the board resets. `init` finds the trailer, checks the image once more and copies it
+//! over the program, running from RAM, as the program it would run from is overwritten.
+//!
+//! NOTE: needs the `ota` feature, which links the program into 236K below the bank, see
+//! build.rs. A reset halfway through the copy starts it over at the next boot, the image
+//! and the trailer stay until it is done. The interface chip can still flash the board from
+//! the MICROBIT drive if everything else goes wrong.
+
+use core::arch::asm;
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::Mutex;
+use fun_core::image::{self, Chunk, Download, Taken, CHUNK_LEN};
+use crate::flash::{self, OTA_BANK, OTA_TRAILER_PAGE, PAGE_SIZE};
+use crate::log;
+use crate::logging::Level;
+use crate::protocol::{Ota, Packet};
+use crate::radio::Payload;
+
+// "ota", then the length and the CRC-32 of the image
+const TRAILER_MAGIC : u32 = 0x0061_746f;
+const CAPACITY : u32 = OTA_TRAILER_PAGE - OTA_BANK;
+// NOTE: the RAM of the nRF52833, where the initial stack pointer of an image has to be
+const RAM : core::ops::RangeInclusive<u32> = 0x2000_0000..=0x2002_0000;
+const PROGRESS_BYTES : u32 = 16 * 1024;
+
+/// How long to wait for a chunk before asking again, the link board only broadcasts what
+/// the host sends every 100 ms, see `radio_log`.
+pub const WAIT_MS : u64 = 300;
+// NOTE: a minute without an answer
+pub const MAX_MISSES : u32 = 200;
+
+static RUNNING : AtomicBool = AtomicBool::new(false);
+// NOTE: set by radio_received, taken by the ota_update task
+static ANSWER : Mutex<Cell<Option<Ota>>> = Mutex::new(Cell::new(None));
+
+pub fn active() -> bool {
+    RUNNING.load(Ordering::Relaxed)
+}
+
+/// Takes a packet, false when it isn't an update packet. Another board's `Want` is
+/// taken too, and dropped.
+pub fn on_packet(packet : &Packet) -> bool {
+    let Packet::Ota(answer) = *packet else { return false };
+    if active() && !matches!(answer, Ota::Want { .. }) {
+        cortex_m::interrupt::free(|cs| ANSWER.borrow(cs).set(Some(answer)));
+    }
+    true
+}
+
+/// The answer that came in since the last call.
+pub fn answer() -> Option<Ota> {
+    cortex_m::interrupt::free(|cs| ANSWER.borrow(cs).take())
+}
+
+pub enum Step {
+    More,
+    /// The image is complete and checked, it is swapped in at the next boot.
+    Staged,
+    Failed(&'static str),
+}
+
+pub struct Update {
+    download : Download,
+    crc      : u32,
+    // NOTE: the bank is erased a page ahead of the chunks, up to here
+    erased   : u32,
+}
+
+impl Update {
+    /// None while another update runs.
+    pub fn start(crc : u32) -> Option<Update> {
+        if RUNNING.swap(true, Ordering::Relaxed) {
+            return None;
+        }
+        cortex_m::interrupt::free(|cs| ANSWER.borrow(cs).set(None));
+        Some(Update { download : Download::new(crc, CAPACITY), crc, erased : 0 })
+    }
+
+    pub fn want(&self) -> Option<Payload> {
+        Packet::Ota(Ota::Want { offset : self.download.offset() }).encode()
+    }
+
+    pub fn take(&mut self, answer : Ota) -> Step {
+        match answer {
+            Ota::Chunk { offset, crc, data } => match self.download.take(offset, crc, &data) {
+                Taken::Next => {
+                    self.write(offset, &data);
+                    Step::More
+                }
+                Taken::Ignored => Step::More,
+                Taken::TooLong => Step::Failed("image too long"),
+            },
+            Ota::End if !self.download.complete() => Step::Failed("image CRC doesn't match"),
+            Ota::End if !plausible(self.download.offset()) => Step::Failed("not a firmware image"),
+            Ota::End => {
+                flash::erase_page(OTA_TRAILER_PAGE);
+                flash::write_words(OTA_TRAILER_PAGE, &[TRAILER_MAGIC, self.download.offset(), self.crc]);
+                Step::Staged
+            }
+            Ota::Want { .. } => Step::More,
+        }
+    }
+
+    fn write(&mut self, offset : u32, data : &Chunk) {
+        while self.erased < offset + CHUNK_LEN as u32 {
+            if self.erased > 0 && self.erased % PROGRESS_BYTES == 0 {
+                log!("ota: {} K received", self.erased / 1024);
+            }
+            flash::erase_page(OTA_BANK + self.erased);
+            self.erased += PAGE_SIZE;
+        }
+        let mut words = [0; CHUNK_LEN / 4];
+        for (word, bytes) in words.iter_mut().zip(data.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        flash::write_words(OTA_BANK + offset, &words);
+    }
+}
+
+impl Drop for Update {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::Relaxed);
+    }
+}
+
+/// True when the `len` bytes in the bank start with a vector table that could be ours.
+fn plausible(len : u32) -> bool {
+    let stack = flash::read_word(OTA_BANK);
+    let reset = flash::read_word(OTA_BANK + 4);
+    RAM.contains(&stack) && reset & 1 == 1 && reset < len
+}
+
+/// Swaps in an image staged before the reset, or drops a trailer whose image doesn't check.
+pub fn init() {
+    let word = |i : u32| flash::read_word(OTA_TRAILER_PAGE + 4 * i);
+    if word(0) != TRAILER_MAGIC {
+        return;
+    }
+    let (len, crc) = (word(1), word(2));
+    if len <= CAPACITY && len % 4 == 0 && plausible(len) {
+        let staged = unsafe { core::slice::from_raw_parts(OTA_BANK as *const u8, len as usize) };
+        if image::crc32(0, staged) == crc {
+            unsafe { swap(len) }
+        }
+    }
+    flash::erase_page(OTA_TRAILER_PAGE);
+    log!(Level::Warn, "ota: the staged image doesn't check, dropped");
+}
+
+// NOTE: the NVMC registers, CONFIG takes 0 to read, 1 to write and 2 to erase
+const NVMC_READY     : u32 = 0x4001_e400;
+const NVMC_CONFIG    : u32 = 0x4001_e504;
+const NVMC_ERASEPAGE : u32 = 0x4001_e508;
+const SCB_AIRCR      : u32 = 0xe000_ed0c;
+const SYSRESETREQ    : u32 = 0x05fa_0004;
+
+macro_rules! load {
+    ($address:expr) => {{
+        let word : u32;
+        asm!("ldr {0}, [{1}]", out(reg) word, in(reg) $address, options(nostack, readonly, preserves_flags));
+        word
+    }};
+}
+
+macro_rules! store {
+    ($address:expr, $word:expr) => {
+        asm!("str {0}, [{1}]", in(reg) $word, in(reg) $address, options(nostack, preserves_flags))
+    };
+}
+
+macro_rules! wait_ready {
+    () => {
+        while load!(NVMC_READY) & 1 == 0 {}
+    };
+}
+
+/// Copies the `len` bytes of the bank over the program, drops the trailer and resets.
+///
+/// NOTE: runs from RAM, cortex-m-rt copies .data there. A call out of it would land in the
+/// program being erased, so the flash is written without flash.rs, and with asm! rather than
+/// the volatile pointer functions, whose debug checks are calls too.
+#[inline(never)]
+#[link_section = ".data.ota_swap"]
+unsafe fn swap(len : u32) -> ! {
+    asm!("cpsid i", options(nomem, nostack, preserves_flags));
+    let mut page = 0u32;
+    while page < len {
+        store!(NVMC_CONFIG, 2u32);
+        store!(NVMC_ERASEPAGE, page);
+        wait_ready!();
+        page = page.wrapping_add(PAGE_SIZE);
+    }
+    store!(NVMC_CONFIG, 1u32);
+    let mut offset = 0u32;
+    while offset < len {
+        store!(offset, load!(OTA_BANK.wrapping_add(offset)));
+        wait_ready!();
+        offset = offset.wrapping_add(4);
+    }
+    store!(NVMC_CONFIG, 2u32);
+    store!(NVMC_ERASEPAGE, OTA_TRAILER_PAGE);
+    wait_ready!();
+    store!(NVMC_CONFIG, 0u32);
+    asm!("dsb", options(nostack, preserves_flags));
+    store!(SCB_AIRCR, SYSRESETREQ);
+    loop {}
+}
@@ -0,0 +1,261 @@
+//! Pairing two boards, so their radio games only talk to each other.
+//!
+//! Holding A in the pair app on both boards makes them search: each broadcasts a request
+//! with its device id, a searching board that hears one answers it with its own, and both
+//! take the other as their peer once they heard from it. A tick confirms it, a cross says
+//! nobody answered within `SEARCH_MS`. B forgets the peer.
+//!
+//! The peer is stored in the key-value store. While there is one the game packets go out
+//! wrapped by `wrap`, addressed to the peer, and `heard` tells them apart from the ones for
+//! every board: a packet addressed to another board is dropped, and so is a game packet
+//! broadcast by any board but the peer, see `radio_received` in main.rs.
+
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::kv;
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::protocol::{self, Packet, Pair};
+use crate::radio::Payload;
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 0],
+    [1, 1, 0, 1, 1],
+    [1, 0, 1, 0, 1],
+    [1, 1, 0, 1, 1],
+    [0, 0, 0, 0, 0],
+];
+
+const RESEND_MS : u64 = 300;
+const SEARCH_MS : u64 = 10_000;
+// NOTE: the answer keeps going out meanwhile, the other board may have missed the first
+const CONFIRM_MS : u64 = 2000;
+const ACTIVE_MS : u64 = 500;
+const POLL_MS : u32 = 20;
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Peer {
+    pub device_id : u64,
+    pub address   : u16,
+}
+
+impl Peer {
+    const LEN : usize = 10;
+
+    fn encode(&self) -> [u8; Peer::LEN] {
+        let mut bytes = [0; Peer::LEN];
+        bytes[..8].copy_from_slice(&self.device_id.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.address.to_le_bytes());
+        bytes
+    }
+
+    fn decode(bytes : &[u8]) -> Option<Peer> {
+        let bytes : [u8; Peer::LEN] = bytes.try_into().ok()?;
+        Some(Peer {
+            device_id : u64::from_le_bytes(bytes[..8].try_into().unwrap()),
+            address   : u16::from_le_bytes([bytes[8], bytes[9]]),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Phase {
+    Idle,
+    Searching { at : Instant },
+    Paired { at : Instant },
+    Failed { at : Instant },
+}
+
+#[derive(Clone, Copy)]
+struct Pairing {
+    phase : Phase,
+    sent  : Option<Instant>,
+    drawn : Instant,
+}
+
+static PEER : Mutex<Cell<Option<Peer>>> = Mutex::new(Cell::new(None));
+// NOTE: the button interrupt, the radio tasks and idle all move the pairing on
+static PAIRING : Mutex<Cell<Option<Pairing>>> = Mutex::new(Cell::new(None));
+
+fn update(next : impl FnOnce(Option<Pairing>) -> Option<Pairing>) -> Option<Pairing> {
+    cortex_m::interrupt::free(|cs| {
+        let pairing = next(PAIRING.borrow(cs).get());
+        PAIRING.borrow(cs).set(pairing);
+        pairing
+    })
+}
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+/// Loads the stored peer.
+pub fn init() {
+    let mut bytes = [0; Peer::LEN];
+    let peer = kv::get(kv::PEER, &mut bytes).and_then(|len| Peer::decode(&bytes[..len]));
+    cortex_m::interrupt::free(|cs| PEER.borrow(cs).set(peer));
+}
+
+pub fn peer() -> Option<Peer> {
+    cortex_m::interrupt::free(|cs| PEER.borrow(cs).get())
+}
+
+// NOTE: an empty value reads as no peer, the store can't delete a key
+fn store(peer : Option<Peer>) {
+    cortex_m::interrupt::free(|cs| PEER.borrow(cs).set(peer));
+    match peer {
+        Some(peer) => kv::set(kv::PEER, &peer.encode()),
+        None       => kv::set(kv::PEER, &[]),
+    }
+}
+
+/// `packet` addressed to the peer, unchanged while there is none. None when it gets too
+/// long for the unicast header.
+pub fn wrap(packet : Payload) -> Option<Payload> {
+    match peer() {
+        Some(peer) => protocol::address(peer.address, &packet),
+        None => Some(packet),
+    }
+}
+
+/// The packet in an opened payload from `sender`, and whether the peer sent it to `own`.
+/// None for a packet addressed to another board, or sent by a board that isn't the peer.
+pub fn heard(sender : u16, own : u16, payload : &[u8]) -> Option<(bool, &[u8])> {
+    match Packet::split(payload) {
+        Some((Packet::Unicast { to }, packet)) => {
+            let from_peer = peer().is_some_and(|peer| peer.address == sender);
+            (to == own && from_peer).then_some((true, packet))
+        }
+        _ => Some((false, payload)),
+    }
+}
+
+pub fn on_input(input : Input, now : Instant) -> bool {
+    let mut taken = false;
+    update(|pairing| {
+        let mut pairing = pairing?;
+        match (input, pairing.phase) {
+            (Input::LongPress(Button::A), Phase::Idle | Phase::Failed { .. }) => {
+                log!("pairing: searching");
+                pairing.phase = Phase::Searching { at : now };
+                pairing.sent = None;
+                taken = true;
+            }
+            (Input::Button(Button::B), Phase::Idle) => {
+                if peer().is_some() {
+                    log!("pairing: peer forgotten");
+                    store(None);
+                }
+                taken = true;
+            }
+            _ => (),
+        }
+        Some(pairing)
+    });
+    taken
+}
+
+/// True while the pair app is on screen, the radio has to listen for the other board then.
+pub fn active(now : Instant) -> bool {
+    update(|pairing| pairing).is_some_and(|pairing| ms_since(now, pairing.drawn) < ACTIVE_MS)
+}
+
+/// The packet to broadcast now, if any, with the device id of this board.
+pub fn next_packet(now : Instant, device_id : u64) -> Option<Payload> {
+    let mut packet = None;
+    update(|pairing| {
+        let mut pairing = pairing?;
+        if pairing.sent.is_some_and(|sent| ms_since(now, sent) < RESEND_MS) {
+            return Some(pairing);
+        }
+        packet = match pairing.phase {
+            Phase::Searching { .. } => Some(Pair::Request { device_id }),
+            Phase::Paired { at } if ms_since(now, at) < CONFIRM_MS => Some(Pair::Answer { device_id }),
+            _ => None,
+        };
+        if packet.is_some() {
+            pairing.sent = Some(now);
+        }
+        Some(pairing)
+    });
+    Packet::Pair(packet?).encode()
+}
+
+/// Takes a packet from `sender`, false when it isn't a pairing packet.
+pub fn on_packet(sender : u16, packet : &Packet, now : Instant) -> bool {
+    let Packet::Pair(Pair::Request { device_id } | Pair::Answer { device_id }) = *packet else {
+        return false;
+    };
+    let mut paired = false;
+    update(|pairing| {
+        let mut pairing = pairing?;
+        if let Phase::Searching { .. } = pairing.phase {
+            pairing.phase = Phase::Paired { at : now };
+            // NOTE: answered right away, a request heard means the other board is searching too
+            pairing.sent = None;
+            paired = true;
+        }
+        Some(pairing)
+    });
+    if paired {
+        log!("pairing: paired with {:04x}", sender);
+        store(Some(Peer { device_id, address : sender }));
+    }
+    true
+}
+
+const TICK : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 1],
+    [0, 0, 0, 1, 0],
+    [1, 0, 1, 0, 0],
+    [0, 1, 0, 0, 0],
+];
+
+const CROSS : Frame = [
+    [1, 0, 0, 0, 1],
+    [0, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+fn lit(frame : Frame, level : u8) -> Frame {
+    frame.map(|row| row.map(|led| led * level))
+}
+
+/// The link icon, bright while paired, then the search and its outcome.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    let pairing = update(|pairing| {
+        let mut pairing = match pairing {
+            Some(pairing) if step > 0 => pairing,
+            // NOTE: the app started over, or the launcher cut a search short
+            _ => Pairing { phase : Phase::Idle, sent : None, drawn : now },
+        };
+        pairing.drawn = now;
+        match pairing.phase {
+            Phase::Searching { at } if ms_since(now, at) > SEARCH_MS => {
+                log!("pairing: nobody answered");
+                pairing.phase = Phase::Failed { at : now };
+            }
+            Phase::Paired { at } | Phase::Failed { at } if ms_since(now, at) > CONFIRM_MS => {
+                pairing.phase = Phase::Idle;
+            }
+            _ => (),
+        }
+        Some(pairing)
+    })?;
+
+    let frame = match pairing.phase {
+        Phase::Idle => lit(ICON, if peer().is_some() { 9 } else { 2 }),
+        // NOTE: the icon pulses while searching
+        Phase::Searching { at } => lit(ICON, if (ms_since(now, at) / 300).is_multiple_of(2) { 2 } else { 6 }),
+        Phase::Paired { .. } => lit(TICK, 9),
+        Phase::Failed { .. } => lit(CROSS, 9),
+    };
+    Some((frame, POLL_MS))
+}
@@ -0,0 +1,117 @@
+//! The idle app, a playlist of the idle animations in `ANIMATIONS`.
+//!
+//! Every animation plays in cycles, after each one the playlist moves on once the current
+//! animation has had its `playlist` seconds, to the next one in order or, with `shuffle`, to
+//! another one picked at random. Both are settings, `configure` applies them. A playlist
+//! time of 0 stays on the current animation, A and B step through them by hand.
+
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use crate::apps::{self, Input};
+use crate::display::Frame;
+use crate::effects::{self, Preset};
+use crate::events::Button;
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::rng::Rng;
+use crate::storage::Settings;
+
+pub const DEFAULT_SECS : u8 = 20;
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 1, 1, 0],
+    [0, 0, 0, 0, 0],
+];
+
+struct Animation {
+    name : &'static str,
+    draw : fn(step : usize) -> Option<(Frame, u32)>,
+}
+
+const ANIMATIONS : [Animation; 5] = [
+    Animation { name : "circle", draw : apps::circle },
+    Animation { name : "fireworks", draw : fireworks },
+    Animation { name : "pulse", draw : pulse },
+    Animation { name : "rain", draw : rain },
+    Animation { name : "sparkle", draw : apps::sparkle },
+];
+
+static SECS : AtomicU8 = AtomicU8::new(DEFAULT_SECS);
+static SHUFFLE : AtomicBool = AtomicBool::new(false);
+static CURRENT : AtomicUsize = AtomicUsize::new(0);
+// NOTE: in ms since boot, when the current animation started
+static STARTED : AtomicUsize = AtomicUsize::new(0);
+
+pub fn configure(settings : &Settings) {
+    SECS.store(settings.playlist_secs, Ordering::Relaxed);
+    SHUFFLE.store(settings.shuffle, Ordering::Relaxed);
+}
+
+fn now_ms() -> usize {
+    Mono::now().duration_since_epoch().to_millis() as usize
+}
+
+fn switch(next : usize) {
+    CURRENT.store(next, Ordering::Relaxed);
+    STARTED.store(now_ms(), Ordering::Relaxed);
+    log!("idle animation {}", ANIMATIONS[next].name);
+}
+
+fn next() -> usize {
+    let current = CURRENT.load(Ordering::Relaxed);
+    if SHUFFLE.load(Ordering::Relaxed) {
+        // NOTE: any one but the current, so a shuffle always changes something
+        (current + 1 + Rng.below(ANIMATIONS.len() as u32 - 1) as usize) % ANIMATIONS.len()
+    } else {
+        (current + 1) % ANIMATIONS.len()
+    }
+}
+
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    if step == 0 {
+        let secs = SECS.load(Ordering::Relaxed) as usize;
+        if secs > 0 && now_ms().wrapping_sub(STARTED.load(Ordering::Relaxed)) >= secs * 1000 {
+            switch(next());
+        }
+    }
+    (ANIMATIONS[CURRENT.load(Ordering::Relaxed)].draw)(step)
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let current = CURRENT.load(Ordering::Relaxed);
+    match input {
+        Input::Button(Button::A) => switch((current + ANIMATIONS.len() - 1) % ANIMATIONS.len()),
+        Input::Button(Button::B) => switch((current + 1) % ANIMATIONS.len()),
+        _ => return false,
+    }
+    true
+}
+
+const PULSE_LEVELS : [u8; 14] = [0, 1, 2, 3, 5, 7, 9, 9, 7, 5, 3, 2, 1, 0];
+
+/// The whole display breathing in and out.
+fn pulse(step : usize) -> Option<(Frame, u32)> {
+    let level = *PULSE_LEVELS.get(step)?;
+    // NOTE: the middle leads, the edges follow a level behind
+    let mut leds = [[level.saturating_sub(1); 5]; 5];
+    for row in &mut leds[1..4] {
+        for led in &mut row[1..4] {
+            *led = level;
+        }
+    }
+    Some((leds, 80))
+}
+
+const EFFECT_STEPS : usize = 24;
+
+/// Drops falling down the columns at random.
+fn rain(step : usize) -> Option<(Frame, u32)> {
+    (step < EFFECT_STEPS).then(|| (effects::next(Preset::Rain), effects::STEP_MS))
+}
+
+/// A firework going off at random places, one after the other.
+fn fireworks(step : usize) -> Option<(Frame, u32)> {
+    (step < EFFECT_STEPS).then(|| (effects::next(Preset::Firework), effects::STEP_MS))
+}
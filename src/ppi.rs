@@ -0,0 +1,36 @@
+//! Programmable peripheral interconnect: wires an event of one peripheral straight to a task
+//! of another, so the pair keeps running without the CPU.
+//!
+//! NOTE: free functions poking the PPI registers, like flash.rs, the board struct doesn't hand
+//! out the PPI. Every feature wires the channel it claimed, see channels.rs, so they can't
+//! wire over each other.
+
+use microbit::pac::PPI;
+use crate::channels::PpiChannel;
+
+fn ppi() -> &'static microbit::pac::ppi::RegisterBlock {
+    unsafe { &*PPI::ptr() }
+}
+
+/// Sets up `channel` to trigger `task` on every `event`, both are pac registers like
+/// `&timer.events_compare[0]` or `gpiote_channel.task_out()`. The channel starts disabled.
+pub fn connect<E, T>(channel : PpiChannel, event : &E, task : &T) {
+    disable(channel);
+    let ch = &ppi().ch[channel.index()];
+    ch.eep.write(|w| unsafe { w.bits(event as *const E as u32) });
+    ch.tep.write(|w| unsafe { w.bits(task as *const T as u32) });
+    ppi().fork[channel.index()].tep.reset();
+}
+
+/// Adds a second task to the event of `channel`.
+pub fn fork<T>(channel : PpiChannel, task : &T) {
+    ppi().fork[channel.index()].tep.write(|w| unsafe { w.bits(task as *const T as u32) });
+}
+
+pub fn enable(channel : PpiChannel) {
+    ppi().chenset.write(|w| unsafe { w.bits(1 << channel.index()) });
+}
+
+pub fn disable(channel : PpiChannel) {
+    ppi().chenclr.write(|w| unsafe { w.bits(1 << channel.index()) });
+}
@@ -0,0 +1,98 @@
+//! Presentation remote: A and B of one board turn the slides on the computer its peer is
+//! plugged into, see pairing.rs and transport.rs.
+//!
+//! The presenter app sends the keyboard report of `fun_core::keyboard::slide` to the peer for
+//! every press, A for the next slide and B for the one before, the icon blinks for each one.
+//! The peer, with `set link uart` or `rtt`, hands the packet to its host in a frame like every
+//! packet it hears, the bytes after the sender, the signal strength and the variant index are
+//! the report, for a program there to press and release the keys. A board with a peer and a
+//! host link keeps its receiver on for them. The pairing is stored, so the remote and its peer
+//! find each other again after a reset.
+//!
+//! NOTE: the request was HID over GATT, the computer pairing with the board itself. There is
+//! no BLE stack for it, see `fun_core::keyboard`, so the peer and its host link stand in for
+//! the Bluetooth link.
+
+use core::cell::{Cell, RefCell};
+use cortex_m::interrupt::Mutex;
+use fun_core::keyboard::{self, REPORT_LEN};
+use heapless::Deque;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::log;
+use crate::logging::Level;
+use crate::mono::{Instant, Mono};
+use crate::pairing;
+use crate::protocol::Packet;
+use crate::radio::Payload;
+use crate::transport::{self, Link};
+
+pub const ICON : Frame = [
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+];
+
+const QUEUE_LEN : usize = 8;
+const BLINK_MS : u64 = 150;
+const POLL_MS : u32 = 20;
+
+// NOTE: filled from the button interrupt, emptied by radio_log
+static SENDING : Mutex<RefCell<Deque<[u8; REPORT_LEN], QUEUE_LEN>>> = Mutex::new(RefCell::new(Deque::new()));
+static PRESSED : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+pub fn on_input(input : Input, now : Instant) -> bool {
+    let next = match input {
+        Input::Button(Button::A) => true,
+        Input::Button(Button::B) => false,
+        _ => return false,
+    };
+    // NOTE: nobody to send it to, the press is still taken so A and B don't run their actions
+    if pairing::peer().is_none() {
+        return true;
+    }
+    cortex_m::interrupt::free(|cs| {
+        let mut sending = SENDING.borrow(cs).borrow_mut();
+        if sending.is_full() {
+            sending.pop_front();
+        }
+        let _ = sending.push_back(keyboard::slide(next).to_bytes());
+        PRESSED.borrow(cs).set(Some(now));
+    });
+    true
+}
+
+/// True on a board with a peer and a host link, the radio has to listen for the slides then.
+pub fn listening() -> bool {
+    transport::link() != Link::Off && pairing::peer().is_some()
+}
+
+/// The next report to send to the peer.
+pub fn next_packet() -> Option<Payload> {
+    let report = cortex_m::interrupt::free(|cs| SENDING.borrow(cs).borrow_mut().pop_front())?;
+    Packet::Hid(report).encode()
+}
+
+/// Takes a packet from the peer, false when it isn't a report. The host link already has it.
+pub fn on_packet(packet : &Packet) -> bool {
+    let Packet::Hid(_) = packet else { return false };
+    if transport::link() == Link::Off {
+        log!(Level::Warn, "slide key from the peer, but no host link to pass it on, set link uart");
+    }
+    true
+}
+
+/// The icon, dim without a peer and blinking off for every slide sent.
+pub fn draw(_step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    let pressed = cortex_m::interrupt::free(|cs| PRESSED.borrow(cs).get());
+    let level = match pressed {
+        _ if pairing::peer().is_none() => 2,
+        Some(at) if now.checked_duration_since(at).is_some_and(|elapsed| elapsed.to_millis() < BLINK_MS) => 0,
+        _ => 9,
+    };
+    Some((ICON.map(|row| row.map(|led| led * level)), POLL_MS))
+}
@@ -0,0 +1,116 @@
+//! Proximity alert: boards that keep their distance, for a game of keeping apart or a
+//! lesson on spreading infections, see `fun_core::proximity`.
+//!
+//! With `set proximity on` a board broadcasts a presence beacon every `PERIOD_MS` from
+//! radio_log and keeps its receiver on, whatever app runs. Every beacon heard goes into the
+//! tracker with its signal strength. A board that stays too close for too long shows `ICON`
+//! as a toast for `ALERT_SECS`, beeps `BEEPS` times, held back in the quiet hours of dnd.rs,
+//! and logs the board and its strength.
+//!
+//! NOTE: the strength is a rough distance, a hand over the antenna or a wall between the
+//! boards makes them seem further apart than they are.
+
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::Mutex;
+use fun_core::board::Beeper;
+use fun_core::proximity::Tracker;
+use crate::display::Frame;
+use crate::dnd;
+use crate::log;
+use crate::logging::Level;
+use crate::mono::Instant;
+use crate::protocol::Packet;
+use crate::radio::Payload;
+use crate::speaker::Speaker;
+use crate::toast;
+
+const BOARDS : usize = 8;
+const PERIOD_MS : u64 = 500;
+const ALERT_SECS : u32 = 2;
+const ALERT_HZ : u32 = 2093;
+const BEEPS : u64 = 3;
+const BEEP_MS : u64 = 150;
+
+pub const ICON : Frame = [
+    [1, 0, 0, 0, 1],
+    [0, 1, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+static ON : AtomicBool = AtomicBool::new(false);
+static TRACKER : Mutex<RefCell<Tracker<BOARDS>>> = Mutex::new(RefCell::new(Tracker::new()));
+static SENT : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+// NOTE: when the beeps of the last alert started, until they are over
+static ALERTED : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+pub fn configure(on : bool) {
+    ON.store(on, Ordering::Relaxed);
+}
+
+/// True while the alert is on, the radio has to listen for the other boards then.
+pub fn active() -> bool {
+    ON.load(Ordering::Relaxed)
+}
+
+/// The presence beacon, every `PERIOD_MS` while the alert is on.
+pub fn next_packet(now : Instant) -> Option<Payload> {
+    if !active() {
+        return None;
+    }
+    let due = cortex_m::interrupt::free(|cs| {
+        let sent = SENT.borrow(cs);
+        let due = sent.get().is_none_or(|at| ms_since(now, at) >= PERIOD_MS);
+        if due {
+            sent.set(Some(now));
+        }
+        due
+    });
+    if !due {
+        return None;
+    }
+    Packet::Presence.encode()
+}
+
+/// Takes a packet from the board at `sender` that came in at `rssi` dBm, false when it isn't
+/// a presence beacon.
+pub fn on_packet(sender : u16, packet : &Packet, rssi : i8, now : Instant) -> bool {
+    let Packet::Presence = packet else { return false };
+    if active() {
+        let now_ms = now.duration_since_epoch().to_millis();
+        cortex_m::interrupt::free(|cs| TRACKER.borrow(cs).borrow_mut().heard(sender, rssi, now_ms));
+    }
+    true
+}
+
+/// Raises the alert for a board too close for too long and plays its beeps, call it every
+/// input poll.
+pub fn watch(now : Instant) {
+    if !active() {
+        return;
+    }
+    let now_ms = now.duration_since_epoch().to_millis();
+    if let Some((address, dbm)) = cortex_m::interrupt::free(|cs| TRACKER.borrow(cs).borrow_mut().alert(now_ms)) {
+        log!(Level::Warn, "board {:04x} too close for too long, {} dBm", address, dbm);
+        toast::icon(ICON, ALERT_SECS);
+        if dnd::sound(ALERT_HZ, now) {
+            cortex_m::interrupt::free(|cs| ALERTED.borrow(cs).set(Some(now)));
+        }
+    }
+    let Some(alerted) = cortex_m::interrupt::free(|cs| ALERTED.borrow(cs).get()) else { return };
+    let elapsed = ms_since(now, alerted);
+    if elapsed >= BEEPS * 2 * BEEP_MS {
+        Speaker.off();
+        cortex_m::interrupt::free(|cs| ALERTED.borrow(cs).set(None));
+    } else if (elapsed / BEEP_MS).is_multiple_of(2) {
+        Speaker.tone(ALERT_HZ);
+    } else {
+        Speaker.off();
+    }
+}
@@ -0,0 +1,119 @@
+//! Pulse width and frequency of a digital signal on an edge pin, timed by hardware.
+//!
+//! Every edge on the pin (a GPIOTE channel, both directions) is wired over PPI to a capture
+//! of the free running TIMER2 at 16 MHz and, as the fork, a count on TIMER3. `edges` reads the
+//! count, so counting over a gate time gives the frequency up to several hundred kHz.
+//! `measure` waits for three edges and takes their capture times, the CPU only has to keep up
+//! with reading them: when the edge count moved by more than one since the last read a
+//! capture was overwritten and the measurement is dropped.
+//!
+//! The `pulse` command measures once, the freq app over and over while it is on screen, see
+//! frequency.rs.
+
+use embedded_hal::digital::v2::InputPin;
+use microbit::hal::gpio::{Pin, Input, Floating};
+use microbit::hal::gpiote::Gpiote;
+use microbit::pac::{GPIOTE, TIMER2, TIMER3};
+use crate::channels::{self, Exhausted, GpioteChannel};
+use crate::ppi;
+
+pub const TICKS_PER_US : u32 = 16;
+
+#[derive(Clone, Copy)]
+pub struct Pulse {
+    pub high_ticks : u32,
+    pub low_ticks  : u32,
+}
+
+impl Pulse {
+    pub fn period_ticks(&self) -> u32 {
+        self.high_ticks + self.low_ticks
+    }
+
+    pub fn frequency_hz(&self) -> u32 {
+        TICKS_PER_US * 1_000_000 / self.period_ticks().max(1)
+    }
+
+    pub fn duty_percent(&self) -> u32 {
+        (self.high_ticks as u64 * 100 / self.period_ticks().max(1) as u64) as u32
+    }
+}
+
+pub struct PulseMeter {
+    timer   : TIMER2,
+    counter : TIMER3,
+    pin     : Pin<Input<Floating>>,
+    edge    : GpioteChannel,
+}
+
+impl PulseMeter {
+    pub fn new(timer : TIMER2, counter : TIMER3, gpiote : &Gpiote, pin : Pin<Input<Floating>>) -> Result<Self, Exhausted> {
+        let (edge, wiring) = (channels::gpiote("pulse_meter")?, channels::ppi("pulse_meter")?);
+        timer.mode.write(|w| w.mode().timer());
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.prescaler.write(|w| unsafe { w.prescaler().bits(0) });
+        timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        timer.tasks_start.write(|w| unsafe { w.bits(1) });
+
+        counter.mode.write(|w| w.mode().low_power_counter());
+        counter.bitmode.write(|w| w.bitmode()._32bit());
+        counter.tasks_clear.write(|w| unsafe { w.bits(1) });
+        counter.tasks_start.write(|w| unsafe { w.bits(1) });
+
+        let channel = edge.of(gpiote);
+        channel.input_pin(&pin).toggle();
+        ppi::connect(wiring, channel.event(), &timer.tasks_capture[0]);
+        ppi::fork(wiring, &counter.tasks_count);
+        ppi::enable(wiring);
+
+        Ok(PulseMeter { timer, counter, pin, edge })
+    }
+
+    /// Edges counted since init, wraps around.
+    pub fn edges(&self) -> u32 {
+        self.counter.tasks_capture[0].write(|w| unsafe { w.bits(1) });
+        self.counter.cc[0].read().bits()
+    }
+
+    fn now(&self) -> u32 {
+        self.timer.tasks_capture[1].write(|w| unsafe { w.bits(1) });
+        self.timer.cc[1].read().bits()
+    }
+
+    /// Times one high and one low phase of the signal, None when it doesn't change within
+    /// `timeout_us` or edges came too fast to follow.
+    pub fn measure(&mut self, timeout_us : u32) -> Option<Pulse> {
+        // NOTE: the event is only polled, it is not routed to the GPIOTE interrupt
+        let gpiote = unsafe { &*GPIOTE::ptr() };
+        let start = self.now();
+        let mut edges = self.edges();
+        gpiote.events_in[self.edge.index()].reset();
+
+        // NOTE: the capture time and the pin level right after each of three edges
+        let mut times = [0; 3];
+        let mut levels = [false; 3];
+        for (time, level) in times.iter_mut().zip(levels.iter_mut()) {
+            while gpiote.events_in[self.edge.index()].read().bits() == 0 {
+                if self.now().wrapping_sub(start) > timeout_us * TICKS_PER_US {
+                    return None;
+                }
+            }
+            gpiote.events_in[self.edge.index()].reset();
+            *time = self.timer.cc[0].read().bits();
+            *level = self.pin.is_high().unwrap();
+            let counted = self.edges();
+            if counted.wrapping_sub(edges) != 1 {
+                return None;
+            }
+            edges = counted;
+        }
+
+        let first = times[1].wrapping_sub(times[0]);
+        let second = times[2].wrapping_sub(times[1]);
+        Some(if levels[0] {
+            Pulse { high_ticks : first, low_ticks : second }
+        } else {
+            Pulse { high_ticks : second, low_ticks : first }
+        })
+    }
+}
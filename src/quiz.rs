@@ -0,0 +1,302 @@
+//! Quiz buzzer: the host board says go and the players race to press A, see
+//! `fun_core::quiz` for how the presses are placed.
+//!
+//! `set quiz host` makes a board the host, every other board plays. In the quiz app A on the
+//! host opens a round, it broadcasts the go every `RESEND_MS` and shows GO until the first
+//! buzz, then the number of buzzes. A player that heard the go shows GO too, A buzzes with
+//! the synced time of the press, again every `RESEND_MS` until the host sends its place, at
+//! most `TRIES` times. `SETTLE_MS` after the first buzz the round settles: the host logs the
+//! places with the time each player took, sends every player its place, which it shows, and
+//! shows the buzzes bright. A buzz coming late, or again because its place got lost, is
+//! answered with its place. A on the host opens the next round, or the same question again.
+//!
+//! NOTE: the places are only fair with the host and the players on the same clock, a time
+//! sync master and its followers, see timesync.rs. Otherwise every board stamps its presses
+//! on its own clock.
+
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+use core::sync::atomic::{AtomicU8, Ordering};
+use cortex_m::interrupt::Mutex;
+use fun_core::quiz::{Round, Taken};
+use heapless::Deque;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::font::{self, Align};
+use crate::log;
+use crate::logging::Level;
+use crate::mono::{Instant, Mono};
+use crate::protocol::{Packet, Quiz};
+use crate::radio::Payload;
+use crate::scroll;
+use crate::timesync;
+
+const PLAYERS : usize = 16;
+const RESEND_MS : u64 = 200;
+const SETTLE_MS : u64 = 500;
+const TRIES : u8 = 10;
+const ACTIVE_MS : u64 = 500;
+const POLL_MS : u32 = 50;
+const DIM_LEVEL : u8 = 3;
+
+pub const ICON : Frame = [
+    [0, 1, 1, 1, 0],
+    [0, 1, 1, 1, 0],
+    [1, 1, 1, 1, 1],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Role {
+    Player,
+    Host,
+}
+
+const ROLES : [Role; 2] = [Role::Player, Role::Host];
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Player => "player",
+            Role::Host   => "host",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Role> {
+        ROLES.into_iter().find(|role| role.name() == name)
+    }
+
+    /// The role stored as `role as u8`.
+    pub fn from_u8(value : u8) -> Option<Role> {
+        ROLES.get(value as usize).copied()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Player {
+    Waiting,
+    Armed { round : u8 },
+    Buzzed { round : u8, us : u64, sent : Option<Instant>, tries : u8 },
+    Placed { round : u8, place : u8 },
+}
+
+impl Player {
+    fn round(self) -> Option<u8> {
+        match self {
+            Player::Waiting => None,
+            Player::Armed { round } | Player::Buzzed { round, .. } | Player::Placed { round, .. } => Some(round),
+        }
+    }
+}
+
+struct Host {
+    round   : u8,
+    // NOTE: None until the first round opens
+    buzzes  : Option<Round<PLAYERS>>,
+    first   : Option<Instant>,
+    settled : bool,
+    sent    : Option<Instant>,
+    // NOTE: the players still to be told their place
+    placing : Deque<(u16, u8), PLAYERS>,
+}
+
+static ROLE : AtomicU8 = AtomicU8::new(Role::Player as u8);
+static PLAYER : Mutex<Cell<Player>> = Mutex::new(Cell::new(Player::Waiting));
+static HOST : Mutex<RefCell<Host>> = Mutex::new(RefCell::new(Host {
+    round   : 0,
+    buzzes  : None,
+    first   : None,
+    settled : false,
+    sent    : None,
+    placing : Deque::new(),
+}));
+static DRAWN : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+fn role() -> Role {
+    Role::from_u8(ROLE.load(Ordering::Relaxed)).unwrap_or(Role::Player)
+}
+
+pub fn configure(role : Role) {
+    ROLE.store(role as u8, Ordering::Relaxed);
+    cortex_m::interrupt::free(|cs| PLAYER.borrow(cs).set(Player::Waiting));
+}
+
+/// True while the quiz app is on screen, the radio has to listen for the other boards then.
+pub fn active(now : Instant) -> bool {
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).get()).is_some_and(|drawn| ms_since(now, drawn) < ACTIVE_MS)
+}
+
+fn host_packet(host : &mut Host, now : Instant) -> Option<Payload> {
+    let round = host.round;
+    let buzzes = host.buzzes.as_ref()?;
+    if host.settled {
+        let (to, place) = host.placing.pop_front()?;
+        return Packet::Quiz(Quiz::Placed { round, to, place }).encode();
+    }
+    if host.first.is_some_and(|first| ms_since(now, first) >= SETTLE_MS) {
+        host.settled = true;
+        log!("quiz round {} settled, {} buzzed", round, buzzes.len());
+        for (buzz, place, us) in buzzes.places() {
+            log!("quiz round {} place {} {:04x} after {} ms", round, place, buzz.address, us / 1000);
+            let _ = host.placing.push_back((buzz.address, place));
+        }
+        return None;
+    }
+    if host.sent.is_some_and(|sent| ms_since(now, sent) < RESEND_MS) {
+        return None;
+    }
+    host.sent = Some(now);
+    Packet::Quiz(Quiz::Go { round, us : buzzes.go_us() }).encode()
+}
+
+fn player_packet(now : Instant) -> Option<Payload> {
+    let packet = cortex_m::interrupt::free(|cs| {
+        let player = PLAYER.borrow(cs);
+        let Player::Buzzed { round, us, sent, tries } = player.get() else { return None };
+        if tries >= TRIES || sent.is_some_and(|sent| ms_since(now, sent) < RESEND_MS) {
+            return None;
+        }
+        player.set(Player::Buzzed { round, us, sent : Some(now), tries : tries + 1 });
+        Some(Quiz::Buzz { round, us })
+    })?;
+    Packet::Quiz(packet).encode()
+}
+
+/// The go or the places on the host, the buzz on a player, call it until None.
+pub fn next_packet(now : Instant) -> Option<Payload> {
+    if !active(now) {
+        return None;
+    }
+    match role() {
+        Role::Host => cortex_m::interrupt::free(|cs| host_packet(&mut HOST.borrow(cs).borrow_mut(), now)),
+        Role::Player => player_packet(now),
+    }
+}
+
+fn on_buzz(sender : u16, round : u8, us : u64, now : Instant) {
+    let taken = cortex_m::interrupt::free(|cs| {
+        let mut host = HOST.borrow(cs).borrow_mut();
+        let host = &mut *host;
+        let Some(buzzes) = host.buzzes.as_mut().filter(|_| round == host.round) else { return None };
+        let taken = buzzes.buzz(sender, us);
+        if taken == Taken::Placed && host.first.is_none() {
+            host.first = Some(now);
+        }
+        // NOTE: a late buzz, or one whose place got lost, is answered right away
+        if host.settled && matches!(taken, Taken::Placed | Taken::Again) {
+            if let Some(place) = buzzes.place_of(sender) {
+                let _ = host.placing.push_back((sender, place));
+            }
+        }
+        Some(taken)
+    });
+    match taken {
+        Some(Taken::FalseStart) => log!(Level::Warn, "quiz buzz from {:04x} before the go", sender),
+        Some(Taken::Full) => log!(Level::Warn, "quiz round full, {:04x} not placed", sender),
+        _ => (),
+    }
+}
+
+/// Takes a packet from the board at `sender`, this board being at `own`, false when it isn't
+/// a quiz packet.
+pub fn on_packet(sender : u16, own : u16, packet : &Packet, now : Instant) -> bool {
+    let Packet::Quiz(quiz) = *packet else { return false };
+    if !active(now) {
+        return true;
+    }
+    match (role(), quiz) {
+        (Role::Host, Quiz::Buzz { round, us }) => on_buzz(sender, round, us, now),
+        (Role::Player, Quiz::Go { round, .. }) => cortex_m::interrupt::free(|cs| {
+            let player = PLAYER.borrow(cs);
+            if player.get().round() != Some(round) {
+                player.set(Player::Armed { round });
+            }
+        }),
+        (Role::Player, Quiz::Placed { round, to, place }) if to == own => cortex_m::interrupt::free(|cs| {
+            let player = PLAYER.borrow(cs);
+            if matches!(player.get(), Player::Buzzed { round : buzzed, .. } if buzzed == round) {
+                player.set(Player::Placed { round, place });
+            }
+        }),
+        _ => (),
+    }
+    true
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    if !matches!(input, Input::Button(Button::A)) {
+        return false;
+    }
+    match role() {
+        Role::Host => {
+            let round = cortex_m::interrupt::free(|cs| {
+                let mut host = HOST.borrow(cs).borrow_mut();
+                host.round = host.round.wrapping_add(1);
+                host.buzzes = Some(Round::new(timesync::now_us()));
+                host.first = None;
+                host.settled = false;
+                host.sent = None;
+                host.placing.clear();
+                host.round
+            });
+            log!("quiz round {} go", round);
+        }
+        Role::Player => {
+            let us = timesync::now_us();
+            cortex_m::interrupt::free(|cs| {
+                let player = PLAYER.borrow(cs);
+                if let Player::Armed { round } = player.get() {
+                    player.set(Player::Buzzed { round, us, sent : None, tries : 0 });
+                }
+            });
+        }
+    }
+    true
+}
+
+fn go(step : usize) -> Option<(Frame, u32)> {
+    scroll::frames("GO").nth(step).map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
+
+fn number(n : usize, level : u8) -> Frame {
+    let mut digits = heapless::String::<2>::new();
+    let _ = write!(digits, "{}", n.min(99));
+    font::render(&digits, Align::Center).unwrap_or_default().map(|row| row.map(|led| led * level))
+}
+
+/// GO, then the buzzes on the host and the place on a player.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).set(Some(now)));
+    let idle = ICON.map(|row| row.map(|led| led * DIM_LEVEL));
+    let frame = match role() {
+        Role::Host => {
+            let (buzzed, settled) = cortex_m::interrupt::free(|cs| {
+                let host = HOST.borrow(cs).borrow();
+                (host.buzzes.as_ref().map(Round::len), host.settled)
+            });
+            match buzzed {
+                None => idle,
+                Some(0) => return go(step),
+                Some(buzzed) => number(buzzed, if settled { 9 } else { DIM_LEVEL }),
+            }
+        }
+        Role::Player => match cortex_m::interrupt::free(|cs| PLAYER.borrow(cs).get()) {
+            Player::Waiting => idle,
+            Player::Armed { .. } => return go(step),
+            Player::Buzzed { .. } => {
+                let mut frame = [[0; 5]; 5];
+                frame[2][2] = if (now.duration_since_epoch().to_millis() / 200).is_multiple_of(2) { 9 } else { DIM_LEVEL };
+                frame
+            }
+            Player::Placed { place, .. } => number(place as usize, 9),
+        },
+    };
+    Some((frame, POLL_MS))
+}
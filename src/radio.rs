@@ -0,0 +1,216 @@
+//! Minimal RADIO driver for short broadcast packets between micro:bits.
+//!
+//! Uses the proprietary Nrf1Mbit mode with the same base address, channel and
+//! whitening as the MakeCode radio, boards only hear packets from their own group.
+//! Sending blocks for the ~0.5 ms a packet takes on air, receiving is interrupt driven:
+//! the RADIO task calls `on_interrupt` which hands back every packet that passed the CRC,
+//! `rssi` then tells how strong it came in. `advertise` sends a BLE advertisement in
+//! between, in the BLE mode on its three advertising channels, see beacon.rs.
+//!
+//! NOTE: the radio needs the HFXO running, see `init`.
+
+use fun_core::beacon::PDU_LEN;
+use heapless::Vec;
+use microbit::pac::RADIO;
+
+pub const PAYLOAD_LEN : usize = 32;
+// NOTE: one length byte in front of the payload, or a whole advertising PDU
+pub const BUFFER_LEN : usize = if PAYLOAD_LEN + 1 > PDU_LEN { PAYLOAD_LEN + 1 } else { PDU_LEN };
+
+// "ubit", the base address the MakeCode radio uses
+const BASE_ADDRESS : u32 = 0x7562_6974;
+// NOTE: 2407 MHz, the MakeCode default
+const FREQUENCY : u8 = 7;
+const DEFAULT_GROUP : u8 = 0;
+// NOTE: the access address of advertisements, 0x8e89bed6, as a prefix and a 3 byte base
+const ADVERTISING_PREFIX : u8 = 0x8e;
+const ADVERTISING_BASE : u32 = 0x89be_d600;
+// NOTE: the index and the MHz above 2400 of the advertising channels 37, 38 and 39
+const ADVERTISING_CHANNELS : [(u8, u8); 3] = [(37, 2), (38, 26), (39, 80)];
+
+pub type Payload = Vec<u8, PAYLOAD_LEN>;
+
+pub struct Radio {
+    radio     : RADIO,
+    // NOTE: the packet buffer is read and written by the radio's DMA,
+    // so it has to stay put, which is why it is a 'static buffer handed in by init
+    buffer    : &'static mut [u8; BUFFER_LEN],
+    listening : bool,
+    group     : u8,
+    // NOTE: dBm, sampled while the last packet came in
+    rssi      : i8,
+}
+
+impl Radio {
+    pub fn new(radio : RADIO, buffer : &'static mut [u8; BUFFER_LEN]) -> Self {
+        radio.power.write(|w| w.power().enabled());
+        radio.txpower.write(|w| w.txpower()._0d_bm());
+        radio.txaddress.write(|w| unsafe { w.txaddress().bits(0) });
+        radio.rxaddresses.write(|w| w.addr0().enabled());
+
+        let mut radio = Radio { radio, buffer, listening : false, group : DEFAULT_GROUP, rssi : i8::MIN };
+        radio.configure();
+        radio
+    }
+
+    // NOTE: the micro:bit radio, again after an advertisement
+    fn configure(&mut self) {
+        let radio = &self.radio;
+        radio.mode.write(|w| w.mode().nrf_1mbit());
+        radio.frequency.write(|w| unsafe { w.frequency().bits(FREQUENCY) });
+
+        // 8 bit length field followed by at most PAYLOAD_LEN bytes
+        radio.pcnf0.write(|w| unsafe { w.lflen().bits(8).s0len().clear_bit().s1len().bits(0) });
+        radio.pcnf1.write(|w| unsafe {
+            w.maxlen().bits(PAYLOAD_LEN as u8)
+                .statlen().bits(0)
+                .balen().bits(4)
+                .endian().little()
+                .whiteen().enabled()
+        });
+        radio.datawhiteiv.write(|w| unsafe { w.datawhiteiv().bits(0x18) });
+
+        radio.base0.write(|w| unsafe { w.bits(BASE_ADDRESS) });
+        radio.prefix0.write(|w| unsafe { w.ap0().bits(self.group) });
+
+        radio.crccnf.write(|w| w.len().two());
+        radio.crcinit.write(|w| unsafe { w.crcinit().bits(0xffff) });
+        radio.crcpoly.write(|w| unsafe { w.crcpoly().bits(0x11021) });
+    }
+
+    /// Boards only receive packets sent with the same group.
+    pub fn set_group(&mut self, group : u8) {
+        self.disable();
+        self.group = group;
+        self.radio.prefix0.write(|w| unsafe { w.ap0().bits(group) });
+        if self.listening {
+            self.start_receive();
+        }
+    }
+
+    pub fn group(&self) -> u8 {
+        self.group
+    }
+
+    pub fn is_listening(&self) -> bool {
+        self.listening
+    }
+
+    /// Keeps the receiver on, packets are then picked up by `on_interrupt`.
+    pub fn listen(&mut self, listening : bool) {
+        self.listening = listening;
+        if listening {
+            self.start_receive();
+        } else {
+            self.radio.intenclr.write(|w| w.end().clear());
+            self.disable();
+        }
+    }
+
+    /// Broadcasts `payload`, cut to `PAYLOAD_LEN`, and goes back to listening if it was.
+    pub fn send(&mut self, payload : &[u8]) {
+        self.disable();
+
+        let len = payload.len().min(PAYLOAD_LEN);
+        self.buffer[0] = len as u8;
+        self.buffer[1..=len].copy_from_slice(&payload[..len]);
+        self.radio.packetptr.write(|w| unsafe { w.bits(self.buffer.as_ptr() as u32) });
+
+        self.radio.shorts.write(|w| w.ready_start().enabled().end_disable().enabled());
+        self.radio.events_disabled.reset();
+        self.radio.tasks_txen.write(|w| w.tasks_txen().set_bit());
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+
+        if self.listening {
+            self.start_receive();
+        }
+    }
+
+    /// Sends `pdu`, an advertising PDU with its header, on the three advertising channels,
+    /// then goes back to the micro:bit radio, and to listening if it was.
+    pub fn advertise(&mut self, pdu : &[u8]) {
+        self.disable();
+
+        let radio = &self.radio;
+        radio.mode.write(|w| w.mode().ble_1mbit());
+        // NOTE: the header byte as S0, then the 8 bit length
+        radio.pcnf0.write(|w| unsafe { w.lflen().bits(8).s0len().set_bit().s1len().bits(0) });
+        radio.pcnf1.write(|w| unsafe {
+            w.maxlen().bits((PDU_LEN - 2) as u8)
+                .statlen().bits(0)
+                .balen().bits(3)
+                .endian().little()
+                .whiteen().enabled()
+        });
+        radio.base0.write(|w| unsafe { w.bits(ADVERTISING_BASE) });
+        radio.prefix0.write(|w| unsafe { w.ap0().bits(ADVERTISING_PREFIX) });
+        radio.crccnf.write(|w| w.len().three().skipaddr().skip());
+        radio.crcinit.write(|w| unsafe { w.crcinit().bits(0x55_5555) });
+        radio.crcpoly.write(|w| unsafe { w.crcpoly().bits(0x65b) });
+
+        let len = pdu.len().min(PDU_LEN);
+        self.buffer[..len].copy_from_slice(&pdu[..len]);
+        radio.packetptr.write(|w| unsafe { w.bits(self.buffer.as_ptr() as u32) });
+        for (channel, mhz) in ADVERTISING_CHANNELS {
+            radio.frequency.write(|w| unsafe { w.frequency().bits(mhz) });
+            radio.datawhiteiv.write(|w| unsafe { w.datawhiteiv().bits(channel) });
+            radio.shorts.write(|w| w.ready_start().enabled().end_disable().enabled());
+            radio.events_disabled.reset();
+            radio.tasks_txen.write(|w| w.tasks_txen().set_bit());
+            while radio.events_disabled.read().bits() == 0 {}
+            radio.events_disabled.reset();
+        }
+
+        self.configure();
+        if self.listening {
+            self.start_receive();
+        }
+    }
+
+    pub fn on_interrupt(&mut self) -> Option<Payload> {
+        if self.radio.events_end.read().bits() == 0 {
+            return None;
+        }
+        self.radio.events_end.reset();
+        // NOTE: RSSISAMPLE holds the signal strength as a positive number, -dBm
+        self.rssi = -(self.radio.rssisample.read().rssisample().bits() as i8);
+
+        let payload = if self.radio.crcstatus.read().crcstatus().is_crcok() {
+            let len = (self.buffer[0] as usize).min(PAYLOAD_LEN);
+            Vec::from_slice(&self.buffer[1..=len]).ok()
+        } else {
+            None
+        };
+
+        // NOTE: after END the receiver idles, START picks up the next packet
+        self.radio.tasks_start.write(|w| w.tasks_start().set_bit());
+        payload
+    }
+
+    /// Signal strength of the last packet received, in dBm.
+    pub fn rssi(&self) -> i8 {
+        self.rssi
+    }
+
+    fn start_receive(&mut self) {
+        self.disable();
+        self.radio.packetptr.write(|w| unsafe { w.bits(self.buffer.as_ptr() as u32) });
+        // NOTE: the RSSI is sampled once the address matched, it's ready well before END
+        self.radio.shorts.write(|w| w.ready_start().enabled().address_rssistart().enabled());
+        self.radio.events_end.reset();
+        self.radio.intenset.write(|w| w.end().set());
+        self.radio.tasks_rxen.write(|w| w.tasks_rxen().set_bit());
+    }
+
+    fn disable(&mut self) {
+        self.radio.shorts.reset();
+        if self.radio.state.read().state().is_disabled() {
+            return;
+        }
+        self.radio.events_disabled.reset();
+        self.radio.tasks_disable.write(|w| w.tasks_disable().set_bit());
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.reset();
+    }
+}
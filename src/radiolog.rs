@@ -0,0 +1,51 @@
+//! Log records broadcast over the radio, for boards without a debugger or serial attached.
+//!
+//! While the radio route is on (from boot with the `radio_log` feature, or `route` console
+//! command) every record that passes the level filter is queued here and the `radio_log` task
+//! seals and broadcasts the queue. A board in listener mode (`listen` console command) picks
+//! the packets up and forwards them over its serial port.
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+use heapless::Deque;
+use crate::logging::Level;
+use crate::protocol::{self, Packet};
+use crate::radio::Payload;
+
+// NOTE: the kind, the level and the length of the text, the sender address is part of the
+// sealed packet
+const TEXT_LEN : usize = protocol::MAX_LEN - 3;
+const QUEUED : usize = 8;
+
+// NOTE: logging happens from every priority, so the queue sits behind a critical section
+static QUEUE : Mutex<RefCell<Deque<Payload, QUEUED>>> = Mutex::new(RefCell::new(Deque::new()));
+
+/// Queues a record for broadcasting, when the queue is full the oldest record is dropped.
+pub fn queue(level : Level, s : &str) {
+    // NOTE: the radio packet is short, so long messages are cut at a char boundary
+    let mut end = s.len().min(TEXT_LEN);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    let Some(payload) = Packet::Log { level : level as u8, text : &s[..end] }.encode() else { return };
+
+    cortex_m::interrupt::free(|cs| {
+        let mut queue = QUEUE.borrow(cs).borrow_mut();
+        if queue.is_full() {
+            queue.pop_front();
+        }
+        let _ = queue.push_back(payload);
+    });
+}
+
+pub fn next() -> Option<Payload> {
+    cortex_m::interrupt::free(|cs| QUEUE.borrow(cs).borrow_mut().pop_front())
+}
+
+/// The level and text of a log packet.
+pub fn decode<'a>(packet : &Packet<'a>) -> Option<(Level, &'a str)> {
+    match *packet {
+        Packet::Log { level, text } => Some((Level::from_u8(level), text)),
+        _ => None,
+    }
+}
@@ -0,0 +1,134 @@
+//! Reaction time game: the display lights up after a random wait and button A stops the clock.
+//!
+//! The press is timestamped with `Mono` in the button interrupt, not in a task, so the time
+//! isn't held up by a frame being drawn. The result scrolls by with the best time so far,
+//! which is kept in the key-value store, then the next round starts.
+
+use core::cell::Cell;
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::kv;
+use crate::log;
+use crate::mono::{Instant, Mono, ExtU64};
+use crate::rng::Rng;
+use crate::scroll::{self, Text};
+
+pub const ICON : Frame = [
+    [0, 1, 1, 1, 0],
+    [1, 0, 1, 0, 1],
+    [1, 0, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [0, 1, 1, 1, 0],
+];
+
+const MIN_WAIT_MS : u32 = 1500;
+const MAX_WAIT_MS : u32 = 4000;
+// NOTE: no press this long after lighting up ends the round
+const TIMEOUT_MS : u64 = 3000;
+// NOTE: how often the display is redrawn while waiting, bounds how late it lights up
+const POLL_MS : u32 = 5;
+
+#[derive(Clone, Copy)]
+enum Outcome {
+    Time { ms : u32, best : Option<u32> },
+    TooSoon,
+    TooSlow,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Waiting { until : Instant },
+    Lit { at : Instant },
+    // NOTE: the result scrolls from `at` on
+    Done { outcome : Outcome, at : Instant },
+}
+
+// NOTE: the button interrupt and idle both move the round on
+static STATE : Mutex<Cell<Option<State>>> = Mutex::new(Cell::new(None));
+
+/// Moves the round on from the state it is in, returns the state it ends up in.
+fn update(next : impl FnOnce(Option<State>) -> Option<State>) -> Option<State> {
+    cortex_m::interrupt::free(|cs| {
+        let state = next(STATE.borrow(cs).get());
+        STATE.borrow(cs).set(state);
+        state
+    })
+}
+
+/// Button A during a round, from the button interrupt.
+pub fn on_input(input : Input, now : Instant) -> bool {
+    let Input::Button(Button::A) = input else { return false };
+    let done = |outcome| Some(State::Done { outcome, at : now });
+    let mut taken = true;
+    update(|state| match state {
+        Some(State::Waiting { .. }) => done(Outcome::TooSoon),
+        Some(State::Lit { at }) => done(Outcome::Time { ms : (now - at).to_millis() as u32, best : None }),
+        // NOTE: while the result scrolls, A does what it always does
+        state => {
+            taken = false;
+            state
+        }
+    });
+    taken
+}
+
+// NOTE: the best time counting this one, stored when this one is it
+fn record(ms : u32) -> u32 {
+    let best = kv::get_u32(kv::REACTION_BEST);
+    if best.is_none_or(|best| ms < best) {
+        kv::set_u32(kv::REACTION_BEST, ms);
+        log!("reaction time {} ms, new best", ms);
+        return ms;
+    }
+    log!("reaction time {} ms", ms);
+    best.unwrap_or(ms)
+}
+
+fn text(outcome : Outcome) -> Text {
+    let mut text = Text::new();
+    let _ = match outcome {
+        Outcome::Time { ms, best : Some(best) } if best == ms => write!(text, "{} ms best!", ms),
+        Outcome::Time { ms, best : Some(best) } => write!(text, "{} ms best {}", ms, best),
+        Outcome::Time { ms, best : None } => write!(text, "{} ms", ms),
+        Outcome::TooSoon => write!(text, "too soon"),
+        Outcome::TooSlow => write!(text, "too slow"),
+    };
+    text
+}
+
+/// One round, from the random wait to the scrolled result.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    let state = update(|state| Some(match state {
+        // NOTE: the clock starts as the frame goes up, a press can land from here on
+        Some(State::Waiting { until }) if step > 0 && now >= until => State::Lit { at : now },
+        Some(State::Lit { at }) if step > 0 && (now - at).to_millis() > TIMEOUT_MS =>
+            State::Done { outcome : Outcome::TooSlow, at : now },
+        Some(state) if step > 0 => state,
+        // NOTE: a new round, or the launcher cut the last one short
+        _ => {
+            let wait = MIN_WAIT_MS + Rng.below(MAX_WAIT_MS - MIN_WAIT_MS);
+            State::Waiting { until : now + (wait as u64).millis() }
+        }
+    }))?;
+
+    let lit = [[9; 5]; 5];
+    match state {
+        State::Waiting { .. } => Some(([[0; 5]; 5], POLL_MS)),
+        State::Lit { .. } => Some((lit, POLL_MS)),
+        State::Done { outcome : Outcome::Time { ms, best : None }, at } => {
+            // NOTE: presses are ignored once the round is done, nothing overwrites this
+            let best = record(ms);
+            update(|_| Some(State::Done { outcome : Outcome::Time { ms, best : Some(best) }, at }));
+            Some(([[0; 5]; 5], POLL_MS))
+        }
+        State::Done { outcome, at } => {
+            let column = (now - at).to_millis() / scroll::STEP_MS as u64;
+            let frame = scroll::frames(&text(outcome)).nth(column as usize)?;
+            Some((frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+        }
+    }
+}
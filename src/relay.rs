@@ -0,0 +1,123 @@
+//! Text messages flooded over the radio, so a message reaches boards out of range of the one
+//! that sent it, through the boards in between.
+//!
+//! `send` (the `send` console command) queues a message with `TTL` hops to go. A board with
+//! relaying on (`set relay on`) keeps its receiver on, toasts every message it hasn't seen
+//! before and queues it again with one hop less, until no hops are left. A message is known
+//! by the board it came from and that board's sequence number, the last `SEEN` of them are
+//! remembered, so a message coming back round the mesh is dropped.
+//!
+//! NOTE: every relay seals the message anew, the origin travels inside the packet. The
+//! messages toasted stay in the inbox, see inbox.rs, the quiet hours of dnd.rs hold the toasts
+//! back.
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+use heapless::{Deque, String};
+use crate::protocol::{self, Packet};
+use crate::radio::Payload;
+
+// NOTE: the kind, origin (2), sequence (2), ttl and the length of the text
+const HEADER_LEN : usize = 7;
+pub const TEXT_LEN : usize = protocol::MAX_LEN - HEADER_LEN;
+const TTL : u8 = 3;
+const SEEN : usize = 16;
+const QUEUED : usize = 4;
+
+pub type Text = String<TEXT_LEN>;
+
+pub struct Message {
+    pub origin : u16,
+    pub text   : Text,
+}
+
+struct Relay {
+    on       : bool,
+    sequence : u16,
+    seen     : Deque<(u16, u16), SEEN>,
+    queue    : Deque<Payload, QUEUED>,
+}
+
+impl Relay {
+    /// False when the message was seen before, it is remembered otherwise.
+    fn remember(&mut self, origin : u16, sequence : u16) -> bool {
+        if self.seen.iter().any(|seen| *seen == (origin, sequence)) {
+            return false;
+        }
+        if self.seen.is_full() {
+            self.seen.pop_front();
+        }
+        let _ = self.seen.push_back((origin, sequence));
+        true
+    }
+
+    // NOTE: when the queue is full the oldest message is dropped
+    fn queue(&mut self, packet : Packet) {
+        let Some(payload) = packet.encode() else { return };
+        if self.queue.is_full() {
+            self.queue.pop_front();
+        }
+        let _ = self.queue.push_back(payload);
+    }
+}
+
+// NOTE: the console, the radio tasks and radio_log all get at it
+static RELAY : Mutex<RefCell<Relay>> = Mutex::new(RefCell::new(Relay {
+    on       : false,
+    sequence : 0,
+    seen     : Deque::new(),
+    queue    : Deque::new(),
+}));
+
+pub fn configure(on : bool) {
+    cortex_m::interrupt::free(|cs| RELAY.borrow(cs).borrow_mut().on = on);
+}
+
+/// True while relaying, the radio has to listen for the messages then.
+pub fn active() -> bool {
+    cortex_m::interrupt::free(|cs| RELAY.borrow(cs).borrow().on)
+}
+
+/// Queues `text`, cut to `TEXT_LEN` at a char boundary, from this board at `origin`.
+pub fn send(origin : u16, text : &str) {
+    let mut end = text.len().min(TEXT_LEN);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    cortex_m::interrupt::free(|cs| {
+        let mut relay = RELAY.borrow(cs).borrow_mut();
+        relay.sequence = relay.sequence.wrapping_add(1);
+        let sequence = relay.sequence;
+        // NOTE: our own message coming back from a neighbour isn't shown or sent again
+        relay.remember(origin, sequence);
+        relay.queue(Packet::Relay { origin, sequence, ttl : TTL, text : &text[..end] });
+    });
+}
+
+/// The next message to broadcast, ours or one passed on.
+pub fn next() -> Option<Payload> {
+    cortex_m::interrupt::free(|cs| RELAY.borrow(cs).borrow_mut().queue.pop_front())
+}
+
+/// Takes a packet, `show` gets the message when it wasn't seen before.
+/// False when it isn't a relayed message.
+pub fn on_packet(packet : &Packet, show : impl FnOnce(Message)) -> bool {
+    let Packet::Relay { origin, sequence, ttl, text } = *packet else { return false };
+    let new = cortex_m::interrupt::free(|cs| {
+        let mut relay = RELAY.borrow(cs).borrow_mut();
+        if !relay.on || !relay.remember(origin, sequence) {
+            return false;
+        }
+        if ttl > 0 {
+            relay.queue(Packet::Relay { origin, sequence, ttl : ttl - 1, text });
+        }
+        true
+    });
+    if new {
+        let mut message = Message { origin, text : Text::new() };
+        // NOTE: a longer text wouldn't have fit the packet, so this never cuts it
+        let _ = message.text.push_str(text);
+        show(message);
+    }
+    true
+}
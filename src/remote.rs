@@ -0,0 +1,135 @@
+//! Remote control: the buttons of one board work the apps of its peer, see pairing.rs.
+//!
+//! The remote app takes the presses of A, B and the logo and the long presses of A and B,
+//! and sends them to the peer, the icon blinks for each one. A+B stays with the launcher,
+//! so the remote can be left. A board with remote control on (`set remote on`) keeps its
+//! receiver on, queues the inputs its peer sent and pends the GPIOTE interrupt, which hands
+//! them on as if they were its own, see `button_pressed` in main.rs.
+
+use core::cell::{Cell, RefCell};
+use cortex_m::interrupt::Mutex;
+use heapless::Deque;
+use microbit::pac::{Interrupt, NVIC};
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::mono::{Instant, Mono};
+use crate::pairing;
+use crate::protocol::{Key, Packet, Remote};
+use crate::radio::Payload;
+
+pub const ICON : Frame = [
+    [0, 1, 1, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 1, 1, 0],
+    [0, 1, 0, 1, 0],
+    [0, 1, 1, 1, 0],
+];
+
+const QUEUE_LEN : usize = 8;
+const BLINK_MS : u64 = 150;
+const POLL_MS : u32 = 20;
+
+// NOTE: the presses the remote app took and the inputs the peer sent, both filled from the
+// button interrupt or the radio tasks and emptied from another priority
+static SENDING : Mutex<RefCell<Deque<Input, QUEUE_LEN>>> = Mutex::new(RefCell::new(Deque::new()));
+static RECEIVED : Mutex<RefCell<Deque<Input, QUEUE_LEN>>> = Mutex::new(RefCell::new(Deque::new()));
+static ENABLED : Mutex<Cell<bool>> = Mutex::new(Cell::new(false));
+static PRESSED : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+fn key(button : Button) -> Key {
+    match button {
+        Button::A    => Key::A,
+        Button::B    => Key::B,
+        Button::AB   => Key::AB,
+        Button::Logo => Key::Logo,
+    }
+}
+
+fn button(key : Key) -> Button {
+    match key {
+        Key::A    => Button::A,
+        Key::B    => Button::B,
+        Key::AB   => Button::AB,
+        Key::Logo => Button::Logo,
+    }
+}
+
+pub fn configure(enabled : bool) {
+    cortex_m::interrupt::free(|cs| {
+        ENABLED.borrow(cs).set(enabled);
+        RECEIVED.borrow(cs).borrow_mut().clear();
+    });
+}
+
+/// True while remote control is on, the radio has to listen for the peer then.
+pub fn active() -> bool {
+    cortex_m::interrupt::free(|cs| ENABLED.borrow(cs).get())
+}
+
+pub fn on_input(input : Input, now : Instant) -> bool {
+    match input {
+        Input::Button(Button::A | Button::B | Button::Logo) | Input::LongPress(Button::A | Button::B) => (),
+        _ => return false,
+    }
+    // NOTE: nobody to send it to, the press is still taken so A and B don't run their actions
+    if pairing::peer().is_none() {
+        return true;
+    }
+    cortex_m::interrupt::free(|cs| {
+        let mut sending = SENDING.borrow(cs).borrow_mut();
+        if sending.is_full() {
+            sending.pop_front();
+        }
+        let _ = sending.push_back(input);
+        PRESSED.borrow(cs).set(Some(now));
+    });
+    true
+}
+
+/// The next input to send to the peer.
+pub fn next_packet() -> Option<Payload> {
+    let input = cortex_m::interrupt::free(|cs| SENDING.borrow(cs).borrow_mut().pop_front())?;
+    let remote = match input {
+        Input::Button(button)    => Remote::Press(key(button)),
+        Input::LongPress(button) => Remote::LongPress(key(button)),
+        _ => return None,
+    };
+    Packet::Remote(remote).encode()
+}
+
+/// Takes a packet from the peer, false when it isn't a remote control packet.
+pub fn on_packet(packet : &Packet) -> bool {
+    let Packet::Remote(remote) = *packet else { return false };
+    let input = match remote {
+        Remote::Press(key)     => Input::Button(button(key)),
+        Remote::LongPress(key) => Input::LongPress(button(key)),
+    };
+    let queued = cortex_m::interrupt::free(|cs| {
+        if !ENABLED.borrow(cs).get() {
+            return false;
+        }
+        RECEIVED.borrow(cs).borrow_mut().push_back(input).is_ok()
+    });
+    if queued {
+        NVIC::pend(Interrupt::GPIOTE);
+    }
+    true
+}
+
+/// The next input the peer sent, for the button interrupt.
+pub fn take() -> Option<Input> {
+    cortex_m::interrupt::free(|cs| RECEIVED.borrow(cs).borrow_mut().pop_front())
+}
+
+/// The icon, dim without a peer and blinking off for every press sent.
+pub fn draw(_step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    let pressed = cortex_m::interrupt::free(|cs| PRESSED.borrow(cs).get());
+    let level = match pressed {
+        _ if pairing::peer().is_none() => 2,
+        Some(at) if now.checked_duration_since(at).is_some_and(|elapsed| elapsed.to_millis() < BLINK_MS) => 0,
+        _ => 9,
+    };
+    Some((ICON.map(|row| row.map(|led| led * level)), POLL_MS))
+}
@@ -0,0 +1,96 @@
+//! Random numbers from the RNG peripheral.
+//!
+//! The RNG runs with its bias correction on and its interrupt fills a small entropy pool,
+//! it is stopped while the pool is full. `Rng` takes from the pool and implements
+//! `rand_core::RngCore` and `fun_core::Random`, `below` picks a number in a range without
+//! modulo bias.
+//!
+//! NOTE: taking from an empty pool waits for the interrupt, so `Rng` must not be used from
+//! a priority at or above the `rng_ready` task, or with interrupts disabled.
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+use heapless::Deque;
+use microbit::pac::RNG;
+use rand_core::{impls, RngCore};
+
+const POOL : usize = 32;
+
+static ENTROPY : Mutex<RefCell<Deque<u8, POOL>>> = Mutex::new(RefCell::new(Deque::new()));
+
+pub fn start(rng : &RNG) {
+    rng.config.write(|w| w.dercen().enabled());
+    rng.intenset.write(|w| w.valrdy().set());
+    rng.tasks_start.write(|w| w.tasks_start().set_bit());
+}
+
+pub fn on_interrupt(rng : &RNG) {
+    rng.events_valrdy.reset();
+    let byte = rng.value.read().value().bits();
+    let full = cortex_m::interrupt::free(|cs| {
+        let mut pool = ENTROPY.borrow(cs).borrow_mut();
+        let _ = pool.push_back(byte);
+        pool.is_full()
+    });
+    if full {
+        rng.tasks_stop.write(|w| w.tasks_stop().set_bit());
+    }
+}
+
+fn take() -> u8 {
+    loop {
+        let (byte, remaining) = cortex_m::interrupt::free(|cs| {
+            let mut pool = ENTROPY.borrow(cs).borrow_mut();
+            (pool.pop_front(), pool.len())
+        });
+        if remaining < POOL / 2 {
+            // NOTE: rng_ready owns the peripheral, starting it again is all that is done here
+            unsafe { (*RNG::ptr()).tasks_start.write(|w| w.tasks_start().set_bit()) };
+        }
+        if let Some(byte) = byte {
+            return byte;
+        }
+    }
+}
+
+/// Handle on the entropy pool, there is nothing to own so it can be made anywhere.
+pub struct Rng;
+
+impl Rng {
+    /// A number in `0..n`, rejecting the values that would favour the low numbers.
+    pub fn below(&mut self, n : u32) -> u32 {
+        assert!(n > 0);
+        let zone = u32::MAX - u32::MAX % n;
+        loop {
+            let value = self.next_u32();
+            if value < zone {
+                return value % n;
+            }
+        }
+    }
+}
+
+impl fun_core::Random for Rng {
+    fn below(&mut self, n : u32) -> u32 {
+        Rng::below(self, n)
+    }
+}
+
+impl RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        u32::from_le_bytes([take(), take(), take(), take()])
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest : &mut [u8]) {
+        dest.fill_with(take);
+    }
+
+    fn try_fill_bytes(&mut self, dest : &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,333 @@
+//! Rock-paper-scissors between two boards in the same radio group.
+//!
+//! A steps through rock, paper and scissors, B locks the choice in. A locked board broadcasts
+//! a commitment, the digest of its choice, a random nonce and its radio address, and only
+//! reveals the choice and nonce once it heard the other board's commitment. The revealed
+//! choice is checked against the commitment, so neither board can wait for the other's choice
+//! and pick the one that beats it. Lost packets are covered by resending every `RESEND_MS`
+//! until the round is over.
+//!
+//! NOTE: meant for two boards, with more playing in one group a board plays whoever it hears
+//! first.
+
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+use rand_core::RngCore;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::log;
+use crate::logging::Level;
+use crate::mono::{Instant, Mono};
+use crate::protocol::{Packet, Rps};
+use crate::radio::Payload;
+use crate::rng::Rng;
+use crate::seal::Digest;
+
+pub const ICON : Frame = [
+    [1, 1, 0, 0, 1],
+    [1, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [1, 1, 0, 1, 0],
+    [1, 1, 0, 0, 1],
+];
+
+const NONCE_LEN : usize = 8;
+// NOTE: choice, nonce and the address of the board committing, so a commitment heard
+// can't be sent back as one's own
+const COMMITTED_LEN : usize = 1 + NONCE_LEN + 2;
+
+const NAMES : [&str; 3] = ["rock", "paper", "scissors"];
+
+const RESEND_MS : u64 = 300;
+// NOTE: how long a locked board waits for an opponent
+const WAIT_MS : u64 = 15_000;
+// NOTE: the opponent's choice, then the outcome, the reveal keeps going out meanwhile
+const THEIRS_MS : u64 = 1000;
+const RESULT_MS : u64 = 3000;
+// NOTE: the radio stays on while idle draws the game at least this often
+const ACTIVE_MS : u64 = 500;
+const POLL_MS : u32 = 20;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Outcome {
+    Win,
+    Lose,
+    Draw,
+    // NOTE: the reveal didn't match the commitment
+    Cheated,
+    NoOpponent,
+}
+
+#[derive(Clone, Copy)]
+enum Phase {
+    Choosing,
+    // NOTE: our commitment goes out until the opponent's is in
+    Locked { at : Instant },
+    Revealing { at : Instant },
+    Result { theirs : u8, outcome : Outcome, at : Instant },
+}
+
+#[derive(Clone, Copy)]
+struct Round {
+    choice   : u8,
+    nonce    : [u8; NONCE_LEN],
+    // NOTE: the address and commitment of the first board heard committing
+    opponent : Option<(u16, Digest)>,
+    phase    : Phase,
+    sent     : Option<Instant>,
+    drawn    : Instant,
+}
+
+// NOTE: the button interrupt, the radio tasks and idle all move the round on
+static ROUND : Mutex<Cell<Option<Round>>> = Mutex::new(Cell::new(None));
+
+fn update(next : impl FnOnce(Option<Round>) -> Option<Round>) -> Option<Round> {
+    cortex_m::interrupt::free(|cs| {
+        let round = next(ROUND.borrow(cs).get());
+        ROUND.borrow(cs).set(round);
+        round
+    })
+}
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+fn committed(choice : u8, nonce : &[u8], address : u16) -> [u8; COMMITTED_LEN] {
+    let mut committed = [0; COMMITTED_LEN];
+    committed[0] = choice;
+    committed[1..=NONCE_LEN].copy_from_slice(nonce);
+    committed[NONCE_LEN + 1..].copy_from_slice(&address.to_le_bytes());
+    committed
+}
+
+fn outcome(mine : u8, theirs : u8) -> Outcome {
+    // NOTE: each choice beats the one before it
+    match (mine + 3 - theirs) % 3 {
+        0 => Outcome::Draw,
+        1 => Outcome::Win,
+        _ => Outcome::Lose,
+    }
+}
+
+pub fn on_input(input : Input, now : Instant) -> bool {
+    let mut taken = false;
+    update(|round| {
+        let mut round = round?;
+        if let Phase::Choosing = round.phase {
+            match input {
+                Input::Button(Button::A) => round.choice = (round.choice + 1) % 3,
+                Input::Button(Button::B) => {
+                    Rng.fill_bytes(&mut round.nonce);
+                    round.phase = match round.opponent {
+                        Some(_) => Phase::Revealing { at : now },
+                        None    => Phase::Locked { at : now },
+                    };
+                    round.sent = None;
+                }
+                _ => return Some(round),
+            }
+            taken = true;
+        }
+        Some(round)
+    });
+    taken
+}
+
+/// True while a game is on screen, the radio has to listen for the opponent then.
+pub fn active(now : Instant) -> bool {
+    update(|round| round).is_some_and(|round| ms_since(now, round.drawn) < ACTIVE_MS)
+}
+
+/// The packet to broadcast now, if any. `digest` hashes the commitment.
+pub fn next_packet(now : Instant, address : u16, digest : impl FnOnce(&[u8]) -> Digest) -> Option<Payload> {
+    let mut due = None;
+    update(|round| {
+        let mut round = round?;
+        if round.sent.is_some_and(|sent| ms_since(now, sent) < RESEND_MS) {
+            return Some(round);
+        }
+        due = match round.phase {
+            Phase::Choosing => None,
+            Phase::Locked { .. } => Some((true, round)),
+            Phase::Result { outcome : Outcome::NoOpponent, .. } => None,
+            Phase::Revealing { .. } | Phase::Result { .. } => Some((false, round)),
+        };
+        if due.is_some() {
+            round.sent = Some(now);
+        }
+        Some(round)
+    });
+
+    let (committing, round) = due?;
+    let packet = if committing {
+        Rps::Commit(digest(&committed(round.choice, &round.nonce, address)))
+    } else {
+        Rps::Reveal { choice : round.choice, nonce : round.nonce }
+    };
+    Packet::Rps(packet).encode()
+}
+
+/// Takes a packet from `sender`, false when it isn't a rock-paper-scissors packet.
+/// `digest` hashes a revealed choice to check it against the commitment.
+pub fn on_packet(sender : u16, packet : &Packet, now : Instant, digest : impl FnOnce(&[u8]) -> Digest) -> bool {
+    let Packet::Rps(packet) = *packet else { return false };
+    match packet {
+        Rps::Commit(commitment) => {
+            update(|round| {
+                let mut round = round?;
+                match round.phase {
+                    Phase::Choosing | Phase::Locked { .. } if round.opponent.is_none() => {
+                        round.opponent = Some((sender, commitment));
+                        if let Phase::Locked { .. } = round.phase {
+                            round.phase = Phase::Revealing { at : now };
+                            round.sent = None;
+                        }
+                    }
+                    _ => (),
+                }
+                Some(round)
+            });
+        }
+        Rps::Reveal { choice : theirs, nonce } => {
+            let Some(round) = update(|round| round) else { return true };
+            // NOTE: a locked board may miss every commitment of an opponent that heard its
+            // own, the reveal can't be a cheat then since our choice went out committed
+            let commitment = match (round.phase, round.opponent) {
+                (Phase::Revealing { .. }, Some((opponent, commitment))) if opponent == sender => Some(commitment),
+                (Phase::Locked { .. }, None) => None,
+                _ => return true,
+            };
+            let cheated = theirs > 2
+                || commitment.is_some_and(|commitment| digest(&committed(theirs, &nonce, sender)) != commitment);
+            let outcome = if cheated {
+                Outcome::Cheated
+            } else {
+                outcome(round.choice, theirs)
+            };
+            update(|round| {
+                let mut round = round?;
+                if let Phase::Locked { .. } | Phase::Revealing { .. } = round.phase {
+                    round.phase = Phase::Result { theirs, outcome, at : now };
+                    round.sent = None;
+                }
+                Some(round)
+            });
+        }
+    }
+    true
+}
+
+const ROCK : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 0],
+    [0, 1, 1, 1, 0],
+    [0, 1, 1, 1, 0],
+    [0, 0, 0, 0, 0],
+];
+
+const PAPER : Frame = [
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+];
+
+const CHOICES : [Frame; 3] = [ROCK, PAPER, ICON];
+
+const WIN : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+    [1, 0, 0, 0, 1],
+    [0, 1, 1, 1, 0],
+];
+
+const LOSE : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+const DRAW : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+    [1, 1, 1, 1, 1],
+    [0, 0, 0, 0, 0],
+];
+
+const CHEATED : Frame = [
+    [1, 0, 0, 0, 1],
+    [0, 1, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+const NO_OPPONENT : Frame = [
+    [0, 1, 1, 1, 0],
+    [0, 0, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+fn lit(frame : Frame, level : u8) -> Frame {
+    frame.map(|row| row.map(|led| led * level))
+}
+
+/// One round, from choosing to the outcome.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    let round = update(|round| {
+        let mut round = match round {
+            Some(round) if step > 0 => round,
+            // NOTE: a new round, or the launcher cut the last one short
+            _ => Round { choice : 0, nonce : [0; NONCE_LEN], opponent : None, phase : Phase::Choosing, sent : None, drawn : now },
+        };
+        round.drawn = now;
+        match round.phase {
+            Phase::Locked { at } | Phase::Revealing { at } if ms_since(now, at) > WAIT_MS => {
+                round.phase = Phase::Result { theirs : 0, outcome : Outcome::NoOpponent, at : now };
+            }
+            _ => (),
+        }
+        Some(round)
+    })?;
+
+    let frame = match round.phase {
+        Phase::Choosing => lit(CHOICES[round.choice as usize], 9),
+        // NOTE: the locked choice pulses while the boards talk
+        Phase::Locked { at } | Phase::Revealing { at } =>
+            lit(CHOICES[round.choice as usize], if (ms_since(now, at) / 300).is_multiple_of(2) { 2 } else { 5 }),
+        Phase::Result { theirs, outcome, at } => {
+            let elapsed = ms_since(now, at);
+            if elapsed >= RESULT_MS {
+                match outcome {
+                    Outcome::NoOpponent => log!("rps: nobody to play"),
+                    Outcome::Cheated => log!(Level::Warn, "rps: the opponent's reveal doesn't match its commitment"),
+                    _ => log!(
+                        "rps: {} against {}, {}",
+                        NAMES[round.choice as usize], NAMES[theirs as usize],
+                        match outcome { Outcome::Win => "won", Outcome::Lose => "lost", _ => "draw" }
+                    ),
+                }
+                return None;
+            }
+            match outcome {
+                Outcome::NoOpponent => lit(NO_OPPONENT, 9),
+                Outcome::Cheated    => lit(CHEATED, 9),
+                _ if elapsed < THEIRS_MS => lit(CHOICES[theirs as usize], 9),
+                Outcome::Win        => lit(WIN, 9),
+                Outcome::Lose       => lit(LOSE, 9),
+                Outcome::Draw       => lit(DRAW, 9),
+            }
+        }
+    };
+    Some((frame, POLL_MS))
+}
@@ -0,0 +1,109 @@
+//! Oscilloscope: ring 0 of the edge connector sampled by the SAADC and drawn as a trace
+//! scrolling across the matrix, see `fun_core::scope`.
+//!
+//! supply_monitor owns the SAADC and the pin, see soil.rs, so while the app is on screen it
+//! comes round every `FRAME_MS` for a burst, paced on the DWT cycle counter. Between the
+//! bursts nothing is sampled, at the short intervals a column is a glimpse of the signal.
+//! A makes the interval shorter, B longer. `scope` on the console streams the raw samples of
+//! every burst as lines too, until it is sent again.
+//!
+//! NOTE: a conversion takes around 180 µs with the oversampling battery.rs sets up, so the
+//! shortest interval, 250 µs, is about as fast as it goes.
+
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::Mutex;
+use fun_core::scope::{Trace, PER_COLUMN};
+use heapless::{String, Vec};
+use crate::apps::Input;
+use crate::bench;
+use crate::console::LINE_LEN;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::log;
+use crate::mono::{Instant, Mono};
+
+const FRAME_MS : u64 = 50;
+// NOTE: how often supply_monitor looks whether the app came on screen
+const IDLE_POLL_MS : u64 = 500;
+const ACTIVE_MS : u64 = 500;
+const CYCLES_PER_US : u32 = 64;
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 0, 0, 0],
+    [1, 0, 1, 0, 1],
+    [0, 0, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+];
+
+static TRACE : Mutex<RefCell<Trace>> = Mutex::new(RefCell::new(Trace::new()));
+static DRAWN : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+static STREAMING : AtomicBool = AtomicBool::new(false);
+
+/// True while the app is on screen, supply_monitor samples for it then.
+pub fn active(now : Instant) -> bool {
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).get())
+        .is_some_and(|drawn| now.checked_duration_since(drawn).map_or(0, |elapsed| elapsed.to_millis()) < ACTIVE_MS)
+}
+
+/// How long supply_monitor waits before it comes round again.
+pub fn poll_ms(now : Instant) -> u64 {
+    if active(now) { FRAME_MS } else { IDLE_POLL_MS }
+}
+
+/// Turns the streaming over serial on or off, true when it is on now.
+pub fn toggle_streaming() -> bool {
+    !STREAMING.fetch_xor(true, Ordering::Relaxed)
+}
+
+/// Takes a burst through `measure`, blocking for its length, the raw samples when they are
+/// streamed.
+pub fn capture(mut measure : impl FnMut() -> Option<u16>) -> Option<String<LINE_LEN>> {
+    let interval = cortex_m::interrupt::free(|cs| TRACE.borrow(cs).borrow().interval_us()) * CYCLES_PER_US;
+    let start = bench::cycles();
+    let mut burst = Vec::<u16, PER_COLUMN>::new();
+    for i in 0..PER_COLUMN as u32 {
+        while bench::cycles().wrapping_sub(start) < i * interval {}
+        let Some(sample) = measure() else { return None };
+        let _ = burst.push(sample);
+    }
+    cortex_m::interrupt::free(|cs| {
+        let mut trace = TRACE.borrow(cs).borrow_mut();
+        for sample in &burst {
+            trace.push(*sample);
+        }
+    });
+    if !STREAMING.load(Ordering::Relaxed) {
+        return None;
+    }
+    let mut line = String::from("scope");
+    for sample in &burst {
+        let _ = write!(line, " {}", sample);
+    }
+    Some(line)
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let faster = match input {
+        Input::Button(Button::A) => true,
+        Input::Button(Button::B) => false,
+        _ => return false,
+    };
+    let interval = cortex_m::interrupt::free(|cs| {
+        let mut trace = TRACE.borrow(cs).borrow_mut();
+        trace.step(faster).then(|| trace.interval_us())
+    });
+    if let Some(interval) = interval {
+        log!("scope {} us between samples", interval);
+    }
+    true
+}
+
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).set(Some(now)));
+    let frame = cortex_m::interrupt::free(|cs| TRACE.borrow(cs).borrow().frame());
+    (step == 0).then_some((frame, FRAME_MS as u32))
+}
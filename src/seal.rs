@@ -0,0 +1,225 @@
+//! Sealed radio packets: AES-CCM encrypted and authenticated with a key the boards of a class
+//! share, so a neighbouring classroom's boards can't inject packets by just sending them.
+//!
+//! The class key is set with the `key` console command and kept in the key-value store, until
+//! then nothing is sealed or opened. The AES key is hashed from it on the ECB (Davies-Meyer over
+//! its 16 byte blocks), sealing and opening run on the CCM peripheral. The same hash is handed
+//! out as `digest`, for the packets that commit to something without telling it yet. A sealed
+//! payload is
+//!
+//! `[SEALED, sender (2), counter (4), ciphertext (..PLAIN_LEN), MIC (4)]`
+//!
+//! The sender and counter make up the nonce, so changing either breaks the MIC, and a nonce
+//! used twice under one key would give the keystream away. The counter only ever grows: the
+//! store keeps a high-water mark `RESERVE` counters ahead of the last one used, written before
+//! any of them goes out, and a boot carries on from it. A factory reset keeps it, see
+//! `storage::factory_reset`. The last counter of the last few senders is remembered, a recorded
+//! packet played back later is dropped.
+//!
+//! `mac` is an HMAC over the same hash, keyed with a second key hashed from the secret, for
+//! the commands of auth.rs.
+
+use heapless::{Deque, Vec};
+use microbit::hal::ccm::{Ccm, CcmData, DataRate};
+use microbit::hal::ecb::Ecb;
+use microbit::pac::{AAR, CCM, ECB};
+use crate::kv;
+use crate::radio::{Payload, PAYLOAD_LEN};
+
+// NOTE: first payload byte, like the kind byte of the packets inside
+const SEALED : u8 = b'S';
+const HEADER_LEN : usize = 7;
+const MIC_LEN : usize = 4;
+pub const PLAIN_LEN : usize = PAYLOAD_LEN - HEADER_LEN - MIC_LEN;
+// NOTE: the packets of protocol.rs are sized for it
+const _ : () = assert!(PLAIN_LEN == crate::protocol::MAX_LEN);
+
+// NOTE: S0, length and S1 in front of the payload, the packet layout the CCM works on
+const CCM_HEADER_LEN : usize = 3;
+// NOTE: at least 43 bytes or 16 more than the packet, whichever is larger
+const SCRATCH_LEN : usize = 43;
+const SENDERS : usize = 8;
+// NOTE: the most `mac` covers, a nonce and a console line
+const MAC_DATA_LEN : usize = 80;
+const IPAD : u8 = 0x36;
+const OPAD : u8 = 0x5c;
+// NOTE: counters taken from the store at a time, a boot skips what is left of its last batch
+const RESERVE : u32 = 4096;
+pub const MAX_SECRET_LEN : usize = kv::MAX_VALUE_LEN;
+
+pub type Digest = [u8; 16];
+
+// NOTE: the AES key and the command key
+type Keys = ([u8; 16], [u8; 16]);
+
+pub struct Seal {
+    ecb      : Ecb,
+    ccm      : Ccm,
+    keys     : Option<Keys>,
+    sender   : u16,
+    counter  : u32,
+    // NOTE: the high-water mark in the store, counters up to it may go out
+    reserved : u32,
+    // NOTE: the last counter accepted from each of the senders heard recently
+    seen     : Deque<(u16, u32), SENDERS>,
+}
+
+fn hash(ecb : &mut Ecb, data : &[u8]) -> Digest {
+    let mut compress = |hash : [u8; 16], block : [u8; 16]| {
+        let mut out = ecb.encrypt_block(hash, block).unwrap();
+        for (out, hash) in out.iter_mut().zip(hash) {
+            *out ^= hash;
+        }
+        out
+    };
+
+    let mut hash = [0; 16];
+    let mut blocks = data.chunks_exact(16);
+    for block in blocks.by_ref() {
+        hash = compress(hash, block.try_into().unwrap());
+    }
+    // NOTE: padded with 0x80 and zeros, so "key" and "key\0" hash differently
+    let rest = blocks.remainder();
+    let mut last = [0; 16];
+    last[..rest.len()].copy_from_slice(rest);
+    last[rest.len()] = 0x80;
+    compress(hash, last)
+}
+
+fn derive(ecb : &mut Ecb, secret : &[u8]) -> Keys {
+    let key = hash(ecb, secret);
+    // NOTE: the key and a label, so the two keys differ and neither gives the other away
+    let mut labelled = [0; 32];
+    labelled[..16].copy_from_slice(&key);
+    labelled[16..23].copy_from_slice(b"command");
+    (key, hash(ecb, &labelled))
+}
+
+impl Seal {
+    /// Takes the class key and the counter from the key-value store.
+    pub fn new(ecb : ECB, ccm : CCM, aar : AAR, sender : u16) -> Self {
+        let mut ecb = Ecb::init(ecb);
+        let mut secret = [0; MAX_SECRET_LEN];
+        let keys = kv::get(kv::CLASS_KEY, &mut secret)
+            .filter(|len| *len > 0)
+            .map(|len| derive(&mut ecb, &secret[..len]));
+        let counter = kv::get_u32(kv::SEAL_COUNTER).unwrap_or(0);
+        Seal {
+            ecb,
+            ccm      : Ccm::init(ccm, aar, DataRate::_1Mbit),
+            keys,
+            sender,
+            counter,
+            reserved : counter,
+            seen     : Deque::new(),
+        }
+    }
+
+    /// True once the class key is set.
+    pub fn keyed(&self) -> bool {
+        self.keys.is_some()
+    }
+
+    /// Stores `secret`, cut to `MAX_SECRET_LEN`, as the class key from now on.
+    pub fn set_key(&mut self, secret : &[u8]) {
+        let secret = &secret[..secret.len().min(MAX_SECRET_LEN)];
+        kv::set(kv::CLASS_KEY, secret);
+        self.keys = Some(derive(&mut self.ecb, secret));
+        // NOTE: the senders heard so far sealed with the old key
+        self.seen.clear();
+    }
+
+    /// The hash the key is derived with, over `data`.
+    pub fn digest(&mut self, data : &[u8]) -> Digest {
+        hash(&mut self.ecb, data)
+    }
+
+    /// HMAC of `data` with the command key, None without a class key or when `data` is longer
+    /// than `MAC_DATA_LEN`.
+    pub fn mac(&mut self, data : &[u8]) -> Option<Digest> {
+        let (_, mac_key) = self.keys?;
+        let mut inner = Vec::<u8, { 16 + MAC_DATA_LEN }>::new();
+        let _ = inner.extend_from_slice(&mac_key.map(|byte| byte ^ IPAD));
+        inner.extend_from_slice(data).ok()?;
+        let mut outer = [0; 32];
+        outer[..16].copy_from_slice(&mac_key.map(|byte| byte ^ OPAD));
+        outer[16..].copy_from_slice(&hash(&mut self.ecb, &inner));
+        Some(hash(&mut self.ecb, &outer))
+    }
+
+    fn nonce(key : [u8; 16], sender : u16, counter : u32) -> CcmData {
+        let [s0, s1] = sender.to_le_bytes();
+        let [c0, c1, c2, c3] = counter.to_le_bytes();
+        CcmData::new(key, [s0, s1, c0, c1, c2, c3, 0, 0])
+    }
+
+    /// Seals `plain`, cut to `PLAIN_LEN`, for broadcasting. None without a class key, and once
+    /// the counter ran out.
+    pub fn seal(&mut self, plain : &[u8]) -> Option<Payload> {
+        let (key, _) = self.keys?;
+        let len = plain.len().min(PLAIN_LEN);
+        // NOTE: the CCM passes an empty packet through without a MIC
+        if len == 0 {
+            return None;
+        }
+        self.counter = self.counter.checked_add(1)?;
+        if self.counter > self.reserved {
+            self.reserved = self.counter.saturating_add(RESERVE);
+            kv::set_u32(kv::SEAL_COUNTER, self.reserved);
+        }
+
+        let mut clear = [0; CCM_HEADER_LEN + PLAIN_LEN];
+        clear[1] = len as u8;
+        clear[CCM_HEADER_LEN..][..len].copy_from_slice(&plain[..len]);
+        let mut cipher = [0; CCM_HEADER_LEN + PLAIN_LEN + MIC_LEN];
+        let mut nonce = Seal::nonce(key, self.sender, self.counter);
+        self.ccm.encrypt_packet(&mut nonce, &clear, &mut cipher, &mut [0; SCRATCH_LEN]).ok()?;
+
+        let mut payload = Payload::new();
+        let _ = payload.push(SEALED);
+        let _ = payload.extend_from_slice(&self.sender.to_le_bytes());
+        let _ = payload.extend_from_slice(&self.counter.to_le_bytes());
+        let _ = payload.extend_from_slice(&cipher[CCM_HEADER_LEN..][..len + MIC_LEN]);
+        Some(payload)
+    }
+
+    /// The sender and plaintext of a payload sealed with our key, None for anything else
+    /// including packets heard before, and for everything without a class key.
+    pub fn open(&mut self, payload : &[u8]) -> Option<(u16, Payload)> {
+        let (key, _) = self.keys?;
+        let [SEALED, s0, s1, c0, c1, c2, c3, sealed @ ..] = payload else {
+            return None;
+        };
+        if sealed.len() <= MIC_LEN {
+            return None;
+        }
+        let sender = u16::from_le_bytes([*s0, *s1]);
+        let counter = u32::from_le_bytes([*c0, *c1, *c2, *c3]);
+        let last = self.seen.iter().find(|(seen, _)| *seen == sender).map(|(_, last)| *last);
+        if last.is_some_and(|last| counter <= last) {
+            return None;
+        }
+
+        let mut cipher = [0; CCM_HEADER_LEN + PLAIN_LEN + MIC_LEN];
+        cipher[1] = sealed.len() as u8;
+        cipher[CCM_HEADER_LEN..][..sealed.len()].copy_from_slice(sealed);
+        let mut clear = [0; CCM_HEADER_LEN + PLAIN_LEN];
+        let mut nonce = Seal::nonce(key, sender, counter);
+        self.ccm.decrypt_packet(&mut nonce, &mut clear, &cipher, &mut [0; SCRATCH_LEN]).ok()?;
+
+        self.remember(sender, counter);
+        let plain = Payload::from_slice(&clear[CCM_HEADER_LEN..][..sealed.len() - MIC_LEN]).ok()?;
+        Some((sender, plain))
+    }
+
+    fn remember(&mut self, sender : u16, counter : u32) {
+        if let Some(seen) = self.seen.iter_mut().find(|(seen, _)| *seen == sender) {
+            seen.1 = counter;
+            return;
+        }
+        if self.seen.is_full() {
+            self.seen.pop_front();
+        }
+        let _ = self.seen.push_back((sender, counter));
+    }
+}
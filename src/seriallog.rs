@@ -0,0 +1,43 @@
+//! Log records on the serial console, for when no debugger is attached.
+//!
+//! Logging happens from every priority while the UART belongs to the console, so with the
+//! uart route on (`route` console command) records are queued here and the `serial_log`
+//! task writes them out.
+
+use core::cell::RefCell;
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use heapless::{Deque, String};
+use crate::logging::Level;
+use crate::timesync;
+
+pub const LINE_LEN : usize = 80;
+pub type Line = String<LINE_LEN>;
+
+const QUEUED : usize = 8;
+
+static QUEUE : Mutex<RefCell<Deque<Line, QUEUED>>> = Mutex::new(RefCell::new(Deque::new()));
+
+/// Queues a record, when the queue is full the oldest record is dropped.
+pub fn queue(level : Level, s : &str) {
+    let us = timesync::now_us();
+    let mut line = Line::new();
+    let _ = write!(line, "{}.{:06} {} ", us / 1_000_000, us % 1_000_000, level.prefix());
+    for c in s.chars() {
+        if line.push(c).is_err() {
+            break;
+        }
+    }
+
+    cortex_m::interrupt::free(|cs| {
+        let mut queue = QUEUE.borrow(cs).borrow_mut();
+        if queue.is_full() {
+            queue.pop_front();
+        }
+        let _ = queue.push_back(line);
+    });
+}
+
+pub fn next() -> Option<Line> {
+    cortex_m::interrupt::free(|cs| QUEUE.borrow(cs).borrow_mut().pop_front())
+}
@@ -0,0 +1,79 @@
+//! Shooter: a ship on the bottom row clears the invaders coming down at it, see
+//! `fun_core::shooter` for the game.
+//!
+//! A and B move the ship left and right, so does tilting the board, A+B fires. The invaders
+//! shot down are the score for the high-score table.
+//!
+//! NOTE: pressing A+B lands one of the two first, so a shot also moves the ship a column.
+//! While a game runs it takes A+B from the launcher, touching the logo ends it.
+
+use core::cell::Cell;
+use critical_section::Mutex;
+use fun_core::shooter::{Control, Game};
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::highscores::{self, GameId};
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::rng::Rng;
+use crate::speaker::Speaker;
+
+pub const GAME : GameId = 2;
+
+pub const ICON : Frame = [
+    [0, 1, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+];
+
+// NOTE: the button interrupt and idle both move the game on
+static GAME_STATE : Mutex<Cell<Option<Game>>> = Mutex::new(Cell::new(None));
+
+fn update(next : impl FnOnce(Option<Game>) -> Option<Game>) -> Option<Game> {
+    critical_section::with(|cs| {
+        let game = next(GAME_STATE.borrow(cs).get());
+        GAME_STATE.borrow(cs).set(game);
+        game
+    })
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let control = match input {
+        Input::Button(Button::A)           => Control::Left,
+        Input::Button(Button::B)           => Control::Right,
+        Input::Button(Button::AB)          => Control::Fire,
+        Input::Button(Button::Logo)        => Control::Quit,
+        Input::Tilt { x, .. }              => Control::Tilt(x),
+        Input::Shake | Input::LongPress(_) => return false,
+    };
+    let mut taken = false;
+    update(|game| {
+        let mut game = game?;
+        taken = game.control(control, &Mono, &mut Speaker);
+        Some(game)
+    });
+    taken
+}
+
+/// One game, from the first invader to the scrolled score.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let game = update(|game| Some(match game {
+        Some(mut game) if step > 0 => {
+            if game.advance(&Mono, &mut Rng, &mut Speaker) {
+                log!("shooter over with {} invaders down", game.score());
+            }
+            game
+        }
+        // NOTE: a new game, or the launcher cut the last one short
+        _ => Game::new(&Mono, &mut Rng),
+    }))?;
+
+    let frame = game.frame(&Mono, &mut Speaker);
+    if frame.is_none() {
+        highscores::finish(GAME, game.score());
+    }
+    frame
+}
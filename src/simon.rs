@@ -0,0 +1,77 @@
+//! Simon: the board plays a growing sequence of quadrants, each with its own tone, and the
+//! player repeats it, see `fun_core::simon` for the game.
+//!
+//! Each quadrant is answered with its own input: touching the logo for the top left, a shake
+//! for the top right, A and B for the bottom left and right. The rounds completed go to the
+//! high-score table.
+
+use core::cell::Cell;
+use critical_section::Mutex;
+use fun_core::simon::Game;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::highscores::{self, GameId};
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::rng::Rng;
+use crate::speaker::Speaker;
+
+pub const GAME : GameId = 1;
+
+pub const ICON : Frame = [
+    [1, 1, 0, 0, 0],
+    [1, 1, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 1, 1],
+    [0, 0, 0, 1, 1],
+];
+
+// NOTE: the input handlers and idle both move the game on
+static GAME_STATE : Mutex<Cell<Option<Game>>> = Mutex::new(Cell::new(None));
+
+fn update(next : impl FnOnce(Option<Game>) -> Option<Game>) -> Option<Game> {
+    critical_section::with(|cs| {
+        let game = next(GAME_STATE.borrow(cs).get());
+        GAME_STATE.borrow(cs).set(game);
+        game
+    })
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let quadrant = match input {
+        Input::Button(Button::Logo) => 0,
+        Input::Shake                => 1,
+        Input::Button(Button::A)    => 2,
+        Input::Button(Button::B)    => 3,
+        Input::Button(Button::AB)   => return false,
+        Input::LongPress(_)         => return false,
+        Input::Tilt { .. }          => return false,
+    };
+    let mut taken = false;
+    update(|game| {
+        let mut game = game?;
+        taken = game.answer(quadrant, &Mono, &mut Speaker);
+        Some(game)
+    });
+    taken
+}
+
+/// One game, from the first round to the scrolled score.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let game = update(|game| Some(match game {
+        Some(mut game) if step > 0 => {
+            game.advance(&Mono, &mut Speaker);
+            game
+        }
+        // NOTE: a new game, or the launcher cut the last one short
+        _ => Game::new(&Mono, &mut Rng),
+    }))?;
+
+    let frame = game.frame(&Mono, &mut Speaker);
+    if frame.is_none() {
+        log!("simon over after {} rounds", game.score());
+        highscores::finish(GAME, game.score());
+    }
+    frame
+}
@@ -0,0 +1,253 @@
+//! Etch-a-sketch: tilting the board moves a cursor over a canvas larger than the display,
+//! leaving a trail behind it.
+//!
+//! B lifts and lowers the pen, a shake clears the canvas and a long press of A saves the
+//! drawing to the key-value store. The last `SLOTS` drawings saved are kept, the gallery app
+//! shows them as idle screens, panning round each one with A and B stepping through them.
+
+use core::cell::Cell;
+use core::sync::atomic::{AtomicI32, Ordering};
+use cortex_m::interrupt::Mutex;
+use crate::apps::Input;
+use crate::canvas::Canvas;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::kv::{self, Key};
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::scroll::{self, Text};
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 1],
+    [0, 0, 0, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 0, 0],
+    [1, 1, 0, 0, 0],
+];
+
+pub const GALLERY_ICON : Frame = [
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 1, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+];
+
+// NOTE: stored as a bit per cell after the width and height, which has to fit a kv value
+const SIZE : usize = 10;
+const STORED_LEN : usize = 2 + (SIZE * SIZE).div_ceil(8);
+const SLOTS : usize = 8;
+
+const TILT_MG : i32 = 200;
+const MOVE_MS : u64 = 150;
+const SAVED_MS : u32 = 600;
+const TRAIL : u8 = 4;
+const POLL_MS : u32 = 20;
+const TOUR_MS : u32 = 150;
+
+#[derive(Clone, Copy)]
+struct Sketch {
+    canvas : Canvas,
+    x      : usize,
+    y      : usize,
+    pen    : bool,
+    tilt   : (i32, i32),
+    moved  : Instant,
+    // NOTE: set by the long press, the drawing is saved from idle
+    save   : bool,
+}
+
+// NOTE: the inputs come from input_poll and the button interrupt, idle draws
+static SKETCH : Mutex<Cell<Option<Sketch>>> = Mutex::new(Cell::new(None));
+
+fn update(next : impl FnOnce(Option<Sketch>) -> Option<Sketch>) -> Option<Sketch> {
+    cortex_m::interrupt::free(|cs| {
+        let sketch = next(SKETCH.borrow(cs).get());
+        SKETCH.borrow(cs).set(sketch);
+        sketch
+    })
+}
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+fn key(slot : usize) -> Key {
+    kv::DRAWINGS + slot as Key
+}
+
+fn save_drawing(canvas : &Canvas) -> usize {
+    let mut bytes = [0; STORED_LEN];
+    bytes[0] = SIZE as u8;
+    bytes[1] = SIZE as u8;
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            if canvas.get(x, y) != 0 {
+                let i = y * SIZE + x;
+                bytes[2 + i / 8] |= 1 << (i % 8);
+            }
+        }
+    }
+    // NOTE: round the slots, the oldest drawing gives way
+    let saved = kv::get_u32(kv::DRAWINGS_SAVED).unwrap_or(0);
+    let slot = saved as usize % SLOTS;
+    kv::set(key(slot), &bytes);
+    kv::set_u32(kv::DRAWINGS_SAVED, saved + 1);
+    slot
+}
+
+fn load(slot : usize) -> Option<Canvas> {
+    let mut bytes = [0; kv::MAX_VALUE_LEN];
+    let len = kv::get(key(slot), &mut bytes)?;
+    let [width, height, bits @ ..] = &bytes[..len] else { return None };
+    let (width, height) = (*width as usize, *height as usize);
+    if bits.len() < (width * height).div_ceil(8) {
+        return None;
+    }
+    let mut canvas = Canvas::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            if bits[i / 8] & (1 << (i % 8)) != 0 {
+                canvas.set(x, y, TRAIL);
+            }
+        }
+    }
+    Some(canvas)
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let mut taken = true;
+    update(|sketch| {
+        let mut sketch = sketch?;
+        match input {
+            Input::Tilt { x, y }        => sketch.tilt = (x, y),
+            Input::Shake                => sketch.canvas.clear(),
+            Input::LongPress(Button::A) => sketch.save = true,
+            Input::Button(Button::B)    => sketch.pen = !sketch.pen,
+            Input::Button(Button::A)    => (),
+            // NOTE: A+B still opens the launcher
+            _                           => taken = false,
+        }
+        Some(sketch)
+    });
+    taken
+}
+
+fn roll(mut sketch : Sketch, now : Instant) -> Sketch {
+    let (tx, ty) = sketch.tilt;
+    if tx.abs().max(ty.abs()) < TILT_MG || ms_since(now, sketch.moved) < MOVE_MS {
+        return sketch;
+    }
+    // NOTE: both axes at once, so lines can run diagonally
+    if tx.abs() >= TILT_MG {
+        sketch.x = if tx > 0 { (sketch.x + 1).min(SIZE - 1) } else { sketch.x.saturating_sub(1) };
+    }
+    if ty.abs() >= TILT_MG {
+        sketch.y = if ty > 0 { (sketch.y + 1).min(SIZE - 1) } else { sketch.y.saturating_sub(1) };
+    }
+    sketch.moved = now;
+    sketch
+}
+
+const SAVED : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 9],
+    [0, 0, 0, 9, 0],
+    [9, 0, 9, 0, 0],
+    [0, 9, 0, 0, 0],
+];
+
+/// The canvas with the cursor, for as long as the app runs.
+pub fn draw(_step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    // NOTE: the drawing stays when the launcher opens, it is only lost to a shake or a reset
+    let mut save = false;
+    let sketch = update(|sketch| {
+        let mut sketch = match sketch {
+            Some(sketch) => roll(sketch, now),
+            None => Sketch {
+                canvas : Canvas::new(SIZE, SIZE),
+                x      : SIZE / 2,
+                y      : SIZE / 2,
+                pen    : true,
+                tilt   : (0, 0),
+                moved  : now,
+                save   : false,
+            },
+        };
+        if sketch.pen {
+            sketch.canvas.set(sketch.x, sketch.y, TRAIL);
+        }
+        save = core::mem::take(&mut sketch.save);
+        Some(sketch)
+    })?;
+
+    if save {
+        let slot = save_drawing(&sketch.canvas);
+        log!("drawing saved to slot {}", slot);
+        return Some((SAVED, SAVED_MS));
+    }
+    let mut canvas = sketch.canvas;
+    // NOTE: the cursor blinks, dimmer with the pen up
+    let on = if sketch.pen { 9 } else { 6 };
+    canvas.set(sketch.x, sketch.y, if (now.duration_since_epoch().to_millis() / 300).is_multiple_of(2) { on } else { 0 });
+    canvas.follow(sketch.x, sketch.y);
+    Some((canvas.frame(), POLL_MS))
+}
+
+// NOTE: which of the saved drawings the gallery shows, wrapped to the saved ones when drawn
+static SHOWN : AtomicI32 = AtomicI32::new(0);
+static SHOWING : Mutex<Cell<Option<Canvas>>> = Mutex::new(Cell::new(None));
+
+pub fn gallery_on_input(input : Input, _now : Instant) -> bool {
+    let step = match input {
+        Input::Button(Button::A) => -1,
+        Input::Button(Button::B) => 1,
+        _ => return false,
+    };
+    SHOWN.fetch_add(step, Ordering::Relaxed);
+    cortex_m::interrupt::free(|cs| SHOWING.borrow(cs).set(None));
+    true
+}
+
+/// The point the window follows round the edge of the canvas at `step`.
+fn tour(canvas : &Canvas, step : usize) -> Option<(usize, usize)> {
+    let right = canvas.width().saturating_sub(1);
+    let bottom = canvas.height().saturating_sub(1);
+    let side = |start : usize, len : usize| step.checked_sub(start).filter(|i| *i < len);
+    if let Some(i) = side(0, right) {
+        Some((i, 0))
+    } else if let Some(i) = side(right, bottom) {
+        Some((right, i))
+    } else if let Some(i) = side(right + bottom, right) {
+        Some((right - i, bottom))
+    } else {
+        side(2 * right + bottom, bottom).map(|i| (0, bottom - i))
+    }
+}
+
+/// One lap round a saved drawing.
+pub fn gallery(step : usize) -> Option<(Frame, u32)> {
+    let showing = cortex_m::interrupt::free(|cs| SHOWING.borrow(cs).get());
+    let canvas = match showing {
+        Some(canvas) if step > 0 => canvas,
+        _ => {
+            let stored = |slot : &usize| kv::get(key(*slot), &mut [0; kv::MAX_VALUE_LEN]).is_some();
+            let count = (0..SLOTS).filter(stored).count();
+            let shown = SHOWN.load(Ordering::Relaxed).rem_euclid(count.max(1) as i32) as usize;
+            let Some(canvas) = (0..SLOTS).filter(stored).nth(shown).and_then(load) else {
+                let mut text = Text::new();
+                let _ = text.push_str("no drawings");
+                return scroll::frames(&text).nth(step)
+                    .map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS));
+            };
+            cortex_m::interrupt::free(|cs| SHOWING.borrow(cs).set(Some(canvas)));
+            canvas
+        }
+    };
+    let mut canvas = canvas;
+    let (x, y) = tour(&canvas, step)?;
+    canvas.follow(x, y);
+    Some((canvas.frame(), TOUR_MS))
+}
@@ -0,0 +1,127 @@
+//! Soil moisture: a probe on ring 0 of the edge connector, read by the SAADC, see
+//! `fun_core::soil`.
+//!
+//! supply_monitor owns the SAADC, so it measures the probe too, every round: `BURST`
+//! conversions and their median, against spikes. The app shows a droplet filled to the
+//! moisture. A long press of A takes the reading as the dry point of the calibration and a
+//! long press of B as the wet one, the calibration is kept in the key-value store. When the
+//! plant turns dry `DRY` is toasted over whatever runs.
+//!
+//! NOTE: with nothing plugged into ring 0 the pin floats and reads anything, the app can't
+//! tell that from a probe.
+
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+use fun_core::soil::{self, Calibration, Watch};
+use microbit::hal::gpio::{p0::P0_02, Disconnected};
+use crate::apps::Input;
+use crate::battery::Monitor;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::kv;
+use crate::log;
+use crate::logging::Level;
+use crate::mono::Instant;
+use crate::toast;
+
+pub const BURST : usize = 5;
+const DRY_SECS : u32 = 3;
+const POLL_MS : u32 = 100;
+
+pub const ICON : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+];
+
+const DRY : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 0, 1, 0],
+    [1, 0, 0, 0, 1],
+    [1, 0, 0, 0, 1],
+    [0, 1, 1, 1, 0],
+];
+
+pub struct Probe {
+    pin   : P0_02<Disconnected>,
+    watch : Watch,
+}
+
+// NOTE: the median of the last burst, None until there is one
+static RAW : Mutex<Cell<Option<u16>>> = Mutex::new(Cell::new(None));
+// NOTE: None until the stored calibration is read, on the first reading
+static CALIBRATION : Mutex<Cell<Option<Calibration>>> = Mutex::new(Cell::new(None));
+
+fn calibration() -> Calibration {
+    if let Some(calibration) = cortex_m::interrupt::free(|cs| CALIBRATION.borrow(cs).get()) {
+        return calibration;
+    }
+    let mut bytes = [0; kv::MAX_VALUE_LEN];
+    let calibration = kv::get(kv::SOIL_CALIBRATION, &mut bytes)
+        .and_then(|len| Calibration::decode(&bytes[..len]))
+        .unwrap_or(Calibration::DEFAULT);
+    cortex_m::interrupt::free(|cs| CALIBRATION.borrow(cs).set(Some(calibration)));
+    calibration
+}
+
+fn calibrate(dry : bool) {
+    let Some(raw) = cortex_m::interrupt::free(|cs| RAW.borrow(cs).get()) else { return };
+    let mut calibration = calibration();
+    if dry {
+        calibration.dry = raw;
+    } else {
+        calibration.wet = raw;
+    }
+    kv::set(kv::SOIL_CALIBRATION, &calibration.encode());
+    cortex_m::interrupt::free(|cs| CALIBRATION.borrow(cs).set(Some(calibration)));
+    log!("soil {} point at {}", if dry { "dry" } else { "wet" }, raw);
+}
+
+/// The moisture in percent, None before the first reading.
+pub fn percent() -> Option<u8> {
+    let raw = cortex_m::interrupt::free(|cs| RAW.borrow(cs).get())?;
+    Some(calibration().percent(raw))
+}
+
+impl Probe {
+    pub fn new(pin : P0_02<Disconnected>) -> Self {
+        Probe { pin, watch : Watch::new() }
+    }
+
+    /// A single conversion through `monitor`, for the scope app.
+    pub fn measure(&mut self, monitor : &mut Monitor) -> Option<u16> {
+        monitor.measure(&mut self.pin)
+    }
+
+    /// Measures a burst through `monitor`, with a toast when the plant just turned dry.
+    pub fn sample(&mut self, monitor : &mut Monitor) {
+        let mut burst = [0; BURST];
+        for sample in &mut burst {
+            let Some(raw) = monitor.measure(&mut self.pin) else { return };
+            *sample = raw;
+        }
+        let raw = soil::median(&mut burst);
+        cortex_m::interrupt::free(|cs| RAW.borrow(cs).set(Some(raw)));
+        let percent = calibration().percent(raw);
+        if self.watch.check(percent) {
+            log!(Level::Warn, "soil dry at {}%", percent);
+            toast::icon(DRY, DRY_SECS);
+        }
+    }
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    match input {
+        Input::LongPress(Button::A) => calibrate(true),
+        Input::LongPress(Button::B) => calibrate(false),
+        _ => return false,
+    }
+    true
+}
+
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let frame = percent().map_or(DRY.map(|row| row.map(|led| led * 2)), soil::droplet);
+    (step == 0).then_some((frame, POLL_MS))
+}
@@ -0,0 +1,89 @@
+//! Square wave tones on the speaker, generated by PWM3.
+//!
+//! NOTE: free functions like ppi.rs, `init` takes the PWM3 so nothing else drives it. The games
+//! start and stop tones from wherever they run, idle or an input handler, and a tone keeps
+//! sounding until the next call. They do that through `Speaker`, the `board::Beeper` of the
+//! board.
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use fun_core::board::Beeper;
+use microbit::hal::gpio::{p0::P0_00, Disconnected, Level};
+use microbit::pac::PWM3;
+
+// NOTE: the PWM counts at 2 MHz, the 15 bit counter reaches down to about 61 Hz
+const CLOCK_HZ : u32 = 2_000_000;
+const MIN_HZ : u32 = CLOCK_HZ / 0x7fff + 1;
+
+static MUTED : AtomicBool = AtomicBool::new(false);
+// NOTE: muted for a while whatever the setting, see facedown.rs
+static HUSHED : AtomicBool = AtomicBool::new(false);
+static PLAYING_HZ : AtomicU32 = AtomicU32::new(0);
+
+// NOTE: read by the PWM's DMA, the half period compare value of the tone playing
+static mut DUTY : [u16; 1] = [0];
+
+fn pwm() -> &'static microbit::pac::pwm0::RegisterBlock {
+    unsafe { &*PWM3::ptr() }
+}
+
+pub fn init(_pwm : PWM3, pin : P0_00<Disconnected>) {
+    let pin = pin.into_push_pull_output(Level::Low).degrade();
+    let pwm = pwm();
+    pwm.psel.out[0].write(|w| unsafe { w.bits(pin.psel_bits()) });
+    pwm.mode.write(|w| w.updown().up());
+    pwm.prescaler.write(|w| w.prescaler().div_8());
+    pwm.decoder.write(|w| w.load().common().mode().refresh_count());
+    pwm.seq0.ptr.write(|w| unsafe { w.bits(core::ptr::addr_of!(DUTY) as u32) });
+    pwm.seq0.cnt.write(|w| unsafe { w.bits(1) });
+    pwm.seq0.refresh.write(|w| unsafe { w.bits(0) });
+    pwm.seq0.enddelay.write(|w| unsafe { w.bits(0) });
+}
+
+/// The sound setting, a muted speaker ignores `tone`.
+pub fn set_muted(muted : bool) {
+    MUTED.store(muted, Ordering::Relaxed);
+    if muted {
+        off();
+    }
+}
+
+/// Silences the speaker until it is called with false, leaving the sound setting alone.
+pub fn set_hushed(hushed : bool) {
+    HUSHED.store(hushed, Ordering::Relaxed);
+    if hushed {
+        off();
+    }
+}
+
+/// Plays `hz` until the next call, 0 or anything below 61 Hz is silence.
+pub fn tone(hz : u32) {
+    let hz = if hz < MIN_HZ || MUTED.load(Ordering::Relaxed) || HUSHED.load(Ordering::Relaxed) { 0 } else { hz };
+    if PLAYING_HZ.swap(hz, Ordering::Relaxed) == hz {
+        return;
+    }
+
+    let pwm = pwm();
+    if hz == 0 {
+        pwm.tasks_stop.write(|w| unsafe { w.bits(1) });
+        pwm.enable.write(|w| w.enable().disabled());
+        return;
+    }
+    let top = CLOCK_HZ / hz;
+    unsafe { DUTY[0] = (top / 2) as u16 };
+    pwm.enable.write(|w| w.enable().enabled());
+    pwm.countertop.write(|w| unsafe { w.countertop().bits(top as u16) });
+    // NOTE: once the one value sequence is done the PWM keeps repeating it
+    pwm.tasks_seqstart[0].write(|w| unsafe { w.bits(1) });
+}
+
+pub fn off() {
+    tone(0);
+}
+
+pub struct Speaker;
+
+impl Beeper for Speaker {
+    fn tone(&mut self, hz : u32) {
+        tone(hz)
+    }
+}
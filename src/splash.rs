@@ -0,0 +1,60 @@
+//! The boot splash: a ring of light spreading out from the centre, then the version scrolling
+//! by, before the app the board boots into takes the display.
+//!
+//! idle plays it, once init is done, so the radio and the sensors are up and the tasks run
+//! while it plays, it only holds up the first app. A button pressed during it cuts it short
+//! and is taken by it, the app doesn't see it. It is the package version alone, the commit
+//! would take a while to scroll by, A+B within `about::BOOT_WINDOW_MS` shows it all, the
+//! splash ends well before that.
+
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+use crate::about;
+use crate::display::Frame;
+use crate::scroll::{self, Text};
+
+const RING_MS : u32 = 80;
+// NOTE: the rings, the centre to the edge, and the two steps each takes to fade out
+const RING_FRAMES : usize = 3 + 2;
+const FADE : [u8; 3] = [9, 5, 2];
+
+static PLAYING : AtomicBool = AtomicBool::new(true);
+
+pub fn playing() -> bool {
+    PLAYING.load(Ordering::Relaxed)
+}
+
+/// Cuts the splash short, true when it was still playing.
+pub fn skip() -> bool {
+    PLAYING.swap(false, Ordering::Relaxed)
+}
+
+fn ring(step : usize) -> Frame {
+    let mut frame = [[0; 5]; 5];
+    for (y, row) in frame.iter_mut().enumerate() {
+        for (x, led) in row.iter_mut().enumerate() {
+            // NOTE: the ring of an LED, 0 in the centre and 2 around the edge
+            let ring = x.abs_diff(2).max(y.abs_diff(2));
+            *led = step.checked_sub(ring).and_then(|age| FADE.get(age)).copied().unwrap_or(0);
+        }
+    }
+    frame
+}
+
+/// The frame at `step` of the splash, None once it is over or was cut short.
+pub fn frame(step : usize) -> Option<(Frame, u32)> {
+    if !playing() {
+        return None;
+    }
+    if step < RING_FRAMES {
+        return Some((ring(step), RING_MS));
+    }
+    let version = about::version();
+    let mut text = Text::new();
+    let _ = write!(text, "v{}.{}.{}", version.major, version.minor, version.patch);
+    let frame = scroll::frames(&text).nth(step - RING_FRAMES);
+    if frame.is_none() {
+        PLAYING.store(false, Ordering::Relaxed);
+    }
+    frame.map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
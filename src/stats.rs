@@ -0,0 +1,45 @@
+//! Statistics: the usage counters and the uptime, scrolling by.
+//!
+//! The presses of A, B and both together and the logo touches come from the counters idle
+//! publishes, see usage.rs, they add up over the life of the board. The text is made at the
+//! start of every lap, so it stays put while it scrolls.
+
+use core::cell::RefCell;
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use crate::display::Frame;
+use crate::mono::Mono;
+use crate::scroll::{self, Text};
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 1],
+    [0, 0, 0, 0, 1],
+    [0, 0, 1, 0, 1],
+    [1, 0, 1, 0, 1],
+    [1, 0, 1, 0, 1],
+];
+
+static TEXT : Mutex<RefCell<Text>> = Mutex::new(RefCell::new(Text::new()));
+
+fn text() -> Text {
+    let mut text = Text::new();
+    if let Some(counters) = crate::usage::published() {
+        let _ = write!(
+            text, "A {} B {} AB {} logo {} ",
+            counters.button_a, counters.button_b, counters.chords, counters.logo);
+    }
+    let secs = Mono::now().duration_since_epoch().to_secs();
+    let _ = write!(text, "up {}h{:02}m", secs / 3600, secs / 60 % 60);
+    text
+}
+
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let text = cortex_m::interrupt::free(|cs| {
+        let mut shown = TEXT.borrow(cs).borrow_mut();
+        if step == 0 {
+            *shown = text();
+        }
+        shown.clone()
+    });
+    scroll::frames(&text).nth(step).map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
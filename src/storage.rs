@@ -0,0 +1,338 @@
+//! Settings that survive a reset, kept in their own flash page.
+//!
+//! The page holds a header `[magic, version, len, crc]` followed by the encoded settings.
+//! New fields are only ever appended to the encoding, so settings written by an older
+//! firmware decode as far as they go and the fields added since keep their defaults. Settings
+//! written by a newer firmware are checked over their whole length, then the fields this one
+//! doesn't know are ignored.
+//! `VERSION` is bumped whenever a field changes meaning, `migrate` then converts the old one.
+
+use fun_core::climate::Limits;
+use fun_core::image;
+use fun_core::quiet::{Hours, DAY_MIN};
+use crate::facedown;
+use crate::flash::{self, PAGE_SIZE, SETTINGS_PAGE};
+use crate::kv;
+use crate::launcher::Boot;
+use crate::log;
+use crate::logging::Level;
+use crate::attendance;
+use crate::beacon;
+use crate::car;
+use crate::motor;
+use crate::quiz;
+use crate::scroll::{Direction, Style};
+use crate::timesync::Role;
+use crate::transport::Link;
+
+// "SETT"
+const MAGIC : u32 = 0x5454_4553;
+const VERSION : u32 = 1;
+const HEADER_WORDS : usize = 4;
+const ENCODED_LEN : usize = 44;
+// NOTE: however long a newer firmware's encoding, it is in the one page
+const MAX_LEN : usize = PAGE_SIZE as usize - 4 * HEADER_WORDS;
+
+#[derive(Clone, Copy)]
+pub struct Calibration {
+    pub accel_offset       : [i16; 3],
+    // NOTE: in the 0.25 °C steps of the TEMP peripheral
+    pub temperature_offset : i16,
+}
+
+#[derive(Clone, Copy)]
+pub struct Settings {
+    pub brightness    : u8,
+    pub sound         : bool,
+    pub radio_group   : u8,
+    pub default_mode  : u8,
+    pub calibration   : Calibration,
+    // NOTE: of the ring 2 comparator, in sixteenths of VDD
+    pub threshold     : u8,
+    // NOTE: of the idle playlist, see playlist.rs
+    pub playlist_secs : u8,
+    pub shuffle       : bool,
+    // NOTE: how scrolled messages go by unless they say otherwise
+    pub scroll        : Style,
+    // NOTE: the display stays dark, see display.rs
+    pub stealth       : bool,
+    // NOTE: whether the board sends or follows the time, see timesync.rs
+    pub time_sync     : Role,
+    // NOTE: passing on the messages flooded over the radio, see relay.rs
+    pub relay         : bool,
+    // NOTE: taking the button presses of the peer, see remote.rs
+    pub remote        : bool,
+    // NOTE: between the telemetry broadcasts, 0 is off, see telemetry.rs
+    pub telemetry_secs : u8,
+    // NOTE: the port frames go to the host over, see transport.rs
+    pub link          : Link,
+    // NOTE: the console commands that change the board need a tag, see auth.rs
+    pub auth          : bool,
+    // NOTE: the app the board boots into, see launcher.rs
+    pub boot          : Boot,
+    // NOTE: evening out the wear of the LEDs, see display.rs
+    pub wear          : bool,
+    // NOTE: turning the frames round while the board is upside down, see display.rs
+    pub flip          : bool,
+    // NOTE: what lying face down does, see facedown.rs
+    pub facedown      : facedown::Mode,
+    // NOTE: settings and updates refused until the knock pattern, see lock.rs
+    pub lock          : bool,
+    // NOTE: the alarm limits of the greenhouse monitor, see greenhouse.rs
+    pub greenhouse    : Limits,
+    // NOTE: what drives the motors, and whether P12 to P15 are theirs, see motor.rs
+    pub motor         : motor::Control,
+    // NOTE: the part the board plays in a radio controlled car, see car.rs
+    pub car           : car::Role,
+    // NOTE: the BLE beacon frame advertised, see beacon.rs
+    pub beacon        : beacon::Kind,
+    // NOTE: the part the board plays in taking attendance, see attendance.rs
+    pub attendance    : attendance::Role,
+    // NOTE: whether the board hosts the quiz buzzer, see quiz.rs
+    pub quiz          : quiz::Role,
+    // NOTE: alerting when another board stays too close, see proximity.rs
+    pub proximity     : bool,
+    // NOTE: the quiet hours, None is off, see dnd.rs
+    pub dnd           : Option<Hours>,
+}
+
+impl Settings {
+    pub const DEFAULT : Settings = Settings {
+        brightness    : 9,
+        sound         : true,
+        radio_group   : 0,
+        default_mode  : 0,
+        calibration   : Calibration { accel_offset : [0; 3], temperature_offset : 0 },
+        threshold     : crate::comparator::DEFAULT_SIXTEENTHS,
+        playlist_secs : crate::playlist::DEFAULT_SECS,
+        shuffle       : false,
+        scroll        : Style::DEFAULT,
+        stealth       : false,
+        time_sync     : Role::Off,
+        relay         : false,
+        remote        : false,
+        telemetry_secs : 0,
+        link          : Link::Off,
+        auth          : false,
+        boot          : Boot::Last,
+        wear          : false,
+        flip          : false,
+        facedown      : facedown::Mode::Off,
+        lock          : false,
+        greenhouse    : Limits::DEFAULT,
+        motor         : motor::Control::Off,
+        car           : car::Role::Off,
+        beacon        : beacon::Kind::Off,
+        attendance    : attendance::Role::Off,
+        quiz          : quiz::Role::Player,
+        proximity     : false,
+        dnd           : None,
+    };
+
+    fn encode(&self) -> [u8; ENCODED_LEN] {
+        let mut bytes = [0; ENCODED_LEN];
+        bytes[0] = self.brightness;
+        bytes[1] = self.sound as u8;
+        bytes[2] = self.radio_group;
+        bytes[3] = self.default_mode;
+        for (i, offset) in self.calibration.accel_offset.iter().enumerate() {
+            bytes[4 + 2 * i..6 + 2 * i].copy_from_slice(&offset.to_le_bytes());
+        }
+        bytes[10..12].copy_from_slice(&self.calibration.temperature_offset.to_le_bytes());
+        bytes[12] = self.threshold;
+        bytes[13] = self.playlist_secs;
+        bytes[14] = self.shuffle as u8;
+        bytes[15] = self.scroll.step_ms.min(u8::MAX as u32) as u8;
+        bytes[16] = self.scroll.direction as u8;
+        bytes[17] = self.scroll.repeat;
+        bytes[18] = self.stealth as u8;
+        bytes[19] = self.time_sync as u8;
+        bytes[20] = self.relay as u8;
+        bytes[21] = self.remote as u8;
+        bytes[22] = self.telemetry_secs;
+        bytes[23] = self.link as u8;
+        bytes[24] = self.auth as u8;
+        bytes[25] = self.boot.to_u8();
+        bytes[26] = self.wear as u8;
+        bytes[27] = self.flip as u8;
+        bytes[28] = self.facedown as u8;
+        bytes[29] = self.lock as u8;
+        bytes[30] = self.greenhouse.temperature.0 as u8;
+        bytes[31] = self.greenhouse.temperature.1 as u8;
+        bytes[32] = self.greenhouse.humidity.0;
+        bytes[33] = self.greenhouse.humidity.1;
+        bytes[34] = self.motor as u8;
+        bytes[35] = self.car as u8;
+        bytes[36] = self.beacon as u8;
+        bytes[37] = self.attendance as u8;
+        bytes[38] = self.quiz as u8;
+        bytes[39] = self.proximity as u8;
+        // NOTE: both ends past the day when off
+        let (start, end) = self.dnd.map_or((u16::MAX, u16::MAX), |hours| (hours.start_min, hours.end_min));
+        bytes[40..42].copy_from_slice(&start.to_le_bytes());
+        bytes[42..44].copy_from_slice(&end.to_le_bytes());
+        bytes
+    }
+
+    /// Decodes as many fields as `bytes` holds, the rest keep their defaults.
+    fn decode(bytes : &[u8]) -> Settings {
+        let mut settings = Settings::DEFAULT;
+        let byte = |i : usize| bytes.get(i).copied();
+        let i16_at = |i : usize| Some(i16::from_le_bytes([byte(i)?, byte(i + 1)?]));
+        let u16_at = |i : usize| Some(u16::from_le_bytes([byte(i)?, byte(i + 1)?]));
+
+        if let Some(brightness) = byte(0) { settings.brightness = brightness.min(9) }
+        if let Some(sound) = byte(1) { settings.sound = sound != 0 }
+        if let Some(group) = byte(2) { settings.radio_group = group }
+        if let Some(mode) = byte(3) { settings.default_mode = mode }
+        for (axis, offset) in settings.calibration.accel_offset.iter_mut().enumerate() {
+            if let Some(stored) = i16_at(4 + 2 * axis) { *offset = stored }
+        }
+        if let Some(offset) = i16_at(10) { settings.calibration.temperature_offset = offset }
+        if let Some(threshold) = byte(12) { settings.threshold = threshold.clamp(1, 15) }
+        if let Some(secs) = byte(13) { settings.playlist_secs = secs }
+        if let Some(shuffle) = byte(14) { settings.shuffle = shuffle != 0 }
+        if let Some(ms) = byte(15).filter(|ms| *ms > 0) { settings.scroll.step_ms = ms as u32 }
+        if let Some(direction) = byte(16).and_then(Direction::from_u8) { settings.scroll.direction = direction }
+        if let Some(repeat) = byte(17) { settings.scroll.repeat = repeat.max(1) }
+        if let Some(stealth) = byte(18) { settings.stealth = stealth != 0 }
+        if let Some(role) = byte(19).and_then(Role::from_u8) { settings.time_sync = role }
+        if let Some(relay) = byte(20) { settings.relay = relay != 0 }
+        if let Some(remote) = byte(21) { settings.remote = remote != 0 }
+        if let Some(secs) = byte(22) { settings.telemetry_secs = secs }
+        if let Some(link) = byte(23).and_then(Link::from_u8) { settings.link = link }
+        if let Some(auth) = byte(24) { settings.auth = auth != 0 }
+        if let Some(boot) = byte(25).and_then(Boot::from_u8) { settings.boot = boot }
+        if let Some(wear) = byte(26) { settings.wear = wear != 0 }
+        if let Some(flip) = byte(27) { settings.flip = flip != 0 }
+        if let Some(mode) = byte(28).and_then(facedown::Mode::from_u8) { settings.facedown = mode }
+        if let Some(lock) = byte(29) { settings.lock = lock != 0 }
+        if let (Some(low), Some(high)) = (byte(30), byte(31)) { settings.greenhouse.temperature = (low as i8, high as i8) }
+        if let (Some(low), Some(high)) = (byte(32), byte(33)) { settings.greenhouse.humidity = (low, high) }
+        if let Some(control) = byte(34).and_then(motor::Control::from_u8) { settings.motor = control }
+        if let Some(role) = byte(35).and_then(car::Role::from_u8) { settings.car = role }
+        if let Some(kind) = byte(36).and_then(beacon::Kind::from_u8) { settings.beacon = kind }
+        if let Some(role) = byte(37).and_then(attendance::Role::from_u8) { settings.attendance = role }
+        if let Some(role) = byte(38).and_then(quiz::Role::from_u8) { settings.quiz = role }
+        if let Some(proximity) = byte(39) { settings.proximity = proximity != 0 }
+        if let (Some(start_min), Some(end_min)) = (u16_at(40), u16_at(42)) {
+            settings.dnd = (start_min < DAY_MIN && end_min < DAY_MIN).then_some(Hours { start_min, end_min });
+        }
+        settings
+    }
+}
+
+/// Converts settings written with an older `VERSION`.
+fn migrate(version : u32, bytes : &[u8]) -> Option<Settings> {
+    match version {
+        VERSION => Some(Settings::decode(bytes)),
+        // NOTE: nothing older than version 1 was ever written
+        _ => None,
+    }
+}
+
+pub fn crc32(bytes : &[u8]) -> u32 {
+    let mut crc = 0xffff_ffff_u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Loads the stored settings, or the defaults when there are none or they don't check out.
+pub fn load() -> Settings {
+    let word = |i : usize| flash::read_word(SETTINGS_PAGE + 4 * i as u32);
+    if word(0) != MAGIC {
+        log!("no stored settings, using defaults");
+        return Settings::DEFAULT;
+    }
+
+    let version = word(1);
+    let len = (word(2) as usize).min(MAX_LEN);
+    // NOTE: the CRC covers all that was written, a newer firmware's fields too, only the
+    // bytes this one knows are kept
+    let mut bytes = [0_u8; ENCODED_LEN];
+    let mut crc = 0;
+    for i in 0..len.div_ceil(4) {
+        let chunk = word(HEADER_WORDS + i).to_le_bytes();
+        let stored = &chunk[..(len - 4 * i).min(4)];
+        crc = image::crc32(crc, stored);
+        for (j, byte) in stored.iter().enumerate() {
+            if let Some(known) = bytes.get_mut(4 * i + j) {
+                *known = *byte;
+            }
+        }
+    }
+    let bytes = &bytes[..len.min(ENCODED_LEN)];
+
+    if crc != word(3) {
+        log!(Level::Warn, "stored settings are corrupt, using defaults");
+        return Settings::DEFAULT;
+    }
+    match migrate(version, bytes) {
+        Some(settings) => settings,
+        None => {
+            log!(Level::Warn, "unknown settings version {}, using defaults", version);
+            Settings::DEFAULT
+        }
+    }
+}
+
+// NOTE: shown after a factory reset, a square shrinking into the centre twice
+pub const RESET_ANIMATION : [[[u8; 5]; 5]; 6] = {
+    const OUTER : [[u8; 5]; 5] = [
+        [1, 1, 1, 1, 1],
+        [1, 0, 0, 0, 1],
+        [1, 0, 0, 0, 1],
+        [1, 0, 0, 0, 1],
+        [1, 1, 1, 1, 1],
+    ];
+    const INNER : [[u8; 5]; 5] = [
+        [0, 0, 0, 0, 0],
+        [0, 1, 1, 1, 0],
+        [0, 1, 0, 1, 0],
+        [0, 1, 1, 1, 0],
+        [0, 0, 0, 0, 0],
+    ];
+    const CENTRE : [[u8; 5]; 5] = [
+        [0, 0, 0, 0, 0],
+        [0, 0, 0, 0, 0],
+        [0, 0, 1, 0, 0],
+        [0, 0, 0, 0, 0],
+        [0, 0, 0, 0, 0],
+    ];
+    [OUTER, INNER, CENTRE, OUTER, INNER, CENTRE]
+};
+
+/// Forgets the settings and everything in the key-value store, both read as defaults afterwards.
+/// Only the sealing counter is kept, a nonce must not come round again, see seal.rs.
+pub fn factory_reset() {
+    let counter = kv::get_u32(kv::SEAL_COUNTER);
+    flash::erase_page(SETTINGS_PAGE);
+    kv::erase();
+    if let Some(counter) = counter {
+        kv::set_u32(kv::SEAL_COUNTER, counter);
+    }
+}
+
+pub fn save(settings : &Settings) {
+    let bytes = settings.encode();
+
+    let mut words = [0xffff_ffff_u32; HEADER_WORDS + ENCODED_LEN.div_ceil(4)];
+    words[0] = MAGIC;
+    words[1] = VERSION;
+    words[2] = ENCODED_LEN as u32;
+    words[3] = crc32(&bytes);
+    for (i, chunk) in bytes.chunks(4).enumerate() {
+        let mut word = [0xff; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        words[HEADER_WORDS + i] = u32::from_le_bytes(word);
+    }
+
+    flash::erase_page(SETTINGS_PAGE);
+    flash::write_words(SETTINGS_PAGE, &words);
+}
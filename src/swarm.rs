@@ -0,0 +1,123 @@
+//! Swarm: every board of a class draws the same animation in lockstep, see
+//! `fun_core::swarm`.
+//!
+//! The time sync master leads, see timesync.rs. In the swarm app A on the master starts the
+//! next animation `LEAD_MS` from now on the synced clock, so the followers hear of it before
+//! it starts. While the app is on screen the master broadcasts the animation and its start
+//! every `RESEND_MS`, a board opening the app late joins in on the next one. The followers
+//! keep their receiver on in the app and draw the animation from the synced time since its
+//! start, nothing before it starts. The icon is dim until an animation was heard.
+//!
+//! NOTE: a board not following the master draws on its own clock, in step with the others
+//! only by chance.
+
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+use fun_core::swarm::{self, Animation};
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::protocol::Packet;
+use crate::radio::Payload;
+use crate::timesync::{self, Role};
+
+const LEAD_MS : u64 = 500;
+const RESEND_MS : u64 = 1000;
+const ACTIVE_MS : u64 = 500;
+const POLL_MS : u32 = 20;
+const DIM_LEVEL : u8 = 3;
+
+pub const ICON : Frame = [
+    [1, 0, 1, 0, 1],
+    [0, 0, 0, 0, 0],
+    [1, 0, 1, 0, 1],
+    [0, 0, 0, 0, 0],
+    [1, 0, 1, 0, 1],
+];
+
+#[derive(Clone, Copy)]
+struct Show {
+    animation : Animation,
+    start_us  : u64,
+}
+
+static SHOW : Mutex<Cell<Option<Show>>> = Mutex::new(Cell::new(None));
+static SENT : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+static DRAWN : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+fn leading(now : Instant) -> bool {
+    timesync::status(now).role == Role::Master
+}
+
+/// True while the swarm app is on screen, the radio has to listen for the master then.
+pub fn active(now : Instant) -> bool {
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).get()).is_some_and(|drawn| ms_since(now, drawn) < ACTIVE_MS)
+}
+
+/// The animation and its start, every `RESEND_MS` on the master while the app is on screen.
+pub fn next_packet(now : Instant) -> Option<Payload> {
+    if !active(now) || !leading(now) {
+        return None;
+    }
+    let show = cortex_m::interrupt::free(|cs| {
+        let show = SHOW.borrow(cs).get()?;
+        let sent = SENT.borrow(cs);
+        if sent.get().is_some_and(|at| ms_since(now, at) < RESEND_MS) {
+            return None;
+        }
+        sent.set(Some(now));
+        Some(show)
+    })?;
+    Packet::Swarm { animation : show.animation as u8, start_us : show.start_us }.encode()
+}
+
+/// Takes a packet from the master, false when it isn't an animation.
+pub fn on_packet(packet : &Packet, now : Instant) -> bool {
+    let Packet::Swarm { animation, start_us } = *packet else { return false };
+    if !active(now) || leading(now) {
+        return true;
+    }
+    let Some(animation) = Animation::from_u8(animation) else {
+        log!("swarm animation {} unknown, newer firmware on the master", animation);
+        return true;
+    };
+    cortex_m::interrupt::free(|cs| SHOW.borrow(cs).set(Some(Show { animation, start_us })));
+    true
+}
+
+pub fn on_input(input : Input, now : Instant) -> bool {
+    if !matches!(input, Input::Button(Button::A)) || !leading(now) {
+        return false;
+    }
+    let start_us = timesync::now_us() + LEAD_MS * 1000;
+    let animation = cortex_m::interrupt::free(|cs| {
+        let show = SHOW.borrow(cs);
+        let animation = show.get().map_or(Animation::Pulse, |show| show.animation.next());
+        show.set(Some(Show { animation, start_us }));
+        // NOTE: sent on the next time round radio_log
+        SENT.borrow(cs).set(None);
+        animation
+    });
+    log!("swarm {} from {} us", animation.name(), start_us);
+    true
+}
+
+/// The animation at the synced time, the dim icon before there is one.
+pub fn draw(_step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    cortex_m::interrupt::free(|cs| DRAWN.borrow(cs).set(Some(now)));
+    let frame = match cortex_m::interrupt::free(|cs| SHOW.borrow(cs).get()) {
+        None => ICON.map(|row| row.map(|led| led * DIM_LEVEL)),
+        Some(show) => match timesync::now_us().checked_sub(show.start_us) {
+            Some(elapsed_us) => swarm::frame(show.animation, elapsed_us / 1000),
+            None => [[0; 5]; 5],
+        },
+    };
+    Some((frame, POLL_MS))
+}
@@ -0,0 +1,168 @@
+//! Per task bookkeeping for `ps` on the console: how often each task was spawned, how often
+//! a spawn was dropped as the task was still busy, when it last ran and its longest run.
+//!
+//! Spawns go through `spawned`, runs are timed from `Run::start` until the `Run` is
+//! dropped, on the DWT cycle counter bench.rs starts. The tasks looping forever take a
+//! `Run` per round, without the await that ends it.
+//!
+//! NOTE: a run of an async task includes its awaits and everything of a higher priority
+//! that ran meanwhile, the longest run is how long the task kept others of its priority
+//! waiting at worst, not CPU time.
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+use crate::bench;
+use crate::mono::Mono;
+
+const CYCLES_PER_US : u32 = 64;
+
+#[derive(Clone, Copy)]
+pub enum Task {
+    ButtonPressed,
+    ButtonStorm,
+    ButtonAAction,
+    ButtonBAction,
+    Heartbeat,
+    PersistCounters,
+    SupplyMonitor,
+    ClockCalibration,
+    ThresholdCrossed,
+    InputPoll,
+    PulseMeasure,
+    RenderBench,
+    DfuReset,
+    OtaUpdate,
+    ScrollText,
+    AppLauncher,
+    SerialReceived,
+    RadioLog,
+    SerialLog,
+    RngReady,
+    RadioInterrupt,
+    RadioReceived,
+    ConsoleCommand,
+    GreenhouseMonitor,
+    FrequencyCounter,
+}
+
+const COUNT : usize = 25;
+
+pub const TASKS : [Task; COUNT] = [
+    Task::ButtonPressed,
+    Task::ButtonStorm,
+    Task::ButtonAAction,
+    Task::ButtonBAction,
+    Task::Heartbeat,
+    Task::PersistCounters,
+    Task::SupplyMonitor,
+    Task::ClockCalibration,
+    Task::ThresholdCrossed,
+    Task::InputPoll,
+    Task::PulseMeasure,
+    Task::RenderBench,
+    Task::DfuReset,
+    Task::OtaUpdate,
+    Task::ScrollText,
+    Task::AppLauncher,
+    Task::SerialReceived,
+    Task::RadioLog,
+    Task::SerialLog,
+    Task::RngReady,
+    Task::RadioInterrupt,
+    Task::RadioReceived,
+    Task::ConsoleCommand,
+    Task::GreenhouseMonitor,
+    Task::FrequencyCounter,
+];
+
+impl Task {
+    pub fn name(self) -> &'static str {
+        match self {
+            Task::ButtonPressed    => "button_pressed",
+            Task::ButtonStorm      => "button_storm",
+            Task::ButtonAAction    => "button_a_action",
+            Task::ButtonBAction    => "button_b_action",
+            Task::Heartbeat        => "heartbeat",
+            Task::PersistCounters  => "persist_counters",
+            Task::SupplyMonitor    => "supply_monitor",
+            Task::ClockCalibration => "clock_calibration",
+            Task::ThresholdCrossed => "threshold_crossed",
+            Task::InputPoll        => "input_poll",
+            Task::PulseMeasure     => "pulse_measure",
+            Task::RenderBench      => "render_bench",
+            Task::DfuReset         => "dfu_reset",
+            Task::OtaUpdate        => "ota_update",
+            Task::ScrollText       => "scroll_text",
+            Task::AppLauncher      => "app_launcher",
+            Task::SerialReceived   => "serial_received",
+            Task::RadioLog         => "radio_log",
+            Task::SerialLog        => "serial_log",
+            Task::RngReady         => "rng_ready",
+            Task::RadioInterrupt   => "radio_interrupt",
+            Task::RadioReceived    => "radio_received",
+            Task::ConsoleCommand   => "console_command",
+            Task::GreenhouseMonitor => "greenhouse_monitor",
+            Task::FrequencyCounter => "frequency_counter",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Stats {
+    pub spawned : u32,
+    pub dropped : u32,
+    pub runs    : u32,
+    // NOTE: since boot, when the last run started
+    pub last_ms : Option<u64>,
+    pub max_us  : u32,
+}
+
+impl Stats {
+    const fn new() -> Self {
+        Stats { spawned : 0, dropped : 0, runs : 0, last_ms : None, max_us : 0 }
+    }
+}
+
+static STATS : Mutex<RefCell<[Stats; COUNT]>> = Mutex::new(RefCell::new([Stats::new(); COUNT]));
+
+fn update(task : Task, f : impl FnOnce(&mut Stats)) {
+    cortex_m::interrupt::free(|cs| f(&mut STATS.borrow(cs).borrow_mut()[task as usize]));
+}
+
+/// Counts the spawn of `task`, or the drop when it failed, and hands the result back.
+pub fn spawned<T>(task : Task, result : Result<(), T>) -> Result<(), T> {
+    update(task, |stats| match result {
+        Ok(()) => stats.spawned += 1,
+        Err(_) => stats.dropped += 1,
+    });
+    result
+}
+
+pub fn stats(task : Task) -> Stats {
+    cortex_m::interrupt::free(|cs| STATS.borrow(cs).borrow()[task as usize])
+}
+
+/// A run of a task, timed until it is dropped.
+pub struct Run {
+    task  : Task,
+    start : u32,
+}
+
+impl Run {
+    pub fn start(task : Task) -> Run {
+        let now_ms = Mono::now().duration_since_epoch().to_millis();
+        update(task, |stats| {
+            stats.runs += 1;
+            stats.last_ms = Some(now_ms);
+        });
+        Run { task, start : bench::cycles() }
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        // NOTE: the counter wraps every 67 s, a run that long is taken for a shorter one
+        let us = bench::cycles().wrapping_sub(self.start) / CYCLES_PER_US;
+        update(self.task, |stats| stats.max_us = stats.max_us.max(us));
+    }
+}
@@ -0,0 +1,116 @@
+//! Telemetry: a snapshot of the sensors and counters, broadcast every `telemetry` seconds.
+//!
+//! The tasks owning the sensors `record` their latest reading here, the die temperature from
+//! clock_calibration, the acceleration from input_poll and the supply from supply_monitor,
+//! the counters come from usage.rs. `next_packet` sends the snapshot as a
+//! `protocol::Telemetry`, with the temperature in quarter °C and None for a sensor not read
+//! yet. A board in listener mode (`listen` console command) prints
+//! the snapshots it hears as JSON lines, see `json`, for collecting them on the host.
+
+use core::cell::Cell;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicU8, Ordering};
+use cortex_m::interrupt::Mutex;
+use heapless::String;
+use crate::mono::{Instant, Mono};
+use crate::protocol::{Packet, Telemetry};
+use crate::radio::Payload;
+use crate::usage;
+
+pub const JSON_LEN : usize = 128;
+
+#[derive(Clone, Copy)]
+pub enum Reading {
+    // NOTE: in the quarter degrees of the TEMP peripheral
+    Temperature(i32),
+    AccelMg(u32),
+    SupplyMv(u16),
+}
+
+static SNAPSHOT : Mutex<Cell<Telemetry>> = Mutex::new(Cell::new(Telemetry {
+    uptime_s    : 0,
+    temperature : None,
+    accel_mg    : None,
+    supply_mv   : 0,
+    presses     : 0,
+}));
+// NOTE: 0 is off
+static SECS : AtomicU8 = AtomicU8::new(0);
+static SENT : Mutex<Cell<Option<Instant>>> = Mutex::new(Cell::new(None));
+
+pub fn configure(secs : u8) {
+    SECS.store(secs, Ordering::Relaxed);
+}
+
+pub fn record(reading : Reading) {
+    cortex_m::interrupt::free(|cs| {
+        let cell = SNAPSHOT.borrow(cs);
+        let mut snapshot = cell.get();
+        match reading {
+            Reading::Temperature(quarters) => snapshot.temperature = Some(quarters),
+            Reading::AccelMg(mg)           => snapshot.accel_mg = Some(mg),
+            Reading::SupplyMv(mv)          => snapshot.supply_mv = mv,
+        }
+        cell.set(snapshot);
+    });
+}
+
+/// The readings recorded last.
+pub fn latest() -> Telemetry {
+    cortex_m::interrupt::free(|cs| SNAPSHOT.borrow(cs).get())
+}
+
+/// The snapshot, when it is due.
+pub fn next_packet(now : Instant) -> Option<Payload> {
+    let secs = SECS.load(Ordering::Relaxed) as u64;
+    if secs == 0 {
+        return None;
+    }
+    let due = cortex_m::interrupt::free(|cs| {
+        let sent = SENT.borrow(cs);
+        let due = sent.get().is_none_or(|at| now.checked_duration_since(at).is_some_and(|elapsed| elapsed.to_secs() >= secs));
+        if due {
+            sent.set(Some(now));
+        }
+        due
+    });
+    if !due {
+        return None;
+    }
+
+    let mut snapshot = cortex_m::interrupt::free(|cs| SNAPSHOT.borrow(cs).get());
+    snapshot.uptime_s = Mono::now().duration_since_epoch().to_secs() as u32;
+    snapshot.presses = usage::published().map_or(0, |counters| counters.button_pressed);
+
+    // NOTE: the varints only outgrow the packet after years up
+    Packet::Telemetry(snapshot).encode()
+}
+
+/// The snapshot in a telemetry packet.
+pub fn decode(packet : &Packet) -> Option<Telemetry> {
+    match *packet {
+        Packet::Telemetry(snapshot) => Some(snapshot),
+        _ => None,
+    }
+}
+
+/// `snapshot` from `sender` as one line of JSON.
+pub fn json(sender : u16, snapshot : &Telemetry) -> String<JSON_LEN> {
+    let mut line = String::new();
+    let _ = write!(line, "{{\"from\":\"{:04x}\",\"uptime_s\":{},\"temp_c\":", sender, snapshot.uptime_s);
+    let _ = match snapshot.temperature {
+        Some(quarters) => {
+            let sign = if quarters < 0 { "-" } else { "" };
+            let quarters = quarters.unsigned_abs();
+            write!(line, "{}{}.{:02}", sign, quarters / 4, quarters % 4 * 25)
+        }
+        None => write!(line, "null"),
+    };
+    let _ = line.push_str(",\"accel_mg\":");
+    let _ = match snapshot.accel_mg {
+        Some(mg) => write!(line, "{}", mg),
+        None => write!(line, "null"),
+    };
+    let _ = write!(line, ",\"supply_mv\":{},\"presses\":{}}}", snapshot.supply_mv, snapshot.presses);
+    line
+}
@@ -0,0 +1,163 @@
+//! Thermometer: the temperature logged every minute, see `fun_core::thermo`, and an app
+//! showing which way it is going.
+//!
+//! clock_calibration measures the temperature anyway and hands `record` a reading a minute,
+//! whatever app runs, so the lowest and highest of the day are there when the app opens.
+//! The days go to the key-value store whenever they change. The app shows an arrow up, down
+//! or flat for the trend, a dot until ten minutes are logged, A scrolls the temperature now
+//! and B the lowest and highest of today. `temps` on the console prints the log as CSV.
+//!
+//! NOTE: it is the temperature of the nRF's die, a degree or two above the room's while the
+//! CPU and the radio work.
+
+use core::cell::{Cell, RefCell};
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use fun_core::thermo::{Celsius, Log, Trend, ENCODED_LEN};
+use heapless::String;
+use crate::apps::Input;
+use crate::console::LINE_LEN;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::kv;
+use crate::mono::Instant;
+use crate::scroll::{self, Text};
+
+pub const ICON : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 1, 0],
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+    [0, 1, 1, 1, 0],
+];
+
+const RISING : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 1, 1, 1, 0],
+    [1, 0, 1, 0, 1],
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+];
+
+const STEADY : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 1, 0],
+    [1, 1, 1, 1, 1],
+    [0, 0, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+];
+
+const FALLING : Frame = [
+    [0, 0, 1, 0, 0],
+    [0, 0, 1, 0, 0],
+    [1, 0, 1, 0, 1],
+    [0, 1, 1, 1, 0],
+    [0, 0, 1, 0, 0],
+];
+
+const WAITING : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 1, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+];
+
+const POLL_MS : u32 = 100;
+
+#[derive(Clone, Copy)]
+enum Showing {
+    Trend,
+    Now,
+    Today,
+}
+
+// NOTE: None until the stored days are read, on the first reading
+static LOG : Mutex<RefCell<Option<Log>>> = Mutex::new(RefCell::new(None));
+// NOTE: what the next lap shows, set by the buttons
+static SHOWING : Mutex<Cell<Showing>> = Mutex::new(Cell::new(Showing::Trend));
+// NOTE: the text of the lap scrolling, empty while the arrow shows
+static TEXT : Mutex<RefCell<Text>> = Mutex::new(RefCell::new(Text::new()));
+
+fn with_log<R>(f : impl FnOnce(&mut Log) -> R) -> R {
+    cortex_m::interrupt::free(|cs| {
+        let mut log = LOG.borrow(cs).borrow_mut();
+        f(log.get_or_insert_with(|| {
+            let mut bytes = [0; kv::MAX_VALUE_LEN];
+            let len = kv::get(kv::THERMO_DAYS, &mut bytes).unwrap_or(0);
+            Log::decode(&bytes[..len])
+        }))
+    })
+}
+
+/// The reading of a minute, in quarter degrees.
+pub fn record(quarters : i32) {
+    let quarters = quarters.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+    let days : Option<[u8; ENCODED_LEN]> = with_log(|log| log.record(quarters).then(|| log.encode()));
+    // NOTE: written outside the critical section, the flash holds the CPU for a while
+    if let Some(days) = days {
+        kv::set(kv::THERMO_DAYS, &days);
+    }
+}
+
+/// The log as CSV, the minutes and then the days, both oldest first.
+pub fn export(mut sink : impl FnMut(&str)) {
+    let (minutes, days) = with_log(|log| (log.minutes().count(), log.days().count()));
+    sink("minutes_ago,celsius");
+    for i in 0..minutes {
+        let Some(quarters) = with_log(|log| log.minutes().nth(i)) else { break };
+        let mut line = String::<LINE_LEN>::new();
+        let _ = write!(line, "{},{}", minutes - 1 - i, Celsius(quarters));
+        sink(&line);
+    }
+    sink("days_ago,min,max");
+    for i in 0..days {
+        let Some(day) = with_log(|log| log.days().nth(i)) else { break };
+        let mut line = String::<LINE_LEN>::new();
+        let _ = write!(line, "{},{},{}", days - 1 - i, Celsius(day.min), Celsius(day.max));
+        sink(&line);
+    }
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let showing = match input {
+        Input::Button(Button::A) => Showing::Now,
+        Input::Button(Button::B) => Showing::Today,
+        _ => return false,
+    };
+    cortex_m::interrupt::free(|cs| SHOWING.borrow(cs).set(showing));
+    true
+}
+
+fn text(showing : Showing) -> Text {
+    let mut text = Text::new();
+    let (latest, today) = with_log(|log| (log.latest(), log.today()));
+    let _ = match (showing, latest, today) {
+        (Showing::Now, Some(quarters), _) => write!(text, "{} C", Celsius(quarters)),
+        (Showing::Today, _, Some(day)) => write!(text, "lo {} hi {}", Celsius(day.min), Celsius(day.max)),
+        (Showing::Trend, ..) => Ok(()),
+        _ => write!(text, "no reading yet"),
+    };
+    text
+}
+
+/// The trend, or a lap of the text A or B asked for.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let text = cortex_m::interrupt::free(|cs| {
+        let mut shown = TEXT.borrow(cs).borrow_mut();
+        if step == 0 {
+            *shown = text(SHOWING.borrow(cs).replace(Showing::Trend));
+        }
+        shown.clone()
+    });
+    if text.is_empty() {
+        let arrow = match with_log(|log| log.trend()) {
+            Some(Trend::Rising) => RISING,
+            Some(Trend::Steady) => STEADY,
+            Some(Trend::Falling) => FALLING,
+            None => WAITING,
+        };
+        return (step == 0).then_some((arrow.map(|row| row.map(|led| led * 9)), POLL_MS));
+    }
+    scroll::frames(&text).nth(step).map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS))
+}
@@ -0,0 +1,167 @@
+//! Time sync over the radio, so the boards of a class agree on the time they log.
+//!
+//! One board is the master (`set sync master`), it broadcasts its monotonic time every
+//! `SYNC_MS`. Followers (`set sync follow`) keep their receiver on and take the first master
+//! they hear, another one only once theirs was silent for `LOST_MS`. Each packet heard gives
+//! the offset from the follower's clock to the master's: the first one, or one more than
+//! `STEP_US` off, is taken as it is, the others move the offset by a `SMOOTHING`th of the
+//! difference, so a late packet only shifts the time a little.
+//!
+//! `now_us` is the synced time, the master's own and the monotonic one while there is
+//! no master. The log timestamps use it, the rate limits stay on the monotonic.
+//!
+//! NOTE: the master stamps the packet before sealing and sending it, the follower when its
+//! END interrupt comes, `LATENCY_US` makes up for the ramp up and the time on air.
+
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::protocol::Packet;
+use crate::radio::Payload;
+
+const SYNC_MS : u64 = 1000;
+const LOST_MS : u64 = 5000;
+const STEP_US : i64 = 100_000;
+const SMOOTHING : i64 = 8;
+// NOTE: the 140 us TX ramp up and 28 bytes on air at 1 Mbit
+const LATENCY_US : i64 = 370;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Role {
+    Off,
+    Master,
+    Follow,
+}
+
+const ROLES : [Role; 3] = [Role::Off, Role::Master, Role::Follow];
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Off    => "off",
+            Role::Master => "master",
+            Role::Follow => "follow",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Role> {
+        ROLES.into_iter().find(|role| role.name() == name)
+    }
+
+    /// The role stored as `role as u8`.
+    pub fn from_u8(value : u8) -> Option<Role> {
+        ROLES.get(value as usize).copied()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Master {
+    address : u16,
+    heard   : Instant,
+}
+
+#[derive(Clone, Copy)]
+struct Sync {
+    role      : Role,
+    // NOTE: added to the monotonic time to get the master's
+    offset_us : i64,
+    master    : Option<Master>,
+    sent      : Option<Instant>,
+}
+
+static SYNC : Mutex<Cell<Sync>> = Mutex::new(Cell::new(Sync { role : Role::Off, offset_us : 0, master : None, sent : None }));
+
+fn update<R>(f : impl FnOnce(&mut Sync) -> R) -> R {
+    cortex_m::interrupt::free(|cs| {
+        let cell = SYNC.borrow(cs);
+        let mut sync = cell.get();
+        let result = f(&mut sync);
+        cell.set(sync);
+        result
+    })
+}
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+/// Takes the stored role, a board that stops following keeps the time it has.
+pub fn configure(role : Role) {
+    update(|sync| {
+        if role != Role::Follow {
+            sync.master = None;
+        }
+        sync.role = role;
+    });
+}
+
+/// The synced time in µs.
+pub fn now_us() -> u64 {
+    let offset_us = cortex_m::interrupt::free(|cs| SYNC.borrow(cs).get().offset_us);
+    Mono::now_us().saturating_add_signed(offset_us)
+}
+
+/// True for a follower, the radio has to listen for the master then.
+pub fn active() -> bool {
+    update(|sync| sync.role) == Role::Follow
+}
+
+/// The time packet, every `SYNC_MS` on a master.
+pub fn next_packet(now : Instant) -> Option<Payload> {
+    let due = update(|sync| {
+        let due = sync.role == Role::Master && sync.sent.is_none_or(|sent| ms_since(now, sent) >= SYNC_MS);
+        if due {
+            sync.sent = Some(now);
+        }
+        due
+    });
+    if !due {
+        return None;
+    }
+    Packet::Time { us : now_us() }.encode()
+}
+
+/// Takes a packet from `sender` that came in `at`, false when it isn't a time packet.
+pub fn on_packet(sender : u16, packet : &Packet, at : Instant) -> bool {
+    let Packet::Time { us } = *packet else { return false };
+    let sample = us as i64 + LATENCY_US - at.duration_since_epoch().to_micros() as i64;
+    let stepped = update(|sync| {
+        if sync.role != Role::Follow {
+            return None;
+        }
+        let ours = match sync.master {
+            Some(master) => master.address == sender || ms_since(at, master.heard) >= LOST_MS,
+            None => true,
+        };
+        if !ours {
+            return None;
+        }
+        let first = sync.master.is_none_or(|master| master.address != sender);
+        let error = sample - sync.offset_us;
+        let stepped = first || error.abs() > STEP_US;
+        sync.offset_us = if stepped { sample } else { sync.offset_us + error / SMOOTHING };
+        sync.master = Some(Master { address : sender, heard : at });
+        stepped.then_some(error)
+    });
+    if let Some(error) = stepped {
+        log!("time synced to {:04x}, stepped {} us", sender, error);
+    }
+    true
+}
+
+pub struct Status {
+    pub role      : Role,
+    pub offset_us : i64,
+    // NOTE: the master and how long ago it was heard
+    pub master    : Option<(u16, u64)>,
+}
+
+pub fn status(now : Instant) -> Status {
+    let sync = update(|sync| *sync);
+    Status {
+        role      : sync.role,
+        offset_us : sync.offset_us,
+        master    : sync.master.map(|master| (master.address, ms_since(now, master.heard))),
+    }
+}
@@ -0,0 +1,72 @@
+//! Toasts: an icon or a short message shown over the running app for a while, then the app's
+//! frames again.
+//!
+//! idle draws the toast instead of the app's frame for as long as it lasts, the app keeps
+//! running underneath and its state is left alone. An icon stays for the seconds it was
+//! given, a message scrolls by once. A new toast takes the place of the one showing.
+//!
+//! NOTE: the launcher, the initials entry and the crash icon aren't covered, a toast over
+//! them would hide what the buttons do.
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+use crate::display::Frame;
+use crate::mono::Instant;
+use crate::scroll::{self, Text};
+
+#[derive(Clone)]
+enum Content {
+    Icon { icon : Frame, ms : u64 },
+    Text(Text),
+}
+
+struct Toast {
+    content : Content,
+    // NOTE: set on the first frame, the toast counts down from when it is seen
+    shown   : Option<Instant>,
+}
+
+static TOAST : Mutex<RefCell<Option<Toast>>> = Mutex::new(RefCell::new(None));
+
+fn show(content : Content) {
+    cortex_m::interrupt::free(|cs| *TOAST.borrow(cs).borrow_mut() = Some(Toast { content, shown : None }));
+}
+
+/// `icon` for `secs`, every LED that isn't 0 fully lit.
+pub fn icon(icon : Frame, secs : u32) {
+    let icon = icon.map(|row| row.map(|led| if led > 0 { 9 } else { 0 }));
+    show(Content::Icon { icon, ms : secs as u64 * 1000 });
+}
+
+pub fn text(text : Text) {
+    show(Content::Text(text));
+}
+
+/// True while a toast is up, or waiting for its first frame.
+pub fn showing() -> bool {
+    cortex_m::interrupt::free(|cs| TOAST.borrow(cs).borrow().is_some())
+}
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+/// The frame to show at `now` instead of the app's and for how long at most, None once
+/// the toast is over or when there is none.
+pub fn frame(now : Instant) -> Option<(Frame, u32)> {
+    cortex_m::interrupt::free(|cs| {
+        let mut toast = TOAST.borrow(cs).borrow_mut();
+        let current = toast.as_mut()?;
+        let elapsed = ms_since(now, *current.shown.get_or_insert(now));
+        let frame = match &current.content {
+            Content::Icon { icon, ms } => (elapsed < *ms).then(|| (*icon, (*ms - elapsed).min(scroll::STEP_MS as u64) as u32)),
+            Content::Text(text) => scroll::frames(text)
+                .nth((elapsed / scroll::STEP_MS as u64) as usize)
+                .map(|frame| (frame.map(|row| row.map(|led| led * 9)), scroll::STEP_MS)),
+        };
+        if frame.is_none() {
+            *toast = None;
+        }
+        frame
+    })
+}
@@ -0,0 +1,54 @@
+//! Touch sensing on the logo above the display.
+//!
+//! The logo is a bare pad with a high value pull-up. Discharged through the pin it charges
+//! back up in a moment, a finger on it adds enough capacitance to make that take several
+//! times longer. `Logo` counts how many reads the pin stays low for, against the count of
+//! the untouched pad measured at init.
+//!
+//! NOTE: the counts depend on the CPU clock and the build, only their ratio is used.
+
+use embedded_hal::digital::v2::InputPin;
+use microbit::hal::gpio::{p1::P1_04, Disconnected, Floating, Input, Level};
+
+// NOTE: bounds the time a measurement takes when the pad is held low by something else
+const MAX_COUNT : u32 = 20_000;
+const SAMPLES : u32 = 8;
+
+pub struct Logo {
+    pin      : Option<P1_04<Input<Floating>>>,
+    baseline : u32,
+    touched  : bool,
+}
+
+impl Logo {
+    pub fn new(pin : P1_04<Disconnected>) -> Self {
+        let mut logo = Logo { pin : Some(pin.into_floating_input()), baseline : 0, touched : false };
+        // NOTE: assumes nobody touches the logo during the reset
+        logo.baseline = (0..SAMPLES).map(|_| logo.charge_count()).sum::<u32>() / SAMPLES;
+        logo
+    }
+
+    fn charge_count(&mut self) -> u32 {
+        let Some(pin) = self.pin.take() else { return 0 };
+        let pin = pin.into_push_pull_output(Level::Low).into_floating_input();
+        let mut count = 0;
+        while pin.is_low().unwrap() && count < MAX_COUNT {
+            count += 1;
+        }
+        self.pin = Some(pin);
+        count
+    }
+
+    /// Measures the pad, true when it went from untouched to touched since the last poll.
+    pub fn poll(&mut self) -> bool {
+        let count = self.charge_count();
+        // NOTE: touched at three times the baseline, released below twice it
+        let was_touched = self.touched;
+        self.touched = if was_touched {
+            count > 2 * self.baseline + 1
+        } else {
+            count > 3 * self.baseline + 2
+        };
+        self.touched && !was_touched
+    }
+}
@@ -0,0 +1,202 @@
+//! A link to a program on the host that speaks the packets of protocol.rs, in the frames of
+//! frame.rs, over whichever port `set link off|uart|rtt` picks.
+//!
+//! Every packet `radio_received` opens goes to the host as a frame of
+//! `[sender (2), rssi, packet..]`, whatever the board makes of it. A frame from the host
+//! holds one packet, which is sealed and broadcast like the board's own, see `radio_log` in
+//! main.rs. The UART link shares the port with the console, bytes outside a frame are console
+//! text. The RTT link has up and down channel 1, "frames", to itself, so it needs `use_rtt`
+//! and isn't there with `hil`, whose commands have channel 1.
+//!
+//! NOTE: the micro:bit's USB port belongs to the interface chip, which passes the UART on,
+//! the USB device of the nRF52833 isn't wired. On a board where it is, USB CDC would be one
+//! more `Transport`.
+
+use core::cell::{Cell, RefCell};
+use cortex_m::interrupt::Mutex;
+use embedded_hal::serial;
+use heapless::Deque;
+use crate::console::SerialTx;
+use crate::frame::{self, Data};
+use crate::protocol::{self, Packet};
+use crate::radio::Payload;
+
+const QUEUED : usize = 4;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Link {
+    Off,
+    Uart,
+    Rtt,
+}
+
+const LINKS : [Link; 3] = [Link::Off, Link::Uart, Link::Rtt];
+
+impl Link {
+    pub fn name(self) -> &'static str {
+        match self {
+            Link::Off  => "off",
+            Link::Uart => "uart",
+            Link::Rtt  => "rtt",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<Link> {
+        LINKS.into_iter().find(|link| link.name() == name)
+    }
+
+    /// The link stored as `link as u8`.
+    pub fn from_u8(value : u8) -> Option<Link> {
+        LINKS.get(value as usize).copied()
+    }
+
+    /// False for the RTT link in a build without it.
+    pub fn available(&self) -> bool {
+        *self != Link::Rtt || cfg!(all(feature = "use_rtt", not(feature = "hil")))
+    }
+}
+
+/// Somewhere frames go to the host.
+pub trait Transport {
+    /// Writes all of `bytes`, or drops them when the host isn't reading.
+    fn write(&mut self, bytes : &[u8]);
+
+    /// Writes `data` as one frame, nothing when it is too long for one.
+    fn send(&mut self, data : &[u8]) {
+        if let Some(frame) = frame::encode(data) {
+            self.write(&frame);
+        }
+    }
+}
+
+impl Transport for SerialTx {
+    fn write(&mut self, bytes : &[u8]) {
+        for byte in bytes {
+            let _ = nb::block!(serial::Write::write(self, *byte));
+        }
+        let _ = nb::block!(serial::Write::flush(self));
+    }
+}
+
+static LINK : Mutex<Cell<Link>> = Mutex::new(Cell::new(Link::Off));
+// NOTE: filled from the UART interrupt or the radio_log task, emptied by radio_log
+static QUEUE : Mutex<RefCell<Deque<Payload, QUEUED>>> = Mutex::new(RefCell::new(Deque::new()));
+
+pub fn configure(link : Link) {
+    cortex_m::interrupt::free(|cs| LINK.borrow(cs).set(link));
+}
+
+pub fn link() -> Link {
+    cortex_m::interrupt::free(|cs| LINK.borrow(cs).get())
+}
+
+/// Sends the packet heard from `sender` at `rssi` dBm to the host.
+pub fn forward(transport : &mut impl Transport, sender : u16, rssi : i8, packet : &[u8]) {
+    let mut data = Data::new();
+    let _ = data.extend_from_slice(&sender.to_le_bytes());
+    let _ = data.push(rssi as u8);
+    if data.extend_from_slice(packet).is_ok() {
+        transport.send(&data);
+    }
+}
+
+/// Takes a frame from the host, it is dropped when it isn't a packet or the queue is full.
+pub fn received(data : &[u8]) {
+    if data.len() > protocol::MAX_LEN || Packet::decode(data).is_none() {
+        return;
+    }
+    let Ok(payload) = Payload::from_slice(data) else { return };
+    cortex_m::interrupt::free(|cs| {
+        let _ = QUEUE.borrow(cs).borrow_mut().push_back(payload);
+    });
+}
+
+/// The next packet from the host to broadcast.
+pub fn next() -> Option<Payload> {
+    #[cfg(all(feature = "use_rtt", not(feature = "hil")))]
+    rtt::poll();
+    cortex_m::interrupt::free(|cs| QUEUE.borrow(cs).borrow_mut().pop_front())
+}
+
+#[cfg(all(feature = "use_rtt", not(feature = "hil")))]
+pub mod rtt {
+    use core::cell::RefCell;
+    use cortex_m::interrupt::Mutex;
+    use rtt_target::{rtt_init, DownChannel, UpChannel};
+    use crate::frame::{Decoder, Received};
+    use super::{Link, Transport};
+
+    struct Channels {
+        up      : UpChannel,
+        down    : DownChannel,
+        decoder : Decoder,
+    }
+
+    static CHANNELS : Mutex<RefCell<Option<Channels>>> = Mutex::new(RefCell::new(None));
+
+    /// Sets up RTT in place of `rtt_init_print!`, the log keeps channel 0.
+    pub fn init() {
+        let channels = rtt_init! {
+            up: {
+                0: {
+                    size: 1024
+                    mode: NoBlockSkip
+                    name: "Terminal"
+                }
+                1: {
+                    size: 256
+                    mode: NoBlockSkip
+                    name: "frames"
+                }
+            }
+            down: {
+                0: {
+                    size: 16
+                    name: "Terminal"
+                }
+                1: {
+                    size: 64
+                    name: "frames"
+                }
+            }
+        };
+        rtt_target::set_print_channel(channels.up.0);
+        let (up, down) = (channels.up.1, channels.down.1);
+        cortex_m::interrupt::free(|cs| {
+            CHANNELS.borrow(cs).replace(Some(Channels { up, down, decoder : Decoder::new() }));
+        });
+    }
+
+    /// The frames channel, a frame that doesn't fit the channel is skipped whole.
+    pub struct Rtt;
+
+    impl Transport for Rtt {
+        fn write(&mut self, bytes : &[u8]) {
+            cortex_m::interrupt::free(|cs| {
+                if let Some(channels) = CHANNELS.borrow(cs).borrow_mut().as_mut() {
+                    channels.up.write(bytes);
+                }
+            });
+        }
+    }
+
+    /// Queues the frames the host wrote since the last call, while the RTT link is on.
+    pub fn poll() {
+        if super::link() != Link::Rtt {
+            return;
+        }
+        let mut byte = [0; 1];
+        loop {
+            let received = cortex_m::interrupt::free(|cs| {
+                let mut channels = CHANNELS.borrow(cs).borrow_mut();
+                let channels = channels.as_mut()?;
+                (channels.down.read(&mut byte) == 1).then(|| channels.decoder.push(byte[0]))
+            });
+            match received {
+                Some(Received::Frame(data)) => super::received(&data),
+                Some(_) => (),
+                None => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Tug-of-war between two boards in the same radio group: whoever mashes A faster pulls the
+//! rope over to their side.
+//!
+//! A press of A puts a board in the lobby, where it broadcasts that it is ready. The first
+//! board to hear another one answers with a go, and both count down from three. During the
+//! pull each board broadcasts how often its A was pressed, the marker on the rope sits
+//! towards the board ahead and a board `WIN_AHEAD` presses ahead won. It tells the other one
+//! so, both show the outcome, then wait for A again for the rematch.
+//!
+//! NOTE: the countdowns start a packet apart, at most `RESEND_MS`, which the loser can blame.
+
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::effects::{self, Preset};
+use crate::events::Button;
+use crate::font::{self, Align};
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::protocol::{Packet, Tug};
+use crate::radio::Payload;
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 0],
+    [1, 0, 0, 0, 0],
+    [1, 1, 1, 1, 1],
+    [1, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+];
+
+const WIN_AHEAD : i32 = 20;
+const RESEND_MS : u64 = 300;
+const COUNT_MS : u64 = 100;
+const COUNTDOWN_MS : u64 = 2100;
+// NOTE: an opponent not heard from for this long left the game
+const SILENT_MS : u64 = 5000;
+const RESULT_MS : u64 = 3000;
+const ACTIVE_MS : u64 = 500;
+const POLL_MS : u32 = 20;
+
+#[derive(Clone, Copy)]
+enum Phase {
+    // NOTE: waiting for A, before the first game and for every rematch
+    Idle,
+    Lobby,
+    Countdown { at : Instant },
+    Pulling { heard : Instant },
+    Over { won : bool, at : Instant },
+}
+
+#[derive(Clone, Copy)]
+struct Game {
+    opponent : Option<u16>,
+    mine     : u16,
+    theirs   : u16,
+    phase    : Phase,
+    sent     : Option<Instant>,
+    drawn    : Instant,
+}
+
+// NOTE: the button interrupt, the radio tasks and idle all move the game on
+static GAME : Mutex<Cell<Option<Game>>> = Mutex::new(Cell::new(None));
+
+fn update(next : impl FnOnce(Option<Game>) -> Option<Game>) -> Option<Game> {
+    cortex_m::interrupt::free(|cs| {
+        let game = next(GAME.borrow(cs).get());
+        GAME.borrow(cs).set(game);
+        game
+    })
+}
+
+fn ms_since(now : Instant, at : Instant) -> u64 {
+    now.checked_duration_since(at).map_or(0, |elapsed| elapsed.to_millis())
+}
+
+fn ahead(game : &Game) -> i32 {
+    game.mine as i32 - game.theirs as i32
+}
+
+fn start(mut game : Game, opponent : u16, now : Instant) -> Game {
+    game.opponent = Some(opponent);
+    game.mine = 0;
+    game.theirs = 0;
+    game.phase = Phase::Countdown { at : now };
+    game.sent = None;
+    game
+}
+
+pub fn on_input(input : Input, now : Instant) -> bool {
+    let Input::Button(Button::A) = input else { return false };
+    let mut taken = true;
+    update(|game| {
+        let mut game = game?;
+        match game.phase {
+            Phase::Idle => {
+                game.phase = Phase::Lobby;
+                game.opponent = None;
+                game.sent = None;
+            }
+            Phase::Pulling { .. } => {
+                game.mine = game.mine.saturating_add(1);
+                if ahead(&game) >= WIN_AHEAD {
+                    log!("tug won {} to {}", game.mine, game.theirs);
+                    game.phase = Phase::Over { won : true, at : now };
+                    game.sent = None;
+                }
+            }
+            // NOTE: presses during the countdown don't count
+            Phase::Countdown { .. } => (),
+            _ => taken = false,
+        }
+        Some(game)
+    });
+    taken
+}
+
+/// True while a game is on screen, the radio has to listen for the opponent then.
+pub fn active(now : Instant) -> bool {
+    update(|game| game).is_some_and(|game| ms_since(now, game.drawn) < ACTIVE_MS)
+}
+
+/// The packet to broadcast now, if any.
+pub fn next_packet(now : Instant) -> Option<Payload> {
+    let mut due = None;
+    update(|game| {
+        let mut game = game?;
+        let (packet, every) = match game.phase {
+            Phase::Lobby => (Tug::Ready, RESEND_MS),
+            Phase::Countdown { .. } => (Tug::Go, RESEND_MS),
+            Phase::Pulling { .. } => (Tug::Count(game.mine), COUNT_MS),
+            Phase::Over { won : true, .. } => (Tug::Won, RESEND_MS),
+            _ => return Some(game),
+        };
+        if game.sent.is_some_and(|sent| ms_since(now, sent) < every) {
+            return Some(game);
+        }
+        game.sent = Some(now);
+        due = Some(packet);
+        Some(game)
+    });
+    Packet::Tug(due?).encode()
+}
+
+/// Takes a packet from `sender`, false when it isn't a tug-of-war packet.
+pub fn on_packet(sender : u16, packet : &Packet, now : Instant) -> bool {
+    let Packet::Tug(packet) = *packet else { return false };
+    update(|game| {
+        let mut game = game?;
+        let from_opponent = game.opponent.is_none_or(|opponent| opponent == sender);
+        game = match (game.phase, packet) {
+            (Phase::Lobby, Tug::Ready | Tug::Go) => start(game, sender, now),
+            (Phase::Countdown { .. }, Tug::Count(theirs)) if from_opponent => Game {
+                theirs,
+                phase : Phase::Pulling { heard : now },
+                ..game
+            },
+            (Phase::Pulling { .. }, Tug::Count(theirs)) if from_opponent => {
+                // NOTE: counts only grow, one overtaken by a later one is dropped
+                game.theirs = game.theirs.max(theirs);
+                game.phase = Phase::Pulling { heard : now };
+                game
+            }
+            (Phase::Pulling { .. }, Tug::Won) if from_opponent => {
+                log!("tug lost {} to {}", game.mine, game.theirs);
+                Game { phase : Phase::Over { won : false, at : now }, ..game }
+            }
+            _ => game,
+        };
+        Some(game)
+    });
+    true
+}
+
+const WIN : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 1],
+    [0, 0, 0, 1, 0],
+    [1, 0, 1, 0, 0],
+    [0, 1, 0, 0, 0],
+];
+
+const LOSE : Frame = [
+    [0, 0, 0, 0, 0],
+    [0, 1, 0, 1, 0],
+    [0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 0],
+    [1, 0, 0, 0, 1],
+];
+
+fn advance(mut game : Game, now : Instant) -> Game {
+    game.phase = match game.phase {
+        Phase::Countdown { at } if ms_since(now, at) >= COUNTDOWN_MS => Phase::Pulling { heard : now },
+        Phase::Pulling { heard } if ms_since(now, heard) > SILENT_MS => {
+            log!("tug opponent gone");
+            Phase::Idle
+        }
+        phase => phase,
+    };
+    game
+}
+
+/// One game, from waiting for A to the outcome.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let now = Mono::now();
+    let game = update(|game| {
+        let mut game = match game {
+            Some(game) if step > 0 => advance(game, now),
+            // NOTE: a rematch, or the launcher cut the last game short
+            _ => Game { opponent : None, mine : 0, theirs : 0, phase : Phase::Idle, sent : None, drawn : now },
+        };
+        game.drawn = now;
+        Some(game)
+    })?;
+
+    let blink = (now.duration_since_epoch().to_millis() / 300).is_multiple_of(2);
+    let frame = match game.phase {
+        Phase::Idle => ICON.map(|row| row.map(|led| led * if blink { 9 } else { 4 })),
+        // NOTE: the rope without a marker while no opponent answered
+        Phase::Lobby => {
+            let mut leds = [[0; 5]; 5];
+            leds[2] = if blink { [2, 0, 2, 0, 2] } else { [0, 2, 0, 2, 0] };
+            leds
+        }
+        Phase::Countdown { at } => {
+            let digit = b'3' - (ms_since(now, at) * 3 / COUNTDOWN_MS).min(2) as u8;
+            let frame = font::render((digit as char).encode_utf8(&mut [0; 4]), Align::Center).unwrap_or_default();
+            frame.map(|row| row.map(|led| led * 9))
+        }
+        Phase::Pulling { .. } => {
+            // NOTE: this board's side is the left one
+            let marker = 2 - (ahead(&game) * 3 / WIN_AHEAD).clamp(-2, 2);
+            let mut leds = [[0; 5]; 5];
+            leds[2] = [2; 5];
+            leds[1][marker as usize] = 9;
+            leds[2][marker as usize] = 9;
+            leds[3][marker as usize] = 9;
+            leds
+        }
+        Phase::Over { won, at } => {
+            let elapsed = ms_since(now, at);
+            if elapsed >= RESULT_MS {
+                return None;
+            }
+            // NOTE: fireworks over the tick for the winner, rain over the cross for the other
+            let (mut leds, preset) = if won { (WIN, Preset::Firework) } else { (LOSE, Preset::Rain) };
+            let effect = effects::at(preset, elapsed);
+            for (row, effect) in leds.iter_mut().zip(effect) {
+                for (led, effect) in row.iter_mut().zip(effect) {
+                    *led = (*led * 3).max(effect);
+                }
+            }
+            leds
+        }
+    };
+    Some((frame, POLL_MS))
+}
@@ -0,0 +1,64 @@
+//! Usage counters that add up over the life of the board instead of restarting at every boot.
+//! `init` restores them from the key-value store, the `persist_counters` task and the
+//! `reboot` console command write back the ones that changed.
+//!
+//! The counters are an RTIC resource, idle `publish`es a copy of them every cycle for the
+//! apps, which can't lock it, see stats.rs.
+
+use core::cell::Cell;
+use cortex_m::interrupt::Mutex;
+use crate::kv;
+
+#[derive(Clone, Copy)]
+pub struct Counters {
+    pub button_pressed : u32,
+    pub button_a       : u32,
+    pub button_b       : u32,
+    // NOTE: presses of A and B together
+    pub chords         : u32,
+    pub logo           : u32,
+    pub idle           : u32,
+}
+
+static PUBLISHED : Mutex<Cell<Option<Counters>>> = Mutex::new(Cell::new(None));
+
+pub fn publish(counters : &Counters) {
+    cortex_m::interrupt::free(|cs| PUBLISHED.borrow(cs).set(Some(*counters)));
+}
+
+/// The counters as of idle's last cycle, None before the first one.
+pub fn published() -> Option<Counters> {
+    cortex_m::interrupt::free(|cs| PUBLISHED.borrow(cs).get())
+}
+
+impl Counters {
+    pub fn load() -> Counters {
+        let load = |key| kv::get_u32(key).unwrap_or(0);
+        Counters {
+            button_pressed : load(kv::BUTTON_PRESSED),
+            button_a       : load(kv::BUTTON_A),
+            button_b       : load(kv::BUTTON_B),
+            chords         : load(kv::CHORDS),
+            logo           : load(kv::LOGO_TOUCHES),
+            idle           : load(kv::IDLE),
+        }
+    }
+
+    /// Stores the counters that differ from `saved`, which is updated to match.
+    pub fn save(&self, saved : &mut Counters) {
+        let fields = [
+            (kv::BUTTON_PRESSED, self.button_pressed, saved.button_pressed),
+            (kv::BUTTON_A, self.button_a, saved.button_a),
+            (kv::BUTTON_B, self.button_b, saved.button_b),
+            (kv::CHORDS, self.chords, saved.chords),
+            (kv::LOGO_TOUCHES, self.logo, saved.logo),
+            (kv::IDLE, self.idle, saved.idle),
+        ];
+        for (key, value, saved) in fields {
+            if value != saved {
+                kv::set_u32(key, value);
+            }
+        }
+        *saved = *self;
+    }
+}
@@ -0,0 +1,68 @@
+//! Classroom utilities: a coin flip, a random number from 1 to N and a group picker, see
+//! `fun_core::utils` for the tools.
+//!
+//! Touching the logo moves on to the next tool, A (or a shake) uses the one shown. For the
+//! number B raises N by one and a long press of B by ten. Every outcome is logged.
+
+use core::cell::Cell;
+use critical_section::Mutex;
+use fun_core::utils::{Tool, Tools};
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::log;
+use crate::mono::{Instant, Mono};
+use crate::rng::Rng;
+use crate::speaker::Speaker;
+
+pub const ICON : Frame = [
+    [0, 1, 1, 1, 0],
+    [1, 0, 0, 0, 1],
+    [1, 0, 0, 0, 1],
+    [1, 0, 0, 0, 1],
+    [0, 1, 1, 1, 0],
+];
+
+// NOTE: the button interrupt and input_poll come in, idle draws
+static TOOLS : Mutex<Cell<Tools>> = Mutex::new(Cell::new(Tools::new()));
+
+fn update(next : impl FnOnce(Tools) -> Tools) -> Tools {
+    critical_section::with(|cs| {
+        let tools = next(TOOLS.borrow(cs).get());
+        TOOLS.borrow(cs).set(tools);
+        tools
+    })
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    let mut taken = true;
+    update(|mut tools| {
+        match input {
+            Input::Button(Button::Logo)             => tools.next_tool(),
+            Input::Button(Button::A) | Input::Shake => taken = tools.start(&Mono, &mut Rng),
+            Input::Button(Button::B)                => taken = tools.raise(1, &Mono),
+            Input::LongPress(Button::B)             => taken = tools.raise(10, &Mono),
+            _                                       => taken = false,
+        }
+        tools
+    });
+    taken
+}
+
+/// The tool shown, and whatever it is doing.
+pub fn draw(step : usize) -> Option<(Frame, u32)> {
+    let tools = update(|mut tools| {
+        // NOTE: the app was launched again, the tool and N stay
+        if step == 0 {
+            tools.stop();
+        } else if let Some(outcome) = tools.advance(&Mono) {
+            match tools.tool() {
+                Tool::Coin   => log!("coin flip {}", if outcome == 0 { "heads" } else { "tails" }),
+                Tool::Number => log!("random number {} of {}", outcome, tools.n()),
+                Tool::Picker => log!("picker points {}", outcome),
+            }
+        }
+        tools
+    });
+    Some(tools.frame(&Mono, &mut Speaker))
+}
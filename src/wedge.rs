@@ -0,0 +1,108 @@
+//! Keyboard wedge: A types the readings into whatever has the focus on the computer the peer
+//! is plugged into, a spreadsheet say, see `fun_core::keyboard`.
+//!
+//! Every press of A types a row, the temperature and the supply in mV separated by a tab and
+//! ended by a newline, from the readings telemetry.rs keeps. The key reports go to the peer
+//! like the slides of presenter.rs, a press and a release per character, and its host link
+//! hands them to a program there that presses the keys. The icon blinks while a row is typed.
+//!
+//! NOTE: the reports go over the radio unacknowledged, a lost one drops a character or leaves
+//! a key down until the next one.
+
+use core::cell::RefCell;
+use core::fmt::Write;
+use cortex_m::interrupt::Mutex;
+use fun_core::keyboard;
+use heapless::String;
+use crate::apps::Input;
+use crate::display::Frame;
+use crate::events::Button;
+use crate::mono::{Instant, Mono};
+use crate::pairing;
+use crate::protocol::Packet;
+use crate::radio::Payload;
+use crate::telemetry;
+
+pub const ICON : Frame = [
+    [0, 0, 0, 0, 0],
+    [1, 1, 1, 1, 1],
+    [1, 0, 1, 0, 1],
+    [1, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0],
+];
+
+const ROW_LEN : usize = 32;
+const BLINK_MS : u64 = 200;
+const POLL_MS : u32 = 50;
+
+struct Typing {
+    row  : String<ROW_LEN>,
+    // NOTE: the reports of `row` sent so far
+    sent : usize,
+}
+
+impl Typing {
+    fn done(&self) -> bool {
+        keyboard::typed(&self.row).nth(self.sent).is_none()
+    }
+}
+
+// NOTE: filled from the button interrupt, emptied by radio_log
+static TYPING : Mutex<RefCell<Typing>> = Mutex::new(RefCell::new(Typing { row : String::new(), sent : 0 }));
+
+/// The row of readings to type, ended by a newline.
+fn readings() -> String<ROW_LEN> {
+    let snapshot = telemetry::latest();
+    let mut row = String::new();
+    if let Some(quarters) = snapshot.temperature {
+        let sign = if quarters < 0 { "-" } else { "" };
+        let quarters = quarters.unsigned_abs();
+        let _ = write!(row, "{}{}.{:02}", sign, quarters / 4, quarters % 4 * 25);
+    }
+    let _ = write!(row, "\t{}\n", snapshot.supply_mv);
+    row
+}
+
+pub fn on_input(input : Input, _now : Instant) -> bool {
+    match input {
+        Input::Button(Button::A) => (),
+        Input::Button(Button::B) => return true,
+        _ => return false,
+    }
+    // NOTE: nobody to send it to, the press is still taken so A doesn't run its action
+    if pairing::peer().is_none() {
+        return true;
+    }
+    let row = readings();
+    cortex_m::interrupt::free(|cs| {
+        let mut typing = TYPING.borrow(cs).borrow_mut();
+        // NOTE: a row still being typed is finished first
+        if typing.done() {
+            *typing = Typing { row, sent : 0 };
+        }
+    });
+    true
+}
+
+/// The next key report to send to the peer.
+pub fn next_packet() -> Option<Payload> {
+    let report = cortex_m::interrupt::free(|cs| {
+        let mut typing = TYPING.borrow(cs).borrow_mut();
+        let report = keyboard::typed(&typing.row).nth(typing.sent)?;
+        typing.sent += 1;
+        Some(report)
+    })?;
+    Packet::Hid(report.to_bytes()).encode()
+}
+
+/// The icon, dim without a peer and blinking while a row is typed.
+pub fn draw(_step : usize) -> Option<(Frame, u32)> {
+    let typing = cortex_m::interrupt::free(|cs| !TYPING.borrow(cs).borrow().done());
+    let blink = (Mono::now().duration_since_epoch().to_millis() / BLINK_MS).is_multiple_of(2);
+    let level = match () {
+        _ if pairing::peer().is_none() => 2,
+        _ if typing && blink => 0,
+        _ => 9,
+    };
+    Some((ICON.map(|row| row.map(|led| led * level)), POLL_MS))
+}
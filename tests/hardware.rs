@@ -0,0 +1,177 @@
+//! On-target tests of the hardware the firmware relies on, run on the micro:bit with
+//! defmt-test and reported over defmt through probe-rs (`make test-target`).
+//!
+//! They check the board rather than the firmware: the LSM303AGR answers on the internal I2C
+//! bus, a spare flash page erases, writes and reads back, the RNG output doesn't look stuck
+//! or biased, and every line of the LED matrix can be pulled both ways.
+//!
+//! defmt-test exits through semihosting once every test passed, a failed test panics. The
+//! result is also logged as the marker line of src/exit.rs, and with the `semihosting_exit`
+//! feature a failure exits through semihosting with status 1 as well, rather than through
+//! panic-probe's fault.
+//!
+//! NOTE: a separate binary without RTIC, the board is taken once in `init` and each test
+//! uses its own part of it.
+
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+#[cfg(not(feature = "semihosting_exit"))]
+use panic_probe as _;
+
+#[cfg(feature = "semihosting_exit")]
+#[panic_handler]
+fn panic(info : &core::panic::PanicInfo) -> ! {
+    use cortex_m_semihosting::debug;
+    defmt::error!("{}", defmt::Display2Format(info));
+    defmt::error!("TEST RESULT: FAIL");
+    loop {
+        debug::exit(debug::EXIT_FAILURE);
+    }
+}
+
+// NOTE: the NVMC driver has no crate dependencies, so the firmware's module is used as is
+#[allow(dead_code)]
+#[path = "../src/flash.rs"]
+mod flash;
+
+use embedded_hal::blocking::i2c::WriteRead;
+use embedded_hal::digital::v2::InputPin;
+use microbit::hal::gpio::{Level, Output, Pin, PushPull};
+use microbit::hal::twim::Twim;
+use microbit::pac::TWIM0;
+use flash::PAGE_SIZE;
+
+// NOTE: below the kv pages and above the 384K memory.x gives the program, see flash.rs
+const TEST_PAGE : u32 = 0x0007_B000;
+
+const ACCEL_ADDRESS : u8 = 0x19;
+const MAG_ADDRESS   : u8 = 0x1e;
+const WHO_AM_I_A    : u8 = 0x0f;
+const WHO_AM_I_M    : u8 = 0x4f;
+
+const RNG_BYTES : usize = 1024;
+
+fn who_am_i(i2c : &mut Twim<TWIM0>, address : u8, register : u8) -> Option<u8> {
+    let mut id = [0];
+    i2c.write_read(address, &[register], &mut id).ok()?;
+    Some(id[0])
+}
+
+fn page_erased() -> bool {
+    (0..PAGE_SIZE / 4).all(|i| flash::read_word(TEST_PAGE + 4 * i) == 0xffff_ffff)
+}
+
+/// Pulled up it reads high and pulled down low, a line stuck to a rail or shorted to a
+/// driven neighbour reads the same both ways. The line goes back to driving `high` or low.
+fn follows_its_pulls(pin : Pin<Output<PushPull>>, high : bool) -> (bool, Pin<Output<PushPull>>) {
+    // NOTE: 100 µs at 64 MHz for the line to settle, it sees the matrix's capacitance
+    let input = pin.into_pullup_input();
+    cortex_m::asm::delay(6_400);
+    let up = input.is_high().unwrap();
+    let input = input.into_pulldown_input();
+    cortex_m::asm::delay(6_400);
+    let down = input.is_low().unwrap();
+    (up && down, input.into_push_pull_output(if high { Level::High } else { Level::Low }))
+}
+
+// NOTE: defmt-test takes nothing but the tests and their hooks in here
+#[defmt_test::tests]
+mod tests {
+    use defmt::{assert, assert_eq};
+    use microbit::gpio::DisplayPins;
+    use microbit::hal::twim::{self, Twim};
+    use microbit::pac::{RNG, TWIM0};
+    use super::*;
+
+    pub struct State {
+        i2c     : Twim<TWIM0>,
+        rng     : RNG,
+        display : Option<DisplayPins>,
+    }
+
+    #[init]
+    fn init() -> State {
+        let board = microbit::Board::take().unwrap();
+        State {
+            i2c     : Twim::new(board.TWIM0, board.i2c_internal.into(), twim::Frequency::K100),
+            rng     : board.RNG,
+            display : Some(board.display_pins),
+        }
+    }
+
+    #[teardown]
+    fn teardown() {
+        defmt::info!("TEST RESULT: PASS");
+    }
+
+    #[test]
+    fn accelerometer_answers(state : &mut State) {
+        assert_eq!(who_am_i(&mut state.i2c, ACCEL_ADDRESS, WHO_AM_I_A), Some(0x33));
+    }
+
+    #[test]
+    fn magnetometer_answers(state : &mut State) {
+        assert_eq!(who_am_i(&mut state.i2c, MAG_ADDRESS, WHO_AM_I_M), Some(0x40));
+    }
+
+    #[test]
+    fn flash_erases_writes_and_reads_back() {
+        flash::erase_page(TEST_PAGE);
+        assert!(page_erased());
+
+        let words : [u32; 64] = core::array::from_fn(|i| 0x5a5a_0000 ^ (i as u32 * 0x0101_0101));
+        flash::write_words(TEST_PAGE + 256, &words);
+        for (i, word) in words.iter().enumerate() {
+            assert_eq!(flash::read_word(TEST_PAGE + 256 + 4 * i as u32), *word);
+        }
+        // NOTE: the words around the written ones are untouched
+        assert_eq!(flash::read_word(TEST_PAGE + 252), 0xffff_ffff);
+        assert_eq!(flash::read_word(TEST_PAGE + 256 + 4 * words.len() as u32), 0xffff_ffff);
+
+        flash::erase_page(TEST_PAGE);
+        assert!(page_erased());
+    }
+
+    #[test]
+    fn rng_output_is_healthy(state : &mut State) {
+        let rng = &state.rng;
+        rng.config.write(|w| w.dercen().enabled());
+        rng.events_valrdy.reset();
+        rng.tasks_start.write(|w| w.tasks_start().set_bit());
+        let mut counts = [0u16; 256];
+        let mut ones = 0;
+        for _ in 0..RNG_BYTES {
+            while rng.events_valrdy.read().bits() == 0 {}
+            rng.events_valrdy.reset();
+            let byte = rng.value.read().value().bits();
+            counts[byte as usize] += 1;
+            ones += byte.count_ones();
+        }
+        rng.tasks_stop.write(|w| w.tasks_stop().set_bit());
+
+        // NOTE: 8192 bits, the standard deviation of the ones is about 45
+        let bits = 8 * RNG_BYTES as u32;
+        defmt::info!("rng: {} ones in {} bits", ones, bits);
+        assert!(ones.abs_diff(bits / 2) < 200);
+        // NOTE: every value is expected 4 times, 16 is far out in the tail
+        assert!(counts.iter().all(|&count| count < 16));
+    }
+
+    #[test]
+    fn display_lines_follow_their_pulls(state : &mut State) {
+        let (columns, rows) = state.display.take().unwrap().degrade();
+        // NOTE: DisplayPins leaves the rows low and the columns high, so every LED stays off
+        // whichever way the line under test is pulled
+        for (lines, high, name) in [(columns, true, "column"), (rows, false, "row")] {
+            for (i, line) in lines.into_iter().enumerate() {
+                let (ok, _line) = follows_its_pulls(line, high);
+                if !ok {
+                    defmt::error!("{} {} doesn't follow its pulls", name, i + 1);
+                }
+                assert!(ok);
+            }
+        }
+    }
+}